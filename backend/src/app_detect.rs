@@ -0,0 +1,134 @@
+//! Running game detection.
+//!
+//! Profiles apply to the current game. The plugin reports it with
+//! `SetGameId`, but the daemon also works it out itself so profiles follow
+//! games when the plugin isn't loaded: gamescope's focused app first, then
+//! the `RunningAppID` Steam keeps in its registry file (which also covers
+//! the Steam overlay, when the focused app is Steam itself).
+
+use crate::display_backend::STEAM_APP_ID;
+use std::path::{Path, PathBuf};
+
+/// Steam's registry file, relative to the home directory.
+const STEAM_REGISTRY_FILE: &str = ".steam/registry.vdf";
+
+/// Registry key holding the app ID of the running game, 0 when none.
+const RUNNING_APP_KEY: &str = "RunningAppID";
+
+/// Default Steam registry path (`~/.steam/registry.vdf`) of the deck user.
+///
+/// Decky runs the plugin, and so the daemon, as root, where `HOME` is
+/// root's; `DECKY_USER_HOME` names the home of the user running Steam.
+pub fn default_registry_path() -> Option<PathBuf> {
+    registry_path_in(
+        std::env::var_os("DECKY_USER_HOME").map(PathBuf::from),
+        std::env::var_os("HOME").map(PathBuf::from),
+    )
+}
+
+/// Registry path under the deck user's home, else `home`.
+fn registry_path_in(decky_user_home: Option<PathBuf>, home: Option<PathBuf>) -> Option<PathBuf> {
+    [decky_user_home, home]
+        .into_iter()
+        .flatten()
+        .find(|dir| dir.is_absolute())
+        .map(|dir| dir.join(STEAM_REGISTRY_FILE))
+}
+
+/// `RunningAppID` from the text of a registry file, `None` when no game
+/// runs or the key is missing. Keys are matched case-insensitively, as
+/// Steam does.
+pub fn running_app_id(registry: &str) -> Option<u32> {
+    registry.lines().find_map(|line| {
+        let mut tokens = line.split('"').skip(1).step_by(2);
+        let key = tokens.next()?;
+        if !key.eq_ignore_ascii_case(RUNNING_APP_KEY) {
+            return None;
+        }
+        tokens.next()?.trim().parse().ok().filter(|&id| id != 0)
+    })
+}
+
+/// Read `RunningAppID` from the registry file at `path`.
+pub fn read_running_app_id(path: &Path) -> Option<u32> {
+    running_app_id(&std::fs::read_to_string(path).ok()?)
+}
+
+/// The running game: the focused app unless it is Steam itself, else the
+/// registry's running app.
+pub fn detect_app(focused_app: Option<u32>, registry_app: impl FnOnce() -> Option<u32>) -> Option<u32> {
+    focused_app.filter(|&app| app != STEAM_APP_ID).or_else(registry_app)
+}
+
+/// Reports the detected game only when it changes.
+#[derive(Debug, Default)]
+pub struct AppDetector {
+    last: Option<u32>,
+}
+
+impl AppDetector {
+    /// `Some(app)` when `detected` differs from the previous detection
+    /// (`Some(None)` once the game is gone).
+    pub fn update(&mut self, detected: Option<u32>) -> Option<Option<u32>> {
+        if detected == self.last {
+            return None;
+        }
+        self.last = detected;
+        Some(detected)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_running_app_id() {
+        let registry = "\"Registry\"\n{\n\t\"HKCU\"\n\t{\n\t\t\"Software\"\n\t\t{\n\t\t\t\"Valve\"\n\t\t\t{\n\t\t\t\t\"Steam\"\n\t\t\t\t{\n\t\t\t\t\t\"language\"\t\t\"english\"\n\t\t\t\t\t\"RunningAppID\"\t\t\"1091500\"\n\t\t\t\t}\n\t\t\t}\n\t\t}\n\t}\n}\n";
+        assert_eq!(running_app_id(registry), Some(1091500));
+        assert_eq!(running_app_id(&registry.replace("RunningAppID", "runningappid")), Some(1091500));
+        assert_eq!(running_app_id(&registry.replace("1091500", "0")), None);
+        assert_eq!(running_app_id("\"Registry\"\n{\n}\n"), None);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("registry.vdf");
+        assert_eq!(read_running_app_id(&path), None);
+        std::fs::write(&path, registry).unwrap();
+        assert_eq!(read_running_app_id(&path), Some(1091500));
+    }
+
+    #[test]
+    fn test_registry_path_prefers_deck_user_home() {
+        let deck = Some(PathBuf::from("/home/deck"));
+        let root = Some(PathBuf::from("/root"));
+        assert_eq!(
+            registry_path_in(deck, root.clone()),
+            Some(PathBuf::from("/home/deck/.steam/registry.vdf"))
+        );
+        assert_eq!(
+            registry_path_in(None, root.clone()),
+            Some(PathBuf::from("/root/.steam/registry.vdf"))
+        );
+        assert_eq!(
+            registry_path_in(Some(PathBuf::from("deck")), root),
+            Some(PathBuf::from("/root/.steam/registry.vdf"))
+        );
+        assert_eq!(registry_path_in(None, Some(PathBuf::from("relative"))), None);
+        assert_eq!(registry_path_in(None, None), None);
+    }
+
+    #[test]
+    fn test_detect_app() {
+        assert_eq!(detect_app(Some(570), || Some(620)), Some(570));
+        // The Steam overlay focuses Steam; the registry still names the game
+        assert_eq!(detect_app(Some(STEAM_APP_ID), || Some(620)), Some(620));
+        assert_eq!(detect_app(Some(STEAM_APP_ID), || None), None);
+        assert_eq!(detect_app(None, || Some(620)), Some(620));
+
+        let mut detector = AppDetector::default();
+        assert_eq!(detector.update(None), None);
+        assert_eq!(detector.update(Some(570)), Some(Some(570)));
+        assert_eq!(detector.update(Some(570)), None);
+        assert_eq!(detector.update(None), Some(None));
+    }
+}
@@ -3,10 +3,11 @@
 //! Reads power consumption from sysfs and estimates savings from dynamic refresh rate.
 
 use serde::{Deserialize, Serialize};
-use std::collections::VecDeque;
+use std::collections::{BTreeMap, VecDeque};
 use std::sync::RwLock;
-use std::time::Instant;
-use tracing::{debug, warn};
+use crate::display_control::MAX_ALLOWED_HZ;
+use crate::units::Hertz;
+use tracing::debug;
 
 /// Path to battery power consumption (in microwatts)
 const POWER_NOW_PATH: &str = "/sys/class/power_supply/BAT1/power_now";
@@ -14,6 +15,9 @@ const POWER_NOW_PATH: &str = "/sys/class/power_supply/BAT1/power_now";
 /// Alternative path for some systems
 const POWER_NOW_PATH_ALT: &str = "/sys/class/power_supply/BAT0/power_now";
 
+/// Power supply class directory, with the battery and the chargers
+pub const POWER_SUPPLY_DIR: &str = "/sys/class/power_supply";
+
 /// Number of samples for moving average
 const POWER_SAMPLE_COUNT: usize = 12;
 
@@ -21,8 +25,7 @@ const POWER_SAMPLE_COUNT: usize = 12;
 #[derive(Debug, Clone)]
 struct PowerSample {
     power_uw: u64,
-    hz: u32,
-    timestamp: Instant,
+    hz: Hertz,
 }
 
 /// Battery monitoring response for IPC
@@ -38,12 +41,39 @@ pub struct BatteryResponse {
     pub available: bool,
 }
 
+/// Average power draw at one refresh rate
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HzPower {
+    pub hz: Hertz,
+    /// Mean power over all samples taken at this rate, in watts
+    pub avg_watts: f64,
+    pub samples: u64,
+}
+
+/// Per-Hz power table response for IPC
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PowerByHzResponse {
+    /// Rates with at least one sample, ascending
+    pub rates: Vec<HzPower>,
+    /// Whether battery monitoring is available
+    pub available: bool,
+}
+
+/// Running sum of power samples at one rate
+#[derive(Debug, Clone, Copy, Default)]
+struct PowerTotal {
+    sum_uw: u64,
+    count: u64,
+}
+
 /// Battery monitor for power tracking
 pub struct BatteryMonitor {
     /// Recent power samples
     samples: RwLock<VecDeque<PowerSample>>,
+    /// Power totals per refresh rate since start
+    by_hz: RwLock<BTreeMap<Hertz, PowerTotal>>,
     /// Max Hz for savings calculation
-    max_hz: RwLock<u32>,
+    max_hz: RwLock<Hertz>,
     /// Whether battery sysfs is available
     available: RwLock<bool>,
 }
@@ -55,13 +85,14 @@ impl BatteryMonitor {
         
         Self {
             samples: RwLock::new(VecDeque::with_capacity(POWER_SAMPLE_COUNT)),
-            max_hz: RwLock::new(90),
+            by_hz: RwLock::new(BTreeMap::new()),
+            max_hz: RwLock::new(MAX_ALLOWED_HZ),
             available: RwLock::new(available),
         }
     }
 
     /// Set max Hz for savings calculation
-    pub fn set_max_hz(&self, hz: u32) {
+    pub fn set_max_hz(&self, hz: Hertz) {
         if let Ok(mut max) = self.max_hz.write() {
             *max = hz;
         }
@@ -93,7 +124,7 @@ impl BatteryMonitor {
     }
 
     /// Record a power sample
-    pub fn record_sample(&self, power_uw: u64, hz: u32) {
+    pub fn record_sample(&self, power_uw: u64, hz: Hertz) {
         if let Ok(mut samples) = self.samples.write() {
             if samples.len() >= POWER_SAMPLE_COUNT {
                 samples.pop_front();
@@ -101,9 +132,36 @@ impl BatteryMonitor {
             samples.push_back(PowerSample {
                 power_uw,
                 hz,
-                timestamp: Instant::now(),
             });
         }
+        if let Ok(mut by_hz) = self.by_hz.write() {
+            let total = by_hz.entry(hz).or_default();
+            total.sum_uw = total.sum_uw.saturating_add(power_uw);
+            total.count += 1;
+        }
+    }
+
+    /// Average power per refresh rate, from every sample since start
+    pub fn power_by_hz(&self) -> PowerByHzResponse {
+        let rates = self
+            .by_hz
+            .read()
+            .map(|by_hz| {
+                by_hz
+                    .iter()
+                    .map(|(hz, total)| HzPower {
+                        hz: *hz,
+                        avg_watts: total.sum_uw as f64 / total.count as f64 / 1_000_000.0,
+                        samples: total.count,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        PowerByHzResponse {
+            rates,
+            available: self.available.read().map(|a| *a).unwrap_or(false),
+        }
     }
 
     /// Get battery status response
@@ -136,12 +194,12 @@ impl BatteryMonitor {
 
                 // Calculate weighted average Hz
                 let avg_hz: f64 = samples.iter()
-                    .map(|s| s.hz as f64)
+                    .map(|s| s.hz.as_f64())
                     .sum::<f64>() / samples.len() as f64;
 
                 // Estimate savings using linear approximation
                 // Power ~ Frequency (simplified model)
-                let max_hz = self.max_hz.read().map(|h| *h).unwrap_or(90) as f64;
+                let max_hz = self.max_hz.read().map(|h| h.as_f64()).unwrap_or(MAX_ALLOWED_HZ.as_f64());
                 
                 if avg_hz >= max_hz || avg_hz <= 0.0 {
                     return (avg_watts, 0.0);
@@ -178,3 +236,56 @@ impl Default for BatteryMonitor {
         Self::new()
     }
 }
+
+/// Whether a charger (any non-battery supply, e.g. the Deck's `ACAD` or a
+/// USB-C dock) is online under `power_supply_dir`.
+pub fn external_power_online(power_supply_dir: &std::path::Path) -> bool {
+    let Ok(entries) = std::fs::read_dir(power_supply_dir) else {
+        return false;
+    };
+    entries.flatten().any(|entry| {
+        let read = |name| std::fs::read_to_string(entry.path().join(name)).unwrap_or_default();
+        read("type").trim() != "Battery" && read("online").trim() == "1"
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_external_power_online() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(!external_power_online(dir.path()));
+        for (name, kind, online) in [("BAT1", "Battery", "1"), ("ACAD", "Mains", "0")] {
+            std::fs::create_dir(dir.path().join(name)).unwrap();
+            std::fs::write(dir.path().join(name).join("type"), format!("{}\n", kind)).unwrap();
+            std::fs::write(dir.path().join(name).join("online"), format!("{}\n", online)).unwrap();
+        }
+        assert!(!external_power_online(dir.path()));
+        std::fs::write(dir.path().join("ACAD").join("online"), "1\n").unwrap();
+        assert!(external_power_online(dir.path()));
+    }
+
+    #[test]
+    fn test_power_by_hz_averages_per_rate() {
+        let monitor = BatteryMonitor::new();
+        monitor.record_sample(9_000_000, Hertz(90));
+        monitor.record_sample(11_000_000, Hertz(90));
+        monitor.record_sample(7_500_000, Hertz(45));
+        // Outlasts the moving-average window
+        for _ in 0..POWER_SAMPLE_COUNT {
+            monitor.record_sample(8_000_000, Hertz(60));
+        }
+
+        let rates = monitor.power_by_hz().rates;
+        assert_eq!(
+            rates,
+            vec![
+                HzPower { hz: Hertz(45), avg_watts: 7.5, samples: 1 },
+                HzPower { hz: Hertz(60), avg_watts: 8.0, samples: POWER_SAMPLE_COUNT as u64 },
+                HzPower { hz: Hertz(90), avg_watts: 10.0, samples: 2 },
+            ]
+        );
+    }
+}
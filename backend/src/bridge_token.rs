@@ -0,0 +1,113 @@
+//! Shared secret for the TCP bridges (WebSocket and HTTP API).
+//!
+//! TCP has no peer credentials, so anything on the machine can reach a
+//! localhost port. The daemon generates a fresh token on every start and
+//! writes it next to the IPC socket with mode 0600; only processes that can
+//! read that file (the daemon's user, or root) can use the bridges.
+
+use std::fs::{self, OpenOptions};
+use std::io::{Read, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+
+/// Token file name, placed in the socket's directory.
+pub const TOKEN_FILE_NAME: &str = "smart-refresh.token";
+
+/// Random bytes per token (hex-encoded to twice the length)
+const TOKEN_BYTES: usize = 32;
+
+/// Token file path for a given socket path.
+pub fn token_path_for(socket_path: &Path) -> PathBuf {
+    socket_path.with_file_name(TOKEN_FILE_NAME)
+}
+
+/// Per-start bridge token.
+#[derive(Debug, Clone)]
+pub struct BridgeToken(String);
+
+impl BridgeToken {
+    /// Generate a token from the kernel's random source.
+    pub fn generate() -> std::io::Result<Self> {
+        let mut bytes = [0u8; TOKEN_BYTES];
+        fs::File::open("/dev/urandom")?.read_exact(&mut bytes)?;
+        Ok(Self(bytes.iter().map(|b| format!("{:02x}", b)).collect()))
+    }
+
+    /// Write the token to `path`, readable by the owner only.
+    ///
+    /// Any previous file is removed first so a stale file with looser
+    /// permissions can't be reused.
+    pub fn write_to(&self, path: &Path) -> std::io::Result<()> {
+        match fs::remove_file(path) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(path)?;
+        file.write_all(self.0.as_bytes())?;
+        file.sync_all()
+    }
+
+    /// Compare a presented token in constant time.
+    pub fn matches(&self, candidate: &str) -> bool {
+        let expected = self.0.as_bytes();
+        let candidate = candidate.as_bytes();
+        if expected.len() != candidate.len() {
+            return false;
+        }
+        expected
+            .iter()
+            .zip(candidate)
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+    }
+
+    #[cfg(test)]
+    pub fn from_secret(secret: &str) -> Self {
+        Self(secret.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_generated_tokens_differ() {
+        let a = BridgeToken::generate().unwrap();
+        let b = BridgeToken::generate().unwrap();
+        assert_eq!(a.0.len(), TOKEN_BYTES * 2);
+        assert_ne!(a.0, b.0);
+    }
+
+    #[test]
+    fn test_matches() {
+        let token = BridgeToken::from_secret("abc123");
+        assert!(token.matches("abc123"));
+        assert!(!token.matches("abc124"));
+        assert!(!token.matches("abc12"));
+        assert!(!token.matches(""));
+    }
+
+    #[test]
+    fn test_written_owner_only_and_replaced() {
+        let dir = tempdir().unwrap();
+        let path = token_path_for(&dir.path().join("smart-refresh.sock"));
+        assert_eq!(path, dir.path().join(TOKEN_FILE_NAME));
+
+        fs::write(&path, "stale").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+
+        let token = BridgeToken::generate().unwrap();
+        token.write_to(&path).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), token.0);
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+}
@@ -0,0 +1,69 @@
+//! Daemon version and build metadata.
+
+use serde::{Deserialize, Serialize};
+
+/// Version information reported by `--version` and `GetVersion`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BuildInfo {
+    pub version: String,
+    pub git_hash: String,
+    pub build_date: String,
+    /// Optional cargo features compiled in
+    pub features: Vec<String>,
+}
+
+impl BuildInfo {
+    /// Information for the running binary.
+    pub fn current() -> Self {
+        let features = [("mqtt", cfg!(feature = "mqtt")), ("http", cfg!(feature = "http"))];
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            git_hash: env!("SMART_REFRESH_GIT_HASH").to_string(),
+            build_date: env!("SMART_REFRESH_BUILD_DATE").to_string(),
+            features: features
+                .iter()
+                .filter(|(_, enabled)| *enabled)
+                .map(|(name, _)| name.to_string())
+                .collect(),
+        }
+    }
+}
+
+impl std::fmt::Display for BuildInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "smart-refresh-daemon {} ({} {})",
+            self.version, self.git_hash, self.build_date
+        )?;
+        if !self.features.is_empty() {
+            write!(f, " [{}]", self.features.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_current_build_info() {
+        let info = BuildInfo::current();
+        assert_eq!(info.version, env!("CARGO_PKG_VERSION"));
+        assert!(!info.git_hash.is_empty());
+        assert_eq!(info.build_date.len(), "YYYY-MM-DD".len());
+        assert_eq!(info.features.contains(&"mqtt".to_string()), cfg!(feature = "mqtt"));
+    }
+
+    #[test]
+    fn test_display() {
+        let info = BuildInfo {
+            version: "2.0.0".to_string(),
+            git_hash: "abc123".to_string(),
+            build_date: "2026-01-02".to_string(),
+            features: vec!["mqtt".to_string()],
+        };
+        assert_eq!(info.to_string(), "smart-refresh-daemon 2.0.0 (abc123 2026-01-02) [mqtt]");
+    }
+}
@@ -0,0 +1,171 @@
+//! Command-line argument parsing for the daemon.
+//!
+//! Parsed with clap; `--help`, the man pages and the shell completions
+//! ([`crate::cli_docs`]) are all generated from [`CliArgs`].
+
+use clap::{Parser, Subcommand, ValueEnum};
+use std::path::PathBuf;
+
+/// Default time `--strict` waits for an FPS source
+pub const DEFAULT_SHM_TIMEOUT_SECS: u64 = 30;
+
+/// Longest `--shm-timeout` accepted (an hour)
+pub const MAX_SHM_TIMEOUT_SECS: u64 = 3600;
+
+/// Binary name used in usage text, man pages and completions.
+pub const BIN_NAME: &str = "smart-refresh-daemon";
+
+/// Process exit codes with their English and Russian descriptions.
+pub const EXIT_CODES: &[(i32, &str, &str)] = &[
+    (0, "clean shutdown", "штатное завершение"),
+    (1, "runtime error", "ошибка во время работы"),
+    (2, "invalid arguments", "неверные аргументы"),
+    (3, "no usable display backend (--strict)", "нет доступного способа управления дисплеем (--strict)"),
+    (4, "no FPS source available (--strict)", "нет доступного источника FPS (--strict)"),
+];
+
+/// `--help` footer listing the exit codes.
+const EXIT_CODES_HELP: &str = "\
+Exit codes:
+  0  clean shutdown
+  1  runtime error
+  2  invalid arguments
+  3  no usable display backend (--strict)
+  4  no FPS source available (--strict)";
+
+/// Output of `--generate`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum DocKind {
+    Man,
+    ManRu,
+    Bash,
+    Zsh,
+    Fish,
+}
+
+/// Subcommands run instead of the daemon.
+#[derive(Debug, Clone, PartialEq, Eq, Subcommand)]
+pub enum CliCommand {
+    /// Install the systemd user unit, man pages and completions
+    InstallService,
+}
+
+/// Parsed command-line options.
+#[derive(Debug, Clone, PartialEq, Parser)]
+#[command(
+    name = BIN_NAME,
+    about = "Dynamic refresh rate daemon for the Steam Deck",
+    disable_version_flag = true,
+    after_help = EXIT_CODES_HELP
+)]
+pub struct CliArgs {
+    /// IPC socket path (default: $XDG_RUNTIME_DIR/smart-refresh.sock)
+    #[arg(long = "socket", value_name = "PATH")]
+    pub socket_path: Option<PathBuf>,
+    /// Exit instead of running degraded when dependencies are missing
+    #[arg(long)]
+    pub strict: bool,
+    /// How long --strict waits for an FPS source, in seconds (max: 3600)
+    #[arg(
+        long = "shm-timeout",
+        value_name = "SECS",
+        default_value_t = DEFAULT_SHM_TIMEOUT_SECS,
+        value_parser = clap::value_parser!(u64).range(..=MAX_SHM_TIMEOUT_SECS)
+    )]
+    pub shm_timeout_secs: u64,
+    /// Print a man page or shell completion script and exit
+    #[arg(long, value_name = "KIND")]
+    pub generate: Option<DocKind>,
+    /// Print version and build information and exit
+    #[arg(short = 'V', long)]
+    pub version: bool,
+    #[command(subcommand)]
+    pub command: Option<CliCommand>,
+}
+
+/// Russian help for each argument id, used for `--generate man-ru`.
+pub const HELP_RU: &[(&str, &str)] = &[
+    ("socket_path", "Путь к IPC-сокету (по умолчанию: $XDG_RUNTIME_DIR/smart-refresh.sock)"),
+    ("strict", "Завершаться вместо работы в урезанном режиме при отсутствии зависимостей"),
+    ("shm_timeout_secs", "Сколько секунд --strict ждёт источник FPS (максимум: 3600)"),
+    ("generate", "Вывести man-страницу или скрипт автодополнения и выйти"),
+    ("version", "Вывести версию и информацию о сборке и выйти"),
+    ("help", "Вывести эту справку и выйти"),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::error::ErrorKind;
+    use clap::CommandFactory;
+
+    fn parse(args: &[&str]) -> Result<CliArgs, clap::Error> {
+        CliArgs::try_parse_from(std::iter::once(BIN_NAME).chain(args.iter().copied()))
+    }
+
+    #[test]
+    fn test_command_definition() {
+        CliArgs::command().debug_assert();
+    }
+
+    #[test]
+    fn test_socket_flag() {
+        let expected = Some(PathBuf::from("/run/user/1000/sr.sock"));
+        assert_eq!(parse(&["--socket", "/run/user/1000/sr.sock"]).unwrap().socket_path, expected);
+        assert_eq!(parse(&["--socket=/run/user/1000/sr.sock"]).unwrap().socket_path, expected);
+        assert_eq!(parse(&[]).unwrap().socket_path, None);
+    }
+
+    #[test]
+    fn test_invalid_args() {
+        assert_eq!(parse(&["--socket"]).unwrap_err().kind(), ErrorKind::InvalidValue);
+        assert_eq!(parse(&["--bogus"]).unwrap_err().kind(), ErrorKind::UnknownArgument);
+        assert_eq!(parse(&["--bogus"]).unwrap_err().exit_code(), 2);
+    }
+
+    #[test]
+    fn test_version_flag() {
+        assert!(parse(&["--version"]).unwrap().version);
+        assert!(parse(&["-V"]).unwrap().version);
+        assert!(!parse(&[]).unwrap().version);
+    }
+
+    #[test]
+    fn test_strict_flags() {
+        let cli = parse(&["--strict", "--shm-timeout", "5"]).unwrap();
+        assert!(cli.strict);
+        assert_eq!(cli.shm_timeout_secs, 5);
+
+        assert_eq!(parse(&[]).unwrap().shm_timeout_secs, DEFAULT_SHM_TIMEOUT_SECS);
+        assert_eq!(parse(&["--shm-timeout=soon"]).unwrap_err().kind(), ErrorKind::ValueValidation);
+        assert!(parse(&["--shm-timeout", "18446744073709551615"]).is_err());
+        assert!(parse(&["--shm-timeout", "3601"]).is_err());
+    }
+
+    #[test]
+    fn test_generate_and_install_service() {
+        assert_eq!(parse(&["--generate", "zsh"]).unwrap().generate, Some(DocKind::Zsh));
+        assert_eq!(parse(&["--generate=man-ru"]).unwrap().generate, Some(DocKind::ManRu));
+        assert!(parse(&["--generate", "powershell"]).is_err());
+        assert_eq!(parse(&["install-service"]).unwrap().command, Some(CliCommand::InstallService));
+        assert_eq!(parse(&[]).unwrap().command, None);
+    }
+
+    #[test]
+    fn test_help_lists_exit_codes() {
+        let help = CliArgs::command().render_help().to_string();
+        for (code, desc, _) in EXIT_CODES {
+            assert!(help.contains(&format!("{}  {}", code, desc)));
+        }
+        assert!(help.contains("install-service"));
+    }
+
+    #[test]
+    fn test_every_argument_has_russian_help() {
+        let command = CliArgs::command().disable_help_flag(false);
+        for arg in command.get_arguments() {
+            let id = arg.get_id().as_str();
+            assert!(HELP_RU.iter().any(|(name, _)| *name == id), "{} has no Russian help", id);
+        }
+    }
+}
@@ -0,0 +1,142 @@
+//! Man page and shell completion generation for `--generate`.
+//!
+//! Rendered with clap_mangen and clap_complete from the [`CliArgs`]
+//! definition, so the packaged docs cannot drift from what the parser
+//! accepts. Only the sections clap has no notion of (exit status, files)
+//! and the Russian translations are written here.
+
+use crate::build_info::BuildInfo;
+use crate::cli::{CliArgs, DocKind, BIN_NAME, EXIT_CODES, HELP_RU};
+use clap::{Command, CommandFactory};
+use clap_complete::Shell;
+
+/// Render the requested document.
+pub fn render(kind: DocKind) -> String {
+    match kind {
+        DocKind::Man => man_page(Lang::En),
+        DocKind::ManRu => man_page(Lang::Ru),
+        DocKind::Bash => completion(Shell::Bash),
+        DocKind::Zsh => completion(Shell::Zsh),
+        DocKind::Fish => completion(Shell::Fish),
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Lang {
+    En,
+    Ru,
+}
+
+impl Lang {
+    fn pick(self, en: &'static str, ru: &'static str) -> &'static str {
+        match self {
+            Lang::En => en,
+            Lang::Ru => ru,
+        }
+    }
+}
+
+/// The command with its help in `lang`.
+fn command(lang: Lang) -> Command {
+    let mut command = CliArgs::command().long_about(lang.pick(
+        "Reads the frame rate reported by MangoHud and adjusts the gamescope \
+         refresh rate to match it, trading unused refresh headroom for battery life. \
+         The daemon is controlled over a Unix socket by the SmartRefresh Decky plugin.",
+        "Читает частоту кадров из MangoHud и подстраивает частоту обновления gamescope \
+         под неё, экономя заряд батареи на неиспользуемом запасе частоты. \
+         Демон управляется через Unix-сокет плагином SmartRefresh для Decky.",
+    ));
+    // The exit codes get their own man page section
+    command = command.after_help(None::<&str>);
+    if let Lang::Ru = lang {
+        command = command
+            .about("Демон динамической частоты обновления для Steam Deck")
+            .mut_subcommand("install-service", |sub| {
+                sub.about("Установить пользовательский unit systemd, man-страницы и автодополнение")
+            });
+        command.build();
+        for (id, help) in HELP_RU {
+            command = command.mut_arg(*id, |arg| arg.help(*help));
+        }
+    }
+    command
+}
+
+/// Escape text for roff: backslashes and dashes.
+fn roff_escape(text: &str) -> String {
+    text.replace('\\', "\\e").replace('-', "\\-")
+}
+
+fn man_page(lang: Lang) -> String {
+    let info = BuildInfo::current();
+    let man = clap_mangen::Man::new(command(lang))
+        .title("SMART-REFRESH-DAEMON")
+        .date(info.build_date)
+        .source(format!("{} {}", BIN_NAME, info.version))
+        .manual(lang.pick("User Commands", "Пользовательские команды"));
+    let mut out = Vec::new();
+    // Writing to a Vec can't fail
+    let _ = man.render(&mut out);
+    let mut out = String::from_utf8_lossy(&out).into_owned();
+
+    out.push_str(&format!(".SH {}\n", lang.pick("EXIT STATUS", "КОДЫ ВОЗВРАТА")));
+    for (code, en, ru) in EXIT_CODES {
+        out.push_str(&format!(".TP\n.B {}\n{}\n", code, roff_escape(lang.pick(en, ru))));
+    }
+
+    out.push_str(&format!(".SH {}\n", lang.pick("FILES", "ФАЙЛЫ")));
+    out.push_str(".TP\n.I $XDG_RUNTIME_DIR/smart\\-refresh.sock\n");
+    out.push_str(lang.pick("IPC socket\n", "IPC-сокет\n"));
+    out.push_str(".TP\n.I ~/.config/smart\\-refresh/config.json\n");
+    out.push_str(lang.pick("Persisted settings\n", "Сохранённые настройки\n"));
+
+    out
+}
+
+fn completion(shell: Shell) -> String {
+    let mut out = Vec::new();
+    clap_complete::generate(shell, &mut CliArgs::command(), BIN_NAME, &mut out);
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::ValueEnum;
+
+    #[test]
+    fn test_every_option_is_documented() {
+        for kind in DocKind::value_variants() {
+            let doc = render(*kind);
+            for arg in CliArgs::command().get_arguments() {
+                let Some(long) = arg.get_long() else { continue };
+                let needle = match kind {
+                    DocKind::Man | DocKind::ManRu => format!("\\-\\-{}", long.replace('-', "\\-")),
+                    DocKind::Fish => format!("-l {}", long),
+                    _ => format!("--{}", long),
+                };
+                assert!(doc.contains(&needle), "{:?} lacks --{}", kind, long);
+            }
+            assert!(doc.contains("install"), "{:?} lacks install-service", kind);
+        }
+    }
+
+    #[test]
+    fn test_man_pages_are_localized() {
+        let en = render(DocKind::Man);
+        let ru = render(DocKind::ManRu);
+        assert!(en.starts_with(".ie") || en.contains(".TH SMART"));
+        assert!(en.contains(".SH EXIT STATUS"));
+        assert!(ru.contains(".SH КОДЫ ВОЗВРАТА"));
+        assert!(ru.contains("штатное завершение"));
+        assert!(ru.contains("Путь к IPC"));
+        assert!(!ru.contains("IPC socket path"));
+    }
+
+    #[test]
+    fn test_generate_choices_completed() {
+        for kind in [DocKind::Bash, DocKind::Zsh, DocKind::Fish] {
+            assert!(render(kind).contains("man-ru"), "{:?} lacks --generate values", kind);
+        }
+    }
+}
@@ -2,59 +2,698 @@
 //!
 //! This module handles loading, saving, and validating daemon configuration.
 
-use crate::core_logic::Sensitivity;
+use crate::core_logic::{
+    ControlPolicy, IdlePolicy, Sensitivity, StepMode, DEFAULT_FPS_TOLERANCE, DEFAULT_PREDICTIVE_DROP_SLOPE,
+    DEFAULT_RESUME_COOLDOWN_SECS, DEFAULT_SATURATION_PACING, MAX_FPS_TOLERANCE, MAX_MIN_DWELL_SECS,
+    MAX_PREDICTIVE_DROP_SLOPE, MAX_RESUME_COOLDOWN_SECS, MAX_SATURATION_PACING, MIN_FPS_TOLERANCE, MIN_PREDICTIVE_DROP_SLOPE, MIN_SATURATION_PACING,
+};
+use crate::device::DeviceModel;
+use crate::display_backend::BackendKind;
+use crate::fps_source::FpsSourceKind;
+use crate::fps_monitor::{FpsSmoothing, DEFAULT_EMA_ALPHA, MANGOHUD_SHM_NAME, MAX_EMA_ALPHA, MIN_EMA_ALPHA};
+use crate::display_control::{StartupRate, MAX_ALLOWED_HZ, MIN_ALLOWED_HZ};
 use crate::error::ConfigError;
+use crate::experimental::ExperimentalConfig;
+use crate::led::LedState;
+use crate::notifications::{EventKind, Route, Severity};
+use crate::persist::atomic_persist;
+use crate::thermal::{DEFAULT_THERMAL_LIMIT_C, MAX_THERMAL_LIMIT_C, MIN_THERMAL_LIMIT_C};
+use crate::units::{Hertz, Millis, Secs};
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::io::Write;
-use std::path::{Path, PathBuf};
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
 use std::sync::RwLock;
+use std::ops::RangeInclusive;
+use std::time::Duration;
+
+/// Accepted range for custom sensitivity durations
+const CUSTOM_THRESHOLD_RANGE: RangeInclusive<Millis> = Millis(100)..=Millis(30_000);
+
+/// Longest loading-screen grace period
+const MAX_LOADING_GRACE: Millis = Millis(30_000);
+
+/// Longest minimum dwell time per refresh level
+const MAX_MIN_DWELL: Secs = Secs(MAX_MIN_DWELL_SECS);
+
+/// Longest silence after wake from suspend
+const MAX_RESUME_COOLDOWN: Secs = Secs(MAX_RESUME_COOLDOWN_SECS);
+
+/// Longest hold after a refresh rate change made outside the daemon
+const MAX_MANUAL_CHANGE_GRACE: Secs = Secs(600);
+
+/// Accepted FPS smoothing windows
+const FPS_WINDOW_RANGE: RangeInclusive<Secs> = Secs(1)..=Secs(60);
+
+/// Rates any panel could show; the panel's own range is checked on update
+const PLAUSIBLE_HZ_RANGE: RangeInclusive<Hertz> = Hertz(20)..=Hertz(240);
 
 /// Daemon configuration.
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct Config {
-    pub min_hz: u32,
-    pub max_hz: u32,
+    pub min_hz: Hertz,
+    pub max_hz: Hertz,
     pub sensitivity: Sensitivity,
+    /// Drop duration when sensitivity is "custom"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub custom_drop_ms: Option<Millis>,
+    /// Increase duration when sensitivity is "custom"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub custom_increase_ms: Option<Millis>,
     pub enabled: bool,
+    /// IPC socket path override (defaults to `$XDG_RUNTIME_DIR/smart-refresh.sock`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub socket_path: Option<PathBuf>,
+    /// Additional UIDs allowed to use the IPC socket (the daemon's own UID and root always are)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allowed_uids: Vec<u32>,
+    /// Port for the localhost WebSocket bridge (disabled when unset).
+    /// TCP has no peer credentials, so clients must present the per-start
+    /// token written next to the socket.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub websocket_port: Option<u16>,
+    /// Port for the local HTTP API (used when built with the `http` feature).
+    /// Requests must carry the same per-start token as the WebSocket bridge.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub http_port: Option<u16>,
+    /// User that per-game hooks run as when the daemon runs as root
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hook_uid: Option<u32>,
+    /// Shell command run on eco/turbo/override mode changes for haptic or sound feedback
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub feedback_command: Option<String>,
+    /// MQTT publishing settings (used when built with the `mqtt` feature)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mqtt: Option<MqttConfig>,
+    /// Seconds without a game or FPS data before entering deep sleep (0 disables)
+    #[serde(default = "default_deep_sleep_after_secs")]
+    pub deep_sleep_after_secs: Secs,
+    /// Drop one step at a time instead of jumping straight to the FPS target.
+    /// Legacy shorthand for `drop_step: "single"`, overrides `drop_step` when set.
+    #[serde(default)]
+    pub ramp_down: bool,
+    /// Drop step size: "jump" to the FPS target (default) or "single" step
+    #[serde(default = "default_drop_step")]
+    pub drop_step: StepMode,
+    /// Increase step size: "single" step (default) or "jump" to max Hz
+    #[serde(default)]
+    pub increase_step: StepMode,
+    /// Snap to a multiple of 24/25/30fps video (e.g. 48/50/60Hz) when FPS locks to it
+    #[serde(default)]
+    pub video_cadence: bool,
+    /// Below min Hz, run at a multiple of FPS (e.g. 56Hz for 28 FPS) with the frame limiter at FPS
+    #[serde(default)]
+    pub low_fps_compensation: bool,
+    /// FPS within this many frames of Hz counts as on target (higher is more stable, lower more responsive)
+    #[serde(default = "default_fps_tolerance")]
+    pub fps_tolerance: f64,
+    /// Increase threshold multiplier for the final step up to max Hz (1.0 disables)
+    #[serde(default = "default_saturation_pacing")]
+    pub saturation_pacing: f64,
+    /// FPS decline in FPS per second that starts the drop timer early
+    /// (used when the `predictive_drop` experiment is enabled)
+    #[serde(default = "default_predictive_drop_fps_per_sec")]
+    pub predictive_drop_fps_per_sec: f64,
+    /// How polled FPS is smoothed: "mean", "median" or "trimmed_mean" of the
+    /// ring buffer, or "ema"
+    #[serde(default)]
+    pub fps_smoothing: FpsSmoothing,
+    /// Weight of the newest sample with "ema" smoothing, 0.01-1.0
+    #[serde(default = "default_fps_ema_alpha")]
+    pub fps_ema_alpha: f64,
+    /// Span of the FPS ring buffer behind smoothing, 1-60s
+    #[serde(default = "default_fps_window_secs")]
+    pub fps_window_secs: Secs,
+    /// What to do with Hz after `idle_after_secs` without valid FPS: "hold", "min" or "max"
+    #[serde(default)]
+    pub idle_policy: IdlePolicy,
+    /// Seconds without valid FPS data before `idle_policy` applies
+    #[serde(default = "default_idle_after_secs")]
+    pub idle_after_secs: Secs,
+    /// Hold Hz this long after an abrupt FPS collapse, e.g. a loading screen (0 disables)
+    #[serde(default)]
+    pub loading_grace_ms: Millis,
+    /// Stay at a new rate at least this long before any further change (0 disables)
+    #[serde(default)]
+    pub min_dwell_secs: Secs,
+    /// No switching for this long after wake from suspend (0 disables)
+    #[serde(default = "default_resume_cooldown_secs")]
+    pub resume_cooldown_secs: Secs,
+    /// Hold a rate set outside the daemon (e.g. the Steam QAM) this long (0 disables)
+    #[serde(default = "default_manual_change_grace_secs")]
+    pub manual_change_grace_secs: Secs,
+    /// Rate set when the daemon starts: "max", "restore" (last session) or "untouched"
+    #[serde(default)]
+    pub startup_rate: StartupRate,
+    /// APU temperature (°C) at which Hz increases stop until it cools down
+    #[serde(default = "default_thermal_limit_c")]
+    pub thermal_limit_c: u32,
+    /// Switch sensitivity automatically with FPS stability
+    #[serde(default)]
+    pub adaptive_sensitivity: bool,
+    /// Sample GPU busy % and CPU load, holding increases while the GPU is pegged
+    #[serde(default)]
+    pub gpu_utilization: bool,
+    /// Poll FPS and run the controller less often while FPS is steady
+    #[serde(default = "default_adaptive_polling")]
+    pub adaptive_polling: bool,
+    /// Keep the rate and controller state in a file for MangoHud's `exec=`
+    #[serde(default)]
+    pub mangohud_overlay: bool,
+    /// Follow the running game (gamescope focus, Steam registry) for profiles
+    #[serde(default = "default_auto_detect_game")]
+    pub auto_detect_game: bool,
+    /// Decision policy: "hysteresis" (default) or "pid"
+    #[serde(default)]
+    pub control_policy: ControlPolicy,
+    /// Explicit refresh rates to use instead of 5Hz steps (e.g. [45, 60, 90])
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allowed_hz: Vec<Hertz>,
+    /// Fixed rate while the Steam UI (no game) is in the foreground
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ui_hz: Option<Hertz>,
+    /// Refresh rate backend (auto-detected when unset)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_backend: Option<BackendKind>,
+    /// FPS source (auto-detected when unset)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fps_source: Option<FpsSourceKind>,
+    /// MangoHud CSV log file, or its `output_folder`, for the `mangohud_log` source
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mangohud_log: Option<PathBuf>,
+    /// MangoHud shared memory names to probe, in order
+    #[serde(default = "default_mangohud_shm_names")]
+    pub mangohud_shm_names: Vec<String>,
+    /// Handheld model for the built-in presets (detected from DMI when unset)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device_model: Option<DeviceModel>,
+    /// Status LED / RGB integration
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub led: Option<LedConfig>,
+    /// Event routing overrides (log, notify, haptic, frontend)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notifications: Option<NotificationConfig>,
+    /// Cap on automatic switches per hour/day
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub switch_budget: Option<SwitchBudgetConfig>,
+    /// Debounce for external display detection (dock/undock bounce)
+    #[serde(default)]
+    pub display_debounce: DisplayDebounceConfig,
+    /// Switch an external display's refresh rate instead of pausing
+    #[serde(default)]
+    pub control_external_display: bool,
+    /// Keep switching in a desktop session (SteamOS desktop mode)
+    #[serde(default)]
+    pub desktop_mode_switching: bool,
+    /// Only switch for games whose profile whitelists them
+    #[serde(default)]
+    pub whitelist_only: bool,
+    /// Connectors that never count as an external display, by output name
+    /// ("DP-1") or DRM connector ("card0-DP-1"), e.g. phantom USB-C DP ports
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ignored_connectors: Vec<String>,
+    /// Opt-in experimental behaviors
+    #[serde(default)]
+    pub experimental: ExperimentalConfig,
+}
+
+/// Per-event and per-severity notification routes.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct NotificationConfig {
+    /// Routes for specific event kinds
+    #[serde(default)]
+    pub routes: BTreeMap<EventKind, Vec<Route>>,
+    /// Routes for all events of a severity without a per-kind entry
+    #[serde(default)]
+    pub severity_routes: BTreeMap<Severity, Vec<Route>>,
+}
+
+/// LED patterns per daemon state.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct LedConfig {
+    /// Command run on state changes with `SMART_REFRESH_LED_STATE` set
+    #[serde(default)]
+    pub command: Option<String>,
+    /// sysfs values written when entering each state
+    #[serde(default)]
+    pub states: BTreeMap<LedState, Vec<SysfsWrite>>,
+}
+
+/// Limits on automatic refresh rate switches.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+pub struct SwitchBudgetConfig {
+    /// Maximum switches in any rolling hour
+    #[serde(default)]
+    pub per_hour: Option<u32>,
+    /// Maximum switches in any rolling 24 hours
+    #[serde(default)]
+    pub per_day: Option<u32>,
+}
+
+/// Debounce for the external display check that pauses the controller.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct DisplayDebounceConfig {
+    /// Consecutive identical readings needed before a change is accepted
+    #[serde(default = "default_debounce_readings")]
+    pub readings: u32,
+    /// Delay between confirming readings once a change is seen
+    #[serde(default = "default_debounce_settle_ms")]
+    pub settle_ms: Millis,
+    /// Minimum time to stay paused once an external display was detected
+    #[serde(default = "default_debounce_min_pause_secs")]
+    pub min_pause_secs: Secs,
+}
+
+impl Default for DisplayDebounceConfig {
+    fn default() -> Self {
+        Self {
+            readings: default_debounce_readings(),
+            settle_ms: default_debounce_settle_ms(),
+            min_pause_secs: default_debounce_min_pause_secs(),
+        }
+    }
+}
+
+/// A value written to a sysfs attribute.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SysfsWrite {
+    /// Attribute path, must be under /sys
+    pub path: PathBuf,
+    pub value: String,
+}
+
+/// MQTT broker settings for publishing daemon status.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct MqttConfig {
+    /// Broker host name or IP address
+    pub host: String,
+    /// Broker TCP port
+    #[serde(default = "default_mqtt_port")]
+    pub port: u16,
+    /// Topic prefix, e.g. `smartrefresh` publishes `smartrefresh/hz`
+    #[serde(default = "default_mqtt_topic_prefix")]
+    pub topic_prefix: String,
+    /// MQTT client identifier
+    #[serde(default = "default_mqtt_client_id")]
+    pub client_id: String,
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+    /// Seconds between status publishes
+    #[serde(default = "default_mqtt_interval_secs")]
+    pub interval_secs: Secs,
+}
+
+fn default_mangohud_shm_names() -> Vec<String> {
+    vec![MANGOHUD_SHM_NAME.to_string()]
+}
+
+fn default_deep_sleep_after_secs() -> Secs {
+    Secs(300)
+}
+
+fn default_idle_after_secs() -> Secs {
+    Secs(10)
+}
+
+fn default_resume_cooldown_secs() -> Secs {
+    Secs(DEFAULT_RESUME_COOLDOWN_SECS)
+}
+
+fn default_manual_change_grace_secs() -> Secs {
+    Secs(30)
+}
+
+fn default_drop_step() -> StepMode {
+    StepMode::Jump
+}
+
+fn default_fps_tolerance() -> f64 {
+    DEFAULT_FPS_TOLERANCE
+}
+
+fn default_fps_ema_alpha() -> f64 {
+    DEFAULT_EMA_ALPHA
+}
+
+fn default_adaptive_polling() -> bool {
+    true
+}
+
+fn default_auto_detect_game() -> bool {
+    true
+}
+
+fn default_fps_window_secs() -> Secs {
+    Secs(12)
+}
+
+fn default_saturation_pacing() -> f64 {
+    DEFAULT_SATURATION_PACING
+}
+
+fn default_thermal_limit_c() -> u32 {
+    DEFAULT_THERMAL_LIMIT_C
+}
+
+fn default_predictive_drop_fps_per_sec() -> f64 {
+    DEFAULT_PREDICTIVE_DROP_SLOPE
+}
+
+fn default_debounce_readings() -> u32 {
+    3
+}
+
+fn default_debounce_settle_ms() -> Millis {
+    Millis(2000)
+}
+
+fn default_debounce_min_pause_secs() -> Secs {
+    Secs(30)
+}
+
+fn default_mqtt_port() -> u16 {
+    1883
+}
+
+fn default_mqtt_topic_prefix() -> String {
+    "smartrefresh".to_string()
+}
+
+fn default_mqtt_client_id() -> String {
+    "smart-refresh-daemon".to_string()
+}
+
+fn default_mqtt_interval_secs() -> Secs {
+    Secs(10)
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
-            min_hz: 40,
-            max_hz: 90,
+            min_hz: MIN_ALLOWED_HZ,
+            max_hz: MAX_ALLOWED_HZ,
             sensitivity: Sensitivity::Balanced,
+            custom_drop_ms: None,
+            custom_increase_ms: None,
             enabled: true,
+            socket_path: None,
+            allowed_uids: Vec::new(),
+            websocket_port: None,
+            http_port: None,
+            hook_uid: None,
+            feedback_command: None,
+            mqtt: None,
+            deep_sleep_after_secs: default_deep_sleep_after_secs(),
+            ramp_down: false,
+            drop_step: default_drop_step(),
+            increase_step: StepMode::default(),
+            video_cadence: false,
+            low_fps_compensation: false,
+            fps_tolerance: default_fps_tolerance(),
+            saturation_pacing: default_saturation_pacing(),
+            predictive_drop_fps_per_sec: default_predictive_drop_fps_per_sec(),
+            loading_grace_ms: Millis(0),
+            min_dwell_secs: Secs(0),
+            resume_cooldown_secs: default_resume_cooldown_secs(),
+            manual_change_grace_secs: default_manual_change_grace_secs(),
+            startup_rate: StartupRate::default(),
+            thermal_limit_c: default_thermal_limit_c(),
+            adaptive_sensitivity: false,
+            gpu_utilization: false,
+            adaptive_polling: default_adaptive_polling(),
+            mangohud_overlay: false,
+            auto_detect_game: default_auto_detect_game(),
+            fps_smoothing: FpsSmoothing::default(),
+            fps_ema_alpha: default_fps_ema_alpha(),
+            fps_window_secs: default_fps_window_secs(),
+            idle_policy: IdlePolicy::default(),
+            idle_after_secs: default_idle_after_secs(),
+            control_policy: ControlPolicy::default(),
+            allowed_hz: Vec::new(),
+            ui_hz: None,
+            display_backend: None,
+            fps_source: None,
+            mangohud_log: None,
+            mangohud_shm_names: default_mangohud_shm_names(),
+            device_model: None,
+            led: None,
+            notifications: None,
+            switch_budget: None,
+            display_debounce: DisplayDebounceConfig::default(),
+            control_external_display: false,
+            desktop_mode_switching: false,
+            whitelist_only: false,
+            ignored_connectors: Vec::new(),
+            experimental: ExperimentalConfig::default(),
         }
     }
 }
 
 impl Config {
+    /// Custom drop/increase durations, if both are set.
+    pub fn custom_thresholds(&self) -> Option<(Duration, Duration)> {
+        Some((self.custom_drop_ms?.into(), self.custom_increase_ms?.into()))
+    }
+
+    /// Drop step size in effect, honoring the legacy `ramp_down` flag.
+    pub fn effective_drop_step(&self) -> StepMode {
+        if self.ramp_down {
+            StepMode::Single
+        } else {
+            self.drop_step
+        }
+    }
+
+    /// Predictive drop slope, if the experiment is enabled.
+    pub fn predictive_drop(&self) -> Option<f64> {
+        self.experimental
+            .predictive_drop
+            .then_some(self.predictive_drop_fps_per_sec)
+    }
+
     /// Validate configuration values.
     /// Returns Ok(()) if valid, Err with descriptive message if invalid.
     pub fn validate(&self) -> Result<(), ConfigError> {
         if self.min_hz > self.max_hz {
-            return Err(ConfigError::ValidationError(format!(
+            return Err(ConfigError::Validation(format!(
                 "min_hz ({}) cannot be greater than max_hz ({})",
                 self.min_hz, self.max_hz
             )));
         }
 
-        if self.min_hz < 40 {
-            return Err(ConfigError::ValidationError(format!(
-                "min_hz ({}) must be at least 40Hz",
-                self.min_hz
+        if self.min_hz < *PLAUSIBLE_HZ_RANGE.start() {
+            return Err(ConfigError::Validation(format!(
+                "min_hz ({}) must be at least {}Hz",
+                self.min_hz,
+                PLAUSIBLE_HZ_RANGE.start()
+            )));
+        }
+
+        if self.max_hz > *PLAUSIBLE_HZ_RANGE.end() {
+            return Err(ConfigError::Validation(format!(
+                "max_hz ({}) must not exceed {}Hz",
+                self.max_hz,
+                PLAUSIBLE_HZ_RANGE.end()
+            )));
+        }
+
+        if self.sensitivity == Sensitivity::Custom && self.custom_thresholds().is_none() {
+            return Err(ConfigError::Validation(
+                "custom sensitivity needs custom_drop_ms and custom_increase_ms".to_string(),
+            ));
+        }
+        for ms in [self.custom_drop_ms, self.custom_increase_ms].into_iter().flatten() {
+            if !CUSTOM_THRESHOLD_RANGE.contains(&ms) {
+                return Err(ConfigError::Validation(format!(
+                    "custom sensitivity duration ({}ms) must be between {} and {}ms",
+                    ms,
+                    CUSTOM_THRESHOLD_RANGE.start(),
+                    CUSTOM_THRESHOLD_RANGE.end()
+                )));
+            }
+        }
+
+        if let Some(hz) = self.allowed_hz.iter().find(|hz| !PLAUSIBLE_HZ_RANGE.contains(*hz)) {
+            return Err(ConfigError::Validation(format!(
+                "allowed_hz value ({}) must be between {} and {}Hz",
+                hz,
+                PLAUSIBLE_HZ_RANGE.start(),
+                PLAUSIBLE_HZ_RANGE.end()
+            )));
+        }
+
+        if let Some(ui_hz) = self.ui_hz {
+            if !PLAUSIBLE_HZ_RANGE.contains(&ui_hz) {
+                return Err(ConfigError::Validation(format!(
+                    "ui_hz ({}) must be between {} and {}Hz",
+                    ui_hz,
+                    PLAUSIBLE_HZ_RANGE.start(),
+                    PLAUSIBLE_HZ_RANGE.end()
+                )));
+            }
+        }
+
+        if !(MIN_FPS_TOLERANCE..=MAX_FPS_TOLERANCE).contains(&self.fps_tolerance) {
+            return Err(ConfigError::Validation(format!(
+                "fps_tolerance ({}) must be between {} and {}",
+                self.fps_tolerance, MIN_FPS_TOLERANCE, MAX_FPS_TOLERANCE
+            )));
+        }
+
+        if !(MIN_EMA_ALPHA..=MAX_EMA_ALPHA).contains(&self.fps_ema_alpha) {
+            return Err(ConfigError::Validation(format!(
+                "fps_ema_alpha ({}) must be between {} and {}",
+                self.fps_ema_alpha, MIN_EMA_ALPHA, MAX_EMA_ALPHA
+            )));
+        }
+
+        if !FPS_WINDOW_RANGE.contains(&self.fps_window_secs) {
+            return Err(ConfigError::Validation(format!(
+                "fps_window_secs ({}) must be between {} and {}s",
+                self.fps_window_secs,
+                FPS_WINDOW_RANGE.start(),
+                FPS_WINDOW_RANGE.end()
             )));
         }
 
-        if self.max_hz > 90 {
-            return Err(ConfigError::ValidationError(format!(
-                "max_hz ({}) must not exceed 90Hz",
-                self.max_hz
+        if !(MIN_SATURATION_PACING..=MAX_SATURATION_PACING).contains(&self.saturation_pacing) {
+            return Err(ConfigError::Validation(format!(
+                "saturation_pacing ({}) must be between {} and {}",
+                self.saturation_pacing, MIN_SATURATION_PACING, MAX_SATURATION_PACING
             )));
         }
 
+        if !(MIN_PREDICTIVE_DROP_SLOPE..=MAX_PREDICTIVE_DROP_SLOPE)
+            .contains(&self.predictive_drop_fps_per_sec)
+        {
+            return Err(ConfigError::Validation(format!(
+                "predictive_drop_fps_per_sec ({}) must be between {} and {}",
+                self.predictive_drop_fps_per_sec, MIN_PREDICTIVE_DROP_SLOPE, MAX_PREDICTIVE_DROP_SLOPE
+            )));
+        }
+
+        if self.loading_grace_ms > MAX_LOADING_GRACE {
+            return Err(ConfigError::Validation(format!(
+                "loading_grace_ms ({}) must not exceed {}ms",
+                self.loading_grace_ms, MAX_LOADING_GRACE
+            )));
+        }
+
+        if !(MIN_THERMAL_LIMIT_C..=MAX_THERMAL_LIMIT_C).contains(&self.thermal_limit_c) {
+            return Err(ConfigError::Validation(format!(
+                "thermal_limit_c ({}) must be between {} and {}",
+                self.thermal_limit_c, MIN_THERMAL_LIMIT_C, MAX_THERMAL_LIMIT_C
+            )));
+        }
+
+        if self.min_dwell_secs > MAX_MIN_DWELL {
+            return Err(ConfigError::Validation(format!(
+                "min_dwell_secs ({}) must not exceed {}s",
+                self.min_dwell_secs, MAX_MIN_DWELL
+            )));
+        }
+
+        if self.resume_cooldown_secs > MAX_RESUME_COOLDOWN {
+            return Err(ConfigError::Validation(format!(
+                "resume_cooldown_secs ({}) must not exceed {}s",
+                self.resume_cooldown_secs, MAX_RESUME_COOLDOWN
+            )));
+        }
+
+        if self.manual_change_grace_secs > MAX_MANUAL_CHANGE_GRACE {
+            return Err(ConfigError::Validation(format!(
+                "manual_change_grace_secs ({}) must not exceed {}s",
+                self.manual_change_grace_secs, MAX_MANUAL_CHANGE_GRACE
+            )));
+        }
+
+        if let Some(budget) = &self.switch_budget {
+            if budget.per_hour == Some(0) || budget.per_day == Some(0) {
+                return Err(ConfigError::Validation(
+                    "switch_budget limits must be at least 1".to_string(),
+                ));
+            }
+        }
+
+        if self.display_debounce.readings == 0 {
+            return Err(ConfigError::Validation(
+                "display_debounce.readings must be at least 1".to_string(),
+            ));
+        }
+        if self.display_debounce.settle_ms > Millis(60_000) {
+            return Err(ConfigError::Validation(format!(
+                "display_debounce.settle_ms ({}) must not exceed 60000ms",
+                self.display_debounce.settle_ms
+            )));
+        }
+
+        if self.mangohud_shm_names.is_empty() {
+            return Err(ConfigError::Validation(
+                "mangohud_shm_names must name at least one segment".to_string(),
+            ));
+        }
+        if let Some(name) = self
+            .mangohud_shm_names
+            .iter()
+            .find(|name| !name.starts_with('/') || name.len() < 2 || name[1..].contains('/') || name.contains('\0'))
+        {
+            return Err(ConfigError::Validation(format!(
+                "mangohud_shm_names entry {:?} must be a single '/'-prefixed name",
+                name
+            )));
+        }
+
+        if let Some(led) = &self.led {
+            let outside_sysfs = led
+                .states
+                .values()
+                .flatten()
+                .find(|w| !w.path.starts_with("/sys/") || w.path.components().any(|c| c == Component::ParentDir));
+            if let Some(write) = outside_sysfs {
+                return Err(ConfigError::Validation(format!(
+                    "led path {:?} must be under /sys",
+                    write.path
+                )));
+            }
+        }
+
+        if let Some(mqtt) = &self.mqtt {
+            if mqtt.host.is_empty() {
+                return Err(ConfigError::Validation(
+                    "mqtt.host must not be empty".to_string(),
+                ));
+            }
+            if mqtt.interval_secs == Secs(0) {
+                return Err(ConfigError::Validation(
+                    "mqtt.interval_secs must be at least 1".to_string(),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Config {
+    /// Check the configured rates against the panel's own refresh range.
+    pub fn validate_for_panel(&self, panel: &RangeInclusive<Hertz>) -> Result<(), ConfigError> {
+        let rates = [("min_hz", self.min_hz), ("max_hz", self.max_hz)]
+            .into_iter()
+            .chain(self.allowed_hz.iter().map(|hz| ("allowed_hz value", *hz)))
+            .chain(self.ui_hz.map(|hz| ("ui_hz", hz)));
+        for (name, hz) in rates {
+            if !panel.contains(&hz) {
+                return Err(ConfigError::Validation(format!(
+                    "{} ({}) is outside the panel's {}-{}Hz range",
+                    name,
+                    hz,
+                    panel.start(),
+                    panel.end()
+                )));
+            }
+        }
         Ok(())
     }
 }
@@ -63,6 +702,8 @@ impl Config {
 pub struct ConfigManager {
     config: RwLock<Config>,
     path: PathBuf,
+    /// Refresh range of the panel, checked on update
+    panel_range: RwLock<RangeInclusive<Hertz>>,
 }
 
 impl ConfigManager {
@@ -71,11 +712,11 @@ impl ConfigManager {
     pub fn load_or_default(path: &Path) -> Result<Self, ConfigError> {
         let config = if path.exists() {
             let contents = fs::read_to_string(path).map_err(|e| {
-                ConfigError::ParseError(format!("Failed to read config file: {}", e))
+                ConfigError::Parse(format!("Failed to read config file: {}", e))
             })?;
 
             let config: Config = serde_json::from_str(&contents)
-                .map_err(|e| ConfigError::ParseError(format!("Invalid JSON: {}", e)))?;
+                .map_err(|e| ConfigError::Parse(format!("Invalid JSON: {}", e)))?;
 
             // Validate loaded config
             config.validate()?;
@@ -87,32 +728,20 @@ impl ConfigManager {
         Ok(Self {
             config: RwLock::new(config),
             path: path.to_path_buf(),
+            panel_range: RwLock::new(MIN_ALLOWED_HZ..=MAX_ALLOWED_HZ),
         })
     }
 
     /// Save configuration to file using atomic write.
     pub fn save(&self) -> Result<(), ConfigError> {
         let config = self.config.read().map_err(|_| {
-            ConfigError::ValidationError("Failed to acquire read lock".to_string())
+            ConfigError::Validation("Failed to acquire read lock".to_string())
         })?;
 
-        // Ensure parent directory exists
-        if let Some(parent) = self.path.parent() {
-            fs::create_dir_all(parent)?;
-        }
-
-        // Atomic write: write to temp file, then rename
-        let temp_path = self.path.with_extension("json.tmp");
         let json = serde_json::to_string_pretty(&*config)
-            .map_err(|e| ConfigError::ParseError(format!("Failed to serialize config: {}", e)))?;
+            .map_err(|e| ConfigError::Parse(format!("Failed to serialize config: {}", e)))?;
 
-        {
-            let mut file = fs::File::create(&temp_path)?;
-            file.write_all(json.as_bytes())?;
-            file.sync_all()?;
-        }
-
-        fs::rename(&temp_path, &self.path)?;
+        atomic_persist(&self.path, json.as_bytes())?;
 
         Ok(())
     }
@@ -129,9 +758,10 @@ impl ConfigManager {
     pub fn update(&self, config: Config) -> Result<(), ConfigError> {
         // Validate before updating
         config.validate()?;
+        config.validate_for_panel(&self.panel_range())?;
 
         let mut current = self.config.write().map_err(|_| {
-            ConfigError::ValidationError("Failed to acquire write lock".to_string())
+            ConfigError::Validation("Failed to acquire write lock".to_string())
         })?;
 
         *current = config;
@@ -143,9 +773,26 @@ impl ConfigManager {
         self.save()
     }
 
-    /// Get the config file path.
-    pub fn path(&self) -> &Path {
-        &self.path
+    /// Refresh range of the panel (`MIN_ALLOWED_HZ`-`MAX_ALLOWED_HZ` until detected).
+    pub fn panel_range(&self) -> RangeInclusive<Hertz> {
+        self.panel_range
+            .read()
+            .map(|range| range.clone())
+            .unwrap_or(MIN_ALLOWED_HZ..=MAX_ALLOWED_HZ)
+    }
+
+    /// Reject updates with rates the panel can't show. The loaded config is
+    /// clamped to the range so later updates of other fields still validate.
+    pub fn set_panel_range(&self, range: RangeInclusive<Hertz>) {
+        if let Ok(mut config) = self.config.write() {
+            config.min_hz = config.min_hz.clamp(*range.start(), *range.end());
+            config.max_hz = config.max_hz.clamp(config.min_hz, *range.end());
+            config.allowed_hz.retain(|hz| range.contains(hz));
+            config.ui_hz = config.ui_hz.filter(|hz| range.contains(hz));
+        }
+        if let Ok(mut current) = self.panel_range.write() {
+            *current = range;
+        }
     }
 
     /// Get the default config path (~/.config/smart-refresh/config.json).
@@ -175,6 +822,7 @@ impl Serialize for Sensitivity {
             Sensitivity::Conservative => "conservative",
             Sensitivity::Balanced => "balanced",
             Sensitivity::Aggressive => "aggressive",
+            Sensitivity::Custom => "custom",
         };
         serializer.serialize_str(s)
     }
@@ -190,8 +838,9 @@ impl<'de> Deserialize<'de> for Sensitivity {
             "conservative" => Ok(Sensitivity::Conservative),
             "balanced" => Ok(Sensitivity::Balanced),
             "aggressive" => Ok(Sensitivity::Aggressive),
+            "custom" => Ok(Sensitivity::Custom),
             _ => Err(serde::de::Error::custom(format!(
-                "invalid sensitivity: {}, expected one of: conservative, balanced, aggressive",
+                "invalid sensitivity: {}, expected one of: conservative, balanced, aggressive, custom",
                 s
             ))),
         }
@@ -207,8 +856,8 @@ mod tests {
     #[test]
     fn test_config_default() {
         let config = Config::default();
-        assert_eq!(config.min_hz, 40);
-        assert_eq!(config.max_hz, 90);
+        assert_eq!(config.min_hz, Hertz(40));
+        assert_eq!(config.max_hz, Hertz(90));
         assert_eq!(config.sensitivity, Sensitivity::Balanced);
         assert!(config.enabled);
     }
@@ -233,8 +882,8 @@ mod tests {
         // Create and save config
         let manager = ConfigManager::load_or_default(&path).unwrap();
         let mut config = manager.get();
-        config.min_hz = 50;
-        config.max_hz = 80;
+        config.min_hz = Hertz(50);
+        config.max_hz = Hertz(80);
         config.sensitivity = Sensitivity::Aggressive;
         manager.update(config.clone()).unwrap();
         
@@ -242,59 +891,312 @@ mod tests {
         let manager2 = ConfigManager::load_or_default(&path).unwrap();
         let loaded = manager2.get();
         
-        assert_eq!(loaded.min_hz, 50);
-        assert_eq!(loaded.max_hz, 80);
+        assert_eq!(loaded.min_hz, Hertz(50));
+        assert_eq!(loaded.max_hz, Hertz(80));
         assert_eq!(loaded.sensitivity, Sensitivity::Aggressive);
     }
 
+    #[test]
+    fn test_config_manager_panel_range() {
+        let dir = tempdir().unwrap();
+        let manager = ConfigManager::load_or_default(&dir.path().join("config.json")).unwrap();
+        manager.set_panel_range(Hertz(45)..=Hertz(90));
+
+        let config = Config { min_hz: Hertz(40), ..Default::default() };
+        assert!(manager.update(config).is_err());
+        let config = Config { min_hz: Hertz(45), allowed_hz: vec![Hertz(40)], ..Default::default() };
+        assert!(manager.update(config).is_err());
+        let config = Config { min_hz: Hertz(45), ..Default::default() };
+        assert!(manager.update(config).is_ok());
+
+        // A loaded config outside a narrower range is clamped into it
+        manager.set_panel_range(Hertz(50)..=Hertz(60));
+        let config = manager.get();
+        assert_eq!((config.min_hz, config.max_hz), (Hertz(50), Hertz(60)));
+        assert!(manager.update(config).is_ok());
+    }
+
     #[test]
     fn test_config_validation_min_greater_than_max() {
         let config = Config {
-            min_hz: 80,
-            max_hz: 60,
+            min_hz: Hertz(80),
+            max_hz: Hertz(60),
             sensitivity: Sensitivity::Balanced,
             enabled: true,
+            ..Default::default()
         };
         
         let result = config.validate();
         assert!(result.is_err());
         let err = result.unwrap_err();
-        assert!(matches!(err, ConfigError::ValidationError(_)));
+        assert!(matches!(err, ConfigError::Validation(_)));
     }
 
     #[test]
     fn test_config_validation_min_too_low() {
         let config = Config {
-            min_hz: 30,
-            max_hz: 90,
+            min_hz: Hertz(30),
+            max_hz: Hertz(90),
             sensitivity: Sensitivity::Balanced,
             enabled: true,
+            ..Default::default()
         };
         
-        let result = config.validate();
-        assert!(result.is_err());
+        assert!(config.validate().is_ok());
+        assert!(config.validate_for_panel(&(MIN_ALLOWED_HZ..=MAX_ALLOWED_HZ)).is_err());
+        assert!(Config { min_hz: Hertz(10), ..config }.validate().is_err());
     }
 
     #[test]
     fn test_config_validation_max_too_high() {
         let config = Config {
-            min_hz: 40,
-            max_hz: 120,
+            min_hz: Hertz(40),
+            max_hz: Hertz(120),
             sensitivity: Sensitivity::Balanced,
             enabled: true,
+            ..Default::default()
         };
         
-        let result = config.validate();
-        assert!(result.is_err());
+        // Fine for a 120Hz panel, not for the fallback range
+        assert!(config.validate().is_ok());
+        assert!(config.validate_for_panel(&(Hertz(40)..=Hertz(120))).is_ok());
+        assert!(config.validate_for_panel(&(MIN_ALLOWED_HZ..=MAX_ALLOWED_HZ)).is_err());
+        assert!(Config { max_hz: Hertz(500), ..config }.validate().is_err());
+    }
+
+    #[test]
+    fn test_config_validation_ui_hz_out_of_range() {
+        let config = Config {
+            ui_hz: Some(Hertz(120)),
+            ..Default::default()
+        };
+        assert!(config.validate_for_panel(&(MIN_ALLOWED_HZ..=MAX_ALLOWED_HZ)).is_err());
+        let config = Config {
+            ui_hz: Some(Hertz(500)),
+            ..Default::default()
+        };
+        assert!(config.validate().is_err());
+
+        let config = Config {
+            ui_hz: Some(Hertz(60)),
+            ..Default::default()
+        };
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_config_validation_switch_budget() {
+        let config = Config {
+            switch_budget: Some(SwitchBudgetConfig { per_hour: Some(0), per_day: None }),
+            ..Default::default()
+        };
+        assert!(config.validate().is_err());
+
+        let config = Config {
+            switch_budget: Some(SwitchBudgetConfig { per_hour: Some(30), per_day: Some(300) }),
+            ..Default::default()
+        };
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_config_validation_loading_grace() {
+        let config = Config {
+            loading_grace_ms: Millis(5000),
+            ..Default::default()
+        };
+        assert!(config.validate().is_ok());
+
+        let config = Config {
+            loading_grace_ms: Millis(60_000),
+            ..Default::default()
+        };
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_predictive_drop_gated_by_experiment() {
+        let mut config = Config::default();
+        assert_eq!(config.predictive_drop(), None);
+        config.experimental.predictive_drop = true;
+        assert_eq!(config.predictive_drop(), Some(DEFAULT_PREDICTIVE_DROP_SLOPE));
+        assert!(config.validate().is_ok());
+
+        config.predictive_drop_fps_per_sec = 0.5;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_effective_drop_step() {
+        let config = Config::default();
+        assert_eq!(config.effective_drop_step(), StepMode::Jump);
+        assert_eq!(config.increase_step, StepMode::Single);
+
+        // Legacy flag wins over the default jump
+        let config = Config { ramp_down: true, ..Default::default() };
+        assert_eq!(config.effective_drop_step(), StepMode::Single);
+
+        let config = Config { drop_step: StepMode::Single, ..Default::default() };
+        assert_eq!(config.effective_drop_step(), StepMode::Single);
+    }
+
+    #[test]
+    fn test_config_validation_thermal_limit() {
+        assert_eq!(Config::default().thermal_limit_c, DEFAULT_THERMAL_LIMIT_C);
+        let config = Config { thermal_limit_c: 40, ..Default::default() };
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_config_validation_min_dwell() {
+        let config = Config {
+            min_dwell_secs: Secs(10),
+            ..Default::default()
+        };
+        assert!(config.validate().is_ok());
+
+        let config = Config {
+            min_dwell_secs: Secs(600),
+            ..Default::default()
+        };
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_config_validation_resume_cooldown() {
+        assert_eq!(Config::default().resume_cooldown_secs, Secs(DEFAULT_RESUME_COOLDOWN_SECS));
+        let config = Config { resume_cooldown_secs: Secs(0), ..Default::default() };
+        assert!(config.validate().is_ok());
+        let config = Config { resume_cooldown_secs: Secs(300), ..Default::default() };
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_config_validation_manual_change_grace() {
+        assert_eq!(Config::default().manual_change_grace_secs, Secs(30));
+        let config = Config { manual_change_grace_secs: Secs(0), ..Default::default() };
+        assert!(config.validate().is_ok());
+        let config = Config { manual_change_grace_secs: Secs(3600), ..Default::default() };
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_display_debounce_config() {
+        let debounce: DisplayDebounceConfig = serde_json::from_str(r#"{"readings":5}"#).unwrap();
+        assert_eq!(debounce.readings, 5);
+        assert_eq!(debounce.min_pause_secs, Secs(30));
+
+        let config = Config {
+            display_debounce: DisplayDebounceConfig { readings: 0, ..Default::default() },
+            ..Default::default()
+        };
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_config_validation_fps_tolerance() {
+        assert_eq!(Config::default().fps_tolerance, DEFAULT_FPS_TOLERANCE);
+        let config = Config { fps_tolerance: 1.0, ..Default::default() };
+        assert!(config.validate().is_err());
+        let config = Config { fps_tolerance: 5.0, ..Default::default() };
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_config_validation_saturation_pacing() {
+        assert_eq!(Config::default().saturation_pacing, DEFAULT_SATURATION_PACING);
+        let config = Config { saturation_pacing: 0.5, ..Default::default() };
+        assert!(config.validate().is_err());
+        let config = Config { saturation_pacing: 1.0, ..Default::default() };
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_config_validation_shm_names() {
+        assert_eq!(Config::default().mangohud_shm_names, vec!["/mangohud-overlay".to_string()]);
+        let names = |names: &[&str]| Config {
+            mangohud_shm_names: names.iter().map(|name| name.to_string()).collect(),
+            ..Default::default()
+        };
+        assert!(names(&["/mangohud-overlay", "/mangohud"]).validate().is_ok());
+        assert!(names(&[]).validate().is_err());
+        assert!(names(&["mangohud"]).validate().is_err());
+        assert!(names(&["/"]).validate().is_err());
+        assert!(names(&["/dev/shm/mangohud"]).validate().is_err());
+    }
+
+    #[test]
+    fn test_config_validation_fps_ema_alpha() {
+        assert_eq!(Config::default().fps_smoothing, FpsSmoothing::Mean);
+        let config = Config { fps_smoothing: FpsSmoothing::Ema, fps_ema_alpha: 0.3, ..Default::default() };
+        assert!(config.validate().is_ok());
+        assert!(Config { fps_ema_alpha: 0.0, ..config.clone() }.validate().is_err());
+        assert!(Config { fps_ema_alpha: 1.5, ..config }.validate().is_err());
+    }
+
+    #[test]
+    fn test_config_validation_fps_window() {
+        assert_eq!(Config::default().fps_window_secs, Secs(12));
+        assert!(Config { fps_window_secs: Secs(3), ..Default::default() }.validate().is_ok());
+        assert!(Config { fps_window_secs: Secs(0), ..Default::default() }.validate().is_err());
+        assert!(Config { fps_window_secs: Secs(120), ..Default::default() }.validate().is_err());
+    }
+
+    #[test]
+    fn test_config_validation_led_paths() {
+        let led_config = |path: &str| Config {
+            led: Some(LedConfig {
+                command: None,
+                states: [(
+                    LedState::Eco,
+                    vec![SysfsWrite { path: PathBuf::from(path), value: "0".to_string() }],
+                )]
+                .into_iter()
+                .collect(),
+            }),
+            ..Default::default()
+        };
+
+        assert!(led_config("/sys/class/leds/power/brightness").validate().is_ok());
+        assert!(led_config("/etc/passwd").validate().is_err());
+        assert!(led_config("/sys/../etc/passwd").validate().is_err());
+    }
+
+    #[test]
+    fn test_config_validation_custom_sensitivity() {
+        let config = Config {
+            sensitivity: Sensitivity::Custom,
+            ..Default::default()
+        };
+        assert!(config.validate().is_err());
+
+        let config = Config {
+            sensitivity: Sensitivity::Custom,
+            custom_drop_ms: Some(Millis(800)),
+            custom_increase_ms: Some(Millis(2500)),
+            ..Default::default()
+        };
+        assert!(config.validate().is_ok());
+        assert_eq!(
+            config.custom_thresholds(),
+            Some((Duration::from_millis(800), Duration::from_millis(2500)))
+        );
+
+        let config = Config {
+            custom_drop_ms: Some(Millis(10)),
+            ..Default::default()
+        };
+        assert!(config.validate().is_err());
     }
 
     #[test]
     fn test_sensitivity_serialization() {
         let config = Config {
-            min_hz: 40,
-            max_hz: 90,
+            min_hz: Hertz(40),
+            max_hz: Hertz(90),
             sensitivity: Sensitivity::Conservative,
             enabled: true,
+            ..Default::default()
         };
         
         let json = serde_json::to_string(&config).unwrap();
@@ -313,6 +1215,21 @@ mod tests {
         assert!(err.contains("invalid sensitivity"));
     }
 
+    #[test]
+    fn test_mqtt_config_defaults() {
+        let json = r#"{"min_hz":40,"max_hz":90,"sensitivity":"balanced","enabled":true,"mqtt":{"host":"homeassistant.local"}}"#;
+        let config: Config = serde_json::from_str(json).unwrap();
+        let mqtt = config.mqtt.as_ref().unwrap();
+        assert_eq!(mqtt.port, 1883);
+        assert_eq!(mqtt.topic_prefix, "smartrefresh");
+        assert_eq!(mqtt.interval_secs, Secs(10));
+        assert!(config.validate().is_ok());
+
+        let mut invalid = config.clone();
+        invalid.mqtt.as_mut().unwrap().host.clear();
+        assert!(invalid.validate().is_err());
+    }
+
     // Strategy to generate valid Sensitivity values
     fn sensitivity_strategy() -> impl Strategy<Value = Sensitivity> {
         prop_oneof![
@@ -328,10 +1245,11 @@ mod tests {
             .prop_filter_map("min must be <= max", |(min, max, sens, enabled)| {
                 if min <= max {
                     Some(Config {
-                        min_hz: min,
-                        max_hz: max,
+                        min_hz: Hertz(min),
+                        max_hz: Hertz(max),
                         sensitivity: sens,
                         enabled,
+                        ..Default::default()
                     })
                 } else {
                     None
@@ -398,48 +1316,50 @@ mod tests {
             prop_assume!(min_hz > max_hz);
             
             let config = Config {
-                min_hz,
-                max_hz,
+                min_hz: Hertz(min_hz),
+                max_hz: Hertz(max_hz),
                 sensitivity,
                 enabled,
+                ..Default::default()
             };
             
             let result = config.validate();
             prop_assert!(result.is_err(), "Should reject config where min_hz > max_hz");
             
-            if let Err(ConfigError::ValidationError(msg)) = result {
+            if let Err(ConfigError::Validation(msg)) = result {
                 prop_assert!(msg.contains("cannot be greater than"), "Error message should mention min > max");
             } else {
-                prop_assert!(false, "Should be ValidationError");
+                prop_assert!(false, "Should be a validation error");
             }
         }
 
         #[test]
-        fn prop_config_validation_rejects_min_below_40(
+        fn prop_config_validation_rejects_min_below_panel(
             min_hz in 0u32..40u32,
             max_hz in 40u32..=90u32,
             sensitivity in sensitivity_strategy(),
             enabled in any::<bool>(),
         ) {
             let config = Config {
-                min_hz,
-                max_hz,
+                min_hz: Hertz(min_hz),
+                max_hz: Hertz(max_hz),
                 sensitivity,
                 enabled,
+                ..Default::default()
             };
             
-            let result = config.validate();
+            let result = config.validate_for_panel(&(MIN_ALLOWED_HZ..=MAX_ALLOWED_HZ));
             prop_assert!(result.is_err(), "Should reject config where min_hz < 40");
             
-            if let Err(ConfigError::ValidationError(msg)) = result {
-                prop_assert!(msg.contains("at least 40Hz"), "Error message should mention minimum 40Hz");
+            if let Err(ConfigError::Validation(msg)) = result {
+                prop_assert!(msg.contains("40-90Hz range"), "Error message should mention the 40-90Hz range");
             } else {
-                prop_assert!(false, "Should be ValidationError");
+                prop_assert!(false, "Should be a validation error");
             }
         }
 
         #[test]
-        fn prop_config_validation_rejects_max_above_90(
+        fn prop_config_validation_rejects_max_above_panel(
             min_hz in 40u32..=90u32,
             max_hz in 91u32..=200u32,
             sensitivity in sensitivity_strategy(),
@@ -449,19 +1369,20 @@ mod tests {
             prop_assume!(min_hz <= max_hz);
             
             let config = Config {
-                min_hz,
-                max_hz,
+                min_hz: Hertz(min_hz),
+                max_hz: Hertz(max_hz),
                 sensitivity,
                 enabled,
+                ..Default::default()
             };
             
-            let result = config.validate();
+            let result = config.validate_for_panel(&(MIN_ALLOWED_HZ..=MAX_ALLOWED_HZ));
             prop_assert!(result.is_err(), "Should reject config where max_hz > 90");
             
-            if let Err(ConfigError::ValidationError(msg)) = result {
-                prop_assert!(msg.contains("not exceed 90Hz"), "Error message should mention maximum 90Hz");
+            if let Err(ConfigError::Validation(msg)) = result {
+                prop_assert!(msg.contains("40-90Hz range"), "Error message should mention the 40-90Hz range");
             } else {
-                prop_assert!(false, "Should be ValidationError");
+                prop_assert!(false, "Should be a validation error");
             }
         }
 
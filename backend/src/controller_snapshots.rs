@@ -0,0 +1,118 @@
+//! Snapshot tests of controller decisions on canonical FPS traces.
+//!
+//! Each trace is replayed for every sensitivity and device mode, and the
+//! resulting switches are compared with insta against
+//! `testdata/snapshots/<trace>.snap`. Review changes with `cargo insta review`.
+
+use crate::config::Config;
+use crate::core_logic::{DeviceMode, Sensitivity, DEFAULT_FPS_TOLERANCE};
+use crate::units::Hertz;
+use crate::whatif::Scenario;
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+
+const POLL: Duration = Duration::from_millis(100);
+
+const SENSITIVITIES: [Sensitivity; 3] = [
+    Sensitivity::Conservative,
+    Sensitivity::Balanced,
+    Sensitivity::Aggressive,
+];
+
+const DEVICE_MODES: [DeviceMode; 2] = [DeviceMode::Oled, DeviceMode::Lcd];
+
+/// A named FPS trace: FPS as a function of seconds since start.
+struct Trace {
+    name: &'static str,
+    secs: u64,
+    fps: fn(f64) -> f64,
+}
+
+const TRACES: &[Trace] = &[
+    // Steady 60 FPS game on a 90Hz start
+    Trace { name: "steady_60", secs: 20, fps: |_| 60.0 },
+    // Level load: 90 FPS, 4s collapse to 10 FPS, back to 90
+    Trace {
+        name: "loading_dip",
+        secs: 20,
+        fps: |t| if (5.0..9.0).contains(&t) { 10.0 } else { 90.0 },
+    },
+    // Heavier scene creeping in: 90 down to 45 over 20s, then held
+    Trace {
+        name: "gradual_decline",
+        secs: 30,
+        fps: |t| (90.0 - 2.25 * t).max(45.0),
+    },
+    // Low FPS recovering to uncapped (FPS follows the panel up to 90)
+    Trace {
+        name: "recovery",
+        secs: 40,
+        fps: |t| if t < 5.0 { 45.0 } else { 95.0 },
+    },
+    // Noisy 50 FPS, +-6 FPS square wave every 300ms
+    Trace {
+        name: "noisy_50",
+        secs: 20,
+        fps: |t| if (t * 10.0).round() as u64 % 6 < 3 { 56.0 } else { 44.0 },
+    },
+    // 24fps video playback
+    Trace { name: "video_24", secs: 15, fps: |_| 23.976 },
+];
+
+/// Replay `trace` under one scenario and describe each switch.
+fn record(trace: &Trace, sensitivity: Sensitivity, mode: DeviceMode) -> String {
+    let config = Config { sensitivity, ..Default::default() };
+    let scenario = Scenario::from_config(&config, DEFAULT_FPS_TOLERANCE, mode);
+    let mut controller = scenario.controller();
+    let mut hz: Hertz = controller.clamp_hz(config.max_hz);
+    let start = Instant::now();
+    let mut out = format!("start {}Hz\n", hz);
+
+    for i in 0..trace.secs * 1000 / POLL.as_millis() as u64 {
+        let elapsed = POLL * i as u32;
+        let fps = (trace.fps)(elapsed.as_secs_f64());
+        if let Some(target) = controller.process_with_time(fps, hz, start + elapsed) {
+            let _ = writeln!(
+                out,
+                "{:>5.1}s fps {:>5.1} {}Hz -> {}Hz",
+                elapsed.as_secs_f64(),
+                fps,
+                hz,
+                target
+            );
+            hz = target;
+        }
+    }
+    let _ = writeln!(out, "end {}Hz", hz);
+    out
+}
+
+/// Render every sensitivity/mode combination for `trace`.
+fn render(trace: &Trace) -> String {
+    let mut out = String::new();
+    for mode in DEVICE_MODES {
+        for sensitivity in SENSITIVITIES {
+            let _ = writeln!(out, "== {:?} / {:?}", mode, sensitivity);
+            out.push_str(&record(trace, sensitivity, mode));
+        }
+    }
+    out
+}
+
+#[test]
+fn test_controller_snapshots() {
+    insta::with_settings!({
+        snapshot_path => "../testdata/snapshots",
+        prepend_module_to_snapshot => false,
+    }, {
+        for trace in TRACES {
+            insta::assert_snapshot!(trace.name, render(trace));
+        }
+    });
+}
+
+#[test]
+fn test_snapshots_are_deterministic() {
+    let trace = &TRACES[1];
+    assert_eq!(render(trace), render(trace));
+}
@@ -12,7 +12,13 @@
 //! - Configurable FPS tolerance (2.0-5.0)
 //! - Resume cooldown (silence period after wake)
 //! - Gamescope frame limiter integration
+//!
+//! An alternative PID policy tracks low-pass filtered FPS continuously
+//! instead of waiting for sustained drops/increases.
 
+use crate::display_control::{MAX_ALLOWED_HZ, MIN_ALLOWED_HZ};
+use crate::units::{Hertz, Millis, Secs};
+use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 use std::time::{Duration, Instant};
 
@@ -25,9 +31,47 @@ pub const MIN_FPS_TOLERANCE: f64 = 2.0;
 /// Maximum FPS tolerance (for stability-focused users)
 pub const MAX_FPS_TOLERANCE: f64 = 5.0;
 
+/// Default increase threshold multiplier for the final step up to max Hz
+pub const DEFAULT_SATURATION_PACING: f64 = 2.0;
+
+/// Accepted range for the saturation pacing multiplier (1.0 disables it)
+pub const MIN_SATURATION_PACING: f64 = 1.0;
+pub const MAX_SATURATION_PACING: f64 = 5.0;
+
+/// Longest minimum dwell time at a refresh level
+pub const MAX_MIN_DWELL_SECS: u64 = 120;
+
+/// Video frame rates snapped to a matching refresh rate (film, PAL, NTSC)
+pub const VIDEO_CADENCES: [u32; 3] = [24, 25, 30];
+
+/// Max distance from a cadence for FPS to count as on it (covers 23.976/29.97)
+const CADENCE_FPS_TOLERANCE: f64 = 0.6;
+
+/// Seconds FPS must hold a cadence before the rate is snapped to it
+pub const CADENCE_LOCK_SECS: u64 = 3;
+
+/// FPS below this fraction of the recent average is a loading-screen collapse
+const LOADING_DROP_RATIO: f64 = 0.5;
+
+/// FPS back to this fraction of the pre-collapse average ends a loading hold
+const LOADING_RECOVERY_RATIO: f64 = 0.9;
+
+/// Samples of history needed before a collapse can be detected
+const LOADING_MIN_SAMPLES: usize = 5;
+
 /// Number of samples for adaptive sensitivity sliding window
 pub const ADAPTIVE_WINDOW_SIZE: usize = 10;
 
+/// Samples of history needed before the FPS trend is trusted
+const TREND_MIN_SAMPLES: usize = 5;
+
+/// Default FPS decline (FPS per second) that starts a predictive drop
+pub const DEFAULT_PREDICTIVE_DROP_SLOPE: f64 = 15.0;
+
+/// Accepted range for the predictive drop slope
+pub const MIN_PREDICTIVE_DROP_SLOPE: f64 = 2.0;
+pub const MAX_PREDICTIVE_DROP_SLOPE: f64 = 100.0;
+
 /// Standard deviation threshold for stable FPS (allow user sensitivity)
 pub const STD_DEV_STABLE: f64 = 2.0;
 
@@ -37,10 +81,124 @@ pub const STD_DEV_UNSTABLE: f64 = 5.0;
 /// Default resume cooldown duration (seconds of silence after wake)
 pub const DEFAULT_RESUME_COOLDOWN_SECS: u64 = 5;
 
+/// Longest resume cooldown
+pub const MAX_RESUME_COOLDOWN_SECS: u64 = 60;
+
+/// Window over which frametime spikes are counted
+pub const SPIKE_WINDOW_SECS: u64 = 5;
+
+/// Spikes within the window that count as stutter
+pub const SPIKE_THRESHOLD: usize = 5;
+
+/// A frametime this many times the running baseline is a spike
+const SPIKE_FACTOR: f64 = 2.0;
+
+/// Smoothing factor for the frametime baseline
+const SPIKE_BASELINE_ALPHA: f64 = 0.1;
+
+/// Window in which direction reversals count as oscillation
+pub const OSCILLATION_WINDOW_SECS: u64 = 60;
+
+/// Reversals within the window that trigger damping
+pub const OSCILLATION_REVERSALS: usize = 3;
+
+/// How long damping lasts once triggered
+pub const OSCILLATION_DAMPING_SECS: u64 = 120;
+
+/// Extra FPS tolerance while damped
+const OSCILLATION_TOLERANCE_BOOST: f64 = 2.0;
+
+/// Minimum change interval multiplier while damped
+const OSCILLATION_INTERVAL_FACTOR: u32 = 3;
+
+/// Proportional gain of the PID policy (Hz per FPS of error)
+const PID_KP: f64 = 0.8;
+
+/// Integral gain of the PID policy (Hz per FPS-second of error)
+const PID_KI: f64 = 0.5;
+
+/// Derivative gain of the PID policy (Hz per FPS/s of smoothed FPS change)
+const PID_KD: f64 = 0.1;
+
+/// Low-pass filter weight of each new FPS sample in the PID policy
+const PID_SMOOTHING_ALPHA: f64 = 0.3;
+
+/// Anti-windup bound on the integral term (FPS-seconds)
+const PID_INTEGRAL_LIMIT: f64 = 10.0;
+
+/// Error fed to the PID policy while FPS is pinned at the refresh rate.
+/// FPS cannot exceed the panel rate, so this is what probes upward.
+const PID_SATURATED_ERROR: f64 = 1.0;
+
+/// Longest sample gap integrated at once, so pauses don't cause jumps
+const PID_MAX_DT: Duration = Duration::from_secs(1);
+
+/// How the controller turns FPS samples into refresh rate decisions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ControlPolicy {
+    /// Discrete state machine with sustained drop/increase thresholds
+    #[default]
+    Hysteresis,
+    /// Continuous PID tracking of low-pass filtered FPS
+    Pid,
+}
+
+/// How far one drop or increase moves the refresh rate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StepMode {
+    /// One rate step (5Hz or the neighbouring allowed rate) per change
+    #[default]
+    Single,
+    /// Straight to the target: the FPS target for drops, max Hz for increases
+    Jump,
+}
+
+/// What to do with the refresh rate when no valid FPS data arrives.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IdlePolicy {
+    /// Leave Hz wherever it was
+    #[default]
+    Hold,
+    /// Drop to min Hz to save power
+    Min,
+    /// Restore max Hz for the desktop/UI
+    Max,
+}
+
+impl IdlePolicy {
+    /// Rate to apply while idle, or `None` to hold.
+    pub fn target(self, min_hz: Hertz, max_hz: Hertz) -> Option<Hertz> {
+        match self {
+            IdlePolicy::Hold => None,
+            IdlePolicy::Min => Some(min_hz),
+            IdlePolicy::Max => Some(max_hz),
+        }
+    }
+}
+
+/// Effective decision timing at the current rate, reported in status.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct DecisionPacing {
+    /// How long FPS must stay low before a drop
+    pub drop_threshold_ms: Millis,
+    /// How long FPS must keep up before the next step up
+    pub increase_threshold_ms: Millis,
+    /// Minimum time between changes (stretched while oscillation damping is active)
+    pub min_change_interval_ms: Millis,
+    /// Increase threshold multiplier for the final step up to max Hz
+    pub saturation_pacing: f64,
+    /// Whether the next step up reaches max Hz, so `saturation_pacing` applies
+    pub near_max: bool,
+}
+
 /// Algorithm state for hysteresis control.
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
 pub enum AlgorithmState {
     /// Stable state - no pending rate change
+    #[default]
     Stable,
     /// FPS has dropped below threshold, waiting for sustained drop
     Dropping { since: Instant },
@@ -48,12 +206,6 @@ pub enum AlgorithmState {
     Increasing { since: Instant },
 }
 
-impl Default for AlgorithmState {
-    fn default() -> Self {
-        Self::Stable
-    }
-}
-
 /// Sensitivity presets for the hysteresis algorithm.
 #[derive(Debug, Clone, Copy, PartialEq, Default)]
 pub enum Sensitivity {
@@ -64,6 +216,8 @@ pub enum Sensitivity {
     Balanced,
     /// Aggressive: 500ms drop, 1.5s increase - faster transitions, more responsive
     Aggressive,
+    /// Custom: user-defined drop/increase durations set on the controller
+    Custom,
 }
 
 /// Device mode for hardware-specific throttling.
@@ -80,10 +234,13 @@ pub enum DeviceMode {
 
 impl Sensitivity {
     /// Get the drop threshold duration for this sensitivity level.
+    ///
+    /// `Custom` has no preset and reports the Balanced default; the
+    /// controller uses its configured custom durations instead.
     pub fn drop_threshold(&self) -> Duration {
         match self {
             Sensitivity::Conservative => Duration::from_secs(2),
-            Sensitivity::Balanced => Duration::from_secs(1),
+            Sensitivity::Balanced | Sensitivity::Custom => Duration::from_secs(1),
             Sensitivity::Aggressive => Duration::from_millis(500),
         }
     }
@@ -92,19 +249,62 @@ impl Sensitivity {
     pub fn increase_threshold(&self) -> Duration {
         match self {
             Sensitivity::Conservative => Duration::from_secs(5),
-            Sensitivity::Balanced => Duration::from_secs(3),
+            Sensitivity::Balanced | Sensitivity::Custom => Duration::from_secs(3),
             Sensitivity::Aggressive => Duration::from_millis(1500),
         }
     }
 }
 
+/// How steady FPS is, judged by the adaptive sensitivity thresholds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FpsStability {
+    /// Standard deviation under `STD_DEV_STABLE`: user sensitivity applies
+    Stable,
+    /// Between the thresholds: adaptive sensitivity keeps what it has
+    Variable,
+    /// Standard deviation over `STD_DEV_UNSTABLE`: Conservative is forced
+    Unstable,
+}
+
+impl FpsStability {
+    pub fn from_std_dev(std_dev: f64) -> Self {
+        if std_dev > STD_DEV_UNSTABLE {
+            FpsStability::Unstable
+        } else if std_dev < STD_DEV_STABLE {
+            FpsStability::Stable
+        } else {
+            FpsStability::Variable
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FpsStability::Stable => "stable",
+            FpsStability::Variable => "variable",
+            FpsStability::Unstable => "unstable",
+        }
+    }
+}
+
 /// Hz step size for quantization (5Hz steps)
-const HZ_STEP_SIZE: u32 = 5;
+const HZ_STEP_SIZE: Hertz = Hertz(5);
 
-/// Sliding window for FPS samples used in adaptive sensitivity
+/// Allowed rate closest to `hz` within `[min, max]` (ties go to the higher rate).
+///
+/// Returns `None` if no allowed rate falls inside the range.
+pub fn nearest_allowed(hz: Hertz, allowed: &[Hertz], min: Hertz, max: Hertz) -> Option<Hertz> {
+    allowed
+        .iter()
+        .copied()
+        .filter(|a| (min..=max).contains(a))
+        .min_by_key(|a| (a.abs_diff(hz), u32::MAX - a.get()))
+}
+
+/// Sliding window of timestamped FPS samples, used for adaptive
+/// sensitivity and the FPS trend.
 #[derive(Debug, Clone)]
 pub struct FpsSlidingWindow {
-    samples: VecDeque<f64>,
+    samples: VecDeque<(Instant, f64)>,
     capacity: usize,
 }
 
@@ -116,11 +316,11 @@ impl FpsSlidingWindow {
         }
     }
 
-    pub fn push(&mut self, fps: f64) {
+    pub fn push(&mut self, fps: f64, at: Instant) {
         if self.samples.len() >= self.capacity {
             self.samples.pop_front();
         }
-        self.samples.push_back(fps);
+        self.samples.push_back((at, fps));
     }
 
     pub fn clear(&mut self) {
@@ -140,7 +340,7 @@ impl FpsSlidingWindow {
         if self.samples.is_empty() {
             return 0.0;
         }
-        self.samples.iter().sum::<f64>() / self.samples.len() as f64
+        self.samples.iter().map(|(_, fps)| fps).sum::<f64>() / self.samples.len() as f64
     }
 
     /// Calculate standard deviation of samples
@@ -150,10 +350,31 @@ impl FpsSlidingWindow {
         }
         let mean = self.mean();
         let variance = self.samples.iter()
-            .map(|x| (x - mean).powi(2))
+            .map(|(_, x)| (x - mean).powi(2))
             .sum::<f64>() / (self.samples.len() - 1) as f64;
         variance.sqrt()
     }
+
+    /// Least-squares FPS trend in FPS per second, once `TREND_MIN_SAMPLES`
+    /// samples spanning some time are available.
+    pub fn slope(&self) -> Option<f64> {
+        if self.samples.len() < TREND_MIN_SAMPLES {
+            return None;
+        }
+        let start = self.samples.front()?.0;
+        let n = self.samples.len() as f64;
+        let secs = |at: Instant| at.duration_since(start).as_secs_f64();
+        let mean_t = self.samples.iter().map(|(at, _)| secs(*at)).sum::<f64>() / n;
+        let mean_fps = self.mean();
+
+        let (mut cov, mut var) = (0.0, 0.0);
+        for (at, fps) in &self.samples {
+            let dt = secs(*at) - mean_t;
+            cov += dt * (fps - mean_fps);
+            var += dt * dt;
+        }
+        (var > 0.0).then(|| cov / var)
+    }
 }
 
 impl Default for FpsSlidingWindow {
@@ -162,6 +383,212 @@ impl Default for FpsSlidingWindow {
     }
 }
 
+/// Counts frametime outliers over a sliding time window.
+///
+/// Average FPS hides short hitches, so spikes are tracked separately: a
+/// sample whose frametime exceeds `SPIKE_FACTOR` times the running baseline
+/// is a spike. Spikes are kept out of the baseline so a stutter burst
+/// doesn't raise the bar for the next one.
+#[derive(Debug, Clone)]
+pub struct SpikeDetector {
+    spikes: VecDeque<Instant>,
+    /// Exponential average of non-spike frametimes (microseconds)
+    baseline_us: Option<f64>,
+    window: Duration,
+    threshold: usize,
+}
+
+impl SpikeDetector {
+    pub fn new(window: Duration, threshold: usize) -> Self {
+        Self {
+            spikes: VecDeque::new(),
+            baseline_us: None,
+            window,
+            threshold,
+        }
+    }
+
+    /// Record one frametime sample. Zero (no data) is ignored.
+    pub fn record(&mut self, frametime_us: u64, now: Instant) {
+        if frametime_us == 0 {
+            return;
+        }
+        let frametime = frametime_us as f64;
+        match self.baseline_us {
+            Some(baseline) if frametime > baseline * SPIKE_FACTOR => self.spikes.push_back(now),
+            Some(baseline) => {
+                self.baseline_us = Some(baseline + SPIKE_BASELINE_ALPHA * (frametime - baseline));
+            }
+            None => self.baseline_us = Some(frametime),
+        }
+        self.prune(now);
+    }
+
+    fn prune(&mut self, now: Instant) {
+        while self
+            .spikes
+            .front()
+            .is_some_and(|t| now.duration_since(*t) >= self.window)
+        {
+            self.spikes.pop_front();
+        }
+    }
+
+    /// Spikes within the window ending at `now`.
+    pub fn count(&self, now: Instant) -> usize {
+        self.spikes
+            .iter()
+            .filter(|t| now.saturating_duration_since(**t) < self.window)
+            .count()
+    }
+
+    /// Whether spikes in the window reached the threshold.
+    pub fn is_stuttering(&self, now: Instant) -> bool {
+        self.count(now) >= self.threshold
+    }
+
+    /// Forget counted spikes (the baseline is kept).
+    pub fn clear_spikes(&mut self) {
+        self.spikes.clear();
+    }
+}
+
+/// Detects ping-ponging between refresh rates.
+///
+/// Each change is recorded with its direction; a change in the opposite
+/// direction of the previous one is a reversal. Enough reversals within
+/// `OSCILLATION_WINDOW_SECS` start a damping period.
+#[derive(Debug, Clone, Default)]
+pub struct OscillationDetector {
+    /// Times of recent reversals
+    reversals: VecDeque<Instant>,
+    /// Direction of the last change (true = up)
+    last_direction: Option<bool>,
+    damped_until: Option<Instant>,
+}
+
+impl OscillationDetector {
+    /// Record a change from `from_hz` to `to_hz`. Returns true if this
+    /// change started a damping period.
+    pub fn record(&mut self, from_hz: Hertz, to_hz: Hertz, now: Instant) -> bool {
+        if from_hz == to_hz {
+            return false;
+        }
+        let up = to_hz > from_hz;
+        if self.last_direction.is_some_and(|last| last != up) {
+            self.reversals.push_back(now);
+        }
+        self.last_direction = Some(up);
+
+        let window = Duration::from_secs(OSCILLATION_WINDOW_SECS);
+        while self
+            .reversals
+            .front()
+            .is_some_and(|t| now.duration_since(*t) >= window)
+        {
+            self.reversals.pop_front();
+        }
+
+        if self.reversals.len() >= OSCILLATION_REVERSALS && !self.is_damped(now) {
+            self.reversals.clear();
+            self.damped_until = Some(now + Duration::from_secs(OSCILLATION_DAMPING_SECS));
+            return true;
+        }
+        false
+    }
+
+    pub fn is_damped(&self, now: Instant) -> bool {
+        self.damped_until.is_some_and(|until| now < until)
+    }
+
+    /// Seconds of damping left.
+    pub fn remaining_secs(&self, now: Instant) -> f64 {
+        self.damped_until
+            .map(|until| until.saturating_duration_since(now).as_secs_f64())
+            .unwrap_or(0.0)
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// Tracks how long FPS has held a video frame rate (24/25/30fps).
+#[derive(Debug, Clone, Copy, Default)]
+struct CadenceDetector {
+    /// Cadence FPS currently sits on and when it got there
+    candidate: Option<(u32, Instant)>,
+}
+
+impl CadenceDetector {
+    /// Feed an FPS sample. Returns the cadence FPS is on, if any, and
+    /// whether it has been held for `CADENCE_LOCK_SECS`.
+    fn update(&mut self, fps: f64, now: Instant) -> Option<(u32, bool)> {
+        let cadence = VIDEO_CADENCES
+            .into_iter()
+            .find(|c| (fps - *c as f64).abs() <= CADENCE_FPS_TOLERANCE);
+        let Some(cadence) = cadence else {
+            self.candidate = None;
+            return None;
+        };
+        let since = match self.candidate {
+            Some((held, since)) if held == cadence => since,
+            _ => now,
+        };
+        self.candidate = Some((cadence, since));
+        let locked = now.duration_since(since) >= Duration::from_secs(CADENCE_LOCK_SECS);
+        Some((cadence, locked))
+    }
+
+    fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// Hold started by an abrupt FPS collapse (loading screen, scene change).
+#[derive(Debug, Clone, Copy)]
+struct LoadingHold {
+    /// Average FPS before the collapse
+    baseline: f64,
+    until: Instant,
+}
+
+/// Filter and integrator state of the PID policy.
+#[derive(Debug, Default)]
+struct PidState {
+    smoothed_fps: Option<f64>,
+    integral: f64,
+    last_sample: Option<Instant>,
+}
+
+impl PidState {
+    /// Feed an FPS sample. Returns the new smoothed FPS, its rate of
+    /// change per second and the time since the previous sample.
+    fn filter(&mut self, fps: f64, now: Instant) -> (f64, f64, f64) {
+        let dt = self
+            .last_sample
+            .map_or(Duration::ZERO, |last| now.saturating_duration_since(last).min(PID_MAX_DT))
+            .as_secs_f64();
+        self.last_sample = Some(now);
+
+        let previous = self.smoothed_fps.unwrap_or(fps);
+        let smoothed = previous + PID_SMOOTHING_ALPHA * (fps - previous);
+        self.smoothed_fps = Some(smoothed);
+        let slope = if dt > 0.0 { (smoothed - previous) / dt } else { 0.0 };
+        (smoothed, slope, dt)
+    }
+
+    fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+impl Default for SpikeDetector {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(SPIKE_WINDOW_SECS), SPIKE_THRESHOLD)
+    }
+}
+
 /// Hysteresis controller for refresh rate decisions.
 ///
 /// Implements a state machine that prevents rapid refresh rate oscillation
@@ -173,6 +600,8 @@ pub struct HysteresisController {
     drop_threshold: Duration,
     /// Duration FPS must stay at/above threshold before increasing Hz
     increase_threshold: Duration,
+    /// Drop/increase durations used when sensitivity is `Custom`
+    custom_thresholds: (Duration, Duration),
     /// Minimum time between consecutive rate changes
     min_change_interval: Duration,
     /// Timestamp of last successful rate change
@@ -184,9 +613,9 @@ pub struct HysteresisController {
     /// Current device mode
     device_mode: DeviceMode,
     /// User-configured min Hz
-    user_min_hz: u32,
+    user_min_hz: Hertz,
     /// User-configured max Hz
-    user_max_hz: u32,
+    user_max_hz: Hertz,
     /// Sliding window for adaptive sensitivity
     fps_window: FpsSlidingWindow,
     /// Whether adaptive sensitivity is enabled
@@ -195,14 +624,50 @@ pub struct HysteresisController {
     external_display_detected: bool,
     /// Configurable FPS tolerance (2.0-5.0)
     fps_tolerance: f64,
+    /// Extra tolerance applied while the switch budget runs low
+    tolerance_boost: f64,
+    /// Frametime spike counter for stutter detection
+    spikes: SpikeDetector,
+    /// Ping-pong detection between Hz levels
+    oscillation: OscillationDetector,
+    /// Step size for drops (jump to the FPS target by default)
+    drop_step: StepMode,
+    /// Step size for increases (one step by default)
+    increase_step: StepMode,
     /// Resume cooldown - timestamp when resume occurred
     resume_cooldown_until: Option<Instant>,
     /// Resume cooldown duration
     resume_cooldown_duration: Duration,
     /// Whether to sync Gamescope frame limiter with Hz
     sync_frame_limiter: bool,
-    /// Last Hz that was set (for frame limiter sync)
-    last_set_hz: Option<u32>,
+    /// Explicit allowed rates (sorted); empty means 5Hz steps
+    allowed_hz: Vec<Hertz>,
+    /// Increase threshold multiplier for the final step up to max Hz
+    saturation_pacing: f64,
+    /// Decision policy (hysteresis state machine or PID)
+    policy: ControlPolicy,
+    /// PID filter/integrator, used when `policy` is `Pid`
+    pid: PidState,
+    /// Snap to a multiple of 24/25/30fps video instead of the FPS target
+    video_cadence: bool,
+    /// Video cadence tracking, used when `video_cadence` is set
+    cadence: CadenceDetector,
+    /// How long to hold Hz after an abrupt FPS collapse (zero disables)
+    loading_grace: Duration,
+    /// Active or expired loading hold, cleared when FPS recovers
+    loading_hold: Option<LoadingHold>,
+    /// Below min Hz, run at a multiple of FPS with the frame limiter at FPS
+    low_fps_compensation: bool,
+    /// Frame limit for the current low FPS compensation rate, if engaged
+    lfc_fps_limit: Option<u32>,
+    /// Minimum time to stay at a new rate before the next change (zero disables)
+    min_dwell: Duration,
+    /// FPS decline (FPS per second) that starts the drop timer early, if enabled
+    predictive_drop: Option<f64>,
+    /// The SoC is thermally throttling - more Hz would only add heat
+    thermal_throttling: bool,
+    /// The GPU is pegged - more Hz cannot be sustained
+    gpu_saturated: bool,
 }
 
 impl HysteresisController {
@@ -211,8 +676,8 @@ impl HysteresisController {
     /// Minimum interval between rate changes for LCD (2000ms) - prevents flickering
     const MIN_CHANGE_INTERVAL_LCD_MS: u64 = 2000;
     /// LCD forced Hz range
-    const LCD_MIN_HZ: u32 = 40;
-    const LCD_MAX_HZ: u32 = 60;
+    const LCD_MIN_HZ: Hertz = Hertz(40);
+    const LCD_MAX_HZ: Hertz = Hertz(60);
 
     /// Create a new HysteresisController with the specified sensitivity.
     pub fn new(sensitivity: Sensitivity) -> Self {
@@ -220,21 +685,43 @@ impl HysteresisController {
             state: AlgorithmState::Stable,
             drop_threshold: sensitivity.drop_threshold(),
             increase_threshold: sensitivity.increase_threshold(),
+            custom_thresholds: (
+                Sensitivity::Custom.drop_threshold(),
+                Sensitivity::Custom.increase_threshold(),
+            ),
             min_change_interval: Duration::from_millis(Self::MIN_CHANGE_INTERVAL_OLED_MS),
             last_change: None,
             user_sensitivity: sensitivity,
             effective_sensitivity: sensitivity,
             device_mode: DeviceMode::Oled,
-            user_min_hz: 40,
-            user_max_hz: 90,
+            user_min_hz: MIN_ALLOWED_HZ,
+            user_max_hz: MAX_ALLOWED_HZ,
             fps_window: FpsSlidingWindow::default(),
             adaptive_sensitivity_enabled: false,
             external_display_detected: false,
             fps_tolerance: DEFAULT_FPS_TOLERANCE,
+            tolerance_boost: 0.0,
+            spikes: SpikeDetector::default(),
+            oscillation: OscillationDetector::default(),
+            drop_step: StepMode::Jump,
+            increase_step: StepMode::Single,
             resume_cooldown_until: None,
             resume_cooldown_duration: Duration::from_secs(DEFAULT_RESUME_COOLDOWN_SECS),
             sync_frame_limiter: false,
-            last_set_hz: None,
+            allowed_hz: Vec::new(),
+            saturation_pacing: DEFAULT_SATURATION_PACING,
+            policy: ControlPolicy::default(),
+            pid: PidState::default(),
+            video_cadence: false,
+            cadence: CadenceDetector::default(),
+            loading_grace: Duration::ZERO,
+            loading_hold: None,
+            low_fps_compensation: false,
+            lfc_fps_limit: None,
+            min_dwell: Duration::ZERO,
+            predictive_drop: None,
+            thermal_throttling: false,
+            gpu_saturated: false,
         }
     }
 
@@ -244,6 +731,8 @@ impl HysteresisController {
     }
 
     /// Get the user-selected sensitivity setting.
+    // Status reports the effective sensitivity; tests check the user's
+    #[cfg_attr(not(test), allow(dead_code))]
     pub fn sensitivity(&self) -> Sensitivity {
         self.user_sensitivity
     }
@@ -259,17 +748,19 @@ impl HysteresisController {
     }
 
     /// Get the timestamp of the last rate change.
+    // Only tests read it; the controller paces itself from the field
+    #[cfg_attr(not(test), allow(dead_code))]
     pub fn last_change(&self) -> Option<Instant> {
         self.last_change
     }
 
     /// Get the user-configured Hz range.
-    pub fn user_range(&self) -> (u32, u32) {
+    pub fn user_range(&self) -> (Hertz, Hertz) {
         (self.user_min_hz, self.user_max_hz)
     }
 
     /// Set the user-configured Hz range.
-    pub fn set_user_range(&mut self, min_hz: u32, max_hz: u32) {
+    pub fn set_user_range(&mut self, min_hz: Hertz, max_hz: Hertz) {
         self.user_min_hz = min_hz;
         self.user_max_hz = max_hz;
     }
@@ -306,6 +797,14 @@ impl HysteresisController {
         self.fps_window.std_dev()
     }
 
+    /// Stability verdict adaptive sensitivity acts on, once the sliding
+    /// window is full. Reported whether or not adaptive sensitivity is on.
+    pub fn fps_stability(&self) -> Option<FpsStability> {
+        self.fps_window
+            .is_full()
+            .then(|| FpsStability::from_std_dev(self.fps_window.std_dev()))
+    }
+
     /// Reset state to Stable and clear last_change timestamp
     /// Used after suspend/resume to prevent stale timestamp issues
     /// Activates resume cooldown period
@@ -313,11 +812,24 @@ impl HysteresisController {
         self.state = AlgorithmState::Stable;
         self.last_change = None;
         self.fps_window.clear();
+        self.oscillation.reset();
+        self.pid.reset();
+        self.cadence.reset();
+        self.loading_hold = None;
+        self.lfc_fps_limit = None;
         // Activate resume cooldown - no changes for N seconds after wake
         self.resume_cooldown_until = Some(Instant::now() + self.resume_cooldown_duration);
         tracing::info!("State reset with {}s resume cooldown", self.resume_cooldown_duration.as_secs());
     }
 
+    /// Drop any pending Dropping/Increasing transition.
+    /// Used when Hz was changed outside the controller (e.g. manual override).
+    pub fn cancel_pending(&mut self) {
+        self.state = AlgorithmState::Stable;
+        self.pid.reset();
+        self.lfc_fps_limit = None;
+    }
+
     /// Check if currently in resume cooldown period
     pub fn is_in_resume_cooldown(&self) -> bool {
         match self.resume_cooldown_until {
@@ -341,9 +853,9 @@ impl HysteresisController {
         }
     }
 
-    /// Set resume cooldown duration
-    pub fn set_resume_cooldown(&mut self, secs: u64) {
-        self.resume_cooldown_duration = Duration::from_secs(secs);
+    /// Set resume cooldown duration (clamped to `MAX_RESUME_COOLDOWN_SECS`; zero disables)
+    pub fn set_resume_cooldown(&mut self, secs: Secs) {
+        self.resume_cooldown_duration = Duration::from_secs(secs.get().min(MAX_RESUME_COOLDOWN_SECS));
     }
 
     /// Get FPS tolerance value
@@ -357,6 +869,242 @@ impl HysteresisController {
         tracing::debug!("FPS tolerance set to {:.1}", self.fps_tolerance);
     }
 
+    /// Feed a frametime sample to the spike detector.
+    pub fn record_frametime(&mut self, frametime_us: u64, now: Instant) {
+        self.spikes.record(frametime_us, now);
+    }
+
+    /// Frametime spikes in the current window.
+    pub fn spike_count(&self) -> usize {
+        self.spikes.count(Instant::now())
+    }
+
+    /// Set how far a single drop moves the rate.
+    pub fn set_drop_step(&mut self, mode: StepMode) {
+        self.drop_step = mode;
+    }
+
+    /// Set how far a single increase moves the rate.
+    pub fn set_increase_step(&mut self, mode: StepMode) {
+        self.increase_step = mode;
+    }
+
+    /// Enable/disable snapping to video cadence multiples.
+    pub fn set_video_cadence(&mut self, enabled: bool) {
+        if self.video_cadence != enabled {
+            self.video_cadence = enabled;
+            self.cadence.reset();
+        }
+    }
+
+    /// Set how long to hold Hz after an abrupt FPS collapse (zero disables).
+    pub fn set_loading_grace(&mut self, grace: Millis) {
+        self.loading_grace = grace.into();
+        self.loading_hold = None;
+    }
+
+    /// Get the loading-screen grace period.
+    pub fn loading_grace(&self) -> Millis {
+        Millis(self.loading_grace.as_millis() as u64)
+    }
+
+    /// Seconds left of the current loading hold (0 when not holding).
+    pub fn loading_hold_remaining(&self) -> f64 {
+        self.loading_hold
+            .map(|hold| hold.until.saturating_duration_since(Instant::now()).as_secs_f64())
+            .unwrap_or(0.0)
+    }
+
+    /// Loading screens: an FPS collapse below `LOADING_DROP_RATIO` of the
+    /// recent average holds the current Hz for the grace period. The hold
+    /// ends early once FPS recovers; if it doesn't, the controller reacts
+    /// normally after the grace period and won't re-arm until recovery.
+    fn hold_for_loading(&mut self, current_fps: f64, baseline: Option<f64>, now: Instant) -> bool {
+        if self.loading_grace.is_zero() {
+            return false;
+        }
+        if let Some(hold) = self.loading_hold {
+            if current_fps >= hold.baseline * LOADING_RECOVERY_RATIO {
+                tracing::debug!("FPS recovered to {:.1}, loading hold cleared", current_fps);
+                self.loading_hold = None;
+                return false;
+            }
+            return now < hold.until;
+        }
+        match baseline {
+            Some(baseline) if current_fps < baseline * LOADING_DROP_RATIO => {
+                tracing::debug!(
+                    "FPS collapsed {:.1} -> {:.1}, holding Hz for {:?}",
+                    baseline,
+                    current_fps,
+                    self.loading_grace
+                );
+                self.loading_hold = Some(LoadingHold {
+                    baseline,
+                    until: now + self.loading_grace,
+                });
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Enable/disable low FPS compensation (opt-in).
+    pub fn set_low_fps_compensation(&mut self, enabled: bool) {
+        self.low_fps_compensation = enabled;
+        if !enabled {
+            self.lfc_fps_limit = None;
+        }
+    }
+
+    /// Set how long to stay at a new rate before any further change
+    /// (clamped to `MAX_MIN_DWELL_SECS`; zero disables).
+    pub fn set_min_dwell(&mut self, dwell: Secs) {
+        self.min_dwell = Duration::from_secs(dwell.get().min(MAX_MIN_DWELL_SECS));
+    }
+
+    /// Get the minimum dwell time per refresh level.
+    pub fn min_dwell(&self) -> Secs {
+        Secs(self.min_dwell.as_secs())
+    }
+
+    /// Start the drop timer while FPS falls faster than `slope` FPS per
+    /// second (clamped to 2-100), or disable prediction with `None`.
+    pub fn set_predictive_drop(&mut self, slope: Option<f64>) {
+        self.predictive_drop =
+            slope.map(|s| s.clamp(MIN_PREDICTIVE_DROP_SLOPE, MAX_PREDICTIVE_DROP_SLOPE));
+    }
+
+    /// Block increases while the SoC is thermally throttling.
+    pub fn set_thermal_throttling(&mut self, throttling: bool) {
+        self.thermal_throttling = throttling;
+    }
+
+    /// Block increases while the GPU is already saturated.
+    pub fn set_gpu_saturated(&mut self, saturated: bool) {
+        self.gpu_saturated = saturated;
+    }
+
+    /// Whether increases are held for stutter, heat or a saturated GPU.
+    fn increases_blocked(&self, now: Instant) -> bool {
+        self.spikes.is_stuttering(now) || self.thermal_throttling || self.gpu_saturated
+    }
+
+    /// Whether FPS is falling fast enough to predict a drop.
+    fn predicts_drop(&self) -> bool {
+        match (self.predictive_drop, self.fps_window.slope()) {
+            (Some(threshold), Some(slope)) => slope <= -threshold,
+            _ => false,
+        }
+    }
+
+    /// Frame limit to apply while low FPS compensation is engaged.
+    pub fn lfc_fps_limit(&self) -> Option<u32> {
+        self.lfc_fps_limit
+    }
+
+    /// Low FPS compensation target for FPS below the effective min: the
+    /// lowest rate in range that is a multiple (2x or more) of the whole
+    /// FPS, e.g. 56Hz for 28 FPS on a 40-90Hz range. Returns the rate and
+    /// the frame limit (the whole FPS).
+    fn lfc_target(&self, fps: f64) -> Option<(Hertz, u32)> {
+        if !self.low_fps_compensation || fps < 1.0 {
+            return None;
+        }
+        let (effective_min, effective_max) = self.get_effective_range();
+        let base = fps.floor() as u32;
+        if Hertz(base) >= effective_min {
+            return None;
+        }
+        let target = if self.allowed_hz.is_empty() {
+            (2..)
+                .map(|k| Hertz(base * k))
+                .skip_while(|hz| *hz < effective_min)
+                .take_while(|hz| *hz <= effective_max)
+                .next()
+        } else {
+            self.allowed_in_range().find(|hz| hz.get() % base == 0)
+        };
+        target.map(|hz| (hz, base))
+    }
+
+    /// Get the saturation pacing multiplier.
+    pub fn saturation_pacing(&self) -> f64 {
+        self.saturation_pacing
+    }
+
+    /// Set the increase threshold multiplier for the final step up to max Hz
+    /// (clamped to 1.0-5.0; 1.0 disables it).
+    pub fn set_saturation_pacing(&mut self, multiplier: f64) {
+        self.saturation_pacing = multiplier.clamp(MIN_SATURATION_PACING, MAX_SATURATION_PACING);
+    }
+
+    /// Whether the next step up from `current_hz` lands on max Hz.
+    fn is_near_max(&self, current_hz: Hertz) -> bool {
+        let (_, effective_max) = self.get_effective_range();
+        current_hz < effective_max && self.increase_target(current_hz) >= effective_max
+    }
+
+    /// Increase threshold for the next step up from `current_hz`.
+    ///
+    /// The final step to max Hz is where most visible flip-flopping happens,
+    /// so it needs FPS to keep up `saturation_pacing` times longer.
+    fn increase_threshold_at(&self, current_hz: Hertz) -> Duration {
+        if self.is_near_max(current_hz) {
+            self.increase_threshold.mul_f64(self.saturation_pacing)
+        } else {
+            self.increase_threshold
+        }
+    }
+
+    /// Effective decision timing at `current_hz`.
+    pub fn pacing(&self, current_hz: Hertz) -> DecisionPacing {
+        let millis = |d: Duration| Millis(d.as_millis() as u64);
+        DecisionPacing {
+            drop_threshold_ms: millis(self.drop_threshold),
+            increase_threshold_ms: millis(self.increase_threshold_at(current_hz)),
+            min_change_interval_ms: millis(self.change_interval(Instant::now())),
+            saturation_pacing: self.saturation_pacing,
+            near_max: self.is_near_max(current_hz),
+        }
+    }
+
+    /// Get the decision policy.
+    // The daemon only sets it; tests read it back
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn control_policy(&self) -> ControlPolicy {
+        self.policy
+    }
+
+    /// Switch the decision policy, discarding pending state of the old one.
+    pub fn set_control_policy(&mut self, policy: ControlPolicy) {
+        if self.policy != policy {
+            self.policy = policy;
+            self.state = AlgorithmState::Stable;
+            self.pid.reset();
+        }
+    }
+
+    /// Seconds left of oscillation damping (0 when not damped).
+    pub fn oscillation_damping_remaining(&self) -> f64 {
+        self.oscillation.remaining_secs(Instant::now())
+    }
+
+    /// FPS tolerance in effect, including budget and oscillation widening.
+    fn effective_tolerance(&self, now: Instant) -> f64 {
+        let damping = if self.oscillation.is_damped(now) {
+            OSCILLATION_TOLERANCE_BOOST
+        } else {
+            0.0
+        };
+        self.fps_tolerance + self.tolerance_boost + damping
+    }
+
+    /// Widen the FPS tolerance by `boost` on top of the configured value.
+    pub fn set_tolerance_boost(&mut self, boost: f64) {
+        self.tolerance_boost = boost.max(0.0);
+    }
+
     /// Enable/disable Gamescope frame limiter sync
     pub fn set_sync_frame_limiter(&mut self, enabled: bool) {
         self.sync_frame_limiter = enabled;
@@ -367,27 +1115,38 @@ impl HysteresisController {
         self.sync_frame_limiter
     }
 
-    /// Get the last Hz that was set (for frame limiter)
-    pub fn last_set_hz(&self) -> Option<u32> {
-        self.last_set_hz
-    }
-
-    /// Record the Hz that was set
-    pub fn set_last_hz(&mut self, hz: u32) {
-        self.last_set_hz = Some(hz);
-    }
-
     /// Check if enough time has passed since the last rate change.
     fn can_change(&self, now: Instant) -> bool {
+        let interval = self.change_interval(now);
         match self.last_change {
-            Some(last) => now.duration_since(last) >= self.min_change_interval,
+            Some(last) => now.duration_since(last) >= interval,
             None => true,
         }
     }
 
+    /// Minimum interval between changes, stretched while damped and never
+    /// shorter than the dwell time.
+    fn change_interval(&self, now: Instant) -> Duration {
+        let interval = if self.oscillation.is_damped(now) {
+            self.min_change_interval * OSCILLATION_INTERVAL_FACTOR
+        } else {
+            self.min_change_interval
+        };
+        interval.max(self.min_dwell)
+    }
+
     /// Record that a rate change occurred.
-    fn record_change(&mut self, now: Instant) {
+    fn record_change(&mut self, now: Instant, from_hz: Hertz, to_hz: Hertz) {
         self.last_change = Some(now);
+        self.lfc_fps_limit = None;
+        if self.oscillation.record(from_hz, to_hz, now) {
+            tracing::info!(
+                "Refresh rate oscillating, widening FPS tolerance by {:.1} and change interval {}x for {}s",
+                OSCILLATION_TOLERANCE_BOOST,
+                OSCILLATION_INTERVAL_FACTOR,
+                OSCILLATION_DAMPING_SECS
+            );
+        }
     }
 
     /// Update effective sensitivity based on device mode
@@ -397,8 +1156,30 @@ impl HysteresisController {
         } else {
             self.effective_sensitivity = self.user_sensitivity;
         }
-        self.drop_threshold = self.effective_sensitivity.drop_threshold();
-        self.increase_threshold = self.effective_sensitivity.increase_threshold();
+        self.apply_thresholds(self.effective_sensitivity);
+    }
+
+    /// Set the thresholds for `sensitivity`, using custom durations for `Custom`.
+    fn apply_thresholds(&mut self, sensitivity: Sensitivity) {
+        (self.drop_threshold, self.increase_threshold) = match sensitivity {
+            Sensitivity::Custom => self.custom_thresholds,
+            preset => (preset.drop_threshold(), preset.increase_threshold()),
+        };
+    }
+
+    /// Set the drop/increase durations used by `Sensitivity::Custom`.
+    pub fn set_custom_thresholds(&mut self, drop: Duration, increase: Duration) {
+        self.custom_thresholds = (drop, increase);
+        if self.effective_sensitivity == Sensitivity::Custom {
+            self.apply_thresholds(Sensitivity::Custom);
+        }
+    }
+
+    /// Current drop and increase durations.
+    // Status reports them through `pacing`; tests compare the raw pair
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn thresholds(&self) -> (Duration, Duration) {
+        (self.drop_threshold, self.increase_threshold)
     }
 
     /// Update sensitivity (adjusts thresholds).
@@ -433,8 +1214,13 @@ impl HysteresisController {
         self.state = AlgorithmState::Stable;
     }
 
+    /// Override the minimum interval between changes set by the device mode.
+    pub fn set_min_change_interval(&mut self, interval: Duration) {
+        self.min_change_interval = interval;
+    }
+
     /// Get the effective Hz range based on device mode and user settings.
-    fn get_effective_range(&self) -> (u32, u32) {
+    fn get_effective_range(&self) -> (Hertz, Hertz) {
         match self.device_mode {
             DeviceMode::Lcd => {
                 let effective_min = self.user_min_hz.max(Self::LCD_MIN_HZ);
@@ -446,65 +1232,211 @@ impl HysteresisController {
     }
 
     /// Quantize Hz value to nearest 5Hz step.
-    fn quantize_hz(hz: u32) -> u32 {
-        ((hz + HZ_STEP_SIZE / 2) / HZ_STEP_SIZE) * HZ_STEP_SIZE
+    fn quantize_hz(hz: Hertz) -> Hertz {
+        let step = HZ_STEP_SIZE.get();
+        Hertz(((hz.get() + step / 2) / step) * step)
     }
 
     /// Quantize Hz value DOWN to nearest 5Hz step (for drops).
-    fn quantize_hz_down(hz: u32) -> u32 {
-        (hz / HZ_STEP_SIZE) * HZ_STEP_SIZE
+    fn quantize_hz_down(hz: Hertz) -> Hertz {
+        let step = HZ_STEP_SIZE.get();
+        Hertz((hz.get() / step) * step)
     }
 
-    /// Clamp Hz value based on device mode constraints and user range.
-    pub fn clamp_hz(&self, hz: u32) -> u32 {
-        let (effective_min, effective_max) = self.get_effective_range();
-        Self::quantize_hz(hz.clamp(effective_min, effective_max))
+    /// Quantize to a 5Hz step inside `min..=max`. The bounds themselves are
+    /// kept, so ranges like 48-144Hz reach both ends.
+    fn quantize_in_range(hz: Hertz, min: Hertz, max: Hertz) -> Hertz {
+        if hz <= min || hz >= max {
+            return hz.clamp(min, max);
+        }
+        Self::quantize_hz(hz).clamp(min, max)
     }
 
-    /// Get the next step up from current Hz (5Hz increment).
-    fn next_step_up(&self, current_hz: u32) -> u32 {
-        let (_, effective_max) = self.get_effective_range();
-        let next = current_hz + HZ_STEP_SIZE;
-        Self::quantize_hz(next.min(effective_max))
+    /// Restrict rates to an explicit set instead of 5Hz steps (empty clears it).
+    pub fn set_allowed_hz(&mut self, mut allowed: Vec<Hertz>) {
+        allowed.sort_unstable();
+        allowed.dedup();
+        self.allowed_hz = allowed;
     }
 
-    /// Get the target Hz for a drop based on FPS (quantized down to 5Hz step).
-    fn target_hz_for_drop(&self, fps: f64) -> u32 {
+    /// Allowed rates inside the effective range, ascending.
+    fn allowed_in_range(&self) -> impl Iterator<Item = Hertz> + '_ {
         let (effective_min, effective_max) = self.get_effective_range();
-        let target = Self::quantize_hz_down(fps.floor() as u32);
-        target.clamp(effective_min, effective_max)
+        self.allowed_hz
+            .iter()
+            .copied()
+            .filter(move |hz| (effective_min..=effective_max).contains(hz))
     }
 
-    /// Apply adaptive sensitivity based on FPS variance
-    fn apply_adaptive_sensitivity(&mut self) {
-        if !self.adaptive_sensitivity_enabled || self.device_mode == DeviceMode::Lcd {
-            return;
-        }
+    /// Clamp Hz value based on device mode constraints and user range.
+    pub fn clamp_hz(&self, hz: Hertz) -> Hertz {
+        let (effective_min, effective_max) = self.get_effective_range();
+        let clamped = hz.clamp(effective_min, effective_max);
+        nearest_allowed(clamped, &self.allowed_hz, effective_min, effective_max)
+            .unwrap_or_else(|| Self::quantize_in_range(clamped, effective_min, effective_max))
+    }
 
-        if !self.fps_window.is_full() {
-            return;
+    /// Get the next step up from current Hz (next allowed rate, or 5Hz increment).
+    fn next_step_up(&self, current_hz: Hertz) -> Hertz {
+        if !self.allowed_hz.is_empty() {
+            if let Some(next) = self.allowed_in_range().find(|hz| *hz > current_hz) {
+                return next;
+            }
         }
+        let (effective_min, effective_max) = self.get_effective_range();
+        Self::quantize_in_range(current_hz + HZ_STEP_SIZE, effective_min, effective_max)
+    }
+
+    /// Rate the next increase from `current_hz` moves to.
+    fn increase_target(&self, current_hz: Hertz) -> Hertz {
+        match self.increase_step {
+            StepMode::Single => self.next_step_up(current_hz),
+            StepMode::Jump => {
+                let (_, effective_max) = self.get_effective_range();
+                self.clamp_hz(effective_max)
+            }
+        }
+    }
+
+    /// Get the next step down from current Hz (previous allowed rate, or 5Hz decrement).
+    fn next_step_down(&self, current_hz: Hertz) -> Hertz {
+        if !self.allowed_hz.is_empty() {
+            if let Some(prev) = self.allowed_in_range().filter(|hz| *hz < current_hz).last() {
+                return prev;
+            }
+        }
+        let (effective_min, effective_max) = self.get_effective_range();
+        Self::quantize_in_range(current_hz.saturating_sub(HZ_STEP_SIZE), effective_min, effective_max)
+    }
+
+    /// PID policy: move Hz toward the smoothed FPS every sample.
+    ///
+    /// The error is smoothed FPS minus Hz, zeroed within the FPS tolerance
+    /// and at least `PID_SATURATED_ERROR` while FPS is pinned at the rate.
+    /// The output is snapped to a 5Hz step (or allowed rate) and applied
+    /// subject to the minimum change interval. Stutter, thermal
+    /// throttling and a saturated GPU block increases.
+    fn process_pid(&mut self, current_fps: f64, current_hz: Hertz, now: Instant) -> Option<Hertz> {
+        self.state = AlgorithmState::Stable;
+        let (smoothed, slope, dt) = self.pid.filter(current_fps, now);
+        let hold_increases = self.increases_blocked(now);
+
+        let gap = smoothed - current_hz.as_f64();
+        let mut error = if gap >= -1.0 {
+            gap.max(PID_SATURATED_ERROR)
+        } else if -gap < self.effective_tolerance(now) {
+            0.0
+        } else {
+            gap
+        };
+        if hold_increases {
+            error = error.min(0.0);
+        }
+        // Anti-windup: don't integrate against a range limit
+        let (effective_min, effective_max) = self.get_effective_range();
+        if (error > 0.0 && current_hz >= effective_max) || (error < 0.0 && current_hz <= effective_min) {
+            error = 0.0;
+        }
+
+        self.pid.integral = (self.pid.integral + error * dt).clamp(-PID_INTEGRAL_LIMIT, PID_INTEGRAL_LIMIT);
+        if hold_increases {
+            self.pid.integral = self.pid.integral.min(0.0);
+        }
+
+        let output = current_hz.as_f64() + PID_KP * error + PID_KI * self.pid.integral + PID_KD * slope;
+        let target_hz = self.clamp_hz(Hertz(output.round().max(0.0) as u32));
+        if target_hz == current_hz || (hold_increases && target_hz > current_hz) || !self.can_change(now) {
+            return None;
+        }
+
+        tracing::debug!("PID: smoothed FPS {:.1}, {}Hz -> {}Hz", smoothed, current_hz, target_hz);
+        self.pid.integral = 0.0;
+        self.record_change(now, current_hz, target_hz);
+        Some(target_hz)
+    }
+
+    /// Lowest rate in range that is a whole multiple of `cadence`
+    /// (e.g. 48Hz for 24fps, 60Hz for 30fps with a 40-90Hz range).
+    fn cadence_target(&self, cadence: u32) -> Option<Hertz> {
+        if !self.allowed_hz.is_empty() {
+            return self.allowed_in_range().find(|hz| hz.get() % cadence == 0);
+        }
+        let (effective_min, effective_max) = self.get_effective_range();
+        (1..)
+            .map(|k| Hertz(cadence * k))
+            .skip_while(|hz| *hz < effective_min)
+            .take_while(|hz| *hz <= effective_max)
+            .next()
+    }
+
+    /// Video cadence: while FPS sits on 24/25/30fps, hold off the regular
+    /// decision until it has been there for `CADENCE_LOCK_SECS`, then snap
+    /// to a matching multiple. Returns `None` when not on a cadence.
+    fn process_cadence(&mut self, current_fps: f64, current_hz: Hertz, now: Instant) -> Option<Option<Hertz>> {
+        let (cadence, locked) = self.cadence.update(current_fps, now)?;
+        let target_hz = self.cadence_target(cadence)?;
+        self.state = AlgorithmState::Stable;
+        if !locked || target_hz == current_hz || !self.can_change(now) {
+            return Some(None);
+        }
+
+        tracing::info!("Video cadence {}fps, snapping {}Hz -> {}Hz", cadence, current_hz, target_hz);
+        self.pid.reset();
+        self.record_change(now, current_hz, target_hz);
+        Some(Some(target_hz))
+    }
 
+    /// Get the target Hz for a drop based on FPS.
+    ///
+    /// Picks the highest allowed rate the FPS can sustain, or quantizes down
+    /// to a 5Hz step when no allowed set is configured.
+    fn target_hz_for_drop(&self, fps: f64) -> Hertz {
+        if let Some((target, _)) = self.lfc_target(fps) {
+            return target;
+        }
+        let (effective_min, effective_max) = self.get_effective_range();
+        let floor = Hertz(fps.floor() as u32);
+        if !self.allowed_hz.is_empty() {
+            let sustainable = self.allowed_in_range().filter(|hz| *hz <= floor).last();
+            if let Some(target) = sustainable.or_else(|| self.allowed_in_range().next()) {
+                return target;
+            }
+        }
+        let target = Self::quantize_hz_down(floor);
+        target.clamp(effective_min, effective_max)
+    }
+
+    /// Apply adaptive sensitivity based on FPS variance
+    fn apply_adaptive_sensitivity(&mut self) {
+        if !self.adaptive_sensitivity_enabled || self.device_mode == DeviceMode::Lcd {
+            return;
+        }
+
+        let Some(stability) = self.fps_stability() else {
+            return;
+        };
         let std_dev = self.fps_window.std_dev();
-        
-        if std_dev > STD_DEV_UNSTABLE {
-            // Unstable FPS - force conservative
-            if self.effective_sensitivity != Sensitivity::Conservative {
-                self.effective_sensitivity = Sensitivity::Conservative;
-                self.drop_threshold = Sensitivity::Conservative.drop_threshold();
-                self.increase_threshold = Sensitivity::Conservative.increase_threshold();
-                tracing::debug!("Adaptive: FPS unstable (std_dev={:.2}), forcing Conservative", std_dev);
+
+        match stability {
+            FpsStability::Unstable => {
+                // Unstable FPS - force conservative
+                if self.effective_sensitivity != Sensitivity::Conservative {
+                    self.effective_sensitivity = Sensitivity::Conservative;
+                    self.apply_thresholds(Sensitivity::Conservative);
+                    tracing::debug!("Adaptive: FPS unstable (std_dev={:.2}), forcing Conservative", std_dev);
+                }
             }
-        } else if std_dev < STD_DEV_STABLE {
-            // Stable FPS - allow user preference
-            if self.effective_sensitivity != self.user_sensitivity {
-                self.effective_sensitivity = self.user_sensitivity;
-                self.drop_threshold = self.user_sensitivity.drop_threshold();
-                self.increase_threshold = self.user_sensitivity.increase_threshold();
-                tracing::debug!("Adaptive: FPS stable (std_dev={:.2}), restoring user sensitivity", std_dev);
+            FpsStability::Stable => {
+                // Stable FPS - allow user preference
+                if self.effective_sensitivity != self.user_sensitivity {
+                    self.effective_sensitivity = self.user_sensitivity;
+                    self.apply_thresholds(self.user_sensitivity);
+                    tracing::debug!("Adaptive: FPS stable (std_dev={:.2}), restoring user sensitivity", std_dev);
+                }
             }
+            // Between thresholds - keep current effective sensitivity
+            FpsStability::Variable => {}
         }
-        // Between thresholds - keep current effective sensitivity
     }
 
     /// Process FPS sample and determine if refresh rate should change.
@@ -513,11 +1445,19 @@ impl HysteresisController {
     ///
     /// # Algorithm
     /// - If external display detected → return None (paused)
+    /// - If frametime spikes reach the stutter threshold → drop one step early, block increases
     /// - If FPS within tolerance of current Hz → force Stable, return None (sticky target)
     /// - If FPS < (CurrentHz - 1) for `drop_threshold` duration → decrease Hz
     /// - If FPS >= CurrentHz for `increase_threshold` duration → increase Hz by 5Hz step
+    ///   (`saturation_pacing` times longer for the final step to max Hz)
+    /// - If FPS collapses below half its recent average → hold Hz for the
+    ///   loading grace period (when configured) unless it recovers
+    /// - If low FPS compensation is enabled and FPS is below min Hz → drop to
+    ///   a whole multiple of FPS instead, with the frame limiter at FPS
+    /// - If video cadence is enabled and FPS holds 24/25/30fps for
+    ///   `CADENCE_LOCK_SECS` → snap to the lowest multiple in range
     /// - Enforces minimum interval between changes
-    pub fn process(&mut self, current_fps: f64, current_hz: u32) -> Option<u32> {
+    pub fn process(&mut self, current_fps: f64, current_hz: Hertz) -> Option<Hertz> {
         self.process_with_time(current_fps, current_hz, Instant::now())
     }
 
@@ -525,11 +1465,14 @@ impl HysteresisController {
     pub fn process_with_time(
         &mut self,
         current_fps: f64,
-        current_hz: u32,
+        current_hz: Hertz,
         now: Instant,
-    ) -> Option<u32> {
+    ) -> Option<Hertz> {
+        // Average before this sample, for loading-screen collapse detection
+        let baseline = (self.fps_window.len() >= LOADING_MIN_SAMPLES).then(|| self.fps_window.mean());
+
         // Add FPS to sliding window for adaptive sensitivity
-        self.fps_window.push(current_fps);
+        self.fps_window.push(current_fps, now);
         
         // Apply adaptive sensitivity if enabled
         self.apply_adaptive_sensitivity();
@@ -547,25 +1490,63 @@ impl HysteresisController {
             return None;
         }
 
+        if self.hold_for_loading(current_fps, baseline, now) {
+            self.state = AlgorithmState::Stable;
+            return None;
+        }
+
+        if self.video_cadence {
+            if let Some(decision) = self.process_cadence(current_fps, current_hz, now) {
+                return decision;
+            }
+        }
+
+        if self.policy == ControlPolicy::Pid {
+            return self.process_pid(current_fps, current_hz, now);
+        }
+
         let (effective_min, effective_max) = self.get_effective_range();
+
+        // Stutter: too many frametime spikes drop early and block increases,
+        // whatever the average FPS says
+        let stuttering = self.spikes.is_stuttering(now);
+        if stuttering && current_hz > effective_min && self.can_change(now) {
+            let target_hz = self
+                .target_hz_for_drop(current_fps)
+                .min(self.next_step_down(current_hz));
+            if target_hz < current_hz {
+                tracing::debug!("Frametime spikes, dropping early to {}Hz", target_hz);
+                self.spikes.clear_spikes();
+                self.state = AlgorithmState::Stable;
+                self.record_change(now, current_hz, target_hz);
+                return Some(target_hz);
+            }
+        }
         
+        // A steep FPS decline starts the drop timer before FPS crosses the
+        // threshold, so the drop can follow as soon as it does
+        let falling = current_hz > effective_min && self.predicts_drop();
+
         // FPS Jitter Tolerance ("Sticky Target")
         // If FPS is within tolerance of current Hz, force stable state
         // Uses configurable fps_tolerance instead of constant
-        let fps_diff = (current_fps - current_hz as f64).abs();
-        if fps_diff < self.fps_tolerance {
+        let fps_diff = (current_fps - current_hz.as_f64()).abs();
+        if fps_diff < self.effective_tolerance(now) && !falling {
             self.state = AlgorithmState::Stable;
             return None;
         }
 
         // Check if FPS is below the drop threshold (CurrentHz - 1)
-        let fps_below_threshold = current_fps < (current_hz as f64 - 1.0);
+        let fps_below_threshold = current_fps < (current_hz.as_f64() - 1.0);
         // Check if FPS is at or above current Hz (can potentially increase)
-        let fps_at_or_above = current_fps >= current_hz as f64;
+        let fps_at_or_above = !self.increases_blocked(now) && current_fps >= current_hz.as_f64();
 
         match self.state {
             AlgorithmState::Stable => {
-                if fps_below_threshold && current_hz > effective_min {
+                if (fps_below_threshold || falling) && current_hz > effective_min {
+                    if falling && !fps_below_threshold {
+                        tracing::debug!("FPS falling fast, starting drop timer early");
+                    }
                     self.state = AlgorithmState::Dropping { since: now };
                 } else if fps_at_or_above && current_hz < effective_max {
                     self.state = AlgorithmState::Increasing { since: now };
@@ -574,21 +1555,34 @@ impl HysteresisController {
             }
 
             AlgorithmState::Dropping { since } => {
-                if !fps_below_threshold {
+                if !fps_below_threshold && !falling {
                     self.state = AlgorithmState::Stable;
                     None
-                } else if now.duration_since(since) >= self.drop_threshold {
+                } else if fps_below_threshold && now.duration_since(since) >= self.drop_threshold {
                     if self.can_change(now) {
-                        let target_hz = self.target_hz_for_drop(current_fps);
+                        let final_hz = self.target_hz_for_drop(current_fps);
                         
-                        if current_hz.abs_diff(target_hz) < HZ_STEP_SIZE {
+                        if current_hz.abs_diff(final_hz) < HZ_STEP_SIZE.get() {
                             self.state = AlgorithmState::Stable;
                             return None;
                         }
-                        
-                        self.state = AlgorithmState::Stable;
-                        self.record_change(now);
-                        self.last_set_hz = Some(target_hz);
+
+                        // Ramping keeps the drop pending so the next step only
+                        // waits for the change interval, not a new drop threshold
+                        let target_hz = match self.drop_step {
+                            StepMode::Single => final_hz.max(self.next_step_down(current_hz)),
+                            StepMode::Jump => final_hz,
+                        };
+                        self.state = if target_hz > final_hz {
+                            AlgorithmState::Dropping { since }
+                        } else {
+                            AlgorithmState::Stable
+                        };
+                        self.record_change(now, current_hz, target_hz);
+                        self.lfc_fps_limit = self
+                            .lfc_target(current_fps)
+                            .filter(|(hz, _)| *hz == target_hz)
+                            .map(|(_, limit)| limit);
                         Some(target_hz)
                     } else {
                         None
@@ -599,15 +1593,15 @@ impl HysteresisController {
             }
 
             AlgorithmState::Increasing { since } => {
-                if fps_below_threshold {
+                if fps_below_threshold || falling {
                     self.state = AlgorithmState::Dropping { since: now };
                     None
                 } else if !fps_at_or_above {
                     self.state = AlgorithmState::Stable;
                     None
-                } else if now.duration_since(since) >= self.increase_threshold {
+                } else if now.duration_since(since) >= self.increase_threshold_at(current_hz) {
                     if self.can_change(now) {
-                        let new_hz = self.next_step_up(current_hz);
+                        let new_hz = self.increase_target(current_hz);
                         
                         if new_hz <= current_hz {
                             self.state = AlgorithmState::Stable;
@@ -615,8 +1609,7 @@ impl HysteresisController {
                         }
                         
                         self.state = AlgorithmState::Stable;
-                        self.record_change(now);
-                        self.last_set_hz = Some(new_hz);
+                        self.record_change(now, current_hz, new_hz);
                         Some(new_hz)
                     } else {
                         None
@@ -645,6 +1638,58 @@ mod tests {
         assert_eq!(Sensitivity::Aggressive.increase_threshold(), Duration::from_millis(1500));
     }
 
+    #[test]
+    fn test_custom_thresholds() {
+        let mut controller = HysteresisController::new(Sensitivity::Balanced);
+        controller.set_custom_thresholds(Duration::from_millis(800), Duration::from_millis(2500));
+        // Custom durations only apply once Custom is selected
+        assert_eq!(controller.thresholds(), (Duration::from_secs(1), Duration::from_secs(3)));
+
+        controller.set_sensitivity(Sensitivity::Custom);
+        assert_eq!(
+            controller.thresholds(),
+            (Duration::from_millis(800), Duration::from_millis(2500))
+        );
+
+        controller.set_custom_thresholds(Duration::from_millis(300), Duration::from_millis(1000));
+        assert_eq!(
+            controller.thresholds(),
+            (Duration::from_millis(300), Duration::from_millis(1000))
+        );
+
+        // LCD still forces Conservative
+        controller.apply_mode_constraints(DeviceMode::Lcd);
+        assert_eq!(controller.thresholds(), (Duration::from_secs(2), Duration::from_secs(5)));
+    }
+
+    #[test]
+    fn test_nearest_allowed() {
+        let allowed = [Hertz(45), Hertz(60), Hertz(90)];
+        assert_eq!(nearest_allowed(Hertz(50), &allowed, Hertz(40), Hertz(90)), Some(Hertz(45)));
+        assert_eq!(nearest_allowed(Hertz(75), &allowed, Hertz(40), Hertz(90)), Some(Hertz(90)));
+        assert_eq!(nearest_allowed(Hertz(88), &allowed, Hertz(40), Hertz(60)), Some(Hertz(60)));
+        assert_eq!(nearest_allowed(Hertz(50), &allowed, Hertz(65), Hertz(85)), None);
+    }
+
+    #[test]
+    fn test_allowed_hz_snapping() {
+        let mut controller = HysteresisController::new(Sensitivity::Balanced);
+        controller.set_allowed_hz(vec![Hertz(90), Hertz(45), Hertz(60)]);
+
+        assert_eq!(controller.clamp_hz(Hertz(52)), Hertz(45));
+        assert_eq!(controller.next_step_up(Hertz(45)), Hertz(60));
+        assert_eq!(controller.next_step_up(Hertz(60)), Hertz(90));
+        assert_eq!(controller.target_hz_for_drop(58.7), Hertz(45));
+        assert_eq!(controller.target_hz_for_drop(72.0), Hertz(60));
+        // Below every allowed rate: lowest allowed
+        assert_eq!(controller.target_hz_for_drop(30.0), Hertz(45));
+
+        let now = Instant::now();
+        controller.process_with_time(50.0, Hertz(90), now);
+        let target = controller.process_with_time(50.0, Hertz(90), now + Duration::from_secs(2));
+        assert_eq!(target, Some(Hertz(45)));
+    }
+
     #[test]
     fn test_new_controller_starts_stable() {
         let controller = HysteresisController::new(Sensitivity::Balanced);
@@ -655,21 +1700,21 @@ mod tests {
     #[test]
     fn test_fps_jitter_tolerance_sticky_target() {
         let mut controller = HysteresisController::new(Sensitivity::Balanced);
-        controller.set_user_range(40, 90);
+        controller.set_user_range(Hertz(40), Hertz(90));
         let start = Instant::now();
 
         // FPS at 58, Hz at 60 - within tolerance (diff = 2 < 3)
-        let result = controller.process_with_time(58.0, 60, start);
+        let result = controller.process_with_time(58.0, Hertz(60), start);
         assert!(result.is_none());
         assert_eq!(controller.state(), AlgorithmState::Stable);
 
         // FPS at 62, Hz at 60 - within tolerance (diff = 2 < 3)
-        let result = controller.process_with_time(62.0, 60, start);
+        let result = controller.process_with_time(62.0, Hertz(60), start);
         assert!(result.is_none());
         assert_eq!(controller.state(), AlgorithmState::Stable);
 
         // FPS at 55, Hz at 60 - outside tolerance (diff = 5 > 3)
-        let result = controller.process_with_time(55.0, 60, start);
+        let result = controller.process_with_time(55.0, Hertz(60), start);
         assert!(result.is_none()); // First sample, enters Dropping state
         assert!(matches!(controller.state(), AlgorithmState::Dropping { .. }));
     }
@@ -677,7 +1722,7 @@ mod tests {
     #[test]
     fn test_reset_state_clears_timestamps() {
         let mut controller = HysteresisController::new(Sensitivity::Balanced);
-        controller.set_user_range(40, 90);
+        controller.set_user_range(Hertz(40), Hertz(90));
         let start = Instant::now();
 
         // Make a change to set last_change
@@ -696,8 +1741,8 @@ mod tests {
     #[test]
     fn test_resume_cooldown_blocks_changes() {
         let mut controller = HysteresisController::new(Sensitivity::Balanced);
-        controller.set_user_range(40, 90);
-        controller.set_resume_cooldown(2); // 2 seconds
+        controller.set_user_range(Hertz(40), Hertz(90));
+        controller.set_resume_cooldown(Secs(2));
         
         // Trigger resume cooldown
         controller.reset_state();
@@ -705,7 +1750,7 @@ mod tests {
         let start = Instant::now();
         
         // Even with low FPS, should return None during cooldown
-        let result = controller.process_with_time(30.0, 60, start);
+        let result = controller.process_with_time(30.0, Hertz(60), start);
         assert!(result.is_none());
         assert_eq!(controller.state(), AlgorithmState::Stable);
         assert!(controller.is_in_resume_cooldown());
@@ -714,7 +1759,7 @@ mod tests {
     #[test]
     fn test_configurable_fps_tolerance() {
         let mut controller = HysteresisController::new(Sensitivity::Balanced);
-        controller.set_user_range(40, 90);
+        controller.set_user_range(Hertz(40), Hertz(90));
         
         // Default tolerance is 3.0
         assert_eq!(controller.fps_tolerance(), DEFAULT_FPS_TOLERANCE);
@@ -726,7 +1771,7 @@ mod tests {
         let start = Instant::now();
         
         // FPS at 56, Hz at 60 - within tolerance of 5.0 (diff = 4)
-        let result = controller.process_with_time(56.0, 60, start);
+        let result = controller.process_with_time(56.0, Hertz(60), start);
         assert!(result.is_none());
         assert_eq!(controller.state(), AlgorithmState::Stable);
         
@@ -734,11 +1779,515 @@ mod tests {
         controller.set_fps_tolerance(2.0);
         
         // FPS at 56, Hz at 60 - outside tolerance of 2.0 (diff = 4)
-        let result = controller.process_with_time(56.0, 60, start);
+        let result = controller.process_with_time(56.0, Hertz(60), start);
         assert!(result.is_none()); // First sample enters Dropping
         assert!(matches!(controller.state(), AlgorithmState::Dropping { .. }));
     }
 
+    #[test]
+    fn test_spike_detector_counts_outliers_in_window() {
+        let mut detector = SpikeDetector::new(Duration::from_secs(5), 3);
+        let now = Instant::now();
+        for i in 0..10 {
+            detector.record(11_000, now + Duration::from_millis(100 * i));
+        }
+        assert_eq!(detector.count(now + Duration::from_secs(1)), 0);
+
+        for i in 0..3 {
+            detector.record(40_000, now + Duration::from_secs(1 + i));
+        }
+        assert!(detector.is_stuttering(now + Duration::from_secs(3)));
+        // Spikes age out of the window
+        assert_eq!(detector.count(now + Duration::from_secs(9)), 0);
+    }
+
+    #[test]
+    fn test_ramp_down_steps_one_at_a_time() {
+        let mut controller = HysteresisController::new(Sensitivity::Balanced);
+        controller.set_drop_step(StepMode::Single);
+        let now = Instant::now();
+        let mut hz = Hertz(90);
+        let mut steps = Vec::new();
+
+        for i in 0..100 {
+            let t = now + Duration::from_millis(100 * i);
+            if let Some(target) = controller.process_with_time(55.0, hz, t) {
+                steps.push(target);
+                hz = target;
+            }
+        }
+
+        assert_eq!(steps, [85, 80, 75, 70, 65, 60, 55].map(Hertz));
+        assert_eq!(controller.state(), AlgorithmState::Stable);
+    }
+
+    #[test]
+    fn test_increase_jump_goes_to_max() {
+        let mut controller = HysteresisController::new(Sensitivity::Balanced);
+        controller.set_drop_step(StepMode::Single);
+        controller.set_increase_step(StepMode::Jump);
+        let start = Instant::now();
+
+        // Drops still step one rate at a time
+        controller.process_with_time(50.0, Hertz(90), start);
+        let drop = controller.process_with_time(50.0, Hertz(90), start + Duration::from_secs(1));
+        assert_eq!(drop, Some(Hertz(85)));
+
+        // FPS keeps up at 60Hz: one increase straight to max, paced as the final step
+        assert!(controller.pacing(Hertz(60)).near_max);
+        let t = start + Duration::from_secs(5);
+        // The first sample settles the pending ramp, the second starts the increase
+        controller.process_with_time(90.0, Hertz(60), t);
+        controller.process_with_time(90.0, Hertz(60), t);
+        let paced = controller.thresholds().1.mul_f64(DEFAULT_SATURATION_PACING);
+        assert_eq!(controller.process_with_time(90.0, Hertz(60), t + paced), Some(Hertz(90)));
+
+        // With allowed rates the jump lands on the highest one
+        controller.set_allowed_hz(vec![Hertz(45), Hertz(60), Hertz(75)]);
+        let t = t + paced + Duration::from_secs(1);
+        controller.process_with_time(90.0, Hertz(45), t);
+        assert_eq!(controller.process_with_time(90.0, Hertz(45), t + paced), Some(Hertz(75)));
+    }
+
+    /// Time of the first drop while FPS declines 20 FPS/s from 110 at 90Hz.
+    fn first_drop_secs(predictive_drop: Option<f64>) -> f64 {
+        let mut controller = HysteresisController::new(Sensitivity::Balanced);
+        controller.set_predictive_drop(predictive_drop);
+        let start = Instant::now();
+        for i in 0..40 {
+            let elapsed = Duration::from_millis(100 * i);
+            let fps = (110.0 - 20.0 * elapsed.as_secs_f64()).max(50.0);
+            if controller.process_with_time(fps, Hertz(90), start + elapsed).is_some() {
+                return elapsed.as_secs_f64();
+            }
+        }
+        panic!("no drop");
+    }
+
+    #[test]
+    fn test_predictive_drop_starts_timer_early() {
+        let reactive = first_drop_secs(None);
+        let predictive = first_drop_secs(Some(DEFAULT_PREDICTIVE_DROP_SLOPE));
+        assert!(predictive + 0.5 < reactive, "{} vs {}", predictive, reactive);
+        // Still only once FPS is actually below the rate
+        assert!(predictive > 1.0);
+
+        // A gentle decline doesn't trigger prediction
+        assert_eq!(first_drop_secs(Some(50.0)), reactive);
+    }
+
+    #[test]
+    fn test_min_dwell_spaces_changes() {
+        let mut controller = HysteresisController::new(Sensitivity::Balanced);
+        controller.set_drop_step(StepMode::Single);
+        controller.set_min_dwell(Secs(10));
+        let now = Instant::now();
+        let mut hz = Hertz(90);
+        let mut changes = Vec::new();
+
+        for i in 0..250 {
+            let t = now + Duration::from_millis(100 * i);
+            if let Some(target) = controller.process_with_time(55.0, hz, t) {
+                changes.push(t);
+                hz = target;
+            }
+        }
+
+        assert_eq!(hz, Hertz(75));
+        for pair in changes.windows(2) {
+            assert!(pair[1] - pair[0] >= Duration::from_secs(10));
+        }
+        assert_eq!(controller.pacing(hz).min_change_interval_ms, Millis(10_000));
+
+        controller.set_min_dwell(Secs(3600));
+        assert_eq!(controller.min_dwell(), Secs(MAX_MIN_DWELL_SECS));
+    }
+
+    #[test]
+    fn test_final_step_to_max_is_paced() {
+        let mut controller = HysteresisController::new(Sensitivity::Balanced);
+        let start = Instant::now();
+        let (_, increase) = controller.thresholds();
+
+        // Regular step: plain increase threshold
+        controller.process_with_time(90.0, Hertz(80), start);
+        assert_eq!(controller.process_with_time(90.0, Hertz(80), start + increase), Some(Hertz(85)));
+
+        // Final step to 90Hz waits DEFAULT_SATURATION_PACING times longer
+        let t = start + increase + Duration::from_secs(1);
+        controller.process_with_time(90.0, Hertz(85), t);
+        assert_eq!(controller.process_with_time(90.0, Hertz(85), t + increase), None);
+        let paced = increase.mul_f64(DEFAULT_SATURATION_PACING);
+        assert_eq!(controller.process_with_time(90.0, Hertz(85), t + paced), Some(Hertz(90)));
+
+        let pacing = controller.pacing(Hertz(85));
+        assert!(pacing.near_max);
+        assert_eq!(Duration::from(pacing.increase_threshold_ms), paced);
+        assert!(!controller.pacing(Hertz(60)).near_max);
+    }
+
+    #[test]
+    fn test_saturation_pacing_disabled_at_one() {
+        let mut controller = HysteresisController::new(Sensitivity::Balanced);
+        controller.set_saturation_pacing(0.2);
+        assert_eq!(controller.saturation_pacing(), MIN_SATURATION_PACING);
+        let start = Instant::now();
+        let (_, increase) = controller.thresholds();
+        controller.process_with_time(90.0, Hertz(85), start);
+        assert_eq!(controller.process_with_time(90.0, Hertz(85), start + increase), Some(Hertz(90)));
+    }
+
+    #[test]
+    fn test_video_cadence_snaps_to_multiple() {
+        for (fps, expected) in [(23.976, 48), (25.0, 50), (29.97, 60)] {
+            let mut controller = HysteresisController::new(Sensitivity::Balanced);
+            controller.set_video_cadence(true);
+            let start = Instant::now();
+            let mut hz = Hertz(90);
+            let mut changes = Vec::new();
+            for i in 0..100 {
+                let t = start + Duration::from_millis(100 * i);
+                if let Some(target) = controller.process_with_time(fps, hz, t) {
+                    changes.push((target, t.duration_since(start)));
+                    hz = target;
+                }
+            }
+            // One snap once the cadence has held, no detour via the FPS target
+            assert_eq!(changes, [(Hertz(expected), Duration::from_secs(CADENCE_LOCK_SECS))]);
+        }
+    }
+
+    #[test]
+    fn test_video_cadence_respects_range_and_allowed_rates() {
+        let mut controller = HysteresisController::new(Sensitivity::Balanced);
+        controller.set_video_cadence(true);
+        controller.set_allowed_hz(vec![Hertz(45), Hertz(60), Hertz(90)]);
+        assert_eq!(controller.cadence_target(30), Some(Hertz(60)));
+        assert_eq!(controller.cadence_target(25), None);
+
+        controller.set_allowed_hz(Vec::new());
+        controller.set_user_range(Hertz(40), Hertz(70));
+        assert_eq!(controller.cadence_target(24), Some(Hertz(48)));
+        assert_eq!(controller.cadence_target(30), Some(Hertz(60)));
+        controller.set_user_range(Hertz(62), Hertz(70));
+        assert_eq!(controller.cadence_target(30), None);
+    }
+
+    #[test]
+    fn test_video_cadence_disabled_uses_fps_target() {
+        let mut controller = HysteresisController::new(Sensitivity::Balanced);
+        let start = Instant::now();
+        let mut hz = Hertz(90);
+        for i in 0..100 {
+            let t = start + Duration::from_millis(100 * i);
+            if let Some(target) = controller.process_with_time(24.0, hz, t) {
+                hz = target;
+            }
+        }
+        assert_eq!(hz, Hertz(40));
+    }
+
+    #[test]
+    fn test_loading_collapse_holds_hz() {
+        let mut controller = HysteresisController::new(Sensitivity::Aggressive);
+        controller.set_loading_grace(Millis(3000));
+        let start = Instant::now();
+        let at = |ms: u64| start + Duration::from_millis(ms);
+
+        for i in 0..10 {
+            assert_eq!(controller.process_with_time(60.0, Hertz(60), at(100 * i)), None);
+        }
+        // Loading screen at 10fps for 2s, then back to 60
+        for i in 10..30 {
+            assert_eq!(controller.process_with_time(10.0, Hertz(60), at(100 * i)), None);
+        }
+        assert!(controller.loading_hold.is_some());
+        assert_eq!(controller.process_with_time(60.0, Hertz(60), at(3000)), None);
+        assert!(controller.loading_hold.is_none());
+    }
+
+    #[test]
+    fn test_sustained_collapse_reacts_after_grace() {
+        let mut controller = HysteresisController::new(Sensitivity::Aggressive);
+        controller.set_loading_grace(Millis(3000));
+        let start = Instant::now();
+        let mut hz = Hertz(60);
+        let mut changed_at = None;
+
+        for i in 0..100 {
+            let t = start + Duration::from_millis(100 * i);
+            let fps = if i < 10 { 60.0 } else { 25.0 };
+            if let Some(target) = controller.process_with_time(fps, hz, t) {
+                changed_at.get_or_insert(t.duration_since(start));
+                hz = target;
+            }
+        }
+        assert_eq!(hz, Hertz(40));
+        // Collapse at 1s, grace 3s, then the regular drop threshold
+        let (drop, _) = controller.thresholds();
+        assert!(changed_at.unwrap() >= Duration::from_secs(4) + drop);
+    }
+
+    /// Feed `fps(hz)` every 100ms for `secs`, applying each change. Returns the final Hz.
+    fn run_pid(
+        controller: &mut HysteresisController,
+        fps: impl Fn(Hertz) -> f64,
+        mut hz: Hertz,
+        start: Instant,
+        secs: u64,
+    ) -> Hertz {
+        for i in 0..secs * 10 {
+            let t = start + Duration::from_millis(100 * i);
+            if let Some(target) = controller.process_with_time(fps(hz), hz, t) {
+                hz = target;
+            }
+        }
+        hz
+    }
+
+    #[test]
+    fn test_pid_tracks_fps_down_and_holds() {
+        let mut controller = HysteresisController::new(Sensitivity::Balanced);
+        controller.set_control_policy(ControlPolicy::Pid);
+        let start = Instant::now();
+
+        // 90 -> 60 -> 50 within two seconds, without waiting for a drop threshold
+        assert_eq!(run_pid(&mut controller, |_| 51.0, Hertz(90), start, 2), Hertz(50));
+        // FPS within tolerance below the rate holds it
+        assert_eq!(run_pid(&mut controller, |_| 57.5, Hertz(60), start + Duration::from_secs(2), 10), Hertz(60));
+        assert_eq!(controller.state(), AlgorithmState::Stable);
+    }
+
+    #[test]
+    fn test_pid_steps_up_while_fps_is_pinned() {
+        let mut controller = HysteresisController::new(Sensitivity::Balanced);
+        controller.set_control_policy(ControlPolicy::Pid);
+        let start = Instant::now();
+
+        // One step needs a few seconds of pinned FPS to integrate
+        let pinned = |hz: Hertz| hz.as_f64();
+        assert_eq!(run_pid(&mut controller, pinned, Hertz(60), start, 2), Hertz(60));
+        assert_eq!(run_pid(&mut controller, pinned, Hertz(60), start + Duration::from_secs(2), 2), Hertz(65));
+        // A game capped at 60 FPS pulls the rate back down
+        assert_eq!(run_pid(&mut controller, |_| 60.0, Hertz(65), start + Duration::from_secs(4), 3), Hertz(60));
+    }
+
+    #[test]
+    fn test_pid_blocks_increase_while_stuttering() {
+        let mut controller = HysteresisController::new(Sensitivity::Balanced);
+        controller.set_control_policy(ControlPolicy::Pid);
+        let start = Instant::now();
+        for i in 0..20 {
+            controller.record_frametime(16_667, start + Duration::from_millis(i * 10));
+        }
+        for i in 0..SPIKE_THRESHOLD as u64 {
+            controller.record_frametime(50_000, start + Duration::from_millis(200 + i * 10));
+        }
+        let pinned = |hz: Hertz| hz.as_f64();
+        assert_eq!(run_pid(&mut controller, pinned, Hertz(60), start + Duration::from_millis(300), 4), Hertz(60));
+    }
+
+    #[test]
+    fn test_low_fps_compensation_doubles_rate() {
+        let mut controller = HysteresisController::new(Sensitivity::Aggressive);
+        controller.set_low_fps_compensation(true);
+        let start = Instant::now();
+        let mut hz = Hertz(90);
+        for i in 0..50 {
+            let t = start + Duration::from_millis(100 * i);
+            if let Some(target) = controller.process_with_time(28.4, hz, t) {
+                hz = target;
+            }
+        }
+        assert_eq!(hz, Hertz(56));
+        assert_eq!(controller.lfc_fps_limit(), Some(28));
+
+        // FPS back in range: regular drop target, limiter released
+        for i in 50..100 {
+            let t = start + Duration::from_millis(100 * i);
+            if let Some(target) = controller.process_with_time(45.0, hz, t) {
+                hz = target;
+            }
+        }
+        assert_eq!(hz, Hertz(45));
+        assert_eq!(controller.lfc_fps_limit(), None);
+    }
+
+    #[test]
+    fn test_low_fps_compensation_targets() {
+        let mut controller = HysteresisController::new(Sensitivity::Balanced);
+        assert_eq!(controller.lfc_target(28.0), None);
+        controller.set_low_fps_compensation(true);
+        assert_eq!(controller.lfc_target(28.0), Some((Hertz(56), 28)));
+        assert_eq!(controller.lfc_target(15.0), Some((Hertz(45), 15)));
+        assert_eq!(controller.lfc_target(50.0), None);
+
+        controller.set_allowed_hz(vec![Hertz(45), Hertz(60), Hertz(90)]);
+        assert_eq!(controller.lfc_target(30.0), Some((Hertz(60), 30)));
+        assert_eq!(controller.lfc_target(28.0), None);
+    }
+
+    #[test]
+    fn test_idle_policy() {
+        assert_eq!(IdlePolicy::default().target(Hertz(40), Hertz(90)), None);
+        assert_eq!(IdlePolicy::Min.target(Hertz(40), Hertz(90)), Some(Hertz(40)));
+        assert_eq!(IdlePolicy::Max.target(Hertz(40), Hertz(90)), Some(Hertz(90)));
+        assert_eq!(serde_json::to_string(&IdlePolicy::Min).unwrap(), r#""min""#);
+    }
+
+    #[test]
+    fn test_control_policy_serde() {
+        assert_eq!(serde_json::to_string(&ControlPolicy::Pid).unwrap(), r#""pid""#);
+        assert_eq!(
+            serde_json::from_str::<ControlPolicy>(r#""hysteresis""#).unwrap(),
+            ControlPolicy::Hysteresis
+        );
+    }
+
+    #[test]
+    fn test_oscillation_detector_damps_after_reversals() {
+        let mut detector = OscillationDetector::default();
+        let now = Instant::now();
+        let at = |s| now + Duration::from_secs(s);
+
+        assert!(!detector.record(Hertz(90), Hertz(60), at(0)));
+        assert!(!detector.record(Hertz(60), Hertz(90), at(10)));
+        assert!(!detector.record(Hertz(90), Hertz(60), at(20)));
+        assert!(detector.record(Hertz(60), Hertz(90), at(30)));
+        assert!(detector.is_damped(at(31)));
+        assert!(!detector.is_damped(at(30 + OSCILLATION_DAMPING_SECS)));
+    }
+
+    #[test]
+    fn test_slow_reversals_are_not_oscillation() {
+        let mut detector = OscillationDetector::default();
+        let now = Instant::now();
+        let mut hz = Hertz(90);
+        for i in 0..6 {
+            let next = if hz == Hertz(90) { Hertz(60) } else { Hertz(90) };
+            assert!(!detector.record(hz, next, now + Duration::from_secs(40 * i)));
+            hz = next;
+        }
+    }
+
+    #[test]
+    fn test_oscillation_widens_tolerance() {
+        let mut controller = HysteresisController::new(Sensitivity::Aggressive);
+        let now = Instant::now();
+        let mut hz = Hertz(90);
+        for i in 0..4 {
+            let next = if hz == Hertz(90) { Hertz(60) } else { Hertz(90) };
+            controller.record_change(now + Duration::from_secs(i), hz, next);
+            hz = next;
+        }
+        assert!(controller.oscillation.is_damped(now + Duration::from_secs(5)));
+
+        // 4 FPS off is outside the base tolerance but inside the damped one
+        let t = now + Duration::from_secs(10);
+        assert_eq!(controller.process_with_time(56.0, Hertz(60), t), None);
+        assert_eq!(controller.state(), AlgorithmState::Stable);
+        // Change interval is stretched
+        assert!(!controller.can_change(now + Duration::from_millis(3000 + 1000)));
+    }
+
+    #[test]
+    fn test_spikes_trigger_early_drop() {
+        let mut controller = HysteresisController::new(Sensitivity::Balanced);
+        let now = Instant::now();
+        for i in 0..10 {
+            controller.record_frametime(11_100, now + Duration::from_millis(100 * i));
+        }
+        for i in 0..SPIKE_THRESHOLD as u64 {
+            controller.record_frametime(50_000, now + Duration::from_millis(1000 + 100 * i));
+        }
+
+        // Average FPS still matches the panel, but stutter forces a step down
+        let t = now + Duration::from_millis(1600);
+        assert_eq!(controller.process_with_time(89.0, Hertz(90), t), Some(Hertz(85)));
+        // Spikes were consumed by the drop
+        assert_eq!(controller.process_with_time(89.0, Hertz(85), t + Duration::from_secs(1)), None);
+    }
+
+    #[test]
+    fn test_spikes_block_increase() {
+        let mut controller = HysteresisController::new(Sensitivity::Aggressive);
+        controller.set_user_range(Hertz(60), Hertz(90));
+        let now = Instant::now();
+        for i in 0..10 {
+            controller.record_frametime(16_000, now + Duration::from_millis(100 * i));
+        }
+        for i in 0..SPIKE_THRESHOLD as u64 {
+            controller.record_frametime(60_000, now + Duration::from_millis(1000 + 100 * i));
+        }
+
+        // At the floor: no early drop possible, and no increase either
+        for i in 0..20 {
+            let t = now + Duration::from_millis(1500 + 100 * i);
+            assert_eq!(controller.process_with_time(80.0, Hertz(60), t), None);
+        }
+    }
+
+    #[test]
+    fn test_thermal_throttling_blocks_increase() {
+        for policy in [ControlPolicy::Hysteresis, ControlPolicy::Pid] {
+            let mut controller = HysteresisController::new(Sensitivity::Aggressive);
+            controller.set_control_policy(policy);
+            controller.set_thermal_throttling(true);
+            let now = Instant::now();
+            for i in 0..50 {
+                let t = now + Duration::from_millis(100 * i);
+                assert_eq!(controller.process_with_time(80.0, Hertz(60), t), None, "{:?}", policy);
+            }
+
+            // Drops still happen while hot
+            let t = now + Duration::from_secs(6);
+            controller.process_with_time(45.0, Hertz(60), t);
+            let dropped = (1..30).find_map(|i| {
+                controller.process_with_time(45.0, Hertz(60), t + Duration::from_millis(100 * i))
+            });
+            assert!(dropped.is_some_and(|hz| hz < Hertz(60)), "{:?}", policy);
+
+            controller.set_thermal_throttling(false);
+            let t = t + Duration::from_secs(5);
+            let raised = (0..50).find_map(|i| {
+                controller.process_with_time(80.0, Hertz(60), t + Duration::from_millis(100 * i))
+            });
+            assert!(raised.is_some_and(|hz| hz > Hertz(60)), "{:?}", policy);
+        }
+    }
+
+    #[test]
+    fn test_gpu_saturated_blocks_increase() {
+        let mut controller = HysteresisController::new(Sensitivity::Aggressive);
+        controller.set_gpu_saturated(true);
+        let now = Instant::now();
+        for i in 0..50 {
+            let t = now + Duration::from_millis(100 * i);
+            assert_eq!(controller.process_with_time(90.0, Hertz(60), t), None);
+        }
+
+        controller.set_gpu_saturated(false);
+        let t = now + Duration::from_secs(6);
+        let raised = (0..50).find_map(|i| {
+            controller.process_with_time(90.0, Hertz(60), t + Duration::from_millis(100 * i))
+        });
+        assert!(raised.is_some_and(|hz| hz > Hertz(60)));
+    }
+
+    #[test]
+    fn test_tolerance_boost_widens_sticky_target() {
+        let mut controller = HysteresisController::new(Sensitivity::Aggressive);
+        let now = Instant::now();
+        controller.set_tolerance_boost(5.0);
+
+        // 6 FPS under the panel rate sits inside 3.0 + 5.0 tolerance
+        for i in 0..30 {
+            let t = now + Duration::from_millis(100 * i);
+            assert_eq!(controller.process_with_time(84.0, Hertz(90), t), None);
+        }
+        assert_eq!(controller.state(), AlgorithmState::Stable);
+    }
+
     #[test]
     fn test_fps_tolerance_clamping() {
         let mut controller = HysteresisController::new(Sensitivity::Balanced);
@@ -755,12 +2304,12 @@ mod tests {
     #[test]
     fn test_external_display_pauses_processing() {
         let mut controller = HysteresisController::new(Sensitivity::Balanced);
-        controller.set_user_range(40, 90);
+        controller.set_user_range(Hertz(40), Hertz(90));
         controller.set_external_display_detected(true);
         let start = Instant::now();
 
         // Even with low FPS, should return None when external display detected
-        let result = controller.process_with_time(30.0, 60, start);
+        let result = controller.process_with_time(30.0, Hertz(60), start);
         assert!(result.is_none());
         assert_eq!(controller.state(), AlgorithmState::Stable);
     }
@@ -770,8 +2319,9 @@ mod tests {
         let mut window = FpsSlidingWindow::new(5);
         
         // Push samples
+        let start = Instant::now();
         for i in 1..=5 {
-            window.push(i as f64 * 10.0);
+            window.push(i as f64 * 10.0, start + Duration::from_millis(100 * i));
         }
         
         assert!(window.is_full());
@@ -781,12 +2331,18 @@ mod tests {
         // Std dev of [10,20,30,40,50] = sqrt(250) ≈ 15.81
         let std_dev = window.std_dev();
         assert!((std_dev - 15.81).abs() < 0.1);
+
+        // +10 FPS every 100ms
+        assert!((window.slope().unwrap() - 100.0).abs() < 1e-6);
+        window.clear();
+        window.push(60.0, start);
+        assert_eq!(window.slope(), None);
     }
 
     #[test]
     fn test_adaptive_sensitivity_unstable_fps() {
         let mut controller = HysteresisController::new(Sensitivity::Aggressive);
-        controller.set_user_range(40, 90);
+        controller.set_user_range(Hertz(40), Hertz(90));
         controller.set_adaptive_sensitivity(true);
 
         // Push highly variable FPS samples (std_dev > 5)
@@ -794,17 +2350,18 @@ mod tests {
         let start = Instant::now();
         
         for fps in fps_samples {
-            controller.process_with_time(fps, 60, start);
+            controller.process_with_time(fps, Hertz(60), start);
         }
 
         // Should have switched to Conservative due to high variance
         assert_eq!(controller.effective_sensitivity(), Sensitivity::Conservative);
+        assert_eq!(controller.fps_stability(), Some(FpsStability::Unstable));
     }
 
     #[test]
     fn test_adaptive_sensitivity_stable_fps() {
         let mut controller = HysteresisController::new(Sensitivity::Aggressive);
-        controller.set_user_range(40, 90);
+        controller.set_user_range(Hertz(40), Hertz(90));
         controller.set_adaptive_sensitivity(true);
 
         // Push stable FPS samples (std_dev < 2)
@@ -812,11 +2369,28 @@ mod tests {
         let start = Instant::now();
         
         for fps in fps_samples {
-            controller.process_with_time(fps, 60, start);
+            controller.process_with_time(fps, Hertz(60), start);
         }
 
         // Should keep user preference (Aggressive) due to low variance
         assert_eq!(controller.effective_sensitivity(), Sensitivity::Aggressive);
+        assert_eq!(controller.fps_stability(), Some(FpsStability::Stable));
+    }
+
+    #[test]
+    fn test_fps_stability_verdict() {
+        let mut controller = HysteresisController::new(Sensitivity::Balanced);
+        assert_eq!(controller.fps_stability(), None);
+
+        // Reported with adaptive sensitivity off, once the window fills
+        let start = Instant::now();
+        for fps in [60.0, 56.0, 60.0, 56.0, 60.0, 56.0, 60.0, 56.0, 60.0] {
+            controller.process_with_time(fps, Hertz(60), start);
+        }
+        assert_eq!(controller.fps_stability(), None);
+        controller.process_with_time(56.0, Hertz(60), start);
+        assert_eq!(controller.fps_stability(), Some(FpsStability::Variable));
+        assert_eq!(FpsStability::from_std_dev(STD_DEV_UNSTABLE + 0.1), FpsStability::Unstable);
     }
 
     #[test]
@@ -833,11 +2407,26 @@ mod tests {
 
     #[test]
     fn test_quantize_hz() {
-        assert_eq!(HysteresisController::quantize_hz(42), 40);
-        assert_eq!(HysteresisController::quantize_hz(43), 45);
-        assert_eq!(HysteresisController::quantize_hz(47), 45);
-        assert_eq!(HysteresisController::quantize_hz(48), 50);
-        assert_eq!(HysteresisController::quantize_hz(50), 50);
+        assert_eq!(HysteresisController::quantize_hz(Hertz(42)), Hertz(40));
+        assert_eq!(HysteresisController::quantize_hz(Hertz(43)), Hertz(45));
+        assert_eq!(HysteresisController::quantize_hz(Hertz(47)), Hertz(45));
+        assert_eq!(HysteresisController::quantize_hz(Hertz(48)), Hertz(50));
+        assert_eq!(HysteresisController::quantize_hz(Hertz(50)), Hertz(50));
+    }
+
+    #[test]
+    fn test_high_refresh_range() {
+        let mut controller = HysteresisController::new(Sensitivity::Balanced);
+        controller.set_user_range(Hertz(48), Hertz(144));
+        assert_eq!(controller.clamp_hz(Hertz(165)), Hertz(144));
+        assert_eq!(controller.clamp_hz(Hertz(143)), Hertz(144));
+        assert_eq!(controller.clamp_hz(Hertz(122)), Hertz(120));
+        assert_eq!(controller.clamp_hz(Hertz(30)), Hertz(48));
+        assert_eq!(controller.next_step_up(Hertz(140)), Hertz(144));
+        assert_eq!(controller.next_step_up(Hertz(144)), Hertz(144));
+        assert_eq!(controller.next_step_down(Hertz(144)), Hertz(140));
+        assert_eq!(controller.next_step_down(Hertz(50)), Hertz(48));
+        assert_eq!(controller.target_hz_for_drop(100.0), Hertz(100));
     }
 
     proptest! {
@@ -846,14 +2435,14 @@ mod tests {
             current_hz in 45u32..=85u32,
             fps_offset in 0.0f64..2.9f64,
         ) {
-            let current_hz = (current_hz / 5) * 5;
+            let current_hz = Hertz((current_hz / 5) * 5);
             let mut controller = HysteresisController::new(Sensitivity::Balanced);
-            controller.set_user_range(40, 90);
+            controller.set_user_range(Hertz(40), Hertz(90));
             let start = Instant::now();
 
             // FPS within tolerance should not trigger state change
-            let fps_above = current_hz as f64 + fps_offset;
-            let fps_below = current_hz as f64 - fps_offset;
+            let fps_above = current_hz.as_f64() + fps_offset;
+            let fps_below = current_hz.as_f64() - fps_offset;
 
             let result1 = controller.process_with_time(fps_above, current_hz, start);
             prop_assert!(result1.is_none());
@@ -869,9 +2458,9 @@ mod tests {
             fps in 35.0f64..=95.0f64,
             current_hz in 40u32..=90u32,
         ) {
-            let current_hz = (current_hz / 5) * 5;
+            let current_hz = Hertz((current_hz / 5) * 5);
             let mut controller = HysteresisController::new(Sensitivity::Balanced);
-            controller.set_user_range(40, 90);
+            controller.set_user_range(Hertz(40), Hertz(90));
             let start = Instant::now();
 
             let _ = controller.process_with_time(fps, current_hz, start);
@@ -879,8 +2468,8 @@ mod tests {
             let result = controller.process_with_time(fps, current_hz, after_threshold);
 
             if let Some(new_hz) = result {
-                prop_assert_eq!(new_hz % 5, 0, "Output Hz must be on 5Hz step boundary");
-                prop_assert!(new_hz >= 40 && new_hz <= 90, "Output Hz must be in valid range");
+                prop_assert_eq!(new_hz.get() % 5, 0, "Output Hz must be on 5Hz step boundary");
+                prop_assert!((Hertz(40)..=Hertz(90)).contains(&new_hz), "Output Hz must be in valid range");
             }
         }
     }
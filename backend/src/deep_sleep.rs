@@ -0,0 +1,240 @@
+//! Deep-sleep mode for an idle daemon.
+//!
+//! When no game is running and no FPS data has arrived for a while, the
+//! FPS, battery and monitor tasks park until something wakes the daemon:
+//! MangoHud shared memory appearing in /dev/shm (watched with inotify) or
+//! a game being detected over IPC.
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+
+/// Tracks activity and the deep-sleep flag shared by all tasks.
+pub struct SleepController {
+    sleeping: watch::Sender<bool>,
+    last_activity: Mutex<Instant>,
+}
+
+impl Default for SleepController {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SleepController {
+    pub fn new() -> Self {
+        Self {
+            sleeping: watch::channel(false).0,
+            last_activity: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Record activity (FPS data or a detected game), waking if asleep.
+    pub fn note_activity(&self) {
+        if let Ok(mut last) = self.last_activity.lock() {
+            *last = Instant::now();
+        }
+        self.sleeping.send_if_modified(|sleeping| {
+            let was_sleeping = *sleeping;
+            if was_sleeping {
+                tracing::info!("Waking from deep sleep");
+            }
+            *sleeping = false;
+            was_sleeping
+        });
+    }
+
+    /// Time since the last recorded activity.
+    pub fn idle_for(&self) -> Duration {
+        self.last_activity
+            .lock()
+            .map(|last| last.elapsed())
+            .unwrap_or_default()
+    }
+
+    pub fn is_sleeping(&self) -> bool {
+        *self.sleeping.borrow()
+    }
+
+    /// Enter deep sleep. Tasks park at their next `wait_until_awake`.
+    pub fn enter(&self) {
+        self.sleeping.send_if_modified(|sleeping| {
+            let changed = !*sleeping;
+            *sleeping = true;
+            changed
+        });
+    }
+
+    /// Subscribe to sleep state changes.
+    pub fn subscribe(&self) -> watch::Receiver<bool> {
+        self.sleeping.subscribe()
+    }
+
+    /// Park while in deep sleep.
+    ///
+    /// Returns true once awake, or false if shutdown was signalled first.
+    pub async fn wait_until_awake(&self, shutdown_rx: &mut watch::Receiver<bool>) -> bool {
+        let mut sleeping_rx = self.sleeping.subscribe();
+        loop {
+            if *shutdown_rx.borrow() {
+                return false;
+            }
+            if !*sleeping_rx.borrow_and_update() {
+                return true;
+            }
+            tokio::select! {
+                result = shutdown_rx.changed() => {
+                    if result.is_err() {
+                        return false;
+                    }
+                }
+                _ = sleeping_rx.changed() => {}
+            }
+        }
+    }
+}
+
+/// Watches a directory for a file being created (inotify).
+pub struct CreateWatcher {
+    fd: tokio::io::unix::AsyncFd<std::os::fd::OwnedFd>,
+}
+
+impl CreateWatcher {
+    /// Start watching `dir` for created or moved-in files.
+    pub fn new(dir: &std::path::Path) -> std::io::Result<Self> {
+        use std::ffi::CString;
+        use std::os::fd::{FromRawFd, OwnedFd};
+        use std::os::unix::ffi::OsStrExt;
+
+        let path = CString::new(dir.as_os_str().as_bytes())
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+
+        // SAFETY: plain syscalls; the returned descriptor is owned below
+        let fd = unsafe { libc::inotify_init1(libc::IN_NONBLOCK | libc::IN_CLOEXEC) };
+        if fd < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        // SAFETY: `fd` is a freshly created descriptor nothing else owns
+        let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+
+        use std::os::fd::AsRawFd;
+        // SAFETY: `path` is a valid NUL-terminated string for the call
+        let wd = unsafe {
+            libc::inotify_add_watch(fd.as_raw_fd(), path.as_ptr(), libc::IN_CREATE | libc::IN_MOVED_TO)
+        };
+        if wd < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        Ok(Self {
+            fd: tokio::io::unix::AsyncFd::new(fd)?,
+        })
+    }
+
+    /// Wait until a file whose name `matches` is created in the watched
+    /// directory.
+    pub async fn wait_for(&mut self, matches: impl Fn(&str) -> bool) -> std::io::Result<()> {
+        use std::os::fd::AsRawFd;
+
+        let mut buf = [0u8; 4096];
+        loop {
+            let mut guard = self.fd.readable().await?;
+            let read = guard.try_io(|inner| {
+                // SAFETY: reading into a stack buffer of the given length
+                let n = unsafe {
+                    libc::read(inner.get_ref().as_raw_fd(), buf.as_mut_ptr().cast(), buf.len())
+                };
+                if n < 0 {
+                    Err(std::io::Error::last_os_error())
+                } else {
+                    Ok(n as usize)
+                }
+            });
+            let len = match read {
+                Ok(result) => result?,
+                Err(_would_block) => continue,
+            };
+            if event_names(&buf[..len]).any(&matches) {
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Iterate over file names in a buffer of inotify events.
+fn event_names(buf: &[u8]) -> impl Iterator<Item = &str> {
+    const HEADER: usize = std::mem::size_of::<libc::inotify_event>();
+    let mut offset = 0;
+    std::iter::from_fn(move || {
+        while offset + HEADER <= buf.len() {
+            // inotify_event is { wd: i32, mask: u32, cookie: u32, len: u32 } then the name
+            let len_bytes: [u8; 4] = buf[offset + 12..offset + 16].try_into().ok()?;
+            let name_len = u32::from_ne_bytes(len_bytes) as usize;
+            let name = buf.get(offset + HEADER..offset + HEADER + name_len)?;
+            offset += HEADER + name_len;
+            let name = name.split(|b| *b == 0).next().unwrap_or_default();
+            if let Ok(name) = std::str::from_utf8(name) {
+                return Some(name);
+            }
+        }
+        None
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_sleep_and_wake() {
+        let controller = SleepController::new();
+        let (_shutdown_tx, mut shutdown_rx) = watch::channel(false);
+        assert!(!controller.is_sleeping());
+
+        controller.enter();
+        assert!(controller.is_sleeping());
+
+        let waiter = async { controller.wait_until_awake(&mut shutdown_rx).await };
+        let waker = async {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            controller.note_activity();
+        };
+        let (awake, ()) = tokio::join!(waiter, waker);
+        assert!(awake);
+        assert!(!controller.is_sleeping());
+        assert!(controller.idle_for() < Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_interrupts_sleep() {
+        let controller = SleepController::new();
+        let (shutdown_tx, mut shutdown_rx) = watch::channel(false);
+        controller.enter();
+
+        let waiter = async { controller.wait_until_awake(&mut shutdown_rx).await };
+        let stopper = async {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            shutdown_tx.send(true).unwrap();
+        };
+        let (awake, ()) = tokio::join!(waiter, stopper);
+        assert!(!awake);
+    }
+
+        #[tokio::test]
+    async fn test_create_watcher_sees_new_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut watcher = CreateWatcher::new(dir.path()).unwrap();
+
+        let path = dir.path().to_path_buf();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            std::fs::write(path.join("unrelated"), b"").unwrap();
+            std::fs::write(path.join("mangohud-overlay"), b"").unwrap();
+        });
+
+        tokio::time::timeout(Duration::from_secs(2), watcher.wait_for(|name| name == "mangohud-overlay"))
+            .await
+            .expect("watcher timed out")
+            .unwrap();
+    }
+}
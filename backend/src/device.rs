@@ -0,0 +1,127 @@
+//! Handheld model detection from DMI, with built-in presets.
+//!
+//! The board name (and, for Lenovo, the product name) under
+//! `/sys/class/dmi/id` identifies the handheld, which picks a default
+//! refresh range, device mode and minimum change interval.
+
+use crate::core_logic::DeviceMode;
+use crate::units::{Hertz, Millis};
+use serde::{Deserialize, Serialize};
+use std::ops::RangeInclusive;
+use std::path::Path;
+
+/// DMI identification directory
+pub const DMI_DIR: &str = "/sys/class/dmi/id";
+
+/// Known handheld models.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeviceModel {
+    /// Steam Deck LCD (board "Jupiter")
+    SteamDeckLcd,
+    /// Steam Deck OLED (board "Galileo")
+    SteamDeckOled,
+    /// ASUS ROG Ally and Ally X (boards "RC71L", "RC72LA")
+    RogAlly,
+    /// Lenovo Legion Go (product "83E1")
+    LegionGo,
+    /// Anything else; no preset
+    #[default]
+    Unknown,
+}
+
+/// Defaults for a known model.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DevicePreset {
+    pub hz_range: RangeInclusive<Hertz>,
+    pub mode: DeviceMode,
+    pub min_change_interval: Millis,
+}
+
+impl DeviceModel {
+    /// Identify the model from DMI `board_name` and `product_name`.
+    pub fn from_dmi(board_name: &str, product_name: &str) -> Self {
+        match (board_name.trim(), product_name.trim()) {
+            ("Jupiter", _) => Self::SteamDeckLcd,
+            ("Galileo", _) => Self::SteamDeckOled,
+            (board, _) if board.starts_with("RC71L") || board.starts_with("RC72L") => Self::RogAlly,
+            ("LNVNB161216", _) | (_, "83E1") => Self::LegionGo,
+            _ => Self::Unknown,
+        }
+    }
+
+    /// Read the model from DMI under `dmi_dir`.
+    pub fn detect(dmi_dir: &Path) -> Self {
+        let read = |name| std::fs::read_to_string(dmi_dir.join(name)).unwrap_or_default();
+        Self::from_dmi(&read("board_name"), &read("product_name"))
+    }
+
+    /// Built-in defaults, or None for an unknown model.
+    pub fn preset(&self) -> Option<DevicePreset> {
+        let (min, max, mode, interval) = match self {
+            Self::SteamDeckLcd => (40, 60, DeviceMode::Lcd, 2000),
+            Self::SteamDeckOled => (45, 90, DeviceMode::Oled, 500),
+            // IPS panels with a VRR range, no LCD flicker constraints
+            Self::RogAlly => (48, 120, DeviceMode::Custom, 1000),
+            Self::LegionGo => (60, 144, DeviceMode::Custom, 1000),
+            Self::Unknown => return None,
+        };
+        Some(DevicePreset {
+            hz_range: Hertz(min)..=Hertz(max),
+            mode,
+            min_change_interval: Millis(interval),
+        })
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::SteamDeckLcd => "steam_deck_lcd",
+            Self::SteamDeckOled => "steam_deck_oled",
+            Self::RogAlly => "rog_ally",
+            Self::LegionGo => "legion_go",
+            Self::Unknown => "unknown",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_from_dmi() {
+        assert_eq!(DeviceModel::from_dmi("Jupiter\n", "Jupiter\n"), DeviceModel::SteamDeckLcd);
+        assert_eq!(DeviceModel::from_dmi("Galileo\n", "Galileo\n"), DeviceModel::SteamDeckOled);
+        assert_eq!(DeviceModel::from_dmi("RC71L\n", "ROG Ally RC71L_RC71L"), DeviceModel::RogAlly);
+        assert_eq!(DeviceModel::from_dmi("RC72LA\n", "ROG Ally X RC72LA"), DeviceModel::RogAlly);
+        assert_eq!(DeviceModel::from_dmi("LNVNB161216\n", "83E1\n"), DeviceModel::LegionGo);
+        assert_eq!(DeviceModel::from_dmi("B550M\n", "System Product Name"), DeviceModel::Unknown);
+        assert!(DeviceModel::Unknown.preset().is_none());
+    }
+
+    #[test]
+    fn test_presets() {
+        let lcd = DeviceModel::SteamDeckLcd.preset().unwrap();
+        assert_eq!(lcd.hz_range, Hertz(40)..=Hertz(60));
+        assert_eq!(lcd.mode, DeviceMode::Lcd);
+        assert_eq!(lcd.min_change_interval, Millis(2000));
+        assert_eq!(DeviceModel::SteamDeckOled.preset().unwrap().mode, DeviceMode::Oled);
+        assert_eq!(DeviceModel::LegionGo.preset().unwrap().hz_range, Hertz(60)..=Hertz(144));
+    }
+
+    #[test]
+    fn test_detect() {
+        let dir = tempdir().unwrap();
+        assert_eq!(DeviceModel::detect(dir.path()), DeviceModel::Unknown);
+        std::fs::write(dir.path().join("board_name"), "Galileo\n").unwrap();
+        assert_eq!(DeviceModel::detect(dir.path()), DeviceModel::SteamDeckOled);
+    }
+
+    #[test]
+    fn test_as_str_matches_serde() {
+        for model in [DeviceModel::SteamDeckLcd, DeviceModel::RogAlly, DeviceModel::Unknown] {
+            assert_eq!(serde_json::to_value(model).unwrap(), model.as_str());
+        }
+    }
+}
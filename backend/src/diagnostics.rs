@@ -0,0 +1,103 @@
+//! Fail-fast startup diagnostics for `--strict` mode.
+//!
+//! Without `--strict` the daemon keeps running in fallback mode when no
+//! display backend or FPS source is usable. Packagers and service managers
+//! can opt into strict mode to get a distinct exit code instead. The checks
+//! probe the configured backend and FPS source, or auto-detect them like the
+//! daemon does.
+
+use crate::config::Config;
+use crate::display_backend::{self, BackendKind};
+use crate::error::{FpsSourceError, StartupError};
+use crate::fps_source;
+use std::time::{Duration, Instant};
+use tracing::info;
+
+/// Delay between FPS source probes
+const FPS_SOURCE_PROBE_INTERVAL_MS: u64 = 500;
+
+/// Check that the configured display backend (or any, when auto-detecting)
+/// is usable.
+pub async fn check_display_backend(preferred: Option<BackendKind>) -> Result<BackendKind, StartupError> {
+    let kind = match preferred {
+        Some(kind) if kind.create().probe().await.unwrap_or(false) => kind,
+        Some(kind) => return Err(StartupError::DisplayBackendUnavailable(kind.as_str().to_string())),
+        None => display_backend::select(None).await.kind(),
+    };
+    if kind == BackendKind::Noop {
+        return Err(StartupError::DisplayBackendUnavailable("auto".to_string()));
+    }
+    Ok(kind)
+}
+
+/// Try the configured FPS source, or every source when auto-detecting.
+fn open_fps_source(config: &Config) -> Result<fps_source::FpsSourceKind, FpsSourceError> {
+    match config.fps_source {
+        Some(kind) => kind.open(config, None).map(|source| source.kind()),
+        None => fps_source::open(config, None).map(|source| source.kind()),
+    }
+}
+
+/// Wait up to `timeout` for the configured FPS source (or any) to open.
+pub async fn wait_for_fps_source(config: &Config, timeout: Duration) -> Option<fps_source::FpsSourceKind> {
+    // The CLI bounds the timeout; an unrepresentable deadline waits forever
+    let deadline = Instant::now().checked_add(timeout);
+    loop {
+        if let Ok(kind) = open_fps_source(config) {
+            return Some(kind);
+        }
+        if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            return None;
+        }
+        tokio::time::sleep(Duration::from_millis(FPS_SOURCE_PROBE_INTERVAL_MS)).await;
+    }
+}
+
+/// Run all strict-mode checks, returning the first failure.
+pub async fn run_strict_checks(config: &Config, timeout_secs: u64) -> Result<(), StartupError> {
+    let backend = check_display_backend(config.display_backend).await?;
+    info!("Strict mode: display backend {} available", backend.as_str());
+
+    let wanted = config.fps_source.map_or("any", |kind| kind.as_str());
+    info!("Strict mode: waiting up to {}s for an FPS source ({})", timeout_secs, wanted);
+    match wait_for_fps_source(config, Duration::from_secs(timeout_secs)).await {
+        Some(kind) => info!("Strict mode: FPS source {} available", kind.as_str()),
+        None => return Err(StartupError::FpsSourceUnavailable(timeout_secs)),
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fps_source::FpsSourceKind;
+
+    #[tokio::test]
+    async fn test_configured_backend_must_be_usable() {
+        // The no-op backend always probes fine but controls nothing
+        assert!(matches!(
+            check_display_backend(Some(BackendKind::Noop)).await,
+            Err(StartupError::DisplayBackendUnavailable(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_configured_fps_source_is_probed() {
+        let config = Config {
+            fps_source: Some(FpsSourceKind::Mangohud),
+            mangohud_shm_names: vec!["/smartrefresh-test-missing".to_string()],
+            ..Config::default()
+        };
+        assert_eq!(wait_for_fps_source(&config, Duration::ZERO).await, None);
+    }
+
+    #[test]
+    fn test_exit_codes_are_distinct() {
+        let backend = StartupError::DisplayBackendUnavailable("auto".to_string()).exit_code();
+        let fps = StartupError::FpsSourceUnavailable(30).exit_code();
+        assert_ne!(backend, fps);
+        // 1 and 2 are used for runtime and usage errors
+        assert!(backend > 2 && fps > 2);
+    }
+}
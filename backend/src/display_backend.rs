@@ -0,0 +1,894 @@
+//! Pluggable refresh rate backends.
+//!
+//! Not every SteamOS variant ships `gamescope-cmd`, so the way a refresh
+//! rate is applied sits behind the `DisplayBackend` trait. At startup the
+//! configured backend is used if it is available; otherwise backends are
+//! probed in `DETECTION_ORDER` and the first available one wins, ending
+//! with the no-op backend so the daemon keeps running (and reporting FPS)
+//! on systems it cannot drive.
+
+use crate::error::DisplayError;
+use crate::units::Hertz;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::Arc;
+use tokio::process::Command;
+use x11rb::connection::Connection;
+use x11rb::protocol::xproto::{AtomEnum, ConnectionExt as _, PropMode, Window};
+use x11rb::rust_connection::RustConnection;
+use x11rb::wrapper::ConnectionExt as _;
+
+/// Future returned by backend operations.
+pub type BackendFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T, DisplayError>> + Send + 'a>>;
+
+/// Available refresh rate backends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackendKind {
+    /// `gamescope-cmd -r` / `-F`
+    #[default]
+    GamescopeCmd,
+    /// Gamescope root window atoms, set over X11
+    GamescopeAtom,
+    /// Panel `refresh_rate` sysfs node, directly or via `steamos-priv-write`
+    Sysfs,
+    /// `wlr-randr` on wlroots/KDE Wayland compositors
+    WlrRandr,
+    /// Accept every change without touching the display
+    Noop,
+}
+
+/// Order in which backends are probed during auto-detection.
+pub const DETECTION_ORDER: [BackendKind; 5] = [
+    BackendKind::GamescopeCmd,
+    BackendKind::GamescopeAtom,
+    BackendKind::Sysfs,
+    BackendKind::WlrRandr,
+    BackendKind::Noop,
+];
+
+/// Process names of a running gamescope compositor
+pub const GAMESCOPE_PROCESSES: [&str; 2] = ["gamescope", "gamescope-wl"];
+
+impl BackendKind {
+    /// Parse an IPC backend name (e.g. "gamescope_cmd").
+    pub fn parse(name: &str) -> Option<Self> {
+        serde_json::from_value(serde_json::Value::String(name.to_lowercase())).ok()
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BackendKind::GamescopeCmd => "gamescope_cmd",
+            BackendKind::GamescopeAtom => "gamescope_atom",
+            BackendKind::Sysfs => "sysfs",
+            BackendKind::WlrRandr => "wlr_randr",
+            BackendKind::Noop => "noop",
+        }
+    }
+
+    /// Create the backend of this kind.
+    pub fn create(&self) -> Arc<dyn DisplayBackend> {
+        match self {
+            BackendKind::GamescopeCmd => Arc::new(GamescopeCmdBackend::default()),
+            BackendKind::GamescopeAtom => Arc::new(GamescopeAtomBackend::default()),
+            BackendKind::Sysfs => Arc::new(SysfsBackend::discover(
+                Path::new(DRM_DIR),
+                Path::new(STEAMOS_PRIV_WRITE),
+            )),
+            BackendKind::WlrRandr => Arc::new(WlrRandrBackend::default()),
+            BackendKind::Noop => Arc::new(NoopBackend),
+        }
+    }
+}
+
+/// A way of applying refresh rates and frame limits.
+pub trait DisplayBackend: Send + Sync {
+    fn kind(&self) -> BackendKind;
+
+    /// Whether this backend can drive the display on this system.
+    fn probe(&self) -> BackendFuture<'_, bool>;
+
+    /// Switch the panel to `hz`.
+    fn set_refresh_rate(&self, hz: Hertz) -> BackendFuture<'_, ()>;
+
+    /// Whether `set_output_refresh_rate` can reach external outputs.
+    fn supports_outputs(&self) -> bool {
+        false
+    }
+
+    /// Switch the external output named `output` (e.g. "DP-1") to `hz`.
+    fn set_output_refresh_rate(&self, _output: &str, _hz: Hertz) -> BackendFuture<'_, ()> {
+        let backend = self.kind().as_str();
+        Box::pin(async move { Err(DisplayError::Unsupported { backend, operation: "external outputs" }) })
+    }
+
+    /// Set the frame limiter to `fps` (0 removes the limit).
+    fn set_fps_limit(&self, fps: u32) -> BackendFuture<'_, ()>;
+
+    /// The panel's current refresh rate, `None` when the backend can't read it.
+    fn current_refresh_rate(&self) -> BackendFuture<'_, Option<Hertz>> {
+        Box::pin(async { Ok(None) })
+    }
+
+    /// Refresh range the panel supports, `None` when the backend can't tell.
+    fn refresh_range(&self) -> BackendFuture<'_, Option<RangeInclusive<Hertz>>> {
+        Box::pin(async { Ok(None) })
+    }
+
+    /// Whether the panel supports VRR through this backend.
+    fn vrr_capable(&self) -> BackendFuture<'_, bool> {
+        Box::pin(async { Ok(false) })
+    }
+
+    /// Turn adaptive sync on or off.
+    fn set_vrr(&self, _enabled: bool) -> BackendFuture<'_, ()> {
+        let backend = self.kind().as_str();
+        Box::pin(async move { Err(DisplayError::Unsupported { backend, operation: "VRR" }) })
+    }
+
+    /// Whether the Steam overlay is open over the game. `false` when the
+    /// backend can't tell.
+    fn overlay_open(&self) -> BackendFuture<'_, bool> {
+        Box::pin(async { Ok(false) })
+    }
+
+    /// Steam app ID of the focused window. `None` when nothing is focused
+    /// or the backend can't tell.
+    fn focused_app(&self) -> BackendFuture<'_, Option<u32>> {
+        Box::pin(async { Ok(None) })
+    }
+
+    /// Identifies the running compositor instance (its PID); a new value
+    /// means it restarted with its default state. `None` when not tracked
+    /// or not running.
+    fn compositor_id(&self) -> Option<u32> {
+        None
+    }
+}
+
+/// Use `preferred` if it is available, otherwise the first available
+/// backend in `DETECTION_ORDER`.
+pub async fn select(preferred: Option<BackendKind>) -> Arc<dyn DisplayBackend> {
+    if let Some(kind) = preferred {
+        let backend = kind.create();
+        if backend.probe().await.unwrap_or(false) {
+            return backend;
+        }
+        tracing::warn!("Display backend {} unavailable, auto-detecting", kind.as_str());
+    }
+    for kind in DETECTION_ORDER {
+        let backend = kind.create();
+        if backend.probe().await.unwrap_or(false) {
+            return backend;
+        }
+        tracing::debug!("Display backend {} unavailable", kind.as_str());
+    }
+    Arc::new(NoopBackend)
+}
+
+/// Find an executable `name` in the directories of a PATH-style list.
+pub fn find_in_path(name: &str, path_var: &str) -> Option<PathBuf> {
+    std::env::split_paths(path_var)
+        .map(|dir| dir.join(name))
+        .find(|candidate| is_executable(candidate))
+}
+
+fn is_executable(path: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        path.metadata()
+            .map(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+            .unwrap_or(false)
+    }
+    #[cfg(not(unix))]
+    {
+        path.is_file()
+    }
+}
+
+/// Whether a process with one of `names` is running, by `/proc/*/comm`.
+pub fn process_running(proc_dir: &Path, names: &[&str]) -> bool {
+    process_pid(proc_dir, names).is_some()
+}
+
+/// Lowest PID of a process with one of `names`.
+fn process_pid(proc_dir: &Path, names: &[&str]) -> Option<u32> {
+    std::fs::read_dir(proc_dir)
+        .ok()?
+        .flatten()
+        .filter_map(|entry| {
+            let pid = entry.file_name().to_str()?.parse::<u32>().ok()?;
+            let comm = std::fs::read_to_string(entry.path().join("comm")).ok()?;
+            names.contains(&comm.trim()).then_some(pid)
+        })
+        .min()
+}
+
+/// Run `command` and return its stdout, mapping a missing binary or
+/// non-zero exit to a `DisplayError`.
+async fn run_command(mut command: Command, program: &str) -> Result<Vec<u8>, DisplayError> {
+    let output = command.output().await.map_err(|e| {
+        if e.kind() == std::io::ErrorKind::NotFound {
+            DisplayError::CommandNotFound { program: program.to_string() }
+        } else {
+            DisplayError::ExecutionFailed(e)
+        }
+    })?;
+
+    if !output.status.success() {
+        return Err(DisplayError::CommandFailed {
+            program: program.to_string(),
+            exit_code: output.status.code(),
+            stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+        });
+    }
+    Ok(output.stdout)
+}
+
+/// Drives gamescope through `gamescope-cmd`. VRR has no gamescope-cmd
+/// switch, so it goes through the root window atoms.
+#[derive(Debug)]
+pub struct GamescopeCmdBackend {
+    program: String,
+    atoms: GamescopeAtomBackend,
+}
+
+impl Default for GamescopeCmdBackend {
+    fn default() -> Self {
+        Self {
+            program: "gamescope-cmd".to_string(),
+            atoms: GamescopeAtomBackend::default(),
+        }
+    }
+}
+
+impl DisplayBackend for GamescopeCmdBackend {
+    fn kind(&self) -> BackendKind {
+        BackendKind::GamescopeCmd
+    }
+
+    /// gamescope-cmd is installed on SteamOS desktop mode too, so the
+    /// compositor itself must be running.
+    fn probe(&self) -> BackendFuture<'_, bool> {
+        Box::pin(async move {
+            let path_var = std::env::var("PATH").unwrap_or_default();
+            Ok(find_in_path(&self.program, &path_var).is_some()
+                && process_running(Path::new("/proc"), &GAMESCOPE_PROCESSES))
+        })
+    }
+
+    fn set_refresh_rate(&self, hz: Hertz) -> BackendFuture<'_, ()> {
+        Box::pin(async move {
+            let mut command = Command::new(&self.program);
+            command.arg("-r").arg(hz.to_string());
+            run_command(command, &self.program).await.map(drop)
+        })
+    }
+
+    fn set_fps_limit(&self, fps: u32) -> BackendFuture<'_, ()> {
+        Box::pin(async move {
+            let mut command = Command::new(&self.program);
+            command.arg("-F").arg(fps.to_string());
+            run_command(command, &self.program).await.map(drop)
+        })
+    }
+
+    fn current_refresh_rate(&self) -> BackendFuture<'_, Option<Hertz>> {
+        self.atoms.current_refresh_rate()
+    }
+
+    fn compositor_id(&self) -> Option<u32> {
+        process_pid(Path::new("/proc"), &GAMESCOPE_PROCESSES)
+    }
+
+    fn vrr_capable(&self) -> BackendFuture<'_, bool> {
+        self.atoms.vrr_capable()
+    }
+
+    fn set_vrr(&self, enabled: bool) -> BackendFuture<'_, ()> {
+        self.atoms.set_vrr(enabled)
+    }
+
+    fn overlay_open(&self) -> BackendFuture<'_, bool> {
+        self.atoms.overlay_open()
+    }
+
+    fn focused_app(&self) -> BackendFuture<'_, Option<u32>> {
+        self.atoms.focused_app()
+    }
+}
+
+/// Root window property gamescope sets on each of its Xwayland servers
+const GAMESCOPE_SERVER_ATOM: &str = "GAMESCOPE_XWAYLAND_SERVER_ID";
+
+/// Refresh rate requested from gamescope (what Steam's slider sets)
+const GAMESCOPE_REFRESH_ATOM: &str = "GAMESCOPE_DYNAMIC_REFRESH";
+
+/// Gamescope frame limit (0 removes it)
+const GAMESCOPE_FPS_LIMIT_ATOM: &str = "GAMESCOPE_FPS_LIMIT";
+
+/// Set by gamescope when the panel supports adaptive sync
+const GAMESCOPE_VRR_CAPABLE_ATOM: &str = "GAMESCOPE_VRR_CAPABLE";
+
+/// Requests adaptive sync from gamescope
+const GAMESCOPE_VRR_ENABLED_ATOM: &str = "GAMESCOPE_VRR_ENABLED";
+
+/// App ID of the window gamescope has focused
+const GAMESCOPE_FOCUSED_APP_ATOM: &str = "GAMESCOPE_FOCUSED_APP";
+
+/// App ID of the Steam client, focused while its overlay is open over a game
+pub const STEAM_APP_ID: u32 = 769;
+
+/// Connect to `display` and return its root window.
+fn x11_connect(display: &str) -> Result<(RustConnection, Window), String> {
+    let (conn, screen) = x11rb::connect(Some(display)).map_err(|e| e.to_string())?;
+    let root = conn.setup().roots[screen].root;
+    Ok((conn, root))
+}
+
+/// Read the 32-bit CARDINAL `atom` from the root window, `None` when the
+/// property is not set.
+fn x11_get_cardinal(display: &str, atom: &str) -> Result<Option<u32>, String> {
+    let (conn, root) = x11_connect(display)?;
+    let atom = conn
+        .intern_atom(true, atom.as_bytes())
+        .map_err(|e| e.to_string())?
+        .reply()
+        .map_err(|e| e.to_string())?
+        .atom;
+    if atom == u32::from(AtomEnum::NONE) {
+        // Never interned, so no window has it
+        return Ok(None);
+    }
+    let reply = conn
+        .get_property(false, root, atom, AtomEnum::CARDINAL, 0, 1)
+        .map_err(|e| e.to_string())?
+        .reply()
+        .map_err(|e| e.to_string())?;
+    Ok(reply.value32().and_then(|mut values| values.next()))
+}
+
+/// Set the 32-bit CARDINAL `atom` on the root window.
+fn x11_set_cardinal(display: &str, atom: &str, value: u32) -> Result<(), String> {
+    let (conn, root) = x11_connect(display)?;
+    let atom = conn
+        .intern_atom(false, atom.as_bytes())
+        .map_err(|e| e.to_string())?
+        .reply()
+        .map_err(|e| e.to_string())?
+        .atom;
+    let cookie = conn
+        .change_property32(PropMode::REPLACE, root, atom, AtomEnum::CARDINAL, &[value])
+        .map_err(|e| e.to_string())?;
+    cookie.check().map_err(|e| e.to_string())
+}
+
+/// Drives gamescope through the X atoms on its nested Xwayland display,
+/// the same properties Steam sets. Works without `gamescope-cmd`.
+#[derive(Debug)]
+pub struct GamescopeAtomBackend {
+    display: String,
+}
+
+impl Default for GamescopeAtomBackend {
+    fn default() -> Self {
+        Self {
+            display: std::env::var("DISPLAY").unwrap_or_else(|_| ":0".to_string()),
+        }
+    }
+}
+
+impl GamescopeAtomBackend {
+    /// Run a blocking X11 call off the runtime, mapping failures to a
+    /// `DisplayError`.
+    async fn x11<T, F>(&self, call: F) -> Result<T, DisplayError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&str) -> Result<T, String> + Send + 'static,
+    {
+        let display = self.display.clone();
+        let error = |reason: String| DisplayError::X11 { display: self.display.clone(), reason };
+        tokio::task::spawn_blocking(move || call(&display))
+            .await
+            .map_err(|e| error(e.to_string()))?
+            .map_err(error)
+    }
+
+    async fn get_atom(&self, atom: &'static str) -> Result<Option<u32>, DisplayError> {
+        self.x11(move |display| x11_get_cardinal(display, atom)).await
+    }
+
+    async fn set_atom(&self, atom: &'static str, value: u32) -> Result<(), DisplayError> {
+        self.x11(move |display| x11_set_cardinal(display, atom, value)).await
+    }
+}
+
+impl DisplayBackend for GamescopeAtomBackend {
+    fn kind(&self) -> BackendKind {
+        BackendKind::GamescopeAtom
+    }
+
+    /// The display must be reachable and be a gamescope Xwayland, which
+    /// sets the server ID atom on its root window.
+    fn probe(&self) -> BackendFuture<'_, bool> {
+        Box::pin(async move { Ok(matches!(self.get_atom(GAMESCOPE_SERVER_ATOM).await, Ok(Some(_)))) })
+    }
+
+    fn set_refresh_rate(&self, hz: Hertz) -> BackendFuture<'_, ()> {
+        Box::pin(async move { self.set_atom(GAMESCOPE_REFRESH_ATOM, hz.get()).await })
+    }
+
+    fn set_fps_limit(&self, fps: u32) -> BackendFuture<'_, ()> {
+        Box::pin(async move { self.set_atom(GAMESCOPE_FPS_LIMIT_ATOM, fps).await })
+    }
+
+    fn current_refresh_rate(&self) -> BackendFuture<'_, Option<Hertz>> {
+        Box::pin(async move {
+            let hz = self.get_atom(GAMESCOPE_REFRESH_ATOM).await?;
+            Ok(hz.filter(|&hz| hz > 0).map(Hertz))
+        })
+    }
+
+    fn compositor_id(&self) -> Option<u32> {
+        process_pid(Path::new("/proc"), &GAMESCOPE_PROCESSES)
+    }
+
+    fn vrr_capable(&self) -> BackendFuture<'_, bool> {
+        Box::pin(async move { Ok(self.get_atom(GAMESCOPE_VRR_CAPABLE_ATOM).await? == Some(1)) })
+    }
+
+    fn set_vrr(&self, enabled: bool) -> BackendFuture<'_, ()> {
+        Box::pin(async move { self.set_atom(GAMESCOPE_VRR_ENABLED_ATOM, enabled as u32).await })
+    }
+
+    fn overlay_open(&self) -> BackendFuture<'_, bool> {
+        Box::pin(async move { Ok(self.get_atom(GAMESCOPE_FOCUSED_APP_ATOM).await? == Some(STEAM_APP_ID)) })
+    }
+
+    fn focused_app(&self) -> BackendFuture<'_, Option<u32>> {
+        Box::pin(async move { Ok(self.get_atom(GAMESCOPE_FOCUSED_APP_ATOM).await?.filter(|&app| app != 0)) })
+    }
+}
+
+/// DRM class directory with one `card*-<connector>` entry per output
+const DRM_DIR: &str = "/sys/class/drm";
+
+/// SteamOS polkit helper writing whitelisted sysfs nodes as root
+const STEAMOS_PRIV_WRITE: &str = "/usr/bin/steamos-polkit-helpers/steamos-priv-write";
+
+/// Refresh rate node some SteamOS kernels add to the panel connector
+const REFRESH_NODE: &str = "refresh_rate";
+
+/// How the sysfs node is written.
+#[derive(Debug, Clone, PartialEq)]
+enum SysfsWrite {
+    Direct,
+    Helper(PathBuf),
+}
+
+/// Writes the panel connector's `refresh_rate` node, directly when the
+/// daemon may, otherwise through `steamos-priv-write`. Capabilities are
+/// probed once when the backend is created.
+#[derive(Debug)]
+pub struct SysfsBackend {
+    node: Option<PathBuf>,
+    write: Option<SysfsWrite>,
+}
+
+impl SysfsBackend {
+    /// Find the internal panel's refresh node under `drm_dir` and a way to write it.
+    pub fn discover(drm_dir: &Path, helper: &Path) -> Self {
+        let mut nodes: Vec<PathBuf> = std::fs::read_dir(drm_dir)
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .map(|e| e.path())
+                    .filter(|p| {
+                        p.file_name()
+                            .and_then(|n| n.to_str())
+                            .is_some_and(|n| n.starts_with("card") && (n.contains("-eDP-") || n.contains("-DSI-")))
+                    })
+                    .map(|connector| connector.join(REFRESH_NODE))
+                    .filter(|node| node.is_file())
+                    .collect()
+            })
+            .unwrap_or_default();
+        nodes.sort();
+        let node = nodes.into_iter().next();
+
+        let writable = node
+            .as_ref()
+            .is_some_and(|n| std::fs::OpenOptions::new().write(true).open(n).is_ok());
+        let write = match &node {
+            Some(_) if writable => Some(SysfsWrite::Direct),
+            Some(_) if is_executable(helper) => Some(SysfsWrite::Helper(helper.to_path_buf())),
+            _ => None,
+        };
+        tracing::debug!("Sysfs refresh node: {:?}, write: {:?}", node, write);
+        Self { node, write }
+    }
+}
+
+impl DisplayBackend for SysfsBackend {
+    fn kind(&self) -> BackendKind {
+        BackendKind::Sysfs
+    }
+
+    fn probe(&self) -> BackendFuture<'_, bool> {
+        Box::pin(async move { Ok(self.write.is_some()) })
+    }
+
+    fn set_refresh_rate(&self, hz: Hertz) -> BackendFuture<'_, ()> {
+        Box::pin(async move {
+            let (Some(node), Some(write)) = (&self.node, &self.write) else {
+                return Err(DisplayError::NoOutput("no writable panel refresh_rate node".to_string()));
+            };
+            match write {
+                SysfsWrite::Direct => tokio::fs::write(node, hz.to_string()).await.map_err(DisplayError::from),
+                SysfsWrite::Helper(helper) => {
+                    let program = helper.to_string_lossy();
+                    let mut command = Command::new(helper);
+                    command.arg(node).arg(hz.to_string());
+                    run_command(command, &program).await.map(drop)
+                }
+            }
+        })
+    }
+
+    fn current_refresh_rate(&self) -> BackendFuture<'_, Option<Hertz>> {
+        Box::pin(async move {
+            let Some(node) = &self.node else {
+                return Ok(None);
+            };
+            let raw = tokio::fs::read_to_string(node).await?;
+            Ok(raw.trim().parse::<f64>().ok().map(|hz| Hertz(hz.round() as u32)))
+        })
+    }
+
+    fn set_fps_limit(&self, _fps: u32) -> BackendFuture<'_, ()> {
+        Box::pin(async {
+            Err(DisplayError::Unsupported {
+                backend: BackendKind::Sysfs.as_str(),
+                operation: "a frame limiter",
+            })
+        })
+    }
+}
+
+/// One output in `wlr-randr --json`.
+#[derive(Debug, Deserialize)]
+struct WlrOutput {
+    name: String,
+    #[serde(default)]
+    enabled: bool,
+    #[serde(default)]
+    modes: Vec<WlrMode>,
+}
+
+#[derive(Debug, Deserialize)]
+struct WlrMode {
+    width: u32,
+    height: u32,
+    refresh: f64,
+    #[serde(default)]
+    current: bool,
+}
+
+impl WlrOutput {
+    /// Refresh rate of the current mode.
+    fn current_hz(&self) -> Option<Hertz> {
+        let current = self.modes.iter().find(|m| m.current)?;
+        Some(Hertz(current.refresh.round() as u32))
+    }
+
+    /// Lowest to highest listed rate at the current resolution; `None` with
+    /// a single rate, which says nothing about what custom modes reach.
+    fn refresh_range(&self) -> Option<RangeInclusive<Hertz>> {
+        let current = self.modes.iter().find(|m| m.current)?;
+        let rates = self
+            .modes
+            .iter()
+            .filter(|m| m.width == current.width && m.height == current.height)
+            .map(|m| Hertz(m.refresh.round() as u32));
+        let (min, max) = (rates.clone().min()?, rates.max()?);
+        (min < max).then_some(min..=max)
+    }
+
+    /// Arguments switching this output to `hz` at its current resolution:
+    /// a listed mode when one matches, otherwise a custom mode.
+    fn mode_args(&self, hz: Hertz) -> Option<Vec<String>> {
+        let current = self.modes.iter().find(|m| m.current)?;
+        let listed = self.modes.iter().find(|m| {
+            m.width == current.width && m.height == current.height && (m.refresh - hz.as_f64()).abs() < 0.5
+        });
+        let (flag, refresh) = match listed {
+            Some(mode) => ("--mode", mode.refresh),
+            None => ("--custom-mode", hz.as_f64()),
+        };
+        Some(vec![
+            "--output".to_string(),
+            self.name.clone(),
+            flag.to_string(),
+            format!("{}x{}@{:.3}Hz", current.width, current.height, refresh),
+        ])
+    }
+}
+
+/// The built-in panel if enabled, otherwise the first enabled output.
+fn pick_output(outputs: &[WlrOutput]) -> Option<&WlrOutput> {
+    let enabled = || outputs.iter().filter(|o| o.enabled);
+    enabled()
+        .find(|o| o.name.starts_with("eDP") || o.name.starts_with("DSI"))
+        .or_else(|| enabled().next())
+}
+
+/// Drives Wayland compositors implementing wlr-output-management (Plasma,
+/// Sway, Hyprland) through `wlr-randr`.
+#[derive(Debug)]
+pub struct WlrRandrBackend {
+    program: String,
+    /// Compositor socket; a system service has no WAYLAND_DISPLAY of its own
+    wayland_display: String,
+}
+
+impl Default for WlrRandrBackend {
+    fn default() -> Self {
+        Self {
+            program: "wlr-randr".to_string(),
+            wayland_display: std::env::var("WAYLAND_DISPLAY").unwrap_or_else(|_| "wayland-0".to_string()),
+        }
+    }
+}
+
+impl WlrRandrBackend {
+    fn command(&self) -> Command {
+        let mut command = Command::new(&self.program);
+        command.env("WAYLAND_DISPLAY", &self.wayland_display);
+        command
+    }
+
+    async fn outputs(&self) -> Result<Vec<WlrOutput>, DisplayError> {
+        let mut command = self.command();
+        command.arg("--json");
+        let stdout = run_command(command, &self.program).await?;
+        serde_json::from_slice(&stdout).map_err(|e| DisplayError::NoOutput(e.to_string()))
+    }
+}
+
+impl DisplayBackend for WlrRandrBackend {
+    fn kind(&self) -> BackendKind {
+        BackendKind::WlrRandr
+    }
+
+    fn probe(&self) -> BackendFuture<'_, bool> {
+        Box::pin(async move {
+            let path_var = std::env::var("PATH").unwrap_or_default();
+            if find_in_path(&self.program, &path_var).is_none() {
+                return Ok(false);
+            }
+            Ok(self.outputs().await.is_ok_and(|outputs| pick_output(&outputs).is_some()))
+        })
+    }
+
+    fn set_refresh_rate(&self, hz: Hertz) -> BackendFuture<'_, ()> {
+        Box::pin(async move {
+            let outputs = self.outputs().await?;
+            let args = pick_output(&outputs)
+                .and_then(|output| output.mode_args(hz))
+                .ok_or_else(|| DisplayError::NoOutput("no output with a current mode".to_string()))?;
+            let mut command = self.command();
+            command.args(&args);
+            run_command(command, &self.program).await.map(drop)
+        })
+    }
+
+    fn supports_outputs(&self) -> bool {
+        true
+    }
+
+    fn set_output_refresh_rate(&self, output: &str, hz: Hertz) -> BackendFuture<'_, ()> {
+        let output = output.to_string();
+        Box::pin(async move {
+            let outputs = self.outputs().await?;
+            let args = outputs
+                .iter()
+                .find(|o| o.enabled && o.name == output)
+                .and_then(|o| o.mode_args(hz))
+                .ok_or_else(|| DisplayError::NoOutput(format!("output {} has no current mode", output)))?;
+            let mut command = self.command();
+            command.args(&args);
+            run_command(command, &self.program).await.map(drop)
+        })
+    }
+
+    fn current_refresh_rate(&self) -> BackendFuture<'_, Option<Hertz>> {
+        Box::pin(async move {
+            let outputs = self.outputs().await?;
+            Ok(pick_output(&outputs).and_then(WlrOutput::current_hz))
+        })
+    }
+
+    fn refresh_range(&self) -> BackendFuture<'_, Option<RangeInclusive<Hertz>>> {
+        Box::pin(async move {
+            let outputs = self.outputs().await?;
+            Ok(pick_output(&outputs).and_then(WlrOutput::refresh_range))
+        })
+    }
+
+    fn set_fps_limit(&self, _fps: u32) -> BackendFuture<'_, ()> {
+        Box::pin(async {
+            Err(DisplayError::Unsupported {
+                backend: BackendKind::WlrRandr.as_str(),
+                operation: "a frame limiter",
+            })
+        })
+    }
+}
+
+/// Accepts every change without touching the display.
+#[derive(Debug, Default)]
+pub struct NoopBackend;
+
+impl DisplayBackend for NoopBackend {
+    fn kind(&self) -> BackendKind {
+        BackendKind::Noop
+    }
+
+    fn probe(&self) -> BackendFuture<'_, bool> {
+        Box::pin(async { Ok(true) })
+    }
+
+    fn set_refresh_rate(&self, hz: Hertz) -> BackendFuture<'_, ()> {
+        Box::pin(async move {
+            tracing::debug!("No display backend, ignoring switch to {}Hz", hz);
+            Ok(())
+        })
+    }
+
+    fn set_fps_limit(&self, fps: u32) -> BackendFuture<'_, ()> {
+        Box::pin(async move {
+            tracing::debug!("No display backend, ignoring frame limit {}", fps);
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_backend_kind_names() {
+        for kind in DETECTION_ORDER {
+            assert_eq!(BackendKind::parse(kind.as_str()), Some(kind));
+            assert_eq!(kind.create().kind(), kind);
+        }
+        assert_eq!(BackendKind::parse("GAMESCOPE_CMD"), Some(BackendKind::GamescopeCmd));
+        assert_eq!(BackendKind::parse("xrandr"), None);
+        assert_eq!(DETECTION_ORDER.last(), Some(&BackendKind::Noop));
+    }
+
+    #[tokio::test]
+    async fn test_only_wlr_randr_addresses_outputs() {
+        for kind in DETECTION_ORDER {
+            let backend = kind.create();
+            assert_eq!(backend.supports_outputs(), kind == BackendKind::WlrRandr, "{:?}", kind);
+            if !backend.supports_outputs() {
+                let result = backend.set_output_refresh_rate("DP-1", Hertz(60)).await;
+                assert!(matches!(result, Err(DisplayError::Unsupported { .. })), "{:?}", kind);
+            }
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_find_in_path() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempdir().unwrap();
+        let bin = dir.path().join("bin");
+        std::fs::create_dir(&bin).unwrap();
+        let tool = bin.join("gamescope-cmd");
+        std::fs::write(&tool, "#!/bin/sh\n").unwrap();
+        let path_var = format!("{}:{}", dir.path().display(), bin.display());
+
+        // Present but not executable
+        assert_eq!(find_in_path("gamescope-cmd", &path_var), None);
+
+        std::fs::set_permissions(&tool, std::fs::Permissions::from_mode(0o755)).unwrap();
+        assert_eq!(find_in_path("gamescope-cmd", &path_var), Some(tool));
+        assert_eq!(find_in_path("wlr-randr", &path_var), None);
+    }
+
+    #[test]
+    fn test_process_running() {
+        let dir = tempdir().unwrap();
+        for (pid, comm) in [("1", "systemd\n"), ("812", "gamescope-wl\n"), ("790", "gamescope\n")] {
+            std::fs::create_dir(dir.path().join(pid)).unwrap();
+            std::fs::write(dir.path().join(pid).join("comm"), comm).unwrap();
+        }
+        assert!(process_running(dir.path(), &GAMESCOPE_PROCESSES));
+        assert!(!process_running(dir.path(), &["kwin_wayland"]));
+        assert_eq!(process_pid(dir.path(), &GAMESCOPE_PROCESSES), Some(790));
+    }
+
+    #[tokio::test]
+    async fn test_gamescope_atom_backend_without_display() {
+        let backend = GamescopeAtomBackend { display: "/nonexistent/x11-socket:0".to_string() };
+        assert!(!backend.probe().await.unwrap());
+        let err = backend.set_refresh_rate(Hertz(60)).await.unwrap_err();
+        assert!(matches!(err, DisplayError::X11 { .. }));
+        assert!(err.is_transient());
+    }
+
+    #[tokio::test]
+    async fn test_sysfs_backend() {
+        let dir = tempdir().unwrap();
+        let helper = dir.path().join("steamos-priv-write");
+        assert!(!SysfsBackend::discover(dir.path(), &helper).probe().await.unwrap());
+
+        for connector in ["card0", "card0-DP-1", "card0-eDP-1"] {
+            std::fs::create_dir(dir.path().join(connector)).unwrap();
+        }
+        std::fs::write(dir.path().join("card0-DP-1").join(REFRESH_NODE), "60").unwrap();
+        let node = dir.path().join("card0-eDP-1").join(REFRESH_NODE);
+        std::fs::write(&node, "90\n").unwrap();
+
+        let backend = SysfsBackend::discover(dir.path(), &helper);
+        assert_eq!(backend.write, Some(SysfsWrite::Direct));
+        assert!(backend.probe().await.unwrap());
+        backend.set_refresh_rate(Hertz(60)).await.unwrap();
+        assert_eq!(std::fs::read_to_string(&node).unwrap(), "60");
+        assert_eq!(backend.current_refresh_rate().await.unwrap(), Some(Hertz(60)));
+        assert!(backend.set_fps_limit(60).await.is_err());
+    }
+
+    #[test]
+    fn test_wlr_randr_mode_args() {
+        let json = r#"[
+            {"name": "DP-1", "enabled": true, "modes": [
+                {"width": 1920, "height": 1080, "refresh": 60.0, "preferred": true, "current": true}
+            ]},
+            {"name": "eDP-1", "enabled": true, "modes": [
+                {"width": 800, "height": 1280, "refresh": 90.001, "preferred": true, "current": true},
+                {"width": 800, "height": 1280, "refresh": 60.0, "preferred": false, "current": false}
+            ]}
+        ]"#;
+        let outputs: Vec<WlrOutput> = serde_json::from_str(json).unwrap();
+        let panel = pick_output(&outputs).unwrap();
+        assert_eq!(panel.name, "eDP-1");
+        assert_eq!(panel.current_hz(), Some(Hertz(90)));
+        assert_eq!(panel.refresh_range(), Some(Hertz(60)..=Hertz(90)));
+
+        // Listed mode: its exact refresh is passed so wlr-randr matches it
+        assert_eq!(
+            panel.mode_args(Hertz(90)).unwrap(),
+            ["--output", "eDP-1", "--mode", "800x1280@90.001Hz"]
+        );
+        assert_eq!(
+            panel.mode_args(Hertz(55)).unwrap(),
+            ["--output", "eDP-1", "--custom-mode", "800x1280@55.000Hz"]
+        );
+
+        let external: Vec<WlrOutput> =
+            serde_json::from_str(r#"[{"name": "DP-1", "enabled": true, "modes": []}]"#).unwrap();
+        assert_eq!(pick_output(&external).unwrap().name, "DP-1");
+        assert!(pick_output(&external).unwrap().mode_args(Hertz(60)).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_select_falls_back_to_available_backend() {
+        let backend = select(Some(BackendKind::Noop)).await;
+        assert_eq!(backend.kind(), BackendKind::Noop);
+        assert!(backend.set_refresh_rate(Hertz(60)).await.is_ok());
+
+        // Auto-detection always ends on an available backend
+        let backend = select(None).await;
+        assert!(backend.probe().await.unwrap());
+    }
+}
@@ -1,101 +1,396 @@
-//! Display Control module for managing refresh rate via Gamescope.
+//! Display Control module for managing refresh rate.
 //!
-//! This module handles refresh rate changes through gamescope-cmd execution.
+//! This module tracks the current rate and range and applies changes through
+//! the selected `DisplayBackend` (gamescope-cmd by default).
 //! v2.0.1: Added Gamescope frame limiter sync for perfect frame pacing.
 
+use crate::core_logic::nearest_allowed;
+use crate::display_backend::{BackendKind, DisplayBackend, GamescopeCmdBackend};
 use crate::error::DisplayError;
+use crate::units::{Hertz, Millis, Secs};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::ops::RangeInclusive;
 use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
-use std::sync::Mutex;
-use std::time::Instant;
-use tokio::process::Command;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
 
-/// Minimum allowed refresh rate in Hz.
-pub const MIN_ALLOWED_HZ: u32 = 40;
+/// Minimum refresh rate in Hz when the panel's range is unknown.
+pub const MIN_ALLOWED_HZ: Hertz = Hertz(40);
 
-/// Maximum allowed refresh rate in Hz.
-pub const MAX_ALLOWED_HZ: u32 = 90;
+/// Maximum refresh rate in Hz when the panel's range is unknown.
+pub const MAX_ALLOWED_HZ: Hertz = Hertz(90);
 
-/// Manages display refresh rate through Gamescope commands.
+/// Where the panel's refresh range came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum HzRangeSource {
+    /// The display backend's mode list
+    Backend,
+    /// The panel's EDID range limits
+    Edid,
+    /// The panel's DRM mode list
+    Drm,
+    /// The detected handheld model's preset
+    Preset,
+    /// The controlled external output's DRM mode list
+    Output,
+    /// `MIN_ALLOWED_HZ`-`MAX_ALLOWED_HZ`
+    #[default]
+    Fallback,
+}
+
+/// Rate the daemon puts the panel at when it starts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StartupRate {
+    /// Configured max Hz
+    #[default]
+    Max,
+    /// The rate the previous session ended at (max Hz if unknown)
+    Restore,
+    /// Leave the panel alone until the first decision
+    Untouched,
+}
+
+/// Attempts per backend call for transient failures
+const RETRY_ATTEMPTS: u32 = 3;
+
+/// Delay before the first retry, doubled for each further one
+const RETRY_BASE_DELAY: Millis = Millis(100);
+
+/// Consecutive failed calls that open the circuit breaker
+const BREAKER_THRESHOLD: u32 = 3;
+
+/// How long backend calls are refused once the breaker opens
+const BREAKER_COOLDOWN: Secs = Secs(30);
+
+/// Readings this soon after our own change may predate it
+const OWN_CHANGE_SETTLE: Millis = Millis(2000);
+
+/// Circuit breaker over backend calls.
+///
+/// After `BREAKER_THRESHOLD` consecutive failures calls are refused for
+/// `BREAKER_COOLDOWN`; the next call after that is a trial, and the breaker
+/// closes on its success or reopens on its failure.
+#[derive(Debug, Default)]
+struct Breaker {
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+}
+
+impl Breaker {
+    fn check(&self, now: Instant) -> Result<(), DisplayError> {
+        match self.open_until {
+            Some(until) if now < until => Err(DisplayError::BackendUnhealthy {
+                retry_in_secs: (until - now).as_secs_f64().ceil() as u64,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    fn record(&mut self, succeeded: bool, now: Instant) {
+        if succeeded {
+            if self.open_until.is_some() {
+                tracing::info!("Display backend recovered, switching resumed");
+            }
+            *self = Self::default();
+            return;
+        }
+        self.consecutive_failures += 1;
+        if self.consecutive_failures >= BREAKER_THRESHOLD {
+            if self.open_until.is_none() {
+                tracing::warn!(
+                    "Display backend failed {} times in a row, pausing switching for {}s",
+                    self.consecutive_failures,
+                    BREAKER_COOLDOWN
+                );
+            }
+            self.open_until = Some(now + Duration::from(BREAKER_COOLDOWN));
+        }
+    }
+}
+
+/// VRR mode state reported in status.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+pub struct VrrStatus {
+    /// VRR mode is requested (vrr_window experiment)
+    pub requested: bool,
+    /// The backend reports a VRR-capable panel
+    pub capable: bool,
+    /// VRR is on and the daemon only drives the frame limiter
+    pub active: bool,
+    /// Frame limit applied in VRR mode
+    pub fps_limit: Option<u32>,
+}
+
+/// Manages display refresh rate through the selected backend.
 pub struct DisplayManager {
+    /// Backend that applies rates and frame limits
+    backend: RwLock<Arc<dyn DisplayBackend>>,
     /// Current refresh rate in Hz (atomic for thread-safe reads).
     current_hz: AtomicU32,
     /// Minimum allowed refresh rate.
     min_hz: AtomicU32,
     /// Maximum allowed refresh rate.
     max_hz: AtomicU32,
+    /// Lowest rate the panel supports
+    panel_min_hz: AtomicU32,
+    /// Highest rate the panel supports
+    panel_max_hz: AtomicU32,
+    /// Where the panel range came from
+    panel_range_source: Mutex<HzRangeSource>,
     /// Timestamp of the last refresh rate change.
     last_change: Mutex<Instant>,
     /// Whether to sync frame limiter with refresh rate
     sync_frame_limiter: AtomicBool,
     /// Current frame limiter value
     current_fps_limit: AtomicU32,
+    /// Explicit allowed rates; empty allows any rate in range
+    allowed_hz: Mutex<Vec<Hertz>>,
+    /// VRR mode state
+    vrr: Mutex<VrrStatus>,
+    /// Circuit breaker over backend calls
+    breaker: Mutex<Breaker>,
+    /// Last rate read back from the backend
+    observed_hz: Mutex<Option<Hertz>>,
+    /// Panel rate before the daemon first changed it
+    original_hz: Mutex<Option<Hertz>>,
+    /// Last compositor instance seen, to detect restarts
+    compositor_id: Mutex<Option<u32>>,
+    /// External output being controlled instead of the panel
+    active_output: Mutex<Option<String>>,
 }
 
 impl DisplayManager {
     /// Create a new DisplayManager with the specified Hz range.
     ///
+    /// Until `set_panel_range` is called the panel is assumed to support
+    /// `MIN_ALLOWED_HZ`-`MAX_ALLOWED_HZ`.
+    ///
     /// # Arguments
-    /// * `min_hz` - Minimum refresh rate (clamped to 40-90 range)
-    /// * `max_hz` - Maximum refresh rate (clamped to 40-90 range)
-    pub fn new(min_hz: u32, max_hz: u32) -> Self {
-        let clamped_min = min_hz.clamp(MIN_ALLOWED_HZ, MAX_ALLOWED_HZ);
-        let clamped_max = max_hz.clamp(MIN_ALLOWED_HZ, MAX_ALLOWED_HZ);
-
-        // Ensure min <= max
-        let (final_min, final_max) = if clamped_min > clamped_max {
-            (clamped_max, clamped_min)
-        } else {
-            (clamped_min, clamped_max)
-        };
-
-        Self {
-            current_hz: AtomicU32::new(final_max), // Start at max Hz
-            min_hz: AtomicU32::new(final_min),
-            max_hz: AtomicU32::new(final_max),
+    /// * `min_hz` - Minimum refresh rate (clamped to the panel range)
+    /// * `max_hz` - Maximum refresh rate (clamped to the panel range)
+    pub fn new(min_hz: Hertz, max_hz: Hertz) -> Self {
+        let manager = Self {
+            backend: RwLock::new(Arc::new(GamescopeCmdBackend::default())),
+            current_hz: AtomicU32::new(0),
+            min_hz: AtomicU32::new(0),
+            max_hz: AtomicU32::new(0),
+            panel_min_hz: AtomicU32::new(MIN_ALLOWED_HZ.get()),
+            panel_max_hz: AtomicU32::new(MAX_ALLOWED_HZ.get()),
+            panel_range_source: Mutex::new(HzRangeSource::Fallback),
             last_change: Mutex::new(Instant::now()),
             sync_frame_limiter: AtomicBool::new(false),
             current_fps_limit: AtomicU32::new(0), // 0 = no limit
+            allowed_hz: Mutex::new(Vec::new()),
+            vrr: Mutex::new(VrrStatus::default()),
+            breaker: Mutex::new(Breaker::default()),
+            observed_hz: Mutex::new(None),
+            original_hz: Mutex::new(None),
+            compositor_id: Mutex::new(None),
+            active_output: Mutex::new(None),
+        };
+        manager.set_range(min_hz, max_hz);
+        // Start at max Hz
+        manager.current_hz.store(manager.max_hz.load(Ordering::Relaxed), Ordering::Relaxed);
+        manager
+    }
+
+    /// Set the range the panel supports; the configured range is clamped to it.
+    pub fn set_panel_range(&self, range: RangeInclusive<Hertz>, source: HzRangeSource) {
+        let (min, max) = (self.get_min_hz(), self.get_max_hz());
+        self.panel_min_hz.store(range.start().get(), Ordering::Relaxed);
+        self.panel_max_hz.store(range.end().get(), Ordering::Relaxed);
+        if let Ok(mut current) = self.panel_range_source.lock() {
+            *current = source;
+        }
+        self.set_range(min, max);
+    }
+
+    /// Range the panel supports.
+    pub fn panel_range(&self) -> RangeInclusive<Hertz> {
+        Hertz(self.panel_min_hz.load(Ordering::Relaxed))..=Hertz(self.panel_max_hz.load(Ordering::Relaxed))
+    }
+
+    /// Where the panel range came from.
+    pub fn panel_range_source(&self) -> HzRangeSource {
+        self.panel_range_source.lock().map(|source| *source).unwrap_or_default()
+    }
+
+    /// Refresh range reported by the backend, if it knows one.
+    pub async fn backend_refresh_range(&self) -> Option<RangeInclusive<Hertz>> {
+        match self.backend().refresh_range().await {
+            Ok(range) => range,
+            Err(e) => {
+                tracing::debug!("Display backend has no refresh range: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Switch to another backend.
+    pub fn set_backend(&self, backend: Arc<dyn DisplayBackend>) {
+        if let Ok(mut current) = self.backend.write() {
+            tracing::info!("Display backend: {}", backend.kind().as_str());
+            *current = backend;
+        }
+        // Capability and health belong to the old backend
+        if let Ok(mut vrr) = self.vrr.lock() {
+            *vrr = VrrStatus::default();
+        }
+        if let Ok(mut breaker) = self.breaker.lock() {
+            *breaker = Breaker::default();
+        }
+        if let Ok(mut observed) = self.observed_hz.lock() {
+            *observed = None;
+        }
+        if let Ok(mut compositor) = self.compositor_id.lock() {
+            *compositor = None;
+        }
+    }
+
+    /// Control the external output named `output` (e.g. "DP-1") instead of
+    /// the panel, or the panel again with `None`.
+    pub fn set_active_output(&self, output: Option<String>) {
+        if let Ok(mut current) = self.active_output.lock() {
+            *current = output;
+        }
+    }
+
+    /// Whether the backend can switch external outputs.
+    pub fn supports_outputs(&self) -> bool {
+        self.backend().supports_outputs()
+    }
+
+    /// External output being controlled, `None` for the panel.
+    pub fn active_output(&self) -> Option<String> {
+        self.active_output.lock().ok().and_then(|output| output.clone())
+    }
+
+    /// Kind of the backend in use.
+    pub fn backend_kind(&self) -> BackendKind {
+        self.backend().kind()
+    }
+
+    fn backend(&self) -> Arc<dyn DisplayBackend> {
+        self.backend
+            .read()
+            .map(|backend| Arc::clone(&backend))
+            .unwrap_or_else(|e| Arc::clone(&e.into_inner()))
+    }
+
+    /// Whether the circuit breaker is open after repeated backend failures.
+    pub fn is_backend_unhealthy(&self) -> bool {
+        self.breaker
+            .lock()
+            .map(|breaker| breaker.open_until.is_some())
+            .unwrap_or(false)
+    }
+
+    /// Run a backend call through the circuit breaker, retrying transient
+    /// failures with exponential backoff.
+    ///
+    /// Unsupported operations don't count as failures.
+    async fn call_backend<F, Fut>(&self, call: F) -> Result<(), DisplayError>
+    where
+        F: Fn(Arc<dyn DisplayBackend>) -> Fut,
+        Fut: Future<Output = Result<(), DisplayError>>,
+    {
+        if let Ok(breaker) = self.breaker.lock() {
+            breaker.check(Instant::now())?;
+        }
+
+        let mut delay = Duration::from(RETRY_BASE_DELAY);
+        let mut attempt = 1;
+        let result = loop {
+            match call(self.backend()).await {
+                Err(e) if e.is_transient() && attempt < RETRY_ATTEMPTS => {
+                    tracing::debug!("Display backend call failed (attempt {}): {}", attempt, e);
+                    tokio::time::sleep(delay).await;
+                    delay *= 2;
+                    attempt += 1;
+                }
+                result => break result,
+            }
+        };
+
+        if !matches!(result, Err(DisplayError::Unsupported { .. })) {
+            if let Ok(mut breaker) = self.breaker.lock() {
+                breaker.record(result.is_ok(), Instant::now());
+            }
         }
+        result
+    }
+
+    /// Switch the active output (the panel unless an external output is
+    /// controlled) to `hz` through `call_backend`.
+    async fn call_set_rate(&self, hz: Hertz) -> Result<(), DisplayError> {
+        let output = self.active_output();
+        let output = output.as_deref();
+        self.call_backend(|backend| async move {
+            match output {
+                Some(output) => backend.set_output_refresh_rate(output, hz).await,
+                None => backend.set_refresh_rate(hz).await,
+            }
+        })
+        .await
     }
 
     /// Clamp a refresh rate value to the configured [min_hz, max_hz] range.
     ///
     /// Values below min_hz become min_hz, values above max_hz become max_hz.
-    pub fn clamp_hz(&self, hz: u32) -> u32 {
-        let min = self.min_hz.load(Ordering::Relaxed);
-        let max = self.max_hz.load(Ordering::Relaxed);
-        hz.clamp(min, max)
+    /// With an allowed set, the result snaps to the nearest allowed rate in range.
+    pub fn clamp_hz(&self, hz: Hertz) -> Hertz {
+        let min = self.get_min_hz();
+        let max = self.get_max_hz();
+        let clamped = hz.clamp(min, max);
+        self.allowed_hz
+            .lock()
+            .ok()
+            .and_then(|allowed| nearest_allowed(clamped, &allowed, min, max))
+            .unwrap_or(clamped)
+    }
+
+    /// Restrict rates to an explicit set (empty allows any rate in range).
+    pub fn set_allowed_hz(&self, allowed: &[Hertz]) {
+        if let Ok(mut current) = self.allowed_hz.lock() {
+            if current.as_slice() != allowed {
+                *current = allowed.to_vec();
+            }
+        }
     }
 
-    /// Set refresh rate via gamescope-cmd.
+    /// Set refresh rate through the backend.
     ///
     /// Returns Ok(true) if rate was changed, Ok(false) if already at target.
     /// If sync_frame_limiter is enabled, also sets the FPS limit to match.
     ///
     /// # Arguments
     /// * `hz` - Target refresh rate (will be clamped to configured range)
-    pub async fn set_refresh_rate(&self, hz: u32) -> Result<bool, DisplayError> {
+    pub async fn set_refresh_rate(&self, hz: Hertz) -> Result<bool, DisplayError> {
         let clamped_hz = self.clamp_hz(hz);
-        let current = self.current_hz.load(Ordering::Relaxed);
+        let current = self.get_current_hz();
 
         // Skip execution if rate unchanged (Requirement 2.4)
         if clamped_hz == current {
             return Ok(false);
         }
 
-        // Execute gamescope-cmd for refresh rate
-        self.execute_gamescope_cmd(clamped_hz).await?;
+        self.call_set_rate(clamped_hz).await?;
 
         // If frame limiter sync is enabled, set FPS limit to match Hz
         if self.sync_frame_limiter.load(Ordering::Relaxed) {
-            if let Err(e) = self.set_fps_limit(clamped_hz).await {
-                tracing::warn!("Failed to sync frame limiter: {}", e);
-                // Don't fail the whole operation if frame limiter fails
+            // Don't fail the whole operation if frame limiter fails
+            match self.set_fps_limit(clamped_hz.get()).await {
+                Ok(()) => {}
+                Err(e @ DisplayError::Unsupported { .. }) => tracing::debug!("Frame limiter sync skipped: {}", e),
+                Err(e) => tracing::warn!("Failed to sync frame limiter: {}", e),
             }
         }
 
         // Update current Hz and timestamp
-        self.current_hz.store(clamped_hz, Ordering::Relaxed);
+        self.current_hz.store(clamped_hz.get(), Ordering::Relaxed);
         if let Ok(mut last_change) = self.last_change.lock() {
             *last_change = Instant::now();
         }
@@ -103,31 +398,153 @@ impl DisplayManager {
         Ok(true)
     }
 
-    /// Set Gamescope FPS limit for perfect frame pacing.
-    /// 
-    /// When Hz is set to 45, setting FPS limit to 45 ensures 1:1 frame pacing
-    /// without tearing or stuttering.
-    pub async fn set_fps_limit(&self, fps: u32) -> Result<(), DisplayError> {
-        let output = Command::new("gamescope-cmd")
-            .arg("-F")
-            .arg(fps.to_string())
-            .output()
-            .await
-            .map_err(|e| {
-                if e.kind() == std::io::ErrorKind::NotFound {
-                    DisplayError::CommandNotFound
-                } else {
-                    DisplayError::ExecutionFailed(e)
+    /// Whether the compositor restarted since the last check. A compositor
+    /// that is gone counts once a new instance appears.
+    pub fn detect_compositor_restart(&self) -> bool {
+        let Some(id) = self.backend().compositor_id() else {
+            return false;
+        };
+        let Ok(mut last) = self.compositor_id.lock() else {
+            return false;
+        };
+        last.replace(id).is_some_and(|previous| previous != id)
+    }
+
+    /// Push the current rate, frame limit and VRR state to the backend
+    /// again, after the compositor restarted with its defaults.
+    pub async fn reapply_state(&self) -> Result<(), DisplayError> {
+        let hz = self.get_current_hz();
+        self.call_set_rate(hz).await?;
+
+        let limit = self.get_current_fps_limit();
+        if limit > 0 {
+            match self.set_fps_limit(limit).await {
+                Ok(()) | Err(DisplayError::Unsupported { .. }) => {}
+                Err(e) => tracing::warn!("Failed to reapply the frame limit: {}", e),
+            }
+        }
+        if self.vrr_status().active {
+            if let Err(e) = self.backend().set_vrr(true).await {
+                tracing::warn!("Failed to reapply VRR: {}", e);
+            }
+        }
+
+        // The new instance's reading is a baseline, not an outside change
+        if let Ok(mut observed) = self.observed_hz.lock() {
+            *observed = None;
+        }
+        Ok(())
+    }
+
+    /// Remember the panel's current rate so it can be restored on exit. Only
+    /// the first successful reading is kept.
+    pub async fn capture_original_rate(&self) -> Option<Hertz> {
+        if let Some(hz) = self.original_hz.lock().ok().and_then(|original| *original) {
+            return Some(hz);
+        }
+        let reading = match self.backend().current_refresh_rate().await {
+            Ok(reading) => reading?,
+            Err(e) => {
+                tracing::debug!("Failed to read the original refresh rate: {}", e);
+                return None;
+            }
+        };
+        if let Ok(mut original) = self.original_hz.lock() {
+            *original = Some(reading);
+        }
+        Some(reading)
+    }
+
+    /// Put the panel at its startup rate. `last_session` is the rate the
+    /// previous session ended at. With `Untouched` nothing is set and the
+    /// current rate is taken from the original reading, if any. Returns the
+    /// rate the panel is now known to be at.
+    pub async fn apply_startup_rate(
+        &self,
+        behavior: StartupRate,
+        last_session: Option<Hertz>,
+    ) -> Result<Option<Hertz>, DisplayError> {
+        let target = match behavior {
+            StartupRate::Max => self.get_max_hz(),
+            StartupRate::Restore => last_session.unwrap_or_else(|| self.get_max_hz()),
+            StartupRate::Untouched => {
+                let original = self.original_hz.lock().ok().and_then(|original| *original);
+                if let Some(hz) = original {
+                    self.current_hz.store(hz.get(), Ordering::Relaxed);
                 }
-            })?;
+                return Ok(original);
+            }
+        };
+        // The current rate is only assumed at this point, so always set it
+        let hz = self.clamp_hz(target);
+        self.call_set_rate(hz).await?;
+        self.current_hz.store(hz.get(), Ordering::Relaxed);
+        Ok(Some(hz))
+    }
+
+    /// Rate to leave the panel at on exit: the captured original when the
+    /// panel supports it, else `fallback`.
+    pub fn exit_rate(&self, fallback: Hertz) -> Hertz {
+        let panel = self.panel_range();
+        self.original_hz
+            .lock()
+            .ok()
+            .and_then(|original| *original)
+            .filter(|hz| panel.contains(hz))
+            .unwrap_or(fallback)
+            .clamp(*panel.start(), *panel.end())
+    }
 
-        if !output.status.success() {
-            return Err(DisplayError::CommandFailed {
-                exit_code: output.status.code(),
-                stderr: String::from_utf8_lossy(&output.stderr).to_string(),
-            });
+    /// Set the exit rate. Unlike `set_refresh_rate` this ignores the
+    /// configured range, the circuit breaker and the cached current rate.
+    /// A synced or temporary frame limit is cleared too, so the game isn't
+    /// left capped at the last rate.
+    pub async fn restore_refresh_rate(&self, fallback: Hertz) -> Result<Hertz, DisplayError> {
+        let hz = self.exit_rate(fallback);
+        let result = self.backend().set_refresh_rate(hz).await;
+        if self.is_sync_frame_limiter_enabled() || self.get_current_fps_limit() != 0 {
+            if let Err(e) = self.clear_fps_limit().await {
+                tracing::warn!("Failed to clear the frame limit on exit: {}", e);
+            }
         }
+        result?;
+        self.current_hz.store(hz.get(), Ordering::Relaxed);
+        Ok(hz)
+    }
 
+    /// Read the panel's rate back and detect a change made outside the daemon,
+    /// e.g. from the Steam QAM slider.
+    ///
+    /// A change counts only when the reading itself moves to a rate other
+    /// than the one we set, so backends whose reading doesn't follow our own
+    /// changes (the gamescope atom under gamescope-cmd) don't trigger it.
+    /// On a change the current rate is resynced and returned.
+    pub async fn detect_external_change(&self) -> Option<Hertz> {
+        let reading = match self.backend().current_refresh_rate().await {
+            Ok(reading) => reading?,
+            Err(e) => {
+                tracing::debug!("Failed to read the current refresh rate: {}", e);
+                return None;
+            }
+        };
+        let previous = self.observed_hz.lock().ok()?.replace(reading);
+
+        let settled = self.get_last_change().elapsed() >= Duration::from(OWN_CHANGE_SETTLE);
+        let current = self.get_current_hz();
+        if previous.is_none_or(|p| p == reading) || reading == current || !settled {
+            return None;
+        }
+        self.current_hz.store(reading.get(), Ordering::Relaxed);
+        Some(reading)
+    }
+
+    /// Set the frame limiter for perfect frame pacing.
+    /// 
+    /// When Hz is set to 45, setting FPS limit to 45 ensures 1:1 frame pacing
+    /// without tearing or stuttering.
+    pub async fn set_fps_limit(&self, fps: u32) -> Result<(), DisplayError> {
+        self.call_backend(|backend| async move { backend.set_fps_limit(fps).await })
+            .await?;
         self.current_fps_limit.store(fps, Ordering::Relaxed);
         tracing::debug!("Frame limiter set to {} FPS", fps);
         Ok(())
@@ -135,34 +552,123 @@ impl DisplayManager {
 
     /// Clear FPS limit (set to 0 / unlimited)
     pub async fn clear_fps_limit(&self) -> Result<(), DisplayError> {
-        let output = Command::new("gamescope-cmd")
-            .arg("-F")
-            .arg("0")
-            .output()
-            .await
-            .map_err(|e| {
-                if e.kind() == std::io::ErrorKind::NotFound {
-                    DisplayError::CommandNotFound
-                } else {
-                    DisplayError::ExecutionFailed(e)
+        self.call_backend(|backend| async move { backend.set_fps_limit(0).await })
+            .await?;
+        self.current_fps_limit.store(0, Ordering::Relaxed);
+        tracing::debug!("Frame limiter cleared");
+        Ok(())
+    }
+
+    /// Current VRR mode state.
+    pub fn vrr_status(&self) -> VrrStatus {
+        let mut status = self.vrr.lock().map(|vrr| *vrr).unwrap_or_default();
+        let limit = self.get_current_fps_limit();
+        status.fps_limit = (status.active && limit > 0).then_some(limit);
+        status
+    }
+
+    /// Enter or leave VRR mode.
+    ///
+    /// Only acts when `requested` changes. Enabling probes the backend and
+    /// falls back to rate switching when the panel is not VRR-capable;
+    /// disabling turns VRR off and clears the frame limit.
+    pub async fn sync_vrr(&self, requested: bool) -> VrrStatus {
+        let current = self.vrr_status();
+        if current.requested == requested {
+            return current;
+        }
+
+        let backend = self.backend();
+        let mut next = VrrStatus { requested, ..VrrStatus::default() };
+        if requested {
+            next.capable = match backend.vrr_capable().await {
+                Ok(capable) => capable,
+                Err(e) => {
+                    tracing::warn!("VRR capability probe failed: {}", e);
+                    false
                 }
-            })?;
+            };
+            if next.capable {
+                match backend.set_vrr(true).await {
+                    Ok(()) => {
+                        tracing::info!("VRR enabled, driving the frame limiter only");
+                        next.active = true;
+                    }
+                    Err(e) => tracing::warn!("Failed to enable VRR: {}", e),
+                }
+            }
+            if !next.active {
+                tracing::info!("VRR unavailable, using refresh rate switching");
+            }
+        } else if current.active {
+            if let Err(e) = backend.set_vrr(false).await {
+                tracing::warn!("Failed to disable VRR: {}", e);
+            }
+            if let Err(e) = self.release_fps_limit().await {
+                tracing::warn!("Failed to release VRR frame limit: {}", e);
+            }
+            tracing::info!("VRR disabled, using refresh rate switching");
+        }
 
-        if !output.status.success() {
-            return Err(DisplayError::CommandFailed {
-                exit_code: output.status.code(),
-                stderr: String::from_utf8_lossy(&output.stderr).to_string(),
-            });
+        if let Ok(mut vrr) = self.vrr.lock() {
+            *vrr = next;
         }
+        next
+    }
 
-        self.current_fps_limit.store(0, Ordering::Relaxed);
-        tracing::debug!("Frame limiter cleared");
-        Ok(())
+    /// Whether the Steam overlay is open, as far as the backend can tell.
+    /// Read failures count as closed so they never hold the controller.
+    pub async fn overlay_open(&self) -> bool {
+        match self.backend().overlay_open().await {
+            Ok(open) => open,
+            Err(e) => {
+                tracing::debug!("Overlay state unavailable: {}", e);
+                false
+            }
+        }
     }
 
-    /// Enable or disable frame limiter sync
-    pub fn set_sync_frame_limiter(&self, enabled: bool) {
-        self.sync_frame_limiter.store(enabled, Ordering::Relaxed);
+    /// Steam app ID of the focused window; read failures count as unknown.
+    pub async fn focused_app(&self) -> Option<u32> {
+        match self.backend().focused_app().await {
+            Ok(app) => app,
+            Err(e) => {
+                tracing::debug!("Focused app unavailable: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Release a temporary frame limit (low FPS compensation, VRR): back to
+    /// the current rate with frame limiter sync, otherwise unlimited.
+    pub async fn release_fps_limit(&self) -> Result<(), DisplayError> {
+        if self.sync_frame_limiter.load(Ordering::Relaxed) {
+            self.set_fps_limit(self.get_current_hz().get()).await
+        } else {
+            self.clear_fps_limit().await
+        }
+    }
+
+    /// Enable or disable frame limiter sync.
+    ///
+    /// Enabling caps FPS at the current rate right away; disabling removes
+    /// the cap if it is still the synced one.
+    pub async fn set_sync_frame_limiter(&self, enabled: bool) {
+        if self.sync_frame_limiter.swap(enabled, Ordering::Relaxed) == enabled {
+            return;
+        }
+        let hz = self.get_current_hz();
+        let result = if enabled {
+            self.set_fps_limit(hz.get()).await
+        } else if self.get_current_fps_limit() == hz.get() {
+            self.clear_fps_limit().await
+        } else {
+            Ok(())
+        };
+        match result {
+            Ok(()) => tracing::info!("Frame limiter sync {}", if enabled { "enabled" } else { "disabled" }),
+            Err(e) => tracing::warn!("Failed to update frame limiter for sync: {}", e),
+        }
     }
 
     /// Check if frame limiter sync is enabled
@@ -175,44 +681,19 @@ impl DisplayManager {
         self.current_fps_limit.load(Ordering::Relaxed)
     }
 
-    /// Execute the gamescope-cmd command to change refresh rate.
-    async fn execute_gamescope_cmd(&self, hz: u32) -> Result<(), DisplayError> {
-        let output = Command::new("gamescope-cmd")
-            .arg("-r")
-            .arg(hz.to_string())
-            .output()
-            .await
-            .map_err(|e| {
-                if e.kind() == std::io::ErrorKind::NotFound {
-                    DisplayError::CommandNotFound
-                } else {
-                    DisplayError::ExecutionFailed(e)
-                }
-            })?;
-
-        if !output.status.success() {
-            return Err(DisplayError::CommandFailed {
-                exit_code: output.status.code(),
-                stderr: String::from_utf8_lossy(&output.stderr).to_string(),
-            });
-        }
-
-        Ok(())
-    }
-
     /// Get current refresh rate.
-    pub fn get_current_hz(&self) -> u32 {
-        self.current_hz.load(Ordering::Relaxed)
+    pub fn get_current_hz(&self) -> Hertz {
+        Hertz(self.current_hz.load(Ordering::Relaxed))
     }
 
     /// Get the minimum configured refresh rate.
-    pub fn get_min_hz(&self) -> u32 {
-        self.min_hz.load(Ordering::Relaxed)
+    pub fn get_min_hz(&self) -> Hertz {
+        Hertz(self.min_hz.load(Ordering::Relaxed))
     }
 
     /// Get the maximum configured refresh rate.
-    pub fn get_max_hz(&self) -> u32 {
-        self.max_hz.load(Ordering::Relaxed)
+    pub fn get_max_hz(&self) -> Hertz {
+        Hertz(self.max_hz.load(Ordering::Relaxed))
     }
 
     /// Update min/max range.
@@ -220,9 +701,10 @@ impl DisplayManager {
     /// # Arguments
     /// * `min` - New minimum refresh rate
     /// * `max` - New maximum refresh rate
-    pub fn set_range(&self, min: u32, max: u32) {
-        let clamped_min = min.clamp(MIN_ALLOWED_HZ, MAX_ALLOWED_HZ);
-        let clamped_max = max.clamp(MIN_ALLOWED_HZ, MAX_ALLOWED_HZ);
+    pub fn set_range(&self, min: Hertz, max: Hertz) {
+        let panel = self.panel_range();
+        let clamped_min = min.clamp(*panel.start(), *panel.end());
+        let clamped_max = max.clamp(*panel.start(), *panel.end());
 
         // Ensure min <= max
         let (final_min, final_max) = if clamped_min > clamped_max {
@@ -231,8 +713,8 @@ impl DisplayManager {
             (clamped_min, clamped_max)
         };
 
-        self.min_hz.store(final_min, Ordering::Relaxed);
-        self.max_hz.store(final_max, Ordering::Relaxed);
+        self.min_hz.store(final_min.get(), Ordering::Relaxed);
+        self.max_hz.store(final_max.get(), Ordering::Relaxed);
     }
 
     /// Get the timestamp of the last refresh rate change.
@@ -265,6 +747,8 @@ mod tests {
             } else {
                 (min_hz, max_hz)
             };
+            let (actual_min, actual_max) = (Hertz(actual_min), Hertz(actual_max));
+            let requested_hz = Hertz(requested_hz);
 
             let manager = DisplayManager::new(actual_min, actual_max);
             let clamped = manager.clamp_hz(requested_hz);
@@ -292,59 +776,403 @@ mod tests {
 
     #[test]
     fn test_display_manager_creation() {
-        let manager = DisplayManager::new(40, 90);
-        assert_eq!(manager.get_min_hz(), 40);
-        assert_eq!(manager.get_max_hz(), 90);
-        assert_eq!(manager.get_current_hz(), 90); // Starts at max
+        let manager = DisplayManager::new(Hertz(40), Hertz(90));
+        assert_eq!(manager.get_min_hz(), Hertz(40));
+        assert_eq!(manager.get_max_hz(), Hertz(90));
+        assert_eq!(manager.get_current_hz(), Hertz(90)); // Starts at max
     }
 
     #[test]
     fn test_display_manager_swapped_range() {
         // If min > max, they should be swapped
-        let manager = DisplayManager::new(90, 40);
-        assert_eq!(manager.get_min_hz(), 40);
-        assert_eq!(manager.get_max_hz(), 90);
+        let manager = DisplayManager::new(Hertz(90), Hertz(40));
+        assert_eq!(manager.get_min_hz(), Hertz(40));
+        assert_eq!(manager.get_max_hz(), Hertz(90));
     }
 
     #[test]
     fn test_display_manager_clamped_range() {
-        // Values outside 40-90 should be clamped
-        let manager = DisplayManager::new(20, 120);
-        assert_eq!(manager.get_min_hz(), 40);
-        assert_eq!(manager.get_max_hz(), 90);
+        // Values outside the fallback 40-90 should be clamped
+        let manager = DisplayManager::new(Hertz(20), Hertz(120));
+        assert_eq!(manager.get_min_hz(), Hertz(40));
+        assert_eq!(manager.get_max_hz(), Hertz(90));
+    }
+
+    #[test]
+    fn test_clamp_hz_snaps_to_allowed() {
+        let manager = DisplayManager::new(Hertz(40), Hertz(90));
+        manager.set_allowed_hz(&[Hertz(45), Hertz(60), Hertz(90)]);
+        assert_eq!(manager.clamp_hz(Hertz(50)), Hertz(45));
+        assert_eq!(manager.clamp_hz(Hertz(100)), Hertz(90));
+
+        manager.set_allowed_hz(&[]);
+        assert_eq!(manager.clamp_hz(Hertz(50)), Hertz(50));
     }
 
     #[test]
     fn test_clamp_hz_below_min() {
-        let manager = DisplayManager::new(50, 80);
-        assert_eq!(manager.clamp_hz(30), 50);
+        let manager = DisplayManager::new(Hertz(50), Hertz(80));
+        assert_eq!(manager.clamp_hz(Hertz(30)), Hertz(50));
     }
 
     #[test]
     fn test_clamp_hz_above_max() {
-        let manager = DisplayManager::new(50, 80);
-        assert_eq!(manager.clamp_hz(100), 80);
+        let manager = DisplayManager::new(Hertz(50), Hertz(80));
+        assert_eq!(manager.clamp_hz(Hertz(100)), Hertz(80));
     }
 
     #[test]
     fn test_clamp_hz_within_range() {
-        let manager = DisplayManager::new(50, 80);
-        assert_eq!(manager.clamp_hz(60), 60);
+        let manager = DisplayManager::new(Hertz(50), Hertz(80));
+        assert_eq!(manager.clamp_hz(Hertz(60)), Hertz(60));
+    }
+
+    #[tokio::test]
+    async fn test_set_refresh_rate_through_backend() {
+        let manager = DisplayManager::new(Hertz(40), Hertz(90));
+        assert_eq!(manager.backend_kind(), BackendKind::GamescopeCmd);
+        manager.set_backend(BackendKind::Noop.create());
+        assert_eq!(manager.backend_kind(), BackendKind::Noop);
+
+        assert!(manager.set_refresh_rate(Hertz(60)).await.unwrap());
+        assert_eq!(manager.get_current_hz(), Hertz(60));
+        assert!(!manager.set_refresh_rate(Hertz(60)).await.unwrap());
+        manager.set_fps_limit(60).await.unwrap();
+        assert_eq!(manager.get_current_fps_limit(), 60);
+    }
+
+    #[tokio::test]
+    async fn test_frame_limiter_sync() {
+        let manager = DisplayManager::new(Hertz(40), Hertz(90));
+        manager.set_backend(BackendKind::Noop.create());
+
+        manager.set_sync_frame_limiter(true).await;
+        assert_eq!(manager.get_current_fps_limit(), 90);
+        manager.set_refresh_rate(Hertz(60)).await.unwrap();
+        assert_eq!(manager.get_current_fps_limit(), 60);
+
+        // A temporary limit is released back to the synced one
+        manager.set_fps_limit(30).await.unwrap();
+        manager.release_fps_limit().await.unwrap();
+        assert_eq!(manager.get_current_fps_limit(), 60);
+
+        manager.set_sync_frame_limiter(false).await;
+        assert_eq!(manager.get_current_fps_limit(), 0);
+        manager.set_refresh_rate(Hertz(45)).await.unwrap();
+        assert_eq!(manager.get_current_fps_limit(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_sync_vrr_falls_back_without_capable_panel() {
+        let manager = DisplayManager::new(Hertz(40), Hertz(90));
+        manager.set_backend(BackendKind::Noop.create());
+
+        let vrr = manager.sync_vrr(true).await;
+        assert!(vrr.requested);
+        assert!(!vrr.capable);
+        assert!(!vrr.active);
+
+        assert_eq!(manager.sync_vrr(false).await, VrrStatus::default());
+    }
+
+    /// Backend failing its first `failures` refresh rate calls.
+    struct FlakyBackend {
+        failures: AtomicU32,
+        calls: AtomicU32,
+        error: fn() -> DisplayError,
+    }
+
+    impl FlakyBackend {
+        fn new(failures: u32, error: fn() -> DisplayError) -> Arc<Self> {
+            Arc::new(Self { failures: AtomicU32::new(failures), calls: AtomicU32::new(0), error })
+        }
+    }
+
+    impl DisplayBackend for FlakyBackend {
+        fn kind(&self) -> BackendKind {
+            BackendKind::Noop
+        }
+
+        fn probe(&self) -> crate::display_backend::BackendFuture<'_, bool> {
+            Box::pin(async { Ok(true) })
+        }
+
+        fn set_refresh_rate(&self, _hz: Hertz) -> crate::display_backend::BackendFuture<'_, ()> {
+            self.calls.fetch_add(1, Ordering::Relaxed);
+            let fail = self
+                .failures
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| n.checked_sub(1))
+                .is_ok();
+            let result = if fail { Err((self.error)()) } else { Ok(()) };
+            Box::pin(async move { result })
+        }
+
+        fn set_fps_limit(&self, _fps: u32) -> crate::display_backend::BackendFuture<'_, ()> {
+            Box::pin(async { Ok(()) })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_transient_failure_is_retried() {
+        let manager = DisplayManager::new(Hertz(40), Hertz(90));
+        let backend = FlakyBackend::new(1, || DisplayError::CommandFailed {
+            program: "gamescope-cmd".to_string(),
+            exit_code: Some(1),
+            stderr: String::new(),
+        });
+        manager.set_backend(backend.clone());
+
+        assert!(manager.set_refresh_rate(Hertz(60)).await.unwrap());
+        assert_eq!(backend.calls.load(Ordering::Relaxed), 2);
+        assert!(!manager.is_backend_unhealthy());
+    }
+
+    #[tokio::test]
+    async fn test_breaker_opens_after_repeated_failures() {
+        let manager = DisplayManager::new(Hertz(40), Hertz(90));
+        let backend = FlakyBackend::new(u32::MAX, || DisplayError::NoOutput("eDP-1".to_string()));
+        manager.set_backend(backend.clone());
+
+        for _ in 0..BREAKER_THRESHOLD {
+            assert!(matches!(manager.set_refresh_rate(Hertz(60)).await, Err(DisplayError::NoOutput(_))));
+        }
+        assert!(manager.is_backend_unhealthy());
+        // Refused without reaching the backend
+        assert!(matches!(
+            manager.set_refresh_rate(Hertz(60)).await,
+            Err(DisplayError::BackendUnhealthy { .. })
+        ));
+        assert_eq!(backend.calls.load(Ordering::Relaxed), BREAKER_THRESHOLD);
+
+        // A new backend starts healthy
+        manager.set_backend(BackendKind::Noop.create());
+        assert!(!manager.is_backend_unhealthy());
+    }
+
+    /// Backend reporting a settable refresh rate.
+    #[derive(Default)]
+    struct ReportingBackend {
+        hz: AtomicU32,
+        /// Rate set on an external output
+        output_hz: AtomicU32,
+        fps_limit: AtomicU32,
+        /// Compositor PID, 0 when not running
+        compositor: AtomicU32,
+    }
+
+    impl DisplayBackend for ReportingBackend {
+        fn kind(&self) -> BackendKind {
+            BackendKind::Noop
+        }
+
+        fn probe(&self) -> crate::display_backend::BackendFuture<'_, bool> {
+            Box::pin(async { Ok(true) })
+        }
+
+        fn set_refresh_rate(&self, hz: Hertz) -> crate::display_backend::BackendFuture<'_, ()> {
+            self.hz.store(hz.get(), Ordering::Relaxed);
+            Box::pin(async { Ok(()) })
+        }
+
+        fn set_output_refresh_rate(&self, _output: &str, hz: Hertz) -> crate::display_backend::BackendFuture<'_, ()> {
+            self.output_hz.store(hz.get(), Ordering::Relaxed);
+            Box::pin(async { Ok(()) })
+        }
+
+        fn set_fps_limit(&self, fps: u32) -> crate::display_backend::BackendFuture<'_, ()> {
+            self.fps_limit.store(fps, Ordering::Relaxed);
+            Box::pin(async { Ok(()) })
+        }
+
+        fn current_refresh_rate(&self) -> crate::display_backend::BackendFuture<'_, Option<Hertz>> {
+            let hz = self.hz.load(Ordering::Relaxed);
+            Box::pin(async move { Ok(Some(Hertz(hz))) })
+        }
+
+        fn compositor_id(&self) -> Option<u32> {
+            Some(self.compositor.load(Ordering::Relaxed)).filter(|&pid| pid > 0)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_detect_external_change() {
+        let manager = DisplayManager::new(Hertz(40), Hertz(90));
+        let backend = Arc::new(ReportingBackend { hz: AtomicU32::new(90), ..Default::default() });
+        manager.set_backend(backend.clone());
+        if let Ok(mut last_change) = manager.last_change.lock() {
+            *last_change = Instant::now() - Duration::from(OWN_CHANGE_SETTLE);
+        }
+
+        // First reading only records the baseline
+        assert_eq!(manager.detect_external_change().await, None);
+
+        backend.hz.store(60, Ordering::Relaxed);
+        assert_eq!(manager.detect_external_change().await, Some(Hertz(60)));
+        assert_eq!(manager.get_current_hz(), Hertz(60));
+        assert_eq!(manager.detect_external_change().await, None);
+
+        // A reading that doesn't move is not a change, even if it differs
+        manager.current_hz.store(45, Ordering::Relaxed);
+        assert_eq!(manager.detect_external_change().await, None);
+        assert_eq!(manager.get_current_hz(), Hertz(45));
+    }
+
+    #[tokio::test]
+    async fn test_restore_original_rate() {
+        let manager = DisplayManager::new(Hertz(40), Hertz(60));
+        assert_eq!(manager.exit_rate(Hertz(60)), Hertz(60));
+
+        let backend = Arc::new(ReportingBackend { hz: AtomicU32::new(90), ..Default::default() });
+        manager.set_backend(backend.clone());
+        assert_eq!(manager.capture_original_rate().await, Some(Hertz(90)));
+        manager.set_refresh_rate(Hertz(45)).await.unwrap();
+        assert_eq!(manager.capture_original_rate().await, Some(Hertz(90)));
+
+        // The original rate is restored even above the configured max
+        assert_eq!(manager.restore_refresh_rate(Hertz(60)).await.unwrap(), Hertz(90));
+        assert_eq!(backend.hz.load(Ordering::Relaxed), 90);
+
+        // Unless the panel can't show it
+        manager.set_panel_range(Hertz(40)..=Hertz(60), HzRangeSource::Edid);
+        assert_eq!(manager.exit_rate(Hertz(60)), Hertz(60));
+    }
+
+    #[tokio::test]
+    async fn test_restore_clears_frame_limit() {
+        let manager = DisplayManager::new(Hertz(40), Hertz(90));
+        let backend = Arc::new(ReportingBackend { hz: AtomicU32::new(90), ..Default::default() });
+        manager.set_backend(backend.clone());
+        manager.capture_original_rate().await;
+
+        manager.set_sync_frame_limiter(true).await;
+        manager.set_refresh_rate(Hertz(45)).await.unwrap();
+        assert_eq!(backend.fps_limit.load(Ordering::Relaxed), 45);
+        assert_eq!(manager.restore_refresh_rate(Hertz(90)).await.unwrap(), Hertz(90));
+        assert_eq!(backend.hz.load(Ordering::Relaxed), 90);
+        assert_eq!(backend.fps_limit.load(Ordering::Relaxed), 0);
+
+        // A temporary limit without sync is cleared as well
+        manager.set_sync_frame_limiter(false).await;
+        manager.set_fps_limit(30).await.unwrap();
+        manager.restore_refresh_rate(Hertz(90)).await.unwrap();
+        assert_eq!(backend.fps_limit.load(Ordering::Relaxed), 0);
+    }
+
+    #[tokio::test]
+    async fn test_apply_startup_rate() {
+        let manager = DisplayManager::new(Hertz(40), Hertz(60));
+        let backend = Arc::new(ReportingBackend { hz: AtomicU32::new(90), ..Default::default() });
+        manager.set_backend(backend.clone());
+        manager.capture_original_rate().await;
+
+        let applied = manager.apply_startup_rate(StartupRate::Untouched, Some(Hertz(45))).await;
+        assert_eq!(applied.unwrap(), Some(Hertz(90)));
+        assert_eq!(manager.get_current_hz(), Hertz(90));
+        assert_eq!(backend.hz.load(Ordering::Relaxed), 90);
+
+        let applied = manager.apply_startup_rate(StartupRate::Restore, Some(Hertz(45))).await;
+        assert_eq!(applied.unwrap(), Some(Hertz(45)));
+        assert_eq!(backend.hz.load(Ordering::Relaxed), 45);
+
+        // Unknown last rate, or one outside the range, falls back to the range
+        let applied = manager.apply_startup_rate(StartupRate::Restore, None).await;
+        assert_eq!(applied.unwrap(), Some(Hertz(60)));
+        let applied = manager.apply_startup_rate(StartupRate::Restore, Some(Hertz(30))).await;
+        assert_eq!(applied.unwrap(), Some(Hertz(40)));
+        let applied = manager.apply_startup_rate(StartupRate::Max, Some(Hertz(45))).await;
+        assert_eq!(applied.unwrap(), Some(Hertz(60)));
+        assert_eq!(backend.hz.load(Ordering::Relaxed), 60);
+    }
+
+    #[tokio::test]
+    async fn test_reapply_after_compositor_restart() {
+        let manager = DisplayManager::new(Hertz(40), Hertz(90));
+        let backend = Arc::new(ReportingBackend { compositor: AtomicU32::new(812), ..Default::default() });
+        manager.set_backend(backend.clone());
+        assert!(!manager.detect_compositor_restart());
+        manager.set_refresh_rate(Hertz(45)).await.unwrap();
+        manager.set_fps_limit(45).await.unwrap();
+        assert!(!manager.detect_compositor_restart());
+
+        // Gone, then back as a new instance at its default rate
+        backend.compositor.store(0, Ordering::Relaxed);
+        assert!(!manager.detect_compositor_restart());
+        backend.compositor.store(1337, Ordering::Relaxed);
+        backend.hz.store(90, Ordering::Relaxed);
+        backend.fps_limit.store(0, Ordering::Relaxed);
+        assert!(manager.detect_compositor_restart());
+        assert!(!manager.detect_compositor_restart());
+
+        manager.reapply_state().await.unwrap();
+        assert_eq!(backend.hz.load(Ordering::Relaxed), 45);
+        assert_eq!(backend.fps_limit.load(Ordering::Relaxed), 45);
+    }
+
+    #[tokio::test]
+    async fn test_active_output_receives_rate() {
+        let manager = DisplayManager::new(Hertz(40), Hertz(90));
+        let backend = Arc::new(ReportingBackend { hz: AtomicU32::new(90), ..Default::default() });
+        manager.set_backend(backend.clone());
+
+        manager.set_active_output(Some("DP-1".to_string()));
+        assert_eq!(manager.active_output().as_deref(), Some("DP-1"));
+        manager.set_refresh_rate(Hertz(60)).await.unwrap();
+        assert_eq!(backend.output_hz.load(Ordering::Relaxed), 60);
+        assert_eq!(backend.hz.load(Ordering::Relaxed), 90);
+
+        manager.set_active_output(None);
+        manager.set_refresh_rate(Hertz(45)).await.unwrap();
+        assert_eq!(backend.hz.load(Ordering::Relaxed), 45);
+        assert_eq!(backend.output_hz.load(Ordering::Relaxed), 60);
+    }
+
+    #[test]
+    fn test_breaker_trial_after_cooldown() {
+        let mut breaker = Breaker::default();
+        let now = Instant::now();
+        for _ in 0..BREAKER_THRESHOLD {
+            breaker.record(false, now);
+        }
+        assert!(breaker.check(now).is_err());
+
+        let later = now + Duration::from(BREAKER_COOLDOWN);
+        assert!(breaker.check(later).is_ok());
+        breaker.record(false, later);
+        assert!(breaker.check(later).is_err());
+        breaker.record(true, later);
+        assert!(breaker.check(later).is_ok());
+        assert!(breaker.open_until.is_none());
+    }
+
+    #[test]
+    fn test_set_panel_range() {
+        let manager = DisplayManager::new(Hertz(40), Hertz(90));
+        assert_eq!(manager.panel_range_source(), HzRangeSource::Fallback);
+
+        manager.set_panel_range(Hertz(45)..=Hertz(90), HzRangeSource::Edid);
+        assert_eq!(manager.get_min_hz(), Hertz(45));
+        assert_eq!(manager.clamp_hz(Hertz(40)), Hertz(45));
+
+        // Wider panels are no longer capped at 90Hz
+        manager.set_panel_range(Hertz(48)..=Hertz(120), HzRangeSource::Backend);
+        manager.set_range(Hertz(48), Hertz(120));
+        assert_eq!(manager.clamp_hz(Hertz(144)), Hertz(120));
+        assert_eq!(manager.panel_range_source(), HzRangeSource::Backend);
     }
 
     #[test]
     fn test_set_range() {
-        let manager = DisplayManager::new(40, 90);
-        manager.set_range(50, 70);
-        assert_eq!(manager.get_min_hz(), 50);
-        assert_eq!(manager.get_max_hz(), 70);
+        let manager = DisplayManager::new(Hertz(40), Hertz(90));
+        manager.set_range(Hertz(50), Hertz(70));
+        assert_eq!(manager.get_min_hz(), Hertz(50));
+        assert_eq!(manager.get_max_hz(), Hertz(70));
     }
 
     #[test]
     fn test_set_range_swapped() {
-        let manager = DisplayManager::new(40, 90);
-        manager.set_range(70, 50);
-        assert_eq!(manager.get_min_hz(), 50);
-        assert_eq!(manager.get_max_hz(), 70);
+        let manager = DisplayManager::new(Hertz(40), Hertz(90));
+        manager.set_range(Hertz(70), Hertz(50));
+        assert_eq!(manager.get_min_hz(), Hertz(50));
+        assert_eq!(manager.get_max_hz(), Hertz(70));
     }
 }
@@ -0,0 +1,157 @@
+//! Page flip counting FPS source, the last resort.
+//!
+//! With neither MangoHud nor gamescope stats, the presented frame rate is
+//! estimated from the panel's CRTC: a thread waits for each vblank and
+//! counts the times the scanned-out framebuffer changed. That only sees
+//! frames that reached the screen, so it reads the UI as well as games,
+//! but it keeps fallback mode making decisions instead of none.
+
+use crate::drm_modes::{PanelCrtc, DRI_DIR};
+use crate::error::FpsSourceError;
+use crate::fps_monitor::{FpsRingBuffer, FpsSample, FpsSmoothing};
+use crate::fps_source::{FpsSource, FpsSourceKind};
+use std::collections::VecDeque;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Span the flip rate is averaged over. At a 100 ms poll a single interval
+/// would only hold a handful of flips.
+const FLIP_WINDOW: Duration = Duration::from_secs(1);
+
+/// Pause between framebuffer reads when vblank waits fail
+const VBLANK_FALLBACK_SLEEP: Duration = Duration::from_millis(5);
+
+/// Estimates FPS from the panel's page flips.
+pub struct DrmFlipSource {
+    flips: Arc<AtomicU64>,
+    /// Cleared to stop the counting thread, and by the thread when the
+    /// CRTC can no longer be read
+    alive: Arc<AtomicBool>,
+    rate: FlipRate,
+    ring_buffer: FpsRingBuffer,
+}
+
+impl DrmFlipSource {
+    /// Find the panel's CRTC and start counting its flips.
+    pub fn open() -> Result<Self, FpsSourceError> {
+        let crtc = PanelCrtc::open(Path::new(DRI_DIR)).ok_or(FpsSourceError::PanelCrtcNotFound)?;
+        let mut last_fb = crtc.fb_id()?;
+
+        let flips = Arc::new(AtomicU64::new(0));
+        let alive = Arc::new(AtomicBool::new(true));
+        let (thread_flips, thread_alive) = (Arc::clone(&flips), Arc::clone(&alive));
+        std::thread::Builder::new()
+            .name("drm-flips".to_string())
+            .spawn(move || {
+                while thread_alive.load(Ordering::Relaxed) {
+                    if let Err(e) = crtc.wait_vblank() {
+                        tracing::trace!("vblank wait failed: {}", e);
+                        std::thread::sleep(VBLANK_FALLBACK_SLEEP);
+                    }
+                    match crtc.fb_id() {
+                        Ok(fb) if fb != 0 && fb != last_fb => {
+                            last_fb = fb;
+                            thread_flips.fetch_add(1, Ordering::Relaxed);
+                        }
+                        Ok(_) => {}
+                        Err(e) => {
+                            tracing::debug!("Page flip counting stopped: {}", e);
+                            thread_alive.store(false, Ordering::Relaxed);
+                        }
+                    }
+                }
+            })?;
+
+        Ok(Self { flips, alive, rate: FlipRate::default(), ring_buffer: FpsRingBuffer::new() })
+    }
+}
+
+impl Drop for DrmFlipSource {
+    fn drop(&mut self) {
+        // The thread exits after its current vblank wait
+        self.alive.store(false, Ordering::Relaxed);
+    }
+}
+
+impl FpsSource for DrmFlipSource {
+    fn kind(&self) -> FpsSourceKind {
+        FpsSourceKind::DrmFlips
+    }
+
+    fn poll(&mut self) -> Result<FpsSample, FpsSourceError> {
+        if !self.alive.load(Ordering::Relaxed) {
+            return Err(FpsSourceError::Closed);
+        }
+        let fps = self.rate.record(Instant::now(), self.flips.load(Ordering::Relaxed));
+        let frametime = if fps > 0.0 { (1_000_000.0 / fps).round() as u64 } else { 0 };
+        let sample = FpsSample::new(fps.round() as u64, frametime);
+        self.ring_buffer.push(sample.clone());
+        Ok(sample)
+    }
+
+    fn smoothed_fps(&self, smoothing: FpsSmoothing) -> f64 {
+        self.ring_buffer.smoothed(smoothing)
+    }
+
+    fn p99_frametime(&self) -> u64 {
+        self.ring_buffer.percentile(0.99)
+    }
+
+    fn set_window(&mut self, capacity: usize) {
+        self.ring_buffer.set_capacity(capacity);
+    }
+}
+
+/// Flip count readings over the last `FLIP_WINDOW`.
+#[derive(Debug, Default)]
+struct FlipRate {
+    readings: VecDeque<(Instant, u64)>,
+}
+
+impl FlipRate {
+    /// Add the flip count read at `now` and return the flips per second
+    /// over the window (0 until two readings are in).
+    fn record(&mut self, now: Instant, count: u64) -> f64 {
+        self.readings.push_back((now, count));
+        // Keep the newest reading at least a window old as the baseline
+        while self.readings.len() > 2 && now.duration_since(self.readings[1].0) >= FLIP_WINDOW {
+            self.readings.pop_front();
+        }
+        let (start, start_count) = self.readings[0];
+        let elapsed = now.duration_since(start).as_secs_f64();
+        if elapsed <= 0.0 {
+            return 0.0;
+        }
+        count.saturating_sub(start_count) as f64 / elapsed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flip_rate() {
+        let start = Instant::now();
+        let mut rate = FlipRate::default();
+        assert_eq!(rate.record(start, 0), 0.0);
+
+        // 60 flips per second, read every 100 ms
+        for i in 1..=30u64 {
+            let fps = rate.record(start + Duration::from_millis(100 * i), 6 * i);
+            assert!((fps - 60.0).abs() < 1e-6, "{} at reading {}", fps, i);
+        }
+        assert!(rate.readings.len() <= 12);
+
+        // Dropping to 30 shows within the window
+        let mut count = 180;
+        let mut fps = 0.0;
+        for i in 31..=40u64 {
+            count += 3;
+            fps = rate.record(start + Duration::from_millis(100 * i), count);
+        }
+        assert!((fps - 30.0).abs() < 3.5, "{}", fps);
+    }
+}
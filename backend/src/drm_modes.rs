@@ -0,0 +1,552 @@
+//! Refresh rates supported by the internal panel, from its DRM modes.
+//!
+//! The sysfs `modes` node only lists resolutions, so the connector's mode
+//! list is read with the `DRM_IOCTL_MODE_GETCONNECTOR` ioctl on the card's
+//! device node. When no panel can be read the daemon's own 40-90 Hz range
+//! is reported instead. External outputs are read the same way, by
+//! connector name.
+
+use crate::display_control::{MAX_ALLOWED_HZ, MIN_ALLOWED_HZ};
+use crate::error::DisplayError;
+use crate::units::Hertz;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::os::fd::AsRawFd;
+use std::path::{Path, PathBuf};
+
+/// Directory with the DRM device nodes
+pub const DRI_DIR: &str = "/dev/dri";
+
+/// DRM_MODE_CONNECTOR_eDP
+const CONNECTOR_EDP: u32 = 14;
+
+/// DRM_MODE_CONNECTOR_DSI
+const CONNECTOR_DSI: u32 = 16;
+
+/// Kernel connector type names by DRM_MODE_CONNECTOR_* value, as used in
+/// connector names like "DP-1"
+const CONNECTOR_TYPE_NAMES: [(u32, &str); 10] = [
+    (1, "VGA"),
+    (2, "DVI-I"),
+    (3, "DVI-D"),
+    (4, "DVI-A"),
+    (10, "DP"),
+    (11, "HDMI-A"),
+    (12, "HDMI-B"),
+    (CONNECTOR_EDP, "eDP"),
+    (CONNECTOR_DSI, "DSI"),
+    (20, "USB"),
+];
+
+/// drm_mode_connection: connector_status_connected
+const CONNECTED: u32 = 1;
+
+/// DRM_MODE_TYPE_PREFERRED
+const MODE_TYPE_PREFERRED: u32 = 1 << 3;
+
+/// `struct drm_mode_card_res`
+#[repr(C)]
+#[derive(Default)]
+struct CardRes {
+    fb_id_ptr: u64,
+    crtc_id_ptr: u64,
+    connector_id_ptr: u64,
+    encoder_id_ptr: u64,
+    count_fbs: u32,
+    count_crtcs: u32,
+    count_connectors: u32,
+    count_encoders: u32,
+    min_width: u32,
+    max_width: u32,
+    min_height: u32,
+    max_height: u32,
+}
+
+/// `struct drm_mode_get_connector`
+#[repr(C)]
+#[derive(Default)]
+struct GetConnector {
+    encoders_ptr: u64,
+    modes_ptr: u64,
+    props_ptr: u64,
+    prop_values_ptr: u64,
+    count_modes: u32,
+    count_props: u32,
+    count_encoders: u32,
+    encoder_id: u32,
+    connector_id: u32,
+    connector_type: u32,
+    connector_type_id: u32,
+    connection: u32,
+    mm_width: u32,
+    mm_height: u32,
+    subpixel: u32,
+    pad: u32,
+}
+
+/// `struct drm_mode_modeinfo`
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+struct ModeInfo {
+    clock: u32,
+    hdisplay: u16,
+    hsync_start: u16,
+    hsync_end: u16,
+    htotal: u16,
+    hskew: u16,
+    vdisplay: u16,
+    vsync_start: u16,
+    vsync_end: u16,
+    vtotal: u16,
+    vscan: u16,
+    vrefresh: u32,
+    flags: u32,
+    mode_type: u32,
+    name: [u8; 32],
+}
+
+/// `struct drm_mode_get_encoder`
+#[repr(C)]
+#[derive(Default)]
+struct GetEncoder {
+    encoder_id: u32,
+    encoder_type: u32,
+    crtc_id: u32,
+    possible_crtcs: u32,
+    possible_clones: u32,
+}
+
+/// `struct drm_mode_crtc`
+#[repr(C)]
+#[derive(Default)]
+struct Crtc {
+    set_connectors_ptr: u64,
+    count_connectors: u32,
+    crtc_id: u32,
+    fb_id: u32,
+    x: u32,
+    y: u32,
+    gamma_size: u32,
+    mode_valid: u32,
+    mode: ModeInfo,
+}
+
+/// `union drm_wait_vblank`: request (type, sequence, signal) and reply
+/// (type, sequence, tval_sec, tval_usec) share the layout
+#[repr(C)]
+#[derive(Default)]
+struct WaitVblank {
+    vblank_type: u32,
+    sequence: u32,
+    signal_or_sec: u64,
+    usec: u64,
+}
+
+/// _DRM_VBLANK_RELATIVE: wait `sequence` vblanks from now
+const VBLANK_RELATIVE: u32 = 0x1;
+
+/// _DRM_VBLANK_HIGH_CRTC_SHIFT / _DRM_VBLANK_HIGH_CRTC_MASK
+const VBLANK_HIGH_CRTC_SHIFT: u32 = 1;
+const VBLANK_HIGH_CRTC_MASK: u32 = 0x3e;
+
+/// `DRM_IOWR(nr, T)`
+const fn drm_iowr<T>(nr: u64) -> u64 {
+    (3 << 30) | ((std::mem::size_of::<T>() as u64) << 16) | ((b'd' as u64) << 8) | nr
+}
+
+const DRM_IOCTL_WAIT_VBLANK: u64 = drm_iowr::<WaitVblank>(0x3A);
+const DRM_IOCTL_MODE_GETRESOURCES: u64 = drm_iowr::<CardRes>(0xA0);
+const DRM_IOCTL_MODE_GETCRTC: u64 = drm_iowr::<Crtc>(0xA1);
+const DRM_IOCTL_MODE_GETENCODER: u64 = drm_iowr::<GetEncoder>(0xA6);
+const DRM_IOCTL_MODE_GETCONNECTOR: u64 = drm_iowr::<GetConnector>(0xA7);
+
+fn ioctl<T>(file: &File, request: u64, arg: &mut T) -> std::io::Result<()> {
+    // SAFETY: `arg` is the repr(C) struct the request expects, and any
+    // pointers in it reference buffers sized by its counts
+    let ret = unsafe { libc::ioctl(file.as_raw_fd(), request as _, arg as *mut T) };
+    if ret < 0 {
+        Err(std::io::Error::last_os_error())
+    } else {
+        Ok(())
+    }
+}
+
+/// One display mode of the panel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PanelMode {
+    pub width: u32,
+    pub height: u32,
+    pub refresh: Hertz,
+    /// Marked preferred (native) by the driver
+    pub preferred: bool,
+}
+
+impl From<&ModeInfo> for PanelMode {
+    fn from(mode: &ModeInfo) -> Self {
+        Self {
+            width: mode.hdisplay.into(),
+            height: mode.vdisplay.into(),
+            refresh: Hertz(mode.vrefresh),
+            preferred: mode.mode_type & MODE_TYPE_PREFERRED != 0,
+        }
+    }
+}
+
+/// Kernel name of a connector, e.g. "DP-1" or "HDMI-A-2".
+fn connector_name(connector_type: u32, connector_type_id: u32) -> Option<String> {
+    let (_, type_name) = CONNECTOR_TYPE_NAMES.iter().find(|(t, _)| *t == connector_type)?;
+    Some(format!("{}-{}", type_name, connector_type_id))
+}
+
+/// IDs of the connectors of an open card.
+fn connector_ids(file: &File) -> std::io::Result<Vec<u32>> {
+    let mut res = CardRes::default();
+    ioctl(file, DRM_IOCTL_MODE_GETRESOURCES, &mut res)?;
+    let mut connector_ids = vec![0u32; res.count_connectors as usize];
+    res = CardRes {
+        connector_id_ptr: connector_ids.as_mut_ptr() as u64,
+        count_connectors: connector_ids.len() as u32,
+        ..CardRes::default()
+    };
+    ioctl(file, DRM_IOCTL_MODE_GETRESOURCES, &mut res)?;
+    connector_ids.truncate(res.count_connectors as usize);
+    Ok(connector_ids)
+}
+
+/// IDs of the CRTCs of an open card; a CRTC's index is its vblank pipe.
+fn crtc_ids(file: &File) -> std::io::Result<Vec<u32>> {
+    let mut res = CardRes::default();
+    ioctl(file, DRM_IOCTL_MODE_GETRESOURCES, &mut res)?;
+    let mut crtc_ids = vec![0u32; res.count_crtcs as usize];
+    res = CardRes {
+        crtc_id_ptr: crtc_ids.as_mut_ptr() as u64,
+        count_crtcs: crtc_ids.len() as u32,
+        ..CardRes::default()
+    };
+    ioctl(file, DRM_IOCTL_MODE_GETRESOURCES, &mut res)?;
+    crtc_ids.truncate(res.count_crtcs as usize);
+    Ok(crtc_ids)
+}
+
+/// The CRTC scanning out the internal panel, opened for page flip counting.
+pub struct PanelCrtc {
+    file: File,
+    crtc_id: u32,
+    pipe: u32,
+}
+
+impl PanelCrtc {
+    /// Find the CRTC driving the connected internal panel on the first card
+    /// under `dri_dir` that has one lit.
+    pub fn open(dri_dir: &Path) -> Option<Self> {
+        card_paths(dri_dir).iter().find_map(|card| match Self::open_card(card) {
+            Ok(crtc) => crtc,
+            Err(e) => {
+                tracing::debug!("Failed to find the panel CRTC on {}: {}", card.display(), e);
+                None
+            }
+        })
+    }
+
+    fn open_card(card: &Path) -> std::io::Result<Option<Self>> {
+        let file = File::options().read(true).write(true).open(card)?;
+        for connector_id in connector_ids(&file)? {
+            let mut scratch = ModeInfo::default();
+            let mut connector = GetConnector {
+                connector_id,
+                modes_ptr: &mut scratch as *mut ModeInfo as u64,
+                count_modes: 1,
+                ..GetConnector::default()
+            };
+            ioctl(&file, DRM_IOCTL_MODE_GETCONNECTOR, &mut connector)?;
+            let internal = matches!(connector.connector_type, CONNECTOR_EDP | CONNECTOR_DSI);
+            if !internal || connector.connection != CONNECTED || connector.encoder_id == 0 {
+                continue;
+            }
+
+            let mut encoder = GetEncoder { encoder_id: connector.encoder_id, ..GetEncoder::default() };
+            ioctl(&file, DRM_IOCTL_MODE_GETENCODER, &mut encoder)?;
+            let Some(pipe) = crtc_ids(&file)?.iter().position(|&id| id == encoder.crtc_id) else {
+                continue;
+            };
+            return Ok(Some(Self { crtc_id: encoder.crtc_id, pipe: pipe as u32, file }));
+        }
+        Ok(None)
+    }
+
+    /// Framebuffer currently scanned out; it changes with every page flip.
+    pub fn fb_id(&self) -> std::io::Result<u32> {
+        let mut crtc = Crtc { crtc_id: self.crtc_id, ..Crtc::default() };
+        ioctl(&self.file, DRM_IOCTL_MODE_GETCRTC, &mut crtc)?;
+        Ok(crtc.fb_id)
+    }
+
+    /// Block until the CRTC's next vblank.
+    pub fn wait_vblank(&self) -> std::io::Result<()> {
+        let mut vblank = WaitVblank {
+            vblank_type: VBLANK_RELATIVE | ((self.pipe << VBLANK_HIGH_CRTC_SHIFT) & VBLANK_HIGH_CRTC_MASK),
+            sequence: 1,
+            ..WaitVblank::default()
+        };
+        ioctl(&self.file, DRM_IOCTL_WAIT_VBLANK, &mut vblank)
+    }
+}
+
+/// Modes of the first connected connector of `card` that `wanted` accepts.
+fn card_connector_modes(
+    card: &Path,
+    wanted: impl Fn(&GetConnector) -> bool,
+) -> std::io::Result<Option<Vec<PanelMode>>> {
+    let file = File::options().read(true).write(true).open(card)?;
+    for connector_id in connector_ids(&file)? {
+        let mut connector = GetConnector { connector_id, ..GetConnector::default() };
+        ioctl(&file, DRM_IOCTL_MODE_GETCONNECTOR, &mut connector)?;
+        if !wanted(&connector) || connector.connection != CONNECTED {
+            continue;
+        }
+
+        let mut modes = vec![ModeInfo::default(); connector.count_modes as usize];
+        connector = GetConnector {
+            connector_id,
+            modes_ptr: modes.as_mut_ptr() as u64,
+            count_modes: modes.len() as u32,
+            ..GetConnector::default()
+        };
+        ioctl(&file, DRM_IOCTL_MODE_GETCONNECTOR, &mut connector)?;
+        modes.truncate(connector.count_modes as usize);
+        return Ok(Some(modes.iter().map(PanelMode::from).collect()));
+    }
+    Ok(None)
+}
+
+/// Mode each connector of `card` is currently scanned out at, by output name.
+fn card_current_modes(card: &Path) -> std::io::Result<Vec<(String, PanelMode)>> {
+    let file = File::options().read(true).write(true).open(card)?;
+    let mut current = Vec::new();
+    for connector_id in connector_ids(&file)? {
+        // A non-zero mode count reads the cached state; zero would reprobe the connector
+        let mut scratch = ModeInfo::default();
+        let mut connector = GetConnector {
+            connector_id,
+            modes_ptr: &mut scratch as *mut ModeInfo as u64,
+            count_modes: 1,
+            ..GetConnector::default()
+        };
+        ioctl(&file, DRM_IOCTL_MODE_GETCONNECTOR, &mut connector)?;
+        let Some(name) = connector_name(connector.connector_type, connector.connector_type_id) else {
+            continue;
+        };
+        if connector.encoder_id == 0 {
+            continue;
+        }
+
+        let mut encoder = GetEncoder { encoder_id: connector.encoder_id, ..GetEncoder::default() };
+        ioctl(&file, DRM_IOCTL_MODE_GETENCODER, &mut encoder)?;
+        if encoder.crtc_id == 0 {
+            continue;
+        }
+        let mut crtc = Crtc { crtc_id: encoder.crtc_id, ..Crtc::default() };
+        ioctl(&file, DRM_IOCTL_MODE_GETCRTC, &mut crtc)?;
+        if crtc.mode_valid != 0 {
+            current.push((name, PanelMode::from(&crtc.mode)));
+        }
+    }
+    Ok(current)
+}
+
+/// Mode each lit output is currently scanned out at, by output name (e.g.
+/// "eDP-1"), across the cards under `dri_dir`. Unreadable cards are skipped.
+pub fn current_modes(dri_dir: &Path) -> Vec<(String, PanelMode)> {
+    card_paths(dri_dir)
+        .iter()
+        .flat_map(|card| {
+            card_current_modes(card).unwrap_or_else(|e| {
+                tracing::debug!("Failed to read current modes from {}: {}", card.display(), e);
+                Vec::new()
+            })
+        })
+        .collect()
+}
+
+/// DRM card device nodes (`card0`, `card1`, ...) under `dri_dir`, sorted.
+fn card_paths(dri_dir: &Path) -> Vec<PathBuf> {
+    let mut cards: Vec<PathBuf> = std::fs::read_dir(dri_dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|e| e.path())
+                .filter(|p| {
+                    p.file_name()
+                        .and_then(|n| n.to_str())
+                        .is_some_and(|n| n.strip_prefix("card").is_some_and(|id| id.parse::<u32>().is_ok()))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    cards.sort();
+    cards
+}
+
+/// Modes of the first connected connector that `wanted` accepts, on the
+/// first card under `dri_dir` that has one.
+fn connector_modes(dri_dir: &Path, wanted: impl Fn(&GetConnector) -> bool) -> Option<Vec<PanelMode>> {
+    for card in card_paths(dri_dir) {
+        match card_connector_modes(&card, &wanted) {
+            Ok(Some(modes)) if !modes.is_empty() => return Some(modes),
+            Ok(_) => {}
+            Err(e) => tracing::debug!("Failed to read DRM modes from {}: {}", card.display(), e),
+        }
+    }
+    None
+}
+
+/// Modes of the internal panel (eDP or DSI).
+pub fn panel_modes(dri_dir: &Path) -> Result<Vec<PanelMode>, DisplayError> {
+    connector_modes(dri_dir, |c| matches!(c.connector_type, CONNECTOR_EDP | CONNECTOR_DSI))
+        .ok_or_else(|| DisplayError::NoOutput("no connected internal panel in DRM".to_string()))
+}
+
+/// Modes of the connected output named `output` (e.g. "DP-1").
+pub fn output_modes(dri_dir: &Path, output: &str) -> Result<Vec<PanelMode>, DisplayError> {
+    connector_modes(dri_dir, |c| {
+        connector_name(c.connector_type, c.connector_type_id).is_some_and(|name| name == output)
+    })
+    .ok_or_else(|| DisplayError::NoOutput(format!("no connected output {} in DRM", output)))
+}
+
+/// Distinct rates at the panel's native resolution (the preferred mode's,
+/// else the largest), ascending.
+pub fn native_rates(modes: &[PanelMode]) -> Vec<Hertz> {
+    let native = modes
+        .iter()
+        .find(|m| m.preferred)
+        .or_else(|| modes.iter().max_by_key(|m| m.width * m.height));
+    let Some(native) = native else {
+        return Vec::new();
+    };
+    let mut rates: Vec<Hertz> = modes
+        .iter()
+        .filter(|m| m.width == native.width && m.height == native.height && m.refresh.get() > 0)
+        .map(|m| m.refresh)
+        .collect();
+    rates.sort();
+    rates.dedup();
+    rates
+}
+
+/// Where the reported rates came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RateSource {
+    /// The panel's DRM mode list
+    Drm,
+    /// No panel could be read; the daemon's built-in range
+    Fallback,
+}
+
+/// Rates returned by `GetSupportedRates`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SupportedRates {
+    pub source: RateSource,
+    pub rates: Vec<Hertz>,
+    pub min: Hertz,
+    pub max: Hertz,
+}
+
+impl SupportedRates {
+    /// Enumerate the panel's rates, falling back to every rate in 40-90 Hz.
+    pub fn detect(dri_dir: &Path) -> Self {
+        match panel_modes(dri_dir).map(|modes| native_rates(&modes)) {
+            Ok(rates) if !rates.is_empty() => Self::from_rates(RateSource::Drm, rates),
+            Ok(_) => Self::fallback(),
+            Err(e) => {
+                tracing::debug!("Panel modes unavailable, reporting the built-in range: {}", e);
+                Self::fallback()
+            }
+        }
+    }
+
+    /// Enumerate the rates of an external output, `None` when it can't be read.
+    pub fn detect_output(dri_dir: &Path, output: &str) -> Option<Self> {
+        match output_modes(dri_dir, output).map(|modes| native_rates(&modes)) {
+            Ok(rates) if !rates.is_empty() => Some(Self::from_rates(RateSource::Drm, rates)),
+            Ok(_) => None,
+            Err(e) => {
+                tracing::debug!("Modes of {} unavailable: {}", output, e);
+                None
+            }
+        }
+    }
+
+    /// Every rate in the built-in 40-90 Hz range.
+    pub fn fallback() -> Self {
+        let rates = (MIN_ALLOWED_HZ.get()..=MAX_ALLOWED_HZ.get()).map(Hertz).collect();
+        Self::from_rates(RateSource::Fallback, rates)
+    }
+
+    fn from_rates(source: RateSource, rates: Vec<Hertz>) -> Self {
+        Self {
+            source,
+            min: rates.first().copied().unwrap_or(MIN_ALLOWED_HZ),
+            max: rates.last().copied().unwrap_or(MAX_ALLOWED_HZ),
+            rates,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn mode(width: u32, height: u32, refresh: u32, preferred: bool) -> PanelMode {
+        PanelMode { width, height, refresh: Hertz(refresh), preferred }
+    }
+
+    #[test]
+    fn test_ioctl_numbers() {
+        // Values from drm.h on x86_64
+        assert_eq!(DRM_IOCTL_MODE_GETRESOURCES, 0xC04064A0);
+        assert_eq!(DRM_IOCTL_MODE_GETCONNECTOR, 0xC05064A7);
+        assert_eq!(DRM_IOCTL_MODE_GETENCODER, 0xC01464A6);
+        assert_eq!(DRM_IOCTL_MODE_GETCRTC, 0xC06864A1);
+        assert_eq!(DRM_IOCTL_WAIT_VBLANK, 0xC018643A);
+        assert_eq!(std::mem::size_of::<ModeInfo>(), 68);
+    }
+
+    #[test]
+    fn test_native_rates() {
+        let modes = [
+            mode(800, 1280, 90, true),
+            mode(800, 1280, 45, false),
+            mode(800, 1280, 60, false),
+            mode(800, 1280, 60, false),
+            mode(640, 480, 30, false),
+        ];
+        assert_eq!(native_rates(&modes), [Hertz(45), Hertz(60), Hertz(90)]);
+
+        // Without a preferred mode the largest resolution is native
+        let modes = [mode(640, 480, 30, false), mode(1280, 800, 60, false)];
+        assert_eq!(native_rates(&modes), [Hertz(60)]);
+        assert!(native_rates(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_connector_name() {
+        assert_eq!(connector_name(10, 1).as_deref(), Some("DP-1"));
+        assert_eq!(connector_name(11, 2).as_deref(), Some("HDMI-A-2"));
+        assert_eq!(connector_name(CONNECTOR_EDP, 1).as_deref(), Some("eDP-1"));
+        assert_eq!(connector_name(0, 1), None);
+    }
+
+    #[test]
+    fn test_detect_falls_back_without_panel() {
+        let dir = tempdir().unwrap();
+        let rates = SupportedRates::detect(dir.path());
+        assert_eq!(rates.source, RateSource::Fallback);
+        assert_eq!((rates.min, rates.max), (MIN_ALLOWED_HZ, MAX_ALLOWED_HZ));
+        assert_eq!(rates.rates.len(), 51);
+        assert_eq!(SupportedRates::detect_output(dir.path(), "DP-1"), None);
+        assert!(current_modes(dir.path()).is_empty());
+    }
+}
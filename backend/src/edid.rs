@@ -0,0 +1,236 @@
+//! EDID of the internal panel.
+//!
+//! Read from `/sys/class/drm/card*-eDP-*/edid` (or `-DSI-`), it gives the
+//! panel's make and name, native resolution and, when the panel carries a
+//! range limits descriptor, the refresh range it supports.
+
+use crate::core_logic::DeviceMode;
+use crate::error::EdidError;
+use crate::units::Hertz;
+use serde::{Deserialize, Serialize};
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+
+/// Base EDID block size
+const BLOCK_LEN: usize = 128;
+
+/// Fixed 8-byte EDID header
+const HEADER: [u8; 8] = [0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x00];
+
+/// Offsets of the four 18-byte descriptors in the base block
+const DESCRIPTOR_OFFSETS: [usize; 4] = [54, 72, 90, 108];
+
+/// Display descriptor tags
+const TAG_PRODUCT_NAME: u8 = 0xFC;
+const TAG_RANGE_LIMITS: u8 = 0xFD;
+
+/// Steam Deck panels as gamescope identifies them: (make, name, mode)
+const KNOWN_PANELS: [(&str, Option<&str>, DeviceMode); 4] = [
+    ("VLV", Some("Galileo"), DeviceMode::Oled),
+    ("VLV", Some("Jupiter"), DeviceMode::Lcd),
+    ("WLC", Some("ANX7530 U"), DeviceMode::Lcd),
+    ("ANX", None, DeviceMode::Lcd),
+];
+
+/// What the panel's EDID says about it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PanelInfo {
+    /// DRM connector, e.g. "card0-eDP-1"
+    pub connector: String,
+    /// Three-letter PNP manufacturer ID
+    pub make: String,
+    pub product_code: u16,
+    /// Product name descriptor
+    pub name: Option<String>,
+    /// Native resolution from the preferred timing
+    pub native_width: Option<u32>,
+    pub native_height: Option<u32>,
+    /// Vertical rate limits from the range descriptor
+    pub min_hz: Option<Hertz>,
+    pub max_hz: Option<Hertz>,
+}
+
+impl PanelInfo {
+    /// Parse the base EDID block read from `connector`.
+    pub fn parse(connector: &str, edid: &[u8]) -> Result<Self, EdidError> {
+        if edid.len() < BLOCK_LEN {
+            return Err(EdidError::TooShort(edid.len()));
+        }
+        let block = &edid[..BLOCK_LEN];
+        if block[..8] != HEADER {
+            return Err(EdidError::BadHeader);
+        }
+        if block.iter().fold(0u8, |sum, b| sum.wrapping_add(*b)) != 0 {
+            return Err(EdidError::BadChecksum);
+        }
+
+        // Three 5-bit letters, 'A' = 1
+        let id = u16::from_be_bytes([block[8], block[9]]);
+        let make = [10, 5, 0]
+            .iter()
+            .map(|shift| char::from(b'@' + ((id >> shift) & 0x1F) as u8))
+            .collect();
+
+        let mut info = Self {
+            connector: connector.to_string(),
+            make,
+            product_code: u16::from_le_bytes([block[10], block[11]]),
+            name: None,
+            native_width: None,
+            native_height: None,
+            min_hz: None,
+            max_hz: None,
+        };
+
+        for offset in DESCRIPTOR_OFFSETS {
+            let d = &block[offset..offset + 18];
+            if d[0] != 0 || d[1] != 0 {
+                // Detailed timing; the first one is the preferred mode
+                if info.native_width.is_none() {
+                    info.native_width = Some(u32::from(d[2]) | (u32::from(d[4] >> 4) << 8));
+                    info.native_height = Some(u32::from(d[5]) | (u32::from(d[7] >> 4) << 8));
+                }
+                continue;
+            }
+            match d[3] {
+                TAG_PRODUCT_NAME => {
+                    let text = String::from_utf8_lossy(&d[5..]);
+                    let name = text.split('\n').next().unwrap_or_default().trim();
+                    info.name = (!name.is_empty()).then(|| name.to_string());
+                }
+                TAG_RANGE_LIMITS => {
+                    // Byte 4 bits 1:0 add 255 to the max and min vertical rates (EDID 1.4)
+                    let max_offset = if d[4] & 0b10 != 0 { 255 } else { 0 };
+                    let min_offset = if d[4] & 0b11 == 0b11 { 255 } else { 0 };
+                    info.min_hz = Some(Hertz(u32::from(d[5]) + min_offset));
+                    info.max_hz = Some(Hertz(u32::from(d[6]) + max_offset));
+                }
+                _ => {}
+            }
+        }
+        Ok(info)
+    }
+
+    /// Read the first internal panel's EDID under `drm_dir`.
+    pub fn detect(drm_dir: &Path) -> Option<Self> {
+        let mut connectors: Vec<PathBuf> = std::fs::read_dir(drm_dir)
+            .ok()?
+            .flatten()
+            .map(|e| e.path())
+            .filter(|p| {
+                p.file_name()
+                    .and_then(|n| n.to_str())
+                    .is_some_and(|n| n.starts_with("card") && (n.contains("-eDP-") || n.contains("-DSI-")))
+            })
+            .collect();
+        connectors.sort();
+
+        connectors.into_iter().find_map(|connector| {
+            let edid = std::fs::read(connector.join("edid")).ok()?;
+            let name = connector.file_name()?.to_string_lossy().into_owned();
+            match Self::parse(&name, &edid) {
+                Ok(info) => Some(info),
+                Err(e) => {
+                    tracing::debug!("Ignoring EDID of {}: {}", name, e);
+                    None
+                }
+            }
+        })
+    }
+
+    /// Refresh range from the range limits descriptor.
+    pub fn hz_range(&self) -> Option<RangeInclusive<Hertz>> {
+        match (self.min_hz, self.max_hz) {
+            (Some(min), Some(max)) if min > Hertz(0) && min <= max => Some(min..=max),
+            _ => None,
+        }
+    }
+
+    /// Device mode for a known Steam Deck panel.
+    pub fn device_mode(&self) -> Option<DeviceMode> {
+        KNOWN_PANELS
+            .iter()
+            .find(|(make, name, _)| self.make == *make && name.is_none_or(|n| self.name.as_deref() == Some(n)))
+            .map(|(_, _, mode)| *mode)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    /// Base block with a 800x1280 preferred timing, a name and a range descriptor.
+    fn edid(make: &str, name: &str, min_hz: u8, max_hz: u8) -> Vec<u8> {
+        let mut block = vec![0u8; BLOCK_LEN];
+        block[..8].copy_from_slice(&HEADER);
+        let id = make.bytes().fold(0u16, |id, c| (id << 5) | u16::from(c - b'@'));
+        block[8..10].copy_from_slice(&id.to_be_bytes());
+        block[10..12].copy_from_slice(&0x3003u16.to_le_bytes());
+
+        // Detailed timing: pixel clock, 800 (0x320) x 1280 (0x500)
+        block[54..56].copy_from_slice(&10_000u16.to_le_bytes());
+        block[56] = 0x20;
+        block[58] = 0x30;
+        block[59] = 0x00;
+        block[61] = 0x50;
+
+        block[75] = TAG_PRODUCT_NAME;
+        let mut text = [b' '; 13];
+        text[..name.len()].copy_from_slice(name.as_bytes());
+        if name.len() < 13 {
+            text[name.len()] = b'\n';
+        }
+        block[77..90].copy_from_slice(&text);
+
+        block[93] = TAG_RANGE_LIMITS;
+        block[95] = min_hz;
+        block[96] = max_hz;
+
+        let sum = block.iter().fold(0u8, |sum, b| sum.wrapping_add(*b));
+        block[127] = 0u8.wrapping_sub(sum);
+        block
+    }
+
+    #[test]
+    fn test_parse_panel() {
+        let info = PanelInfo::parse("card0-eDP-1", &edid("VLV", "Galileo", 45, 90)).unwrap();
+        assert_eq!(info.make, "VLV");
+        assert_eq!(info.product_code, 0x3003);
+        assert_eq!(info.name.as_deref(), Some("Galileo"));
+        assert_eq!((info.native_width, info.native_height), (Some(800), Some(1280)));
+        assert_eq!(info.hz_range(), Some(Hertz(45)..=Hertz(90)));
+        assert_eq!(info.device_mode(), Some(DeviceMode::Oled));
+
+        let lcd = PanelInfo::parse("card0-eDP-1", &edid("ANX", "", 40, 60)).unwrap();
+        assert_eq!(lcd.name, None);
+        assert_eq!(lcd.device_mode(), Some(DeviceMode::Lcd));
+
+        let other = PanelInfo::parse("card0-eDP-1", &edid("BOE", "NE070", 48, 120)).unwrap();
+        assert_eq!(other.device_mode(), None);
+        assert_eq!(other.hz_range(), Some(Hertz(48)..=Hertz(120)));
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid() {
+        assert!(matches!(PanelInfo::parse("eDP", &[0; 16]), Err(EdidError::TooShort(16))));
+        assert!(matches!(PanelInfo::parse("eDP", &[0; 128]), Err(EdidError::BadHeader)));
+        let mut corrupt = edid("VLV", "Galileo", 45, 90);
+        corrupt[100] ^= 1;
+        assert!(matches!(PanelInfo::parse("eDP", &corrupt), Err(EdidError::BadChecksum)));
+    }
+
+    #[test]
+    fn test_detect_internal_panel() {
+        let dir = tempdir().unwrap();
+        assert!(PanelInfo::detect(dir.path()).is_none());
+
+        for (connector, make) in [("card0-DP-1", "DEL"), ("card0-eDP-1", "VLV")] {
+            std::fs::create_dir(dir.path().join(connector)).unwrap();
+            std::fs::write(dir.path().join(connector).join("edid"), edid(make, "Jupiter", 40, 60)).unwrap();
+        }
+        let info = PanelInfo::detect(dir.path()).unwrap();
+        assert_eq!(info.connector, "card0-eDP-1");
+        assert_eq!(info.device_mode(), Some(DeviceMode::Lcd));
+    }
+}
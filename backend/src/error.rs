@@ -23,6 +23,34 @@ pub enum ShmError {
 
     #[error("Shared memory segment not available, MangoHud may not be running")]
     NotAvailable,
+
+    #[error("Unsupported MangoHud shared memory layout version {0}")]
+    UnsupportedVersion(u32),
+}
+
+/// Errors from an FPS source.
+#[derive(Error, Debug)]
+pub enum FpsSourceError {
+    #[error(transparent)]
+    Shm(#[from] ShmError),
+
+    #[error("No FPS source is available")]
+    NoneAvailable,
+
+    #[error("Gamescope stats path not found")]
+    GamescopeStatsNotFound,
+
+    #[error("No MangoHud log at the configured mangohud_log path")]
+    MangohudLogNotFound,
+
+    #[error("No lit internal panel CRTC to count page flips on")]
+    PanelCrtcNotFound,
+
+    #[error("FPS source closed by its writer")]
+    Closed,
+
+    #[error("FPS source read failed: {0}")]
+    Io(#[from] std::io::Error),
 }
 
 /// Errors related to profile management.
@@ -34,27 +62,63 @@ pub enum ProfileError {
     #[error("Failed to save profiles: {0}")]
     SaveFailed(String),
 
-    #[error("Profile not found for app_id: {0}")]
-    NotFound(String),
+    #[error("Profiles file '{path}' is locked by another process")]
+    LockContended { path: String },
 }
 
 /// Errors related to display control operations.
 #[derive(Error, Debug)]
 pub enum DisplayError {
-    #[error("gamescope-cmd not found in PATH")]
-    CommandNotFound,
+    #[error("{program} not found in PATH")]
+    CommandNotFound { program: String },
 
-    #[error("gamescope-cmd failed with exit code {exit_code:?}: {stderr}")]
+    #[error("{program} failed with exit code {exit_code:?}: {stderr}")]
     CommandFailed {
+        program: String,
         exit_code: Option<i32>,
         stderr: String,
     },
 
-    #[error("Requested refresh rate {requested}Hz is outside valid range [{min}-{max}Hz]")]
-    RateOutOfRange { requested: u32, min: u32, max: u32 },
+    #[error("The {backend} display backend does not support {operation}")]
+    Unsupported {
+        backend: &'static str,
+        operation: &'static str,
+    },
+
+    #[error("No enabled display output found: {0}")]
+    NoOutput(String),
 
     #[error("Failed to execute command: {0}")]
     ExecutionFailed(#[from] std::io::Error),
+
+    #[error("X11 request on display {display} failed: {reason}")]
+    X11 { display: String, reason: String },
+
+    #[error("Display backend unhealthy, switching paused for {retry_in_secs}s")]
+    BackendUnhealthy { retry_in_secs: u64 },
+}
+
+impl DisplayError {
+    /// Whether retrying the same call may succeed.
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            DisplayError::CommandFailed { .. } | DisplayError::ExecutionFailed(_) | DisplayError::X11 { .. }
+        )
+    }
+}
+
+/// Errors from parsing a panel's EDID.
+#[derive(Error, Debug)]
+pub enum EdidError {
+    #[error("EDID is {0} bytes, shorter than one block")]
+    TooShort(usize),
+
+    #[error("EDID header is missing")]
+    BadHeader,
+
+    #[error("EDID checksum mismatch")]
+    BadChecksum,
 }
 
 /// Errors related to IPC server operations.
@@ -67,6 +131,9 @@ pub enum IpcError {
         source: std::io::Error,
     },
 
+    #[error("Connection from uid {0} is not authorized")]
+    Unauthorized(u32),
+
     #[error("Client connection dropped unexpectedly")]
     ConnectionDropped,
 
@@ -76,6 +143,9 @@ pub enum IpcError {
     #[error("Failed to serialize response: {0}")]
     SerializationFailed(#[from] serde_json::Error),
 
+    #[error("Failed to encode MessagePack response: {0}")]
+    MsgpackFailed(#[from] rmp_serde::encode::Error),
+
     #[error("IO error: {0}")]
     IoError(#[from] std::io::Error),
 }
@@ -83,34 +153,83 @@ pub enum IpcError {
 /// Errors related to configuration management.
 #[derive(Error, Debug)]
 pub enum ConfigError {
-    #[error("Configuration file not found at '{0}'")]
-    FileNotFound(String),
-
     #[error("Failed to parse configuration: {0}")]
-    ParseError(String),
+    Parse(String),
 
     #[error("Configuration validation failed: {0}")]
-    ValidationError(String),
+    Validation(String),
 
     #[error("Failed to write configuration: {0}")]
-    WriteError(#[from] std::io::Error),
+    Write(#[from] std::io::Error),
 }
 
-/// Top-level daemon errors.
+/// Errors related to MQTT status publishing.
+#[cfg(feature = "mqtt")]
 #[derive(Error, Debug)]
-pub enum DaemonError {
-    #[error("Shared memory error: {0}")]
-    Shm(#[from] ShmError),
+pub enum MqttError {
+    #[error("Failed to connect to MQTT broker '{addr}': {source}")]
+    ConnectFailed {
+        addr: String,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("MQTT broker refused connection with return code {0}")]
+    ConnectionRefused(u8),
 
-    #[error("Display control error: {0}")]
-    Display(#[from] DisplayError),
+    #[error("Unexpected packet from MQTT broker: 0x{0:02x}")]
+    UnexpectedPacket(u8),
 
-    #[error("IPC error: {0}")]
-    Ipc(#[from] IpcError),
+    #[error("MQTT packet too large: {0} bytes")]
+    PacketTooLarge(usize),
+
+    #[error("MQTT IO error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// Errors starting a sandboxed hook command.
+#[derive(Error, Debug)]
+pub enum HookError {
+    #[error("Refusing to run hook as root (set hook_uid in the config)")]
+    RefusedAsRoot,
+
+    #[error("Failed to spawn hook: {0}")]
+    Spawn(#[source] std::io::Error),
+}
 
-    #[error("Configuration error: {0}")]
-    Config(#[from] ConfigError),
+/// Errors from the `install-service` subcommand.
+#[derive(Error, Debug)]
+pub enum InstallError {
+    #[error("HOME is not set to an absolute path")]
+    NoHome,
+
+    #[error("Failed to locate the daemon executable: {0}")]
+    CurrentExe(std::io::Error),
+
+    #[error("Failed to write '{path}': {source}")]
+    WriteFailed {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+}
+
+/// Missing dependencies detected by `--strict` startup checks.
+#[derive(Error, Debug, PartialEq)]
+pub enum StartupError {
+    #[error("display backend {0} unavailable")]
+    DisplayBackendUnavailable(String),
+
+    #[error("no FPS source available after {0}s")]
+    FpsSourceUnavailable(u64),
+}
 
-    #[error("Runtime error: {0}")]
-    Runtime(String),
+impl StartupError {
+    /// Process exit code reported for this failure.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            StartupError::DisplayBackendUnavailable(_) => 3,
+            StartupError::FpsSourceUnavailable(_) => 4,
+        }
+    }
 }
@@ -0,0 +1,183 @@
+//! Opt-in experimental behaviors.
+//!
+//! Risky features ship dark behind a flag here and check it at runtime, so
+//! users can try them (and turn them off again) over IPC without a new
+//! build. Flags start from the `experimental` config section and IPC changes
+//! are persisted back to it.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use serde::{Deserialize, Serialize};
+
+/// An experimental behavior gated behind a flag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Experiment {
+    /// Drop Hz ahead of a predicted FPS decline
+    PredictiveDrop,
+    /// Drive the VRR window instead of fixed refresh rates
+    VrrWindow,
+    /// Let battery power draw influence the chosen rate
+    PowerCoupling,
+}
+
+impl Experiment {
+    pub const ALL: [Experiment; 3] = [
+        Experiment::PredictiveDrop,
+        Experiment::VrrWindow,
+        Experiment::PowerCoupling,
+    ];
+
+    /// Parse an IPC experiment name (e.g. "predictive_drop").
+    pub fn parse(name: &str) -> Option<Self> {
+        serde_json::from_value(serde_json::Value::String(name.to_lowercase())).ok()
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Experiment::PredictiveDrop => "predictive_drop",
+            Experiment::VrrWindow => "vrr_window",
+            Experiment::PowerCoupling => "power_coupling",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Experiment::PredictiveDrop => "Drop Hz ahead of a predicted FPS decline",
+            Experiment::VrrWindow => "Drive the VRR window instead of fixed refresh rates",
+            Experiment::PowerCoupling => "Let battery power draw influence the chosen rate",
+        }
+    }
+}
+
+/// `experimental` config section. Unknown keys (flags removed in a later
+/// version) are ignored so old configs keep loading.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+pub struct ExperimentalConfig {
+    #[serde(default)]
+    pub predictive_drop: bool,
+    #[serde(default)]
+    pub vrr_window: bool,
+    #[serde(default)]
+    pub power_coupling: bool,
+}
+
+impl ExperimentalConfig {
+    pub fn get(&self, experiment: Experiment) -> bool {
+        match experiment {
+            Experiment::PredictiveDrop => self.predictive_drop,
+            Experiment::VrrWindow => self.vrr_window,
+            Experiment::PowerCoupling => self.power_coupling,
+        }
+    }
+
+    fn flag_mut(&mut self, experiment: Experiment) -> &mut bool {
+        match experiment {
+            Experiment::PredictiveDrop => &mut self.predictive_drop,
+            Experiment::VrrWindow => &mut self.vrr_window,
+            Experiment::PowerCoupling => &mut self.power_coupling,
+        }
+    }
+
+    /// Names of the enabled experiments.
+    pub fn enabled(&self) -> Vec<&'static str> {
+        Experiment::ALL
+            .iter()
+            .filter(|e| self.get(**e))
+            .map(|e| e.as_str())
+            .collect()
+    }
+}
+
+/// Runtime experiment flags, checked by the gated code paths.
+#[derive(Debug, Default)]
+pub struct Experiments {
+    predictive_drop: AtomicBool,
+    vrr_window: AtomicBool,
+    power_coupling: AtomicBool,
+}
+
+impl Experiments {
+    pub fn from_config(config: &ExperimentalConfig) -> Self {
+        let experiments = Self::default();
+        for experiment in Experiment::ALL {
+            experiments.set(experiment, config.get(experiment));
+        }
+        experiments
+    }
+
+    fn flag(&self, experiment: Experiment) -> &AtomicBool {
+        match experiment {
+            Experiment::PredictiveDrop => &self.predictive_drop,
+            Experiment::VrrWindow => &self.vrr_window,
+            Experiment::PowerCoupling => &self.power_coupling,
+        }
+    }
+
+    /// Whether an experiment is enabled.
+    pub fn is_enabled(&self, experiment: Experiment) -> bool {
+        self.flag(experiment).load(Ordering::Relaxed)
+    }
+
+    /// Enable or disable an experiment. Returns the previous value.
+    pub fn set(&self, experiment: Experiment, enabled: bool) -> bool {
+        self.flag(experiment).swap(enabled, Ordering::Relaxed)
+    }
+
+    /// Current flags, in config form.
+    pub fn snapshot(&self) -> ExperimentalConfig {
+        let mut config = ExperimentalConfig::default();
+        for experiment in Experiment::ALL {
+            *config.flag_mut(experiment) = self.is_enabled(experiment);
+        }
+        config
+    }
+}
+
+/// One experiment as reported by GetCapabilities.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ExperimentInfo {
+    pub name: Experiment,
+    pub enabled: bool,
+    pub description: String,
+}
+
+impl ExperimentInfo {
+    pub fn list(experiments: &Experiments) -> Vec<Self> {
+        Experiment::ALL
+            .iter()
+            .map(|e| Self {
+                name: *e,
+                enabled: experiments.is_enabled(*e),
+                description: e.description().to_string(),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_round_trips() {
+        for experiment in Experiment::ALL {
+            assert_eq!(Experiment::parse(experiment.as_str()), Some(experiment));
+        }
+        assert_eq!(Experiment::parse("VRR_Window"), Some(Experiment::VrrWindow));
+        assert_eq!(Experiment::parse("warp_drive"), None);
+    }
+
+    #[test]
+    fn test_config_round_trip() {
+        let config: ExperimentalConfig =
+            serde_json::from_str(r#"{"vrr_window":true,"retired_flag":true}"#).unwrap();
+        assert_eq!(config.enabled(), ["vrr_window"]);
+
+        let experiments = Experiments::from_config(&config);
+        assert!(experiments.is_enabled(Experiment::VrrWindow));
+        assert!(!experiments.set(Experiment::PowerCoupling, true));
+        assert!(experiments.set(Experiment::VrrWindow, false));
+        assert_eq!(experiments.snapshot().enabled(), ["power_coupling"]);
+    }
+}
@@ -0,0 +1,123 @@
+//! Optional haptic/sound feedback on mode changes.
+//!
+//! Runs a user-provided shell command when the daemon drops into its
+//! lowest refresh rate ("eco"), returns to its highest ("turbo"), or a
+//! manual override engages. The command receives the event in the
+//! `SMART_REFRESH_EVENT` environment variable and the rate in
+//! `SMART_REFRESH_HZ`, so one script can map events to a haptic pulse
+//! (e.g. via a Steam Input bridge) or a sound.
+
+use crate::notifications::EventKind;
+use crate::units::Hertz;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::{debug, warn};
+
+/// Minimum time between two feedback commands
+const FEEDBACK_MIN_INTERVAL_SECS: u64 = 5;
+
+/// Feedback commands that run longer than this are killed
+const FEEDBACK_TIMEOUT_SECS: u64 = 5;
+
+/// Rate-limited runner for the feedback command.
+pub struct FeedbackHook {
+    last_fired: Mutex<Option<Instant>>,
+    min_interval: Duration,
+}
+
+impl Default for FeedbackHook {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(FEEDBACK_MIN_INTERVAL_SECS))
+    }
+}
+
+impl FeedbackHook {
+    pub fn new(min_interval: Duration) -> Self {
+        Self {
+            last_fired: Mutex::new(None),
+            min_interval,
+        }
+    }
+
+    /// Run `command` for `event` unless feedback fired too recently.
+    ///
+    /// Returns true if the command was started. The command runs in the
+    /// background and never blocks the caller.
+    pub fn fire(&self, command: Option<&str>, event: EventKind, hz: Hertz) -> bool {
+        let Some(command) = command.filter(|c| !c.trim().is_empty()) else {
+            return false;
+        };
+
+        let now = Instant::now();
+        {
+            let Ok(mut last_fired) = self.last_fired.lock() else {
+                return false;
+            };
+            if last_fired.is_some_and(|last| now.duration_since(last) < self.min_interval) {
+                debug!("Skipping {} feedback, fired too recently", event.as_str());
+                return false;
+            }
+            *last_fired = Some(now);
+        }
+
+        let child = tokio::process::Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .env("SMART_REFRESH_EVENT", event.as_str())
+            .env("SMART_REFRESH_HZ", hz.to_string())
+            .stdin(std::process::Stdio::null())
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .kill_on_drop(true)
+            .spawn();
+
+        match child {
+            Ok(mut child) => {
+                tokio::spawn(async move {
+                    let timeout = Duration::from_secs(FEEDBACK_TIMEOUT_SECS);
+                    if tokio::time::timeout(timeout, child.wait()).await.is_err() {
+                        warn!("Feedback command timed out, killing it");
+                        let _ = child.kill().await;
+                    }
+                });
+                true
+            }
+            Err(e) => {
+                warn!("Failed to run feedback command: {}", e);
+                false
+            }
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_fire_runs_command_with_event() {
+        let dir = tempdir().unwrap();
+        let out = dir.path().join("event");
+        let command = format!("echo \"$SMART_REFRESH_EVENT $SMART_REFRESH_HZ\" > {}", out.display());
+
+        let hook = FeedbackHook::default();
+        assert!(hook.fire(Some(&command), EventKind::Eco, Hertz(40)));
+
+        for _ in 0..50 {
+            if out.exists() && !std::fs::read_to_string(&out).unwrap().is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert_eq!(std::fs::read_to_string(&out).unwrap().trim(), "eco 40");
+    }
+
+    #[tokio::test]
+    async fn test_fire_is_rate_limited() {
+        let hook = FeedbackHook::new(Duration::from_secs(60));
+        assert!(!hook.fire(None, EventKind::Turbo, Hertz(90)));
+        assert!(hook.fire(Some("true"), EventKind::Turbo, Hertz(90)));
+        assert!(!hook.fire(Some("true"), EventKind::Eco, Hertz(40)));
+    }
+}
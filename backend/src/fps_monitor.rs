@@ -4,16 +4,55 @@
 //! MangoHud's shared memory segment.
 
 use crate::error::ShmError;
+use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
+use std::path::Path;
 use std::sync::{Arc, Mutex};
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
-/// Shared memory segment name for MangoHud overlay data.
+/// Shared memory segment name for MangoHud overlay data. Probed when the
+/// config names no others.
 pub const MANGOHUD_SHM_NAME: &str = "/mangohud-overlay";
 
+/// Directory POSIX shared memory segments appear in.
+pub const SHM_DIR: &str = "/dev/shm";
+
 /// Ring buffer capacity for FPS samples (120 samples = 12 seconds at 100ms polling).
 pub const RING_BUFFER_CAPACITY: usize = 120;
 
+/// Magic at the start of a versioned segment ("MHUD", little endian).
+pub const MANGOHUD_SHM_MAGIC: u32 = u32::from_le_bytes(*b"MHUD");
+
+/// Newest versioned layout this reader understands.
+pub const MANGOHUD_SHM_VERSION: u32 = 2;
+
+/// Readings above this are treated as garbage rather than frame rates.
+pub const MAX_PLAUSIBLE_FPS: u64 = 1000;
+
+/// Default EMA weight of the newest sample (about a 1 second time
+/// constant at 100ms polling).
+pub const DEFAULT_EMA_ALPHA: f64 = 0.1;
+
+/// Valid EMA weights. Zero would never move.
+pub const MIN_EMA_ALPHA: f64 = 0.01;
+pub const MAX_EMA_ALPHA: f64 = 1.0;
+
+/// Share of samples dropped from each end for the trimmed mean.
+pub const TRIM_FRACTION: f64 = 0.1;
+
+/// Span the FPS spread is measured over for adaptive polling.
+pub const STABILITY_WINDOW: Duration = Duration::from_secs(2);
+
+/// Largest FPS spread (max - min) over `STABILITY_WINDOW` that counts as stable.
+pub const STABLE_FPS_SPREAD: u64 = 3;
+
+/// How long FPS has to stay stable before polling slows down.
+pub const STABLE_BEFORE_SLOWDOWN: Duration = Duration::from_secs(30);
+
+/// How long identical samples have to repeat before the FPS counts as
+/// frozen. Measured in time since adaptive polling varies the interval.
+pub const STALE_FPS_AFTER: Duration = Duration::from_secs(2);
+
 /// C-compatible struct matching MangoHud's shared memory layout.
 /// 
 /// This struct uses #[repr(C)] to ensure memory layout matches the C ABI,
@@ -40,6 +79,8 @@ impl MangoHudData {
     /// - `ptr` points to valid memory of at least `size_of::<MangoHudData>()` bytes
     /// - The memory is properly aligned for MangoHudData
     /// - The memory contains valid data in the expected C layout
+    // The reader decodes through `ShmLayout`; tests round-trip the C layout
+    #[cfg_attr(not(test), allow(dead_code))]
     pub unsafe fn from_raw_ptr(ptr: *const u8) -> Self {
         let data_ptr = ptr as *const MangoHudData;
         std::ptr::read_volatile(data_ptr)
@@ -51,15 +92,273 @@ impl MangoHudData {
     /// The caller must ensure that:
     /// - `ptr` points to valid, writable memory of at least `size_of::<MangoHudData>()` bytes
     /// - The memory is properly aligned for MangoHudData
-    pub unsafe fn to_raw_ptr(&self, ptr: *mut u8) {
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub unsafe fn to_raw_ptr(self, ptr: *mut u8) {
         let data_ptr = ptr as *mut MangoHudData;
-        std::ptr::write_volatile(data_ptr, *self);
+        std::ptr::write_volatile(data_ptr, self);
     }
 
     /// Get the size of the struct in bytes.
     pub const fn size() -> usize {
         std::mem::size_of::<MangoHudData>()
     }
+
+    /// Whether this looks like a real reading. Zeroes mean no frames yet;
+    /// an FPS over `MAX_PLAUSIBLE_FPS` or frames without a frametime mean
+    /// the layout doesn't match what MangoHud wrote.
+    pub fn is_plausible(&self) -> bool {
+        self.fps_val <= MAX_PLAUSIBLE_FPS && (self.fps_val == 0 || self.frametime > 0)
+    }
+
+    /// Convert a float reading (frametime in milliseconds). NaN, infinite
+    /// or negative values become an implausible reading rather than 0.
+    pub fn from_float(fps: f64, frametime_ms: f64) -> Self {
+        if !fps.is_finite() || !frametime_ms.is_finite() || fps < 0.0 || frametime_ms < 0.0 {
+            return Self::new(u64::MAX, 0);
+        }
+        Self::new(fps.round().min(u64::MAX as f64) as u64, (frametime_ms * 1000.0).round() as u64)
+    }
+}
+
+/// Replaces implausible readings with the last good one.
+#[derive(Debug, Default)]
+pub struct ReadingFilter {
+    last_valid: MangoHudData,
+    /// Readings rejected in a row
+    rejected: u64,
+}
+
+impl ReadingFilter {
+    /// `data` if plausible, else the last plausible reading. Warns at the
+    /// start of each run of rejected readings.
+    pub fn apply(&mut self, data: MangoHudData) -> MangoHudData {
+        if data.is_plausible() {
+            self.last_valid = data;
+            self.rejected = 0;
+            return data;
+        }
+        if self.rejected == 0 {
+            tracing::warn!(
+                "Rejected MangoHud reading (fps {}, frametime {}), its shared memory layout may have changed",
+                data.fps_val,
+                data.frametime
+            );
+        }
+        self.rejected += 1;
+        self.last_valid
+    }
+
+    /// Readings rejected since the last plausible one.
+    // Only tests read the count back
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn rejected(&self) -> u64 {
+        self.rejected
+    }
+}
+
+/// Header in front of `MangoHudData` in a versioned segment.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MangoHudHeader {
+    pub magic: u32,
+    pub version: u32,
+}
+
+/// Shared memory layouts the reader accepts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShmLayout {
+    /// Bare `MangoHudData`, the original 16-byte segment
+    Legacy,
+    /// Bare f64 FPS and frametime (ms) in the same 16 bytes, as newer
+    /// builds without a header write it
+    LegacyFloat,
+    /// `MangoHudHeader` followed by the record for that version: version 1
+    /// is `MangoHudData`, version 2 an f32 FPS and frametime (ms) followed
+    /// by fields this reader skips
+    Versioned(u32),
+}
+
+/// Bytes read from the segment per poll, enough for any record.
+const RECORD_SIZE: usize = 16;
+
+impl ShmLayout {
+    /// Work out the layout of a mapped segment, rejecting sizes and
+    /// versions that don't match anything known. Headerless segments are
+    /// probed: a reading that only makes sense as floats means `LegacyFloat`.
+    pub fn detect(segment: &[u8]) -> Result<Self, ShmError> {
+        let header_size = std::mem::size_of::<MangoHudHeader>();
+        if segment.len() < MangoHudData::size() {
+            return Err(ShmError::InvalidData(format!(
+                "segment is {} bytes, expected at least {}",
+                segment.len(),
+                MangoHudData::size()
+            )));
+        }
+        let magic = u32::from_le_bytes(segment[0..4].try_into().unwrap_or_default());
+        if magic != MANGOHUD_SHM_MAGIC {
+            if segment.len() != MangoHudData::size() {
+                return Err(ShmError::InvalidData(format!(
+                    "segment is {} bytes without a layout header",
+                    segment.len()
+                )));
+            }
+            let record: [u8; RECORD_SIZE] = segment[..RECORD_SIZE].try_into().unwrap_or_default();
+            return Ok(ShmLayout::Legacy.reprobe(&record).unwrap_or(ShmLayout::Legacy));
+        }
+        let version = u32::from_le_bytes(segment[4..8].try_into().unwrap_or_default());
+        if version == 0 || version > MANGOHUD_SHM_VERSION {
+            return Err(ShmError::UnsupportedVersion(version));
+        }
+        let layout = ShmLayout::Versioned(version);
+        if segment.len() < header_size + layout.record_size() {
+            return Err(ShmError::InvalidData(format!(
+                "version {} segment is only {} bytes",
+                version,
+                segment.len()
+            )));
+        }
+        Ok(layout)
+    }
+
+    /// Byte offset of the record in the segment.
+    pub fn data_offset(&self) -> usize {
+        match self {
+            ShmLayout::Legacy | ShmLayout::LegacyFloat => 0,
+            ShmLayout::Versioned(_) => std::mem::size_of::<MangoHudHeader>(),
+        }
+    }
+
+    /// Bytes of the record this reader uses.
+    pub fn record_size(&self) -> usize {
+        match self {
+            ShmLayout::Versioned(2) => 8,
+            _ => MangoHudData::size(),
+        }
+    }
+
+    /// Decode a record read from `data_offset` (bytes past `record_size`
+    /// are ignored).
+    pub fn decode(&self, record: &[u8; RECORD_SIZE]) -> MangoHudData {
+        let u64_at = |offset: usize| u64::from_ne_bytes(record[offset..offset + 8].try_into().unwrap_or_default());
+        let f64_at = |offset: usize| f64::from_ne_bytes(record[offset..offset + 8].try_into().unwrap_or_default());
+        let f32_at = |offset: usize| f32::from_ne_bytes(record[offset..offset + 4].try_into().unwrap_or_default());
+        match self {
+            ShmLayout::Legacy | ShmLayout::Versioned(1) => MangoHudData::new(u64_at(0), u64_at(8)),
+            ShmLayout::LegacyFloat => MangoHudData::from_float(f64_at(0), f64_at(8)),
+            ShmLayout::Versioned(_) => MangoHudData::from_float(f32_at(0).into(), f32_at(4).into()),
+        }
+    }
+
+    /// The other headerless layout, if `record` is a plausible nonzero
+    /// reading in that one but implausible or zero in this. Integers read as
+    /// floats come out as tiny denormals, and floats read as integers as
+    /// huge values, so a real reading only fits one of them. Versioned
+    /// layouts are never guessed.
+    pub fn reprobe(&self, record: &[u8; RECORD_SIZE]) -> Option<Self> {
+        let other = match self {
+            ShmLayout::Legacy => ShmLayout::LegacyFloat,
+            ShmLayout::LegacyFloat => ShmLayout::Legacy,
+            ShmLayout::Versioned(_) => return None,
+        };
+        let current = self.decode(record);
+        let data = other.decode(record);
+        let fits_other = data.is_plausible() && data.fps_val > 0;
+        (fits_other && (!current.is_plausible() || current.fps_val == 0)).then_some(other)
+    }
+}
+
+impl std::fmt::Display for ShmLayout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ShmLayout::Legacy => write!(f, "legacy (integer FPS)"),
+            ShmLayout::LegacyFloat => write!(f, "legacy (float FPS)"),
+            ShmLayout::Versioned(1) => write!(f, "version 1 (integer FPS)"),
+            ShmLayout::Versioned(version) => write!(f, "version {} (float FPS)", version),
+        }
+    }
+}
+
+/// A MangoHud shared memory segment found in `SHM_DIR`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShmSegment {
+    /// Name to pass to `shm_open` (with the leading '/')
+    pub name: String,
+    /// Process the segment belongs to, for per-process `<name>-<pid>` segments
+    pub pid: Option<u32>,
+}
+
+/// Segments in `shm_dir` for `names`: each name itself and its
+/// per-process `<name>-<pid>` variants, as written when several Vulkan
+/// apps run MangoHud at once.
+pub fn find_segments(shm_dir: &Path, names: &[String]) -> Vec<ShmSegment> {
+    let Ok(entries) = std::fs::read_dir(shm_dir) else {
+        return Vec::new();
+    };
+    let files: Vec<String> = entries
+        .flatten()
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .collect();
+    let mut segments = Vec::new();
+    for name in names {
+        for file in &files {
+            if let Some(pid) = segment_pid(file, name) {
+                segments.push(ShmSegment { name: format!("/{}", file), pid });
+            }
+        }
+    }
+    segments
+}
+
+/// Whether `file` in `/dev/shm` is the segment `name` (`Some(None)`) or a
+/// per-process `<name>-<pid>` one (`Some(Some(pid))`).
+fn segment_pid(file: &str, name: &str) -> Option<Option<u32>> {
+    match file.strip_prefix(name.trim_start_matches('/'))? {
+        "" => Some(None),
+        suffix => suffix.strip_prefix('-')?.parse().ok().map(Some),
+    }
+}
+
+/// Whether `file` in `/dev/shm` is one of the segments `names`, shared or
+/// per process.
+pub fn is_segment_file(file: &str, names: &[String]) -> bool {
+    names.iter().any(|name| segment_pid(file, name).is_some())
+}
+
+/// The segment to read: the one whose process runs `app_id` (Steam sets
+/// `SteamAppId` in game environments), else the shared one, else the
+/// newest process's. Segments left behind by exited processes are skipped.
+pub fn pick_segment<'a>(segments: &'a [ShmSegment], proc_dir: &Path, app_id: Option<u32>) -> Option<&'a ShmSegment> {
+    let live = || {
+        segments
+            .iter()
+            .filter(|segment| segment.pid.is_none_or(|pid| process_alive(proc_dir, pid)))
+    };
+    if let Some(app_id) = app_id {
+        let found = live().find(|segment| segment.pid.is_some_and(|pid| runs_app(proc_dir, pid, app_id)));
+        if found.is_some() {
+            return found;
+        }
+    }
+    live()
+        .find(|segment| segment.pid.is_none())
+        .or_else(|| live().max_by_key(|segment| segment.pid))
+}
+
+/// Whether process `pid` still exists.
+pub fn process_alive(proc_dir: &Path, pid: u32) -> bool {
+    proc_dir.join(pid.to_string()).exists()
+}
+
+/// Whether process `pid` was launched by Steam for `app_id`.
+fn runs_app(proc_dir: &Path, pid: u32, app_id: u32) -> bool {
+    let Ok(environ) = std::fs::read(proc_dir.join(pid.to_string()).join("environ")) else {
+        return false;
+    };
+    let app_id = app_id.to_string();
+    environ.split(|&b| b == 0).any(|var| {
+        let var = String::from_utf8_lossy(var);
+        matches!(var.split_once('='), Some(("SteamAppId" | "SteamGameId", value)) if value == app_id)
+    })
 }
 
 /// A single FPS sample with timestamp.
@@ -84,6 +383,8 @@ impl FpsSample {
     }
 
     /// Create a new FPS sample with a specific timestamp.
+    // Tests build sample sequences at fixed times
+    #[cfg_attr(not(test), allow(dead_code))]
     pub fn with_timestamp(fps: u64, frametime: u64, timestamp: Instant) -> Self {
         Self {
             fps,
@@ -93,6 +394,110 @@ impl FpsSample {
     }
 }
 
+/// How polled FPS is smoothed before the controller sees it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FpsSmoothing {
+    /// Mean of the source's ring buffer
+    #[default]
+    Mean,
+    /// Exponential moving average weighted by `fps_ema_alpha`
+    Ema,
+    /// Median of the ring buffer
+    Median,
+    /// Ring buffer mean without the highest and lowest `TRIM_FRACTION`
+    TrimmedMean,
+}
+
+/// Exponential moving average of FPS.
+#[derive(Debug, Default)]
+pub struct EmaSmoother {
+    value: Option<f64>,
+}
+
+impl EmaSmoother {
+    /// Fold in `fps` with weight `alpha` and return the average. The first
+    /// sample starts it.
+    pub fn update(&mut self, fps: f64, alpha: f64) -> f64 {
+        let value = match self.value {
+            Some(previous) => previous + alpha.clamp(MIN_EMA_ALPHA, MAX_EMA_ALPHA) * (fps - previous),
+            None => fps,
+        };
+        self.value = Some(value);
+        value
+    }
+}
+
+/// Picks the polling interval: `slow` once FPS has held steady for
+/// `STABLE_BEFORE_SLOWDOWN`, `fast` as soon as it moves again.
+#[derive(Debug)]
+pub struct AdaptivePoller {
+    fast: Duration,
+    slow: Duration,
+    recent: VecDeque<(Instant, u64)>,
+    stable_since: Option<Instant>,
+}
+
+impl AdaptivePoller {
+    pub fn new(fast: Duration, slow: Duration) -> Self {
+        Self { fast, slow, recent: VecDeque::new(), stable_since: None }
+    }
+
+    /// Feed the FPS polled at `now` and return the interval to poll at next.
+    /// No frames count as unsettled, so a game starting is seen promptly.
+    pub fn observe(&mut self, now: Instant, fps: u64) -> Duration {
+        self.recent.push_back((now, fps));
+        while self
+            .recent
+            .front()
+            .is_some_and(|&(at, _)| now.saturating_duration_since(at) > STABILITY_WINDOW)
+        {
+            self.recent.pop_front();
+        }
+        let (min, max) = self
+            .recent
+            .iter()
+            .fold((u64::MAX, 0), |(min, max), &(_, fps)| (min.min(fps), max.max(fps)));
+        if fps == 0 || max - min > STABLE_FPS_SPREAD {
+            self.stable_since = None;
+            return self.fast;
+        }
+        let since = *self.stable_since.get_or_insert(now);
+        if now.saturating_duration_since(since) >= STABLE_BEFORE_SLOWDOWN {
+            self.slow
+        } else {
+            self.fast
+        }
+    }
+}
+
+/// Notices when a source keeps repeating the same sample, as MangoHud's
+/// shared memory does while the game is paused or hung.
+#[derive(Debug, Default)]
+pub struct StaleDetector {
+    last: Option<(u64, u64)>,
+    /// When the samples started repeating `last`
+    repeated_since: Option<Instant>,
+}
+
+impl StaleDetector {
+    /// Feed the next sample and return whether the FPS is stale: the same
+    /// nonzero FPS and frametime for `STALE_FPS_AFTER`. Real frametimes
+    /// jitter by a few microseconds, so exact repeats mean nothing is
+    /// updating them.
+    pub fn observe(&mut self, sample: &FpsSample) -> bool {
+        let values = (sample.fps, sample.frametime);
+        if sample.fps > 0 && self.last == Some(values) {
+            let since = *self.repeated_since.get_or_insert(sample.timestamp);
+            self.last = Some(values);
+            return sample.timestamp.saturating_duration_since(since) >= STALE_FPS_AFTER;
+        }
+        self.repeated_since = None;
+        self.last = Some(values);
+        false
+    }
+}
+
 /// Ring buffer for storing FPS samples with fixed capacity.
 /// 
 /// Maintains the last 120 samples for data smoothing and analysis.
@@ -134,6 +539,45 @@ impl FpsRingBuffer {
         sum as f64 / self.samples.len() as f64
     }
 
+    /// Get the median FPS in the buffer, the mean of the middle two for an
+    /// even count. Returns 0.0 if the buffer is empty.
+    pub fn median(&self) -> f64 {
+        let fps = self.sorted_fps();
+        match fps.len() {
+            0 => 0.0,
+            len if len % 2 == 1 => fps[len / 2] as f64,
+            len => (fps[len / 2 - 1] + fps[len / 2]) as f64 / 2.0,
+        }
+    }
+
+    /// Get the mean FPS without the highest and lowest `fraction` of the
+    /// samples. Returns 0.0 if the buffer is empty.
+    pub fn trimmed_mean(&self, fraction: f64) -> f64 {
+        let fps = self.sorted_fps();
+        let trim = (fps.len() as f64 * fraction.clamp(0.0, 0.49)) as usize;
+        let kept = &fps[trim..fps.len() - trim];
+        if kept.is_empty() {
+            return 0.0;
+        }
+        kept.iter().sum::<u64>() as f64 / kept.len() as f64
+    }
+
+    /// Get the smoothed FPS for `smoothing`. EMA is kept by the caller
+    /// across samples, so the buffer answers it with the mean.
+    pub fn smoothed(&self, smoothing: FpsSmoothing) -> f64 {
+        match smoothing {
+            FpsSmoothing::Mean | FpsSmoothing::Ema => self.average(),
+            FpsSmoothing::Median => self.median(),
+            FpsSmoothing::TrimmedMean => self.trimmed_mean(TRIM_FRACTION),
+        }
+    }
+
+    fn sorted_fps(&self) -> Vec<u64> {
+        let mut fps: Vec<u64> = self.samples.iter().map(|s| s.fps).collect();
+        fps.sort_unstable();
+        fps
+    }
+
     /// Get the percentile value of frametimes in the buffer.
     /// 
     /// # Arguments
@@ -153,35 +597,23 @@ impl FpsRingBuffer {
     }
 
     /// Get the current number of samples in the buffer.
+    // The daemon only reads aggregates; tests inspect the samples
+    #[cfg_attr(not(test), allow(dead_code))]
     pub fn len(&self) -> usize {
         self.samples.len()
     }
 
-    /// Check if the buffer is empty.
-    pub fn is_empty(&self) -> bool {
-        self.samples.is_empty()
-    }
-
-    /// Get the capacity of the buffer.
-    pub fn capacity(&self) -> usize {
-        self.capacity
-    }
-
-    /// Clear all samples from the buffer.
-    pub fn clear(&mut self) {
-        self.samples.clear();
-    }
-
-    /// Get samples within a specific time window from now.
-    pub fn samples_in_window(&self, duration: std::time::Duration) -> Vec<&FpsSample> {
-        let cutoff = Instant::now() - duration;
-        self.samples
-            .iter()
-            .filter(|s| s.timestamp >= cutoff)
-            .collect()
+    /// Change the capacity, dropping the oldest samples if it shrinks.
+    pub fn set_capacity(&mut self, capacity: usize) {
+        let capacity = capacity.max(1);
+        while self.samples.len() > capacity {
+            self.samples.pop_front();
+        }
+        self.capacity = capacity;
     }
 
     /// Get an iterator over all samples.
+    #[cfg_attr(not(test), allow(dead_code))]
     pub fn iter(&self) -> impl Iterator<Item = &FpsSample> {
         self.samples.iter()
     }
@@ -207,6 +639,13 @@ pub struct MangoHudReader {
     ring_buffer: Arc<Mutex<FpsRingBuffer>>,
     /// Size of the mapped memory region.
     shm_size: usize,
+    /// Layout found when the segment was opened, re-probed while readings
+    /// don't fit it.
+    layout: Mutex<ShmLayout>,
+    /// Drops implausible readings.
+    filter: Mutex<ReadingFilter>,
+    /// Process of a per-process segment.
+    pid: Option<u32>,
 }
 
 #[cfg(target_family = "unix")]
@@ -220,17 +659,48 @@ unsafe impl Sync for MangoHudReader {}
 
 #[cfg(target_family = "unix")]
 impl MangoHudReader {
-    /// Connect to MangoHud shared memory segment.
-    /// 
-    /// Opens the shared memory segment named "/mangohud-overlay" and maps it
-    /// into the process address space for reading.
-    pub fn new() -> Result<Self, ShmError> {
+    /// Connect to the first of `names` that opens, logging which one it
+    /// was. The error is the last name's.
+    pub fn open_any(names: &[String]) -> Result<Self, ShmError> {
+        let mut last_error = ShmError::NotAvailable;
+        for name in names {
+            match Self::open(name) {
+                Ok(reader) => {
+                    tracing::info!("Found MangoHud shared memory {}", name);
+                    return Ok(reader);
+                }
+                Err(e) => last_error = e,
+            }
+        }
+        Err(last_error)
+    }
+
+    /// Connect to the segment of the process running `app_id` when
+    /// MangoHud exposes one per process, otherwise as `open_any`.
+    pub fn open_for_app(names: &[String], app_id: Option<u32>) -> Result<Self, ShmError> {
+        let segments = find_segments(Path::new(SHM_DIR), names);
+        let Some(segment) = pick_segment(&segments, Path::new("/proc"), app_id) else {
+            return Self::open_any(names);
+        };
+        let mut reader = Self::open(&segment.name)?;
+        reader.pid = segment.pid;
+        match segment.pid {
+            Some(pid) => tracing::info!("Found MangoHud shared memory {} (PID {})", segment.name, pid),
+            None => tracing::info!("Found MangoHud shared memory {}", segment.name),
+        }
+        Ok(reader)
+    }
+
+    /// Open the shared memory segment `name` and map it into the process
+    /// address space for reading. The whole segment is mapped so its size
+    /// and layout header can be checked.
+    pub fn open(name: &str) -> Result<Self, ShmError> {
         use libc::{
-            c_char, close, mmap, shm_open, MAP_FAILED, MAP_SHARED, O_RDONLY, PROT_READ,
+            c_char, close, fstat, mmap, munmap, shm_open, MAP_FAILED, MAP_SHARED, O_RDONLY, PROT_READ,
         };
         use std::ffi::CString;
 
-        let shm_name = CString::new(MANGOHUD_SHM_NAME)
+        let shm_name = CString::new(name)
             .map_err(|_| ShmError::InvalidData("Invalid SHM name".to_string()))?;
 
         // Open the shared memory segment
@@ -238,12 +708,26 @@ impl MangoHudReader {
 
         if shm_fd < 0 {
             return Err(ShmError::OpenFailed {
-                name: MANGOHUD_SHM_NAME.to_string(),
+                name: name.to_string(),
                 source: std::io::Error::last_os_error(),
             });
         }
 
-        let shm_size = MangoHudData::size();
+        let mut stat: libc::stat = unsafe { std::mem::zeroed() };
+        if unsafe { fstat(shm_fd, &mut stat) } < 0 {
+            let error = std::io::Error::last_os_error();
+            unsafe { close(shm_fd) };
+            return Err(ShmError::MmapFailed(error));
+        }
+        let shm_size = stat.st_size.max(0) as usize;
+        if shm_size < MangoHudData::size() {
+            unsafe { close(shm_fd) };
+            return Err(ShmError::InvalidData(format!(
+                "segment is {} bytes, expected at least {}",
+                shm_size,
+                MangoHudData::size()
+            )));
+        }
 
         // Map the shared memory into our address space
         let shm_ptr = unsafe {
@@ -262,20 +746,56 @@ impl MangoHudReader {
             return Err(ShmError::MmapFailed(std::io::Error::last_os_error()));
         }
 
+        let segment = unsafe { std::slice::from_raw_parts(shm_ptr as *const u8, shm_size) };
+        let layout = match ShmLayout::detect(segment) {
+            Ok(layout) => layout,
+            Err(e) => {
+                unsafe {
+                    munmap(shm_ptr, shm_size);
+                    close(shm_fd);
+                }
+                return Err(e);
+            }
+        };
+        tracing::info!("MangoHud shared memory {}: {} bytes, {} layout", name, shm_size, layout);
+
         Ok(Self {
             shm_ptr: shm_ptr as *const MangoHudData,
             shm_fd,
             ring_buffer: Arc::new(Mutex::new(FpsRingBuffer::new())),
             shm_size,
+            layout: Mutex::new(layout),
+            filter: Mutex::new(ReadingFilter::default()),
+            pid: None,
         })
     }
 
     /// Poll current FPS data from shared memory.
     /// 
     /// Reads the current FPS and frametime values from the MangoHud shared
-    /// memory segment and adds them to the ring buffer.
+    /// memory segment and adds them to the ring buffer. Implausible readings
+    /// are dropped in favour of the last good one.
     pub fn poll(&self) -> Result<FpsSample, ShmError> {
-        let data = unsafe { MangoHudData::from_raw_ptr(self.shm_ptr as *const u8) };
+        let mut layout = self.layout.lock().map(|layout| *layout).unwrap_or(ShmLayout::Legacy);
+        let mut record = [0u8; RECORD_SIZE];
+        let base = unsafe { (self.shm_ptr as *const u8).add(layout.data_offset()) };
+        for (i, byte) in record.iter_mut().take(layout.record_size()).enumerate() {
+            // SAFETY: `detect` checked the mapping holds the record at this offset
+            *byte = unsafe { std::ptr::read_volatile(base.add(i)) };
+        }
+        // A headerless segment read as zeroes at open may turn out to hold floats
+        if let Some(probed) = layout.reprobe(&record) {
+            tracing::info!("MangoHud shared memory readings fit the {} layout, switching from {}", probed, layout);
+            layout = probed;
+            if let Ok(mut current) = self.layout.lock() {
+                *current = probed;
+            }
+        }
+        let data = layout.decode(&record);
+        let data = match self.filter.lock() {
+            Ok(mut filter) => filter.apply(data),
+            Err(_) => data,
+        };
 
         let sample = FpsSample::new(data.fps_val, data.frametime);
 
@@ -287,14 +807,21 @@ impl MangoHudReader {
         Ok(sample)
     }
 
-    /// Get smoothed FPS average from ring buffer.
-    pub fn get_smoothed_fps(&self) -> f64 {
+    /// Get smoothed FPS from ring buffer.
+    pub fn get_smoothed_fps(&self, smoothing: FpsSmoothing) -> f64 {
         self.ring_buffer
             .lock()
-            .map(|buffer| buffer.average())
+            .map(|buffer| buffer.smoothed(smoothing))
             .unwrap_or(0.0)
     }
 
+    /// Resize the ring buffer to `capacity` samples.
+    pub fn set_window(&self, capacity: usize) {
+        if let Ok(mut buffer) = self.ring_buffer.lock() {
+            buffer.set_capacity(capacity);
+        }
+    }
+
     /// Get P99 frametime from ring buffer.
     pub fn get_p99_frametime(&self) -> u64 {
         self.ring_buffer
@@ -303,9 +830,9 @@ impl MangoHudReader {
             .unwrap_or(0)
     }
 
-    /// Get a clone of the ring buffer Arc for external access.
-    pub fn get_ring_buffer(&self) -> Arc<Mutex<FpsRingBuffer>> {
-        Arc::clone(&self.ring_buffer)
+    /// Process whose per-process segment is being read, if any.
+    pub fn pid(&self) -> Option<u32> {
+        self.pid
     }
 }
 
@@ -334,35 +861,44 @@ impl Drop for MangoHudReader {
 /// The actual daemon only runs on Linux (SteamOS).
 #[cfg(not(target_family = "unix"))]
 pub struct MangoHudReader {
-    ring_buffer: Arc<Mutex<FpsRingBuffer>>,
+    /// Never constructed: every constructor reports `NotAvailable`
+    _private: (),
 }
 
 #[cfg(not(target_family = "unix"))]
 impl MangoHudReader {
     /// Stub: Returns NotAvailable on non-Unix platforms.
-    pub fn new() -> Result<Self, ShmError> {
+    pub fn open_any(_names: &[String]) -> Result<Self, ShmError> {
         Err(ShmError::NotAvailable)
     }
 
+    /// Stub: Returns NotAvailable on non-Unix platforms.
+    pub fn open_for_app(_names: &[String], _app_id: Option<u32>) -> Result<Self, ShmError> {
+        Err(ShmError::NotAvailable)
+    }
+
+    /// Stub: Returns None on non-Unix platforms.
+    pub fn pid(&self) -> Option<u32> {
+        None
+    }
+
     /// Stub: Returns NotAvailable on non-Unix platforms.
     pub fn poll(&self) -> Result<FpsSample, ShmError> {
         Err(ShmError::NotAvailable)
     }
 
     /// Stub: Returns 0.0 on non-Unix platforms.
-    pub fn get_smoothed_fps(&self) -> f64 {
+    pub fn get_smoothed_fps(&self, _smoothing: FpsSmoothing) -> f64 {
         0.0
     }
 
+    /// Stub: Does nothing on non-Unix platforms.
+    pub fn set_window(&self, _capacity: usize) {}
+
     /// Stub: Returns 0 on non-Unix platforms.
     pub fn get_p99_frametime(&self) -> u64 {
         0
     }
-
-    /// Get a clone of the ring buffer Arc for external access.
-    pub fn get_ring_buffer(&self) -> Arc<Mutex<FpsRingBuffer>> {
-        Arc::clone(&self.ring_buffer)
-    }
 }
 
 #[cfg(test)]
@@ -436,6 +972,171 @@ mod tests {
         assert_eq!(MangoHudData::size(), 16);
     }
 
+    #[test]
+    fn test_pick_segment_for_focused_app() {
+        let shm_dir = tempfile::tempdir().unwrap();
+        let proc_dir = tempfile::tempdir().unwrap();
+        for file in ["mangohud-overlay-4100", "mangohud-overlay-5200", "mangohud-overlay-x", "pulse-shm-1"] {
+            std::fs::write(shm_dir.path().join(file), b"").unwrap();
+        }
+        for (pid, environ) in [(4100, "HOME=/home/deck\0SteamAppId=1091500\0"), (5200, "SteamAppId=0\0")] {
+            std::fs::create_dir(proc_dir.path().join(pid.to_string())).unwrap();
+            std::fs::write(proc_dir.path().join(pid.to_string()).join("environ"), environ).unwrap();
+        }
+
+        let mut segments = find_segments(shm_dir.path(), &[MANGOHUD_SHM_NAME.to_string()]);
+        segments.sort_by_key(|segment| segment.pid);
+        assert_eq!(segments.iter().map(|segment| segment.pid).collect::<Vec<_>>(), vec![Some(4100), Some(5200)]);
+        assert_eq!(segments[0].name, "/mangohud-overlay-4100");
+
+        let names = [MANGOHUD_SHM_NAME.to_string()];
+        assert!(is_segment_file("mangohud-overlay-4100", &names));
+        assert!(!is_segment_file("mangohud-overlay-x", &names));
+
+        let pick = |app_id| pick_segment(&segments, proc_dir.path(), app_id).and_then(|segment| segment.pid);
+        assert_eq!(pick(Some(1091500)), Some(4100));
+        // Unknown focus: the newest process
+        assert_eq!(pick(None), Some(5200));
+        assert_eq!(pick(Some(620)), Some(5200));
+
+        // Segments of exited processes are ignored
+        std::fs::write(shm_dir.path().join("mangohud-overlay-6300"), b"").unwrap();
+        let segments = find_segments(shm_dir.path(), &[MANGOHUD_SHM_NAME.to_string()]);
+        assert_eq!(pick_segment(&segments, proc_dir.path(), None).unwrap().pid, Some(5200));
+
+        // A shared segment wins when the focused app has none of its own
+        std::fs::write(shm_dir.path().join("mangohud-overlay"), b"").unwrap();
+        let segments = find_segments(shm_dir.path(), &[MANGOHUD_SHM_NAME.to_string()]);
+        assert_eq!(pick_segment(&segments, proc_dir.path(), Some(620)).unwrap().pid, None);
+    }
+
+    #[test]
+    fn test_shm_layout_detect() {
+        let legacy = vec![0u8; MangoHudData::size()];
+        assert_eq!(ShmLayout::detect(&legacy).unwrap(), ShmLayout::Legacy);
+        assert!(matches!(ShmLayout::detect(&legacy[..8]), Err(ShmError::InvalidData(_))));
+        assert!(matches!(ShmLayout::detect(&[0u8; 64]), Err(ShmError::InvalidData(_))));
+
+        let mut versioned = vec![0u8; 24];
+        versioned[0..4].copy_from_slice(&MANGOHUD_SHM_MAGIC.to_le_bytes());
+        versioned[4..8].copy_from_slice(&1u32.to_le_bytes());
+        let layout = ShmLayout::detect(&versioned).unwrap();
+        assert_eq!(layout, ShmLayout::Versioned(1));
+        assert_eq!(layout.data_offset(), 8);
+        assert!(matches!(ShmLayout::detect(&versioned[..16]), Err(ShmError::InvalidData(_))));
+
+        versioned[4..8].copy_from_slice(&(MANGOHUD_SHM_VERSION + 1).to_le_bytes());
+        assert!(matches!(ShmLayout::detect(&versioned), Err(ShmError::UnsupportedVersion(3))));
+    }
+
+    #[test]
+    fn test_float_layouts() {
+        // Version 2: f32 FPS and frametime (ms), then fields the reader skips
+        let mut segment = vec![0u8; 32];
+        segment[0..4].copy_from_slice(&MANGOHUD_SHM_MAGIC.to_le_bytes());
+        segment[4..8].copy_from_slice(&2u32.to_le_bytes());
+        segment[8..12].copy_from_slice(&59.6f32.to_ne_bytes());
+        segment[12..16].copy_from_slice(&16.7f32.to_ne_bytes());
+        segment[16..24].copy_from_slice(&12345u64.to_ne_bytes());
+        let layout = ShmLayout::detect(&segment).unwrap();
+        assert_eq!(layout, ShmLayout::Versioned(2));
+        let record: [u8; RECORD_SIZE] = segment[8..24].try_into().unwrap();
+        assert_eq!(layout.decode(&record), MangoHudData::new(60, 16_700));
+        assert!(ShmLayout::detect(&segment[..16]).is_ok());
+        assert!(matches!(ShmLayout::detect(&segment[..15]), Err(ShmError::InvalidData(_))));
+
+        // Headerless f64 pair is told apart from the u64 pair by probing
+        let mut legacy = [0u8; RECORD_SIZE];
+        legacy[0..8].copy_from_slice(&144.0f64.to_ne_bytes());
+        legacy[8..16].copy_from_slice(&6.94f64.to_ne_bytes());
+        let layout = ShmLayout::detect(&legacy).unwrap();
+        assert_eq!(layout, ShmLayout::LegacyFloat);
+        assert_eq!(layout.decode(&legacy), MangoHudData::new(144, 6_940));
+
+        // Zeroes read as the original layout until real readings arrive
+        let zeroes = [0u8; RECORD_SIZE];
+        assert_eq!(ShmLayout::detect(&zeroes).unwrap(), ShmLayout::Legacy);
+        assert_eq!(ShmLayout::Legacy.reprobe(&zeroes), None);
+        assert_eq!(ShmLayout::Legacy.reprobe(&legacy), Some(ShmLayout::LegacyFloat));
+        let mut integers = [0u8; RECORD_SIZE];
+        integers[0..8].copy_from_slice(&60u64.to_ne_bytes());
+        integers[8..16].copy_from_slice(&16_666u64.to_ne_bytes());
+        assert_eq!(ShmLayout::Legacy.reprobe(&integers), None);
+        assert_eq!(ShmLayout::LegacyFloat.reprobe(&integers), Some(ShmLayout::Legacy));
+
+        assert!(!MangoHudData::from_float(f64::NAN, 16.0).is_plausible());
+        assert!(!MangoHudData::from_float(-1.0, 16.0).is_plausible());
+    }
+
+    #[test]
+    fn test_reading_filter() {
+        assert!(MangoHudData::new(0, 0).is_plausible());
+        assert!(!MangoHudData::new(60, 0).is_plausible());
+        assert!(!MangoHudData::new(4_000_000, 16666).is_plausible());
+
+        let mut filter = ReadingFilter::default();
+        assert_eq!(filter.apply(MangoHudData::new(60, 16666)), MangoHudData::new(60, 16666));
+        assert_eq!(filter.apply(MangoHudData::new(u64::MAX, 7)), MangoHudData::new(60, 16666));
+        assert_eq!(filter.apply(MangoHudData::new(45, 0)), MangoHudData::new(60, 16666));
+        assert_eq!(filter.rejected(), 2);
+        assert_eq!(filter.apply(MangoHudData::new(45, 22222)), MangoHudData::new(45, 22222));
+        assert_eq!(filter.rejected(), 0);
+    }
+
+    #[test]
+    fn test_ema_smoother() {
+        let mut ema = EmaSmoother::default();
+        assert_eq!(ema.update(60.0, 0.5), 60.0);
+        assert_eq!(ema.update(40.0, 0.5), 50.0);
+        assert_eq!(ema.update(40.0, 0.5), 45.0);
+        // Alpha 1 follows the raw value
+        assert_eq!(ema.update(30.0, 1.0), 30.0);
+    }
+
+    #[test]
+    fn test_adaptive_poller() {
+        let fast = Duration::from_millis(100);
+        let slow = Duration::from_millis(500);
+        let mut poller = AdaptivePoller::new(fast, slow);
+        let start = Instant::now();
+
+        // Steady with a frame of jitter
+        let mut now = start;
+        for i in 0u64.. {
+            if now >= start + STABLE_BEFORE_SLOWDOWN {
+                break;
+            }
+            assert_eq!(poller.observe(now, 59 + i % 2), fast);
+            now += fast;
+        }
+        assert_eq!(poller.observe(now, 60), slow);
+        assert_eq!(poller.observe(now + slow, 60), slow);
+
+        // A swing snaps back at once
+        assert_eq!(poller.observe(now + slow * 2, 45), fast);
+        assert_eq!(poller.observe(now + slow * 3, 45), fast);
+        assert_eq!(poller.observe(now + slow * 4, 0), fast);
+    }
+
+    #[test]
+    fn test_stale_detector() {
+        let start = Instant::now();
+        let at = |ms: u64| start + Duration::from_millis(ms);
+        let mut detector = StaleDetector::default();
+        for ms in (0..=2000).step_by(100) {
+            // The repeats start with the second sample, at 100ms
+            assert!(!detector.observe(&FpsSample::with_timestamp(60, 16666, at(ms))));
+        }
+        assert!(detector.observe(&FpsSample::with_timestamp(60, 16666, at(2100))));
+        // Any change in the values clears it
+        assert!(!detector.observe(&FpsSample::with_timestamp(60, 16671, at(2200))));
+
+        // No frames is not a frozen reading
+        for ms in (3000..6000).step_by(500) {
+            assert!(!detector.observe(&FpsSample::with_timestamp(0, 0, at(ms))));
+        }
+    }
+
     #[test]
     fn test_fps_ring_buffer_average() {
         let mut buffer = FpsRingBuffer::new();
@@ -452,6 +1153,24 @@ mod tests {
         assert_eq!(buffer.average(), 60.0);
     }
 
+    #[test]
+    fn test_fps_ring_buffer_outlier_filters() {
+        let mut buffer = FpsRingBuffer::new();
+        assert_eq!(buffer.median(), 0.0);
+        assert_eq!(buffer.trimmed_mean(TRIM_FRACTION), 0.0);
+
+        // One hitch among steady frames
+        for fps in [60, 61, 59, 60, 5, 60, 61, 59, 60, 60] {
+            buffer.push(FpsSample::new(fps, 16666));
+        }
+        assert_eq!(buffer.average(), 54.5);
+        assert_eq!(buffer.smoothed(FpsSmoothing::Median), 60.0);
+        assert_eq!(buffer.smoothed(FpsSmoothing::TrimmedMean), 59.875);
+
+        buffer.push(FpsSample::new(62, 16129));
+        assert_eq!(buffer.median(), 60.0);
+    }
+
     #[test]
     fn test_fps_ring_buffer_percentile() {
         let mut buffer = FpsRingBuffer::new();
@@ -466,7 +1185,7 @@ mod tests {
         
         // P99 should be close to the 99th value (9900)
         let p99 = buffer.percentile(0.99);
-        assert!(p99 >= 9800 && p99 <= 10000);
+        assert!((9800..=10000).contains(&p99));
     }
 
     #[test]
@@ -483,5 +1202,13 @@ mod tests {
         // Should have the last 5 samples (5, 6, 7, 8, 9)
         let fps_values: Vec<u64> = buffer.iter().map(|s| s.fps).collect();
         assert_eq!(fps_values, vec![5, 6, 7, 8, 9]);
+
+        // Shrinking keeps the newest
+        buffer.set_capacity(2);
+        let fps_values: Vec<u64> = buffer.iter().map(|s| s.fps).collect();
+        assert_eq!(fps_values, vec![8, 9]);
+        buffer.set_capacity(4);
+        buffer.push(FpsSample::new(10, 10000));
+        assert_eq!(buffer.len(), 3);
     }
 }
@@ -0,0 +1,184 @@
+//! Pluggable FPS providers.
+//!
+//! The polling loop reads frames through the `FpsSource` trait rather than
+//! a concrete reader, so other providers can sit next to MangoHud. The
+//! configured source is opened if it can be; otherwise sources are tried
+//! in `DETECTION_ORDER` and the first that opens wins.
+
+use crate::config::Config;
+use crate::drm_flips::DrmFlipSource;
+use crate::error::FpsSourceError;
+use crate::fps_monitor::{FpsSample, FpsSmoothing, MangoHudReader};
+use crate::gamescope_stats::GamescopeStatsSource;
+use crate::mangohud_log::MangoHudLogSource;
+use crate::vulkan_layer::{VulkanLayerSource, LAYER_SHM_NAME};
+use serde::{Deserialize, Serialize};
+
+/// Available FPS sources.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FpsSourceKind {
+    /// MangoHud's shared memory segment
+    #[default]
+    Mangohud,
+    /// SmartRefresh's own Vulkan layer (`vulkan-layer/`)
+    VulkanLayer,
+    /// MangoHud's CSV log at `mangohud_log`
+    MangohudLog,
+    /// Gamescope's `--stats-path` file or FIFO
+    GamescopeStats,
+    /// Page flips counted on the panel's CRTC
+    DrmFlips,
+}
+
+/// Order in which sources are tried during auto-detection.
+pub const DETECTION_ORDER: [FpsSourceKind; 5] = [
+    FpsSourceKind::Mangohud,
+    FpsSourceKind::VulkanLayer,
+    FpsSourceKind::MangohudLog,
+    FpsSourceKind::GamescopeStats,
+    FpsSourceKind::DrmFlips,
+];
+
+impl FpsSourceKind {
+    /// Parse an IPC source name (e.g. "mangohud").
+    pub fn parse(name: &str) -> Option<Self> {
+        serde_json::from_value(serde_json::Value::String(name.to_lowercase())).ok()
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FpsSourceKind::Mangohud => "mangohud",
+            FpsSourceKind::VulkanLayer => "vulkan_layer",
+            FpsSourceKind::MangohudLog => "mangohud_log",
+            FpsSourceKind::GamescopeStats => "gamescope_stats",
+            FpsSourceKind::DrmFlips => "drm_flips",
+        }
+    }
+
+    /// Open the source of this kind with the paths from `config`, following
+    /// the focused Steam app where the source is per process.
+    pub fn open(&self, config: &Config, focused_app: Option<u32>) -> Result<Box<dyn FpsSource>, FpsSourceError> {
+        match self {
+            FpsSourceKind::Mangohud => {
+                Ok(Box::new(MangoHudReader::open_for_app(&config.mangohud_shm_names, focused_app)?))
+            }
+            FpsSourceKind::VulkanLayer => Ok(Box::new(VulkanLayerSource::open(focused_app)?)),
+            FpsSourceKind::MangohudLog => {
+                let path = config.mangohud_log.as_deref().ok_or(FpsSourceError::MangohudLogNotFound)?;
+                Ok(Box::new(MangoHudLogSource::open(path)?))
+            }
+            FpsSourceKind::GamescopeStats => Ok(Box::new(GamescopeStatsSource::open()?)),
+            FpsSourceKind::DrmFlips => Ok(Box::new(DrmFlipSource::open()?)),
+        }
+    }
+}
+
+/// A provider of frame samples.
+pub trait FpsSource: Send {
+    /// Which source this is.
+    fn kind(&self) -> FpsSourceKind;
+
+    /// Read the latest frame sample. An error means the source went away
+    /// and has to be reopened.
+    fn poll(&mut self) -> Result<FpsSample, FpsSourceError>;
+
+    /// FPS over the recent samples, smoothed per `smoothing`.
+    fn smoothed_fps(&self, smoothing: FpsSmoothing) -> f64;
+
+    /// 99th percentile frametime (µs) over the recent samples.
+    fn p99_frametime(&self) -> u64;
+
+    /// Keep the last `capacity` samples for smoothing.
+    fn set_window(&mut self, capacity: usize);
+
+    /// Process the samples come from, when the source is per process.
+    fn pid(&self) -> Option<u32> {
+        None
+    }
+}
+
+impl FpsSource for MangoHudReader {
+    fn kind(&self) -> FpsSourceKind {
+        FpsSourceKind::Mangohud
+    }
+
+    fn poll(&mut self) -> Result<FpsSample, FpsSourceError> {
+        Ok(MangoHudReader::poll(self)?)
+    }
+
+    fn smoothed_fps(&self, smoothing: FpsSmoothing) -> f64 {
+        self.get_smoothed_fps(smoothing)
+    }
+
+    fn p99_frametime(&self) -> u64 {
+        self.get_p99_frametime()
+    }
+
+    fn set_window(&mut self, capacity: usize) {
+        MangoHudReader::set_window(self, capacity)
+    }
+
+    fn pid(&self) -> Option<u32> {
+        MangoHudReader::pid(self)
+    }
+}
+
+/// Shared memory segment names read by the MangoHud and Vulkan layer
+/// sources, each also per process as `<name>-<pid>`.
+pub fn shm_names(config: &Config) -> Vec<String> {
+    let mut names = config.mangohud_shm_names.clone();
+    names.push(LAYER_SHM_NAME.to_string());
+    names
+}
+
+/// Open the configured `fps_source` if it opens, otherwise the first
+/// source in `DETECTION_ORDER` that does. The error is the last source's.
+pub fn open(config: &Config, focused_app: Option<u32>) -> Result<Box<dyn FpsSource>, FpsSourceError> {
+    let preferred = config.fps_source;
+    let mut last_error = FpsSourceError::NoneAvailable;
+    if let Some(kind) = preferred {
+        match kind.open(config, focused_app) {
+            Ok(source) => return Ok(source),
+            Err(e) => {
+                tracing::debug!("FPS source {} unavailable: {}", kind.as_str(), e);
+                last_error = e;
+            }
+        }
+    }
+    for kind in DETECTION_ORDER.iter().filter(|&&kind| Some(kind) != preferred) {
+        match kind.open(config, focused_app) {
+            Ok(source) => return Ok(source),
+            Err(e) => last_error = e,
+        }
+    }
+    Err(last_error)
+}
+
+/// Open a source ahead of `current` in `DETECTION_ORDER`, if one opens
+/// now. Lets auto-detection move back to MangoHud once a game starts with it.
+pub fn open_preferred_over(
+    current: FpsSourceKind,
+    config: &Config,
+    focused_app: Option<u32>,
+) -> Option<Box<dyn FpsSource>> {
+    DETECTION_ORDER
+        .iter()
+        .take_while(|&&kind| kind != current)
+        .find_map(|kind| kind.open(config, focused_app).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_source_kind_names() {
+        for kind in DETECTION_ORDER {
+            assert_eq!(FpsSourceKind::parse(kind.as_str()), Some(kind));
+        }
+        assert_eq!(FpsSourceKind::parse("MangoHud"), Some(FpsSourceKind::Mangohud));
+        assert_eq!(FpsSourceKind::parse("gamescope_stats"), Some(FpsSourceKind::GamescopeStats));
+        assert_eq!(FpsSourceKind::parse("fraps"), None);
+    }
+}
@@ -0,0 +1,197 @@
+//! Gamescope frame statistics FPS source.
+//!
+//! Gamescope writes `key=value` lines to the file or FIFO given with
+//! `--stats-path`; the SteamOS session creates it as `stats.pipe` in a
+//! `gamescope.*` directory under the user's runtime directory and passes it
+//! on as `GAMESCOPE_STATS`. Reading it gives FPS without MangoHud. Steam
+//! reads the same pipe, so each reader only sees part of the lines; the
+//! latest values are what matters here.
+
+use crate::error::FpsSourceError;
+use crate::fps_monitor::{FpsRingBuffer, FpsSample, FpsSmoothing};
+use crate::fps_source::{FpsSource, FpsSourceKind};
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// Environment variable the SteamOS session sets to the stats path
+const STATS_ENV: &str = "GAMESCOPE_STATS";
+
+/// Stats FIFO created by the SteamOS session
+const STATS_PIPE: &str = "stats.pipe";
+
+/// Per-user runtime directories the session creates `gamescope.*` in
+const RUN_USER_DIR: &str = "/run/user";
+
+/// Reads FPS from gamescope's stats file or FIFO.
+pub struct GamescopeStatsSource {
+    file: File,
+    is_fifo: bool,
+    /// Bytes after the last complete line
+    pending: String,
+    latest: FpsSample,
+    ring_buffer: FpsRingBuffer,
+}
+
+impl GamescopeStatsSource {
+    /// Open the stats path from `GAMESCOPE_STATS`, else the first
+    /// `gamescope.*/stats.pipe` under `/run/user`.
+    pub fn open() -> Result<Self, FpsSourceError> {
+        let path = std::env::var_os(STATS_ENV)
+            .map(PathBuf::from)
+            .filter(|path| path.exists())
+            .or_else(|| find_stats_pipe(Path::new(RUN_USER_DIR)))
+            .ok_or(FpsSourceError::GamescopeStatsNotFound)?;
+        Self::open_path(&path)
+    }
+
+    /// Open `path` without blocking, so a FIFO with no data never stalls
+    /// the polling loop.
+    pub fn open_path(path: &Path) -> Result<Self, FpsSourceError> {
+        use std::os::unix::fs::{FileTypeExt, OpenOptionsExt};
+
+        let file = std::fs::OpenOptions::new()
+            .read(true)
+            .custom_flags(libc::O_NONBLOCK)
+            .open(path)?;
+        let is_fifo = file.metadata()?.file_type().is_fifo();
+        Ok(Self {
+            file,
+            is_fifo,
+            pending: String::new(),
+            latest: FpsSample::new(0, 0),
+            ring_buffer: FpsRingBuffer::new(),
+        })
+    }
+
+    /// Read what is available and apply the complete lines. Returns false
+    /// once a FIFO has no writer left (gamescope exited).
+    fn read_available(&mut self) -> Result<bool, FpsSourceError> {
+        let mut buf = [0u8; 4096];
+        loop {
+            match self.file.read(&mut buf) {
+                Ok(0) => return Ok(!self.is_fifo),
+                Ok(n) => self.pending.push_str(&String::from_utf8_lossy(&buf[..n])),
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => return Ok(true),
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e.into()),
+            }
+            while let Some(end) = self.pending.find('\n') {
+                let line: String = self.pending.drain(..=end).collect();
+                apply_line(&mut self.latest, line.trim());
+            }
+        }
+    }
+}
+
+impl FpsSource for GamescopeStatsSource {
+    fn kind(&self) -> FpsSourceKind {
+        FpsSourceKind::GamescopeStats
+    }
+
+    fn poll(&mut self) -> Result<FpsSample, FpsSourceError> {
+        if !self.read_available()? {
+            return Err(FpsSourceError::Closed);
+        }
+        let sample = FpsSample::new(self.latest.fps, self.latest.frametime);
+        self.ring_buffer.push(sample.clone());
+        Ok(sample)
+    }
+
+    fn smoothed_fps(&self, smoothing: FpsSmoothing) -> f64 {
+        self.ring_buffer.smoothed(smoothing)
+    }
+
+    fn p99_frametime(&self) -> u64 {
+        self.ring_buffer.percentile(0.99)
+    }
+
+    fn set_window(&mut self, capacity: usize) {
+        self.ring_buffer.set_capacity(capacity);
+    }
+}
+
+/// First `gamescope.*/stats.pipe` in any user's runtime directory.
+fn find_stats_pipe(run_user_dir: &Path) -> Option<PathBuf> {
+    std::fs::read_dir(run_user_dir).ok()?.flatten().find_map(|user| {
+        std::fs::read_dir(user.path()).ok()?.flatten().find_map(|entry| {
+            let is_gamescope = entry.file_name().to_str()?.starts_with("gamescope.");
+            let pipe = entry.path().join(STATS_PIPE);
+            (is_gamescope && pipe.exists()).then_some(pipe)
+        })
+    })
+}
+
+/// Apply one stats line: `fps=<float>` or `frametime=<ms>`. A line with
+/// only one of them derives the other.
+fn apply_line(latest: &mut FpsSample, line: &str) {
+    let Some((key, value)) = line.split_once('=') else {
+        return;
+    };
+    let Ok(value) = value.trim().parse::<f64>() else {
+        return;
+    };
+    if !value.is_finite() || value <= 0.0 {
+        return;
+    }
+    match key.trim() {
+        "fps" => {
+            latest.fps = value.round() as u64;
+            latest.frametime = (1_000_000.0 / value).round() as u64;
+        }
+        "frametime" => {
+            latest.frametime = (value * 1000.0).round() as u64;
+            latest.fps = (1000.0 / value).round() as u64;
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_apply_line() {
+        let mut latest = FpsSample::new(0, 0);
+        apply_line(&mut latest, "fps=59.8");
+        assert_eq!((latest.fps, latest.frametime), (60, 16722));
+        apply_line(&mut latest, "frametime=25");
+        assert_eq!((latest.fps, latest.frametime), (40, 25000));
+        apply_line(&mut latest, "app=1091500");
+        apply_line(&mut latest, "fps=nan");
+        apply_line(&mut latest, "garbage");
+        assert_eq!((latest.fps, latest.frametime), (40, 25000));
+    }
+
+    #[test]
+    fn test_stats_file_source() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("stats");
+        let mut writer = File::create(&path).unwrap();
+        let mut source = GamescopeStatsSource::open_path(&path).unwrap();
+        assert_eq!(source.kind(), FpsSourceKind::GamescopeStats);
+        assert_eq!(source.poll().unwrap().fps, 0);
+
+        // A partial line waits for its end
+        write!(writer, "app=1091500\nfps=4").unwrap();
+        assert_eq!(source.poll().unwrap().fps, 0);
+        writeln!(writer, "5").unwrap();
+        let sample = source.poll().unwrap();
+        assert_eq!((sample.fps, sample.frametime), (45, 22222));
+        assert_eq!(source.smoothed_fps(FpsSmoothing::Mean), 15.0);
+    }
+
+    #[test]
+    fn test_find_stats_pipe() {
+        let run_user = tempdir().unwrap();
+        assert_eq!(find_stats_pipe(run_user.path()), None);
+        let session = run_user.path().join("1000").join("gamescope.Xa7f3k");
+        std::fs::create_dir_all(&session).unwrap();
+        assert_eq!(find_stats_pipe(run_user.path()), None);
+        std::fs::write(session.join(STATS_PIPE), "").unwrap();
+        assert_eq!(find_stats_pipe(run_user.path()), Some(session.join(STATS_PIPE)));
+    }
+}
@@ -0,0 +1,97 @@
+//! Downsampled FPS and refresh rate history for the UI.
+//!
+//! Once a second the smoothed FPS and current rate are appended to a five
+//! minute ring buffer, so the plugin can draw a sparkline from a single
+//! `GetHistory` call instead of streaming samples.
+
+use crate::units::Hertz;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Seconds between history points
+pub const HISTORY_INTERVAL_SECS: u64 = 1;
+
+/// Points kept: five minutes at one per second
+pub const HISTORY_CAPACITY: usize = 300;
+
+/// One history point.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct HistoryPoint {
+    /// Unix time in seconds
+    pub timestamp: u64,
+    /// Smoothed FPS, 0 without a game
+    pub fps: f64,
+    pub hz: Hertz,
+}
+
+impl HistoryPoint {
+    /// A point stamped with the current time.
+    pub fn now(fps: f64, hz: Hertz) -> Self {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        // One decimal is plenty for a sparkline and keeps responses small
+        Self { timestamp, fps: (fps * 10.0).round() / 10.0, hz }
+    }
+}
+
+/// Ring buffer of the last `HISTORY_CAPACITY` points.
+#[derive(Debug, Default)]
+pub struct History {
+    points: VecDeque<HistoryPoint>,
+}
+
+impl History {
+    pub fn push(&mut self, point: HistoryPoint) {
+        if self.points.len() >= HISTORY_CAPACITY {
+            self.points.pop_front();
+        }
+        self.points.push_back(point);
+    }
+
+    /// The last `limit` points (all by default), oldest first.
+    pub fn recent(&self, limit: Option<usize>) -> Vec<HistoryPoint> {
+        let skip = limit.map_or(0, |l| self.points.len().saturating_sub(l));
+        self.points.iter().skip(skip).copied().collect()
+    }
+}
+
+/// History response for GetHistory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryResponse {
+    /// Points, oldest first
+    pub points: Vec<HistoryPoint>,
+    /// Seconds between points
+    pub interval_secs: u64,
+    /// Ring buffer capacity
+    pub capacity: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_history_keeps_last_five_minutes() {
+        let mut history = History::default();
+        for second in 0..HISTORY_CAPACITY as u64 + 20 {
+            history.push(HistoryPoint { timestamp: second, fps: 60.0, hz: Hertz(60) });
+        }
+        let points = history.recent(None);
+        assert_eq!(points.len(), HISTORY_CAPACITY);
+        assert_eq!(points[0].timestamp, 20);
+
+        let last = history.recent(Some(2));
+        assert_eq!(last.iter().map(|p| p.timestamp).collect::<Vec<_>>(), vec![318, 319]);
+        assert_eq!(history.recent(Some(1000)).len(), HISTORY_CAPACITY);
+    }
+
+    #[test]
+    fn test_history_point_rounds_fps() {
+        let point = HistoryPoint::now(59.96, Hertz(60));
+        assert_eq!(point.fps, 60.0);
+        assert!(point.timestamp > 0);
+    }
+}
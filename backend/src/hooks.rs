@@ -0,0 +1,148 @@
+//! Sandboxed execution of per-game launch/exit hooks.
+//!
+//! Hook commands come from profiles. Only Unix socket clients that pass the
+//! UID allowlist can set them (the WebSocket and HTTP bridges refuse hook
+//! fields), and they still run through `sh -c` with:
+//!
+//! - a minimal environment (`PATH`, `HOME`, plus hook-specific variables)
+//! - no stdin, discarded output, and `HOME` as working directory
+//! - a hard timeout after which the command is killed
+//! - dropped privileges when the daemon runs as root (see [`hook_identity`])
+
+use crate::error::HookError;
+use std::time::Duration;
+use tokio::process::Command;
+use tracing::warn;
+
+/// PATH given to hook commands
+const HOOK_PATH: &str = "/usr/local/bin:/usr/bin:/bin";
+
+/// User and group a hook runs as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HookIdentity {
+    pub uid: u32,
+    pub gid: u32,
+}
+
+/// Pick the identity for hooks given the daemon's euid.
+///
+/// Non-root daemons keep their own identity (`None`). A root daemon uses
+/// `configured_uid`, falling back to the owner of `$HOME`; it never runs
+/// hooks as root.
+fn select_identity(
+    euid: u32,
+    configured_uid: Option<u32>,
+    home_owner: Option<u32>,
+) -> Result<Option<u32>, HookError> {
+    if euid != 0 {
+        return Ok(None);
+    }
+    match configured_uid.or(home_owner) {
+        Some(uid) if uid != 0 => Ok(Some(uid)),
+        _ => Err(HookError::RefusedAsRoot),
+    }
+}
+
+/// Resolve the identity hooks should run as in this process.
+#[cfg(unix)]
+pub fn hook_identity(configured_uid: Option<u32>) -> Result<Option<HookIdentity>, HookError> {
+    use std::os::unix::fs::MetadataExt;
+
+    let home_owner = std::env::var_os("HOME")
+        .and_then(|home| std::fs::metadata(home).ok())
+        .map(|meta| meta.uid());
+    // SAFETY: geteuid has no preconditions and cannot fail
+    let euid = unsafe { libc::geteuid() };
+
+    let Some(uid) = select_identity(euid, configured_uid, home_owner)? else {
+        return Ok(None);
+    };
+    // SAFETY: getpwuid returns NULL or a pointer to a static passwd entry,
+    // which is only read before any other passwd call
+    let gid = unsafe {
+        let entry = libc::getpwuid(uid);
+        if entry.is_null() { uid } else { (*entry).pw_gid }
+    };
+    Ok(Some(HookIdentity { uid, gid }))
+}
+
+#[cfg(not(unix))]
+pub fn hook_identity(_configured_uid: Option<u32>) -> Result<Option<HookIdentity>, HookError> {
+    Ok(None)
+}
+
+/// Spawn `command` in the sandbox and reap it in the background.
+///
+/// The caller is never blocked; commands exceeding `timeout` are killed.
+pub fn spawn_sandboxed(
+    command: &str,
+    envs: &[(&str, &str)],
+    identity: Option<HookIdentity>,
+    timeout: Duration,
+) -> Result<(), HookError> {
+    let home = std::env::var("HOME").unwrap_or_else(|_| "/".to_string());
+
+    let mut cmd = Command::new("sh");
+    cmd.arg("-c")
+        .arg(command)
+        .env_clear()
+        .env("PATH", HOOK_PATH)
+        .env("HOME", &home)
+        .envs(envs.iter().copied())
+        .current_dir(&home)
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .kill_on_drop(true);
+
+    #[cfg(unix)]
+    if let Some(identity) = identity {
+        cmd.uid(identity.uid).gid(identity.gid);
+    }
+
+    let mut child = cmd.spawn().map_err(HookError::Spawn)?;
+    tokio::spawn(async move {
+        if tokio::time::timeout(timeout, child.wait()).await.is_err() {
+            warn!("Hook command timed out after {:?}, killing it", timeout);
+            let _ = child.kill().await;
+        }
+    });
+    Ok(())
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_select_identity() {
+        assert_eq!(select_identity(1000, Some(1001), Some(1000)).unwrap(), None);
+        assert_eq!(select_identity(0, Some(1001), Some(1000)).unwrap(), Some(1001));
+        assert_eq!(select_identity(0, None, Some(1000)).unwrap(), Some(1000));
+        assert!(matches!(select_identity(0, None, Some(0)), Err(HookError::RefusedAsRoot)));
+        assert!(matches!(select_identity(0, Some(0), None), Err(HookError::RefusedAsRoot)));
+        assert!(matches!(select_identity(0, None, None), Err(HookError::RefusedAsRoot)));
+    }
+
+    #[tokio::test]
+    async fn test_environment_is_minimal() {
+        let dir = tempdir().unwrap();
+        let out = dir.path().join("env");
+        std::env::set_var("SMART_REFRESH_TEST_SECRET", "leak");
+        let command = format!(
+            "echo \"$SMART_REFRESH_APP_ID:${{SMART_REFRESH_TEST_SECRET:-none}}\" > {}",
+            out.display()
+        );
+        spawn_sandboxed(&command, &[("SMART_REFRESH_APP_ID", "620")], None, Duration::from_secs(5))
+            .unwrap();
+
+        for _ in 0..50 {
+            if std::fs::read_to_string(&out).is_ok_and(|c| !c.is_empty()) {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert_eq!(std::fs::read_to_string(&out).unwrap().trim(), "620:none");
+    }
+}
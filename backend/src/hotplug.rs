@@ -0,0 +1,115 @@
+//! DRM hotplug events from the kernel's uevent netlink socket.
+//!
+//! The kernel broadcasts a `change` uevent with `HOTPLUG=1` on the DRM card
+//! whenever a connector's status changes, which lets monitor detection react
+//! to a plugged display right away instead of on its next poll.
+
+use std::io;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use tokio::io::unix::AsyncFd;
+
+/// Multicast group of kernel uevents (udev rebroadcasts on group 2)
+const KERNEL_UEVENT_GROUP: u32 = 1;
+
+/// Large enough for any single uevent
+const UEVENT_BUFFER_LEN: usize = 8192;
+
+/// Whether a raw uevent is a DRM connector hotplug.
+fn is_drm_hotplug(message: &[u8]) -> bool {
+    let mut subsystem_drm = false;
+    let mut hotplug = false;
+    for field in message.split(|&b| b == 0) {
+        match field {
+            b"SUBSYSTEM=drm" => subsystem_drm = true,
+            b"HOTPLUG=1" => hotplug = true,
+            _ => {}
+        }
+    }
+    subsystem_drm && hotplug
+}
+
+/// Listener for DRM hotplug uevents.
+pub struct HotplugMonitor {
+    socket: AsyncFd<OwnedFd>,
+}
+
+impl HotplugMonitor {
+    /// Subscribe to kernel uevents.
+    pub fn open() -> io::Result<Self> {
+        // SAFETY: plain socket(2) call; the fd is owned right after
+        let fd = unsafe {
+            libc::socket(
+                libc::AF_NETLINK,
+                libc::SOCK_DGRAM | libc::SOCK_CLOEXEC | libc::SOCK_NONBLOCK,
+                libc::NETLINK_KOBJECT_UEVENT,
+            )
+        };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // SAFETY: `fd` is a freshly created socket nothing else owns
+        let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+
+        // SAFETY: sockaddr_nl is plain data, all-zero is a valid value
+        let mut addr: libc::sockaddr_nl = unsafe { std::mem::zeroed() };
+        addr.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+        addr.nl_groups = KERNEL_UEVENT_GROUP;
+        // SAFETY: `addr` is a valid sockaddr_nl of the given length
+        let ret = unsafe {
+            libc::bind(
+                fd.as_raw_fd(),
+                &addr as *const libc::sockaddr_nl as *const libc::sockaddr,
+                std::mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t,
+            )
+        };
+        if ret < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(Self { socket: AsyncFd::new(fd)? })
+    }
+
+    /// Wait for the next DRM hotplug event, skipping other uevents.
+    pub async fn next_event(&mut self) -> io::Result<()> {
+        let mut buffer = vec![0u8; UEVENT_BUFFER_LEN];
+        loop {
+            let mut guard = self.socket.readable().await?;
+            let received = guard.try_io(|socket| {
+                // SAFETY: `buffer` is valid for writes of its length
+                let n = unsafe {
+                    libc::recv(socket.as_raw_fd(), buffer.as_mut_ptr().cast(), buffer.len(), 0)
+                };
+                if n < 0 {
+                    Err(io::Error::last_os_error())
+                } else {
+                    Ok(n as usize)
+                }
+            });
+            match received {
+                Ok(Ok(n)) if is_drm_hotplug(&buffer[..n]) => return Ok(()),
+                Ok(Ok(_)) => {}
+                // Dropped events just mean the next check comes from polling
+                Ok(Err(e)) if e.raw_os_error() == Some(libc::ENOBUFS) => {}
+                Ok(Err(e)) => return Err(e),
+                Err(_would_block) => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_drm_hotplug() {
+        let hotplug = b"change@/devices/pci0000:00/0000:04:00.0/drm/card0\0ACTION=change\0\
+            DEVPATH=/devices/pci0000:00/0000:04:00.0/drm/card0\0SUBSYSTEM=drm\0HOTPLUG=1\0SEQNUM=4242\0";
+        assert!(is_drm_hotplug(hotplug));
+
+        let usb = b"add@/devices/usb1/1-1\0ACTION=add\0SUBSYSTEM=usb\0SEQNUM=4243\0";
+        assert!(!is_drm_hotplug(usb));
+        let drm_change = b"change@/devices/drm/card0\0ACTION=change\0SUBSYSTEM=drm\0";
+        assert!(!is_drm_hotplug(drm_change));
+    }
+}
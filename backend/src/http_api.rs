@@ -0,0 +1,406 @@
+//! Optional local HTTP REST API (enabled with the `http` feature).
+//!
+//! Maps REST routes onto the IPC command handlers:
+//!
+//! | Route                       | Command            |
+//! |-----------------------------|--------------------|
+//! | `GET /status`               | GetStatus          |
+//! | `GET /metrics`              | GetMetrics         |
+//! | `GET /battery`              | GetBatteryStatus   |
+//! | `GET /profiles`             | GetProfiles        |
+//! | `GET /advisories`           | GetAdvisories      |
+//! | `GET /transitions?limit=N`  | GetTransitions     |
+//! | `POST /config`              | SetConfig          |
+//! | `POST /start`, `POST /stop` | Start, Stop        |
+//! | `POST /command`             | any JSON command   |
+//!
+//! The server binds to 127.0.0.1 and handles one request per connection.
+//! Every request must carry the per-start bridge token as
+//! `Authorization: Bearer <token>` (see [`crate::bridge_token`]), since TCP
+//! has no peer credentials. POST bodies must be `application/json` and
+//! browser origins are refused, so web pages can't drive the daemon through
+//! the user's browser. Commands storing shell commands (profile hooks) are
+//! refused; those need the Unix socket.
+
+use crate::bridge_token::BridgeToken;
+use crate::error::IpcError;
+use crate::ipc_server::{DaemonState, IpcServer};
+use serde_json::Value;
+use std::sync::Arc;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// Maximum size of the request line and headers
+const MAX_HEADER_BYTES: usize = 8192;
+
+/// Maximum request body size
+const MAX_BODY_BYTES: usize = 64 * 1024;
+
+/// Parsed HTTP request.
+#[derive(Debug)]
+struct Request {
+    method: String,
+    path: String,
+    query: Option<String>,
+    content_type: Option<String>,
+    origin: Option<String>,
+    authorization: Option<String>,
+    body: Vec<u8>,
+}
+
+/// HTTP error status with a message for the JSON body.
+#[derive(Debug, PartialEq)]
+struct HttpError {
+    status: u16,
+    message: String,
+}
+
+impl HttpError {
+    fn new(status: u16, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+}
+
+fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        413 => "Payload Too Large",
+        415 => "Unsupported Media Type",
+        _ => "Error",
+    }
+}
+
+/// Read the request head and body.
+async fn read_request<S: AsyncRead + Unpin>(stream: &mut S) -> Result<Request, HttpError> {
+    let io_err = |_| HttpError::new(400, "Malformed request");
+
+    let mut head = Vec::with_capacity(1024);
+    let mut byte = [0u8; 1];
+    while !head.ends_with(b"\r\n\r\n") {
+        if head.len() >= MAX_HEADER_BYTES {
+            return Err(HttpError::new(413, "Request headers too large"));
+        }
+        if stream.read(&mut byte).await.map_err(io_err)? == 0 {
+            return Err(HttpError::new(400, "Connection closed mid-request"));
+        }
+        head.push(byte[0]);
+    }
+    let head = String::from_utf8(head).map_err(|_| HttpError::new(400, "Malformed request"))?;
+
+    let mut lines = head.lines();
+    let mut request_line = lines.next().unwrap_or_default().split_whitespace();
+    let (Some(method), Some(target)) = (request_line.next(), request_line.next()) else {
+        return Err(HttpError::new(400, "Malformed request line"));
+    };
+    let (path, query) = match target.split_once('?') {
+        Some((path, query)) => (path, Some(query.to_string())),
+        None => (target, None),
+    };
+
+    let mut content_length = 0;
+    let mut content_type = None;
+    let mut origin = None;
+    let mut authorization = None;
+    for line in lines {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        match name.trim().to_ascii_lowercase().as_str() {
+            "content-length" => {
+                content_length = value
+                    .parse()
+                    .map_err(|_| HttpError::new(400, "Invalid Content-Length"))?;
+            }
+            "content-type" => content_type = Some(value.to_ascii_lowercase()),
+            "origin" => origin = Some(value.to_string()),
+            "authorization" => authorization = Some(value.to_string()),
+            _ => {}
+        }
+    }
+    if content_length > MAX_BODY_BYTES {
+        return Err(HttpError::new(413, "Request body too large"));
+    }
+
+    let mut body = vec![0u8; content_length];
+    stream.read_exact(&mut body).await.map_err(io_err)?;
+
+    Ok(Request {
+        method: method.to_string(),
+        path: path.to_string(),
+        query,
+        content_type,
+        origin,
+        authorization,
+        body,
+    })
+}
+
+/// Parse the request body as a JSON object.
+fn json_body(request: &Request) -> Result<serde_json::Map<String, Value>, HttpError> {
+    let is_json = request
+        .content_type
+        .as_deref()
+        .is_some_and(|t| t.starts_with("application/json"));
+    if !is_json {
+        return Err(HttpError::new(415, "Content-Type must be application/json"));
+    }
+    match serde_json::from_slice(&request.body) {
+        Ok(Value::Object(map)) => Ok(map),
+        Ok(_) => Err(HttpError::new(400, "Body must be a JSON object")),
+        Err(e) => Err(HttpError::new(400, format!("Invalid JSON: {}", e))),
+    }
+}
+
+fn query_param<'a>(query: Option<&'a str>, name: &str) -> Option<&'a str> {
+    query?
+        .split('&')
+        .find_map(|pair| pair.split_once('=').filter(|(key, _)| *key == name))
+        .map(|(_, value)| value)
+}
+
+/// Check the request's bearer token.
+fn authorize(request: &Request, token: &BridgeToken) -> Result<(), HttpError> {
+    let presented = request
+        .authorization
+        .as_deref()
+        .and_then(|value| value.strip_prefix("Bearer "));
+    if presented.is_some_and(|t| token.matches(t.trim())) {
+        Ok(())
+    } else {
+        Err(HttpError::new(401, "Missing or invalid bridge token"))
+    }
+}
+
+/// Translate a request into an IPC command object.
+fn route(request: &Request) -> Result<Value, HttpError> {
+    if request.origin.is_some() {
+        return Err(HttpError::new(403, "Browser requests are not allowed"));
+    }
+
+    let command = |name: &str| serde_json::json!({ "command": name });
+    match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/status") => Ok(command("GetStatus")),
+        ("GET", "/metrics") => Ok(command("GetMetrics")),
+        ("GET", "/battery") => Ok(command("GetBatteryStatus")),
+        ("GET", "/profiles") => Ok(command("GetProfiles")),
+        ("GET", "/advisories") => Ok(command("GetAdvisories")),
+        ("GET", "/transitions") => {
+            let limit = match query_param(request.query.as_deref(), "limit") {
+                Some(raw) => Some(
+                    raw.parse::<usize>()
+                        .map_err(|_| HttpError::new(400, "limit must be a non-negative integer"))?,
+                ),
+                None => None,
+            };
+            Ok(serde_json::json!({ "command": "GetTransitions", "limit": limit }))
+        }
+        ("POST", "/start") => Ok(command("Start")),
+        ("POST", "/stop") => Ok(command("Stop")),
+        ("POST", "/config") => {
+            let mut body = json_body(request)?;
+            body.insert("command".to_string(), Value::from("SetConfig"));
+            Ok(Value::Object(body))
+        }
+        ("POST", "/command") => Ok(Value::Object(json_body(request)?)),
+        (
+            _,
+            "/status" | "/metrics" | "/battery" | "/profiles" | "/advisories" | "/transitions"
+            | "/start" | "/stop" | "/config" | "/command",
+        ) => Err(HttpError::new(405, "Method not allowed")),
+        _ => Err(HttpError::new(404, "Not found")),
+    }
+}
+
+async fn write_response<S: AsyncWrite + Unpin>(
+    stream: &mut S,
+    status: u16,
+    body: &Value,
+) -> Result<(), IpcError> {
+    let body = serde_json::to_string(body)?;
+    let response = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        reason_phrase(status),
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+/// Serve one request on a connection.
+async fn handle_connection<S>(
+    mut stream: S,
+    state: Arc<DaemonState>,
+    token: Arc<BridgeToken>,
+) -> Result<(), IpcError>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let command = read_request(&mut stream)
+        .await
+        .and_then(|request| authorize(&request, &token).and_then(|()| route(&request)));
+
+    match command {
+        Ok(command) => {
+            let response = IpcServer::handle_remote_request(&command.to_string(), &state).await;
+            let status = if response.get("error").is_some() { 400 } else { 200 };
+            write_response(&mut stream, status, &response).await
+        }
+        Err(e) => {
+            write_response(&mut stream, e.status, &serde_json::json!({ "error": e.message })).await
+        }
+    }
+}
+
+/// Accept HTTP connections on 127.0.0.1:`port` until the task is dropped.
+pub async fn run_http_server(
+    state: Arc<DaemonState>,
+    token: Arc<BridgeToken>,
+    port: u16,
+) -> Result<(), IpcError> {
+    let listener = TcpListener::bind(("127.0.0.1", port)).await.map_err(|e| {
+        IpcError::SocketBindFailed {
+            path: format!("127.0.0.1:{}", port),
+            source: e,
+        }
+    })?;
+    tracing::info!("HTTP API listening on {}", listener.local_addr()?);
+    serve(listener, state, token).await
+}
+
+async fn serve(
+    listener: TcpListener,
+    state: Arc<DaemonState>,
+    token: Arc<BridgeToken>,
+) -> Result<(), IpcError> {
+    loop {
+        let (stream, _addr) = listener.accept().await?;
+        let state = Arc::clone(&state);
+        let token = Arc::clone(&token);
+        tokio::spawn(async move {
+            if let Err(e) = handle_connection(stream, state, token).await {
+                tracing::debug!("HTTP connection ended: {}", e);
+            }
+        });
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::battery::BatteryMonitor;
+    use crate::config::ConfigManager;
+    use crate::metrics::MetricsCollector;
+    use crate::profiles::ProfileManager;
+    use tempfile::tempdir;
+    use tokio::net::TcpStream;
+    use tokio::sync::RwLock;
+
+    fn request(method: &str, target: &str, content_type: Option<&str>, body: &str) -> Request {
+        let (path, query) = match target.split_once('?') {
+            Some((path, query)) => (path, Some(query.to_string())),
+            None => (target, None),
+        };
+        Request {
+            method: method.to_string(),
+            path: path.to_string(),
+            query,
+            content_type: content_type.map(str::to_string),
+            origin: None,
+            authorization: None,
+            body: body.as_bytes().to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_routes_map_to_commands() {
+        let get = |target| route(&request("GET", target, None, "")).unwrap();
+        assert_eq!(get("/status")["command"], "GetStatus");
+        assert_eq!(get("/metrics")["command"], "GetMetrics");
+        assert_eq!(get("/transitions?limit=5")["limit"], 5);
+        assert!(get("/transitions")["limit"].is_null());
+
+        let config = route(&request(
+            "POST",
+            "/config",
+            Some("application/json"),
+            r#"{"min_hz":45,"max_hz":90,"sensitivity":"balanced"}"#,
+        ))
+        .unwrap();
+        assert_eq!(config["command"], "SetConfig");
+        assert_eq!(config["min_hz"], 45);
+    }
+
+    #[test]
+    fn test_route_errors() {
+        let status = |r: Request| route(&r).unwrap_err().status;
+        assert_eq!(status(request("GET", "/nope", None, "")), 404);
+        assert_eq!(status(request("DELETE", "/status", None, "")), 405);
+        assert_eq!(status(request("POST", "/config", Some("text/plain"), "{}")), 415);
+        assert_eq!(status(request("POST", "/command", Some("application/json"), "[1]")), 400);
+        assert_eq!(status(request("GET", "/transitions?limit=-1", None, "")), 400);
+
+        let mut browser = request("GET", "/status", None, "");
+        browser.origin = Some("https://example.com".to_string());
+        assert_eq!(status(browser), 403);
+    }
+
+    #[test]
+    fn test_authorize_requires_bearer_token() {
+        let token = BridgeToken::from_secret("0123abcd");
+        let with_auth = |value: Option<&str>| {
+            let mut r = request("GET", "/status", None, "");
+            r.authorization = value.map(str::to_string);
+            authorize(&r, &token).map_err(|e| e.status)
+        };
+        assert_eq!(with_auth(None), Err(401));
+        assert_eq!(with_auth(Some("Bearer 0123abce")), Err(401));
+        assert_eq!(with_auth(Some("Basic 0123abcd")), Err(401));
+        assert_eq!(with_auth(Some("Bearer 0123abcd")), Ok(()));
+    }
+
+    #[tokio::test]
+    async fn test_http_roundtrip() {
+        let dir = tempdir().unwrap();
+        let config = ConfigManager::load_or_default(&dir.path().join("config.json")).unwrap();
+        let state = Arc::new(DaemonState::new(
+            Arc::new(config),
+            Arc::new(RwLock::new(ProfileManager::default())),
+            Arc::new(MetricsCollector::new()),
+            Arc::new(BatteryMonitor::new()),
+        ));
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(serve(listener, state, Arc::new(BridgeToken::from_secret("0123abcd"))));
+
+        let get_status = |auth: &'static str| async move {
+            let mut stream = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+            let request = format!("GET /status HTTP/1.1\r\nHost: localhost\r\n{}\r\n", auth);
+            stream.write_all(request.as_bytes()).await.unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).await.unwrap();
+            response
+        };
+
+        let response = get_status("").await;
+        assert!(response.starts_with("HTTP/1.1 401 Unauthorized"), "{}", response);
+
+        let response = get_status("Authorization: Bearer 0123abcd\r\n").await;
+        assert!(response.starts_with("HTTP/1.1 200 OK"), "{}", response);
+        let (_, body) = response.split_once("\r\n\r\n").unwrap();
+        let body: Value = serde_json::from_str(body).unwrap();
+        assert!(body.get("current_hz").is_some());
+    }
+}
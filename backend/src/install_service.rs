@@ -0,0 +1,173 @@
+//! `install-service` subcommand: systemd user unit, man pages and completions.
+//!
+//! Files go into the invoking user's XDG directories so no root access is
+//! needed; on SteamOS the root filesystem is read-only anyway.
+
+use crate::cli::{DocKind, BIN_NAME};
+use crate::cli_docs;
+use crate::error::InstallError;
+use crate::persist::atomic_persist;
+use std::path::{Path, PathBuf};
+
+/// Name of the installed systemd user unit.
+pub const UNIT_NAME: &str = "smart-refresh.service";
+
+/// Destination of every installed file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InstallPaths {
+    pub unit: PathBuf,
+    pub man: PathBuf,
+    pub man_ru: PathBuf,
+    pub bash: PathBuf,
+    pub zsh: PathBuf,
+    pub fish: PathBuf,
+}
+
+impl InstallPaths {
+    /// Resolve paths from `HOME`, `XDG_CONFIG_HOME` and `XDG_DATA_HOME`.
+    pub fn from_env() -> Result<Self, InstallError> {
+        let home = std::env::var_os("HOME")
+            .map(PathBuf::from)
+            .filter(|p| p.is_absolute())
+            .ok_or(InstallError::NoHome)?;
+        Ok(Self::resolve(
+            &home,
+            std::env::var_os("XDG_CONFIG_HOME").map(PathBuf::from),
+            std::env::var_os("XDG_DATA_HOME").map(PathBuf::from),
+        ))
+    }
+
+    /// Relative XDG overrides are ignored, as the spec requires.
+    fn resolve(home: &Path, config_home: Option<PathBuf>, data_home: Option<PathBuf>) -> Self {
+        let config = config_home
+            .filter(|p| p.is_absolute())
+            .unwrap_or_else(|| home.join(".config"));
+        let data = data_home
+            .filter(|p| p.is_absolute())
+            .unwrap_or_else(|| home.join(".local/share"));
+        let man_name = format!("{}.1", BIN_NAME);
+
+        Self {
+            unit: config.join("systemd/user").join(UNIT_NAME),
+            man: data.join("man/man1").join(&man_name),
+            man_ru: data.join("man/ru/man1").join(&man_name),
+            bash: data.join("bash-completion/completions").join(BIN_NAME),
+            zsh: data
+                .join("zsh/site-functions")
+                .join(format!("_{}", BIN_NAME)),
+            fish: config
+                .join("fish/completions")
+                .join(format!("{}.fish", BIN_NAME)),
+        }
+    }
+}
+
+/// systemd user unit running `exe`.
+pub fn unit_file(exe: &Path) -> String {
+    format!(
+        "[Unit]\n\
+         Description=SmartRefresh dynamic refresh rate daemon\n\
+         Documentation=man:{bin}(1)\n\
+         \n\
+         [Service]\n\
+         ExecStart=\"{exe}\"\n\
+         Restart=on-failure\n\
+         RestartSec=5\n\
+         \n\
+         [Install]\n\
+         WantedBy=default.target\n",
+        bin = BIN_NAME,
+        exe = exe.display(),
+    )
+}
+
+/// Write the unit, man pages and completions. Returns the written paths.
+pub fn install(paths: &InstallPaths, exe: &Path) -> Result<Vec<PathBuf>, InstallError> {
+    let files = [
+        (&paths.unit, unit_file(exe)),
+        (&paths.man, cli_docs::render(DocKind::Man)),
+        (&paths.man_ru, cli_docs::render(DocKind::ManRu)),
+        (&paths.bash, cli_docs::render(DocKind::Bash)),
+        (&paths.zsh, cli_docs::render(DocKind::Zsh)),
+        (&paths.fish, cli_docs::render(DocKind::Fish)),
+    ];
+
+    let mut written = Vec::with_capacity(files.len());
+    for (path, contents) in files {
+        atomic_persist(path, contents.as_bytes()).map_err(|source| InstallError::WriteFailed {
+            path: path.display().to_string(),
+            source,
+        })?;
+        written.push(path.clone());
+    }
+    Ok(written)
+}
+
+/// Entry point for `smart-refresh-daemon install-service`.
+pub fn run() -> Result<(), InstallError> {
+    let exe = std::env::current_exe().map_err(InstallError::CurrentExe)?;
+    let paths = InstallPaths::from_env()?;
+    for path in install(&paths, &exe)? {
+        println!("Installed {}", path.display());
+    }
+    println!(
+        "\nEnable with:\n  systemctl --user daemon-reload\n  systemctl --user enable --now {}",
+        UNIT_NAME
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_resolve_paths() {
+        let home = Path::new("/home/deck");
+        let paths = InstallPaths::resolve(home, None, Some(PathBuf::from("relative")));
+        assert_eq!(
+            paths.unit,
+            home.join(".config/systemd/user/smart-refresh.service")
+        );
+        assert_eq!(
+            paths.man_ru,
+            home.join(".local/share/man/ru/man1/smart-refresh-daemon.1")
+        );
+
+        let paths = InstallPaths::resolve(
+            home,
+            Some(PathBuf::from("/cfg")),
+            Some(PathBuf::from("/data")),
+        );
+        assert_eq!(
+            paths.fish,
+            PathBuf::from("/cfg/fish/completions/smart-refresh-daemon.fish")
+        );
+        assert_eq!(
+            paths.zsh,
+            PathBuf::from("/data/zsh/site-functions/_smart-refresh-daemon")
+        );
+    }
+
+    #[test]
+    fn test_install_writes_all_files() {
+        let dir = tempdir().unwrap();
+        let paths = InstallPaths::resolve(dir.path(), None, None);
+        let exe = Path::new("/home/deck/homebrew/plugins/SmartRefresh/bin/smart-refresh-daemon");
+
+        let written = install(&paths, exe).unwrap();
+        assert_eq!(written.len(), 6);
+        for path in &written {
+            assert!(
+                path.starts_with(dir.path()) && path.is_file(),
+                "{}",
+                path.display()
+            );
+        }
+
+        let unit = std::fs::read_to_string(&paths.unit).unwrap();
+        assert!(unit.contains(&format!("ExecStart=\"{}\"", exe.display())));
+        assert!(unit.contains("WantedBy=default.target"));
+    }
+}
@@ -10,18 +10,44 @@
 //! - Transition history
 
 use crate::battery::BatteryMonitor;
+use crate::build_info::BuildInfo;
 use crate::config::{Config, ConfigManager};
-use crate::core_logic::{AlgorithmState, DeviceMode, HysteresisController, Sensitivity};
-use crate::error::IpcError;
+use crate::core_logic::{
+    AlgorithmState, ControlPolicy, DecisionPacing, DeviceMode, HysteresisController, IdlePolicy,
+    Sensitivity, StepMode,
+};
+use crate::deep_sleep::SleepController;
+use crate::device::DeviceModel;
+use crate::display_backend::{self, BackendKind};
+use crate::fps_source::{self, FpsSourceKind};
+use crate::fps_monitor::FpsSmoothing;
+use crate::display_control::{DisplayManager, HzRangeSource, StartupRate, VrrStatus, MAX_ALLOWED_HZ, MIN_ALLOWED_HZ};
+use crate::drm_modes::{self, PanelMode, RateSource, SupportedRates};
+use crate::edid::PanelInfo;
+use crate::error::{IpcError, ProfileError};
+use crate::experimental::{Experiment, ExperimentInfo, Experiments, ExperimentalConfig};
+use crate::notifications::{EventKind, Notifications};
+use crate::history::{History, HistoryPoint, HistoryResponse, HISTORY_CAPACITY, HISTORY_INTERVAL_SECS};
+use crate::hooks;
 use crate::metrics::MetricsCollector;
-use crate::profiles::{GameProfile, ProfileListResponse, ProfileManager};
+use crate::monitor_detect::{self, ConnectorStatus, DisplayStatus, MonitorDetector};
+use crate::session::SessionKind;
+use crate::profiles::{GameProfile, GameSwitching, ProfileListResponse, ProfileManager};
+use crate::subsystems::{Subsystem, SubsystemStatus, Subsystems};
+use crate::suggestion::SessionObserver;
+use crate::switch_budget::{self, SwitchBudget, SwitchBudgetStatus};
+use crate::thermal::ThermalStatus;
+use crate::utilization::UtilizationStatus;
+use crate::trends::Advisory;
+use crate::units::{Hertz, Millis, Secs};
+use crate::whatif::{self, Scenario, MAX_JOURNAL_SAMPLES};
 
 use serde::{Deserialize, Serialize};
-#[cfg(unix)]
+use std::collections::VecDeque;
 use std::path::{Path, PathBuf};
-use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
-use std::sync::Arc;
-use std::time::Instant;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
 
 #[cfg(unix)]
@@ -29,11 +55,71 @@ use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 #[cfg(unix)]
 use tokio::net::{UnixListener, UnixStream};
 
-/// Default socket path for IPC communication.
-pub const DEFAULT_SOCKET_PATH: &str = "/tmp/smart-refresh.sock";
+/// Socket file name inside the runtime directory.
+pub const SOCKET_FILE_NAME: &str = "smart-refresh.sock";
+
+/// Socket path used when `XDG_RUNTIME_DIR` is not set.
+pub const FALLBACK_SOCKET_PATH: &str = "/tmp/smart-refresh.sock";
+
+/// Default socket path: `$XDG_RUNTIME_DIR/smart-refresh.sock`, or /tmp if unset.
+pub fn default_socket_path() -> PathBuf {
+    socket_path_in(std::env::var_os("XDG_RUNTIME_DIR").map(PathBuf::from))
+}
+
+fn socket_path_in(runtime_dir: Option<PathBuf>) -> PathBuf {
+    match runtime_dir {
+        Some(dir) if dir.is_absolute() => dir.join(SOCKET_FILE_NAME),
+        _ => PathBuf::from(FALLBACK_SOCKET_PATH),
+    }
+}
+
+/// Resolve the socket path. Priority: CLI flag > config file > default.
+pub fn resolve_socket_path(cli: Option<&Path>, config: Option<&Path>) -> PathBuf {
+    cli.or(config)
+        .map(Path::to_path_buf)
+        .unwrap_or_else(default_socket_path)
+}
+
+/// Maximum transition history entries kept in the ring buffer
+const MAX_TRANSITION_HISTORY: usize = 100;
+
+/// Number of most recent transitions embedded in GetStatus
+const STATUS_TRANSITION_COUNT: usize = 20;
+
+/// Per-game launch/exit hooks are killed after this many seconds
+const HOOK_TIMEOUT_SECS: u64 = 30;
+
+/// Longest timed SetHz override (a day); longer holds use no duration
+const MAX_OVERRIDE_SECS: Secs = Secs(24 * 60 * 60);
+
+/// Maximum number of commands in one Batch request
+const MAX_BATCH_COMMANDS: usize = 32;
+
+/// Default StreamFps push interval (4 Hz)
+const DEFAULT_STREAM_INTERVAL_MS: Millis = Millis(250);
+
+/// Accepted StreamFps interval range in milliseconds
+const MIN_STREAM_INTERVAL_MS: Millis = Millis(50);
+const MAX_STREAM_INTERVAL_MS: Millis = Millis(5000);
 
-/// Maximum transition history entries
-const MAX_TRANSITION_HISTORY: usize = 20;
+/// Journal sample spacing assumed by SimulateSettings (the FPS poll interval)
+const DEFAULT_JOURNAL_INTERVAL_MS: Millis = Millis(100);
+
+/// Maximum scenarios per SimulateSettings request
+const MAX_SIMULATION_SCENARIOS: usize = 8;
+
+/// How long after startup the previous run's status may be served
+const COLD_START_WINDOW: Duration = Duration::from_secs(15);
+
+/// Deserialize a field where `null` differs from absent: an absent field is
+/// `None`, `null` is `Some(None)`.
+fn present<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
 
 /// Commands that can be received via IPC.
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
@@ -42,23 +128,104 @@ pub enum IpcCommand {
     Start,
     Stop,
     SetConfig {
-        min_hz: u32,
-        max_hz: u32,
+        min_hz: Hertz,
+        max_hz: Hertz,
         sensitivity: String,
         #[serde(default)]
         adaptive_sensitivity: Option<bool>,
+        /// Sticky-target FPS tolerance, 2.0-5.0 (persisted)
         #[serde(default)]
         fps_tolerance: Option<f64>,
         #[serde(default)]
         sync_frame_limiter: Option<bool>,
+        /// Durations used when sensitivity is "custom"
+        #[serde(default)]
+        custom_drop_ms: Option<Millis>,
+        #[serde(default)]
+        custom_increase_ms: Option<Millis>,
+        /// Drop one step at a time instead of jumping to the target
+        /// (shorthand for `drop_step`)
+        #[serde(default)]
+        ramp_down: Option<bool>,
+        /// Drop step size: "jump" or "single"
+        #[serde(default)]
+        drop_step: Option<StepMode>,
+        /// Increase step size: "single" or "jump"
+        #[serde(default)]
+        increase_step: Option<StepMode>,
+        /// Snap to video cadence multiples (24/25/30fps)
+        #[serde(default)]
+        video_cadence: Option<bool>,
+        /// Run at a multiple of FPS below min Hz, with the frame limiter at FPS
+        #[serde(default)]
+        low_fps_compensation: Option<bool>,
+        /// "hysteresis" or "pid"
+        #[serde(default)]
+        control_policy: Option<ControlPolicy>,
     },
     SetDeviceMode {
         mode: String,
     },
     SetAdvancedConfig {
+        /// Sticky-target FPS tolerance, 2.0-5.0 (persisted)
         fps_tolerance: Option<f64>,
-        resume_cooldown_secs: Option<u64>,
+        /// Silence after wake from suspend, up to 60s (persisted, 0 disables)
+        resume_cooldown_secs: Option<Secs>,
         sync_frame_limiter: Option<bool>,
+        /// Increase threshold multiplier for the final step to max Hz (persisted)
+        #[serde(default)]
+        saturation_pacing: Option<f64>,
+        /// Hold Hz after an abrupt FPS collapse (persisted, 0 disables)
+        #[serde(default)]
+        loading_grace_ms: Option<Millis>,
+        /// Hz handling without FPS data: "hold", "min" or "max" (persisted)
+        #[serde(default)]
+        idle_policy: Option<IdlePolicy>,
+        /// Seconds without FPS data before `idle_policy` applies (persisted)
+        #[serde(default)]
+        idle_after_secs: Option<Secs>,
+        /// Minimum time at a new rate before any further change (persisted, 0 disables)
+        #[serde(default)]
+        min_dwell_secs: Option<Secs>,
+        /// FPS decline that starts a predictive drop (persisted, needs the experiment)
+        #[serde(default)]
+        predictive_drop_fps_per_sec: Option<f64>,
+        /// Hold increases while the GPU is pegged (persisted)
+        #[serde(default)]
+        gpu_utilization: Option<bool>,
+        /// Hold a rate set outside the daemon, up to 600s (persisted, 0 disables)
+        #[serde(default)]
+        manual_change_grace_secs: Option<Secs>,
+        /// Rate set at startup: "max", "restore" or "untouched" (persisted)
+        #[serde(default)]
+        startup_rate: Option<StartupRate>,
+        /// Control an external display instead of pausing (persisted)
+        #[serde(default)]
+        control_external_display: Option<bool>,
+        /// Keep switching in desktop mode (persisted)
+        #[serde(default)]
+        desktop_mode_switching: Option<bool>,
+        /// Only switch for whitelisted games (persisted)
+        #[serde(default)]
+        whitelist_only: Option<bool>,
+        /// FPS smoothing: "mean", "ema", "median" or "trimmed_mean" (persisted)
+        #[serde(default)]
+        fps_smoothing: Option<FpsSmoothing>,
+        /// Newest sample weight for "ema" smoothing, 0.01-1.0 (persisted)
+        #[serde(default)]
+        fps_ema_alpha: Option<f64>,
+        /// FPS smoothing window, 1-60s (persisted)
+        #[serde(default)]
+        fps_window_secs: Option<Secs>,
+        /// Slow polling down while FPS is steady (persisted)
+        #[serde(default)]
+        adaptive_polling: Option<bool>,
+        /// Write the MangoHud overlay line (persisted)
+        #[serde(default)]
+        mangohud_overlay: Option<bool>,
+        /// Detect the running game for profiles (persisted)
+        #[serde(default)]
+        auto_detect_game: Option<bool>,
     },
     GetStatus,
     GetMetrics,
@@ -71,38 +238,330 @@ pub enum IpcCommand {
     SaveProfile {
         app_id: String,
         name: String,
-        min_hz: u32,
-        max_hz: u32,
+        min_hz: Hertz,
+        max_hz: Hertz,
         sensitivity: String,
         #[serde(default)]
         adaptive_sensitivity: bool,
+        /// Kept from the existing profile when omitted; `null` removes it
+        #[serde(default, deserialize_with = "present", skip_serializing_if = "Option::is_none")]
+        min_dwell_secs: Option<Option<Secs>>,
+        /// Kept from the existing profile when omitted; `null` or "" removes it
+        #[serde(default, deserialize_with = "present", skip_serializing_if = "Option::is_none")]
+        on_launch: Option<Option<String>>,
+        /// Kept from the existing profile when omitted; `null` or "" removes it
+        #[serde(default, deserialize_with = "present", skip_serializing_if = "Option::is_none")]
+        on_exit: Option<Option<String>>,
+        /// Kept from the existing profile when omitted
+        #[serde(default)]
+        switching: Option<GameSwitching>,
     },
     DeleteProfile {
         app_id: String,
     },
+    AcceptProfileSuggestion {
+        app_id: String,
+    },
+    /// Blacklist or whitelist a game, creating its profile if needed
+    SetGameSwitching {
+        app_id: String,
+        switching: GameSwitching,
+        #[serde(default)]
+        name: Option<String>,
+    },
     GetProfiles,
     // Battery
     GetBatteryStatus,
+    /// Average power draw per refresh rate
+    GetPowerByHz,
+    // Manual override
+    SetHz {
+        hz: Hertz,
+        /// Override duration; `None` holds the rate until `ClearHz`
+        #[serde(default)]
+        duration_secs: Option<Secs>,
+    },
+    ClearHz,
+    // Transition history
+    GetTransitions {
+        #[serde(default)]
+        limit: Option<usize>,
+    },
+    // Long-term drift advisories
+    GetAdvisories,
+    /// Smoothed FPS and current Hz once a second over the last five minutes
+    GetHistory {
+        #[serde(default)]
+        limit: Option<usize>,
+    },
+    /// Run several commands in order and return all responses at once.
+    /// Entries are parsed individually so one bad entry doesn't fail the batch.
+    Batch {
+        commands: Vec<serde_json::Value>,
+    },
+    /// Push smoothed FPS and current Hz on this connection until the client
+    /// sends another command or disconnects. Unix socket only.
+    StreamFps {
+        #[serde(default)]
+        interval_ms: Option<Millis>,
+    },
+    GetVersion,
+    /// Events routed to the frontend, newer than `since`
+    GetEvents {
+        #[serde(default)]
+        since: Option<u64>,
+    },
+    /// Switch an optional subsystem on or off until the daemon restarts
+    SetSubsystemEnabled {
+        name: String,
+        enabled: bool,
+    },
+    /// Build features, switchable subsystems and experiments
+    GetCapabilities,
+    /// Refresh rates the internal panel (or controlled external output) supports
+    GetSupportedRates,
+    /// Display connectors with their status and whether they are ignored
+    GetConnectors,
+    /// Switch an experimental behavior on or off (persisted)
+    SetExperimentEnabled {
+        name: String,
+        enabled: bool,
+    },
+    /// Switch the refresh rate backend ("auto" re-runs detection). Persisted.
+    SetDisplayBackend {
+        backend: String,
+    },
+    /// Switch the FPS source ("auto" re-runs detection). Persisted; the
+    /// polling loop reconnects on its next poll.
+    SetFpsSource {
+        source: String,
+    },
+    /// Replay a recorded FPS journal under the current settings and each
+    /// scenario. Unset scenario fields inherit the current settings.
+    SimulateSettings {
+        samples: Vec<f64>,
+        #[serde(default)]
+        sample_interval_ms: Option<Millis>,
+        #[serde(default)]
+        scenarios: Vec<ScenarioOverrides>,
+    },
+    /// Negotiate the response encoding for this connection. The reply is
+    /// always JSON; later responses use the chosen encoding. Unix socket only.
+    Handshake {
+        #[serde(default)]
+        encoding: Encoding,
+    },
+}
+
+impl IpcCommand {
+    /// Whether the command stores a shell command (profile hooks). Only
+    /// Unix socket clients, which pass the UID allowlist, may do that.
+    pub fn sets_shell_command(&self) -> bool {
+        match self {
+            IpcCommand::SaveProfile { on_launch, on_exit, .. } => [on_launch, on_exit]
+                .iter()
+                .any(|hook| hook.as_ref().and_then(Option::as_deref).is_some_and(|cmd| !cmd.trim().is_empty())),
+            IpcCommand::Batch { commands } => commands.iter().any(|command| {
+                serde_json::from_value::<IpcCommand>(command.clone()).is_ok_and(|c| c.sets_shell_command())
+            }),
+            _ => false,
+        }
+    }
+}
+
+/// Response encoding negotiated with Handshake.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Encoding {
+    /// Newline-delimited JSON
+    #[default]
+    Json,
+    /// 4-byte big-endian length prefix followed by MessagePack
+    Msgpack,
+}
+
+/// What the socket loop sends for one request line.
+#[cfg(unix)]
+pub enum Reply {
+    /// A JSON response
+    Json(serde_json::Value),
+    /// Status, written from its type so MessagePack clients don't pay for an
+    /// intermediate JSON tree
+    Status(Box<StatusResponse>),
+    /// Metrics, written from their type like `Status`
+    Metrics(crate::metrics::MetricsResponse),
+    /// Send `ack` (always JSON), then switch to `encoding`
+    Handshake { encoding: Encoding, ack: serde_json::Value },
+    /// Send `ack`, then push FPS frames every `interval`
+    StreamFps { interval: Duration, ack: serde_json::Value },
+}
+
+/// Alternative settings for one SimulateSettings scenario.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ScenarioOverrides {
+    #[serde(default)]
+    pub sensitivity: Option<String>,
+    #[serde(default)]
+    pub fps_tolerance: Option<f64>,
+    #[serde(default)]
+    pub custom_drop_ms: Option<Millis>,
+    #[serde(default)]
+    pub custom_increase_ms: Option<Millis>,
+    #[serde(default)]
+    pub ramp_down: Option<bool>,
+    #[serde(default)]
+    pub drop_step: Option<StepMode>,
+    #[serde(default)]
+    pub increase_step: Option<StepMode>,
+    #[serde(default)]
+    pub video_cadence: Option<bool>,
+    #[serde(default)]
+    pub low_fps_compensation: Option<bool>,
+    #[serde(default)]
+    pub saturation_pacing: Option<f64>,
+    #[serde(default)]
+    pub loading_grace_ms: Option<Millis>,
+    #[serde(default)]
+    pub min_dwell_secs: Option<Secs>,
+    /// Enable the predictive drop experiment for this scenario
+    #[serde(default)]
+    pub predictive_drop: Option<bool>,
+    #[serde(default)]
+    pub predictive_drop_fps_per_sec: Option<f64>,
+    #[serde(default)]
+    pub control_policy: Option<ControlPolicy>,
+}
+
+impl ScenarioOverrides {
+    /// Apply the overrides on top of the current settings.
+    fn resolve(&self, config: &Config, fps_tolerance: f64, mode: DeviceMode) -> Result<Scenario, IpcError> {
+        let mut config = config.clone();
+        if let Some(sensitivity) = &self.sensitivity {
+            config.sensitivity = parse_sensitivity(sensitivity)?;
+        }
+        if self.custom_drop_ms.is_some() {
+            config.custom_drop_ms = self.custom_drop_ms;
+        }
+        if self.custom_increase_ms.is_some() {
+            config.custom_increase_ms = self.custom_increase_ms;
+        }
+        if let Some(ramp_down) = self.ramp_down {
+            config.ramp_down = false;
+            config.drop_step = if ramp_down { StepMode::Single } else { StepMode::Jump };
+        }
+        if let Some(step) = self.drop_step {
+            config.ramp_down = false;
+            config.drop_step = step;
+        }
+        if let Some(step) = self.increase_step {
+            config.increase_step = step;
+        }
+        if let Some(video_cadence) = self.video_cadence {
+            config.video_cadence = video_cadence;
+        }
+        if let Some(lfc) = self.low_fps_compensation {
+            config.low_fps_compensation = lfc;
+        }
+        if let Some(multiplier) = self.saturation_pacing {
+            config.saturation_pacing = multiplier;
+        }
+        if let Some(grace) = self.loading_grace_ms {
+            config.loading_grace_ms = grace;
+        }
+        if let Some(dwell) = self.min_dwell_secs {
+            config.min_dwell_secs = dwell;
+        }
+        if let Some(enabled) = self.predictive_drop {
+            config.experimental.predictive_drop = enabled;
+        }
+        if let Some(slope) = self.predictive_drop_fps_per_sec {
+            config.predictive_drop_fps_per_sec = slope;
+        }
+        if let Some(policy) = self.control_policy {
+            config.control_policy = policy;
+        }
+        config
+            .validate()
+            .map_err(|e| IpcError::InvalidCommand(e.to_string()))?;
+        Ok(Scenario::from_config(
+            &config,
+            self.fps_tolerance.unwrap_or(fps_tolerance),
+            mode,
+        ))
+    }
+}
+
+/// One frame pushed by StreamFps
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct FpsStreamFrame {
+    pub fps: f64,
+    pub hz: Hertz,
 }
 
 /// Transition record for UI display
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TransitionRecord {
     pub timestamp: String,
-    pub from_hz: u32,
-    pub to_hz: u32,
+    pub from_hz: Hertz,
+    pub to_hz: Hertz,
     pub fps: f64,
     pub direction: String, // "Dropped" or "Increased"
 }
 
+/// Transition history response for GetTransitions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransitionsResponse {
+    /// Most recent transitions, oldest first
+    pub transitions: Vec<TransitionRecord>,
+    /// Ring buffer capacity
+    pub capacity: usize,
+}
+
+/// Manual refresh rate override set via IPC.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HzOverride {
+    pub hz: Hertz,
+    /// Expiry time, or `None` to hold until cleared
+    pub until: Option<Instant>,
+}
+
+impl HzOverride {
+    /// Check if the override has run out at the given time.
+    pub fn is_expired(&self, now: Instant) -> bool {
+        self.until.is_some_and(|until| now >= until)
+    }
+
+    /// Remaining override time in seconds (`None` if held until cleared).
+    pub fn remaining_secs(&self, now: Instant) -> Option<f64> {
+        self.until
+            .map(|until| until.saturating_duration_since(now).as_secs_f64())
+    }
+}
+
 /// Configuration portion of status response.
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct ConfigResponse {
-    pub min_hz: u32,
-    pub max_hz: u32,
+    pub min_hz: Hertz,
+    pub max_hz: Hertz,
     pub sensitivity: String,
     pub enabled: bool,
     pub adaptive_sensitivity: bool,
+    pub custom_drop_ms: Option<Millis>,
+    pub custom_increase_ms: Option<Millis>,
+    #[serde(default)]
+    pub ramp_down: bool,
+    #[serde(default)]
+    pub drop_step: StepMode,
+    #[serde(default)]
+    pub increase_step: StepMode,
+    #[serde(default)]
+    pub video_cadence: bool,
+    #[serde(default)]
+    pub low_fps_compensation: bool,
+    #[serde(default)]
+    pub control_policy: ControlPolicy,
+    #[serde(default)]
+    pub idle_policy: IdlePolicy,
 }
 
 impl ConfigResponse {
@@ -113,6 +572,15 @@ impl ConfigResponse {
             sensitivity: sensitivity_to_string(config.sensitivity),
             enabled: config.enabled,
             adaptive_sensitivity: adaptive,
+            custom_drop_ms: config.custom_drop_ms,
+            custom_increase_ms: config.custom_increase_ms,
+            ramp_down: config.effective_drop_step() == StepMode::Single,
+            drop_step: config.effective_drop_step(),
+            increase_step: config.increase_step,
+            video_cadence: config.video_cadence,
+            low_fps_compensation: config.low_fps_compensation,
+            control_policy: config.control_policy,
+            idle_policy: config.idle_policy,
         }
     }
 }
@@ -122,7 +590,7 @@ impl ConfigResponse {
 pub struct StatusResponse {
     pub running: bool,
     pub current_fps: f64,
-    pub current_hz: u32,
+    pub current_hz: Hertz,
     pub state: String,
     pub device_mode: String,
     pub config: ConfigResponse,
@@ -131,10 +599,120 @@ pub struct StatusResponse {
     pub fps_std_dev: f64,
     pub current_app_id: Option<String>,
     pub transitions: Vec<TransitionRecord>,
+    // Sensitivity in use; differs from config.sensitivity under adaptive or LCD mode
+    #[serde(default)]
+    pub effective_sensitivity: String,
     // v2.0.1 advanced fields
     pub fps_tolerance: f64,
     pub resume_cooldown_remaining: f64,
     pub sync_frame_limiter: bool,
+    // Manual override (SetHz)
+    pub override_hz: Option<Hertz>,
+    pub override_remaining: Option<f64>,
+    // Idle deep sleep
+    pub deep_sleep: bool,
+    // Runtime subsystem switches
+    pub subsystems: SubsystemStatus,
+    // Switch budget usage (when configured)
+    pub switch_budget: Option<SwitchBudgetStatus>,
+    // Frametime spikes in the stutter window
+    pub frametime_spikes: usize,
+    // Seconds left of oscillation damping (widened tolerance/interval)
+    #[serde(default)]
+    pub oscillation_damping_remaining: f64,
+    // Seconds left of a loading-screen hold
+    #[serde(default)]
+    pub loading_hold_remaining: f64,
+    // Frame limit while low FPS compensation is engaged
+    #[serde(default)]
+    pub lfc_fps_limit: Option<u32>,
+    // Effective drop/increase timing at the current rate
+    #[serde(default)]
+    pub pacing: DecisionPacing,
+    // Enabled experimental behaviors
+    #[serde(default)]
+    pub experimental: ExperimentalConfig,
+    // APU temperature and thermal hold
+    #[serde(default)]
+    pub thermal: ThermalStatus,
+    // GPU busy, CPU load and the saturated-GPU hold
+    #[serde(default)]
+    pub utilization: UtilizationStatus,
+    // Backend applying refresh rates
+    #[serde(default)]
+    pub display_backend: BackendKind,
+    // Source the FPS is read from, none while disconnected
+    #[serde(default)]
+    pub fps_source: Option<FpsSourceKind>,
+    // Process the FPS is read from, with per-process MangoHud segments
+    #[serde(default)]
+    pub fps_pid: Option<u32>,
+    // VRR mode: requested, panel capability, active and its frame limit
+    #[serde(default)]
+    pub vrr: VrrStatus,
+    // Switching paused after repeated display backend failures
+    #[serde(default)]
+    pub display_backend_unhealthy: bool,
+    // Handheld model selecting the built-in presets
+    #[serde(default)]
+    pub device_model: DeviceModel,
+    // Docked profile in use (external display on external power)
+    #[serde(default)]
+    pub docked: bool,
+    // External output controlled instead of the panel (e.g. "DP-1")
+    #[serde(default)]
+    pub active_output: Option<String>,
+    // Display connectors with their current mode and role
+    #[serde(default)]
+    pub displays: Vec<DisplayStatus>,
+    // Game mode (gamescope) or desktop mode session
+    #[serde(default)]
+    pub session: SessionKind,
+    // Switching paused for a desktop session
+    #[serde(default)]
+    pub session_paused: bool,
+    // Steam overlay open, controller frozen until it closes
+    #[serde(default)]
+    pub overlay_open: bool,
+    // FPS readings frozen (game paused or hung), holding the current rate
+    #[serde(default)]
+    pub fps_stale: bool,
+    // Current FPS polling and controller interval (slower while FPS is steady)
+    #[serde(default)]
+    pub poll_interval_ms: Millis,
+    // Smoother behind current_fps: "mean", "ema", "median" or "trimmed_mean"
+    #[serde(default)]
+    pub fps_smoothing: FpsSmoothing,
+    // 99th percentile frametime in microseconds over the FPS window
+    #[serde(default)]
+    pub frametime_p99_us: u64,
+    // "stable", "variable" or "unstable" by the adaptive sensitivity thresholds;
+    // null until the sliding window fills
+    #[serde(default)]
+    pub fps_stability: Option<String>,
+    // What is recording or streaming (e.g. "obs"), locking the rate until it ends
+    #[serde(default)]
+    pub recording: Option<String>,
+    // OS update in progress (updater or lock), holding the rate until it ends
+    #[serde(default)]
+    pub system_update: Option<String>,
+    // Current game blacklisted (or not whitelisted), holding its max rate
+    #[serde(default)]
+    pub game_switching_disabled: bool,
+    // Served from the previous run's snapshot while live data is pending
+    #[serde(default)]
+    pub cached: bool,
+}
+
+/// Build features, switchable subsystems and experiments.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CapabilitiesResponse {
+    pub build: BuildInfo,
+    pub subsystems: Vec<Subsystem>,
+    pub experiments: Vec<ExperimentInfo>,
+    /// Internal panel from its EDID, when readable
+    #[serde(default)]
+    pub panel: Option<PanelInfo>,
 }
 
 /// Convert Sensitivity enum to string.
@@ -143,6 +721,7 @@ pub fn sensitivity_to_string(sensitivity: Sensitivity) -> String {
         Sensitivity::Conservative => "conservative".to_string(),
         Sensitivity::Balanced => "balanced".to_string(),
         Sensitivity::Aggressive => "aggressive".to_string(),
+        Sensitivity::Custom => "custom".to_string(),
     }
 }
 
@@ -152,8 +731,9 @@ pub fn parse_sensitivity(s: &str) -> Result<Sensitivity, IpcError> {
         "conservative" => Ok(Sensitivity::Conservative),
         "balanced" => Ok(Sensitivity::Balanced),
         "aggressive" => Ok(Sensitivity::Aggressive),
+        "custom" => Ok(Sensitivity::Custom),
         _ => Err(IpcError::InvalidCommand(format!(
-            "Invalid sensitivity '{}', expected one of: conservative, balanced, aggressive",
+            "Invalid sensitivity '{}', expected one of: conservative, balanced, aggressive, custom",
             s
         ))),
     }
@@ -190,6 +770,31 @@ pub fn algorithm_state_to_string(state: AlgorithmState) -> String {
     }
 }
 
+/// What the daemon is doing with the rate, for the MangoHud overlay line
+/// and the status page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ControlState {
+    /// Control stopped
+    Paused,
+    /// Manual override (SetHz) in effect
+    Manual,
+    Stable,
+    Dropping,
+    Increasing,
+}
+
+impl ControlState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ControlState::Paused => "Paused",
+            ControlState::Manual => "Manual",
+            ControlState::Stable => "Stable",
+            ControlState::Dropping => "Dropping",
+            ControlState::Increasing => "Increasing",
+        }
+    }
+}
+
 /// Shared daemon state accessible by the IPC server.
 pub struct DaemonState {
     /// Whether the refresh rate control loop is running
@@ -200,18 +805,86 @@ pub struct DaemonState {
     pub current_hz: AtomicU32,
     /// Hysteresis controller for algorithm state
     pub controller: RwLock<HysteresisController>,
+    /// Applies refresh rates through the selected backend
+    pub display: Arc<DisplayManager>,
     /// Configuration manager
     pub config_manager: Arc<ConfigManager>,
     /// Profile manager
     pub profile_manager: Arc<RwLock<ProfileManager>>,
+    /// Held while writing profiles.json, so saves land in order
+    profile_save: tokio::sync::Mutex<()>,
     /// Metrics collector
     pub metrics: Arc<MetricsCollector>,
     /// Battery monitor
     pub battery_monitor: Arc<BatteryMonitor>,
+    /// External display connectors
+    pub monitor_detector: Arc<MonitorDetector>,
+    /// Display connectors at the last display check
+    connectors: RwLock<Vec<ConnectorStatus>>,
+    /// Mode each output was scanned out at, by output name
+    current_modes: RwLock<Vec<(String, PanelMode)>>,
     /// MangoHud availability
     mangohud_available: AtomicBool,
-    /// Transition history
-    transitions: RwLock<Vec<TransitionRecord>>,
+    /// Connected FPS source
+    fps_source: Mutex<Option<FpsSourceKind>>,
+    /// Process the FPS source follows
+    fps_pid: Mutex<Option<u32>>,
+    /// Docked with a docked profile in use
+    docked: AtomicBool,
+    /// Detected graphical session
+    session: Mutex<SessionKind>,
+    /// Steam overlay open over the game
+    overlay_open: AtomicBool,
+    /// FPS source repeating the same sample
+    fps_stale: AtomicBool,
+    /// FPS polling and controller interval in milliseconds
+    poll_interval_ms: AtomicU64,
+    /// P99 frametime over the FPS window in microseconds
+    frametime_p99_us: AtomicU64,
+    /// Active screen recorder or stream, locking the rate
+    recording: Mutex<Option<String>>,
+    /// In-progress OS update, holding the rate
+    system_update: Mutex<Option<String>>,
+    /// Transition history (ring buffer, oldest first)
+    transitions: RwLock<VecDeque<TransitionRecord>>,
+    /// Manual refresh rate override
+    hz_override: RwLock<Option<HzOverride>>,
+    /// Event routing to log, feedback command and frontend
+    notifications: Notifications,
+    /// First-session observer for a game without a profile
+    session_observer: RwLock<Option<SessionObserver>>,
+    /// Drift advisories from long-term trend analysis
+    advisories: RwLock<Vec<Advisory>>,
+    /// Idle deep-sleep state shared with the background tasks
+    pub sleep: SleepController,
+    /// Runtime on/off switches for optional subsystems
+    pub subsystems: Subsystems,
+    /// Experimental behavior flags
+    pub experiments: Experiments,
+    /// Last APU temperature reading and throttling state
+    thermal: Mutex<ThermalStatus>,
+    /// Last GPU/CPU utilization sample
+    utilization: Mutex<UtilizationStatus>,
+    /// Downsampled FPS and Hz for the UI sparkline
+    history: Mutex<History>,
+    /// Panel rates, enumerated on first request
+    supported_rates: tokio::sync::OnceCell<SupportedRates>,
+    /// Rates of the external output controlled instead of the panel
+    output_rates: RwLock<Option<SupportedRates>>,
+    /// Internal panel from its EDID
+    panel: std::sync::OnceLock<PanelInfo>,
+    /// Handheld model selecting the built-in presets
+    device_model: std::sync::OnceLock<DeviceModel>,
+    /// Recent switches counted against the configured budget
+    switch_budget: Mutex<SwitchBudget>,
+    /// Whether the budget was spent at the last check (for one-shot logging)
+    switch_budget_exhausted: AtomicBool,
+    /// Status from the previous run, served until live data arrives
+    cold_start_status: RwLock<Option<StatusResponse>>,
+    /// Whether the FPS or battery task has produced data yet
+    live: AtomicBool,
+    /// Daemon start time (bounds how long the cold-start status is served)
+    started: Instant,
 }
 
 impl DaemonState {
@@ -225,172 +898,1061 @@ impl DaemonState {
         let config = config_manager.get();
         let mut controller = HysteresisController::new(config.sensitivity);
         controller.set_user_range(config.min_hz, config.max_hz);
+        if let Some((drop, increase)) = config.custom_thresholds() {
+            controller.set_custom_thresholds(drop, increase);
+        }
+        controller.set_allowed_hz(config.allowed_hz.clone());
+        controller.set_drop_step(config.effective_drop_step());
+        controller.set_increase_step(config.increase_step);
+        controller.set_video_cadence(config.video_cadence);
+        controller.set_low_fps_compensation(config.low_fps_compensation);
+        controller.set_control_policy(config.control_policy);
+        controller.set_saturation_pacing(config.saturation_pacing);
+        controller.set_loading_grace(config.loading_grace_ms);
+        controller.set_min_dwell(config.min_dwell_secs);
+        controller.set_predictive_drop(config.predictive_drop());
+        controller.set_adaptive_sensitivity(config.adaptive_sensitivity);
+        controller.set_fps_tolerance(config.fps_tolerance);
+        controller.set_resume_cooldown(config.resume_cooldown_secs);
         
         Self {
             running: AtomicBool::new(config.enabled),
             current_fps: RwLock::new(0.0),
-            current_hz: AtomicU32::new(config.max_hz),
+            current_hz: AtomicU32::new(config.max_hz.get()),
             controller: RwLock::new(controller),
+            display: Arc::new(DisplayManager::new(config.min_hz, config.max_hz)),
             config_manager,
             profile_manager,
+            profile_save: tokio::sync::Mutex::new(()),
             metrics,
             battery_monitor,
+            monitor_detector: Arc::new(MonitorDetector::new()),
+            connectors: RwLock::new(Vec::new()),
+            current_modes: RwLock::new(Vec::new()),
             mangohud_available: AtomicBool::new(false),
-            transitions: RwLock::new(Vec::new()),
+            fps_source: Mutex::new(None),
+            fps_pid: Mutex::new(None),
+            docked: AtomicBool::new(false),
+            session: Mutex::new(SessionKind::Unknown),
+            overlay_open: AtomicBool::new(false),
+            fps_stale: AtomicBool::new(false),
+            poll_interval_ms: AtomicU64::new(0),
+            frametime_p99_us: AtomicU64::new(0),
+            recording: Mutex::new(None),
+            system_update: Mutex::new(None),
+            transitions: RwLock::new(VecDeque::with_capacity(MAX_TRANSITION_HISTORY)),
+            hz_override: RwLock::new(None),
+            notifications: Notifications::default(),
+            session_observer: RwLock::new(None),
+            advisories: RwLock::new(Vec::new()),
+            sleep: SleepController::new(),
+            subsystems: Subsystems::default(),
+            experiments: Experiments::from_config(&config.experimental),
+            thermal: Mutex::new(ThermalStatus::default()),
+            utilization: Mutex::new(UtilizationStatus::default()),
+            history: Mutex::new(History::default()),
+            supported_rates: tokio::sync::OnceCell::new(),
+            output_rates: RwLock::new(None),
+            panel: std::sync::OnceLock::new(),
+            device_model: std::sync::OnceLock::new(),
+            switch_budget: Mutex::new(SwitchBudget::default()),
+            switch_budget_exhausted: AtomicBool::new(false),
+            cold_start_status: RwLock::new(None),
+            live: AtomicBool::new(false),
+            started: Instant::now(),
         }
     }
 
-    /// Set MangoHud availability
-    pub fn set_mangohud_available(&self, available: bool) {
-        self.mangohud_available.store(available, Ordering::SeqCst);
+    /// Whether the docked profile is in use.
+    pub fn is_docked(&self) -> bool {
+        self.docked.load(Ordering::Relaxed)
     }
 
-    /// Record a transition for UI display
-    pub async fn record_transition(&self, from_hz: u32, to_hz: u32, fps: f64) {
-        let direction = if to_hz < from_hz { "Dropped" } else { "Increased" };
-        let timestamp = chrono_lite_timestamp();
-        
-        let record = TransitionRecord {
-            timestamp,
-            from_hz,
-            to_hz,
-            fps,
-            direction: direction.to_string(),
-        };
+    /// Detected graphical session.
+    pub fn session(&self) -> SessionKind {
+        self.session.lock().map(|session| *session).unwrap_or_default()
+    }
 
-        if let Ok(mut transitions) = self.transitions.write() {
-            transitions.push(record);
-            if transitions.len() > MAX_TRANSITION_HISTORY {
-                transitions.remove(0);
-            }
+    /// Record the detected session. Returns the previous one.
+    pub fn set_session(&self, session: SessionKind) -> SessionKind {
+        self.session
+            .lock()
+            .map(|mut current| std::mem::replace(&mut *current, session))
+            .unwrap_or_default()
+    }
+
+    /// Whether switching is paused for a desktop session
+    /// (`desktop_mode_switching` off).
+    pub fn is_session_paused(&self) -> bool {
+        self.session() == SessionKind::Desktop && !self.config_manager.get().desktop_mode_switching
+    }
+
+    /// Whether the Steam overlay is open, freezing the controller.
+    pub fn is_overlay_open(&self) -> bool {
+        self.overlay_open.load(Ordering::Relaxed)
+    }
+
+    /// Record whether the Steam overlay is open. Opening it drops a pending
+    /// switch, since the FPS dip that started it is the overlay's. Returns
+    /// the previous state.
+    pub async fn set_overlay_open(&self, open: bool) -> bool {
+        let was_open = self.overlay_open.swap(open, Ordering::Relaxed);
+        if open && !was_open {
+            self.controller.write().await.cancel_pending();
         }
+        was_open
     }
 
-    /// Get the current status as a StatusResponse.
-    pub async fn get_status(&self) -> StatusResponse {
-        let config = self.config_manager.get();
-        let controller = self.controller.read().await;
-        let current_fps = *self.current_fps.read().await;
-        let profile_manager = self.profile_manager.read().await;
-        let transitions = self.transitions.read()
-            .map(|t| t.clone())
-            .unwrap_or_default();
+    /// Whether the FPS readings are frozen, holding the current rate.
+    pub fn is_fps_stale(&self) -> bool {
+        self.fps_stale.load(Ordering::Relaxed)
+    }
 
-        StatusResponse {
-            running: self.running.load(Ordering::SeqCst),
-            current_fps,
-            current_hz: self.current_hz.load(Ordering::SeqCst),
-            state: algorithm_state_to_string(controller.state()),
-            device_mode: device_mode_to_string(controller.device_mode()),
-            config: ConfigResponse::from_config(&config, controller.is_adaptive_sensitivity_enabled()),
-            mangohud_available: self.mangohud_available.load(Ordering::SeqCst),
-            external_display_detected: controller.is_external_display_detected(),
-            fps_std_dev: controller.get_fps_std_dev(),
-            current_app_id: profile_manager.get_current_game().cloned(),
-            transitions,
-            // v2.0.1 advanced fields
-            fps_tolerance: controller.fps_tolerance(),
-            resume_cooldown_remaining: controller.resume_cooldown_remaining(),
-            sync_frame_limiter: controller.is_sync_frame_limiter_enabled(),
+    /// Record whether the FPS readings are frozen. Going stale drops a
+    /// pending switch, which was started by the frozen value. Returns the
+    /// previous state.
+    pub async fn set_fps_stale(&self, stale: bool) -> bool {
+        let was_stale = self.fps_stale.swap(stale, Ordering::Relaxed);
+        if stale && !was_stale {
+            self.controller.write().await.cancel_pending();
         }
+        was_stale
     }
 
-    /// Start the refresh rate control loop.
-    pub fn start(&self) {
-        self.running.store(true, Ordering::SeqCst);
+    /// Interval the FPS loop polls and the controller runs at.
+    pub fn poll_interval(&self) -> Duration {
+        Duration::from_millis(self.poll_interval_ms.load(Ordering::Relaxed))
     }
 
-    /// Stop the refresh rate control loop.
-    pub fn stop(&self) {
-        self.running.store(false, Ordering::SeqCst);
+    pub fn set_poll_interval(&self, interval: Duration) {
+        self.poll_interval_ms.store(interval.as_millis() as u64, Ordering::Relaxed);
     }
 
-    /// Check if the daemon is running.
-    pub fn is_running(&self) -> bool {
-        self.running.load(Ordering::SeqCst)
+    /// P99 frametime (µs) the FPS source last reported.
+    pub fn frametime_p99(&self) -> u64 {
+        self.frametime_p99_us.load(Ordering::Relaxed)
     }
-}
 
-/// Simple timestamp without chrono dependency
-fn chrono_lite_timestamp() -> String {
-    use std::time::{SystemTime, UNIX_EPOCH};
-    let duration = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap_or_default();
-    let secs = duration.as_secs();
-    let hours = (secs / 3600) % 24;
-    let mins = (secs / 60) % 60;
-    let secs = secs % 60;
-    format!("{:02}:{:02}:{:02}", hours, mins, secs)
-}
+    pub fn set_frametime_p99(&self, frametime_us: u64) {
+        self.frametime_p99_us.store(frametime_us, Ordering::Relaxed);
+    }
 
-/// Unix Domain Socket server for IPC.
-#[cfg(unix)]
-pub struct IpcServer {
-    socket_path: PathBuf,
-    listener: UnixListener,
-}
+    /// Active screen recorder or stream, if any.
+    pub fn recording(&self) -> Option<String> {
+        self.recording.lock().ok().and_then(|recording| recording.clone())
+    }
 
-#[cfg(unix)]
-impl IpcServer {
-    pub async fn new(path: &str) -> Result<Self, IpcError> {
-        let socket_path = PathBuf::from(path);
-        Self::cleanup_socket(&socket_path)?;
+    /// Record the active recorder. A rate lock would strand a pending
+    /// switch, so starting a recording drops it. Returns the previous one.
+    pub async fn set_recording(&self, recording: Option<String>) -> Option<String> {
+        let starting = recording.is_some();
+        let previous = self
+            .recording
+            .lock()
+            .ok()
+            .and_then(|mut current| std::mem::replace(&mut *current, recording));
+        if starting && previous.is_none() {
+            self.controller.write().await.cancel_pending();
+        }
+        previous
+    }
 
-        let listener = UnixListener::bind(&socket_path).map_err(|e| IpcError::SocketBindFailed {
-            path: path.to_string(),
-            source: e,
-        })?;
+    /// In-progress OS update, if any.
+    pub fn system_update(&self) -> Option<String> {
+        self.system_update.lock().ok().and_then(|update| update.clone())
+    }
 
-        Ok(Self {
-            socket_path,
-            listener,
-        })
+    /// Record the in-progress OS update. Returns the previous one.
+    pub fn set_system_update(&self, update: Option<String>) -> Option<String> {
+        self.system_update
+            .lock()
+            .ok()
+            .and_then(|mut current| std::mem::replace(&mut *current, update))
     }
 
-    pub async fn new_default() -> Result<Self, IpcError> {
-        Self::new(DEFAULT_SOCKET_PATH).await
+    /// Enter or leave the docked profile and apply the settings that now
+    /// hold.
+    pub async fn set_docked(&self, docked: bool) {
+        if self.docked.swap(docked, Ordering::Relaxed) != docked {
+            let profile_manager = self.profile_manager.read().await;
+            self.apply_profile_settings(&profile_manager).await;
+        }
     }
 
-    fn cleanup_socket(path: &Path) -> Result<(), IpcError> {
-        if path.exists() {
-            std::fs::remove_file(path).map_err(|e| IpcError::SocketBindFailed {
-                path: path.display().to_string(),
-                source: e,
-            })?;
+    /// Apply the docked profile while docked, else the current game's
+    /// profile or the global defaults, to the controller and the display
+    /// range. Returns the name of the game profile applied, if any.
+    pub async fn apply_profile_settings(&self, profile_manager: &ProfileManager) -> Option<String> {
+        let config = self.config_manager.get();
+        let mut controller = self.controller.write().await;
+        if let Some(docked) = profile_manager.docked.as_ref().filter(|_| self.is_docked()) {
+            let (min_hz, max_hz) = docked.hz_range();
+            controller.set_user_range(min_hz, max_hz);
+            controller.set_sensitivity(docked.get_sensitivity());
+            controller.set_adaptive_sensitivity(docked.adaptive_sensitivity);
+            controller.set_min_dwell(config.min_dwell_secs);
+            self.display.set_range(min_hz, max_hz);
+            tracing::info!("Applied the docked profile ({}-{}Hz)", min_hz, max_hz);
+            return None;
         }
-        Ok(())
+
+        let profile = profile_manager
+            .get_current_game()
+            .and_then(|id| profile_manager.get_profile(id));
+        if let Some(profile) = profile {
+            controller.set_user_range(profile.min_hz, profile.max_hz);
+            controller.set_sensitivity(profile.get_sensitivity());
+            controller.set_adaptive_sensitivity(profile.adaptive_sensitivity);
+            controller.set_min_dwell(profile.min_dwell_secs.unwrap_or(config.min_dwell_secs));
+            self.display.set_range(profile.min_hz, profile.max_hz);
+            tracing::info!(
+                "Applied profile for {} ({}): {}-{}Hz, {:?} sensitivity",
+                profile.name,
+                profile.app_id,
+                profile.min_hz,
+                profile.max_hz,
+                profile.get_sensitivity()
+            );
+            return Some(profile.name.clone());
+        }
+
+        // Global defaults
+        let (min_hz, max_hz, sensitivity, _) = profile_manager.get_current_settings();
+        controller.set_user_range(min_hz, max_hz);
+        controller.set_sensitivity(sensitivity);
+        controller.set_adaptive_sensitivity(config.adaptive_sensitivity);
+        controller.set_min_dwell(config.min_dwell_secs);
+        self.display.set_range(min_hz, max_hz);
+        tracing::info!("Applied global defaults: {}-{}Hz, {:?} sensitivity", min_hz, max_hz, sensitivity);
+        None
     }
 
-    pub fn socket_path(&self) -> &Path {
-        &self.socket_path
+    /// Make `app_id` the current game: run the game hooks if it changed,
+    /// watch the first session of games without a profile and apply the
+    /// matching settings. Returns the name of the game profile applied, if any.
+    pub async fn switch_game(&self, app_id: Option<String>, name: Option<String>) -> Option<String> {
+        let mut profile_manager = self.profile_manager.write().await;
+
+        let previous_app_id = profile_manager.get_current_game().cloned();
+        profile_manager.set_current_game(app_id.clone());
+        if app_id.is_some() {
+            self.sleep.note_activity();
+        }
+        if previous_app_id != app_id {
+            self.run_game_hooks(&profile_manager, previous_app_id.as_deref(), app_id.as_deref());
+        }
+
+        // Watch the first session of games without a profile
+        let observer = app_id
+            .as_ref()
+            .filter(|id| profile_manager.get_profile(id).is_none())
+            .map(|id| {
+                let name = name.filter(|n| !n.is_empty()).unwrap_or_else(|| id.clone());
+                SessionObserver::new(id.clone(), name)
+            });
+        self.set_session_observer(observer).await;
+
+        // Apply the profile if one exists (the docked profile wins while docked)
+        self.apply_profile_settings(&profile_manager).await
     }
 
-    pub async fn run(&self, state: Arc<DaemonState>) -> Result<(), IpcError> {
-        loop {
-            match self.listener.accept().await {
-                Ok((stream, _addr)) => {
-                    let state = Arc::clone(&state);
-                    tokio::spawn(async move {
-                        if let Err(e) = Self::handle_connection(stream, state).await {
-                            tracing::warn!("Error handling IPC connection: {}", e);
-                        }
-                    });
-                }
-                Err(e) => {
-                    tracing::error!("Error accepting IPC connection: {}", e);
-                }
-            }
+    /// Record the internal panel: its refresh range bounds the allowed Hz
+    /// range and a known Steam Deck panel selects the device mode.
+    pub async fn set_panel(&self, panel: PanelInfo) {
+        // A model preset already picked the device mode
+        if let Some(mode) = panel.device_mode().filter(|_| self.device_model().preset().is_none()) {
+            tracing::info!("Detected device mode {} from the panel EDID", device_mode_to_string(mode));
+            self.controller.write().await.apply_mode_constraints(mode);
         }
+        let _ = self.panel.set(panel);
+        self.apply_hz_range().await;
     }
 
-    async fn handle_connection(
-        stream: UnixStream,
-        state: Arc<DaemonState>,
-    ) -> Result<(), IpcError> {
-        let (reader, mut writer) = stream.into_split();
-        let mut reader = BufReader::new(reader);
-        let mut line = String::new();
+    /// Record the handheld model and apply its preset's device mode and
+    /// minimum change interval.
+    pub async fn set_device_model(&self, model: DeviceModel) {
+        if let Some(preset) = model.preset() {
+            tracing::info!(
+                "Device model {}: {}-{}Hz, mode {}, min change interval {}ms",
+                model.as_str(),
+                preset.hz_range.start(),
+                preset.hz_range.end(),
+                device_mode_to_string(preset.mode),
+                preset.min_change_interval.get()
+            );
+            let mut controller = self.controller.write().await;
+            controller.apply_mode_constraints(preset.mode);
+            controller.set_min_change_interval(Duration::from(preset.min_change_interval));
+        }
+        let _ = self.device_model.set(model);
+    }
+
+    /// Handheld model, `Unknown` until detected.
+    pub fn device_model(&self) -> DeviceModel {
+        self.device_model.get().copied().unwrap_or_default()
+    }
+
+    /// Derive the allowed Hz range from, in order, the display backend, the
+    /// panel's EDID, its DRM modes and the model preset, falling back to
+    /// 40-90 Hz, and bound
+    /// the display, config and controller ranges by it.
+    pub async fn apply_hz_range(&self) -> HzRangeSource {
+        // A controlled external output is bounded by its own rates, without
+        // narrowing the configured range to them
+        if let Some(rates) = self.output_rates.read().await.clone() {
+            tracing::info!("Allowed refresh range {}-{}Hz (external output)", rates.min, rates.max);
+            self.display.set_panel_range(rates.min..=rates.max, HzRangeSource::Output);
+            let config = self.config_manager.get();
+            self.display.set_range(config.min_hz, config.max_hz);
+            return HzRangeSource::Output;
+        }
+
+        let (range, source) = if let Some(range) = self.display.backend_refresh_range().await {
+            (range, HzRangeSource::Backend)
+        } else if let Some(range) = self.panel().and_then(PanelInfo::hz_range) {
+            (range, HzRangeSource::Edid)
+        } else {
+            let rates = self.supported_rates().await;
+            match rates.source {
+                RateSource::Drm => (rates.min..=rates.max, HzRangeSource::Drm),
+                RateSource::Fallback => match self.device_model().preset() {
+                    Some(preset) => (preset.hz_range, HzRangeSource::Preset),
+                    None => (MIN_ALLOWED_HZ..=MAX_ALLOWED_HZ, HzRangeSource::Fallback),
+                },
+            }
+        };
+        tracing::info!("Allowed refresh range {}-{}Hz ({:?})", range.start(), range.end(), source);
+
+        self.display.set_panel_range(range.clone(), source);
+        self.battery_monitor.set_max_hz(*range.end());
+        self.config_manager.set_panel_range(range);
+        let config = self.config_manager.get();
+        self.display.set_range(config.min_hz, config.max_hz);
+        let (min, max) = (self.display.get_min_hz(), self.display.get_max_hz());
+        self.controller.write().await.set_user_range(min, max);
+        source
+    }
+
+    /// Control the external output `output` (e.g. "DP-1") instead of the
+    /// panel, or the panel again with `None`. The output's DRM rates bound
+    /// the range and are the only rates it is switched between. Returns
+    /// whether an external output is now controlled; one the backend can't
+    /// reach or whose modes can't be read is not.
+    pub async fn set_active_output(&self, output: Option<String>) -> bool {
+        let output = output.filter(|_| self.display.supports_outputs());
+        if self.display.active_output() == output {
+            return output.is_some();
+        }
+        let rates = match &output {
+            Some(name) => {
+                let name = name.clone();
+                tokio::task::spawn_blocking(move || {
+                    SupportedRates::detect_output(Path::new(drm_modes::DRI_DIR), &name)
+                })
+                .await
+                .ok()
+                .flatten()
+            }
+            None => None,
+        };
+        if let (Some(name), None) = (&output, &rates) {
+            tracing::debug!("Can't read the modes of {}, not controlling it", name);
+        }
+        let output = output.filter(|_| rates.is_some());
+        if self.display.active_output() == output {
+            return output.is_some();
+        }
+
+        let allowed = match &rates {
+            Some(rates) => rates.rates.clone(),
+            None => self.config_manager.get().allowed_hz,
+        };
+        *self.output_rates.write().await = rates;
+        self.display.set_active_output(output.clone());
+        self.controller.write().await.set_allowed_hz(allowed);
+        self.apply_hz_range().await;
+        match &output {
+            Some(name) => tracing::info!("Controlling external output {}", name),
+            None => tracing::info!("Controlling the internal panel"),
+        }
+        output.is_some()
+    }
+
+    /// Re-read the display connectors and their current modes for `GetStatus`.
+    pub async fn refresh_displays(&self) {
+        let ignored = self.config_manager.get().ignored_connectors;
+        let connectors = self.monitor_detector.connectors(&ignored).await;
+        let modes = tokio::task::spawn_blocking(|| drm_modes::current_modes(Path::new(drm_modes::DRI_DIR)))
+            .await
+            .unwrap_or_default();
+        *self.connectors.write().await = connectors;
+        *self.current_modes.write().await = modes;
+    }
+
+    /// Rates the controlled output may switch between: the external
+    /// output's own rates, else the configured allowed set.
+    pub async fn allowed_hz(&self) -> Vec<Hertz> {
+        match self.output_rates.read().await.as_ref() {
+            Some(rates) => rates.rates.clone(),
+            None => self.config_manager.get().allowed_hz,
+        }
+    }
+
+    /// Rates of the controlled external output, if any.
+    pub async fn output_rates(&self) -> Option<SupportedRates> {
+        self.output_rates.read().await.clone()
+    }
+
+    /// Internal panel from its EDID, if detected.
+    pub fn panel(&self) -> Option<&PanelInfo> {
+        self.panel.get()
+    }
+
+    /// Refresh rates the internal panel supports (enumerated once).
+    pub async fn supported_rates(&self) -> SupportedRates {
+        self.supported_rates
+            .get_or_init(|| async {
+                tokio::task::spawn_blocking(|| SupportedRates::detect(Path::new(drm_modes::DRI_DIR)))
+                    .await
+                    .unwrap_or_else(|_| SupportedRates::fallback())
+            })
+            .await
+            .clone()
+    }
+
+    /// Serve `status` from GetStatus until live data arrives.
+    pub async fn set_cold_start_status(&self, status: StatusResponse) {
+        *self.cold_start_status.write().await = Some(status);
+    }
+
+    /// Note that a background task produced real data; stops serving the
+    /// cold-start status.
+    pub async fn mark_live(&self) {
+        if !self.live.swap(true, Ordering::Relaxed) {
+            self.cold_start_status.write().await.take();
+        }
+    }
+
+    /// Set MangoHud availability
+    pub fn set_mangohud_available(&self, available: bool) {
+        self.mangohud_available.store(available, Ordering::SeqCst);
+    }
+
+    /// Record the connected FPS source (`None` when disconnected), which
+    /// also sets FPS availability.
+    pub fn set_fps_source(&self, source: Option<FpsSourceKind>) {
+        if let Ok(mut current) = self.fps_source.lock() {
+            *current = source;
+        }
+        if source.is_none() {
+            self.set_fps_pid(None);
+        }
+        self.set_mangohud_available(source.is_some());
+    }
+
+    /// Record the process the FPS source follows (per-process MangoHud
+    /// segments).
+    pub fn set_fps_pid(&self, pid: Option<u32>) {
+        if let Ok(mut current) = self.fps_pid.lock() {
+            *current = pid;
+        }
+    }
+
+    /// Process the FPS source follows, if it is per process.
+    pub fn fps_pid(&self) -> Option<u32> {
+        self.fps_pid.lock().ok().and_then(|pid| *pid)
+    }
+
+    /// Connected FPS source, if any.
+    pub fn fps_source(&self) -> Option<FpsSourceKind> {
+        self.fps_source.lock().ok().and_then(|source| *source)
+    }
+
+    /// Record a transition for UI display
+    pub async fn record_transition(&self, from_hz: Hertz, to_hz: Hertz, fps: f64) {
+        let direction = if to_hz < from_hz { "Dropped" } else { "Increased" };
+        let timestamp = chrono_lite_timestamp();
+        
+        let record = TransitionRecord {
+            timestamp,
+            from_hz,
+            to_hz,
+            fps,
+            direction: direction.to_string(),
+        };
+
+        let mut transitions = self.transitions.write().await;
+        if transitions.len() >= MAX_TRANSITION_HISTORY {
+            transitions.pop_front();
+        }
+        transitions.push_back(record);
+    }
+
+    /// Get the most recent transitions in chronological order.
+    ///
+    /// `limit` caps the number of entries returned; `None` returns the whole history.
+    pub async fn get_transitions(&self, limit: Option<usize>) -> Vec<TransitionRecord> {
+        let transitions = self.transitions.read().await;
+        let skip = limit.map_or(0, |l| transitions.len().saturating_sub(l));
+        transitions.iter().skip(skip).cloned().collect()
+    }
+
+    /// Force a refresh rate, bypassing the controller.
+    ///
+    /// With `duration` set the override expires on its own; otherwise (or
+    /// if the expiry can't be represented) it holds until
+    /// `clear_hz_override` is called.
+    pub async fn set_hz_override(&self, hz: Hertz, duration: Option<Duration>) {
+        let until = duration.and_then(|d| Instant::now().checked_add(d));
+        *self.hz_override.write().await = Some(HzOverride { hz, until });
+        // Pending transitions refer to the pre-override rate
+        self.controller.write().await.cancel_pending();
+        self.raise_event(EventKind::Override, format!("Manual override to {}Hz", hz), hz);
+    }
+
+    /// Raise an event through the configured notification routes.
+    pub fn raise_event(&self, kind: EventKind, message: String, hz: Hertz) {
+        let config = self.config_manager.get();
+        self.notifications.raise(
+            config.notifications.as_ref(),
+            config.feedback_command.as_deref(),
+            kind,
+            message,
+            hz,
+        );
+    }
+
+    /// Write the profiles to disk.
+    ///
+    /// Another process may hold the profiles file lock for a while, so the
+    /// write runs on a blocking thread from a snapshot taken once the
+    /// `profile_manager` lock is free again. Callers must not hold it.
+    pub async fn save_profiles(&self) -> Result<(), ProfileError> {
+        let _save = self.profile_save.lock().await;
+        let snapshot = self.profile_manager.read().await.clone();
+        tokio::task::spawn_blocking(move || snapshot.save())
+            .await
+            .map_err(|e| ProfileError::SaveFailed(e.to_string()))?
+    }
+
+    /// Run the exit hook of the previous game and the launch hook of the new one.
+    pub fn run_game_hooks(
+        &self,
+        profile_manager: &ProfileManager,
+        previous: Option<&str>,
+        current: Option<&str>,
+    ) {
+        let exit_hook = previous
+            .and_then(|id| profile_manager.get_profile(id))
+            .and_then(|p| p.on_exit.as_deref().map(|cmd| (p, cmd, "exit")));
+        let launch_hook = current
+            .and_then(|id| profile_manager.get_profile(id))
+            .and_then(|p| p.on_launch.as_deref().map(|cmd| (p, cmd, "launch")));
+        if exit_hook.is_none() && launch_hook.is_none() {
+            return;
+        }
+
+        let identity = match hooks::hook_identity(self.config_manager.get().hook_uid) {
+            Ok(identity) => identity,
+            Err(e) => {
+                tracing::warn!("Skipping game hooks: {}", e);
+                return;
+            }
+        };
+        for (profile, command, event) in exit_hook.into_iter().chain(launch_hook) {
+            let envs = [
+                ("SMART_REFRESH_APP_ID", profile.app_id.as_str()),
+                ("SMART_REFRESH_GAME", profile.name.as_str()),
+                ("SMART_REFRESH_EVENT", event),
+            ];
+            let timeout = Duration::from_secs(HOOK_TIMEOUT_SECS);
+            match hooks::spawn_sandboxed(command, &envs, identity, timeout) {
+                Ok(()) => tracing::info!("Ran {} hook for {}", event, profile.name),
+                Err(e) => tracing::warn!("Failed to run {} hook for {}: {}", event, profile.name, e),
+            }
+        }
+    }
+
+    /// Start or stop observing the current game for a profile suggestion.
+    pub async fn set_session_observer(&self, observer: Option<SessionObserver>) {
+        *self.session_observer.write().await = observer;
+    }
+
+    /// Feed a frame sample to the session observer.
+    ///
+    /// Once enough samples are collected, a profile seeded from the global
+    /// defaults with the suggested sensitivity is created and applied.
+    pub async fn observe_session_sample(&self, fps: f64, frametime_us: u64) {
+        let mut observer_slot = self.session_observer.write().await;
+        let Some(observer) = observer_slot.as_mut() else {
+            return;
+        };
+        observer.record(fps, frametime_us);
+        let Some(suggestion) = observer.suggest() else {
+            return;
+        };
+        let observer = observer_slot.take().expect("observer present");
+        drop(observer_slot);
+
+        let mut profile_manager = self.profile_manager.write().await;
+        if profile_manager.get_profile(observer.app_id()).is_some() {
+            return;
+        }
+
+        let defaults = profile_manager.global_default.clone();
+        let mut profile = GameProfile::new(
+            observer.app_id().to_string(),
+            observer.name().to_string(),
+            defaults.min_hz,
+            defaults.max_hz,
+            sensitivity_to_string(suggestion.sensitivity),
+        );
+        profile.adaptive_sensitivity = defaults.adaptive_sensitivity;
+        profile.suggested = true;
+
+        tracing::info!(
+            "Suggested {} sensitivity for {} (frame capped: {}, frametime CV: {:.3})",
+            profile.sensitivity, profile.name, suggestion.frame_capped, suggestion.frametime_cv
+        );
+        profile_manager.set_profile(profile);
+        let is_current = profile_manager.get_current_game().map(String::as_str) == Some(observer.app_id());
+        drop(profile_manager);
+        if let Err(e) = self.save_profiles().await {
+            tracing::warn!("Failed to save suggested profile: {}", e);
+        }
+
+        if is_current {
+            self.controller.write().await.set_sensitivity(suggestion.sensitivity);
+        }
+    }
+
+    /// Replace the current drift advisories, raising events for new ones.
+    pub async fn set_advisories(&self, advisories: Vec<Advisory>) {
+        let mut current = self.advisories.write().await;
+        for advisory in crate::trends::new_advisories(&current, &advisories) {
+            let message = format!("Advisory: {}", advisory.message);
+            self.raise_event(EventKind::Advisory, message, self.current_hz());
+        }
+        *current = advisories;
+    }
+
+    /// Get the current drift advisories.
+    pub async fn get_advisories(&self) -> Vec<Advisory> {
+        self.advisories.read().await.clone()
+    }
+
+    /// Append a point to the FPS/Hz history.
+    pub fn record_history(&self, point: HistoryPoint) {
+        if let Ok(mut history) = self.history.lock() {
+            history.push(point);
+        }
+    }
+
+    /// The most recent history points, oldest first.
+    pub fn get_history(&self, limit: Option<usize>) -> Vec<HistoryPoint> {
+        self.history.lock().map(|history| history.recent(limit)).unwrap_or_default()
+    }
+
+    /// Clear the manual override. Returns true if one was active.
+    pub async fn clear_hz_override(&self) -> bool {
+        let cleared = self.hz_override.write().await.take().is_some();
+        if cleared {
+            self.controller.write().await.cancel_pending();
+        }
+        cleared
+    }
+
+    /// Get the active override target, dropping it once expired.
+    /// What the daemon is doing with the rate right now.
+    pub async fn control_state(&self) -> ControlState {
+        if !self.is_running() {
+            return ControlState::Paused;
+        }
+        if self.current_hz_override().await.is_some() {
+            return ControlState::Manual;
+        }
+        match self.controller.read().await.state() {
+            AlgorithmState::Stable => ControlState::Stable,
+            AlgorithmState::Dropping { .. } => ControlState::Dropping,
+            AlgorithmState::Increasing { .. } => ControlState::Increasing,
+        }
+    }
+
+    pub async fn current_hz_override(&self) -> Option<Hertz> {
+        let mut hz_override = self.hz_override.write().await;
+        match *hz_override {
+            Some(o) if o.is_expired(Instant::now()) => {
+                *hz_override = None;
+                drop(hz_override);
+                self.controller.write().await.cancel_pending();
+                tracing::info!("Manual override of {}Hz expired, resuming automatic control", o.hz);
+                None
+            }
+            Some(o) => Some(o.hz),
+            None => None,
+        }
+    }
+
+    /// Refresh rate to hold while no game is in the foreground, if configured.
+    pub async fn ui_hz_target(&self) -> Option<Hertz> {
+        let ui_hz = self.config_manager.get().ui_hz?;
+        let in_game = self.profile_manager.read().await.get_current_game().is_some();
+        (!in_game).then_some(ui_hz)
+    }
+
+    /// Rate to hold while the current game is excluded from switching
+    /// (blacklisted, or not whitelisted in `whitelist_only` mode): its maximum.
+    pub async fn game_hold_hz(&self) -> Option<Hertz> {
+        let whitelist_only = self.config_manager.get().whitelist_only;
+        if self.profile_manager.read().await.current_switching_allowed(whitelist_only) {
+            return None;
+        }
+        Some(self.controller.read().await.user_range().1)
+    }
+
+    /// Rate to apply after `without_fps` with no valid FPS data, per the idle policy.
+    pub fn idle_hz_target(&self, without_fps: Duration) -> Option<Hertz> {
+        let config = self.config_manager.get();
+        if without_fps < Duration::from(config.idle_after_secs) {
+            return None;
+        }
+        config.idle_policy.target(config.min_hz, config.max_hz)
+    }
+
+    /// Whether MangoHud shared memory is connected.
+    pub fn is_mangohud_available(&self) -> bool {
+        self.mangohud_available.load(Ordering::SeqCst)
+    }
+
+    /// Current smoothed FPS and refresh rate for StreamFps.
+    pub async fn fps_frame(&self) -> FpsStreamFrame {
+        FpsStreamFrame {
+            fps: *self.current_fps.read().await,
+            hz: self.current_hz(),
+        }
+    }
+
+    /// Get the current status as a StatusResponse.
+    pub async fn get_status(&self) -> StatusResponse {
+        let config = self.config_manager.get();
+        let controller = self.controller.read().await;
+
+        if !self.live.load(Ordering::Relaxed) && self.started.elapsed() < COLD_START_WINDOW {
+            if let Some(mut cached) = self.cold_start_status.read().await.clone() {
+                // Settings are known now even if measurements aren't
+                cached.running = self.running.load(Ordering::SeqCst);
+                cached.config =
+                    ConfigResponse::from_config(&config, controller.is_adaptive_sensitivity_enabled());
+                cached.cached = true;
+                return cached;
+            }
+        }
+
+        let current_fps = *self.current_fps.read().await;
+        let profile_manager = self.profile_manager.read().await;
+        let transitions = self.get_transitions(Some(STATUS_TRANSITION_COUNT)).await;
+        let now = Instant::now();
+        let hz_override = (*self.hz_override.read().await).filter(|o| !o.is_expired(now));
+
+        StatusResponse {
+            running: self.running.load(Ordering::SeqCst),
+            current_fps,
+            current_hz: self.current_hz(),
+            state: algorithm_state_to_string(controller.state()),
+            device_mode: device_mode_to_string(controller.device_mode()),
+            config: ConfigResponse::from_config(&config, controller.is_adaptive_sensitivity_enabled()),
+            mangohud_available: self.mangohud_available.load(Ordering::SeqCst),
+            external_display_detected: controller.is_external_display_detected(),
+            fps_std_dev: controller.get_fps_std_dev(),
+            current_app_id: profile_manager.get_current_game().cloned(),
+            transitions,
+            effective_sensitivity: sensitivity_to_string(controller.effective_sensitivity()),
+            // v2.0.1 advanced fields
+            fps_tolerance: controller.fps_tolerance(),
+            resume_cooldown_remaining: controller.resume_cooldown_remaining(),
+            sync_frame_limiter: controller.is_sync_frame_limiter_enabled(),
+            override_hz: hz_override.map(|o| o.hz),
+            override_remaining: hz_override.and_then(|o| o.remaining_secs(now)),
+            deep_sleep: self.sleep.is_sleeping(),
+            subsystems: SubsystemStatus::new(
+                &self.subsystems,
+                controller.is_adaptive_sensitivity_enabled(),
+            ),
+            frametime_spikes: controller.spike_count(),
+            oscillation_damping_remaining: controller.oscillation_damping_remaining(),
+            loading_hold_remaining: controller.loading_hold_remaining(),
+            lfc_fps_limit: controller.lfc_fps_limit(),
+            pacing: controller.pacing(self.current_hz()),
+            experimental: self.experiments.snapshot(),
+            thermal: self.thermal.lock().map(|t| *t).unwrap_or_default(),
+            utilization: self.utilization.lock().map(|u| *u).unwrap_or_default(),
+            display_backend: self.display.backend_kind(),
+            fps_source: self.fps_source(),
+            fps_pid: self.fps_pid(),
+            vrr: self.display.vrr_status(),
+            display_backend_unhealthy: self.display.is_backend_unhealthy(),
+            device_model: self.device_model(),
+            docked: self.is_docked(),
+            active_output: self.display.active_output(),
+            displays: monitor_detect::display_statuses(
+                &self.connectors.read().await,
+                &self.current_modes.read().await,
+                self.display.active_output().as_deref(),
+                controller.is_external_display_detected(),
+                self.current_hz(),
+            ),
+            session: self.session(),
+            session_paused: self.is_session_paused(),
+            overlay_open: self.is_overlay_open(),
+            fps_stale: self.is_fps_stale(),
+            fps_smoothing: config.fps_smoothing,
+            poll_interval_ms: Millis(self.poll_interval().as_millis() as u64),
+            frametime_p99_us: self.frametime_p99(),
+            fps_stability: controller.fps_stability().map(|s| s.as_str().to_string()),
+            recording: self.recording(),
+            system_update: self.system_update(),
+            game_switching_disabled: !profile_manager.current_switching_allowed(config.whitelist_only),
+            cached: false,
+            switch_budget: config.switch_budget.and_then(|limits| {
+                let mut budget = self.switch_budget.lock().ok()?;
+                Some(budget.status(&limits, now))
+            }),
+        }
+    }
+
+    /// Record an APU temperature reading (`None` when unavailable or the
+    /// thermal subsystem is off) and hold increases while throttling.
+    pub async fn update_thermal(&self, temp_c: Option<f64>) {
+        let limit = self.config_manager.get().thermal_limit_c;
+        let changed = match self.thermal.lock() {
+            Ok(mut thermal) => {
+                thermal.available = true;
+                thermal.update(temp_c, limit).then_some(thermal.throttling)
+            }
+            Err(_) => None,
+        };
+        if let Some(throttling) = changed {
+            let message = if throttling {
+                format!("APU at {:.0}°C, holding refresh rate increases", temp_c.unwrap_or_default())
+            } else {
+                "APU cooled down, refresh rate increases allowed".to_string()
+            };
+            self.raise_event(EventKind::Thermal, message, self.current_hz());
+            self.controller.write().await.set_thermal_throttling(throttling);
+        }
+    }
+
+    /// Record a utilization sample (`None` while sampling is disabled) and
+    /// hold increases while the GPU is saturated.
+    pub async fn update_utilization(&self, sample: Option<UtilizationStatus>) {
+        let sample = sample.unwrap_or_default();
+        let changed = match self.utilization.lock() {
+            Ok(mut utilization) => {
+                let changed = utilization.gpu_saturated != sample.gpu_saturated;
+                *utilization = sample;
+                changed
+            }
+            Err(_) => false,
+        };
+        if changed {
+            if sample.gpu_saturated {
+                tracing::info!(
+                    "GPU busy at {}%, holding refresh rate increases",
+                    sample.gpu_busy_percent.unwrap_or_default()
+                );
+            } else {
+                tracing::info!("GPU no longer saturated, refresh rate increases allowed");
+            }
+            self.controller.write().await.set_gpu_saturated(sample.gpu_saturated);
+        }
+    }
+
+    /// Select the display backend: `preferred` if available, otherwise the
+    /// first available one in detection order.
+    pub async fn select_display_backend(&self, preferred: Option<BackendKind>) -> BackendKind {
+        let backend = display_backend::select(preferred).await;
+        let kind = backend.kind();
+        self.display.set_backend(backend);
+        self.apply_hz_range().await;
+        kind
+    }
+
+    /// Count an applied refresh rate change against the switch budget.
+    pub fn note_switch(&self) {
+        if let Ok(mut budget) = self.switch_budget.lock() {
+            budget.record(Instant::now());
+        }
+    }
+
+    /// Extra FPS tolerance to apply given the current switch budget usage.
+    pub fn switch_budget_boost(&self) -> f64 {
+        let Some(limits) = self.config_manager.get().switch_budget else {
+            return 0.0;
+        };
+        self.switch_budget
+            .lock()
+            .map(|mut budget| switch_budget::tolerance_boost(budget.usage(&limits, Instant::now())))
+            .unwrap_or(0.0)
+    }
+
+    /// Whether another automatic switch fits in the switch budget.
+    pub fn switch_budget_allows(&self) -> bool {
+        let Some(limits) = self.config_manager.get().switch_budget else {
+            return true;
+        };
+        let allows = self
+            .switch_budget
+            .lock()
+            .map(|mut budget| budget.allows(&limits, Instant::now()))
+            .unwrap_or(true);
+        let was_exhausted = self.switch_budget_exhausted.swap(!allows, Ordering::Relaxed);
+        if !allows && !was_exhausted {
+            tracing::warn!("Switch budget spent, holding the current refresh rate");
+        } else if allows && was_exhausted {
+            tracing::info!("Switch budget available again");
+        }
+        allows
+    }
+
+    /// Start the refresh rate control loop.
+    pub fn start(&self) {
+        self.running.store(true, Ordering::SeqCst);
+    }
+
+    /// Stop the refresh rate control loop.
+    pub fn stop(&self) {
+        self.running.store(false, Ordering::SeqCst);
+    }
+
+    /// Check if the daemon is running.
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::SeqCst)
+    }
+
+    /// Refresh rate last applied to the display.
+    pub fn current_hz(&self) -> Hertz {
+        Hertz(self.current_hz.load(Ordering::SeqCst))
+    }
+
+    pub fn set_current_hz(&self, hz: Hertz) {
+        self.current_hz.store(hz.get(), Ordering::SeqCst);
+    }
+}
+
+/// Simple timestamp without chrono dependency
+pub(crate) fn chrono_lite_timestamp() -> String {
+    use std::time::{SystemTime, UNIX_EPOCH};
+    let duration = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let secs = duration.as_secs();
+    let hours = (secs / 3600) % 24;
+    let mins = (secs / 60) % 60;
+    let secs = secs % 60;
+    format!("{:02}:{:02}:{:02}", hours, mins, secs)
+}
+
+/// Check whether a peer UID may use the IPC socket.
+///
+/// The daemon's own user and root are always allowed; other users must be
+/// listed in `allowed_uids`.
+pub fn is_uid_authorized(uid: u32, daemon_uid: u32, allowed_uids: &[u32]) -> bool {
+    uid == daemon_uid || uid == 0 || allowed_uids.contains(&uid)
+}
+
+/// Unix Domain Socket server for IPC.
+#[cfg(unix)]
+pub struct IpcServer {
+    socket_path: PathBuf,
+    listener: UnixListener,
+}
+
+#[cfg(unix)]
+impl IpcServer {
+    /// Bind the socket at `path`.
+    ///
+    /// The socket is owner-only unless `allow_other_users` is set, in which
+    /// case it is world-connectable and access relies on the per-connection
+    /// peer credential check.
+    pub async fn new(path: &Path, allow_other_users: bool) -> Result<Self, IpcError> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let socket_path = path.to_path_buf();
+        Self::cleanup_socket(&socket_path)?;
+
+        let listener = UnixListener::bind(&socket_path).map_err(|e| IpcError::SocketBindFailed {
+            path: path.display().to_string(),
+            source: e,
+        })?;
+
+        // Owner-only access, even when falling back to a shared directory like /tmp
+        let mode = if allow_other_users { 0o666 } else { 0o600 };
+        std::fs::set_permissions(&socket_path, std::fs::Permissions::from_mode(mode))
+            .map_err(|e| IpcError::SocketBindFailed {
+                path: path.display().to_string(),
+                source: e,
+            })?;
+
+        Ok(Self {
+            socket_path,
+            listener,
+        })
+    }
+
+    fn cleanup_socket(path: &Path) -> Result<(), IpcError> {
+        if path.exists() {
+            std::fs::remove_file(path).map_err(|e| IpcError::SocketBindFailed {
+                path: path.display().to_string(),
+                source: e,
+            })?;
+        }
+        Ok(())
+    }
+
+    pub fn socket_path(&self) -> &Path {
+        &self.socket_path
+    }
+
+    pub async fn run(&self, state: Arc<DaemonState>) -> Result<(), IpcError> {
+        loop {
+            match self.listener.accept().await {
+                Ok((stream, _addr)) => {
+                    let state = Arc::clone(&state);
+                    tokio::spawn(async move {
+                        if let Err(e) = Self::handle_connection(stream, state).await {
+                            tracing::warn!("Error handling IPC connection: {}", e);
+                        }
+                    });
+                }
+                Err(e) => {
+                    tracing::error!("Error accepting IPC connection: {}", e);
+                }
+            }
+        }
+    }
+
+    async fn handle_connection(
+        mut stream: UnixStream,
+        state: Arc<DaemonState>,
+    ) -> Result<(), IpcError> {
+        let uid = stream.peer_cred()?.uid();
+        let allowed_uids = state.config_manager.get().allowed_uids;
+        // SAFETY: geteuid has no preconditions and cannot fail
+        let daemon_uid = unsafe { libc::geteuid() };
+        if !is_uid_authorized(uid, daemon_uid, &allowed_uids) {
+            tracing::warn!("Rejected IPC connection from unauthorized uid {}", uid);
+            let response = serde_json::json!({
+                "error": format!("Permission denied: uid {} is not authorized", uid)
+            });
+            stream.write_all(serde_json::to_string(&response)?.as_bytes()).await?;
+            stream.write_all(b"\n").await?;
+            return Err(IpcError::Unauthorized(uid));
+        }
+
+        let (reader, mut writer) = stream.into_split();
+        let mut reader = BufReader::new(reader);
+        let mut line = String::new();
+        let mut encoding = Encoding::Json;
 
         while reader.read_line(&mut line).await? > 0 {
             let trimmed = line.trim();
@@ -398,298 +1960,2269 @@ impl IpcServer {
                 line.clear();
                 continue;
             }
+            let reply = Self::handle_request(trimmed, &state).await;
+            line.clear();
+
+            match reply {
+                Reply::Json(response) => write_response(&mut writer, &response, encoding).await?,
+                Reply::Status(status) => write_response(&mut writer, &status, encoding).await?,
+                Reply::Metrics(metrics) => write_response(&mut writer, &metrics, encoding).await?,
+                Reply::Handshake { encoding: requested, ack } => {
+                    write_response(&mut writer, &ack, Encoding::Json).await?;
+                    encoding = requested;
+                }
+                Reply::StreamFps { interval, ack } => {
+                    write_response(&mut writer, &ack, encoding).await?;
+                    if !Self::stream_fps(&mut reader, &mut writer, &state, interval, encoding).await? {
+                        break;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Push FPS frames until the client sends more input or disconnects.
+    ///
+    /// Returns `true` if input is waiting (the next command ends the stream)
+    /// and `false` on EOF. Only `fill_buf` is raced against the timer since it
+    /// is cancel safe; the pending line is read by the caller.
+    async fn stream_fps<R, W>(
+        reader: &mut R,
+        writer: &mut W,
+        state: &DaemonState,
+        interval: Duration,
+        encoding: Encoding,
+    ) -> Result<bool, IpcError>
+    where
+        R: tokio::io::AsyncBufRead + Unpin,
+        W: tokio::io::AsyncWrite + Unpin,
+    {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+        loop {
+            tokio::select! {
+                available = reader.fill_buf() => return Ok(!available?.is_empty()),
+                _ = ticker.tick() => {
+                    write_response(writer, &state.fps_frame().await, encoding).await?;
+                }
+            }
+        }
+    }
+
+    /// Parse a single request line from the Unix socket and dispatch it.
+    ///
+    /// Parse failures are reported to the client as an `error` object rather
+    /// than closing the connection. Handshakes and FPS streams change the
+    /// connection itself, so they are returned for the socket loop to apply.
+    pub async fn handle_request(line: &str, state: &Arc<DaemonState>) -> Reply {
+        let command = match serde_json::from_str::<IpcCommand>(line) {
+            Ok(command) => command,
+            Err(e) => {
+                return Reply::Json(serde_json::json!({
+                    "error": format!("Invalid command: {}", e)
+                }))
+            }
+        };
+
+        match command {
+            IpcCommand::Handshake { encoding } => Reply::Handshake {
+                encoding,
+                ack: serde_json::json!({
+                    "success": true,
+                    "message": format!("Using {:?} encoding", encoding),
+                    "encoding": encoding,
+                }),
+            },
+            IpcCommand::StreamFps { interval_ms } => {
+                let interval_ms = interval_ms.unwrap_or(DEFAULT_STREAM_INTERVAL_MS);
+                if (MIN_STREAM_INTERVAL_MS..=MAX_STREAM_INTERVAL_MS).contains(&interval_ms) {
+                    Reply::StreamFps {
+                        interval: Duration::from(interval_ms),
+                        ack: serde_json::json!({
+                            "success": true,
+                            "message": format!("Streaming FPS every {}ms", interval_ms)
+                        }),
+                    }
+                } else {
+                    Reply::Json(serde_json::json!({
+                        "error": format!(
+                            "interval_ms must be between {} and {}",
+                            MIN_STREAM_INTERVAL_MS, MAX_STREAM_INTERVAL_MS
+                        )
+                    }))
+                }
+            }
+            IpcCommand::GetStatus => Reply::Status(Box::new(state.get_status().await)),
+            IpcCommand::GetMetrics => Reply::Metrics(state.metrics.get_metrics()),
+            command => Reply::Json(Self::handle_command(command, state).await),
+        }
+    }
+
+    /// Handle a request from a TCP bridge (WebSocket, HTTP). Peer
+    /// credentials aren't available there, so commands storing shell
+    /// commands are refused.
+    pub async fn handle_remote_request(line: &str, state: &Arc<DaemonState>) -> serde_json::Value {
+        match serde_json::from_str::<IpcCommand>(line) {
+            Ok(command) if command.sets_shell_command() => serde_json::json!({
+                "error": "Hook commands can only be set over the Unix socket"
+            }),
+            Ok(command) => Self::handle_command(command, state).await,
+            Err(e) => serde_json::json!({
+                "error": format!("Invalid command: {}", e)
+            }),
+        }
+    }
+
+    pub async fn handle_command(
+        command: IpcCommand,
+        state: &Arc<DaemonState>,
+    ) -> serde_json::Value {
+        match command {
+            IpcCommand::Start => {
+                state.start();
+                tracing::info!("Daemon started via IPC");
+                serde_json::json!({ "success": true, "message": "Daemon started" })
+            }
+
+            IpcCommand::Stop => {
+                state.stop();
+                tracing::info!("Daemon stopped via IPC");
+                serde_json::json!({ "success": true, "message": "Daemon stopped" })
+            }
+
+            IpcCommand::SetConfig {
+                min_hz,
+                max_hz,
+                sensitivity,
+                adaptive_sensitivity,
+                fps_tolerance,
+                sync_frame_limiter,
+                custom_drop_ms,
+                custom_increase_ms,
+                ramp_down,
+                drop_step,
+                increase_step,
+                video_cadence,
+                low_fps_compensation,
+                control_policy,
+            } => {
+                let sensitivity_enum = match parse_sensitivity(&sensitivity) {
+                    Ok(s) => s,
+                    Err(e) => {
+                        return serde_json::json!({
+                            "success": false,
+                            "error": e.to_string()
+                        });
+                    }
+                };
+
+                let mut config = state.config_manager.get();
+                config.min_hz = min_hz;
+                config.max_hz = max_hz;
+                config.sensitivity = sensitivity_enum;
+                if custom_drop_ms.is_some() {
+                    config.custom_drop_ms = custom_drop_ms;
+                }
+                if custom_increase_ms.is_some() {
+                    config.custom_increase_ms = custom_increase_ms;
+                }
+                // `ramp_down` is stored as `drop_step`; an explicit `drop_step` wins
+                if let Some(ramp_down) = ramp_down {
+                    config.ramp_down = false;
+                    config.drop_step = if ramp_down { StepMode::Single } else { StepMode::Jump };
+                }
+                if let Some(step) = drop_step {
+                    config.ramp_down = false;
+                    config.drop_step = step;
+                }
+                if let Some(step) = increase_step {
+                    config.increase_step = step;
+                }
+                if let Some(video_cadence) = video_cadence {
+                    config.video_cadence = video_cadence;
+                }
+                if let Some(lfc) = low_fps_compensation {
+                    config.low_fps_compensation = lfc;
+                }
+                if let Some(policy) = control_policy {
+                    config.control_policy = policy;
+                }
+                if let Some(adaptive) = adaptive_sensitivity {
+                    config.adaptive_sensitivity = adaptive;
+                }
+                if let Some(tolerance) = fps_tolerance {
+                    config.fps_tolerance = tolerance;
+                }
+                let custom_thresholds = config.custom_thresholds();
+                let drop_step = config.effective_drop_step();
+                let increase_step = config.increase_step;
+                let video_cadence = config.video_cadence;
+                let low_fps_compensation = config.low_fps_compensation;
+                let control_policy = config.control_policy;
+                let adaptive_sensitivity = config.adaptive_sensitivity;
+                let fps_tolerance = config.fps_tolerance;
+
+                match state.config_manager.update(config) {
+                    Ok(()) => {
+                        let mut controller = state.controller.write().await;
+                        controller.set_user_range(min_hz, max_hz);
+                        if let Some((drop, increase)) = custom_thresholds {
+                            controller.set_custom_thresholds(drop, increase);
+                        }
+                        controller.set_sensitivity(sensitivity_enum);
+                        controller.set_drop_step(drop_step);
+                        controller.set_increase_step(increase_step);
+                        controller.set_video_cadence(video_cadence);
+                        controller.set_low_fps_compensation(low_fps_compensation);
+                        controller.set_control_policy(control_policy);
+                        controller.set_adaptive_sensitivity(adaptive_sensitivity);
+                        controller.set_fps_tolerance(fps_tolerance);
+                        if let Some(sync_fl) = sync_frame_limiter {
+                            controller.set_sync_frame_limiter(sync_fl);
+                        }
+                        tracing::info!(
+                            "Config updated via IPC: min_hz={}, max_hz={}, sensitivity={}",
+                            min_hz, max_hz, sensitivity
+                        );
+                        serde_json::json!({ "success": true, "message": "Configuration updated" })
+                    }
+                    Err(e) => {
+                        tracing::warn!("Failed to update config via IPC: {}", e);
+                        serde_json::json!({
+                            "success": false,
+                            "error": e.to_string()
+                        })
+                    }
+                }
+            }
+
+            IpcCommand::SetAdvancedConfig {
+                fps_tolerance,
+                resume_cooldown_secs,
+                sync_frame_limiter,
+                saturation_pacing,
+                loading_grace_ms,
+                idle_policy,
+                idle_after_secs,
+                min_dwell_secs,
+                predictive_drop_fps_per_sec,
+                gpu_utilization,
+                manual_change_grace_secs,
+                startup_rate,
+                control_external_display,
+                desktop_mode_switching,
+                whitelist_only,
+                fps_smoothing,
+                fps_ema_alpha,
+                fps_window_secs,
+                adaptive_polling,
+                mangohud_overlay,
+                auto_detect_game,
+            } => {
+                if fps_tolerance.is_some()
+                    || resume_cooldown_secs.is_some()
+                    || saturation_pacing.is_some()
+                    || loading_grace_ms.is_some()
+                    || idle_policy.is_some()
+                    || idle_after_secs.is_some()
+                    || min_dwell_secs.is_some()
+                    || predictive_drop_fps_per_sec.is_some()
+                    || gpu_utilization.is_some()
+                    || manual_change_grace_secs.is_some()
+                    || startup_rate.is_some()
+                    || control_external_display.is_some()
+                    || desktop_mode_switching.is_some()
+                    || whitelist_only.is_some()
+                    || fps_smoothing.is_some()
+                    || fps_ema_alpha.is_some()
+                    || fps_window_secs.is_some()
+                    || adaptive_polling.is_some()
+                    || mangohud_overlay.is_some()
+                    || auto_detect_game.is_some()
+                {
+                    let mut config = state.config_manager.get();
+                    if let Some(policy) = idle_policy {
+                        config.idle_policy = policy;
+                    }
+                    if let Some(secs) = idle_after_secs {
+                        config.idle_after_secs = secs;
+                    }
+                    if let Some(tolerance) = fps_tolerance {
+                        config.fps_tolerance = tolerance;
+                    }
+                    if let Some(cooldown) = resume_cooldown_secs {
+                        config.resume_cooldown_secs = cooldown;
+                    }
+                    if let Some(multiplier) = saturation_pacing {
+                        config.saturation_pacing = multiplier;
+                    }
+                    if let Some(grace) = loading_grace_ms {
+                        config.loading_grace_ms = grace;
+                    }
+                    if let Some(dwell) = min_dwell_secs {
+                        config.min_dwell_secs = dwell;
+                    }
+                    if let Some(slope) = predictive_drop_fps_per_sec {
+                        config.predictive_drop_fps_per_sec = slope;
+                    }
+                    if let Some(enabled) = gpu_utilization {
+                        config.gpu_utilization = enabled;
+                    }
+                    if let Some(grace) = manual_change_grace_secs {
+                        config.manual_change_grace_secs = grace;
+                    }
+                    if let Some(behavior) = startup_rate {
+                        config.startup_rate = behavior;
+                    }
+                    if let Some(control) = control_external_display {
+                        config.control_external_display = control;
+                    }
+                    if let Some(enabled) = desktop_mode_switching {
+                        config.desktop_mode_switching = enabled;
+                    }
+                    if let Some(enabled) = whitelist_only {
+                        config.whitelist_only = enabled;
+                    }
+                    if let Some(smoothing) = fps_smoothing {
+                        config.fps_smoothing = smoothing;
+                    }
+                    if let Some(alpha) = fps_ema_alpha {
+                        config.fps_ema_alpha = alpha;
+                    }
+                    if let Some(window) = fps_window_secs {
+                        config.fps_window_secs = window;
+                    }
+                    if let Some(enabled) = adaptive_polling {
+                        config.adaptive_polling = enabled;
+                    }
+                    if let Some(enabled) = mangohud_overlay {
+                        config.mangohud_overlay = enabled;
+                    }
+                    if let Some(enabled) = auto_detect_game {
+                        config.auto_detect_game = enabled;
+                    }
+                    if let Err(e) = state.config_manager.update(config) {
+                        return serde_json::json!({
+                            "success": false,
+                            "error": e.to_string()
+                        });
+                    }
+                }
+
+                let profile_dwell = state.profile_manager.read().await.current_min_dwell();
+                let mut controller = state.controller.write().await;
+                
+                if let Some(tolerance) = fps_tolerance {
+                    controller.set_fps_tolerance(tolerance);
+                }
+                if let Some(cooldown) = resume_cooldown_secs {
+                    controller.set_resume_cooldown(cooldown);
+                }
+                if let Some(sync_fl) = sync_frame_limiter {
+                    controller.set_sync_frame_limiter(sync_fl);
+                }
+                if let Some(multiplier) = saturation_pacing {
+                    controller.set_saturation_pacing(multiplier);
+                }
+                if let Some(grace) = loading_grace_ms {
+                    controller.set_loading_grace(grace);
+                }
+                if predictive_drop_fps_per_sec.is_some() {
+                    controller.set_predictive_drop(state.config_manager.get().predictive_drop());
+                }
+                if min_dwell_secs.is_some() {
+                    // A profile override for the running game keeps precedence
+                    controller.set_min_dwell(
+                        profile_dwell.unwrap_or_else(|| state.config_manager.get().min_dwell_secs),
+                    );
+                }
+                
+                tracing::info!(
+                    "Advanced config updated: fps_tolerance={:?}, resume_cooldown={:?}, sync_frame_limiter={:?}, saturation_pacing={:?}, loading_grace_ms={:?}, min_dwell_secs={:?}",
+                    fps_tolerance, resume_cooldown_secs, sync_frame_limiter, saturation_pacing, loading_grace_ms, min_dwell_secs
+                );
+                
+                serde_json::json!({
+                    "success": true,
+                    "message": "Advanced configuration updated",
+                    "fps_tolerance": controller.fps_tolerance(),
+                    "resume_cooldown_secs": state.config_manager.get().resume_cooldown_secs,
+                    "sync_frame_limiter": controller.is_sync_frame_limiter_enabled(),
+                    "saturation_pacing": controller.saturation_pacing(),
+                    "loading_grace_ms": controller.loading_grace(),
+                    "min_dwell_secs": controller.min_dwell(),
+                    "idle_policy": state.config_manager.get().idle_policy,
+                    "gpu_utilization": state.config_manager.get().gpu_utilization,
+                    "manual_change_grace_secs": state.config_manager.get().manual_change_grace_secs,
+                    "startup_rate": state.config_manager.get().startup_rate,
+                    "control_external_display": state.config_manager.get().control_external_display,
+                    "desktop_mode_switching": state.config_manager.get().desktop_mode_switching,
+                    "whitelist_only": state.config_manager.get().whitelist_only,
+                    "fps_smoothing": state.config_manager.get().fps_smoothing,
+                    "fps_ema_alpha": state.config_manager.get().fps_ema_alpha,
+                    "fps_window_secs": state.config_manager.get().fps_window_secs,
+                    "adaptive_polling": state.config_manager.get().adaptive_polling,
+                    "mangohud_overlay": state.config_manager.get().mangohud_overlay,
+                    "auto_detect_game": state.config_manager.get().auto_detect_game
+                })
+            }
+
+            IpcCommand::SetDeviceMode { mode } => {
+                let mode_enum = match parse_device_mode(&mode) {
+                    Ok(m) => m,
+                    Err(e) => {
+                        return serde_json::json!({
+                            "success": false,
+                            "error": e.to_string()
+                        });
+                    }
+                };
+
+                let mut controller = state.controller.write().await;
+                controller.apply_mode_constraints(mode_enum);
+                
+                let effective_sens = sensitivity_to_string(controller.effective_sensitivity());
+                let min_interval = if mode_enum == DeviceMode::Lcd { 2000 } else { 500 };
+                
+                tracing::info!(
+                    "Device mode set via IPC: mode={}, min_change_interval={}ms",
+                    mode, min_interval
+                );
+                
+                serde_json::json!({
+                    "success": true,
+                    "message": format!("Device mode set to {}", mode),
+                    "mode": mode,
+                    "effective_sensitivity": effective_sens,
+                    "min_change_interval_ms": min_interval
+                })
+            }
+
+            IpcCommand::GetStatus => {
+                let status = state.get_status().await;
+                serde_json::to_value(status).unwrap_or_else(|e| {
+                    serde_json::json!({
+                        "error": format!("Failed to serialize status: {}", e)
+                    })
+                })
+            }
+
+            IpcCommand::GetMetrics => {
+                let metrics = state.metrics.get_metrics();
+                serde_json::to_value(metrics).unwrap_or_else(|e| {
+                    serde_json::json!({
+                        "error": format!("Failed to serialize metrics: {}", e)
+                    })
+                })
+            }
+
+            IpcCommand::SetGameId { app_id, name } => {
+                let app_id = Some(app_id).filter(|id| !id.is_empty() && id != "0");
+                if let Some(name) = state.switch_game(app_id, name).await {
+                    return serde_json::json!({
+                        "success": true,
+                        "message": format!("Loaded profile for {}", name),
+                        "profile_applied": true,
+                        "profile_name": name
+                    });
+                }
+
+                let message = if state.is_docked() {
+                    "Game ID updated, using the docked profile"
+                } else {
+                    "Game ID updated, using global defaults"
+                };
+                serde_json::json!({
+                    "success": true,
+                    "message": message,
+                    "profile_applied": false
+                })
+            }
+
+            IpcCommand::SaveProfile {
+                app_id,
+                name,
+                min_hz,
+                max_hz,
+                sensitivity,
+                adaptive_sensitivity,
+                min_dwell_secs,
+                on_launch,
+                on_exit,
+                switching,
+            } => {
+                let non_empty = |cmd: Option<String>| cmd.filter(|c| !c.trim().is_empty());
+                let mut profile_manager = state.profile_manager.write().await;
+                let existing = profile_manager.get_profile(&app_id);
+                let switching = switching.unwrap_or_else(|| existing.map(|p| p.switching).unwrap_or_default());
+                let min_dwell_secs = min_dwell_secs.unwrap_or_else(|| existing.and_then(|p| p.min_dwell_secs));
+                let on_launch = match on_launch {
+                    Some(cmd) => non_empty(cmd),
+                    None => existing.and_then(|p| p.on_launch.clone()),
+                };
+                let on_exit = match on_exit {
+                    Some(cmd) => non_empty(cmd),
+                    None => existing.and_then(|p| p.on_exit.clone()),
+                };
+                let profile = GameProfile {
+                    app_id: app_id.clone(),
+                    name: name.clone(),
+                    min_hz,
+                    max_hz,
+                    sensitivity,
+                    adaptive_sensitivity,
+                    suggested: false,
+                    min_dwell_secs,
+                    on_launch,
+                    on_exit,
+                    switching,
+                };
+
+                profile_manager.set_profile(profile);
+                drop(profile_manager);
+
+                if let Err(e) = state.save_profiles().await {
+                    tracing::warn!("Failed to save profiles: {}", e);
+                    return serde_json::json!({
+                        "success": false,
+                        "error": format!("Failed to save profile: {}", e)
+                    });
+                }
+
+                tracing::info!("Saved profile for {} ({})", name, app_id);
+                serde_json::json!({
+                    "success": true,
+                    "message": format!("Profile saved for {}", name)
+                })
+            }
+
+            IpcCommand::DeleteProfile { app_id } => {
+                let removed = state.profile_manager.write().await.remove_profile(&app_id).is_some();
+
+                if removed {
+                    if let Err(e) = state.save_profiles().await {
+                        tracing::warn!("Failed to save profiles after delete: {}", e);
+                    }
+                    serde_json::json!({
+                        "success": true,
+                        "message": "Profile deleted"
+                    })
+                } else {
+                    serde_json::json!({
+                        "success": false,
+                        "error": "Profile not found"
+                    })
+                }
+            }
+
+            IpcCommand::AcceptProfileSuggestion { app_id } => {
+                let accepted = state.profile_manager.write().await.accept_suggestion(&app_id);
+
+                if accepted {
+                    if let Err(e) = state.save_profiles().await {
+                        tracing::warn!("Failed to save profiles after accepting suggestion: {}", e);
+                    }
+                    serde_json::json!({
+                        "success": true,
+                        "message": "Suggested profile accepted"
+                    })
+                } else {
+                    serde_json::json!({
+                        "success": false,
+                        "error": "Profile not found"
+                    })
+                }
+            }
+
+            IpcCommand::SetGameSwitching { app_id, switching, name } => {
+                state.profile_manager.write().await.set_switching(&app_id, name.as_deref(), switching);
+                if let Err(e) = state.save_profiles().await {
+                    tracing::warn!("Failed to save profiles: {}", e);
+                    return serde_json::json!({
+                        "success": false,
+                        "error": format!("Failed to save profile: {}", e)
+                    });
+                }
+                serde_json::json!({
+                    "success": true,
+                    "app_id": app_id,
+                    "switching": switching
+                })
+            }
+
+            IpcCommand::GetProfiles => {
+                let profile_manager = state.profile_manager.read().await;
+                let response = ProfileListResponse::from(&*profile_manager);
+                serde_json::to_value(response).unwrap_or_else(|e| {
+                    serde_json::json!({
+                        "error": format!("Failed to serialize profiles: {}", e)
+                    })
+                })
+            }
+
+            IpcCommand::GetBatteryStatus => {
+                let status = state.battery_monitor.get_status();
+                serde_json::to_value(status).unwrap_or_else(|e| {
+                    serde_json::json!({
+                        "error": format!("Failed to serialize battery status: {}", e)
+                    })
+                })
+            }
+
+            IpcCommand::GetPowerByHz => {
+                let table = state.battery_monitor.power_by_hz();
+                serde_json::to_value(table).unwrap_or_else(|e| {
+                    serde_json::json!({
+                        "error": format!("Failed to serialize power table: {}", e)
+                    })
+                })
+            }
+
+            IpcCommand::SetHz { hz, duration_secs } => {
+                let config = state.config_manager.get();
+                if hz < config.min_hz || hz > config.max_hz {
+                    return serde_json::json!({
+                        "success": false,
+                        "error": format!(
+                            "Refresh rate {}Hz is outside the configured range [{}-{}Hz]",
+                            hz, config.min_hz, config.max_hz
+                        )
+                    });
+                }
+                if duration_secs.is_some_and(|secs| secs > MAX_OVERRIDE_SECS) {
+                    return serde_json::json!({
+                        "success": false,
+                        "error": format!(
+                            "duration_secs must be at most {}, omit it to hold until cleared",
+                            MAX_OVERRIDE_SECS
+                        )
+                    });
+                }
+
+                state
+                    .set_hz_override(hz, duration_secs.map(Duration::from))
+                    .await;
+
+                let message = match duration_secs {
+                    Some(secs) => format!("Refresh rate locked to {}Hz for {}s", hz, secs),
+                    None => format!("Refresh rate locked to {}Hz until cleared", hz),
+                };
+                tracing::info!("Manual override via IPC: {}", message);
+
+                serde_json::json!({
+                    "success": true,
+                    "message": message,
+                    "hz": hz,
+                    "duration_secs": duration_secs
+                })
+            }
+
+            IpcCommand::GetTransitions { limit } => {
+                let response = TransitionsResponse {
+                    transitions: state.get_transitions(limit).await,
+                    capacity: MAX_TRANSITION_HISTORY,
+                };
+                serde_json::to_value(response).unwrap_or_else(|e| {
+                    serde_json::json!({
+                        "error": format!("Failed to serialize transitions: {}", e)
+                    })
+                })
+            }
+
+            IpcCommand::GetAdvisories => {
+                let advisories = state.get_advisories().await;
+                serde_json::json!({ "advisories": advisories })
+            }
+
+            IpcCommand::GetHistory { limit } => {
+                let response = HistoryResponse {
+                    points: state.get_history(limit),
+                    interval_secs: HISTORY_INTERVAL_SECS,
+                    capacity: HISTORY_CAPACITY,
+                };
+                serde_json::to_value(response).unwrap_or_else(|e| {
+                    serde_json::json!({
+                        "error": format!("Failed to serialize history: {}", e)
+                    })
+                })
+            }
+
+            IpcCommand::Batch { commands } => {
+                if commands.len() > MAX_BATCH_COMMANDS {
+                    return serde_json::json!({
+                        "error": format!(
+                            "Batch of {} commands exceeds the limit of {}",
+                            commands.len(), MAX_BATCH_COMMANDS
+                        )
+                    });
+                }
+
+                let mut responses = Vec::with_capacity(commands.len());
+                for command in commands {
+                    let response = match serde_json::from_value::<IpcCommand>(command) {
+                        Ok(IpcCommand::Batch { .. }) => serde_json::json!({
+                            "error": "Nested Batch requests are not supported"
+                        }),
+                        Ok(command) => Box::pin(Self::handle_command(command, state)).await,
+                        Err(e) => serde_json::json!({
+                            "error": format!("Invalid command: {}", e)
+                        }),
+                    };
+                    responses.push(response);
+                }
+                serde_json::json!({ "responses": responses })
+            }
+
+            IpcCommand::GetVersion => serde_json::to_value(BuildInfo::current()).unwrap_or_else(|e| {
+                serde_json::json!({
+                    "error": format!("Failed to serialize version: {}", e)
+                })
+            }),
+
+            IpcCommand::SetSubsystemEnabled { name, enabled } => {
+                let Some(subsystem) = Subsystem::parse(&name) else {
+                    let names: Vec<_> = Subsystem::ALL.iter().map(|s| s.as_str()).collect();
+                    return serde_json::json!({
+                        "success": false,
+                        "error": format!(
+                            "Unknown subsystem '{}', expected one of: {}",
+                            name,
+                            names.join(", ")
+                        )
+                    });
+                };
+
+                match subsystem {
+                    Subsystem::AdaptiveSensitivity => {
+                        state.controller.write().await.set_adaptive_sensitivity(enabled);
+                    }
+                    Subsystem::MonitorDetection => {
+                        state.subsystems.set(subsystem, enabled);
+                        if !enabled {
+                            // Don't stay paused on, or keep driving, a display nobody is watching anymore
+                            state.controller.write().await.set_external_display_detected(false);
+                            state.set_active_output(None).await;
+                        }
+                    }
+                    _ => {
+                        state.subsystems.set(subsystem, enabled);
+                    }
+                }
+
+                let verb = if enabled { "enabled" } else { "disabled" };
+                tracing::info!("Subsystem {} {} via IPC", subsystem.as_str(), verb);
+                serde_json::json!({
+                    "success": true,
+                    "message": format!("{} {}", subsystem.as_str(), verb)
+                })
+            }
+
+            IpcCommand::GetCapabilities => {
+                let capabilities = CapabilitiesResponse {
+                    build: BuildInfo::current(),
+                    subsystems: Subsystem::ALL.to_vec(),
+                    experiments: ExperimentInfo::list(&state.experiments),
+                    panel: state.panel().cloned(),
+                };
+                serde_json::to_value(capabilities).unwrap_or_else(|e| {
+                    serde_json::json!({
+                        "error": format!("Failed to serialize capabilities: {}", e)
+                    })
+                })
+            }
+
+            IpcCommand::GetSupportedRates => {
+                // The controlled external output's rates while there is one
+                let rates = match state.output_rates().await {
+                    Some(rates) => rates,
+                    None => state.supported_rates().await,
+                };
+                serde_json::json!({
+                    "success": true,
+                    "output": state.display.active_output(),
+                    "source": rates.source,
+                    "rates": rates.rates,
+                    "min": rates.min,
+                    "max": rates.max,
+                    // Range the daemon actually allows, and where it came from
+                    "range_min": state.display.panel_range().start(),
+                    "range_max": state.display.panel_range().end(),
+                    "range_source": state.display.panel_range_source()
+                })
+            }
+
+            IpcCommand::GetConnectors => {
+                let ignored = state.config_manager.get().ignored_connectors;
+                serde_json::json!({
+                    "success": true,
+                    "connectors": state.monitor_detector.connectors(&ignored).await,
+                    "ignored": ignored
+                })
+            }
+
+            IpcCommand::SetExperimentEnabled { name, enabled } => {
+                let Some(experiment) = Experiment::parse(&name) else {
+                    let names: Vec<_> = Experiment::ALL.iter().map(|e| e.as_str()).collect();
+                    return serde_json::json!({
+                        "success": false,
+                        "error": format!(
+                            "Unknown experiment '{}', expected one of: {}",
+                            name,
+                            names.join(", ")
+                        )
+                    });
+                };
+
+                let previous = state.experiments.set(experiment, enabled);
+                let mut config = state.config_manager.get();
+                config.experimental = state.experiments.snapshot();
+                if let Err(e) = state.config_manager.update(config) {
+                    state.experiments.set(experiment, previous);
+                    return serde_json::json!({
+                        "success": false,
+                        "error": e.to_string()
+                    });
+                }
+
+                if experiment == Experiment::PredictiveDrop {
+                    let slope = state.config_manager.get().predictive_drop();
+                    state.controller.write().await.set_predictive_drop(slope);
+                }
+
+                let verb = if enabled { "enabled" } else { "disabled" };
+                tracing::info!("Experiment {} {} via IPC", experiment.as_str(), verb);
+                serde_json::json!({
+                    "success": true,
+                    "message": format!("{} {}", experiment.as_str(), verb)
+                })
+            }
+
+            IpcCommand::SetDisplayBackend { backend } => {
+                let preferred = if backend.eq_ignore_ascii_case("auto") {
+                    None
+                } else {
+                    match BackendKind::parse(&backend) {
+                        Some(kind) => Some(kind),
+                        None => {
+                            let names: Vec<_> =
+                                display_backend::DETECTION_ORDER.iter().map(|k| k.as_str()).collect();
+                            return serde_json::json!({
+                                "success": false,
+                                "error": format!(
+                                    "Unknown display backend '{}', expected auto or one of: {}",
+                                    backend,
+                                    names.join(", ")
+                                )
+                            });
+                        }
+                    }
+                };
+
+                let mut config = state.config_manager.get();
+                config.display_backend = preferred;
+                if let Err(e) = state.config_manager.update(config) {
+                    return serde_json::json!({
+                        "success": false,
+                        "error": e.to_string()
+                    });
+                }
+
+                let selected = state.select_display_backend(preferred).await;
+                serde_json::json!({
+                    "success": true,
+                    "backend": selected,
+                    // The requested backend was unavailable and detection picked another
+                    "fallback": preferred.is_some_and(|kind| kind != selected)
+                })
+            }
+
+            IpcCommand::SetFpsSource { source } => {
+                let preferred = if source.eq_ignore_ascii_case("auto") {
+                    None
+                } else {
+                    match FpsSourceKind::parse(&source) {
+                        Some(kind) => Some(kind),
+                        None => {
+                            let names: Vec<_> =
+                                fps_source::DETECTION_ORDER.iter().map(|k| k.as_str()).collect();
+                            return serde_json::json!({
+                                "success": false,
+                                "error": format!(
+                                    "Unknown FPS source '{}', expected auto or one of: {}",
+                                    source,
+                                    names.join(", ")
+                                )
+                            });
+                        }
+                    }
+                };
+
+                let mut config = state.config_manager.get();
+                config.fps_source = preferred;
+                if let Err(e) = state.config_manager.update(config) {
+                    return serde_json::json!({
+                        "success": false,
+                        "error": e.to_string()
+                    });
+                }
+
+                tracing::info!("FPS source set to {}", preferred.map_or("auto", |kind| kind.as_str()));
+                serde_json::json!({
+                    "success": true,
+                    "source": preferred
+                })
+            }
+
+            IpcCommand::GetEvents { since } => {
+                serde_json::json!({ "events": state.notifications.events_since(since) })
+            }
+
+            IpcCommand::SimulateSettings { samples, sample_interval_ms, scenarios } => {
+                if samples.is_empty() || samples.len() > MAX_JOURNAL_SAMPLES {
+                    return serde_json::json!({
+                        "error": format!("Journal must contain 1 to {} samples", MAX_JOURNAL_SAMPLES)
+                    });
+                }
+                if scenarios.len() > MAX_SIMULATION_SCENARIOS {
+                    return serde_json::json!({
+                        "error": format!("At most {} scenarios per request", MAX_SIMULATION_SCENARIOS)
+                    });
+                }
+                let interval = Duration::from(
+                    sample_interval_ms
+                        .unwrap_or(DEFAULT_JOURNAL_INTERVAL_MS)
+                        .clamp(Millis(10), Millis(60_000)),
+                );
+
+                let config = state.config_manager.get();
+                let (fps_tolerance, mode) = {
+                    let controller = state.controller.read().await;
+                    (controller.fps_tolerance(), controller.device_mode())
+                };
+                let resolved: Result<Vec<Scenario>, IpcError> = scenarios
+                    .iter()
+                    .map(|overrides| overrides.resolve(&config, fps_tolerance, mode))
+                    .collect();
+                match resolved {
+                    Ok(resolved) => {
+                        let baseline = Scenario::from_config(&config, fps_tolerance, mode);
+                        let projections: Vec<_> = resolved
+                            .iter()
+                            .map(|scenario| whatif::simulate(&samples, interval, scenario))
+                            .collect();
+                        serde_json::json!({
+                            "baseline": whatif::simulate(&samples, interval, &baseline),
+                            "scenarios": projections
+                        })
+                    }
+                    Err(e) => serde_json::json!({ "error": e.to_string() }),
+                }
+            }
+
+            IpcCommand::StreamFps { .. } => serde_json::json!({
+                "error": "StreamFps is only available on a persistent socket connection"
+            }),
+
+            IpcCommand::Handshake { .. } => serde_json::json!({
+                "error": "Handshake is only available on a persistent socket connection"
+            }),
+
+            IpcCommand::ClearHz => {
+                if state.clear_hz_override().await {
+                    tracing::info!("Manual override cleared via IPC, resuming automatic control");
+                    serde_json::json!({ "success": true, "message": "Manual override cleared" })
+                } else {
+                    serde_json::json!({ "success": true, "message": "No manual override active" })
+                }
+            }
+        }
+    }
+}
+
+/// Write one response in the connection's negotiated encoding: a JSON
+/// line, or a MessagePack map (field names kept) after its length as a
+/// big-endian u32.
+#[cfg(unix)]
+async fn write_response<W, T>(writer: &mut W, response: &T, encoding: Encoding) -> Result<(), IpcError>
+where
+    W: tokio::io::AsyncWrite + Unpin,
+    T: Serialize + ?Sized,
+{
+    match encoding {
+        Encoding::Json => {
+            writer.write_all(serde_json::to_string(response)?.as_bytes()).await?;
+            writer.write_all(b"\n").await?;
+        }
+        Encoding::Msgpack => {
+            let payload = rmp_serde::to_vec_named(response)?;
+            writer.write_all(&(payload.len() as u32).to_be_bytes()).await?;
+            writer.write_all(&payload).await?;
+        }
+    }
+    writer.flush().await?;
+    Ok(())
+}
+
+#[cfg(unix)]
+impl Drop for IpcServer {
+    fn drop(&mut self) {
+        if self.socket_path.exists() {
+            let _ = std::fs::remove_file(&self.socket_path);
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::notifications::Severity;
+    use crate::profiles::DockedProfile;
+    use tempfile::tempdir;
+
+    /// Recorded request/response pairs as sent by the shipped Decky plugin
+    /// (main.py). Entries without a `response` are parse-only because
+    /// replaying them would touch the user's profile store.
+    const FRONTEND_TRAFFIC: &str = include_str!("../testdata/frontend_ipc_traffic.jsonl");
+
+    #[derive(Debug, Deserialize)]
+    struct RecordedExchange {
+        origin: String,
+        request: serde_json::Value,
+        #[serde(default)]
+        response: Option<serde_json::Value>,
+    }
+
+    fn recorded_exchanges() -> Vec<RecordedExchange> {
+        FRONTEND_TRAFFIC
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| serde_json::from_str(line).expect("corpus line must be valid JSON"))
+            .collect()
+    }
+
+    /// Dispatch one line as the socket loop does, as JSON.
+    async fn dispatch(line: &str, state: &Arc<DaemonState>) -> serde_json::Value {
+        match IpcServer::handle_request(line, state).await {
+            Reply::Json(value) | Reply::Handshake { ack: value, .. } | Reply::StreamFps { ack: value, .. } => {
+                value
+            }
+            Reply::Status(status) => serde_json::to_value(status).unwrap(),
+            Reply::Metrics(metrics) => serde_json::to_value(metrics).unwrap(),
+        }
+    }
+
+    fn test_state(dir: &Path) -> Arc<DaemonState> {
+        let config_manager =
+            Arc::new(ConfigManager::load_or_default(&dir.join("config.json")).unwrap());
+        Arc::new(DaemonState::new(
+            config_manager,
+            Arc::new(RwLock::new(ProfileManager::default())),
+            Arc::new(MetricsCollector::new()),
+            Arc::new(BatteryMonitor::new()),
+        ))
+    }
+
+    /// Check that `actual` still provides everything the frontend read from
+    /// `recorded`: same keys, same JSON types. Values are not compared since
+    /// they depend on runtime state, except for `success` flags.
+    fn assert_shape_compatible(recorded: &serde_json::Value, actual: &serde_json::Value, path: &str) {
+        use serde_json::Value;
+
+        match (recorded, actual) {
+            // Nullable fields (e.g. current_app_id) may legitimately differ
+            (Value::Null, _) | (_, Value::Null) => {}
+            (Value::Object(rec), Value::Object(act)) => {
+                for (key, rec_value) in rec {
+                    let child = format!("{}.{}", path, key);
+                    let act_value = act
+                        .get(key)
+                        .unwrap_or_else(|| panic!("{}: field missing from response", child));
+                    if key == "success" {
+                        assert_eq!(rec_value, act_value, "{}: success flag changed", child);
+                    }
+                    assert_shape_compatible(rec_value, act_value, &child);
+                }
+            }
+            (Value::Array(rec), Value::Array(act)) => {
+                if let Some(first) = rec.first() {
+                    for (i, item) in act.iter().enumerate() {
+                        assert_shape_compatible(first, item, &format!("{}[{}]", path, i));
+                    }
+                }
+            }
+            (Value::Number(_), Value::Number(_))
+            | (Value::String(_), Value::String(_))
+            | (Value::Bool(_), Value::Bool(_)) => {}
+            _ => panic!("{}: type changed from {} to {}", path, recorded, actual),
+        }
+    }
+
+    #[test]
+    fn test_recorded_frontend_requests_parse() {
+        let exchanges = recorded_exchanges();
+        assert!(!exchanges.is_empty());
+
+        for exchange in exchanges {
+            let expects_error = exchange
+                .response
+                .as_ref()
+                .is_some_and(|r| r.get("error").is_some() && r.get("success").is_none());
+            let parsed = serde_json::from_value::<IpcCommand>(exchange.request.clone());
+
+            if expects_error {
+                assert!(parsed.is_err(), "{}: expected parse failure", exchange.origin);
+            } else {
+                assert!(
+                    parsed.is_ok(),
+                    "{}: request no longer parses: {:?}",
+                    exchange.origin,
+                    parsed.err()
+                );
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_recorded_frontend_responses_compatible() {
+        let dir = tempdir().unwrap();
+        let state = test_state(dir.path());
+
+        for exchange in recorded_exchanges() {
+            let Some(recorded) = exchange.response else {
+                continue;
+            };
+
+            // Replay the exact line format main.py writes to the socket
+            let line = serde_json::to_string(&exchange.request).unwrap();
+            let actual = dispatch(&line, &state).await;
+
+            assert_shape_compatible(&recorded, &actual, &exchange.origin);
+
+            if let (Some(expected), Some(got)) = (
+                recorded.get("error").and_then(|e| e.as_str()),
+                actual.get("error").and_then(|e| e.as_str()),
+            ) {
+                assert!(
+                    got.starts_with(expected),
+                    "{}: error changed from '{}' to '{}'",
+                    exchange.origin,
+                    expected,
+                    got
+                );
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_hz_override_holds_until_cleared() {
+        let dir = tempdir().unwrap();
+        let state = test_state(dir.path());
+
+        state.set_hz_override(Hertz(60), None).await;
+        assert_eq!(state.current_hz_override().await, Some(Hertz(60)));
+
+        let status = state.get_status().await;
+        assert_eq!(status.override_hz, Some(Hertz(60)));
+        assert_eq!(status.override_remaining, None);
+
+        assert!(state.clear_hz_override().await);
+        assert_eq!(state.current_hz_override().await, None);
+        assert!(!state.clear_hz_override().await);
+    }
+
+    #[tokio::test]
+    async fn test_hz_override_expires() {
+        let dir = tempdir().unwrap();
+        let state = test_state(dir.path());
+
+        state.set_hz_override(Hertz(60), Some(Duration::ZERO)).await;
+        assert_eq!(state.current_hz_override().await, None);
+        assert_eq!(state.get_status().await.override_hz, None);
+    }
+
+    #[tokio::test]
+    async fn test_set_hz_rejects_out_of_range() {
+        let dir = tempdir().unwrap();
+        let state = test_state(dir.path());
+
+        let response =
+            dispatch(r#"{"command":"SetHz","hz":120}"#, &state).await;
+        assert_eq!(response["success"], false);
+        assert_eq!(state.current_hz_override().await, None);
+
+        let response =
+            dispatch(r#"{"command":"SetHz","hz":60,"duration_secs":30}"#, &state)
+                .await;
+        assert_eq!(response["success"], true);
+        assert_eq!(state.current_hz_override().await, Some(Hertz(60)));
+
+        let request = r#"{"command":"SetHz","hz":60,"duration_secs":18446744073709551615}"#;
+        let response = dispatch(request, &state).await;
+        assert_eq!(response["success"], false);
+
+        // Past the bound the override holds instead of panicking
+        state.set_hz_override(Hertz(50), Some(Duration::MAX)).await;
+        assert_eq!(state.current_hz_override().await, Some(Hertz(50)));
+    }
+
+    #[tokio::test]
+    async fn test_get_transitions_limit() {
+        let dir = tempdir().unwrap();
+        let state = test_state(dir.path());
+
+        for i in 0..(MAX_TRANSITION_HISTORY as u32 + 5) {
+            state.record_transition(Hertz(90), Hertz(40 + i % 50), 45.0).await;
+        }
+
+        // Ring buffer keeps only the newest entries
+        let all = state.get_transitions(None).await;
+        assert_eq!(all.len(), MAX_TRANSITION_HISTORY);
+        assert_eq!(all[0].to_hz, Hertz(40 + 5));
+
+        let recent = state.get_transitions(Some(3)).await;
+        assert_eq!(recent.len(), 3);
+        assert_eq!(recent[2].to_hz, all[all.len() - 1].to_hz);
+        assert_eq!(recent[0].to_hz, all[all.len() - 3].to_hz);
+
+        assert_eq!(state.get_status().await.transitions.len(), STATUS_TRANSITION_COUNT);
+
+        let response =
+            dispatch(r#"{"command":"GetTransitions","limit":2}"#, &state).await;
+        assert_eq!(response["transitions"].as_array().unwrap().len(), 2);
+        assert_eq!(response["capacity"], MAX_TRANSITION_HISTORY);
+    }
+
+    #[tokio::test]
+    async fn test_get_history() {
+        let dir = tempdir().unwrap();
+        let state = test_state(dir.path());
+        for fps in [58.0, 59.0, 60.0] {
+            state.record_history(HistoryPoint::now(fps, Hertz(60)));
+        }
+
+        let response = dispatch(r#"{"command":"GetHistory","limit":2}"#, &state).await;
+        let points = response["points"].as_array().unwrap();
+        assert_eq!(points.len(), 2);
+        assert_eq!(points[1]["fps"], 60.0);
+        assert_eq!(points[1]["hz"], 60);
+        assert_eq!(response["capacity"], HISTORY_CAPACITY);
+        assert_eq!(response["interval_secs"], HISTORY_INTERVAL_SECS);
+    }
+
+    #[test]
+    fn test_socket_path_resolution() {
+        assert_eq!(
+            socket_path_in(Some(PathBuf::from("/run/user/1000"))),
+            PathBuf::from("/run/user/1000/smart-refresh.sock")
+        );
+        assert_eq!(socket_path_in(None), PathBuf::from(FALLBACK_SOCKET_PATH));
+        // A relative XDG_RUNTIME_DIR is invalid per the spec and must be ignored
+        assert_eq!(
+            socket_path_in(Some(PathBuf::from("relative"))),
+            PathBuf::from(FALLBACK_SOCKET_PATH)
+        );
+
+        let cli = Path::new("/cli.sock");
+        let config = Path::new("/config.sock");
+        assert_eq!(resolve_socket_path(Some(cli), Some(config)), cli);
+        assert_eq!(resolve_socket_path(None, Some(config)), config);
+        assert_eq!(resolve_socket_path(None, None), default_socket_path());
+    }
+
+    #[tokio::test]
+    async fn test_socket_is_owner_only() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(SOCKET_FILE_NAME);
+        let server = IpcServer::new(&path, false).await.unwrap();
+
+        let mode = std::fs::metadata(server.socket_path()).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    #[test]
+    fn test_uid_authorization() {
+        assert!(is_uid_authorized(1000, 1000, &[]));
+        assert!(is_uid_authorized(0, 1000, &[]));
+        assert!(!is_uid_authorized(1001, 1000, &[]));
+        assert!(is_uid_authorized(1001, 1000, &[1001]));
+    }
+
+    #[tokio::test]
+    async fn test_same_user_connection_is_served() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(SOCKET_FILE_NAME);
+        let server = IpcServer::new(&path, false).await.unwrap();
+        let state = test_state(dir.path());
+        tokio::spawn(async move { server.run(state).await });
+
+        let stream = UnixStream::connect(&path).await.unwrap();
+        let (reader, mut writer) = stream.into_split();
+        writer.write_all(b"{\"command\":\"GetStatus\"}\n").await.unwrap();
+
+        let mut response = String::new();
+        BufReader::new(reader).read_line(&mut response).await.unwrap();
+        let response: serde_json::Value = serde_json::from_str(&response).unwrap();
+        assert!(response.get("error").is_none(), "unexpected error: {}", response);
+        assert!(response.get("current_hz").is_some());
+    }
+
+    #[tokio::test]
+    async fn test_stream_fps_pushes_until_next_command() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(SOCKET_FILE_NAME);
+        let server = IpcServer::new(&path, false).await.unwrap();
+        let state = test_state(dir.path());
+        *state.current_fps.write().await = 58.5;
+        tokio::spawn(async move { server.run(state).await });
+
+        let stream = UnixStream::connect(&path).await.unwrap();
+        let (reader, mut writer) = stream.into_split();
+        let mut reader = BufReader::new(reader);
+        writer
+            .write_all(b"{\"command\":\"StreamFps\",\"interval_ms\":50}\n")
+            .await
+            .unwrap();
+
+        let mut line = String::new();
+        reader.read_line(&mut line).await.unwrap();
+        let ack: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(ack["success"], true);
+
+        for _ in 0..2 {
+            line.clear();
+            reader.read_line(&mut line).await.unwrap();
+            let frame: FpsStreamFrame = serde_json::from_str(&line).unwrap();
+            assert_eq!(frame.fps, 58.5);
+        }
+
+        // Any new command ends the stream and is answered normally
+        writer.write_all(b"{\"command\":\"GetStatus\"}\n").await.unwrap();
+        let status = loop {
+            line.clear();
+            reader.read_line(&mut line).await.unwrap();
+            let value: serde_json::Value = serde_json::from_str(&line).unwrap();
+            if value.get("running").is_some() {
+                break value;
+            }
+        };
+        assert_eq!(status["current_fps"], 58.5);
+    }
+
+    #[tokio::test]
+    async fn test_msgpack_handshake_switches_encoding() {
+        use tokio::io::AsyncReadExt;
+
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(SOCKET_FILE_NAME);
+        let server = IpcServer::new(&path, false).await.unwrap();
+        let state = test_state(dir.path());
+        tokio::spawn(async move { server.run(state).await });
+
+        let stream = UnixStream::connect(&path).await.unwrap();
+        let (reader, mut writer) = stream.into_split();
+        let mut reader = BufReader::new(reader);
+        writer
+            .write_all(b"{\"command\":\"Handshake\",\"encoding\":\"msgpack\"}\n")
+            .await
+            .unwrap();
+
+        let mut line = String::new();
+        reader.read_line(&mut line).await.unwrap();
+        let ack: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(ack["encoding"], "msgpack");
+
+        let mut read_frame = async || {
+            let mut len = [0u8; 4];
+            reader.read_exact(&mut len).await.unwrap();
+            let mut payload = vec![0u8; u32::from_be_bytes(len) as usize];
+            reader.read_exact(&mut payload).await.unwrap();
+            rmp_serde::from_slice::<serde_json::Value>(&payload).unwrap()
+        };
+
+        writer.write_all(b"{\"command\":\"ClearHz\"}\n").await.unwrap();
+        let expected = dispatch(r#"{"command":"ClearHz"}"#, &test_state(dir.path())).await;
+        assert_eq!(read_frame().await, expected);
+
+        // Typed responses keep their field names
+        writer.write_all(b"{\"command\":\"GetStatus\"}\n").await.unwrap();
+        let status = read_frame().await;
+        assert_eq!(status["running"], true);
+        assert!(status.get("current_hz").is_some());
+    }
+
+    #[tokio::test]
+    async fn test_ui_hz_only_applies_without_game() {
+        let dir = tempdir().unwrap();
+        let state = test_state(dir.path());
+        assert_eq!(state.ui_hz_target().await, None);
+
+        let mut config = state.config_manager.get();
+        config.ui_hz = Some(Hertz(60));
+        state.config_manager.update(config).unwrap();
+        assert_eq!(state.ui_hz_target().await, Some(Hertz(60)));
+
+        dispatch(r#"{"command":"SetGameId","app_id":"1091500"}"#, &state).await;
+        assert_eq!(state.ui_hz_target().await, None);
+    }
+
+    #[tokio::test]
+    async fn test_set_config_custom_sensitivity() {
+        let dir = tempdir().unwrap();
+        let state = test_state(dir.path());
+
+        // Custom without durations is rejected
+        let response = dispatch(
+            r#"{"command":"SetConfig","min_hz":40,"max_hz":90,"sensitivity":"custom"}"#,
+            &state,
+        )
+        .await;
+        assert_eq!(response["success"], false);
+
+        let response = dispatch(
+            r#"{"command":"SetConfig","min_hz":40,"max_hz":90,"sensitivity":"custom",
+                "custom_drop_ms":750,"custom_increase_ms":2000}"#,
+            &state,
+        )
+        .await;
+        assert_eq!(response["success"], true);
+        assert_eq!(
+            state.controller.read().await.thresholds(),
+            (Duration::from_millis(750), Duration::from_millis(2000))
+        );
+
+        let status = state.get_status().await;
+        assert_eq!(status.config.sensitivity, "custom");
+        assert_eq!(status.config.custom_drop_ms, Some(Millis(750)));
+    }
+
+    #[tokio::test]
+    async fn test_set_config_control_policy() {
+        let dir = tempdir().unwrap();
+        let state = test_state(dir.path());
+        assert_eq!(state.controller.read().await.control_policy(), ControlPolicy::Hysteresis);
+
+        let response = dispatch(
+            r#"{"command":"SetConfig","min_hz":40,"max_hz":90,"sensitivity":"balanced","control_policy":"pid"}"#,
+            &state,
+        )
+        .await;
+        assert_eq!(response["success"], true);
+        assert_eq!(state.controller.read().await.control_policy(), ControlPolicy::Pid);
+        assert_eq!(state.config_manager.get().control_policy, ControlPolicy::Pid);
+        assert_eq!(state.get_status().await.config.control_policy, ControlPolicy::Pid);
+
+        let response = dispatch(
+            r#"{"command":"SetConfig","min_hz":40,"max_hz":90,"sensitivity":"balanced","control_policy":"fuzzy"}"#,
+            &state,
+        )
+        .await;
+        assert!(response.get("error").is_some());
+    }
+
+    #[tokio::test]
+    async fn test_set_config_adaptive_sensitivity() {
+        let dir = tempdir().unwrap();
+        let state = test_state(dir.path());
+        let response = dispatch(
+            r#"{"command":"SetConfig","min_hz":40,"max_hz":90,"sensitivity":"aggressive","adaptive_sensitivity":true}"#,
+            &state,
+        )
+        .await;
+        assert_eq!(response["success"], true);
+        assert!(state.config_manager.get().adaptive_sensitivity);
+        let status = state.get_status().await;
+        assert!(status.config.adaptive_sensitivity);
+        assert_eq!(status.effective_sensitivity, "aggressive");
+
+        // Persisted: a restarted daemon comes up with it enabled
+        let restarted = test_state(dir.path());
+        assert!(restarted.controller.read().await.is_adaptive_sensitivity_enabled());
+
+        // Omitting the field keeps the stored setting
+        dispatch(
+            r#"{"command":"SetConfig","min_hz":40,"max_hz":90,"sensitivity":"balanced"}"#,
+            &state,
+        )
+        .await;
+        assert!(state.controller.read().await.is_adaptive_sensitivity_enabled());
+    }
+
+    #[tokio::test]
+    async fn test_fps_tolerance_persisted() {
+        let dir = tempdir().unwrap();
+        let state = test_state(dir.path());
+        assert_eq!(state.get_status().await.fps_tolerance, crate::core_logic::DEFAULT_FPS_TOLERANCE);
+
+        let advanced = |tolerance: f64| {
+            format!(r#"{{"command":"SetAdvancedConfig","fps_tolerance":{}}}"#, tolerance)
+        };
+        let response = dispatch(&advanced(4.5), &state).await;
+        assert_eq!(response["success"], true);
+        assert_eq!(state.config_manager.get().fps_tolerance, 4.5);
+        assert_eq!(test_state(dir.path()).get_status().await.fps_tolerance, 4.5);
+
+        // Out of range is rejected rather than clamped
+        let response = dispatch(&advanced(9.0), &state).await;
+        assert_eq!(response["success"], false);
+        assert_eq!(state.controller.read().await.fps_tolerance(), 4.5);
+    }
+
+    #[tokio::test]
+    async fn test_resume_cooldown_persisted() {
+        let dir = tempdir().unwrap();
+        let state = test_state(dir.path());
+        let request = r#"{"command":"SetAdvancedConfig","resume_cooldown_secs":20}"#;
+        let response = dispatch(request, &state).await;
+        assert_eq!(response["success"], true);
+        assert_eq!(state.config_manager.get().resume_cooldown_secs, Secs(20));
+
+        // A restarted daemon uses the stored cooldown after the next wake
+        let restarted = test_state(dir.path());
+        restarted.controller.write().await.reset_state();
+        let remaining = restarted.get_status().await.resume_cooldown_remaining;
+        assert!(remaining > 15.0 && remaining <= 20.0);
+
+        let request = r#"{"command":"SetAdvancedConfig","resume_cooldown_secs":600}"#;
+        let response = dispatch(request, &state).await;
+        assert_eq!(response["success"], false);
+    }
+
+    #[tokio::test]
+    async fn test_panel_sets_device_mode_and_capabilities() {
+        let dir = tempdir().unwrap();
+        let state = test_state(dir.path());
+        let panel = PanelInfo {
+            connector: "card0-eDP-1".to_string(),
+            make: "VLV".to_string(),
+            product_code: 0x3003,
+            name: Some("Jupiter".to_string()),
+            native_width: Some(800),
+            native_height: Some(1280),
+            min_hz: Some(Hertz(40)),
+            max_hz: Some(Hertz(60)),
+        };
+        state.set_panel(panel).await;
+        assert_eq!(state.controller.read().await.device_mode(), DeviceMode::Lcd);
+        assert_eq!(state.config_manager.panel_range(), Hertz(40)..=Hertz(60));
+        assert_eq!(state.display.panel_range_source(), HzRangeSource::Edid);
+        assert_eq!(state.display.get_max_hz(), Hertz(60));
+        assert_eq!(state.controller.read().await.user_range(), (Hertz(40), Hertz(60)));
+
+        let capabilities = dispatch(r#"{"command":"GetCapabilities"}"#, &state).await;
+        assert_eq!(capabilities["panel"]["name"], "Jupiter");
+        assert_eq!(capabilities["panel"]["max_hz"], 60);
+    }
+
+    #[tokio::test]
+    async fn test_device_model_preset() {
+        let dir = tempdir().unwrap();
+        let state = test_state(dir.path());
+        state.set_device_model(DeviceModel::LegionGo).await;
+        assert_eq!(state.apply_hz_range().await, HzRangeSource::Preset);
+        assert_eq!(state.display.panel_range(), Hertz(60)..=Hertz(144));
+        {
+            let controller = state.controller.read().await;
+            assert_eq!(controller.device_mode(), DeviceMode::Custom);
+            assert_eq!(controller.pacing(Hertz(60)).min_change_interval_ms, Millis(1000));
+        }
+
+        // The preset's device mode wins over the panel's, its EDID range over the preset's
+        let panel = PanelInfo {
+            connector: "card0-eDP-1".to_string(),
+            make: "VLV".to_string(),
+            product_code: 0x3003,
+            name: Some("Jupiter".to_string()),
+            native_width: Some(800),
+            native_height: Some(1280),
+            min_hz: Some(Hertz(40)),
+            max_hz: Some(Hertz(60)),
+        };
+        state.set_panel(panel).await;
+        assert_eq!(state.controller.read().await.device_mode(), DeviceMode::Custom);
+        assert_eq!(state.display.panel_range_source(), HzRangeSource::Edid);
+
+        let status = dispatch(r#"{"command":"GetStatus"}"#, &state).await;
+        assert_eq!(status["device_model"], "legion_go");
+    }
+
+    #[tokio::test]
+    async fn test_desktop_session_pauses_switching() {
+        let dir = tempdir().unwrap();
+        let state = test_state(dir.path());
+        assert_eq!(state.set_session(SessionKind::Desktop), SessionKind::Unknown);
+        assert!(state.is_session_paused());
+        let status = dispatch(r#"{"command":"GetStatus"}"#, &state).await;
+        assert_eq!(status["session"], "desktop");
+        assert_eq!(status["session_paused"], true);
+
+        let request = r#"{"command":"SetAdvancedConfig","desktop_mode_switching":true}"#;
+        let response = dispatch(request, &state).await;
+        assert_eq!(response["desktop_mode_switching"], true);
+        assert!(!state.is_session_paused());
+
+        state.set_session(SessionKind::Gaming);
+        state.config_manager.update(Config::default()).unwrap();
+        assert!(!state.is_session_paused());
+    }
+
+    #[tokio::test]
+    async fn test_overlay_freezes_pending_switch() {
+        let dir = tempdir().unwrap();
+        let state = test_state(dir.path());
+        {
+            let mut controller = state.controller.write().await;
+            controller.process(30.0, Hertz(60));
+            assert!(matches!(controller.state(), AlgorithmState::Dropping { .. }));
+        }
+
+        assert!(!state.set_overlay_open(true).await);
+        assert_eq!(state.controller.read().await.state(), AlgorithmState::Stable);
+        let status = dispatch(r#"{"command":"GetStatus"}"#, &state).await;
+        assert_eq!(status["overlay_open"], true);
+
+        assert!(state.set_overlay_open(false).await);
+        assert!(!state.is_overlay_open());
+    }
+
+    #[tokio::test]
+    async fn test_stale_fps_drops_pending_switch() {
+        let dir = tempdir().unwrap();
+        let state = test_state(dir.path());
+        state.controller.write().await.process(30.0, Hertz(60));
+
+        assert!(!state.set_fps_stale(true).await);
+        assert_eq!(state.controller.read().await.state(), AlgorithmState::Stable);
+        let status = dispatch(r#"{"command":"GetStatus"}"#, &state).await;
+        assert_eq!(status["fps_stale"], true);
+        assert!(state.set_fps_stale(false).await);
+    }
+
+    #[tokio::test]
+    async fn test_frame_stability_in_status() {
+        let dir = tempdir().unwrap();
+        let state = test_state(dir.path());
+        let status = dispatch(r#"{"command":"GetStatus"}"#, &state).await;
+        assert_eq!(status["fps_stability"], serde_json::Value::Null);
+
+        state.set_frametime_p99(21_400);
+        let start = Instant::now();
+        for fps in [30.0, 60.0, 35.0, 55.0, 40.0, 65.0, 32.0, 58.0, 38.0, 62.0] {
+            state.controller.write().await.process_with_time(fps, Hertz(60), start);
+        }
+        let status = dispatch(r#"{"command":"GetStatus"}"#, &state).await;
+        assert_eq!(status["frametime_p99_us"], 21_400);
+        assert_eq!(status["fps_stability"], "unstable");
+    }
+
+    #[tokio::test]
+    async fn test_blacklisted_game_holds_max_hz() {
+        let dir = tempdir().unwrap();
+        let state = test_state(dir.path());
+        state.profile_manager.write().await.set_current_game(Some("570".into()));
+        assert_eq!(state.game_hold_hz().await, None);
+
+        let request = r#"{"command":"SetGameSwitching","app_id":"570","switching":"disabled"}"#;
+        let command: IpcCommand = serde_json::from_str(request).unwrap();
+        assert!(matches!(command, IpcCommand::SetGameSwitching { switching: GameSwitching::Disabled, .. }));
+
+        // Profiles are saved under $HOME, so set the flag directly
+        state.profile_manager.write().await.set_switching("570", Some("Dota 2"), GameSwitching::Disabled);
+        let max_hz = state.controller.read().await.user_range().1;
+        assert_eq!(state.game_hold_hz().await, Some(max_hz));
+        let status = dispatch(r#"{"command":"GetStatus"}"#, &state).await;
+        assert_eq!(status["game_switching_disabled"], true);
+
+        // Whitelist-only mode holds games not whitelisted
+        state.profile_manager.write().await.set_switching("570", None, GameSwitching::Auto);
+        assert_eq!(state.game_hold_hz().await, None);
+        let response =
+            dispatch(r#"{"command":"SetAdvancedConfig","whitelist_only":true}"#, &state).await;
+        assert_eq!(response["whitelist_only"], true);
+        assert_eq!(state.game_hold_hz().await, Some(max_hz));
+        state.profile_manager.write().await.set_switching("570", None, GameSwitching::Enabled);
+        assert_eq!(state.game_hold_hz().await, None);
+    }
+
+    #[tokio::test]
+    async fn test_recording_locks_rate() {
+        let dir = tempdir().unwrap();
+        let state = test_state(dir.path());
+        {
+            let mut controller = state.controller.write().await;
+            controller.process(30.0, Hertz(60));
+            assert!(matches!(controller.state(), AlgorithmState::Dropping { .. }));
+        }
+
+        assert_eq!(state.set_recording(Some("obs".into())).await, None);
+        assert_eq!(state.controller.read().await.state(), AlgorithmState::Stable);
+        let status = dispatch(r#"{"command":"GetStatus"}"#, &state).await;
+        assert_eq!(status["recording"], "obs");
+
+        assert_eq!(state.set_recording(None).await, Some("obs".into()));
+        let status = dispatch(r#"{"command":"GetStatus"}"#, &state).await;
+        assert!(status["recording"].is_null());
+    }
+
+    #[tokio::test]
+    async fn test_system_update_status() {
+        let dir = tempdir().unwrap();
+        let state = test_state(dir.path());
+        assert_eq!(state.set_system_update(Some("steamos-atomupd".into())), None);
+        let status = dispatch(r#"{"command":"GetStatus"}"#, &state).await;
+        assert_eq!(status["system_update"], "steamos-atomupd");
+        assert_eq!(state.set_system_update(None), Some("steamos-atomupd".into()));
+    }
+
+    #[tokio::test]
+    async fn test_get_connectors() {
+        let dir = tempdir().unwrap();
+        let state = test_state(dir.path());
+        let mut config = state.config_manager.get();
+        config.ignored_connectors = vec!["DP-2".to_string()];
+        state.config_manager.update(config).unwrap();
+
+        let response = dispatch(r#"{"command":"GetConnectors"}"#, &state).await;
+        assert_eq!(response["success"], true);
+        assert!(response["connectors"].is_array());
+        assert_eq!(response["ignored"], serde_json::json!(["DP-2"]));
+
+        state.refresh_displays().await;
+        let status = dispatch(r#"{"command":"GetStatus"}"#, &state).await;
+        assert!(status["displays"].is_array());
+    }
+
+    #[tokio::test]
+    async fn test_external_output_bounds_range() {
+        let dir = tempdir().unwrap();
+        let state = test_state(dir.path());
+
+        // Unreadable modes leave the panel in control
+        assert!(!state.set_active_output(Some("DP-9".to_string())).await);
+        assert_eq!(state.display.active_output(), None);
+
+        *state.output_rates.write().await = Some(SupportedRates {
+            source: RateSource::Drm,
+            rates: vec![Hertz(60), Hertz(75), Hertz(144)],
+            min: Hertz(60),
+            max: Hertz(144),
+        });
+        assert_eq!(state.apply_hz_range().await, HzRangeSource::Output);
+        assert_eq!(state.display.panel_range(), Hertz(60)..=Hertz(144));
+        assert_eq!(state.allowed_hz().await, [Hertz(60), Hertz(75), Hertz(144)]);
+        let response = dispatch(r#"{"command":"GetSupportedRates"}"#, &state).await;
+        assert_eq!(response["max"], 144);
+        assert_eq!(response["range_source"], "output");
+
+        // The configured range is not narrowed to the output's
+        assert_eq!(state.config_manager.get().min_hz, Hertz(40));
+    }
+
+    #[tokio::test]
+    async fn test_get_supported_rates() {
+        let dir = tempdir().unwrap();
+        let state = test_state(dir.path());
+        let response = dispatch(r#"{"command":"GetSupportedRates"}"#, &state).await;
+        assert_eq!(response["success"], true);
+        let rates = response["rates"].as_array().unwrap();
+        assert!(!rates.is_empty());
+        assert_eq!(response["min"], rates[0]);
+        assert_eq!(response["max"], *rates.last().unwrap());
+        assert_eq!(response["range_source"], "fallback");
+        assert_eq!(response["range_min"], 40);
+        assert_eq!(response["range_max"], 90);
+    }
+
+    #[tokio::test]
+    async fn test_manual_change_grace_persisted() {
+        let dir = tempdir().unwrap();
+        let state = test_state(dir.path());
+        let request = r#"{"command":"SetAdvancedConfig","manual_change_grace_secs":0}"#;
+        let response = dispatch(request, &state).await;
+        assert_eq!(response["success"], true);
+        assert_eq!(test_state(dir.path()).config_manager.get().manual_change_grace_secs, Secs(0));
+
+        let request = r#"{"command":"SetAdvancedConfig","manual_change_grace_secs":3600}"#;
+        let response = dispatch(request, &state).await;
+        assert_eq!(response["success"], false);
+    }
+
+    #[tokio::test]
+    async fn test_docked_profile_replaces_settings() {
+        let dir = tempdir().unwrap();
+        let state = test_state(dir.path());
+        state.profile_manager.write().await.docked = Some(DockedProfile {
+            min_hz: Hertz(50),
+            max_hz: Hertz(60),
+            sensitivity: "aggressive".to_string(),
+            adaptive_sensitivity: false,
+            power_saving: true,
+        });
+
+        state.set_docked(true).await;
+        assert_eq!(state.controller.read().await.user_range(), (Hertz(50), Hertz(60)));
+        let response = dispatch(r#"{"command":"SetGameId","app_id":"620"}"#, &state).await;
+        assert_eq!(response["profile_applied"], false);
+        assert_eq!(state.controller.read().await.user_range(), (Hertz(50), Hertz(60)));
+        let status = dispatch(r#"{"command":"GetStatus"}"#, &state).await;
+        assert_eq!(status["docked"], true);
+
+        state.set_docked(false).await;
+        assert_eq!(state.controller.read().await.user_range(), (Hertz(40), Hertz(90)));
+    }
+
+    #[tokio::test]
+    async fn test_startup_rate_persisted() {
+        let dir = tempdir().unwrap();
+        let state = test_state(dir.path());
+        let request = r#"{"command":"SetAdvancedConfig","startup_rate":"restore"}"#;
+        let response = dispatch(request, &state).await;
+        assert_eq!(response["success"], true);
+        assert_eq!(response["startup_rate"], "restore");
+        assert_eq!(test_state(dir.path()).config_manager.get().startup_rate, StartupRate::Restore);
+    }
+
+    #[tokio::test]
+    async fn test_fps_smoothing_persisted() {
+        let dir = tempdir().unwrap();
+        let state = test_state(dir.path());
+        let request = r#"{"command":"SetAdvancedConfig","fps_smoothing":"ema","fps_ema_alpha":0.25}"#;
+        let response = dispatch(request, &state).await;
+        assert_eq!(response["success"], true);
+        assert_eq!(response["fps_smoothing"], "ema");
+        assert_eq!(test_state(dir.path()).config_manager.get().fps_ema_alpha, 0.25);
+        let status = dispatch(r#"{"command":"GetStatus"}"#, &state).await;
+        assert_eq!(status["fps_smoothing"], "ema");
+
+        let request = r#"{"command":"SetAdvancedConfig","fps_ema_alpha":0}"#;
+        let response = dispatch(request, &state).await;
+        assert_eq!(response["success"], false);
+
+        let request = r#"{"command":"SetAdvancedConfig","fps_window_secs":4}"#;
+        let response = dispatch(request, &state).await;
+        assert_eq!(response["fps_window_secs"], 4);
+    }
+
+    #[tokio::test]
+    async fn test_set_config_video_cadence() {
+        let dir = tempdir().unwrap();
+        let state = test_state(dir.path());
+        let response = dispatch(
+            r#"{"command":"SetConfig","min_hz":40,"max_hz":90,"sensitivity":"balanced","video_cadence":true}"#,
+            &state,
+        )
+        .await;
+        assert_eq!(response["success"], true);
+        assert!(state.config_manager.get().video_cadence);
+        assert!(state.get_status().await.config.video_cadence);
+    }
+
+    #[tokio::test]
+    async fn test_save_profile_keeps_omitted_dwell() {
+        let dir = tempdir().unwrap();
+        let state = test_state(dir.path());
+        let save = |extra: &str| {
+            format!(
+                r#"{{"command":"SaveProfile","app_id":"570","name":"Dota 2","min_hz":40,"max_hz":90,"sensitivity":"balanced"{}}}"#,
+                extra
+            )
+        };
+        let dwell = || async { state.profile_manager.read().await.get_profile("570").unwrap().min_dwell_secs };
+
+        dispatch(&save(r#","min_dwell_secs":15"#), &state).await;
+        assert_eq!(dwell().await, Some(Secs(15)));
+        // The QAM saves without the field
+        let response = dispatch(&save(""), &state).await;
+        assert_eq!(response["success"], true);
+        assert_eq!(dwell().await, Some(Secs(15)));
+
+        dispatch(&save(r#","min_dwell_secs":null"#), &state).await;
+        assert_eq!(dwell().await, None);
+    }
+
+    #[tokio::test]
+    async fn test_profile_min_dwell_overrides_config() {
+        let dir = tempdir().unwrap();
+        let state = test_state(dir.path());
+        let mut profile = GameProfile::new("570".into(), "Dota 2".into(), Hertz(40), Hertz(90), "balanced".into());
+        profile.min_dwell_secs = Some(Secs(20));
+        state.profile_manager.write().await.set_profile(profile);
+        let dwell = || async { state.controller.read().await.min_dwell() };
+
+        let advanced = |secs: u64| format!(r#"{{"command":"SetAdvancedConfig","min_dwell_secs":{}}}"#, secs);
+        let response = dispatch(&advanced(5), &state).await;
+        assert_eq!(response["success"], true);
+        assert_eq!(state.config_manager.get().min_dwell_secs, Secs(5));
+        assert_eq!(dwell().await, Secs(5));
+
+        dispatch(r#"{"command":"SetGameId","app_id":"570"}"#, &state).await;
+        assert_eq!(dwell().await, Secs(20));
+
+        // The global value changes underneath, the profile keeps precedence
+        dispatch(&advanced(8), &state).await;
+        assert_eq!(dwell().await, Secs(20));
+
+        dispatch(r#"{"command":"SetGameId","app_id":"0"}"#, &state).await;
+        assert_eq!(dwell().await, Secs(8));
+
+        let response = dispatch(&advanced(600), &state).await;
+        assert_eq!(response["success"], false);
+    }
+
+    #[tokio::test]
+    async fn test_remote_requests_cannot_set_hooks() {
+        let dir = tempdir().unwrap();
+        let state = test_state(dir.path());
+        let save = |hook: &str| {
+            format!(
+                r#"{{"command":"SaveProfile","app_id":"570","name":"Dota 2","min_hz":40,"max_hz":90,"sensitivity":"balanced","on_launch":"{}"}}"#,
+                hook
+            )
+        };
+
+        let response = IpcServer::handle_remote_request(&save("touch /tmp/pwned"), &state).await;
+        assert!(response["error"].as_str().unwrap().contains("Unix socket"));
+        let batch = format!(r#"{{"command":"Batch","commands":[{}]}}"#, save("touch /tmp/pwned"));
+        assert!(IpcServer::handle_remote_request(&batch, &state).await.get("error").is_some());
+        assert!(state.profile_manager.read().await.get_profile("570").is_none());
+
+        let response = IpcServer::handle_remote_request(&save(""), &state).await;
+        assert_eq!(response["success"], true);
+        let response = dispatch(&save("true"), &state).await;
+        assert_eq!(response["success"], true);
+        assert_eq!(state.profile_manager.read().await.get_profile("570").unwrap().on_launch.as_deref(), Some("true"));
+    }
+
+    #[tokio::test]
+    async fn test_save_profile_keeps_omitted_hooks() {
+        let dir = tempdir().unwrap();
+        let state = test_state(dir.path());
+        let hooks = |state: &Arc<DaemonState>| {
+            let state = Arc::clone(state);
+            async move {
+                let profile_manager = state.profile_manager.read().await;
+                let profile = profile_manager.get_profile("570").unwrap();
+                (profile.on_launch.clone(), profile.on_exit.clone())
+            }
+        };
+        let save = |extra: &str| {
+            format!(
+                r#"{{"command":"SaveProfile","app_id":"570","name":"Dota 2","min_hz":40,"max_hz":90,"sensitivity":"balanced"{}}}"#,
+                extra
+            )
+        };
+
+        dispatch(&save(r#","on_launch":"gamemode on","on_exit":"gamemode off""#), &state).await;
+        // The QAM saves without the hook fields
+        let response = dispatch(&save(""), &state).await;
+        assert_eq!(response["success"], true);
+        assert_eq!(hooks(&state).await, (Some("gamemode on".into()), Some("gamemode off".into())));
+
+        dispatch(&save(r#","on_launch":null,"on_exit":"""#), &state).await;
+        assert_eq!(hooks(&state).await, (None, None));
+    }
+
+    #[tokio::test]
+    async fn test_game_switch_applies_profile() {
+        let dir = tempdir().unwrap();
+        let state = test_state(dir.path());
+        let mut profile = GameProfile::new("570".into(), "Dota 2".into(), Hertz(45), Hertz(60), "aggressive".into());
+        profile.adaptive_sensitivity = true;
+        state.profile_manager.write().await.set_profile(profile);
+
+        assert_eq!(state.switch_game(Some("570".into()), None).await.as_deref(), Some("Dota 2"));
+        {
+            let controller = state.controller.read().await;
+            assert_eq!(controller.user_range(), (Hertz(45), Hertz(60)));
+            assert!(controller.is_adaptive_sensitivity_enabled());
+        }
+        assert_eq!((state.display.get_min_hz(), state.display.get_max_hz()), (Hertz(45), Hertz(60)));
+
+        // Leaving the game goes back to the global defaults
+        assert_eq!(state.switch_game(None, None).await, None);
+        assert_eq!(state.controller.read().await.user_range(), (Hertz(40), Hertz(90)));
+        assert!(!state.controller.read().await.is_adaptive_sensitivity_enabled());
+        assert_eq!((state.display.get_min_hz(), state.display.get_max_hz()), (Hertz(40), Hertz(90)));
+    }
+
+    #[tokio::test]
+    async fn test_thermal_status_reported() {
+        let dir = tempdir().unwrap();
+        let state = test_state(dir.path());
+        assert!(!state.get_status().await.thermal.available);
+
+        state.update_thermal(Some(97.0)).await;
+        let thermal = state.get_status().await.thermal;
+        assert!(thermal.available && thermal.throttling);
+        assert_eq!(thermal.temp_c, Some(97.0));
+
+        // Thermal subsystem switched off: no reading, hold released
+        state.update_thermal(None).await;
+        assert!(!state.get_status().await.thermal.throttling);
+
+        let events = state.notifications.events_since(None);
+        assert_eq!(events.len(), 2);
+        assert!(events.iter().all(|e| e.kind == EventKind::Thermal && e.severity == Severity::Warning));
+        assert!(events[0].message.contains("97°C"));
+    }
+
+    #[tokio::test]
+    async fn test_gpu_saturation_holds_increases() {
+        let dir = tempdir().unwrap();
+        let state = test_state(dir.path());
+        let request = r#"{"command":"SetAdvancedConfig","gpu_utilization":true}"#;
+        let response = dispatch(request, &state).await;
+        assert_eq!(response["success"], true);
+        assert!(state.config_manager.get().gpu_utilization);
 
-            let response = match serde_json::from_str::<IpcCommand>(trimmed) {
-                Ok(command) => Self::handle_command(command, &state).await,
-                Err(e) => serde_json::json!({
-                    "error": format!("Invalid command: {}", e)
-                }),
-            };
+        let sample = UtilizationStatus {
+            enabled: true,
+            gpu_busy_percent: Some(99),
+            cpu_load_percent: Some(40.0),
+            gpu_saturated: true,
+        };
+        state.update_utilization(Some(sample)).await;
+        assert_eq!(state.get_status().await.utilization, sample);
 
-            let response_str = serde_json::to_string(&response)?;
-            writer.write_all(response_str.as_bytes()).await?;
-            writer.write_all(b"\n").await?;
-            writer.flush().await?;
+        // Sampling disabled: readings cleared, hold released
+        state.update_utilization(None).await;
+        let utilization = state.get_status().await.utilization;
+        assert!(!utilization.enabled && !utilization.gpu_saturated);
+    }
 
-            line.clear();
-        }
+    #[tokio::test]
+    async fn test_idle_policy_target() {
+        let dir = tempdir().unwrap();
+        let state = test_state(dir.path());
+        // Default policy holds
+        assert_eq!(state.idle_hz_target(Duration::from_secs(60)), None);
 
-        Ok(())
+        let response = dispatch(
+            r#"{"command":"SetAdvancedConfig","idle_policy":"min","idle_after_secs":5}"#,
+            &state,
+        )
+        .await;
+        assert_eq!(response["success"], true);
+        assert_eq!(state.config_manager.get().idle_policy, IdlePolicy::Min);
+        assert_eq!(state.idle_hz_target(Duration::from_secs(4)), None);
+        assert_eq!(state.idle_hz_target(Duration::from_secs(5)), Some(Hertz(40)));
     }
 
-    pub async fn handle_command(
-        command: IpcCommand,
-        state: &Arc<DaemonState>,
-    ) -> serde_json::Value {
-        match command {
-            IpcCommand::Start => {
-                state.start();
-                tracing::info!("Daemon started via IPC");
-                serde_json::json!({ "success": true, "message": "Daemon started" })
-            }
+    #[tokio::test]
+    async fn test_get_version() {
+        let dir = tempdir().unwrap();
+        let state = test_state(dir.path());
+        let response = dispatch(r#"{"command":"GetVersion"}"#, &state).await;
+        assert_eq!(response["version"], env!("CARGO_PKG_VERSION"));
+        assert!(response["features"].is_array());
+    }
 
-            IpcCommand::Stop => {
-                state.stop();
-                tracing::info!("Daemon stopped via IPC");
-                serde_json::json!({ "success": true, "message": "Daemon stopped" })
-            }
+    #[tokio::test]
+    async fn test_cold_start_status_until_live() {
+        let dir = tempdir().unwrap();
+        let state = test_state(dir.path());
 
-            IpcCommand::SetConfig {
-                min_hz,
-                max_hz,
-                sensitivity,
-                adaptive_sensitivity,
-                fps_tolerance,
-                sync_frame_limiter,
-            } => {
-                let sensitivity_enum = match parse_sensitivity(&sensitivity) {
-                    Ok(s) => s,
-                    Err(e) => {
-                        return serde_json::json!({
-                            "success": false,
-                            "error": e.to_string()
-                        });
-                    }
-                };
+        let mut previous = state.get_status().await;
+        previous.current_fps = 58.0;
+        previous.current_hz = Hertz(60);
+        state.set_cold_start_status(previous).await;
 
-                let mut config = state.config_manager.get();
-                config.min_hz = min_hz;
-                config.max_hz = max_hz;
-                config.sensitivity = sensitivity_enum;
+        let status = state.get_status().await;
+        assert!(status.cached);
+        assert_eq!(status.current_hz, Hertz(60));
+        assert_eq!(status.current_fps, 58.0);
 
-                match state.config_manager.update(config) {
-                    Ok(()) => {
-                        let mut controller = state.controller.write().await;
-                        controller.set_user_range(min_hz, max_hz);
-                        controller.set_sensitivity(sensitivity_enum);
-                        if let Some(adaptive) = adaptive_sensitivity {
-                            controller.set_adaptive_sensitivity(adaptive);
-                        }
-                        if let Some(tolerance) = fps_tolerance {
-                            controller.set_fps_tolerance(tolerance);
-                        }
-                        if let Some(sync_fl) = sync_frame_limiter {
-                            controller.set_sync_frame_limiter(sync_fl);
-                        }
-                        tracing::info!(
-                            "Config updated via IPC: min_hz={}, max_hz={}, sensitivity={}",
-                            min_hz, max_hz, sensitivity
-                        );
-                        serde_json::json!({ "success": true, "message": "Configuration updated" })
-                    }
-                    Err(e) => {
-                        tracing::warn!("Failed to update config via IPC: {}", e);
-                        serde_json::json!({
-                            "success": false,
-                            "error": e.to_string()
-                        })
-                    }
-                }
-            }
+        state.mark_live().await;
+        let status = state.get_status().await;
+        assert!(!status.cached);
+        assert_eq!(status.current_fps, 0.0);
+    }
 
-            IpcCommand::SetAdvancedConfig {
-                fps_tolerance,
-                resume_cooldown_secs,
-                sync_frame_limiter,
-            } => {
-                let mut controller = state.controller.write().await;
-                
-                if let Some(tolerance) = fps_tolerance {
-                    controller.set_fps_tolerance(tolerance);
-                }
-                if let Some(cooldown) = resume_cooldown_secs {
-                    controller.set_resume_cooldown(cooldown);
-                }
-                if let Some(sync_fl) = sync_frame_limiter {
-                    controller.set_sync_frame_limiter(sync_fl);
-                }
-                
-                tracing::info!(
-                    "Advanced config updated: fps_tolerance={:?}, resume_cooldown={:?}, sync_frame_limiter={:?}",
-                    fps_tolerance, resume_cooldown_secs, sync_frame_limiter
-                );
-                
-                serde_json::json!({
-                    "success": true,
-                    "message": "Advanced configuration updated",
-                    "fps_tolerance": controller.fps_tolerance(),
-                    "sync_frame_limiter": controller.is_sync_frame_limiter_enabled()
-                })
-            }
+    #[tokio::test]
+    async fn test_set_subsystem_enabled() {
+        let dir = tempdir().unwrap();
+        let state = test_state(dir.path());
+        state.controller.write().await.set_external_display_detected(true);
 
-            IpcCommand::SetDeviceMode { mode } => {
-                let mode_enum = match parse_device_mode(&mode) {
-                    Ok(m) => m,
-                    Err(e) => {
-                        return serde_json::json!({
-                            "success": false,
-                            "error": e.to_string()
-                        });
-                    }
-                };
+        let response = dispatch(
+            r#"{"command":"SetSubsystemEnabled","name":"monitor_detection","enabled":false}"#,
+            &state,
+        )
+        .await;
+        assert_eq!(response["success"], true);
+        assert!(!state.subsystems.is_enabled(Subsystem::MonitorDetection));
+        assert!(!state.controller.read().await.is_external_display_detected());
 
-                let mut controller = state.controller.write().await;
-                controller.apply_mode_constraints(mode_enum);
-                
-                let effective_sens = sensitivity_to_string(controller.effective_sensitivity());
-                let min_interval = if mode_enum == DeviceMode::Lcd { 2000 } else { 500 };
-                
-                tracing::info!(
-                    "Device mode set via IPC: mode={}, min_change_interval={}ms",
-                    mode, min_interval
-                );
-                
-                serde_json::json!({
-                    "success": true,
-                    "message": format!("Device mode set to {}", mode),
-                    "mode": mode,
-                    "effective_sensitivity": effective_sens,
-                    "min_change_interval_ms": min_interval
-                })
-            }
+        dispatch(
+            r#"{"command":"SetSubsystemEnabled","name":"adaptive_sensitivity","enabled":true}"#,
+            &state,
+        )
+        .await;
+        let status = state.get_status().await;
+        assert!(!status.subsystems.monitor_detection);
+        assert!(status.subsystems.adaptive_sensitivity);
+        assert!(status.subsystems.battery);
 
-            IpcCommand::GetStatus => {
-                let status = state.get_status().await;
-                serde_json::to_value(status).unwrap_or_else(|e| {
-                    serde_json::json!({
-                        "error": format!("Failed to serialize status: {}", e)
-                    })
-                })
-            }
+        let response = dispatch(
+            r#"{"command":"SetSubsystemEnabled","name":"gpu","enabled":false}"#,
+            &state,
+        )
+        .await;
+        assert_eq!(response["success"], false);
+    }
 
-            IpcCommand::GetMetrics => {
-                let metrics = state.metrics.get_metrics();
-                serde_json::to_value(metrics).unwrap_or_else(|e| {
-                    serde_json::json!({
-                        "error": format!("Failed to serialize metrics: {}", e)
-                    })
-                })
-            }
+    #[tokio::test]
+    async fn test_experiments_toggle_and_capabilities() {
+        let dir = tempdir().unwrap();
+        let state = test_state(dir.path());
 
-            IpcCommand::SetGameId { app_id, name } => {
-                let mut profile_manager = state.profile_manager.write().await;
-                
-                let app_id_opt = if app_id.is_empty() || app_id == "0" {
-                    None
-                } else {
-                    Some(app_id.clone())
-                };
-                
-                profile_manager.set_current_game(app_id_opt.clone());
-                
-                // Apply profile settings if exists
-                if let Some(ref id) = app_id_opt {
-                    if let Some(profile) = profile_manager.get_profile(id) {
-                        let mut controller = state.controller.write().await;
-                        controller.set_user_range(profile.min_hz, profile.max_hz);
-                        controller.set_sensitivity(profile.get_sensitivity());
-                        controller.set_adaptive_sensitivity(profile.adaptive_sensitivity);
-                        
-                        tracing::info!("Applied profile for {} ({})", profile.name, id);
-                        return serde_json::json!({
-                            "success": true,
-                            "message": format!("Loaded profile for {}", profile.name),
-                            "profile_applied": true,
-                            "profile_name": profile.name
-                        });
-                    }
-                }
-                
-                // Revert to global defaults
-                let (min_hz, max_hz, sensitivity, adaptive) = profile_manager.get_current_settings();
-                let mut controller = state.controller.write().await;
-                controller.set_user_range(min_hz, max_hz);
-                controller.set_sensitivity(sensitivity);
-                controller.set_adaptive_sensitivity(adaptive);
-                
-                serde_json::json!({
-                    "success": true,
-                    "message": "Game ID updated, using global defaults",
-                    "profile_applied": false
-                })
-            }
+        let response = dispatch(
+            r#"{"command":"SetExperimentEnabled","name":"predictive_drop","enabled":true}"#,
+            &state,
+        )
+        .await;
+        assert_eq!(response["success"], true);
+        assert!(state.experiments.is_enabled(Experiment::PredictiveDrop));
+        assert!(state.config_manager.get().experimental.predictive_drop);
+        assert!(state.get_status().await.experimental.predictive_drop);
 
-            IpcCommand::SaveProfile {
-                app_id,
-                name,
-                min_hz,
-                max_hz,
-                sensitivity,
-                adaptive_sensitivity,
-            } => {
-                let profile = GameProfile {
-                    app_id: app_id.clone(),
-                    name: name.clone(),
-                    min_hz,
-                    max_hz,
-                    sensitivity,
-                    adaptive_sensitivity,
-                };
+        let capabilities = dispatch(r#"{"command":"GetCapabilities"}"#, &state).await;
+        assert_eq!(capabilities["build"]["version"], env!("CARGO_PKG_VERSION"));
+        assert_eq!(capabilities["subsystems"].as_array().unwrap().len(), Subsystem::ALL.len());
+        let experiments = capabilities["experiments"].as_array().unwrap();
+        assert_eq!(experiments.len(), Experiment::ALL.len());
+        assert!(experiments
+            .iter()
+            .any(|e| e["name"] == "predictive_drop" && e["enabled"] == true));
 
-                let mut profile_manager = state.profile_manager.write().await;
-                profile_manager.set_profile(profile);
-                
-                if let Err(e) = profile_manager.save() {
-                    tracing::warn!("Failed to save profiles: {}", e);
-                    return serde_json::json!({
-                        "success": false,
-                        "error": format!("Failed to save profile: {}", e)
-                    });
-                }
+        let response = dispatch(
+            r#"{"command":"SetExperimentEnabled","name":"warp_drive","enabled":true}"#,
+            &state,
+        )
+        .await;
+        assert_eq!(response["success"], false);
+    }
 
-                tracing::info!("Saved profile for {} ({})", name, app_id);
-                serde_json::json!({
-                    "success": true,
-                    "message": format!("Profile saved for {}", name)
-                })
-            }
+    #[tokio::test]
+    async fn test_set_display_backend() {
+        let dir = tempdir().unwrap();
+        let state = test_state(dir.path());
+        assert_eq!(state.get_status().await.display_backend, BackendKind::GamescopeCmd);
 
-            IpcCommand::DeleteProfile { app_id } => {
-                let mut profile_manager = state.profile_manager.write().await;
-                
-                if profile_manager.remove_profile(&app_id).is_some() {
-                    if let Err(e) = profile_manager.save() {
-                        tracing::warn!("Failed to save profiles after delete: {}", e);
-                    }
-                    serde_json::json!({
-                        "success": true,
-                        "message": "Profile deleted"
-                    })
-                } else {
-                    serde_json::json!({
-                        "success": false,
-                        "error": "Profile not found"
-                    })
-                }
-            }
+        let request = r#"{"command":"SetDisplayBackend","backend":"noop"}"#;
+        let response = dispatch(request, &state).await;
+        assert_eq!(response["success"], true);
+        assert_eq!(response["backend"], "noop");
+        assert_eq!(state.config_manager.get().display_backend, Some(BackendKind::Noop));
+        assert_eq!(state.get_status().await.display_backend, BackendKind::Noop);
 
-            IpcCommand::GetProfiles => {
-                let profile_manager = state.profile_manager.read().await;
-                let response = ProfileListResponse::from(&*profile_manager);
-                serde_json::to_value(response).unwrap_or_else(|e| {
-                    serde_json::json!({
-                        "error": format!("Failed to serialize profiles: {}", e)
-                    })
-                })
-            }
+        let request = r#"{"command":"SetDisplayBackend","backend":"auto"}"#;
+        let response = dispatch(request, &state).await;
+        assert_eq!(response["success"], true);
+        assert_eq!(state.config_manager.get().display_backend, None);
 
-            IpcCommand::GetBatteryStatus => {
-                let status = state.battery_monitor.get_status();
-                serde_json::to_value(status).unwrap_or_else(|e| {
-                    serde_json::json!({
-                        "error": format!("Failed to serialize battery status: {}", e)
-                    })
-                })
-            }
-        }
+        let request = r#"{"command":"SetDisplayBackend","backend":"xrandr"}"#;
+        let response = dispatch(request, &state).await;
+        assert_eq!(response["success"], false);
     }
-}
 
-#[cfg(unix)]
-impl Drop for IpcServer {
-    fn drop(&mut self) {
-        if self.socket_path.exists() {
-            let _ = std::fs::remove_file(&self.socket_path);
-        }
+    #[tokio::test]
+    async fn test_set_fps_source() {
+        let dir = tempdir().unwrap();
+        let state = test_state(dir.path());
+        assert_eq!(state.get_status().await.fps_source, None);
+
+        let request = r#"{"command":"SetFpsSource","source":"mangohud"}"#;
+        let response = dispatch(request, &state).await;
+        assert_eq!(response["success"], true);
+        assert_eq!(state.config_manager.get().fps_source, Some(FpsSourceKind::Mangohud));
+
+        state.set_fps_source(Some(FpsSourceKind::Mangohud));
+        let status = dispatch(r#"{"command":"GetStatus"}"#, &state).await;
+        assert_eq!(status["fps_source"], "mangohud");
+        assert_eq!(status["mangohud_available"], true);
+
+        let request = r#"{"command":"SetFpsSource","source":"auto"}"#;
+        dispatch(request, &state).await;
+        assert_eq!(state.config_manager.get().fps_source, None);
+
+        let request = r#"{"command":"SetFpsSource","source":"fraps"}"#;
+        let response = dispatch(request, &state).await;
+        assert_eq!(response["success"], false);
+    }
+
+    #[tokio::test]
+    async fn test_simulate_settings() {
+        let dir = tempdir().unwrap();
+        let state = test_state(dir.path());
+
+        let samples: Vec<f64> = std::iter::repeat_n(90.0, 50)
+            .chain(std::iter::repeat_n(50.0, 100))
+            .collect();
+        let request = serde_json::json!({
+            "command": "SimulateSettings",
+            "samples": samples,
+            "scenarios": [{"sensitivity": "aggressive"}, {"fps_tolerance": 5.0}]
+        });
+        let response = dispatch(&request.to_string(), &state).await;
+        assert_eq!(response["baseline"]["sensitivity"], "balanced");
+        assert_eq!(response["scenarios"][0]["sensitivity"], "aggressive");
+        assert_eq!(response["scenarios"][1]["fps_tolerance"], 5.0);
+        assert_eq!(response["baseline"]["duration_secs"], 15.0);
+        assert!(response["baseline"]["switches"].as_u64().unwrap() > 0);
+
+        let bad = r#"{"command":"SimulateSettings","samples":[60.0],"scenarios":[{"sensitivity":"custom"}]}"#;
+        assert!(dispatch(bad, &state).await.get("error").is_some());
+        let empty = r#"{"command":"SimulateSettings","samples":[]}"#;
+        assert!(dispatch(empty, &state).await.get("error").is_some());
+    }
+
+    #[tokio::test]
+    async fn test_stream_fps_rejected_outside_socket() {
+        let dir = tempdir().unwrap();
+        let state = test_state(dir.path());
+        let response = IpcServer::handle_remote_request(r#"{"command":"StreamFps"}"#, &state).await;
+        assert!(response.get("error").is_some());
+    }
+
+    #[tokio::test]
+    async fn test_batch_runs_commands_in_order() {
+        let dir = tempdir().unwrap();
+        let state = test_state(dir.path());
+
+        let request = r#"{"command":"Batch","commands":[
+            {"command":"GetStatus"},
+            {"command":"Bogus"},
+            {"command":"Batch","commands":[]},
+            {"command":"GetBatteryStatus"},
+            {"command":"GetMetrics"},
+            {"command":"GetPowerByHz"}
+        ]}"#;
+        let response = dispatch(request, &state).await;
+        let responses = response["responses"].as_array().unwrap();
+
+        assert_eq!(responses.len(), 6);
+        assert!(responses[0].get("current_hz").is_some());
+        assert!(responses[1]["error"].as_str().unwrap().starts_with("Invalid command"));
+        assert!(responses[2]["error"].as_str().unwrap().contains("Nested"));
+        assert!(responses[3].get("available").is_some());
+        assert!(responses[4].get("total_switches").is_some());
+        assert!(responses[5]["rates"].is_array());
+    }
+
+    #[tokio::test]
+    async fn test_batch_size_limit() {
+        let dir = tempdir().unwrap();
+        let state = test_state(dir.path());
+
+        let commands = vec![serde_json::json!({"command": "GetStatus"}); MAX_BATCH_COMMANDS + 1];
+        let response =
+            IpcServer::handle_command(IpcCommand::Batch { commands }, &state).await;
+        assert!(response.get("responses").is_none());
+        assert!(response["error"].as_str().unwrap().contains("exceeds"));
     }
 }
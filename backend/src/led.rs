@@ -0,0 +1,159 @@
+//! Optional status LED / RGB integration.
+//!
+//! Maps the daemon state to LED patterns on handhelds that expose them,
+//! either by writing configured values to sysfs files (e.g.
+//! `/sys/class/leds/*/brightness` or `multi_intensity`) or by running a
+//! command that receives the state in `SMART_REFRESH_LED_STATE`.
+
+use crate::config::LedConfig;
+use crate::units::Hertz;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::Duration;
+use tracing::{debug, warn};
+
+/// LED commands that run longer than this are killed
+const LED_COMMAND_TIMEOUT_SECS: u64 = 5;
+
+/// Daemon states shown on the LED.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LedState {
+    /// Running at the configured minimum rate
+    Eco,
+    /// Running between the minimum and maximum rate
+    Active,
+    /// Running at the configured maximum rate
+    Turbo,
+    /// Control stopped or paused for an external display
+    Paused,
+}
+
+impl LedState {
+    /// Name passed to the LED command.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LedState::Eco => "eco",
+            LedState::Active => "active",
+            LedState::Turbo => "turbo",
+            LedState::Paused => "paused",
+        }
+    }
+
+    /// Classify the current daemon state.
+    pub fn classify(paused: bool, hz: Hertz, min_hz: Hertz, max_hz: Hertz) -> Self {
+        if paused {
+            LedState::Paused
+        } else if hz <= min_hz {
+            LedState::Eco
+        } else if hz >= max_hz {
+            LedState::Turbo
+        } else {
+            LedState::Active
+        }
+    }
+}
+
+/// Applies LED patterns when the daemon state changes.
+#[derive(Default)]
+pub struct LedIndicator {
+    last: Mutex<Option<LedState>>,
+}
+
+impl LedIndicator {
+    /// Show `state` unless it is already shown. Returns true if applied.
+    pub fn update(&self, config: &LedConfig, state: LedState) -> bool {
+        {
+            let Ok(mut last) = self.last.lock() else {
+                return false;
+            };
+            if *last == Some(state) {
+                return false;
+            }
+            *last = Some(state);
+        }
+
+        debug!("LED state: {}", state.as_str());
+        for write in config.states.get(&state).into_iter().flatten() {
+            if let Err(e) = std::fs::write(&write.path, &write.value) {
+                warn!("Failed to write LED value to {:?}: {}", write.path, e);
+            }
+        }
+
+        if let Some(command) = config.command.as_deref().filter(|c| !c.trim().is_empty()) {
+            run_command(command, state);
+        }
+        true
+    }
+}
+
+fn run_command(command: &str, state: LedState) {
+    let child = tokio::process::Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .env("SMART_REFRESH_LED_STATE", state.as_str())
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .kill_on_drop(true)
+        .spawn();
+
+    match child {
+        Ok(mut child) => {
+            tokio::spawn(async move {
+                let timeout = Duration::from_secs(LED_COMMAND_TIMEOUT_SECS);
+                if tokio::time::timeout(timeout, child.wait()).await.is_err() {
+                    warn!("LED command timed out, killing it");
+                    let _ = child.kill().await;
+                }
+            });
+        }
+        Err(e) => warn!("Failed to run LED command: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SysfsWrite;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(LedState::classify(false, Hertz(40), Hertz(40), Hertz(90)), LedState::Eco);
+        assert_eq!(LedState::classify(false, Hertz(60), Hertz(40), Hertz(90)), LedState::Active);
+        assert_eq!(LedState::classify(false, Hertz(90), Hertz(40), Hertz(90)), LedState::Turbo);
+        assert_eq!(LedState::classify(true, Hertz(40), Hertz(40), Hertz(90)), LedState::Paused);
+    }
+
+    #[test]
+    fn test_update_writes_sysfs_values_on_change() {
+        let dir = tempdir().unwrap();
+        let brightness = dir.path().join("brightness");
+        let config = LedConfig {
+            command: None,
+            states: [
+                (LedState::Eco, vec![SysfsWrite { path: brightness.clone(), value: "10".to_string() }]),
+                (LedState::Turbo, vec![SysfsWrite { path: brightness.clone(), value: "255".to_string() }]),
+            ]
+            .into_iter()
+            .collect(),
+        };
+
+        let indicator = LedIndicator::default();
+        assert!(indicator.update(&config, LedState::Eco));
+        assert_eq!(std::fs::read_to_string(&brightness).unwrap(), "10");
+
+        // Unchanged state is not re-applied
+        std::fs::write(&brightness, "x").unwrap();
+        assert!(!indicator.update(&config, LedState::Eco));
+        assert_eq!(std::fs::read_to_string(&brightness).unwrap(), "x");
+
+        assert!(indicator.update(&config, LedState::Turbo));
+        assert_eq!(std::fs::read_to_string(&brightness).unwrap(), "255");
+
+        // Unmapped states change nothing on disk
+        assert!(indicator.update(&config, LedState::Active));
+        assert_eq!(std::fs::read_to_string(&brightness).unwrap(), "255");
+    }
+}
@@ -16,8 +16,6 @@ use tracing_subscriber::{
 
 /// Default log directory relative to user's home
 const LOG_DIR: &str = ".local/share/smart-refresh";
-/// Log file name
-const LOG_FILE: &str = "daemon.log";
 /// Maximum number of log files to retain
 const MAX_LOG_FILES: usize = 3;
 
@@ -147,6 +145,7 @@ impl std::error::Error for LoggingError {
 
 /// Format a log entry with all required fields for Property 9 validation.
 /// This is a helper for testing that log entries contain required fields.
+#[cfg_attr(not(test), allow(dead_code))]
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct LogEntry {
     /// ISO 8601 timestamp
@@ -166,6 +165,7 @@ pub struct LogEntry {
     pub line_number: Option<u32>,
 }
 
+#[cfg_attr(not(test), allow(dead_code))]
 impl LogEntry {
     /// Validate that the log entry contains all required fields per Requirements 10.1.
     /// Returns true if the entry has timestamp, level, and message.
@@ -194,6 +194,7 @@ impl LogEntry {
 }
 
 /// Parse a JSON log line into a LogEntry for validation.
+#[cfg_attr(not(test), allow(dead_code))]
 pub fn parse_log_entry(json_line: &str) -> Result<LogEntry, serde_json::Error> {
     // tracing-subscriber JSON format uses different field names
     // We need to map them to our LogEntry structure
@@ -11,85 +11,240 @@
 //! - Multi-monitor detection
 //! - Adaptive sensitivity
 
+mod cli;
+mod cli_docs;
+mod app_detect;
+#[cfg(unix)]
+mod bridge_token;
 mod config;
+#[cfg(test)]
+mod controller_snapshots;
 mod core_logic;
+mod deep_sleep;
+mod device;
+mod diagnostics;
+mod display_backend;
 mod display_control;
+mod drm_flips;
+mod drm_modes;
+mod edid;
 mod error;
+mod experimental;
+mod feedback;
 mod fps_monitor;
+mod fps_source;
+mod gamescope_stats;
+mod history;
+mod hooks;
+mod hotplug;
+mod install_service;
+#[cfg(all(unix, feature = "http"))]
+mod http_api;
 mod ipc_server;
+mod led;
 mod logging;
+mod mangohud_log;
+mod mangohud_overlay;
 mod metrics;
 mod profiles;
+mod recording;
 mod battery;
+mod build_info;
 mod monitor_detect;
+mod notifications;
+mod persist;
+mod session;
+#[cfg(feature = "mqtt")]
+mod mqtt;
+mod shutdown;
+mod system_update;
+mod status_page;
+mod status_snapshot;
+mod subsystems;
+mod suggestion;
+mod switch_budget;
+mod thermal;
+mod trends;
+mod units;
+mod utilization;
+mod vulkan_layer;
+mod whatif;
+#[cfg(unix)]
+mod websocket;
 
+use build_info::BuildInfo;
+use clap::Parser;
+use cli::CliArgs;
 use config::ConfigManager;
 use display_control::DisplayManager;
-use fps_monitor::MangoHudReader;
+use experimental::Experiment;
+use fps_monitor::{AdaptivePoller, EmaSmoother, FpsSmoothing, StaleDetector};
 use ipc_server::DaemonState;
+use led::{LedIndicator, LedState};
+use notifications::EventKind;
 use metrics::MetricsCollector;
 use profiles::ProfileManager;
 use battery::BatteryMonitor;
-use monitor_detect::MonitorDetector;
+use monitor_detect::{DisplayDebouncer, MonitorDetector};
+use shutdown::{ShutdownManager, ShutdownPhase};
+use subsystems::Subsystem;
+use trends::TrendStore;
+use units::{Hertz, Secs};
 
 #[cfg(unix)]
 use ipc_server::IpcServer;
 
 use std::panic::AssertUnwindSafe;
-use std::sync::atomic::Ordering;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::sync::watch;
 use tracing::{error, info, warn, debug};
 
 /// FPS polling interval in milliseconds
 const FPS_POLL_INTERVAL_MS: u64 = 100;
 
-/// Retry interval for MangoHud connection in seconds
+/// FPS polling interval once FPS has been steady for a while (adaptive polling)
+const STABLE_POLL_INTERVAL_MS: u64 = 500;
+
+/// Retry interval for MangoHud connection in seconds (fallback when
+/// `/dev/shm` can't be watched)
 const SHM_RETRY_INTERVAL_SECS: u64 = 5;
 
-/// Graceful shutdown timeout in seconds
+/// Wait between an FPS segment appearing and connecting, so its writer
+/// has sized it
+const SHM_ATTACH_DELAY_MS: u64 = 100;
+
+/// Time budget shared by the drain and persist shutdown phases in seconds
 const SHUTDOWN_TIMEOUT_SECS: u64 = 2;
 
-/// Monitor detection interval in seconds
+/// Time reserved for restoring the exit refresh rate in seconds, so a
+/// stalled earlier phase cannot leave the panel at the game's rate
+const SHUTDOWN_RESTORE_TIMEOUT_SECS: u64 = 2;
+
+/// Monitor detection polling interval in seconds (a fallback for hotplug events)
 const MONITOR_CHECK_INTERVAL_SECS: u64 = 10;
 
+/// Session (game mode / desktop mode) detection interval in seconds
+const SESSION_CHECK_INTERVAL_SECS: u64 = 10;
+
+/// Screen recording check interval in seconds
+const RECORDING_CHECK_INTERVAL_SECS: u64 = 2;
+
+/// OS update check interval in seconds
+const UPDATE_CHECK_INTERVAL_SECS: u64 = 5;
+
+/// Steam overlay check interval in milliseconds
+const OVERLAY_CHECK_INTERVAL_MS: u64 = 500;
+
+/// Running game detection interval in seconds
+const APP_DETECT_INTERVAL_SECS: u64 = 2;
+
 /// Battery polling interval in seconds
 const BATTERY_POLL_INTERVAL_SECS: u64 = 5;
 
+/// APU temperature poll interval in seconds
+const THERMAL_POLL_INTERVAL_SECS: u64 = 2;
+
+/// GPU/CPU utilization poll interval in seconds
+const UTILIZATION_POLL_INTERVAL_SECS: u64 = 1;
+
+/// Interval for reading back the panel's refresh rate, in seconds
+const EXTERNAL_CHANGE_POLL_INTERVAL_SECS: u64 = 2;
+
+/// Deep-sleep idle check interval in seconds
+const DEEP_SLEEP_CHECK_INTERVAL_SECS: u64 = 10;
+
+/// Directory where MangoHud creates its shared memory
+const SHM_DIR: &str = "/dev/shm";
+
+/// Status LED update interval in seconds
+const LED_UPDATE_INTERVAL_SECS: u64 = 1;
+
+/// MangoHud overlay line update interval in milliseconds
+const MANGOHUD_OVERLAY_INTERVAL_MS: u64 = 500;
+
+/// Trend sampling interval in seconds
+const TREND_SAMPLE_INTERVAL_SECS: u64 = 60;
+
+/// Trend samples between saves and drift analysis
+const TREND_SAMPLES_PER_SAVE: u32 = 10;
+
+/// Status snapshot check interval in seconds (writes are debounced further)
+const STATUS_SNAPSHOT_INTERVAL_SECS: u64 = 5;
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let cli = CliArgs::parse();
+    if cli.version {
+        println!("{}", BuildInfo::current());
+        return Ok(());
+    }
+    if let Some(kind) = cli.generate {
+        print!("{}", cli_docs::render(kind));
+        return Ok(());
+    }
+    if cli.command == Some(cli::CliCommand::InstallService) {
+        if let Err(e) = install_service::run() {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
     // Initialize logging
-    let _log_guard = logging::init_logging().map_err(|e| {
+    let log_guard = logging::init_logging().map_err(|e| {
         eprintln!("Failed to initialize logging: {}", e);
         e
     })?;
 
-    info!("SmartRefresh daemon v2.0 starting...");
+    info!("{} starting...", BuildInfo::current());
+
+    if cli.strict {
+        let config = ConfigManager::load_or_default(&ConfigManager::default_path())
+            .map(|manager| manager.get())
+            .unwrap_or_default();
+        if let Err(e) = diagnostics::run_strict_checks(&config, cli.shm_timeout_secs).await {
+            error!("Strict startup check failed: {}", e);
+            drop(log_guard);
+            std::process::exit(e.exit_code());
+        }
+    }
 
-    let result = run_daemon().await;
+    let result = run_daemon(cli).await;
 
     match &result {
         Ok(()) => info!("SmartRefresh daemon shut down gracefully"),
         Err(e) => error!("SmartRefresh daemon error: {}", e),
     }
 
+    // Final shutdown step: flush and close the log writers
+    drop(log_guard);
+
     result
 }
 
 /// Main daemon entry point with panic recovery.
-async fn run_daemon() -> Result<(), Box<dyn std::error::Error>> {
+async fn run_daemon(cli: CliArgs) -> Result<(), Box<dyn std::error::Error>> {
     // Load configuration
     let config_path = ConfigManager::default_path();
     let config_manager = Arc::new(ConfigManager::load_or_default(&config_path)?);
     info!("Configuration loaded from {:?}", config_path);
 
     let config = config_manager.get();
+    let experiments = config.experimental.enabled();
+    if !experiments.is_empty() {
+        warn!("Experimental features enabled: {}", experiments.join(", "));
+    }
 
-    // Load profile manager
-    let profile_manager = Arc::new(tokio::sync::RwLock::new(
-        ProfileManager::load_or_default().unwrap_or_default()
-    ));
+    let socket_path = ipc_server::resolve_socket_path(
+        cli.socket_path.as_deref(),
+        config.socket_path.as_deref(),
+    );
+
+    // Load profile manager; starting with empty profiles would let the next
+    // save overwrite the file, so a load failure stops startup
+    let profile_manager = Arc::new(tokio::sync::RwLock::new(ProfileManager::load_or_default()?));
     info!("Profile manager initialized");
 
     // Create metrics collector
@@ -98,9 +253,6 @@ async fn run_daemon() -> Result<(), Box<dyn std::error::Error>> {
     // Create battery monitor
     let battery_monitor = Arc::new(BatteryMonitor::new());
 
-    // Create monitor detector
-    let monitor_detector = Arc::new(MonitorDetector::new());
-
     // Create shared daemon state
     let daemon_state = Arc::new(DaemonState::new(
         Arc::clone(&config_manager),
@@ -108,17 +260,73 @@ async fn run_daemon() -> Result<(), Box<dyn std::error::Error>> {
         Arc::clone(&metrics),
         Arc::clone(&battery_monitor),
     ));
+    daemon_state.set_poll_interval(Duration::from_millis(FPS_POLL_INTERVAL_MS));
+
+    // Presets for the handheld model (configured one, else from DMI)
+    let device_model = config
+        .device_model
+        .unwrap_or_else(|| device::DeviceModel::detect(Path::new(device::DMI_DIR)));
+    daemon_state.set_device_model(device_model).await;
+
+    // Pick the refresh rate backend (configured one, else auto-detect)
+    let display_manager = Arc::clone(&daemon_state.display);
+    let backend = daemon_state.select_display_backend(config.display_backend).await;
+    if backend == display_backend::BackendKind::Noop {
+        warn!("No usable display backend found, refresh rate changes will have no effect");
+    }
+
+    // Remember the rate to leave the panel at when we exit, even by panicking
+    match display_manager.capture_original_rate().await {
+        Some(hz) => info!("Original refresh rate: {}Hz", hz),
+        None => debug!("Original refresh rate unknown, the configured max is restored on exit"),
+    }
+    let _restore_on_panic = RestoreOnPanic {
+        display: Arc::clone(&display_manager),
+        config_manager: Arc::clone(&config_manager),
+    };
+
+    // The previous run's status also records the rate it ended at
+    let snapshot_path = status_snapshot::default_path();
+    let previous_status = status_snapshot::load(&snapshot_path);
+
+    // Identify the internal panel from its EDID
+    match edid::PanelInfo::detect(Path::new(utilization::DRM_DIR)) {
+        Some(panel) => {
+            info!(
+                "Panel: {} {} on {}, range {:?}-{:?}Hz",
+                panel.make,
+                panel.name.as_deref().unwrap_or("(unnamed)"),
+                panel.connector,
+                panel.min_hz,
+                panel.max_hz
+            );
+            daemon_state.set_panel(panel).await;
+        }
+        None => debug!("No internal panel EDID found"),
+    }
+
+    // Startup rate, once the panel's range is known
+    let startup_rate = config_manager.get().startup_rate;
+    let last_session_hz = previous_status.as_ref().map(|status| status.current_hz);
+    match display_manager.apply_startup_rate(startup_rate, last_session_hz).await {
+        Ok(Some(hz)) => {
+            info!("Startup refresh rate: {}Hz ({:?})", hz, startup_rate);
+            daemon_state.set_current_hz(hz);
+        }
+        Ok(None) => info!("Leaving the refresh rate untouched until the first decision"),
+        Err(e) => warn!("Failed to apply the startup refresh rate: {}", e),
+    }
 
-    // Create display manager with configured range
-    let display_manager = Arc::new(DisplayManager::new(config.min_hz, config.max_hz));
+    // Create shutdown trigger channel (fired by signal handlers)
+    let (shutdown_tx, mut shutdown_trigger_rx) = watch::channel(false);
 
-    // Create shutdown signal channel
-    let (shutdown_tx, shutdown_rx) = watch::channel(false);
+    // Each task stops in the shutdown phase it belongs to
+    let mut shutdown = ShutdownManager::new(Duration::from_secs(SHUTDOWN_TIMEOUT_SECS));
+    shutdown.reserve(ShutdownPhase::RestoreDisplay, Duration::from_secs(SHUTDOWN_RESTORE_TIMEOUT_SECS));
 
     // Set up signal handlers
-    let shutdown_tx_clone = shutdown_tx.clone();
     tokio::spawn(async move {
-        if let Err(e) = setup_signal_handlers(shutdown_tx_clone).await {
+        if let Err(e) = setup_signal_handlers(shutdown_tx).await {
             error!("Signal handler error: {}", e);
         }
     });
@@ -127,70 +335,301 @@ async fn run_daemon() -> Result<(), Box<dyn std::error::Error>> {
     #[cfg(unix)]
     {
         let dbus_state = Arc::clone(&daemon_state);
-        let dbus_shutdown_rx = shutdown_rx.clone();
-        tokio::spawn(async move {
+        let dbus_shutdown_rx = shutdown.subscribe(ShutdownPhase::FlushDisplay);
+        let dbus_handle = tokio::spawn(async move {
             run_dbus_monitor(dbus_state, dbus_shutdown_rx).await;
         });
+        shutdown.register(ShutdownPhase::FlushDisplay, "dbus_monitor", dbus_handle);
     }
 
+    // Bridge token, shared by the TCP bridges and written next to the socket
+    #[cfg(unix)]
+    let bridge_token = if config.websocket_port.is_some()
+        || (cfg!(feature = "http") && config.http_port.is_some())
+    {
+        create_bridge_token(&socket_path)
+    } else {
+        None
+    };
+
     // Spawn IPC server task
     let ipc_state = Arc::clone(&daemon_state);
-    let ipc_shutdown_rx = shutdown_rx.clone();
+    let ipc_shutdown_rx = shutdown.subscribe(ShutdownPhase::StopIpc);
     let ipc_handle = tokio::spawn(async move {
-        run_ipc_server_with_panic_catch(ipc_state, ipc_shutdown_rx).await
+        run_ipc_server_with_panic_catch(ipc_state, socket_path, ipc_shutdown_rx).await
     });
+    shutdown.register(ShutdownPhase::StopIpc, "ipc_server", ipc_handle);
+
+    // Spawn WebSocket bridge
+    #[cfg(unix)]
+    if let (Some(port), Some(token)) = (config.websocket_port, bridge_token.clone()) {
+        let ws_state = Arc::clone(&daemon_state);
+        let mut ws_shutdown_rx = shutdown.subscribe(ShutdownPhase::StopIpc);
+        let ws_handle = tokio::spawn(async move {
+            tokio::select! {
+                _ = ws_shutdown_rx.changed() => info!("WebSocket bridge shutting down"),
+                result = websocket::run_websocket_server(ws_state, token, port) => {
+                    if let Err(e) = result {
+                        error!("WebSocket bridge error: {}", e);
+                    }
+                }
+            }
+        });
+        shutdown.register(ShutdownPhase::StopIpc, "websocket_bridge", ws_handle);
+    }
+
+    // Spawn HTTP API
+    #[cfg(all(unix, feature = "http"))]
+    if let (Some(port), Some(token)) = (config.http_port, bridge_token.clone()) {
+        let http_state = Arc::clone(&daemon_state);
+        let mut http_shutdown_rx = shutdown.subscribe(ShutdownPhase::StopIpc);
+        let http_handle = tokio::spawn(async move {
+            tokio::select! {
+                _ = http_shutdown_rx.changed() => info!("HTTP API shutting down"),
+                result = http_api::run_http_server(http_state, token, port) => {
+                    if let Err(e) = result {
+                        error!("HTTP API error: {}", e);
+                    }
+                }
+            }
+        });
+        shutdown.register(ShutdownPhase::StopIpc, "http_api", http_handle);
+    }
+    #[cfg(not(all(unix, feature = "http")))]
+    if config.http_port.is_some() {
+        warn!("HTTP API configured but daemon was built without the 'http' feature");
+    }
 
     // Spawn FPS polling task
     let fps_state = Arc::clone(&daemon_state);
-    let fps_shutdown_rx = shutdown_rx.clone();
+    let fps_shutdown_rx = shutdown.subscribe(ShutdownPhase::FlushDisplay);
     let fps_handle = tokio::spawn(async move {
         run_fps_polling_with_panic_catch(fps_state, fps_shutdown_rx).await
     });
+    shutdown.register(ShutdownPhase::FlushDisplay, "fps_polling", fps_handle);
 
     // Spawn core logic task
     let logic_state = Arc::clone(&daemon_state);
     let logic_display = Arc::clone(&display_manager);
     let logic_metrics = Arc::clone(&metrics);
-    let logic_shutdown_rx = shutdown_rx.clone();
+    let logic_shutdown_rx = shutdown.subscribe(ShutdownPhase::FlushDisplay);
     let logic_handle = tokio::spawn(async move {
         run_core_logic_with_panic_catch(logic_state, logic_display, logic_metrics, logic_shutdown_rx).await
     });
+    shutdown.register(ShutdownPhase::FlushDisplay, "core_logic", logic_handle);
+
+    // Spawn external refresh rate change detection
+    let resync_state = Arc::clone(&daemon_state);
+    let resync_display = Arc::clone(&display_manager);
+    let resync_shutdown_rx = shutdown.subscribe(ShutdownPhase::FlushDisplay);
+    let resync_handle = tokio::spawn(async move {
+        run_external_change_detection(resync_state, resync_display, resync_shutdown_rx).await
+    });
+    shutdown.register(ShutdownPhase::FlushDisplay, "external_change_detection", resync_handle);
 
     // Spawn monitor detection task
     let monitor_state = Arc::clone(&daemon_state);
-    let monitor_detector_clone = Arc::clone(&monitor_detector);
-    let monitor_shutdown_rx = shutdown_rx.clone();
-    tokio::spawn(async move {
+    let monitor_detector_clone = Arc::clone(&daemon_state.monitor_detector);
+    let monitor_shutdown_rx = shutdown.subscribe(ShutdownPhase::FlushDisplay);
+    let monitor_handle = tokio::spawn(async move {
         run_monitor_detection(monitor_state, monitor_detector_clone, monitor_shutdown_rx).await
     });
+    shutdown.register(ShutdownPhase::FlushDisplay, "monitor_detection", monitor_handle);
+
+    // Spawn session detection task
+    let session_state = Arc::clone(&daemon_state);
+    let session_shutdown_rx = shutdown.subscribe(ShutdownPhase::FlushDisplay);
+    let session_handle = tokio::spawn(async move {
+        run_session_detection(session_state, session_shutdown_rx).await
+    });
+    shutdown.register(ShutdownPhase::FlushDisplay, "session_detection", session_handle);
+
+    // Spawn screen recording detection task
+    let recording_state = Arc::clone(&daemon_state);
+    let recording_shutdown_rx = shutdown.subscribe(ShutdownPhase::FlushDisplay);
+    let recording_handle = tokio::spawn(async move {
+        run_recording_detection(recording_state, recording_shutdown_rx).await
+    });
+    shutdown.register(ShutdownPhase::FlushDisplay, "recording_detection", recording_handle);
+
+    // Spawn OS update detection task
+    let update_state = Arc::clone(&daemon_state);
+    let update_shutdown_rx = shutdown.subscribe(ShutdownPhase::FlushDisplay);
+    let update_handle = tokio::spawn(async move {
+        run_update_detection(update_state, update_shutdown_rx).await
+    });
+    shutdown.register(ShutdownPhase::FlushDisplay, "update_detection", update_handle);
+
+    // Spawn Steam overlay detection task
+    let overlay_state = Arc::clone(&daemon_state);
+    let overlay_display = Arc::clone(&display_manager);
+    let overlay_shutdown_rx = shutdown.subscribe(ShutdownPhase::FlushDisplay);
+    let overlay_handle = tokio::spawn(async move {
+        run_overlay_detection(overlay_state, overlay_display, overlay_shutdown_rx).await
+    });
+    shutdown.register(ShutdownPhase::FlushDisplay, "overlay_detection", overlay_handle);
+
+    // Spawn running game detection task
+    let app_state = Arc::clone(&daemon_state);
+    let app_display = Arc::clone(&display_manager);
+    let app_shutdown_rx = shutdown.subscribe(ShutdownPhase::FlushDisplay);
+    let app_handle = tokio::spawn(async move {
+        run_app_detection(app_state, app_display, app_shutdown_rx).await
+    });
+    shutdown.register(ShutdownPhase::FlushDisplay, "app_detection", app_handle);
 
     // Spawn battery monitoring task
     let battery_state = Arc::clone(&daemon_state);
     let battery_monitor_clone = Arc::clone(&battery_monitor);
-    let battery_shutdown_rx = shutdown_rx.clone();
-    tokio::spawn(async move {
+    let battery_shutdown_rx = shutdown.subscribe(ShutdownPhase::FlushDisplay);
+    let battery_handle = tokio::spawn(async move {
         run_battery_monitoring(battery_state, battery_monitor_clone, battery_shutdown_rx).await
     });
+    shutdown.register(ShutdownPhase::FlushDisplay, "battery_monitoring", battery_handle);
+
+    // Spawn thermal monitoring task
+    let thermal_state = Arc::clone(&daemon_state);
+    let thermal_shutdown_rx = shutdown.subscribe(ShutdownPhase::FlushDisplay);
+    let thermal_handle = tokio::spawn(async move {
+        run_thermal_monitoring(thermal_state, thermal_shutdown_rx).await
+    });
+    shutdown.register(ShutdownPhase::FlushDisplay, "thermal_monitoring", thermal_handle);
+
+    // Spawn GPU/CPU utilization sampling task
+    let utilization_state = Arc::clone(&daemon_state);
+    let utilization_shutdown_rx = shutdown.subscribe(ShutdownPhase::FlushDisplay);
+    let utilization_handle = tokio::spawn(async move {
+        run_utilization_monitoring(utilization_state, utilization_shutdown_rx).await
+    });
+    shutdown.register(ShutdownPhase::FlushDisplay, "utilization_monitoring", utilization_handle);
+
+    // Spawn deep-sleep supervisor
+    let sleep_state = Arc::clone(&daemon_state);
+    let sleep_shutdown_rx = shutdown.subscribe(ShutdownPhase::FlushDisplay);
+    let sleep_handle = tokio::spawn(async move {
+        run_deep_sleep_supervisor(sleep_state, sleep_shutdown_rx).await
+    });
+    shutdown.register(ShutdownPhase::FlushDisplay, "deep_sleep", sleep_handle);
+
+    // Spawn long-term trend tracking (flushes history in the persist phase)
+    let trend_state = Arc::clone(&daemon_state);
+    let trend_shutdown_rx = shutdown.subscribe(ShutdownPhase::PersistState);
+    let trend_handle = tokio::spawn(async move {
+        let store = TrendStore::load_or_default(&TrendStore::default_path());
+        run_trend_tracking(trend_state, store, trend_shutdown_rx).await
+    });
+    shutdown.register(ShutdownPhase::PersistState, "trend_tracking", trend_handle);
+
+    // Spawn FPS/Hz history sampling for the UI sparkline
+    let history_state = Arc::clone(&daemon_state);
+    let history_shutdown_rx = shutdown.subscribe(ShutdownPhase::StopIpc);
+    let history_handle = tokio::spawn(async move {
+        run_history_sampling(history_state, history_shutdown_rx).await
+    });
+    shutdown.register(ShutdownPhase::StopIpc, "history_sampling", history_handle);
+
+    // Serve the previous run's status until live data arrives, and keep the
+    // snapshot current for the next start
+    if let Some(previous) = previous_status {
+        daemon_state.set_cold_start_status(previous).await;
+    }
+    let snapshot_state = Arc::clone(&daemon_state);
+    let snapshot_shutdown_rx = shutdown.subscribe(ShutdownPhase::PersistState);
+    let snapshot_handle = tokio::spawn(async move {
+        let writer = status_snapshot::SnapshotWriter::new(snapshot_path);
+        run_status_snapshots(snapshot_state, writer, snapshot_shutdown_rx).await
+    });
+    shutdown.register(ShutdownPhase::PersistState, "status_snapshot", snapshot_handle);
+
+    // Spawn status LED integration
+    if config.led.is_some() {
+        let led_state = Arc::clone(&daemon_state);
+        let led_shutdown_rx = shutdown.subscribe(ShutdownPhase::FlushDisplay);
+        let led_handle = tokio::spawn(async move {
+            run_led_indicator(led_state, led_shutdown_rx).await
+        });
+        shutdown.register(ShutdownPhase::FlushDisplay, "led_indicator", led_handle);
+    }
+
+    // Spawn the MangoHud overlay line writer (idle while switched off)
+    let overlay_line_state = Arc::clone(&daemon_state);
+    let overlay_line_shutdown_rx = shutdown.subscribe(ShutdownPhase::FlushDisplay);
+    let overlay_line_handle = tokio::spawn(async move {
+        run_mangohud_overlay(overlay_line_state, overlay_line_shutdown_rx).await
+    });
+    shutdown.register(ShutdownPhase::FlushDisplay, "mangohud_overlay", overlay_line_handle);
+
+    // Spawn MQTT status publisher
+    #[cfg(feature = "mqtt")]
+    if let Some(mqtt_config) = config.mqtt.clone() {
+        let mqtt_state = Arc::clone(&daemon_state);
+        let mqtt_shutdown_rx = shutdown.subscribe(ShutdownPhase::StopIpc);
+        let mqtt_handle = tokio::spawn(async move {
+            mqtt::run_mqtt_publisher(mqtt_state, mqtt_config, mqtt_shutdown_rx).await
+        });
+        shutdown.register(ShutdownPhase::StopIpc, "mqtt_publisher", mqtt_handle);
+    }
+    #[cfg(not(feature = "mqtt"))]
+    if config.mqtt.is_some() {
+        warn!("MQTT configured but daemon was built without the 'mqtt' feature");
+    }
 
     info!("SmartRefresh daemon v2.0 initialized and running");
 
     // Wait for shutdown signal
-    let mut shutdown_rx_main = shutdown_rx.clone();
-    shutdown_rx_main.changed().await.ok();
+    shutdown_trigger_rx.changed().await.ok();
 
     info!("Shutdown signal received, stopping tasks...");
 
-    // Give tasks time to shut down gracefully
-    let shutdown_timeout = Duration::from_secs(SHUTDOWN_TIMEOUT_SECS);
-    let _ = tokio::time::timeout(shutdown_timeout, async {
-        let _ = tokio::join!(ipc_handle, fps_handle, logic_handle);
-    })
-    .await;
+    shutdown.run_phase(ShutdownPhase::StopIpc, async {}).await;
+    shutdown.run_phase(ShutdownPhase::FlushDisplay, async {}).await;
+    shutdown
+        .run_phase(ShutdownPhase::PersistState, async {
+            if let Err(e) = config_manager.save() {
+                warn!("Failed to persist configuration on shutdown: {}", e);
+            }
+        })
+        .await;
+    shutdown
+        .run_phase(ShutdownPhase::RestoreDisplay, async {
+            match display_manager.restore_refresh_rate(config_manager.get().max_hz).await {
+                Ok(hz) => info!("Restored refresh rate to {}Hz on exit", hz),
+                Err(e) => warn!("Failed to restore refresh rate on exit: {}", e),
+            }
+        })
+        .await;
 
     info!("All tasks stopped");
     Ok(())
 }
 
+/// Restores the exit refresh rate when `run_daemon` unwinds from a panic,
+/// which skips the RestoreDisplay shutdown phase.
+struct RestoreOnPanic {
+    display: Arc<DisplayManager>,
+    config_manager: Arc<ConfigManager>,
+}
+
+impl Drop for RestoreOnPanic {
+    fn drop(&mut self) {
+        if !std::thread::panicking() {
+            return;
+        }
+        let display = Arc::clone(&self.display);
+        let fallback = self.config_manager.get().max_hz;
+        // The current runtime can't be blocked on from inside it
+        let restore = std::thread::spawn(move || {
+            let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build()?;
+            runtime.block_on(display.restore_refresh_rate(fallback)).map_err(std::io::Error::other)
+        });
+        match restore.join() {
+            Ok(Ok(hz)) => info!("Restored refresh rate to {}Hz after panic", hz),
+            Ok(Err(e)) => error!("Failed to restore refresh rate after panic: {}", e),
+            Err(_) => error!("Failed to restore refresh rate after panic"),
+        }
+    }
+}
+
 /// Set up signal handlers for graceful shutdown.
 #[cfg(unix)]
 async fn setup_signal_handlers(
@@ -230,8 +669,6 @@ async fn run_dbus_monitor(
     state: Arc<DaemonState>,
     mut shutdown_rx: watch::Receiver<bool>,
 ) {
-    use zbus::Connection;
-
     info!("Starting D-Bus suspend/resume monitor");
 
     loop {
@@ -258,10 +695,7 @@ async fn monitor_sleep_signals(state: &Arc<DaemonState>) -> Result<(), Box<dyn s
     use futures_util::StreamExt;
 
     let connection = Connection::system().await?;
-    
-    // Subscribe to PrepareForSleep signal
-    let proxy = zbus::fdo::DBusProxy::new(&connection).await?;
-    
+
     // Use match rule for login1 PrepareForSleep
     connection.call_method(
         Some("org.freedesktop.DBus"),
@@ -278,21 +712,19 @@ async fn monitor_sleep_signals(state: &Arc<DaemonState>) -> Result<(), Box<dyn s
     
     while let Some(msg) = stream.next().await {
         if let Ok(msg) = msg {
-            if msg.member().map(|m| m.as_str()) == Some("PrepareForSleep") {
+            if msg.header().member().map(|m| m.as_str()) == Some("PrepareForSleep") {
                 // Parse the boolean argument (true = going to sleep, false = waking up)
-                if let Ok(body) = msg.body() {
-                    let going_to_sleep: bool = body.deserialize()?;
-                    
-                    if going_to_sleep {
-                        info!("System going to sleep");
-                    } else {
-                        info!("System waking up - resetting hysteresis state");
-                        // Reset controller state on resume
-                        let mut controller = state.controller.write().await;
-                        controller.reset_state();
-                        drop(controller);
-                        info!("Hysteresis controller reset after resume");
-                    }
+                let going_to_sleep: bool = msg.body().deserialize()?;
+
+                if going_to_sleep {
+                    info!("System going to sleep");
+                } else {
+                    info!("System waking up - resetting hysteresis state");
+                    // Reset controller state on resume
+                    let mut controller = state.controller.write().await;
+                    controller.reset_state();
+                    drop(controller);
+                    info!("Hysteresis controller reset after resume");
                 }
             }
         }
@@ -301,9 +733,32 @@ async fn monitor_sleep_signals(state: &Arc<DaemonState>) -> Result<(), Box<dyn s
     Ok(())
 }
 
+/// Generate the bridge token and write it next to the IPC socket.
+///
+/// Returns None (bridges stay off) if the token can't be written.
+#[cfg(unix)]
+fn create_bridge_token(socket_path: &Path) -> Option<Arc<bridge_token::BridgeToken>> {
+    let path = bridge_token::token_path_for(socket_path);
+    let token = bridge_token::BridgeToken::generate().and_then(|token| {
+        token.write_to(&path)?;
+        Ok(token)
+    });
+    match token {
+        Ok(token) => {
+            info!("Bridge token written to {:?}", path);
+            Some(Arc::new(token))
+        }
+        Err(e) => {
+            error!("Failed to write bridge token to {:?}, TCP bridges disabled: {}", path, e);
+            None
+        }
+    }
+}
+
 /// Run IPC server with panic catching
 async fn run_ipc_server_with_panic_catch(
     state: Arc<DaemonState>,
+    socket_path: PathBuf,
     mut shutdown_rx: watch::Receiver<bool>,
 ) {
     loop {
@@ -314,7 +769,7 @@ async fn run_ipc_server_with_panic_catch(
                     break;
                 }
             }
-            result = run_ipc_server_inner(Arc::clone(&state)) => {
+            result = run_ipc_server_inner(Arc::clone(&state), &socket_path) => {
                 match result {
                     Ok(()) => break,
                     Err(e) => {
@@ -328,14 +783,15 @@ async fn run_ipc_server_with_panic_catch(
 }
 
 #[cfg(unix)]
-async fn run_ipc_server_inner(state: Arc<DaemonState>) -> Result<(), error::IpcError> {
-    let server = IpcServer::new_default().await?;
+async fn run_ipc_server_inner(state: Arc<DaemonState>, socket_path: &Path) -> Result<(), error::IpcError> {
+    let allow_other_users = !state.config_manager.get().allowed_uids.is_empty();
+    let server = IpcServer::new(socket_path, allow_other_users).await?;
     info!("IPC server listening on {:?}", server.socket_path());
     server.run(state).await
 }
 
 #[cfg(not(unix))]
-async fn run_ipc_server_inner(_state: Arc<DaemonState>) -> Result<(), error::IpcError> {
+async fn run_ipc_server_inner(_state: Arc<DaemonState>, _socket_path: &Path) -> Result<(), error::IpcError> {
     warn!("IPC server not available on this platform");
     loop {
         tokio::time::sleep(Duration::from_secs(3600)).await;
@@ -347,23 +803,40 @@ async fn run_fps_polling_with_panic_catch(
     state: Arc<DaemonState>,
     mut shutdown_rx: watch::Receiver<bool>,
 ) {
-    let poll_interval = Duration::from_millis(FPS_POLL_INTERVAL_MS);
+    let fast_interval = Duration::from_millis(FPS_POLL_INTERVAL_MS);
+    let stable_interval = Duration::from_millis(STABLE_POLL_INTERVAL_MS);
     let retry_interval = Duration::from_secs(SHM_RETRY_INTERVAL_SECS);
 
     loop {
-        if *shutdown_rx.borrow() {
+        if !state.sleep.wait_until_awake(&mut shutdown_rx).await {
             info!("FPS polling shutting down");
             break;
         }
-
-        // Try to connect to MangoHud shared memory with fallback
-        let reader = match MangoHudReader::new() {
-            Ok(r) => Some(r),
+        state.set_poll_interval(fast_interval);
+
+        // Open the configured FPS source, or the first available one
+        let config = state.config_manager.get();
+        let preferred = config.fps_source;
+        let log_path = config.mangohud_log.clone();
+        let shm_names = config.mangohud_shm_names.clone();
+        // Watch before opening so a segment created in between is not missed
+        let watch_names = fps_source::shm_names(&config);
+        let mut shm_watcher = match deep_sleep::CreateWatcher::new(Path::new(SHM_DIR)) {
+            Ok(watcher) => Some(watcher),
+            Err(e) => {
+                debug!("Cannot watch {} for FPS segments, retrying on a timer: {}", SHM_DIR, e);
+                None
+            }
+        };
+        let mut focused_app = state.display.focused_app().await;
+        let mut source = match fps_source::open(&config, focused_app) {
+            Ok(source) => source,
             Err(e) => {
-                // MangoHud fallback: log warning but keep daemon alive
-                warn!("MangoHud not active: {}. Running in fallback mode.", e);
-                state.set_mangohud_available(false);
-                
+                // No FPS data: log warning but keep daemon alive
+                warn!("No FPS source active: {}. Running in fallback mode.", e);
+                state.set_fps_source(None);
+                state.set_fps_stale(false).await;
+
                 tokio::select! {
                     _ = shutdown_rx.changed() => {
                         if *shutdown_rx.borrow() {
@@ -371,50 +844,155 @@ async fn run_fps_polling_with_panic_catch(
                         }
                     }
                     _ = tokio::time::sleep(retry_interval) => {}
+                    _ = shm_created(&mut shm_watcher, &watch_names) => {
+                        info!("FPS shared memory appeared, connecting");
+                        tokio::time::sleep(Duration::from_millis(SHM_ATTACH_DELAY_MS)).await;
+                    }
                 }
                 continue;
             }
         };
 
-        if let Some(reader) = reader {
-            info!("Connected to MangoHud shared memory");
-            state.set_mangohud_available(true);
-
-            // Poll loop
-            loop {
-                tokio::select! {
-                    _ = shutdown_rx.changed() => {
-                        if *shutdown_rx.borrow() {
-                            info!("FPS polling shutting down");
-                            return;
-                        }
+        info!("Connected to FPS source {}", source.kind().as_str());
+        state.set_fps_source(Some(source.kind()));
+        state.set_fps_pid(source.pid());
+        let mut window = config.fps_window_secs;
+        let mut poll_interval = fast_interval;
+        source.set_window(window_capacity(window, poll_interval));
+        let mut poller = AdaptivePoller::new(fast_interval, stable_interval);
+        state.set_fps_stale(false).await;
+        let mut stale_detector = StaleDetector::default();
+        let mut ema = EmaSmoother::default();
+        let mut last_upgrade_check = Instant::now();
+        // Set when an FPS segment appears, to check sources on the next poll
+        let mut recheck_sources = false;
+
+        // Poll loop
+        loop {
+            tokio::select! {
+                _ = shutdown_rx.changed() => {
+                    if *shutdown_rx.borrow() {
+                        info!("FPS polling shutting down");
+                        return;
+                    }
+                }
+                _ = shm_created(&mut shm_watcher, &watch_names) => {
+                    recheck_sources = true;
+                }
+                _ = tokio::time::sleep(poll_interval) => {
+                    if state.sleep.is_sleeping() {
+                        info!("Releasing FPS source for deep sleep");
+                        break;
                     }
-                    _ = tokio::time::sleep(poll_interval) => {
-                        if !state.is_running() {
-                            continue;
+                    let config = state.config_manager.get();
+                    if config.fps_source != preferred
+                        || config.mangohud_log != log_path
+                        || config.mangohud_shm_names != shm_names
+                    {
+                        info!("FPS source setting changed, reconnecting");
+                        break;
+                    }
+                    if recheck_sources || last_upgrade_check.elapsed() >= retry_interval {
+                        last_upgrade_check = Instant::now();
+                        recheck_sources = false;
+                        // Per-process segments: follow the focus to another game, but
+                        // not to the Steam overlay
+                        let focused = state.display.focused_app().await;
+                        if focused != focused_app && focused.is_some() && !state.is_overlay_open() {
+                            focused_app = focused;
+                            if source.pid().is_some() {
+                                info!("Focus moved to app {:?}, picking its FPS segment", focused);
+                                break;
+                            }
                         }
+                        // Auto-detection: move to a better source once one appears
+                        if preferred.is_none() {
+                            if let Some(better) = fps_source::open_preferred_over(source.kind(), &config, focused_app) {
+                                info!("Switching FPS source {} -> {}", source.kind().as_str(), better.kind().as_str());
+                                source = better;
+                                source.set_window(window_capacity(window, poll_interval));
+                                state.set_fps_source(Some(source.kind()));
+                                state.set_fps_pid(source.pid());
+                            }
+                        }
+                    }
+                    if config.fps_window_secs != window {
+                        window = config.fps_window_secs;
+                        source.set_window(window_capacity(window, poll_interval));
+                    }
+                    if !state.is_running() {
+                        continue;
+                    }
 
-                        let poll_result = std::panic::catch_unwind(AssertUnwindSafe(|| {
-                            reader.poll()
-                        }));
-
-                        match poll_result {
-                            Ok(Ok(sample)) => {
-                                let smoothed_fps = reader.get_smoothed_fps();
-                                if let Ok(mut fps) = state.current_fps.try_write() {
-                                    *fps = smoothed_fps;
+                    let poll_result = std::panic::catch_unwind(AssertUnwindSafe(|| {
+                        source.poll()
+                    }));
+
+                    match poll_result {
+                        Ok(Ok(sample)) => {
+                            // Kept current in both modes so switching to EMA starts warm
+                            let ema_fps = ema.update(sample.fps as f64, config.fps_ema_alpha);
+                            let smoothed_fps = match config.fps_smoothing {
+                                FpsSmoothing::Ema => ema_fps,
+                                smoothing => source.smoothed_fps(smoothing),
+                            };
+                            if let Ok(mut fps) = state.current_fps.try_write() {
+                                *fps = smoothed_fps;
+                            }
+                            state.set_frametime_p99(source.p99_frametime());
+                            state.mark_live().await;
+                            let stale = stale_detector.observe(&sample);
+                            if state.set_fps_stale(stale).await != stale {
+                                if stale {
+                                    info!("FPS frozen at {}, holding {}Hz", sample.fps, state.current_hz());
+                                } else {
+                                    info!("FPS updating again");
                                 }
-                                debug!("FPS: {} (smoothed: {:.1})", sample.fps, smoothed_fps);
                             }
-                            Ok(Err(e)) => {
-                                warn!("FPS poll error: {}, reconnecting...", e);
-                                state.set_mangohud_available(false);
-                                break;
+                            if sample.fps > 0 && !stale {
+                                state.sleep.note_activity();
+                            }
+                            // Frames under the Steam overlay say nothing about the game,
+                            // and a frozen reading says nothing at all
+                            if !state.is_overlay_open() && !stale {
+                                if sample.fps > 0 {
+                                    state
+                                        .controller
+                                        .write()
+                                        .await
+                                        .record_frametime(sample.frametime, Instant::now());
+                                }
+                                state.observe_session_sample(sample.fps as f64, sample.frametime).await;
                             }
-                            Err(_) => {
-                                error!("Panic during FPS polling, continuing operation");
+                            debug!("FPS: {} (smoothed: {:.1})", sample.fps, smoothed_fps);
+
+                            // Steady FPS: poll and decide less often until it moves
+                            let next_interval = if config.adaptive_polling {
+                                poller.observe(Instant::now(), sample.fps)
+                            } else {
+                                fast_interval
+                            };
+                            if next_interval != poll_interval {
+                                debug!("Polling every {}ms", next_interval.as_millis());
+                                poll_interval = next_interval;
+                                state.set_poll_interval(poll_interval);
+                                source.set_window(window_capacity(window, poll_interval));
                             }
                         }
+                        Ok(Err(e)) => {
+                            state.raise_event(
+                                EventKind::MangohudLost,
+                                format!("FPS poll error: {}, reconnecting...", e),
+                                state.current_hz(),
+                            );
+                            state.set_fps_source(None);
+                            state.set_fps_stale(false).await;
+                            state.set_frametime_p99(0);
+                            break;
+                        }
+                        Err(_) => {
+                            error!("Panic during FPS polling, continuing operation");
+                        }
                     }
                 }
             }
@@ -422,6 +1000,25 @@ async fn run_fps_polling_with_panic_catch(
     }
 }
 
+/// Wait until an FPS shared memory segment is created, or forever without
+/// a watcher. A failed watch drops the watcher, leaving the timed retries.
+async fn shm_created(watcher: &mut Option<deep_sleep::CreateWatcher>, names: &[String]) {
+    let result = match watcher.as_mut() {
+        Some(watcher) => watcher.wait_for(|file| fps_monitor::is_segment_file(file, names)).await,
+        None => std::future::pending().await,
+    };
+    if let Err(e) = result {
+        warn!("Watching {} for FPS segments failed, retrying on a timer: {}", SHM_DIR, e);
+        *watcher = None;
+        std::future::pending::<()>().await;
+    }
+}
+
+/// Ring buffer samples spanning `window` at `poll_interval`
+fn window_capacity(window: Secs, poll_interval: Duration) -> usize {
+    (Duration::from(window).as_millis() / poll_interval.as_millis().max(1)) as usize
+}
+
 /// Run core logic with panic catching
 async fn run_core_logic_with_panic_catch(
     state: Arc<DaemonState>,
@@ -429,7 +1026,16 @@ async fn run_core_logic_with_panic_catch(
     metrics: Arc<MetricsCollector>,
     mut shutdown_rx: watch::Receiver<bool>,
 ) {
-    let process_interval = Duration::from_millis(FPS_POLL_INTERVAL_MS);
+    let mut last_valid_fps = Instant::now();
+    // Frame limit set for low FPS compensation or VRR mode, cleared when it disengages
+    let mut lfc_limit: Option<u32> = None;
+    let status_page = match status_page::StatusPage::create(status_page::STATUS_PAGE_NAME) {
+        Ok(page) => Some(page),
+        Err(e) => {
+            warn!("Status page {} unavailable: {}", status_page::STATUS_PAGE_NAME, e);
+            None
+        }
+    };
 
     loop {
         tokio::select! {
@@ -439,8 +1045,75 @@ async fn run_core_logic_with_panic_catch(
                     break;
                 }
             }
-            _ = tokio::time::sleep(process_interval) => {
-                if !state.is_running() {
+            _ = tokio::time::sleep(state.poll_interval()) => {
+                if let Some(page) = &status_page {
+                    publish_status_page(&state, page).await;
+                }
+
+                let sync_limiter = state.controller.read().await.is_sync_frame_limiter_enabled();
+                display_manager.set_sync_frame_limiter(sync_limiter).await;
+
+                // VRR mode replaces rate switching with the frame limiter while running
+                let vrr_requested = state.is_running() && state.experiments.is_enabled(Experiment::VrrWindow);
+                let was_vrr = display_manager.vrr_status().active;
+                let vrr = display_manager.sync_vrr(vrr_requested).await;
+                if was_vrr && !vrr.active {
+                    // Leaving VRR already cleared the limit
+                    lfc_limit = None;
+                }
+
+                // Manual override bypasses the hysteresis controller until it expires
+                if let Some(override_hz) = state.current_hz_override().await {
+                    sync_lfc_limit(&display_manager, &mut lfc_limit, None).await;
+                    if display_manager.get_current_hz() != override_hz {
+                        let current_fps = *state.current_fps.read().await;
+                        apply_refresh_rate(&state, &display_manager, &metrics, override_hz, current_fps).await;
+                    }
+                    continue;
+                }
+
+                if !state.is_running() || state.is_session_paused() {
+                    sync_lfc_limit(&display_manager, &mut lfc_limit, None).await;
+                    continue;
+                }
+
+                // Recording or streaming: a rate change would corrupt the capture
+                if state.recording().is_some() {
+                    continue;
+                }
+
+                // OS update running: a mode switch could stall the compositor mid-update
+                if state.system_update().is_some() {
+                    continue;
+                }
+
+                // Steam overlay open: its FPS dip is not the game's, hold everything as is
+                if state.is_overlay_open() {
+                    continue;
+                }
+
+                // FPS frozen: the game is paused or hung, the reading is no guide
+                if state.is_fps_stale() {
+                    continue;
+                }
+
+                // Game excluded from switching: hold its maximum rate
+                if let Some(hold_hz) = state.game_hold_hz().await {
+                    sync_lfc_limit(&display_manager, &mut lfc_limit, None).await;
+                    if display_manager.get_current_hz() != hold_hz {
+                        let current_fps = *state.current_fps.read().await;
+                        apply_refresh_rate(&state, &display_manager, &metrics, hold_hz, current_fps).await;
+                    }
+                    continue;
+                }
+
+                // Steam UI in the foreground: hold the UI rate instead of following FPS
+                if let Some(ui_hz) = state.ui_hz_target().await {
+                    sync_lfc_limit(&display_manager, &mut lfc_limit, None).await;
+                    if display_manager.get_current_hz() != ui_hz {
+                        let current_fps = *state.current_fps.read().await;
+                        apply_refresh_rate(&state, &display_manager, &metrics, ui_hz, current_fps).await;
+                    }
                     continue;
                 }
 
@@ -448,9 +1121,41 @@ async fn run_core_logic_with_panic_catch(
                     Ok(fps) => *fps,
                     Err(_) => continue,
                 };
-                let current_hz = state.current_hz.load(Ordering::SeqCst);
+                let current_hz = state.current_hz();
+
+                // No game or MangoHud: apply the idle policy once enough time passes
+                if current_fps <= 0.0 || !state.is_mangohud_available() {
+                    sync_lfc_limit(&display_manager, &mut lfc_limit, None).await;
+                    if let Some(idle_hz) = state.idle_hz_target(last_valid_fps.elapsed()) {
+                        if display_manager.get_current_hz() != idle_hz {
+                            info!("No FPS data for {}s, applying idle rate {}Hz", last_valid_fps.elapsed().as_secs(), idle_hz);
+                            state.controller.write().await.cancel_pending();
+                            apply_refresh_rate(&state, &display_manager, &metrics, idle_hz, 0.0).await;
+                        }
+                    }
+                    continue;
+                }
+                last_valid_fps = Instant::now();
 
-                if current_fps <= 0.0 {
+                // VRR mode: hold the panel at max and step the frame limit instead
+                if vrr.active {
+                    let max_hz = state.config_manager.get().max_hz;
+                    if display_manager.get_current_hz() != max_hz {
+                        apply_refresh_rate(&state, &display_manager, &metrics, max_hz, current_fps).await;
+                    }
+                    let limit_hz = lfc_limit.map(Hertz).unwrap_or(max_hz);
+                    let target = {
+                        let mut controller = state.controller.write().await;
+                        controller.set_tolerance_boost(state.switch_budget_boost());
+                        controller.process(current_fps, limit_hz)
+                    };
+                    if let Some(target_hz) = target.or(lfc_limit.is_none().then_some(max_hz)) {
+                        let target_hz = display_manager.clamp_hz(target_hz);
+                        if lfc_limit != Some(target_hz.get()) {
+                            info!("VRR frame limit: {}Hz → {}Hz (FPS: {:.1})", limit_hz, target_hz, current_fps);
+                        }
+                        sync_lfc_limit(&display_manager, &mut lfc_limit, Some(target_hz.get())).await;
+                    }
                     continue;
                 }
 
@@ -464,37 +1169,136 @@ async fn run_core_logic_with_panic_catch(
                     }
 
                     let mut controller = state.controller.write().await;
+                    controller.set_tolerance_boost(state.switch_budget_boost());
                     controller.process(current_fps, current_hz)
                 };
+                let wanted_limit = state.controller.read().await.lfc_fps_limit();
 
                 // Apply refresh rate change if needed
                 if let Some(target_hz) = new_hz {
-                    let config = state.config_manager.get();
-                    display_manager.set_range(config.min_hz, config.max_hz);
-
-                    let old_hz = display_manager.get_current_hz();
-                    
-                    match display_manager.set_refresh_rate(target_hz).await {
-                        Ok(true) => {
-                            let new_hz_actual = display_manager.get_current_hz();
-                            state.current_hz.store(new_hz_actual, Ordering::SeqCst);
-                            
-                            // Record metrics
-                            metrics.record_switch(old_hz, new_hz_actual);
-                            
-                            // Record transition for UI
-                            state.record_transition(old_hz, new_hz_actual, current_fps).await;
-                            
-                            info!(
-                                "Refresh rate changed: {}Hz → {}Hz (FPS: {:.1})",
-                                old_hz, new_hz_actual, current_fps
-                            );
-                        }
-                        Ok(false) => {}
-                        Err(e) => {
-                            error!("Failed to set refresh rate: {}", e);
-                        }
+                    if !state.switch_budget_allows() {
+                        continue;
+                    }
+                    apply_refresh_rate(&state, &display_manager, &metrics, target_hz, current_fps).await;
+                }
+                sync_lfc_limit(&display_manager, &mut lfc_limit, wanted_limit).await;
+            }
+        }
+    }
+}
+
+/// Engage or release the frame limiter for low FPS compensation.
+///
+/// Only a limit set here is cleared, so a user's own limit is left alone.
+async fn sync_lfc_limit(display_manager: &DisplayManager, active: &mut Option<u32>, wanted: Option<u32>) {
+    if *active == wanted {
+        return;
+    }
+    let result = match wanted {
+        Some(limit) => display_manager.set_fps_limit(limit).await,
+        None => display_manager.release_fps_limit().await,
+    };
+    if let Err(e) = result {
+        warn!("Failed to update low FPS compensation frame limit: {}", e);
+    }
+    // Not retried on failure, to avoid spawning gamescope-cmd every tick
+    *active = wanted;
+}
+
+/// Apply a refresh rate through the display manager and record the switch.
+async fn apply_refresh_rate(
+    state: &DaemonState,
+    display_manager: &DisplayManager,
+    metrics: &MetricsCollector,
+    target_hz: Hertz,
+    current_fps: f64,
+) {
+    // The game's range (profile or global defaults), widened for targets
+    // outside it (the UI rate)
+    let (min_hz, max_hz) = state.controller.read().await.user_range();
+    display_manager.set_range(min_hz.min(target_hz), max_hz.max(target_hz));
+    display_manager.set_allowed_hz(&state.allowed_hz().await);
+
+    let old_hz = display_manager.get_current_hz();
+
+    match display_manager.set_refresh_rate(target_hz).await {
+        Ok(true) => {
+            let new_hz_actual = display_manager.get_current_hz();
+            state.set_current_hz(new_hz_actual);
+            state.note_switch();
+
+            // Record metrics
+            if state.subsystems.is_enabled(Subsystem::Metrics) {
+                metrics.record_switch(old_hz, new_hz_actual);
+            }
+
+            // Record transition for UI
+            state.record_transition(old_hz, new_hz_actual, current_fps).await;
+
+            if let Some(event) = EventKind::for_transition(old_hz, new_hz_actual, min_hz, max_hz) {
+                let message = format!("Entered {} mode at {}Hz", event.as_str(), new_hz_actual);
+                state.raise_event(event, message, new_hz_actual);
+            }
+
+            info!(
+                "Refresh rate changed: {}Hz → {}Hz (FPS: {:.1})",
+                old_hz, new_hz_actual, current_fps
+            );
+        }
+        Ok(false) => {}
+        // Switching is paused; the breaker already logged why
+        Err(error::DisplayError::BackendUnhealthy { .. }) => {}
+        Err(e) => {
+            error!("Failed to set refresh rate: {}", e);
+        }
+    }
+}
+
+/// Resync the current rate when it is changed outside the daemon, and hold
+/// the user's choice for `manual_change_grace_secs`. Reapplies the rate and
+/// frame limit when the compositor restarts
+async fn run_external_change_detection(
+    state: Arc<DaemonState>,
+    display_manager: Arc<DisplayManager>,
+    mut shutdown_rx: watch::Receiver<bool>,
+) {
+    let poll_interval = Duration::from_secs(EXTERNAL_CHANGE_POLL_INTERVAL_SECS);
+
+    loop {
+        if !state.sleep.wait_until_awake(&mut shutdown_rx).await {
+            info!("External change detection shutting down");
+            break;
+        }
+        tokio::select! {
+            _ = shutdown_rx.changed() => {
+                if *shutdown_rx.borrow() {
+                    info!("External change detection shutting down");
+                    break;
+                }
+            }
+            _ = tokio::time::sleep(poll_interval) => {
+                // A restarted compositor comes back at its default rate
+                if display_manager.detect_compositor_restart() {
+                    info!("Compositor restarted, reapplying {}Hz", display_manager.get_current_hz());
+                    if let Err(e) = display_manager.reapply_state().await {
+                        warn!("Failed to reapply display state after compositor restart: {}", e);
                     }
+                    continue;
+                }
+
+                let old_hz = state.current_hz();
+                let Some(new_hz) = display_manager.detect_external_change().await else {
+                    continue;
+                };
+                info!("Refresh rate changed outside the daemon: {}Hz → {}Hz", old_hz, new_hz);
+                state.set_current_hz(new_hz);
+
+                let grace = state.config_manager.get().manual_change_grace_secs;
+                if grace > Secs(0) {
+                    state.set_hz_override(new_hz, Some(Duration::from(grace))).await;
+                } else {
+                    // Pending transitions refer to the old rate
+                    state.controller.write().await.cancel_pending();
                 }
             }
         }
@@ -508,8 +1312,21 @@ async fn run_monitor_detection(
     mut shutdown_rx: watch::Receiver<bool>,
 ) {
     let check_interval = Duration::from_secs(MONITOR_CHECK_INTERVAL_SECS);
+    let mut debouncer = DisplayDebouncer::new(state.config_manager.get().display_debounce);
+    state.refresh_displays().await;
+    let mut hotplug = match hotplug::HotplugMonitor::open() {
+        Ok(monitor) => Some(monitor),
+        Err(e) => {
+            warn!("DRM hotplug events unavailable, polling every {}s: {}", MONITOR_CHECK_INTERVAL_SECS, e);
+            None
+        }
+    };
 
     loop {
+        if !state.sleep.wait_until_awake(&mut shutdown_rx).await {
+            info!("Monitor detection shutting down");
+            break;
+        }
         tokio::select! {
             _ = shutdown_rx.changed() => {
                 if *shutdown_rx.borrow() {
@@ -517,21 +1334,288 @@ async fn run_monitor_detection(
                     break;
                 }
             }
-            _ = tokio::time::sleep(check_interval) => {
-                let external_detected = detector.has_external_display().await;
-                
-                let mut controller = state.controller.write().await;
-                let was_detected = controller.is_external_display_detected();
-                
-                if external_detected != was_detected {
-                    controller.set_external_display_detected(external_detected);
-                    if external_detected {
-                        info!("External display detected - Pausing SmartRefresh");
-                    } else {
-                        info!("External display disconnected - Resuming SmartRefresh");
+            event = next_hotplug_event(&mut hotplug) => {
+                if let Err(e) = event {
+                    warn!("DRM hotplug events failed, falling back to polling: {}", e);
+                    hotplug = None;
+                    continue;
+                }
+                debug!("DRM hotplug event, checking displays");
+                if state.subsystems.is_enabled(Subsystem::MonitorDetection) {
+                    check_displays(&state, &detector, &mut debouncer).await;
+                }
+            }
+            _ = tokio::time::sleep(debouncer.next_check(check_interval)) => {
+                if state.subsystems.is_enabled(Subsystem::MonitorDetection) {
+                    check_displays(&state, &detector, &mut debouncer).await;
+                }
+            }
+        }
+    }
+}
+
+/// Next DRM hotplug event, or never without a hotplug monitor.
+async fn next_hotplug_event(hotplug: &mut Option<hotplug::HotplugMonitor>) -> std::io::Result<()> {
+    match hotplug {
+        Some(monitor) => monitor.next_event().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Read the external display and power state, and pause, resume, control
+/// the external display or switch to the docked profile once a change is
+/// debounced.
+async fn check_displays(state: &DaemonState, detector: &MonitorDetector, debouncer: &mut DisplayDebouncer) {
+    let config = state.config_manager.get();
+    debouncer.set_config(config.display_debounce);
+    let reading = detector.connected_external(&config.ignored_connectors).await;
+    state.refresh_displays().await;
+
+    let was_controlled = state.display.active_output().is_some();
+    let was_detected =
+        state.is_docked() || was_controlled || state.controller.read().await.is_external_display_detected();
+    let external = debouncer
+        .observe(reading.is_some(), was_detected, Instant::now())
+        .unwrap_or(was_detected);
+
+    // Docked: a docked profile replaces the pause while on external power
+    let docked = external
+        && state.profile_manager.read().await.docked.is_some()
+        && battery::external_power_online(Path::new(battery::POWER_SUPPLY_DIR));
+    let was_docked = state.is_docked();
+    state.set_docked(docked).await;
+
+    // Controlled: the external display's rate is switched instead of pausing
+    let output = match reading {
+        Some(output) if external && config.control_external_display => Some(output),
+        // Held through a minimum pause while the display is briefly gone
+        None if external && config.control_external_display => state.display.active_output(),
+        _ => None,
+    };
+    let controlled = state.set_active_output(output).await;
+    state
+        .controller
+        .write()
+        .await
+        .set_external_display_detected(external && !docked && !controlled);
+
+    if docked && !was_docked {
+        state.raise_event(
+            EventKind::ExternalDisplay,
+            "Docked - Using the docked profile".to_string(),
+            state.current_hz(),
+        );
+    } else if controlled && !was_controlled {
+        state.raise_event(
+            EventKind::ExternalDisplay,
+            format!(
+                "External display {} detected - Controlling its refresh rate",
+                state.display.active_output().unwrap_or_default()
+            ),
+            state.current_hz(),
+        );
+    } else if external && !docked && !controlled && (was_docked || was_controlled || !was_detected) {
+        state.raise_event(
+            EventKind::ExternalDisplay,
+            "External display detected - Pausing SmartRefresh".to_string(),
+            state.current_hz(),
+        );
+    } else if !external && was_detected {
+        info!("External display disconnected - Resuming SmartRefresh");
+    }
+}
+
+/// Run session detection task: tell game mode from desktop mode
+async fn run_session_detection(state: Arc<DaemonState>, mut shutdown_rx: watch::Receiver<bool>) {
+    let check_interval = Duration::from_secs(SESSION_CHECK_INTERVAL_SECS);
+    let current_desktop = std::env::var("XDG_CURRENT_DESKTOP").ok();
+
+    loop {
+        let session = session::SessionKind::detect(
+            Path::new("/proc"),
+            Path::new(session::RUN_USER_DIR),
+            current_desktop.as_deref(),
+        );
+        if state.set_session(session) != session {
+            if state.is_session_paused() {
+                info!("Session: {} - Pausing SmartRefresh (desktop_mode_switching is off)", session.as_str());
+            } else {
+                info!("Session: {}", session.as_str());
+            }
+        }
+
+        if !state.sleep.wait_until_awake(&mut shutdown_rx).await {
+            info!("Session detection shutting down");
+            break;
+        }
+        tokio::select! {
+            _ = shutdown_rx.changed() => {
+                if *shutdown_rx.borrow() {
+                    info!("Session detection shutting down");
+                    break;
+                }
+            }
+            _ = tokio::time::sleep(check_interval) => {}
+        }
+    }
+}
+
+/// Run screen recording detection task: lock the rate while recording
+async fn run_recording_detection(state: Arc<DaemonState>, mut shutdown_rx: watch::Receiver<bool>) {
+    let check_interval = Duration::from_secs(RECORDING_CHECK_INTERVAL_SECS);
+
+    loop {
+        let recording = recording::detect(Path::new("/proc")).await;
+        if state.set_recording(recording.clone()).await != recording {
+            match &recording {
+                Some(recorder) => info!("Recording detected ({}) - Locking {}Hz", recorder, state.current_hz()),
+                None => info!("Recording stopped - Resuming"),
+            }
+        }
+
+        if !state.sleep.wait_until_awake(&mut shutdown_rx).await {
+            info!("Recording detection shutting down");
+            break;
+        }
+        tokio::select! {
+            _ = shutdown_rx.changed() => {
+                if *shutdown_rx.borrow() {
+                    info!("Recording detection shutting down");
+                    break;
+                }
+            }
+            _ = tokio::time::sleep(check_interval) => {}
+        }
+    }
+}
+
+/// Run OS update detection task: hold the rate while an update runs
+async fn run_update_detection(state: Arc<DaemonState>, mut shutdown_rx: watch::Receiver<bool>) {
+    let check_interval = Duration::from_secs(UPDATE_CHECK_INTERVAL_SECS);
+    let detector = system_update::UpdateDetector::default();
+    #[cfg(unix)]
+    let mut connection: Option<zbus::Connection> = None;
+
+    loop {
+        let mut update = detector.detect();
+        #[cfg(unix)]
+        if update.is_none() {
+            if connection.is_none() {
+                connection = zbus::Connection::system().await.ok();
+            }
+            if let Some(bus) = &connection {
+                match system_update::packagekit_locked(bus).await {
+                    Ok(true) => update = Some("PackageKit".to_string()),
+                    Ok(false) => {}
+                    Err(e) => {
+                        debug!("PackageKit query failed: {}", e);
+                        connection = None;
+                    }
+                }
+            }
+        }
+        if state.set_system_update(update.clone()) != update {
+            match &update {
+                Some(updater) => info!("OS update in progress ({}) - Holding {}Hz", updater, state.current_hz()),
+                None => info!("OS update finished - Resuming"),
+            }
+        }
+
+        if !state.sleep.wait_until_awake(&mut shutdown_rx).await {
+            info!("Update detection shutting down");
+            break;
+        }
+        tokio::select! {
+            _ = shutdown_rx.changed() => {
+                if *shutdown_rx.borrow() {
+                    info!("Update detection shutting down");
+                    break;
+                }
+            }
+            _ = tokio::time::sleep(check_interval) => {}
+        }
+    }
+}
+
+/// Run Steam overlay detection task: freeze the controller while it is open
+async fn run_overlay_detection(
+    state: Arc<DaemonState>,
+    display_manager: Arc<DisplayManager>,
+    mut shutdown_rx: watch::Receiver<bool>,
+) {
+    let check_interval = Duration::from_millis(OVERLAY_CHECK_INTERVAL_MS);
+
+    loop {
+        let open = state.is_running() && display_manager.overlay_open().await;
+        if state.set_overlay_open(open).await != open {
+            if open {
+                info!("Steam overlay opened - Holding {}Hz", display_manager.get_current_hz());
+            } else {
+                info!("Steam overlay closed - Resuming");
+            }
+        }
+
+        if !state.sleep.wait_until_awake(&mut shutdown_rx).await {
+            info!("Overlay detection shutting down");
+            break;
+        }
+        tokio::select! {
+            _ = shutdown_rx.changed() => {
+                if *shutdown_rx.borrow() {
+                    info!("Overlay detection shutting down");
+                    break;
+                }
+            }
+            _ = tokio::time::sleep(check_interval) => {}
+        }
+    }
+}
+
+/// Run game detection task: switch the current game (and its profile) when
+/// the focused or running Steam app changes
+async fn run_app_detection(
+    state: Arc<DaemonState>,
+    display_manager: Arc<DisplayManager>,
+    mut shutdown_rx: watch::Receiver<bool>,
+) {
+    let check_interval = Duration::from_secs(APP_DETECT_INTERVAL_SECS);
+    let registry_path = app_detect::default_registry_path();
+    let mut detector = app_detect::AppDetector::default();
+
+    loop {
+        if state.config_manager.get().auto_detect_game {
+            let focused = display_manager.focused_app().await;
+            let detected = app_detect::detect_app(focused, || {
+                registry_path.as_deref().and_then(app_detect::read_running_app_id)
+            });
+            // Only act on changes, so a game set over IPC stays until the detection moves on
+            if let Some(app) = detector.update(detected) {
+                let app_id = app.map(|id| id.to_string());
+                let current = state.profile_manager.read().await.get_current_game().cloned();
+                if current != app_id {
+                    match &app_id {
+                        Some(id) => info!("Detected game {}", id),
+                        None => info!("Game closed"),
                     }
+                    state.switch_game(app_id, None).await;
+                }
+            }
+        } else {
+            detector = app_detect::AppDetector::default();
+        }
+
+        if !state.sleep.wait_until_awake(&mut shutdown_rx).await {
+            info!("Game detection shutting down");
+            break;
+        }
+        tokio::select! {
+            _ = shutdown_rx.changed() => {
+                if *shutdown_rx.borrow() {
+                    info!("Game detection shutting down");
+                    break;
                 }
             }
+            _ = tokio::time::sleep(check_interval) => {}
         }
     }
 }
@@ -545,6 +1629,10 @@ async fn run_battery_monitoring(
     let poll_interval = Duration::from_secs(BATTERY_POLL_INTERVAL_SECS);
 
     loop {
+        if !state.sleep.wait_until_awake(&mut shutdown_rx).await {
+            info!("Battery monitoring shutting down");
+            break;
+        }
         tokio::select! {
             _ = shutdown_rx.changed() => {
                 if *shutdown_rx.borrow() {
@@ -553,8 +1641,329 @@ async fn run_battery_monitoring(
                 }
             }
             _ = tokio::time::sleep(poll_interval) => {
+                if !state.subsystems.is_enabled(Subsystem::Battery) {
+                    continue;
+                }
                 if let Some(power_uw) = monitor.read_power_now() {
-                    monitor.record_sample(power_uw, state.current_hz.load(Ordering::SeqCst));
+                    monitor.record_sample(power_uw, state.current_hz());
+                    state.mark_live().await;
+                }
+            }
+        }
+    }
+}
+
+/// Run thermal monitoring task: hold Hz increases while the APU is hot
+async fn run_thermal_monitoring(state: Arc<DaemonState>, mut shutdown_rx: watch::Receiver<bool>) {
+    let sensor = thermal::ThermalSensor::discover(Path::new(thermal::HWMON_DIR));
+    if !sensor.is_available() {
+        info!("No APU temperature sensor found, thermal-aware switching disabled");
+        return;
+    }
+    let poll_interval = Duration::from_secs(THERMAL_POLL_INTERVAL_SECS);
+
+    loop {
+        if !state.sleep.wait_until_awake(&mut shutdown_rx).await {
+            info!("Thermal monitoring shutting down");
+            break;
+        }
+        tokio::select! {
+            _ = shutdown_rx.changed() => {
+                if *shutdown_rx.borrow() {
+                    info!("Thermal monitoring shutting down");
+                    break;
+                }
+            }
+            _ = tokio::time::sleep(poll_interval) => {
+                let reading = if state.subsystems.is_enabled(Subsystem::Thermal) {
+                    sensor.read_celsius()
+                } else {
+                    None
+                };
+                state.update_thermal(reading).await;
+            }
+        }
+    }
+}
+
+/// Run utilization sampling task: hold Hz increases while the GPU is pegged
+async fn run_utilization_monitoring(state: Arc<DaemonState>, mut shutdown_rx: watch::Receiver<bool>) {
+    let mut sampler = utilization::UtilizationSampler::new(
+        Path::new(utilization::DRM_DIR),
+        Path::new(utilization::PROC_STAT),
+    );
+    let poll_interval = Duration::from_secs(UTILIZATION_POLL_INTERVAL_SECS);
+
+    loop {
+        if !state.sleep.wait_until_awake(&mut shutdown_rx).await {
+            info!("Utilization monitoring shutting down");
+            break;
+        }
+        tokio::select! {
+            _ = shutdown_rx.changed() => {
+                if *shutdown_rx.borrow() {
+                    info!("Utilization monitoring shutting down");
+                    break;
+                }
+            }
+            _ = tokio::time::sleep(poll_interval) => {
+                // Checked every tick so SetAdvancedConfig takes effect live
+                let sample = state
+                    .config_manager
+                    .get()
+                    .gpu_utilization
+                    .then(|| sampler.sample());
+                state.update_utilization(sample).await;
+            }
+        }
+    }
+}
+
+/// Put the daemon into deep sleep when idle, and wake it on MangoHud SHM creation
+async fn run_deep_sleep_supervisor(state: Arc<DaemonState>, mut shutdown_rx: watch::Receiver<bool>) {
+    let check_interval = Duration::from_secs(DEEP_SLEEP_CHECK_INTERVAL_SECS);
+
+    loop {
+        tokio::select! {
+            _ = shutdown_rx.changed() => {
+                if *shutdown_rx.borrow() {
+                    info!("Deep-sleep supervisor shutting down");
+                    break;
+                }
+            }
+            _ = tokio::time::sleep(check_interval) => {}
+        }
+
+        let config = state.config_manager.get();
+        let idle_after = config.deep_sleep_after_secs;
+        if idle_after == Secs(0) || state.sleep.is_sleeping() {
+            continue;
+        }
+        if state.profile_manager.read().await.get_current_game().is_some()
+            || state.sleep.idle_for() < Duration::from(idle_after)
+        {
+            continue;
+        }
+
+        // Watch before sleeping so an SHM created in between is not missed
+        let shm_names = fps_source::shm_names(&config);
+        let mut watcher = match deep_sleep::CreateWatcher::new(Path::new(SHM_DIR)) {
+            Ok(w) => w,
+            Err(e) => {
+                warn!("Cannot watch {} for MangoHud, not entering deep sleep: {}", SHM_DIR, e);
+                continue;
+            }
+        };
+
+        state.raise_event(
+            EventKind::DeepSleep,
+            format!("No game or FPS data for {}s, entering deep sleep", idle_after),
+            state.current_hz(),
+        );
+        state.sleep.enter();
+
+        let mut sleeping_rx = state.sleep.subscribe();
+        tokio::select! {
+            _ = shutdown_rx.changed() => {
+                if *shutdown_rx.borrow() {
+                    info!("Deep-sleep supervisor shutting down");
+                    break;
+                }
+            }
+            result = watcher.wait_for(|file| fps_monitor::is_segment_file(file, &shm_names)) => match result {
+                Ok(()) => {
+                    info!("MangoHud shared memory appeared");
+                    state.sleep.note_activity();
+                }
+                Err(e) => {
+                    warn!("Deep-sleep SHM watch failed: {}", e);
+                    state.sleep.note_activity();
+                }
+            },
+            // Woken by something else, e.g. game detection over IPC
+            _ = sleeping_rx.wait_for(|sleeping| !*sleeping) => {}
+        }
+    }
+}
+
+/// Mirror the daemon state on the status LED
+async fn run_led_indicator(state: Arc<DaemonState>, mut shutdown_rx: watch::Receiver<bool>) {
+    let indicator = LedIndicator::default();
+    let update_interval = Duration::from_secs(LED_UPDATE_INTERVAL_SECS);
+
+    loop {
+        tokio::select! {
+            _ = shutdown_rx.changed() => {
+                if *shutdown_rx.borrow() {
+                    info!("LED indicator shutting down");
+                    break;
+                }
+            }
+            _ = tokio::time::sleep(update_interval) => {
+                let config = state.config_manager.get();
+                let Some(led) = config.led else {
+                    continue;
+                };
+                let paused = !state.is_running()
+                    || state.controller.read().await.is_external_display_detected();
+                let led_state = LedState::classify(
+                    paused,
+                    state.current_hz(),
+                    config.min_hz,
+                    config.max_hz,
+                );
+                indicator.update(&led, led_state);
+            }
+        }
+    }
+}
+
+/// Write the current rate, FPS, state and savings to the status page
+async fn publish_status_page(state: &DaemonState, page: &status_page::StatusPage) {
+    let battery = state.battery_monitor.get_status();
+    page.publish(&status_page::PageData {
+        hz: state.current_hz(),
+        fps: *state.current_fps.read().await,
+        state: state.control_state().await,
+        savings_minutes: if battery.available { battery.estimated_savings_minutes } else { 0.0 },
+    });
+}
+
+/// Keep the MangoHud overlay line current while `mangohud_overlay` is on
+async fn run_mangohud_overlay(state: Arc<DaemonState>, mut shutdown_rx: watch::Receiver<bool>) {
+    let mut writer = mangohud_overlay::OverlayWriter::new(mangohud_overlay::default_overlay_path());
+    let update_interval = Duration::from_millis(MANGOHUD_OVERLAY_INTERVAL_MS);
+    // Warn once per run of failed writes
+    let mut write_failed = false;
+
+    loop {
+        tokio::select! {
+            _ = shutdown_rx.changed() => {
+                if *shutdown_rx.borrow() {
+                    writer.clear();
+                    info!("MangoHud overlay shutting down");
+                    break;
+                }
+            }
+            _ = tokio::time::sleep(update_interval) => {
+                if !state.config_manager.get().mangohud_overlay {
+                    writer.clear();
+                    continue;
+                }
+                let control_state = state.control_state().await;
+                let text = mangohud_overlay::overlay_text(state.current_hz(), control_state.as_str());
+                match writer.update(&text) {
+                    Ok(()) => write_failed = false,
+                    Err(e) if !write_failed => {
+                        warn!("Failed to write MangoHud overlay line to {}: {}", writer.path().display(), e);
+                        write_failed = true;
+                    }
+                    Err(_) => {}
+                }
+            }
+        }
+    }
+}
+
+/// Run long-term trend tracking task
+/// Persist status snapshots for the next daemon start
+async fn run_status_snapshots(
+    state: Arc<DaemonState>,
+    mut writer: status_snapshot::SnapshotWriter,
+    mut shutdown_rx: watch::Receiver<bool>,
+) {
+    let check_interval = Duration::from_secs(STATUS_SNAPSHOT_INTERVAL_SECS);
+
+    loop {
+        tokio::select! {
+            _ = shutdown_rx.changed() => {
+                if *shutdown_rx.borrow() {
+                    if let Err(e) = writer.flush(&state.get_status().await) {
+                        warn!("Failed to save status snapshot: {}", e);
+                    }
+                    info!("Status snapshots shutting down");
+                    break;
+                }
+            }
+            _ = tokio::time::sleep(check_interval) => {
+                if let Err(e) = writer.maybe_save(&state.get_status().await, Instant::now()) {
+                    warn!("Failed to save status snapshot: {}", e);
+                }
+            }
+        }
+    }
+}
+
+/// Append the smoothed FPS and current Hz to the UI history once a second.
+async fn run_history_sampling(state: Arc<DaemonState>, mut shutdown_rx: watch::Receiver<bool>) {
+    let sample_interval = Duration::from_secs(history::HISTORY_INTERVAL_SECS);
+    loop {
+        tokio::select! {
+            _ = shutdown_rx.changed() => {
+                if *shutdown_rx.borrow() {
+                    info!("History sampling shutting down");
+                    break;
+                }
+            }
+            _ = tokio::time::sleep(sample_interval) => {
+                let fps = *state.current_fps.read().await;
+                state.record_history(history::HistoryPoint::now(fps, state.current_hz()));
+            }
+        }
+    }
+}
+
+async fn run_trend_tracking(
+    state: Arc<DaemonState>,
+    mut store: TrendStore,
+    mut shutdown_rx: watch::Receiver<bool>,
+) {
+    let sample_interval = Duration::from_secs(TREND_SAMPLE_INTERVAL_SECS);
+    let os_version = trends::read_os_version();
+    let mut last_switches = state.metrics.get_metrics().total_switches;
+    let mut samples_since_save = 0;
+
+    state.set_advisories(store.analyze(trends::current_day())).await;
+
+    loop {
+        tokio::select! {
+            _ = shutdown_rx.changed() => {
+                if *shutdown_rx.borrow() {
+                    if let Err(e) = store.save() {
+                        warn!("Failed to save trend history: {}", e);
+                    }
+                    info!("Trend tracking shutting down");
+                    break;
+                }
+            }
+            _ = tokio::time::sleep(sample_interval) => {
+                let switches = state.metrics.get_metrics().total_switches;
+                let new_switches = switches.saturating_sub(last_switches);
+                last_switches = switches;
+
+                let fps = *state.current_fps.read().await;
+                if !state.is_running() || fps <= 0.0 {
+                    continue;
+                }
+
+                let app_id = state.profile_manager.read().await.get_current_game().cloned();
+                let today = trends::current_day();
+                store.record(
+                    today,
+                    app_id.as_deref(),
+                    TREND_SAMPLE_INTERVAL_SECS,
+                    fps,
+                    new_switches,
+                    os_version.as_deref(),
+                );
+
+                samples_since_save += 1;
+                if samples_since_save >= TREND_SAMPLES_PER_SAVE {
+                    samples_since_save = 0;
+                    if let Err(e) = store.save() {
+                        warn!("Failed to save trend history: {}", e);
+                    }
+                    state.set_advisories(store.analyze(today)).await;
                 }
             }
         }
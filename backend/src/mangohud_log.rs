@@ -0,0 +1,227 @@
+//! MangoHud CSV log tailing FPS source.
+//!
+//! With `output_folder` and `autostart_log` set, MangoHud writes a CSV per
+//! session: two lines of system info, then a header naming the columns
+//! (`fps,frametime,cpu_load,...`) and one row per sample. Some games never
+//! create the shared memory segment but still log, so the configured log
+//! file, or the newest `.csv` in the configured folder, is tailed instead.
+
+use crate::error::FpsSourceError;
+use crate::fps_monitor::{FpsRingBuffer, FpsSample, FpsSmoothing};
+use crate::fps_source::{FpsSource, FpsSourceKind};
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// How often a log folder is checked for a newer session's file
+const RESCAN_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Column positions from the CSV header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Columns {
+    fps: Option<usize>,
+    frametime: Option<usize>,
+}
+
+impl Columns {
+    /// Parse a header line; `None` unless it names an FPS or frametime column.
+    fn parse(line: &str) -> Option<Self> {
+        let names: Vec<&str> = line.split(',').map(str::trim).collect();
+        let columns = Self {
+            fps: names.iter().position(|&name| name == "fps"),
+            frametime: names.iter().position(|&name| name == "frametime"),
+        };
+        (columns.fps.is_some() || columns.frametime.is_some()).then_some(columns)
+    }
+
+    /// FPS and frametime (µs) from a data row. Frametime is logged in ms.
+    fn sample(&self, line: &str) -> Option<FpsSample> {
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        let value = |index: Option<usize>| -> Option<f64> {
+            let value: f64 = fields.get(index?)?.parse().ok()?;
+            (value.is_finite() && value > 0.0).then_some(value)
+        };
+        match (value(self.fps), value(self.frametime)) {
+            (Some(fps), Some(ms)) => Some(FpsSample::new(fps.round() as u64, (ms * 1000.0).round() as u64)),
+            (Some(fps), None) => Some(FpsSample::new(fps.round() as u64, (1_000_000.0 / fps).round() as u64)),
+            (None, Some(ms)) => Some(FpsSample::new((1000.0 / ms).round() as u64, (ms * 1000.0).round() as u64)),
+            (None, None) => None,
+        }
+    }
+}
+
+/// Tails a MangoHud CSV log.
+pub struct MangoHudLogSource {
+    /// Configured file or folder
+    configured: PathBuf,
+    path: PathBuf,
+    file: File,
+    position: u64,
+    columns: Option<Columns>,
+    /// Bytes after the last complete line
+    pending: String,
+    latest: FpsSample,
+    ring_buffer: FpsRingBuffer,
+    last_scan: Instant,
+}
+
+impl MangoHudLogSource {
+    /// Tail `configured`: a CSV file, or the newest `.csv` in a folder.
+    /// Only rows written from now on count.
+    pub fn open(configured: &Path) -> Result<Self, FpsSourceError> {
+        let path = resolve_log(configured).ok_or(FpsSourceError::MangohudLogNotFound)?;
+        let mut source = Self {
+            configured: configured.to_path_buf(),
+            file: File::open(&path)?,
+            path,
+            position: 0,
+            columns: None,
+            pending: String::new(),
+            latest: FpsSample::new(0, 0),
+            ring_buffer: FpsRingBuffer::new(),
+            last_scan: Instant::now(),
+        };
+        // Take the header from what is there, then drop the old rows
+        source.read_new_lines()?;
+        source.latest = FpsSample::new(0, 0);
+        Ok(source)
+    }
+
+    /// Start over on `path` (a new session's log, or a truncated one).
+    fn reopen(&mut self, path: PathBuf) -> Result<(), FpsSourceError> {
+        tracing::info!("Tailing MangoHud log {}", path.display());
+        self.file = File::open(&path)?;
+        self.path = path;
+        self.position = 0;
+        self.columns = None;
+        self.pending.clear();
+        Ok(())
+    }
+
+    /// Read the bytes appended since the last call and apply complete lines.
+    fn read_new_lines(&mut self) -> Result<(), FpsSourceError> {
+        let len = self.file.metadata()?.len();
+        if len < self.position {
+            self.reopen(self.path.clone())?;
+        }
+        self.file.seek(SeekFrom::Start(self.position))?;
+        let mut appended = Vec::new();
+        self.position += self.file.read_to_end(&mut appended)? as u64;
+        self.pending.push_str(&String::from_utf8_lossy(&appended));
+
+        while let Some(end) = self.pending.find('\n') {
+            let line: String = self.pending.drain(..=end).collect();
+            let line = line.trim();
+            match self.columns {
+                Some(columns) => {
+                    if let Some(sample) = columns.sample(line) {
+                        self.latest = sample;
+                    }
+                }
+                None => self.columns = Columns::parse(line),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FpsSource for MangoHudLogSource {
+    fn kind(&self) -> FpsSourceKind {
+        FpsSourceKind::MangohudLog
+    }
+
+    fn poll(&mut self) -> Result<FpsSample, FpsSourceError> {
+        if self.configured.is_dir() && self.last_scan.elapsed() >= RESCAN_INTERVAL {
+            self.last_scan = Instant::now();
+            if let Some(newest) = resolve_log(&self.configured).filter(|newest| *newest != self.path) {
+                self.reopen(newest)?;
+                self.latest = FpsSample::new(0, 0);
+            }
+        }
+        self.read_new_lines()?;
+        let sample = FpsSample::new(self.latest.fps, self.latest.frametime);
+        self.ring_buffer.push(sample.clone());
+        Ok(sample)
+    }
+
+    fn smoothed_fps(&self, smoothing: FpsSmoothing) -> f64 {
+        self.ring_buffer.smoothed(smoothing)
+    }
+
+    fn p99_frametime(&self) -> u64 {
+        self.ring_buffer.percentile(0.99)
+    }
+
+    fn set_window(&mut self, capacity: usize) {
+        self.ring_buffer.set_capacity(capacity);
+    }
+}
+
+/// `configured` itself if it is a file, else its most recently modified
+/// `.csv`.
+fn resolve_log(configured: &Path) -> Option<PathBuf> {
+    if configured.is_file() {
+        return Some(configured.to_path_buf());
+    }
+    std::fs::read_dir(configured)
+        .ok()?
+        .flatten()
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "csv"))
+        .filter_map(|entry| Some((entry.metadata().ok()?.modified().ok()?, entry.path())))
+        .max()
+        .map(|(_, path)| path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::tempdir;
+
+    const HEADER: &str = "os,cpu,gpu,ram,kernel,driver,cpuscheduler\n\
+        SteamOS,AMD Custom APU 0405,AMD Custom GPU 0405,15GB,6.1.52-valve16-1-neptune-61,Mesa 24.1,\n\
+        fps,frametime,cpu_load,gpu_load,cpu_temp,gpu_temp,gpu_core_clock,gpu_mem_clock,gpu_vram_used,gpu_power,ram_used,swap_used,process_rss,elapsed\n";
+
+    #[test]
+    fn test_columns() {
+        assert_eq!(Columns::parse("os,cpu,gpu,ram,kernel,driver,cpuscheduler"), None);
+        let columns = Columns::parse("fps,frametime,cpu_load").unwrap();
+        let sample = columns.sample("59.9,16.7,23").unwrap();
+        assert_eq!((sample.fps, sample.frametime), (60, 16700));
+
+        let columns = Columns::parse("elapsed,fps").unwrap();
+        let sample = columns.sample("1200,40").unwrap();
+        assert_eq!((sample.fps, sample.frametime), (40, 25000));
+        assert!(columns.sample("1200,").is_none());
+    }
+
+    #[test]
+    fn test_tail_log_folder() {
+        let dir = tempdir().unwrap();
+        let first = dir.path().join("Celeste_2024-05-01_20-00-00.csv");
+        let mut writer = File::create(&first).unwrap();
+        writeln!(writer, "{}30.0,33.3,10,20,50,50,1600,800,1.2,10,5,0,1.1,1000", HEADER).unwrap();
+
+        let mut source = MangoHudLogSource::open(dir.path()).unwrap();
+        assert_eq!(source.kind(), FpsSourceKind::MangohudLog);
+        // Rows from before opening don't count
+        assert_eq!(source.poll().unwrap().fps, 0);
+
+        writeln!(writer, "45.0,22.2,10,20,50,50,1600,800,1.2,10,5,0,1.1,1100").unwrap();
+        write!(writer, "60.0,16.6,10,20,50,50,1600,800,1.2,10,5,0,1.1,").unwrap();
+        let sample = source.poll().unwrap();
+        assert_eq!((sample.fps, sample.frametime), (45, 22200));
+        writeln!(writer, "1200").unwrap();
+        assert_eq!(source.poll().unwrap().fps, 60);
+    }
+
+    #[test]
+    fn test_open_without_log() {
+        let dir = tempdir().unwrap();
+        assert!(matches!(
+            MangoHudLogSource::open(dir.path()),
+            Err(FpsSourceError::MangohudLogNotFound)
+        ));
+    }
+}
@@ -0,0 +1,90 @@
+//! MangoHud overlay text.
+//!
+//! MangoHud shows a command's output in its overlay (`exec=` in
+//! MangoHud.conf). With `mangohud_overlay` on, the daemon keeps a one-line
+//! file next to its socket current, e.g. `SR: 60 Hz (Dropping)`, so
+//! `exec=cat $XDG_RUNTIME_DIR/smart-refresh-overlay.txt` puts the rate and
+//! controller state in the overlay players already have open.
+
+use crate::persist::atomic_persist;
+use crate::units::Hertz;
+use std::path::{Path, PathBuf};
+
+/// Overlay file name inside the runtime directory.
+pub const OVERLAY_FILE_NAME: &str = "smart-refresh-overlay.txt";
+
+/// Overlay file path used when `XDG_RUNTIME_DIR` is not set.
+const FALLBACK_OVERLAY_PATH: &str = "/tmp/smart-refresh-overlay.txt";
+
+/// Default overlay file path: `$XDG_RUNTIME_DIR/smart-refresh-overlay.txt`,
+/// or /tmp if unset, like the socket.
+pub fn default_overlay_path() -> PathBuf {
+    match std::env::var_os("XDG_RUNTIME_DIR").map(PathBuf::from) {
+        Some(dir) if dir.is_absolute() => dir.join(OVERLAY_FILE_NAME),
+        _ => PathBuf::from(FALLBACK_OVERLAY_PATH),
+    }
+}
+
+/// Overlay line for the current rate and what the daemon is doing.
+pub fn overlay_text(hz: Hertz, state: &str) -> String {
+    format!("SR: {} Hz ({})", hz, state)
+}
+
+/// Keeps the overlay file in step with the daemon, writing only on change.
+pub struct OverlayWriter {
+    path: PathBuf,
+    last: Option<String>,
+}
+
+impl OverlayWriter {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path, last: None }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Replace the file's line with `text` if it changed.
+    pub fn update(&mut self, text: &str) -> std::io::Result<()> {
+        if self.last.as_deref() == Some(text) {
+            return Ok(());
+        }
+        atomic_persist(&self.path, format!("{}\n", text).as_bytes())?;
+        self.last = Some(text.to_string());
+        Ok(())
+    }
+
+    /// Remove the file, so MangoHud shows nothing once the overlay is
+    /// switched off or the daemon stops.
+    pub fn clear(&mut self) {
+        if self.last.take().is_some() {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_overlay_writer() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(OVERLAY_FILE_NAME);
+        let mut writer = OverlayWriter::new(path.clone());
+
+        writer.update(&overlay_text(Hertz(60), "Dropping")).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "SR: 60 Hz (Dropping)\n");
+
+        // Unchanged text is not rewritten
+        std::fs::remove_file(&path).unwrap();
+        writer.update(&overlay_text(Hertz(60), "Dropping")).unwrap();
+        assert!(!path.exists());
+
+        writer.update(&overlay_text(Hertz(90), "Stable")).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "SR: 90 Hz (Stable)\n");
+        writer.clear();
+        assert!(!path.exists());
+    }
+}
@@ -6,6 +6,7 @@ use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::RwLock;
 use std::time::{Duration, Instant};
 use serde::{Deserialize, Serialize};
+use crate::units::Hertz;
 
 /// Metrics data exposed via IPC
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -56,7 +57,7 @@ impl MetricsCollector {
     }
 
     /// Record a refresh rate switch
-    pub fn record_switch(&self, old_hz: u32, new_hz: u32) {
+    pub fn record_switch(&self, old_hz: Hertz, new_hz: Hertz) {
         let now = Instant::now();
         
         self.total_switches.fetch_add(1, Ordering::SeqCst);
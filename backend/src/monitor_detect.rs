@@ -1,8 +1,14 @@
 //! Multi-monitor detection for SmartRefresh daemon.
 //!
-//! Detects external displays and pauses SmartRefresh when connected.
+//! Detects external displays and pauses SmartRefresh when connected, or
+//! names the connected output so it can be controlled instead.
 
+use crate::config::DisplayDebounceConfig;
+use crate::drm_modes::PanelMode;
+use crate::units::Hertz;
+use serde::{Deserialize, Serialize};
 use std::path::Path;
+use std::time::{Duration, Instant};
 use tracing::{debug, warn};
 
 /// DRM connector paths to check
@@ -11,26 +17,107 @@ const DRM_PATH: &str = "/sys/class/drm";
 /// Connector types that indicate external displays
 const EXTERNAL_CONNECTORS: &[&str] = &["HDMI", "DP", "DisplayPort", "DVI", "VGA"];
 
+/// Connector types of built-in panels
+const INTERNAL_CONNECTORS: &[&str] = &["eDP", "DSI"];
+
+/// A display connector and whether it counts.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConnectorStatus {
+    /// Output name, e.g. "DP-1"
+    pub name: String,
+    /// DRM connector, e.g. "card0-DP-1"
+    pub connector: String,
+    pub connected: bool,
+    /// Built-in panel (eDP/DSI); never pauses the daemon
+    #[serde(default)]
+    pub internal: bool,
+    /// Listed in `ignored_connectors`, so it never pauses the daemon
+    pub ignored: bool,
+}
+
+/// A display in `GetStatus`: its connector, current mode and role.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DisplayStatus {
+    #[serde(flatten)]
+    pub connector: ConnectorStatus,
+    /// Mode it is scanned out at, `None` when off or unreadable
+    pub mode: Option<PanelMode>,
+    /// Its refresh rate is the one being switched
+    pub controlled: bool,
+    /// Counts as an external display that pauses switching
+    pub pausing: bool,
+}
+
+/// Build the `GetStatus` displays from the connectors and their current
+/// modes. `controlled` is the external output being switched (`None` for
+/// the panel), `paused` whether an external display paused switching; the
+/// controlled display's refresh is reported as `current_hz`.
+pub fn display_statuses(
+    connectors: &[ConnectorStatus],
+    modes: &[(String, PanelMode)],
+    controlled: Option<&str>,
+    paused: bool,
+    current_hz: Hertz,
+) -> Vec<DisplayStatus> {
+    connectors
+        .iter()
+        .map(|connector| {
+            let is_controlled = !paused
+                && connector.connected
+                && match controlled {
+                    Some(output) => connector.name == output,
+                    None => connector.internal,
+                };
+            let mode = modes
+                .iter()
+                .find(|(name, _)| *name == connector.name)
+                .map(|(_, mode)| PanelMode {
+                    refresh: if is_controlled { current_hz } else { mode.refresh },
+                    ..*mode
+                });
+            DisplayStatus {
+                connector: connector.clone(),
+                mode,
+                controlled: is_controlled,
+                pausing: paused && connector.connected && !connector.internal && !connector.ignored,
+            }
+        })
+        .collect()
+}
+
+/// Whether `ignored` lists a connector, by output name ("DP-1") or DRM
+/// connector name ("card0-DP-1").
+fn is_ignored(ignored: &[String], name: &str, connector: &str) -> bool {
+    ignored.iter().any(|entry| entry == name || entry == connector)
+}
+
 /// Monitor detector for external display detection
 pub struct MonitorDetector {
     /// Cached connector paths
     connector_paths: Vec<String>,
+    /// Cached built-in panel connector paths
+    internal_paths: Vec<String>,
 }
 
 impl MonitorDetector {
     pub fn new() -> Self {
-        let connector_paths = Self::find_external_connectors();
+        Self::with_drm_dir(Path::new(DRM_PATH))
+    }
+
+    /// Detector over the connectors in `drm_dir`.
+    pub fn with_drm_dir(drm_dir: &Path) -> Self {
+        let connector_paths = Self::find_connectors(drm_dir, EXTERNAL_CONNECTORS);
+        let internal_paths = Self::find_connectors(drm_dir, INTERNAL_CONNECTORS);
         debug!("Found {} potential external connector paths", connector_paths.len());
-        Self { connector_paths }
+        Self { connector_paths, internal_paths }
     }
 
-    /// Find all external connector paths in /sys/class/drm
-    fn find_external_connectors() -> Vec<String> {
+    /// Find the paths of connectors of `types` in `drm_path`
+    fn find_connectors(drm_path: &Path, types: &[&str]) -> Vec<String> {
         let mut paths = Vec::new();
         
-        let drm_path = Path::new(DRM_PATH);
         if !drm_path.exists() {
-            warn!("DRM path {} does not exist", DRM_PATH);
+            warn!("DRM path {} does not exist", drm_path.display());
             return paths;
         }
 
@@ -38,9 +125,11 @@ impl MonitorDetector {
             for entry in entries.flatten() {
                 let name = entry.file_name().to_string_lossy().to_string();
                 
-                // Check if this is an external connector type
-                for connector_type in EXTERNAL_CONNECTORS {
-                    if name.contains(connector_type) {
+                // Check the connector type ("card0-eDP-1" contains "DP" too,
+                // so match the type after the card prefix)
+                let output = name.split_once('-').map(|(_, output)| output).unwrap_or_default();
+                for connector_type in types {
+                    if output.starts_with(connector_type) {
                         let status_path = entry.path().join("status");
                         if status_path.exists() {
                             paths.push(status_path.to_string_lossy().to_string());
@@ -51,43 +140,136 @@ impl MonitorDetector {
             }
         }
 
+        paths.sort();
         paths
     }
 
-    /// Check if any external display is connected
-    pub async fn has_external_display(&self) -> bool {
-        for path in &self.connector_paths {
-            if let Ok(status) = tokio::fs::read_to_string(path).await {
-                let status = status.trim().to_lowercase();
-                if status == "connected" {
-                    debug!("External display detected at {}", path);
-                    return true;
-                }
+    /// Output name (e.g. "DP-1") of the first connected external display
+    /// that is not in `ignored`.
+    pub async fn connected_external(&self, ignored: &[String]) -> Option<String> {
+        for connector in self.connectors(ignored).await {
+            if connector.connected && !connector.internal && !connector.ignored {
+                debug!("External display detected at {}", connector.connector);
+                return Some(connector.name);
             }
         }
-        false
+        None
     }
 
-    /// Synchronous version for non-async contexts
-    pub fn has_external_display_sync(&self) -> bool {
-        for path in &self.connector_paths {
-            if let Ok(status) = std::fs::read_to_string(path) {
-                let status = status.trim().to_lowercase();
-                if status == "connected" {
-                    return true;
-                }
-            }
+    /// Every display connector with its status, built-in panels first.
+    pub async fn connectors(&self, ignored: &[String]) -> Vec<ConnectorStatus> {
+        let internal = self.internal_paths.iter().map(|path| (path, true));
+        let external = self.connector_paths.iter().map(|path| (path, false));
+        let mut connectors = Vec::with_capacity(self.internal_paths.len() + self.connector_paths.len());
+        for (path, is_internal) in internal.chain(external) {
+            let path = Path::new(path);
+            let (Some(name), Some(connector)) = (output_name(path), connector_name(path)) else {
+                continue;
+            };
+            let connected = tokio::fs::read_to_string(path)
+                .await
+                .is_ok_and(|status| status.trim().eq_ignore_ascii_case("connected"));
+            connectors.push(ConnectorStatus {
+                ignored: is_ignored(ignored, &name, &connector),
+                name,
+                connector,
+                connected,
+                internal: is_internal,
+            });
         }
-        false
+        connectors
     }
 }
 
+/// DRM connector of a `status` node: its directory name ("card0-DP-1").
+fn connector_name(status_path: &Path) -> Option<String> {
+    Some(status_path.parent()?.file_name()?.to_str()?.to_string())
+}
+
+/// Output name of a connector's `status` node: its directory without the
+/// card prefix ("card0-DP-1" -> "DP-1").
+fn output_name(status_path: &Path) -> Option<String> {
+    let connector = connector_name(status_path)?;
+    let (_, output) = connector.split_once('-')?;
+    Some(output.to_string())
+}
+
 impl Default for MonitorDetector {
     fn default() -> Self {
         Self::new()
     }
 }
 
+/// Debounces external display readings before they pause/resume the controller.
+///
+/// A reading that differs from the current state must repeat `readings`
+/// times in a row to be accepted, and a pause lasts at least
+/// `min_pause_secs` even if the display goes away sooner.
+#[derive(Debug, Clone)]
+pub struct DisplayDebouncer {
+    config: DisplayDebounceConfig,
+    /// Consecutive readings that differ from the current state
+    pending: u32,
+    /// When the last accepted pause started
+    paused_at: Option<Instant>,
+}
+
+impl DisplayDebouncer {
+    pub fn new(config: DisplayDebounceConfig) -> Self {
+        Self {
+            config,
+            pending: 0,
+            paused_at: None,
+        }
+    }
+
+    /// Replace the settings, keeping any pause in progress.
+    pub fn set_config(&mut self, config: DisplayDebounceConfig) {
+        self.config = config;
+    }
+
+    /// Feed a reading against the `current` detection state. Returns the
+    /// new state once a change is confirmed.
+    pub fn observe(&mut self, reading: bool, current: bool, now: Instant) -> Option<bool> {
+        if reading == current {
+            self.pending = 0;
+            return None;
+        }
+        self.pending = self.pending.saturating_add(1);
+        if self.pending < self.config.readings.max(1) {
+            return None;
+        }
+        if !reading && self.in_min_pause(now) {
+            return None;
+        }
+
+        self.pending = 0;
+        self.paused_at = reading.then_some(now);
+        Some(reading)
+    }
+
+    /// Whether a change has been seen but not yet confirmed.
+    pub fn is_settling(&self) -> bool {
+        self.pending > 0
+    }
+
+    /// Delay until the next reading: the settle delay while confirming a
+    /// change, `interval` otherwise.
+    pub fn next_check(&self, interval: Duration) -> Duration {
+        if self.is_settling() {
+            Duration::from(self.config.settle_ms).min(interval)
+        } else {
+            interval
+        }
+    }
+
+    fn in_min_pause(&self, now: Instant) -> bool {
+        self.paused_at.is_some_and(|at| {
+            now.saturating_duration_since(at) < Duration::from(self.config.min_pause_secs)
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -96,7 +278,135 @@ mod tests {
     fn test_monitor_detector_creation() {
         let detector = MonitorDetector::new();
         // Should not panic even if DRM path doesn't exist
-        assert!(detector.connector_paths.len() >= 0);
+        assert!(detector.connector_paths.iter().all(|p| p.ends_with("status")));
+    }
+
+    #[test]
+    fn test_output_name() {
+        assert_eq!(
+            output_name(Path::new("/sys/class/drm/card0-DP-1/status")).as_deref(),
+            Some("DP-1")
+        );
+        assert_eq!(
+            output_name(Path::new("/sys/class/drm/card1-HDMI-A-1/status")).as_deref(),
+            Some("HDMI-A-1")
+        );
+        assert_eq!(output_name(Path::new("/sys/class/drm/card0/status")), None);
+    }
+
+    #[tokio::test]
+    async fn test_ignored_connectors() {
+        let dir = tempfile::tempdir().unwrap();
+        for (connector, status) in [("card0-DP-1", "connected\n"), ("card0-HDMI-A-1", "disconnected\n")] {
+            std::fs::create_dir(dir.path().join(connector)).unwrap();
+            std::fs::write(dir.path().join(connector).join("status"), status).unwrap();
+        }
+        std::fs::create_dir(dir.path().join("card0-eDP-1")).unwrap();
+        std::fs::write(dir.path().join("card0-eDP-1").join("status"), "connected\n").unwrap();
+
+        // The internal eDP panel is never external
+        let detector = MonitorDetector::with_drm_dir(dir.path());
+        assert_eq!(detector.connector_paths.len(), 2);
+        assert_eq!(detector.connected_external(&[]).await.as_deref(), Some("DP-1"));
+        assert_eq!(detector.connected_external(&["DP-1".to_string()]).await, None);
+        assert_eq!(detector.connected_external(&["card0-DP-1".to_string()]).await, None);
+
+        let connectors = detector.connectors(&["DP-1".to_string()]).await;
+        assert_eq!(
+            connectors,
+            [
+                ConnectorStatus {
+                    name: "eDP-1".to_string(),
+                    connector: "card0-eDP-1".to_string(),
+                    connected: true,
+                    internal: true,
+                    ignored: false,
+                },
+                ConnectorStatus {
+                    name: "DP-1".to_string(),
+                    connector: "card0-DP-1".to_string(),
+                    connected: true,
+                    internal: false,
+                    ignored: true,
+                },
+                ConnectorStatus {
+                    name: "HDMI-A-1".to_string(),
+                    connector: "card0-HDMI-A-1".to_string(),
+                    connected: false,
+                    internal: false,
+                    ignored: false,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_display_statuses() {
+        let connector = |name: &str, connected, internal| ConnectorStatus {
+            name: name.to_string(),
+            connector: format!("card0-{}", name),
+            connected,
+            internal,
+            ignored: false,
+        };
+        let connectors = [connector("eDP-1", true, true), connector("DP-1", true, false)];
+        let mode = |refresh| PanelMode { width: 800, height: 1280, refresh: Hertz(refresh), preferred: true };
+        let modes = [("eDP-1".to_string(), mode(90))];
+
+        let displays = display_statuses(&connectors, &modes, None, false, Hertz(60));
+        assert!(displays[0].controlled && !displays[1].controlled);
+        assert_eq!(displays[0].mode.unwrap().refresh, Hertz(60));
+        assert_eq!(displays[1].mode, None);
+
+        let displays = display_statuses(&connectors, &modes, None, true, Hertz(60));
+        assert!(!displays[0].controlled && !displays[0].pausing);
+        assert!(displays[1].pausing);
+        assert_eq!(displays[0].mode.unwrap().refresh, Hertz(90));
+
+        let displays = display_statuses(&connectors, &modes, Some("DP-1"), false, Hertz(120));
+        assert!(!displays[0].controlled && displays[1].controlled);
+    }
+
+    fn debouncer() -> DisplayDebouncer {
+        DisplayDebouncer::new(DisplayDebounceConfig {
+            readings: 3,
+            settle_ms: crate::units::Millis(2000),
+            min_pause_secs: crate::units::Secs(30),
+        })
+    }
+
+    #[test]
+    fn test_debounce_needs_consecutive_readings() {
+        let mut debouncer = debouncer();
+        let now = Instant::now();
+        assert_eq!(debouncer.observe(true, false, now), None);
+        assert!(debouncer.is_settling());
+        assert_eq!(debouncer.next_check(Duration::from_secs(10)), Duration::from_secs(2));
+
+        // A bounce back resets the count
+        assert_eq!(debouncer.observe(false, false, now), None);
+        assert!(!debouncer.is_settling());
+
+        assert_eq!(debouncer.observe(true, false, now), None);
+        assert_eq!(debouncer.observe(true, false, now), None);
+        assert_eq!(debouncer.observe(true, false, now), Some(true));
+        assert!(!debouncer.is_settling());
+    }
+
+    #[test]
+    fn test_debounce_min_pause() {
+        let mut debouncer = debouncer();
+        let now = Instant::now();
+        for _ in 0..3 {
+            debouncer.observe(true, false, now);
+        }
+
+        // Unplugged right away: stays paused until min_pause_secs
+        let soon = now + Duration::from_secs(10);
+        for _ in 0..5 {
+            assert_eq!(debouncer.observe(false, true, soon), None);
+        }
+        assert_eq!(debouncer.observe(false, true, now + Duration::from_secs(30)), Some(false));
     }
 
     #[test]
@@ -0,0 +1,341 @@
+//! MQTT status publishing (enabled with the `mqtt` feature).
+//!
+//! Publishes current Hz, FPS, power draw and algorithm state to a broker so
+//! home automation (e.g. Home Assistant) can react to gaming activity.
+//! Only the small subset of MQTT 3.1.1 needed for QoS 0 publishing is
+//! implemented: CONNECT/CONNACK, PUBLISH and DISCONNECT.
+
+use crate::config::MqttConfig;
+use crate::error::MqttError;
+use crate::ipc_server::DaemonState;
+use crate::units::Secs;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::sync::watch;
+use tracing::{debug, info, warn};
+
+/// Maximum value of the MQTT variable-length "remaining length" field
+const MAX_REMAINING_LENGTH: usize = 268_435_455;
+
+/// Timeout for connecting and waiting for CONNACK
+const CONNECT_TIMEOUT_SECS: u64 = 5;
+
+/// Delay before reconnecting after a broker error
+const RECONNECT_DELAY_SECS: u64 = 30;
+
+const PACKET_CONNECT: u8 = 0x10;
+const PACKET_CONNACK: u8 = 0x20;
+const PACKET_PUBLISH: u8 = 0x30;
+const PACKET_DISCONNECT: u8 = 0xE0;
+
+/// PUBLISH fixed-header flag for retained messages
+const FLAG_RETAIN: u8 = 0x01;
+
+/// Encode the variable-length "remaining length" field.
+fn encode_remaining_length(mut len: usize, out: &mut Vec<u8>) -> Result<(), MqttError> {
+    if len > MAX_REMAINING_LENGTH {
+        return Err(MqttError::PacketTooLarge(len));
+    }
+    loop {
+        let mut byte = (len % 128) as u8;
+        len /= 128;
+        if len > 0 {
+            byte |= 0x80;
+        }
+        out.push(byte);
+        if len == 0 {
+            return Ok(());
+        }
+    }
+}
+
+/// Append a length-prefixed UTF-8 string or binary field.
+fn encode_bytes(data: &[u8], out: &mut Vec<u8>) -> Result<(), MqttError> {
+    let len = u16::try_from(data.len()).map_err(|_| MqttError::PacketTooLarge(data.len()))?;
+    out.extend_from_slice(&len.to_be_bytes());
+    out.extend_from_slice(data);
+    Ok(())
+}
+
+/// Prepend the fixed header to a packet body.
+fn finish_packet(header: u8, body: Vec<u8>) -> Result<Vec<u8>, MqttError> {
+    let mut packet = Vec::with_capacity(body.len() + 5);
+    packet.push(header);
+    encode_remaining_length(body.len(), &mut packet)?;
+    packet.extend_from_slice(&body);
+    Ok(packet)
+}
+
+/// Build a CONNECT packet with a retained "offline" last will on the availability topic.
+fn encode_connect(config: &MqttConfig, keep_alive_secs: u16) -> Result<Vec<u8>, MqttError> {
+    let mut body = Vec::new();
+    encode_bytes(b"MQTT", &mut body)?;
+    body.push(4); // protocol level 3.1.1
+
+    // Clean session, will flag, will retain (will QoS 0)
+    let mut flags = 0x02 | 0x04 | 0x20;
+    if config.username.is_some() {
+        flags |= 0x80;
+    }
+    if config.password.is_some() {
+        flags |= 0x40;
+    }
+    body.push(flags);
+    body.extend_from_slice(&keep_alive_secs.to_be_bytes());
+
+    encode_bytes(config.client_id.as_bytes(), &mut body)?;
+    encode_bytes(availability_topic(config).as_bytes(), &mut body)?;
+    encode_bytes(b"offline", &mut body)?;
+    if let Some(username) = &config.username {
+        encode_bytes(username.as_bytes(), &mut body)?;
+    }
+    if let Some(password) = &config.password {
+        encode_bytes(password.as_bytes(), &mut body)?;
+    }
+
+    finish_packet(PACKET_CONNECT, body)
+}
+
+/// Build a QoS 0 PUBLISH packet.
+fn encode_publish(topic: &str, payload: &[u8], retain: bool) -> Result<Vec<u8>, MqttError> {
+    let mut body = Vec::with_capacity(topic.len() + payload.len() + 2);
+    encode_bytes(topic.as_bytes(), &mut body)?;
+    body.extend_from_slice(payload);
+
+    let header = if retain { PACKET_PUBLISH | FLAG_RETAIN } else { PACKET_PUBLISH };
+    finish_packet(header, body)
+}
+
+fn availability_topic(config: &MqttConfig) -> String {
+    format!("{}/availability", config.topic_prefix)
+}
+
+/// Connected MQTT client that can publish QoS 0 messages.
+pub struct MqttPublisher {
+    stream: TcpStream,
+    topic_prefix: String,
+}
+
+impl MqttPublisher {
+    /// Connect to the broker and wait for a successful CONNACK.
+    pub async fn connect(config: &MqttConfig) -> Result<Self, MqttError> {
+        let addr = format!("{}:{}", config.host, config.port);
+        let timeout = Duration::from_secs(CONNECT_TIMEOUT_SECS);
+        let connect_err = |source| MqttError::ConnectFailed { addr: addr.clone(), source };
+
+        let mut stream = tokio::time::timeout(timeout, TcpStream::connect(&addr))
+            .await
+            .map_err(|_| connect_err(std::io::ErrorKind::TimedOut.into()))?
+            .map_err(connect_err)?;
+
+        // Any packet resets the keep-alive timer, so allow a few missed publishes
+        let keep_alive = u16::try_from(config.interval_secs.get().saturating_mul(3)).unwrap_or(u16::MAX);
+        stream.write_all(&encode_connect(config, keep_alive)?).await?;
+
+        let mut connack = [0u8; 4];
+        tokio::time::timeout(timeout, stream.read_exact(&mut connack))
+            .await
+            .map_err(|_| connect_err(std::io::ErrorKind::TimedOut.into()))??;
+        if connack[0] != PACKET_CONNACK || connack[1] != 2 {
+            return Err(MqttError::UnexpectedPacket(connack[0]));
+        }
+        if connack[3] != 0 {
+            return Err(MqttError::ConnectionRefused(connack[3]));
+        }
+
+        let mut publisher = Self {
+            stream,
+            topic_prefix: config.topic_prefix.clone(),
+        };
+        publisher
+            .publish_raw(&availability_topic(config), b"online", true)
+            .await?;
+        Ok(publisher)
+    }
+
+    async fn publish_raw(&mut self, topic: &str, payload: &[u8], retain: bool) -> Result<(), MqttError> {
+        self.stream.write_all(&encode_publish(topic, payload, retain)?).await?;
+        Ok(())
+    }
+
+    /// Publish a retained value to `<prefix>/<name>`.
+    pub async fn publish(&mut self, name: &str, payload: &str) -> Result<(), MqttError> {
+        let topic = format!("{}/{}", self.topic_prefix, name);
+        self.publish_raw(&topic, payload.as_bytes(), true).await
+    }
+
+    /// Publish the current daemon status.
+    pub async fn publish_status(&mut self, state: &DaemonState) -> Result<(), MqttError> {
+        let status = state.get_status().await;
+        let battery = state.battery_monitor.get_status();
+
+        self.publish("hz", &status.current_hz.to_string()).await?;
+        self.publish("fps", &format!("{:.1}", status.current_fps)).await?;
+        self.publish("state", &status.state).await?;
+        if battery.available {
+            self.publish("watts", &format!("{:.2}", battery.power_watts)).await?;
+        }
+        Ok(())
+    }
+
+    /// Mark the daemon offline and disconnect cleanly.
+    pub async fn disconnect(mut self, config: &MqttConfig) -> Result<(), MqttError> {
+        self.publish_raw(&availability_topic(config), b"offline", true).await?;
+        self.stream.write_all(&[PACKET_DISCONNECT, 0]).await?;
+        self.stream.shutdown().await?;
+        Ok(())
+    }
+}
+
+/// Publish status periodically until shutdown, reconnecting on errors.
+pub async fn run_mqtt_publisher(
+    state: Arc<DaemonState>,
+    config: MqttConfig,
+    mut shutdown_rx: watch::Receiver<bool>,
+) {
+    let interval = Duration::from(config.interval_secs.max(Secs(1)));
+    let reconnect_delay = Duration::from_secs(RECONNECT_DELAY_SECS);
+
+    while !*shutdown_rx.borrow() {
+        let mut publisher = match MqttPublisher::connect(&config).await {
+            Ok(publisher) => {
+                info!("MQTT connected to {}:{}", config.host, config.port);
+                publisher
+            }
+            Err(e) => {
+                warn!("MQTT connection failed: {}, retrying in {}s", e, RECONNECT_DELAY_SECS);
+                tokio::select! {
+                    _ = shutdown_rx.changed() => {}
+                    _ = tokio::time::sleep(reconnect_delay) => {}
+                }
+                continue;
+            }
+        };
+
+        loop {
+            if let Err(e) = publisher.publish_status(&state).await {
+                warn!("MQTT publish failed: {}, reconnecting", e);
+                break;
+            }
+
+            tokio::select! {
+                _ = shutdown_rx.changed() => {
+                    if *shutdown_rx.borrow() {
+                        if let Err(e) = publisher.disconnect(&config).await {
+                            debug!("MQTT disconnect failed: {}", e);
+                        }
+                        info!("MQTT publisher shutting down");
+                        return;
+                    }
+                }
+                _ = tokio::time::sleep(interval) => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    fn test_config(port: u16) -> MqttConfig {
+        MqttConfig {
+            host: "127.0.0.1".to_string(),
+            port,
+            topic_prefix: "deck".to_string(),
+            client_id: "test".to_string(),
+            username: None,
+            password: None,
+            interval_secs: Secs(10),
+        }
+    }
+
+    #[test]
+    fn test_remaining_length_encoding() {
+        let encode = |len| {
+            let mut out = Vec::new();
+            encode_remaining_length(len, &mut out).unwrap();
+            out
+        };
+        assert_eq!(encode(0), vec![0x00]);
+        assert_eq!(encode(127), vec![0x7F]);
+        assert_eq!(encode(128), vec![0x80, 0x01]);
+        assert_eq!(encode(16_383), vec![0xFF, 0x7F]);
+        assert_eq!(encode(MAX_REMAINING_LENGTH), vec![0xFF, 0xFF, 0xFF, 0x7F]);
+        assert!(encode_remaining_length(MAX_REMAINING_LENGTH + 1, &mut Vec::new()).is_err());
+    }
+
+    #[test]
+    fn test_publish_packet() {
+        let packet = encode_publish("deck/hz", b"60", true).unwrap();
+        assert_eq!(packet, b"\x31\x0b\x00\x07deck/hz60");
+    }
+
+    #[test]
+    fn test_connect_packet_flags() {
+        let mut config = test_config(1883);
+        let packet = encode_connect(&config, 30).unwrap();
+        // Fixed header, remaining length, "MQTT" name, level 4, flags
+        assert_eq!(&packet[..2], &[PACKET_CONNECT, (packet.len() - 2) as u8]);
+        assert_eq!(&packet[2..9], b"\x00\x04MQTT\x04");
+        assert_eq!(packet[9], 0x26);
+        assert_eq!(&packet[10..12], &30u16.to_be_bytes());
+
+        config.username = Some("user".to_string());
+        config.password = Some("pass".to_string());
+        let packet = encode_connect(&config, 30).unwrap();
+        assert_eq!(packet[9], 0xE6);
+        assert!(packet.ends_with(b"\x00\x04user\x00\x04pass"));
+    }
+
+    #[tokio::test]
+    async fn test_connect_and_publish() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let broker = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut header = [0u8; 2];
+            socket.read_exact(&mut header).await.unwrap();
+            assert_eq!(header[0], PACKET_CONNECT);
+            let mut connect = vec![0u8; header[1] as usize];
+            socket.read_exact(&mut connect).await.unwrap();
+            socket.write_all(&[PACKET_CONNACK, 2, 0, 0]).await.unwrap();
+
+            let mut received = Vec::new();
+            socket.read_to_end(&mut received).await.unwrap();
+            received
+        });
+
+        let config = test_config(port);
+        let mut publisher = MqttPublisher::connect(&config).await.unwrap();
+        publisher.publish("hz", "45").await.unwrap();
+        publisher.disconnect(&config).await.unwrap();
+
+        let mut expected = encode_publish("deck/availability", b"online", true).unwrap();
+        expected.extend(encode_publish("deck/hz", b"45", true).unwrap());
+        expected.extend(encode_publish("deck/availability", b"offline", true).unwrap());
+        expected.extend([PACKET_DISCONNECT, 0]);
+        assert_eq!(broker.await.unwrap(), expected);
+    }
+
+    #[tokio::test]
+    async fn test_connect_refused() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 256];
+            let _ = socket.read(&mut buf).await;
+            // Return code 5: not authorized
+            socket.write_all(&[PACKET_CONNACK, 2, 0, 5]).await.unwrap();
+        });
+
+        let result = MqttPublisher::connect(&test_config(port)).await;
+        assert!(matches!(result, Err(MqttError::ConnectionRefused(5))));
+    }
+}
@@ -0,0 +1,294 @@
+//! Event severity model and per-event notification routing.
+//!
+//! Every noteworthy occurrence is raised as a [`DaemonEvent`] with a
+//! severity. Routing decides what happens to it: log only, haptic/sound
+//! feedback via the feedback command, a silent frontend event, or a
+//! frontend notification (toast). Users override the built-in routes per
+//! event kind or per severity in the `notifications` config section.
+
+use crate::config::NotificationConfig;
+use crate::feedback::FeedbackHook;
+use crate::units::Hertz;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+/// Events kept for the frontend to poll
+const MAX_EVENT_HISTORY: usize = 50;
+
+/// How much an event deserves the user's attention.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Info,
+    Warning,
+    Critical,
+}
+
+/// Kinds of events raised by the daemon.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    /// Refresh rate dropped to the configured minimum
+    Eco,
+    /// Refresh rate returned to the configured maximum
+    Turbo,
+    /// A manual refresh rate override engaged
+    Override,
+    /// An external display was connected and control paused
+    ExternalDisplay,
+    /// MangoHud shared memory went away
+    MangohudLost,
+    /// A new long-term drift advisory
+    Advisory,
+    /// The daemon entered deep sleep
+    DeepSleep,
+    /// The APU reached the thermal limit or cooled down again
+    Thermal,
+}
+
+impl EventKind {
+    /// Built-in severity for this kind.
+    pub fn severity(&self) -> Severity {
+        match self {
+            EventKind::Eco | EventKind::Turbo | EventKind::Override => Severity::Info,
+            EventKind::Advisory | EventKind::DeepSleep => Severity::Info,
+            EventKind::ExternalDisplay | EventKind::MangohudLost | EventKind::Thermal => Severity::Warning,
+        }
+    }
+
+    /// Name used in config routes and passed to the feedback command.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventKind::Eco => "eco",
+            EventKind::Turbo => "turbo",
+            EventKind::Override => "override",
+            EventKind::ExternalDisplay => "external_display",
+            EventKind::MangohudLost => "mangohud_lost",
+            EventKind::Advisory => "advisory",
+            EventKind::DeepSleep => "deep_sleep",
+            EventKind::Thermal => "thermal",
+        }
+    }
+
+    /// Classify a refresh rate transition against the configured range.
+    pub fn for_transition(old_hz: Hertz, new_hz: Hertz, min_hz: Hertz, max_hz: Hertz) -> Option<Self> {
+        if old_hz == new_hz {
+            None
+        } else if new_hz <= min_hz {
+            Some(EventKind::Eco)
+        } else if new_hz >= max_hz {
+            Some(EventKind::Turbo)
+        } else {
+            None
+        }
+    }
+
+    /// Built-in routes, used when the config has no override.
+    fn default_routes(&self) -> Vec<Route> {
+        match self {
+            // Mode changes keep the feedback command behaviour
+            EventKind::Eco | EventKind::Turbo | EventKind::Override => vec![Route::Log, Route::Haptic],
+            _ => default_severity_routes(self.severity()),
+        }
+    }
+}
+
+/// Where an event is delivered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Route {
+    /// Write to the daemon log
+    Log,
+    /// Show a notification in the frontend
+    Notify,
+    /// Run the feedback command
+    Haptic,
+    /// Record for the frontend without interrupting
+    Frontend,
+}
+
+fn default_severity_routes(severity: Severity) -> Vec<Route> {
+    match severity {
+        Severity::Info => vec![Route::Log],
+        Severity::Warning => vec![Route::Log, Route::Frontend],
+        Severity::Critical => vec![Route::Log, Route::Notify],
+    }
+}
+
+/// Routes for `kind`: per-kind config, then per-severity config, then built-ins.
+pub fn routes_for(config: Option<&NotificationConfig>, kind: EventKind) -> Vec<Route> {
+    config
+        .and_then(|c| {
+            c.routes
+                .get(&kind)
+                .or_else(|| c.severity_routes.get(&kind.severity()))
+                .cloned()
+        })
+        .unwrap_or_else(|| kind.default_routes())
+}
+
+/// An event recorded for the frontend.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DaemonEvent {
+    /// Increasing id, for polling with `since`
+    pub id: u64,
+    pub timestamp: String,
+    pub kind: EventKind,
+    pub severity: Severity,
+    pub message: String,
+    /// Whether the frontend should show a notification
+    pub notify: bool,
+}
+
+/// Dispatches events to their routes and keeps frontend history.
+#[derive(Default)]
+pub struct Notifications {
+    feedback: FeedbackHook,
+    history: Mutex<VecDeque<DaemonEvent>>,
+    next_id: Mutex<u64>,
+}
+
+impl Notifications {
+    /// Raise an event. `hz` is passed to the feedback command.
+    pub fn raise(
+        &self,
+        config: Option<&NotificationConfig>,
+        feedback_command: Option<&str>,
+        kind: EventKind,
+        message: String,
+        hz: Hertz,
+    ) {
+        let routes = routes_for(config, kind);
+        let severity = kind.severity();
+
+        if routes.contains(&Route::Log) {
+            match severity {
+                Severity::Info => tracing::info!("{}", message),
+                Severity::Warning => tracing::warn!("{}", message),
+                Severity::Critical => tracing::error!("{}", message),
+            }
+        }
+
+        if routes.contains(&Route::Haptic) {
+            self.feedback.fire(feedback_command, kind, hz);
+        }
+
+        let notify = routes.contains(&Route::Notify);
+        if notify || routes.contains(&Route::Frontend) {
+            self.record(DaemonEvent {
+                id: 0,
+                timestamp: crate::ipc_server::chrono_lite_timestamp(),
+                kind,
+                severity,
+                message,
+                notify,
+            });
+        }
+    }
+
+    fn record(&self, mut event: DaemonEvent) {
+        let (Ok(mut history), Ok(mut next_id)) = (self.history.lock(), self.next_id.lock()) else {
+            return;
+        };
+        *next_id += 1;
+        event.id = *next_id;
+        if history.len() >= MAX_EVENT_HISTORY {
+            history.pop_front();
+        }
+        history.push_back(event);
+    }
+
+    /// Recorded events with an id greater than `since`, oldest first.
+    pub fn events_since(&self, since: Option<u64>) -> Vec<DaemonEvent> {
+        let since = since.unwrap_or(0);
+        self.history
+            .lock()
+            .map(|h| h.iter().filter(|e| e.id > since).cloned().collect())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transition_classification() {
+        let transition = |old, new| EventKind::for_transition(Hertz(old), Hertz(new), Hertz(40), Hertz(90));
+        assert_eq!(transition(60, 40), Some(EventKind::Eco));
+        assert_eq!(transition(40, 90), Some(EventKind::Turbo));
+        assert_eq!(transition(90, 60), None);
+        assert_eq!(transition(40, 40), None);
+    }
+
+    #[test]
+    fn test_names_match_config_keys() {
+        for kind in [EventKind::Eco, EventKind::ExternalDisplay, EventKind::DeepSleep, EventKind::Thermal] {
+            assert_eq!(serde_json::to_value(kind).unwrap(), kind.as_str());
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_haptic_route_runs_for_any_kind() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("event");
+        let command = format!("echo \"$SMART_REFRESH_EVENT\" > {}", out.display());
+        let config = NotificationConfig {
+            routes: [(EventKind::MangohudLost, vec![Route::Haptic])].into_iter().collect(),
+            ..Default::default()
+        };
+
+        let notifications = Notifications::default();
+        notifications.raise(Some(&config), Some(&command), EventKind::MangohudLost, "lost".to_string(), Hertz(60));
+        for _ in 0..50 {
+            if out.exists() && !std::fs::read_to_string(&out).unwrap().is_empty() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        assert_eq!(std::fs::read_to_string(&out).unwrap().trim(), "mangohud_lost");
+    }
+
+    #[test]
+    fn test_route_resolution_order() {
+        assert_eq!(routes_for(None, EventKind::Eco), vec![Route::Log, Route::Haptic]);
+        assert_eq!(
+            routes_for(None, EventKind::ExternalDisplay),
+            vec![Route::Log, Route::Frontend]
+        );
+
+        let config = NotificationConfig {
+            routes: [(EventKind::Eco, vec![Route::Log])].into_iter().collect(),
+            severity_routes: [(Severity::Warning, vec![Route::Notify])].into_iter().collect(),
+        };
+        assert_eq!(routes_for(Some(&config), EventKind::Eco), vec![Route::Log]);
+        assert_eq!(routes_for(Some(&config), EventKind::MangohudLost), vec![Route::Notify]);
+        assert_eq!(routes_for(Some(&config), EventKind::Turbo), vec![Route::Log, Route::Haptic]);
+    }
+
+    #[test]
+    fn test_frontend_history() {
+        let notifications = Notifications::default();
+        let config = NotificationConfig {
+            routes: [(EventKind::Advisory, vec![Route::Notify])].into_iter().collect(),
+            ..Default::default()
+        };
+
+        // Log-only events are not recorded
+        notifications.raise(None, None, EventKind::DeepSleep, "sleeping".to_string(), Hertz(0));
+        notifications.raise(None, None, EventKind::MangohudLost, "lost".to_string(), Hertz(0));
+        notifications.raise(Some(&config), None, EventKind::Advisory, "fps down".to_string(), Hertz(0));
+
+        let events = notifications.events_since(None);
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].kind, EventKind::MangohudLost);
+        assert!(!events[0].notify);
+        assert!(events[1].notify);
+
+        let newer = notifications.events_since(Some(events[0].id));
+        assert_eq!(newer.len(), 1);
+        assert_eq!(newer[0].message, "fps down");
+    }
+}
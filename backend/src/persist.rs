@@ -0,0 +1,73 @@
+//! Crash-resistant file persistence.
+//!
+//! The Deck can lose power at any moment, so state files are written to a
+//! temp file, fsynced, renamed over the target, and the directory is fsynced
+//! so the rename itself is durable.
+
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Atomically replace `path` with `contents`, creating parent directories.
+///
+/// Readers see either the old file or the new one, never a partial write.
+pub fn atomic_persist(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let parent = match path.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p,
+        _ => Path::new("."),
+    };
+    fs::create_dir_all(parent)?;
+
+    let temp_path = temp_path_for(path);
+    let result = write_synced(&temp_path, contents).and_then(|()| fs::rename(&temp_path, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+        return result;
+    }
+
+    // Persist the directory entry for the rename
+    File::open(parent)?.sync_all()
+}
+
+/// Temp file next to `path`, e.g. `config.json` -> `config.json.tmp`.
+fn temp_path_for(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".tmp");
+    path.with_file_name(name)
+}
+
+fn write_synced(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let mut file = File::create(path)?;
+    file.write_all(contents)?;
+    file.sync_all()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_replaces_file_and_leaves_no_temp() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("nested").join("state.json");
+
+        atomic_persist(&path, b"first").unwrap();
+        atomic_persist(&path, b"second").unwrap();
+
+        assert_eq!(fs::read(&path).unwrap(), b"second");
+        assert!(!temp_path_for(&path).exists());
+    }
+
+    #[test]
+    fn test_failed_rename_keeps_old_contents() {
+        let dir = tempdir().unwrap();
+        // A non-empty directory cannot be replaced by a file
+        let path = dir.path().join("target");
+        fs::create_dir_all(path.join("child")).unwrap();
+
+        assert!(atomic_persist(&path, b"data").is_err());
+        assert!(path.join("child").is_dir());
+        assert!(!temp_path_for(&path).exists());
+    }
+}
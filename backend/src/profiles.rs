@@ -1,13 +1,135 @@
 //! Per-game profile management for SmartRefresh daemon.
 //!
 //! Stores and loads game-specific settings based on Steam AppID.
+//!
+//! Access to profiles.json is guarded by an advisory `flock` on a sidecar
+//! lock file, so a CLI instance and the daemon can't interleave writes.
 
 use crate::core_logic::Sensitivity;
+use crate::error::ProfileError;
+use crate::persist::atomic_persist;
+use crate::units::{Hertz, Secs};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 use tracing::{info, warn};
 
+/// How long to wait for another process to release the profiles lock
+const LOCK_TIMEOUT_MS: u64 = 500;
+
+/// Delay between lock attempts
+const LOCK_RETRY_MS: u64 = 25;
+
+/// Load attempts at startup while another process holds the lock
+const LOAD_ATTEMPTS: u32 = 4;
+
+/// Backoff before the second load attempt, doubled after each one
+const LOAD_BACKOFF_MS: u64 = 250;
+
+/// Advisory lock on a profiles file, released on drop.
+pub struct ProfileLock {
+    _file: File,
+}
+
+impl ProfileLock {
+    /// Lock `<profiles path>.lock`, shared for reads or exclusive for writes.
+    ///
+    /// Retries until `LOCK_TIMEOUT_MS` and then reports contention. This
+    /// blocks the thread, so async code takes it on a blocking thread.
+    pub fn acquire(profiles_path: &Path, exclusive: bool) -> Result<Self, ProfileError> {
+        Self::acquire_with_timeout(profiles_path, exclusive, Duration::from_millis(LOCK_TIMEOUT_MS))
+    }
+
+    fn acquire_with_timeout(
+        profiles_path: &Path,
+        exclusive: bool,
+        timeout: Duration,
+    ) -> Result<Self, ProfileError> {
+        // Exclusive locks are taken to save, shared ones to load
+        let fail = |message: String| {
+            if exclusive {
+                ProfileError::SaveFailed(message)
+            } else {
+                ProfileError::LoadFailed(message)
+            }
+        };
+
+        let lock_path = profiles_path.with_extension("json.lock");
+        let file = File::options()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&lock_path)
+            .map_err(|e| fail(format!("Failed to open lock file: {}", e)))?;
+
+        let deadline = Instant::now() + timeout;
+        loop {
+            let locked = try_flock(&file, exclusive)
+                .map_err(|e| fail(format!("Failed to lock profiles: {}", e)))?;
+            if locked {
+                return Ok(Self { _file: file });
+            }
+            if Instant::now() >= deadline {
+                return Err(ProfileError::LockContended {
+                    path: profiles_path.display().to_string(),
+                });
+            }
+            std::thread::sleep(Duration::from_millis(LOCK_RETRY_MS));
+        }
+    }
+}
+
+/// Try to take the lock without blocking. Returns false if it is held elsewhere.
+#[cfg(unix)]
+fn try_flock(file: &File, exclusive: bool) -> std::io::Result<bool> {
+    use std::os::unix::io::AsRawFd;
+
+    let operation = if exclusive { libc::LOCK_EX } else { libc::LOCK_SH };
+    // SAFETY: the descriptor is owned by `file` and stays open for the call
+    let result = unsafe { libc::flock(file.as_raw_fd(), operation | libc::LOCK_NB) };
+    if result == 0 {
+        return Ok(true);
+    }
+
+    let err = std::io::Error::last_os_error();
+    if err.kind() == std::io::ErrorKind::WouldBlock {
+        Ok(false)
+    } else {
+        Err(err)
+    }
+}
+
+#[cfg(not(unix))]
+fn try_flock(_file: &File, _exclusive: bool) -> std::io::Result<bool> {
+    Ok(true)
+}
+
+/// Whether the daemon switches refresh rates while a game runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GameSwitching {
+    /// Switch, unless `whitelist_only` is set
+    #[default]
+    Auto,
+    /// Always switch (whitelisted)
+    Enabled,
+    /// Never switch, hold the maximum rate (blacklisted)
+    Disabled,
+}
+
+impl GameSwitching {
+    /// Whether switching runs for a game with this setting.
+    pub fn allows(self, whitelist_only: bool) -> bool {
+        match self {
+            GameSwitching::Auto => !whitelist_only,
+            GameSwitching::Enabled => true,
+            GameSwitching::Disabled => false,
+        }
+    }
+}
+
 /// Profile configuration for a specific game
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct GameProfile {
@@ -16,18 +138,33 @@ pub struct GameProfile {
     /// Game name (for display)
     pub name: String,
     /// Minimum refresh rate
-    pub min_hz: u32,
+    pub min_hz: Hertz,
     /// Maximum refresh rate
-    pub max_hz: u32,
+    pub max_hz: Hertz,
     /// Sensitivity preset
     pub sensitivity: String,
     /// Whether adaptive sensitivity is enabled
     #[serde(default)]
     pub adaptive_sensitivity: bool,
+    /// Sensitivity was guessed from the first session and not yet confirmed
+    #[serde(default)]
+    pub suggested: bool,
+    /// Minimum dwell time per refresh level, overriding the global setting
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_dwell_secs: Option<Secs>,
+    /// Shell command run (sandboxed) when the game starts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_launch: Option<String>,
+    /// Shell command run (sandboxed) when the game stops
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_exit: Option<String>,
+    /// Blacklist or whitelist the game for switching
+    #[serde(default)]
+    pub switching: GameSwitching,
 }
 
 impl GameProfile {
-    pub fn new(app_id: String, name: String, min_hz: u32, max_hz: u32, sensitivity: String) -> Self {
+    pub fn new(app_id: String, name: String, min_hz: Hertz, max_hz: Hertz, sensitivity: String) -> Self {
         Self {
             app_id,
             name,
@@ -35,21 +172,70 @@ impl GameProfile {
             max_hz,
             sensitivity,
             adaptive_sensitivity: false,
+            suggested: false,
+            min_dwell_secs: None,
+            on_launch: None,
+            on_exit: None,
+            switching: GameSwitching::Auto,
         }
     }
 
     /// Parse sensitivity string to enum
     pub fn get_sensitivity(&self) -> Sensitivity {
-        match self.sensitivity.to_lowercase().as_str() {
-            "conservative" => Sensitivity::Conservative,
-            "aggressive" => Sensitivity::Aggressive,
-            _ => Sensitivity::Balanced,
+        parse_sensitivity(&self.sensitivity)
+    }
+}
+
+/// Settings used instead of pausing while docked (external display on
+/// external power).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DockedProfile {
+    pub min_hz: Hertz,
+    pub max_hz: Hertz,
+    #[serde(default = "default_sensitivity")]
+    pub sensitivity: String,
+    #[serde(default)]
+    pub adaptive_sensitivity: bool,
+    /// Keep switching rates; false holds `max_hz`
+    #[serde(default = "default_power_saving")]
+    pub power_saving: bool,
+}
+
+fn default_sensitivity() -> String {
+    "balanced".to_string()
+}
+
+fn default_power_saving() -> bool {
+    true
+}
+
+impl DockedProfile {
+    /// Hz range to run in: `max_hz` only with power saving off
+    pub fn hz_range(&self) -> (Hertz, Hertz) {
+        if self.power_saving {
+            (self.min_hz, self.max_hz)
+        } else {
+            (self.max_hz, self.max_hz)
         }
     }
+
+    pub fn get_sensitivity(&self) -> Sensitivity {
+        parse_sensitivity(&self.sensitivity)
+    }
+}
+
+/// Parse a sensitivity name, defaulting to balanced
+fn parse_sensitivity(name: &str) -> Sensitivity {
+    match name.to_lowercase().as_str() {
+        "conservative" => Sensitivity::Conservative,
+        "aggressive" => Sensitivity::Aggressive,
+        "custom" => Sensitivity::Custom,
+        _ => Sensitivity::Balanced,
+    }
 }
 
 /// Profile manager for loading/saving game profiles
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ProfileManager {
     /// Map of AppID to profile
     profiles: HashMap<String, GameProfile>,
@@ -58,12 +244,15 @@ pub struct ProfileManager {
     current_app_id: Option<String>,
     /// Global default settings (used when no profile matches)
     pub global_default: GlobalDefault,
+    /// Settings while docked; without one, docking pauses switching
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub docked: Option<DockedProfile>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GlobalDefault {
-    pub min_hz: u32,
-    pub max_hz: u32,
+    pub min_hz: Hertz,
+    pub max_hz: Hertz,
     pub sensitivity: String,
     pub adaptive_sensitivity: bool,
 }
@@ -71,24 +260,14 @@ pub struct GlobalDefault {
 impl Default for GlobalDefault {
     fn default() -> Self {
         Self {
-            min_hz: 40,
-            max_hz: 90,
+            min_hz: Hertz(40),
+            max_hz: Hertz(90),
             sensitivity: "balanced".to_string(),
             adaptive_sensitivity: false,
         }
     }
 }
 
-impl Default for ProfileManager {
-    fn default() -> Self {
-        Self {
-            profiles: HashMap::new(),
-            current_app_id: None,
-            global_default: GlobalDefault::default(),
-        }
-    }
-}
-
 impl ProfileManager {
     /// Get the profiles file path
     pub fn profiles_path() -> PathBuf {
@@ -103,12 +282,41 @@ impl ProfileManager {
     }
 
     /// Load profiles from file or return default
-    pub fn load_or_default() -> Result<Self, std::io::Error> {
-        let path = Self::profiles_path();
-        
+    ///
+    /// Retries with backoff while another process holds the lock. Defaulting
+    /// on contention would let the next save overwrite the stored profiles,
+    /// so persistent contention is returned as an error.
+    pub fn load_or_default() -> Result<Self, ProfileError> {
+        Self::load_with_retry(
+            &Self::profiles_path(),
+            LOAD_ATTEMPTS,
+            Duration::from_millis(LOAD_BACKOFF_MS),
+        )
+    }
+
+    fn load_with_retry(path: &Path, attempts: u32, backoff: Duration) -> Result<Self, ProfileError> {
+        let mut delay = backoff;
+        let mut attempt = 1;
+        loop {
+            match Self::load_from(path) {
+                Err(ProfileError::LockContended { .. }) if attempt < attempts => {
+                    warn!("profiles.json is locked, retrying in {:?}", delay);
+                    std::thread::sleep(delay);
+                    delay *= 2;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Load profiles from `path` or return default
+    pub fn load_from(path: &Path) -> Result<Self, ProfileError> {
         if path.exists() {
-            let contents = std::fs::read_to_string(&path)?;
-            match serde_json::from_str(&contents) {
+            let _lock = ProfileLock::acquire(path, false)?;
+            let contents = std::fs::read_to_string(path)
+                .map_err(|e| ProfileError::LoadFailed(e.to_string()))?;
+            match serde_json::from_str::<Self>(&contents) {
                 Ok(manager) => {
                     info!("Loaded {} game profiles from {:?}", 
                         manager.profiles.len(), path);
@@ -126,21 +334,25 @@ impl ProfileManager {
     }
 
     /// Save profiles to file
-    pub fn save(&self) -> Result<(), std::io::Error> {
-        let path = Self::profiles_path();
-        
-        // Ensure directory exists
+    pub fn save(&self) -> Result<(), ProfileError> {
+        self.save_to(&Self::profiles_path())
+    }
+
+    /// Save profiles to `path`
+    pub fn save_to(&self, path: &Path) -> Result<(), ProfileError> {
+        let save_err = |e: std::io::Error| ProfileError::SaveFailed(e.to_string());
+
+        // Ensure directory exists (for the lock file)
         if let Some(parent) = path.parent() {
-            std::fs::create_dir_all(parent)?;
+            std::fs::create_dir_all(parent).map_err(save_err)?;
         }
 
         let json = serde_json::to_string_pretty(self)
-            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+            .map_err(|e| ProfileError::SaveFailed(e.to_string()))?;
         
-        // Atomic write
-        let temp_path = path.with_extension("json.tmp");
-        std::fs::write(&temp_path, &json)?;
-        std::fs::rename(&temp_path, &path)?;
+        // Atomic write under the exclusive lock
+        let _lock = ProfileLock::acquire(path, true)?;
+        atomic_persist(path, json.as_bytes()).map_err(save_err)?;
         
         info!("Saved {} profiles to {:?}", self.profiles.len(), path);
         Ok(())
@@ -157,16 +369,55 @@ impl ProfileManager {
         self.profiles.insert(profile.app_id.clone(), profile);
     }
 
+    /// Mark a suggested profile as confirmed by the user.
+    /// Returns false if no profile exists for `app_id`.
+    pub fn accept_suggestion(&mut self, app_id: &str) -> bool {
+        match self.profiles.get_mut(app_id) {
+            Some(profile) => {
+                profile.suggested = false;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Blacklist or whitelist `app_id`, creating a profile from the global
+    /// defaults if it has none.
+    pub fn set_switching(&mut self, app_id: &str, name: Option<&str>, switching: GameSwitching) {
+        let defaults = &self.global_default;
+        let profile = self.profiles.entry(app_id.to_string()).or_insert_with(|| {
+            let mut profile = GameProfile::new(
+                app_id.to_string(),
+                name.unwrap_or(app_id).to_string(),
+                defaults.min_hz,
+                defaults.max_hz,
+                defaults.sensitivity.clone(),
+            );
+            profile.adaptive_sensitivity = defaults.adaptive_sensitivity;
+            profile
+        });
+        profile.switching = switching;
+        info!("Set switching for {} ({}) to {:?}", profile.name, app_id, switching);
+    }
+
+    /// Whether switching runs for the current game. Always true with no
+    /// game in the foreground.
+    pub fn current_switching_allowed(&self, whitelist_only: bool) -> bool {
+        match &self.current_app_id {
+            Some(app_id) => self
+                .profiles
+                .get(app_id)
+                .map_or(GameSwitching::Auto, |profile| profile.switching)
+                .allows(whitelist_only),
+            None => true,
+        }
+    }
+
     /// Remove a profile
     pub fn remove_profile(&mut self, app_id: &str) -> Option<GameProfile> {
         self.profiles.remove(app_id)
     }
 
-    /// Get all profiles
-    pub fn get_all_profiles(&self) -> Vec<&GameProfile> {
-        self.profiles.values().collect()
-    }
-
     /// Set current active game
     pub fn set_current_game(&mut self, app_id: Option<String>) {
         self.current_app_id = app_id;
@@ -177,8 +428,16 @@ impl ProfileManager {
         self.current_app_id.as_ref()
     }
 
+    /// Dwell time override from the current game's profile, if any
+    pub fn current_min_dwell(&self) -> Option<Secs> {
+        self.current_app_id
+            .as_ref()
+            .and_then(|app_id| self.profiles.get(app_id))
+            .and_then(|profile| profile.min_dwell_secs)
+    }
+
     /// Get settings for current game (profile or global default)
-    pub fn get_current_settings(&self) -> (u32, u32, Sensitivity, bool) {
+    pub fn get_current_settings(&self) -> (Hertz, Hertz, Sensitivity, bool) {
         if let Some(app_id) = &self.current_app_id {
             if let Some(profile) = self.profiles.get(app_id) {
                 return (
@@ -191,29 +450,13 @@ impl ProfileManager {
         }
         
         // Return global defaults
-        let sensitivity = match self.global_default.sensitivity.to_lowercase().as_str() {
-            "conservative" => Sensitivity::Conservative,
-            "aggressive" => Sensitivity::Aggressive,
-            _ => Sensitivity::Balanced,
-        };
-        
         (
             self.global_default.min_hz,
             self.global_default.max_hz,
-            sensitivity,
+            parse_sensitivity(&self.global_default.sensitivity),
             self.global_default.adaptive_sensitivity,
         )
     }
-
-    /// Update global defaults
-    pub fn set_global_default(&mut self, min_hz: u32, max_hz: u32, sensitivity: String, adaptive: bool) {
-        self.global_default = GlobalDefault {
-            min_hz,
-            max_hz,
-            sensitivity,
-            adaptive_sensitivity: adaptive,
-        };
-    }
 }
 
 /// Profile list response for IPC
@@ -233,3 +476,118 @@ impl From<&ProfileManager> for ProfileListResponse {
         }
     }
 }
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_save_and_load_roundtrip() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("profiles.json");
+
+        let mut manager = ProfileManager::default();
+        manager.set_profile(GameProfile::new(
+            "620".to_string(),
+            "Portal 2".to_string(),
+            Hertz(45),
+            Hertz(90),
+            "aggressive".to_string(),
+        ));
+        manager.save_to(&path).unwrap();
+
+        let loaded = ProfileManager::load_from(&path).unwrap();
+        assert_eq!(loaded.get_profile("620"), manager.get_profile("620"));
+    }
+
+    #[test]
+    fn test_save_reports_lock_contention() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("profiles.json");
+
+        // flock locks belong to the open file description, so a second
+        // descriptor in the same process contends like another process would
+        let _held = ProfileLock::acquire(&path, true).unwrap();
+        let result = ProfileLock::acquire_with_timeout(&path, true, Duration::from_millis(50));
+        assert!(matches!(result, Err(ProfileError::LockContended { .. })));
+
+        let result = ProfileManager::default().save_to(&path);
+        assert!(matches!(result, Err(ProfileError::LockContended { .. })));
+    }
+
+    #[test]
+    fn test_load_retries_then_reports_contention() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("profiles.json");
+        ProfileManager::default().save_to(&path).unwrap();
+
+        let held = ProfileLock::acquire(&path, true).unwrap();
+        let result = ProfileManager::load_with_retry(&path, 2, Duration::from_millis(10));
+        assert!(matches!(result, Err(ProfileError::LockContended { .. })));
+
+        // Released during the backoff: a later attempt succeeds
+        let release = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(100));
+            drop(held);
+        });
+        let result = ProfileManager::load_with_retry(&path, 4, Duration::from_millis(200));
+        release.join().unwrap();
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_lock_file_errors_match_operation() {
+        let dir = tempdir().unwrap();
+        // The lock file can't be created inside a missing directory
+        let path = dir.path().join("missing").join("profiles.json");
+
+        let load = ProfileLock::acquire(&path, false);
+        assert!(matches!(load, Err(ProfileError::LoadFailed(_))));
+        let save = ProfileLock::acquire(&path, true);
+        assert!(matches!(save, Err(ProfileError::SaveFailed(_))));
+    }
+
+    #[test]
+    fn test_docked_profile() {
+        let json = r#"{"profiles":{},"global_default":{"min_hz":40,"max_hz":90,"sensitivity":"balanced","adaptive_sensitivity":false},
+            "docked":{"min_hz":50,"max_hz":60,"power_saving":false}}"#;
+        let manager: ProfileManager = serde_json::from_str(json).unwrap();
+        let docked = manager.docked.unwrap();
+        assert_eq!(docked.hz_range(), (Hertz(60), Hertz(60)));
+        assert_eq!(docked.get_sensitivity(), Sensitivity::Balanced);
+
+        let docked = DockedProfile { power_saving: true, ..docked };
+        assert_eq!(docked.hz_range(), (Hertz(50), Hertz(60)));
+        assert!(ProfileManager::default().docked.is_none());
+    }
+
+    #[test]
+    fn test_game_switching() {
+        let mut manager = ProfileManager::default();
+        assert!(manager.current_switching_allowed(true));
+
+        manager.set_current_game(Some("570".to_string()));
+        assert!(manager.current_switching_allowed(false));
+        assert!(!manager.current_switching_allowed(true));
+
+        manager.set_switching("570", Some("Dota 2"), GameSwitching::Disabled);
+        let profile = manager.get_profile("570").unwrap();
+        assert_eq!(profile.name, "Dota 2");
+        assert_eq!(profile.max_hz, manager.global_default.max_hz);
+        assert!(!manager.current_switching_allowed(false));
+
+        manager.set_switching("570", None, GameSwitching::Enabled);
+        assert_eq!(manager.get_profile("570").unwrap().name, "Dota 2");
+        assert!(manager.current_switching_allowed(true));
+    }
+
+    #[test]
+    fn test_shared_locks_do_not_contend() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("profiles.json");
+
+        let _reader = ProfileLock::acquire(&path, false).unwrap();
+        assert!(ProfileLock::acquire(&path, false).is_ok());
+    }
+}
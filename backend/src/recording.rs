@@ -0,0 +1,108 @@
+//! Screen recording and streaming detection.
+//!
+//! A refresh rate change mid-recording corrupts captures from gamescope's
+//! PipeWire stream (Steam game recording, Remote Play) and from some
+//! recorders, so the rate is locked while one is active. Recorders are
+//! found by process name, the gamescope stream by its PipeWire node state
+//! in `pw-dump` output.
+
+use crate::display_backend::find_in_path;
+use std::path::Path;
+use std::time::Duration;
+use tokio::process::Command;
+
+/// Recorders and streaming tools, by `/proc/*/comm` (truncated to 15 bytes)
+const RECORDER_PROCESSES: [&str; 5] = ["obs", "gpu-screen-reco", "wf-recorder", "kooha", "simplescreenrec"];
+
+/// Name of the PipeWire node gamescope exports its output on
+const GAMESCOPE_NODE: &str = "gamescope";
+
+/// Bound on a `pw-dump` run, so a stuck PipeWire never stalls detection
+const PW_DUMP_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// What is recording, if anything: a recorder's process name or
+/// "gamescope stream".
+pub async fn detect(proc_dir: &Path) -> Option<String> {
+    if let Some(recorder) = running_recorder(proc_dir) {
+        return Some(recorder.to_string());
+    }
+    gamescope_stream_active().await.then(|| "gamescope stream".to_string())
+}
+
+/// First running recorder from `RECORDER_PROCESSES`.
+fn running_recorder(proc_dir: &Path) -> Option<&'static str> {
+    std::fs::read_dir(proc_dir).ok()?.flatten().find_map(|entry| {
+        entry.file_name().to_str()?.parse::<u32>().ok()?;
+        let comm = std::fs::read_to_string(entry.path().join("comm")).ok()?;
+        RECORDER_PROCESSES.iter().copied().find(|&name| name == comm.trim())
+    })
+}
+
+/// Whether gamescope's PipeWire stream is being consumed. `false` without
+/// `pw-dump` or a reachable PipeWire.
+async fn gamescope_stream_active() -> bool {
+    let path_var = std::env::var("PATH").unwrap_or_default();
+    let Some(pw_dump) = find_in_path("pw-dump", &path_var) else {
+        return false;
+    };
+    let mut command = Command::new(pw_dump);
+    command.kill_on_drop(true);
+    match tokio::time::timeout(PW_DUMP_TIMEOUT, command.output()).await {
+        Ok(Ok(output)) if output.status.success() => {
+            node_running(&String::from_utf8_lossy(&output.stdout), GAMESCOPE_NODE)
+        }
+        Ok(Ok(_)) | Ok(Err(_)) => false,
+        Err(_) => {
+            tracing::debug!("pw-dump timed out");
+            false
+        }
+    }
+}
+
+/// Whether `pw-dump` output has a node named `name` in the "running" state,
+/// i.e. linked to a consumer and streaming.
+fn node_running(pw_dump: &str, name: &str) -> bool {
+    let Ok(serde_json::Value::Array(objects)) = serde_json::from_str(pw_dump) else {
+        return false;
+    };
+    objects.iter().any(|object| {
+        object["type"] == "PipeWire:Interface:Node"
+            && object["info"]["props"]["node.name"] == name
+            && object["info"]["state"] == "running"
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_running_recorder() {
+        let proc_dir = tempdir().unwrap();
+        let add = |pid: u32, comm: &str| {
+            let dir = proc_dir.path().join(pid.to_string());
+            std::fs::create_dir(&dir).unwrap();
+            std::fs::write(dir.join("comm"), format!("{}\n", comm)).unwrap();
+        };
+        add(1, "systemd");
+        assert_eq!(running_recorder(proc_dir.path()), None);
+        add(2310, "gpu-screen-reco");
+        assert_eq!(running_recorder(proc_dir.path()), Some("gpu-screen-reco"));
+    }
+
+    #[test]
+    fn test_node_running() {
+        let dump = |state: &str| {
+            format!(
+                r#"[{{"id":30,"type":"PipeWire:Interface:Core","info":{{}}}},
+                    {{"id":58,"type":"PipeWire:Interface:Node","info":{{"state":"{}","props":{{"node.name":"gamescope"}}}}}}]"#,
+                state
+            )
+        };
+        assert!(node_running(&dump("running"), GAMESCOPE_NODE));
+        assert!(!node_running(&dump("suspended"), GAMESCOPE_NODE));
+        assert!(!node_running(&dump("idle"), GAMESCOPE_NODE));
+        assert!(!node_running("not json", GAMESCOPE_NODE));
+    }
+}
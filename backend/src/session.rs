@@ -0,0 +1,103 @@
+//! Game mode vs desktop mode session detection.
+//!
+//! SteamOS runs games in the gamescope session and switches to a KDE
+//! Plasma session for desktop mode, where a windowed desktop has no use
+//! for FPS-driven switching. The session is told apart by its compositor
+//! process, then gamescope's Wayland socket, then `XDG_CURRENT_DESKTOP`.
+
+use crate::display_backend::{process_running, GAMESCOPE_PROCESSES};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Per-user runtime directories holding the Wayland sockets
+pub const RUN_USER_DIR: &str = "/run/user";
+
+/// Compositors of a desktop session. Checked before gamescope, since
+/// desktop mode can run a nested gamescope for a single game.
+const DESKTOP_PROCESSES: [&str; 6] = ["kwin_wayland", "kwin_x11", "plasmashell", "gnome-shell", "sway", "Hyprland"];
+
+/// Wayland socket gamescope creates in the user's runtime directory
+const GAMESCOPE_SOCKET: &str = "gamescope-0";
+
+/// Kind of graphical session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionKind {
+    /// Gamescope session (SteamOS game mode)
+    Gaming,
+    /// A desktop compositor such as KDE Plasma (SteamOS desktop mode)
+    Desktop,
+    /// Nothing recognized
+    #[default]
+    Unknown,
+}
+
+impl SessionKind {
+    /// Detect the session from the processes under `proc_dir`, the sockets
+    /// under `run_user_dir` and the `XDG_CURRENT_DESKTOP` value, in that order.
+    pub fn detect(proc_dir: &Path, run_user_dir: &Path, current_desktop: Option<&str>) -> Self {
+        if process_running(proc_dir, &DESKTOP_PROCESSES) {
+            return SessionKind::Desktop;
+        }
+        if process_running(proc_dir, &GAMESCOPE_PROCESSES) || has_gamescope_socket(run_user_dir) {
+            return SessionKind::Gaming;
+        }
+        match current_desktop.map(str::trim).filter(|d| !d.is_empty()) {
+            Some(desktop) if desktop.eq_ignore_ascii_case("gamescope") => SessionKind::Gaming,
+            Some(_) => SessionKind::Desktop,
+            None => SessionKind::Unknown,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SessionKind::Gaming => "gaming",
+            SessionKind::Desktop => "desktop",
+            SessionKind::Unknown => "unknown",
+        }
+    }
+}
+
+/// Whether any user's runtime directory under `run_user_dir` has
+/// gamescope's Wayland socket.
+fn has_gamescope_socket(run_user_dir: &Path) -> bool {
+    std::fs::read_dir(run_user_dir)
+        .map(|entries| entries.flatten().any(|e| e.path().join(GAMESCOPE_SOCKET).exists()))
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn add_process(proc_dir: &Path, pid: u32, comm: &str) {
+        let dir = proc_dir.join(pid.to_string());
+        std::fs::create_dir(&dir).unwrap();
+        std::fs::write(dir.join("comm"), format!("{}\n", comm)).unwrap();
+    }
+
+    #[test]
+    fn test_detect_session() {
+        let proc_dir = tempdir().unwrap();
+        let run_user = tempdir().unwrap();
+        add_process(proc_dir.path(), 1, "systemd");
+        assert_eq!(SessionKind::detect(proc_dir.path(), run_user.path(), None), SessionKind::Unknown);
+        assert_eq!(
+            SessionKind::detect(proc_dir.path(), run_user.path(), Some("gamescope")),
+            SessionKind::Gaming
+        );
+        assert_eq!(SessionKind::detect(proc_dir.path(), run_user.path(), Some("KDE")), SessionKind::Desktop);
+
+        // gamescope's Wayland socket
+        std::fs::create_dir(run_user.path().join("1000")).unwrap();
+        std::fs::write(run_user.path().join("1000").join(GAMESCOPE_SOCKET), "").unwrap();
+        assert_eq!(SessionKind::detect(proc_dir.path(), run_user.path(), Some("KDE")), SessionKind::Gaming);
+
+        // A desktop compositor wins over a nested gamescope
+        add_process(proc_dir.path(), 812, "gamescope-wl");
+        assert_eq!(SessionKind::detect(proc_dir.path(), run_user.path(), None), SessionKind::Gaming);
+        add_process(proc_dir.path(), 700, "kwin_wayland");
+        assert_eq!(SessionKind::detect(proc_dir.path(), run_user.path(), None), SessionKind::Desktop);
+    }
+}
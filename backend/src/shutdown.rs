@@ -0,0 +1,234 @@
+//! Shutdown coordination for the SmartRefresh daemon.
+//!
+//! Shutdown runs in ordered phases so that nothing can change the display
+//! after the exit refresh rate has been restored:
+//!
+//! 1. Stop accepting IPC commands
+//! 2. Stop control tasks and flush any in-flight display command
+//! 3. Persist config, trend history and the status snapshot
+//! 4. Restore the exit refresh rate
+//!
+//! The first three phases share one time budget. A phase can be given a
+//! reserved budget of its own, so restoring the display still gets its full
+//! time when an earlier phase stalls.
+//!
+//! Logging is flushed last by `main` once the manager has finished.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+/// Shutdown phases, executed in declaration order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ShutdownPhase {
+    /// Stop the IPC server so no new commands arrive
+    StopIpc,
+    /// Stop FPS/control/monitor tasks, waiting for pending display commands
+    FlushDisplay,
+    /// Write the config, trend history and status snapshot to disk
+    PersistState,
+    /// Put the panel back to the exit refresh rate
+    RestoreDisplay,
+}
+
+/// A task that must finish within its phase.
+struct RegisteredTask {
+    phase: ShutdownPhase,
+    name: &'static str,
+    handle: JoinHandle<()>,
+}
+
+/// Coordinates phased shutdown within fixed time budgets.
+pub struct ShutdownManager {
+    /// Stop signal per phase
+    signals: HashMap<ShutdownPhase, watch::Sender<bool>>,
+    /// Tasks to drain, tagged with the phase that stops them
+    tasks: Vec<RegisteredTask>,
+    /// Time shared by all phases without a reserved budget
+    budget: Duration,
+    /// Phases with a budget of their own
+    reserved: HashMap<ShutdownPhase, Duration>,
+    /// When the first phase started
+    started: Option<Instant>,
+    /// Last phase that ran (phases must run in order)
+    last_phase: Option<ShutdownPhase>,
+}
+
+impl ShutdownManager {
+    /// Create a manager with the given shared shutdown budget.
+    pub fn new(budget: Duration) -> Self {
+        Self {
+            signals: HashMap::new(),
+            tasks: Vec::new(),
+            budget,
+            reserved: HashMap::new(),
+            started: None,
+            last_phase: None,
+        }
+    }
+
+    /// Give `phase` its own `budget`, counted from when it starts instead of
+    /// against the shared budget.
+    pub fn reserve(&mut self, phase: ShutdownPhase, budget: Duration) {
+        self.reserved.insert(phase, budget);
+    }
+
+    /// Get a stop signal that fires when `phase` begins.
+    pub fn subscribe(&mut self, phase: ShutdownPhase) -> watch::Receiver<bool> {
+        self.signals
+            .entry(phase)
+            .or_insert_with(|| watch::channel(false).0)
+            .subscribe()
+    }
+
+    /// Register a task that must exit during `phase`.
+    pub fn register(&mut self, phase: ShutdownPhase, name: &'static str, handle: JoinHandle<()>) {
+        self.tasks.push(RegisteredTask { phase, name, handle });
+    }
+
+    /// Time left in the shared budget.
+    // Phases track their own deadline; tests check the budget ran out
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn remaining(&self) -> Duration {
+        match self.started {
+            Some(started) => self.budget.saturating_sub(started.elapsed()),
+            None => self.budget,
+        }
+    }
+
+    /// Run a phase: signal its subscribers, wait for its tasks, then run `action`.
+    ///
+    /// Tasks still running when the phase's budget runs out are aborted. The
+    /// action is also bounded by what is left of it.
+    pub async fn run_phase<F>(&mut self, phase: ShutdownPhase, action: F)
+    where
+        F: Future<Output = ()>,
+    {
+        debug_assert!(
+            self.last_phase.is_none_or(|last| phase > last),
+            "shutdown phases must run in order"
+        );
+        let now = Instant::now();
+        let started = *self.started.get_or_insert(now);
+        self.last_phase = Some(phase);
+        let deadline = match self.reserved.get(&phase) {
+            Some(&budget) => now + budget,
+            None => started + self.budget,
+        };
+        let remaining = || deadline.saturating_duration_since(Instant::now());
+
+        info!("Shutdown phase {:?} starting", phase);
+
+        if let Some(signal) = self.signals.get(&phase) {
+            let _ = signal.send(true);
+        }
+
+        let (phase_tasks, rest): (Vec<_>, Vec<_>) =
+            std::mem::take(&mut self.tasks).into_iter().partition(|t| t.phase == phase);
+        self.tasks = rest;
+
+        for mut task in phase_tasks {
+            if tokio::time::timeout(remaining(), &mut task.handle).await.is_err() {
+                warn!("Task '{}' did not stop during {:?}, aborting", task.name, phase);
+                task.handle.abort();
+            }
+        }
+
+        if tokio::time::timeout(remaining(), action).await.is_err() {
+            warn!("Shutdown phase {:?} timed out", phase);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_phase_only_signals_its_subscribers() {
+        let mut manager = ShutdownManager::new(Duration::from_secs(1));
+        let ipc_rx = manager.subscribe(ShutdownPhase::StopIpc);
+        let display_rx = manager.subscribe(ShutdownPhase::FlushDisplay);
+
+        manager.run_phase(ShutdownPhase::StopIpc, async {}).await;
+        assert!(*ipc_rx.borrow());
+        assert!(!*display_rx.borrow());
+
+        manager.run_phase(ShutdownPhase::FlushDisplay, async {}).await;
+        assert!(*display_rx.borrow());
+    }
+
+    #[tokio::test]
+    async fn test_phase_waits_for_tasks_before_action() {
+        let mut manager = ShutdownManager::new(Duration::from_secs(1));
+        let mut rx = manager.subscribe(ShutdownPhase::FlushDisplay);
+        let flushed = Arc::new(AtomicBool::new(false));
+
+        let task_flushed = Arc::clone(&flushed);
+        manager.register(
+            ShutdownPhase::FlushDisplay,
+            "core_logic",
+            tokio::spawn(async move {
+                rx.changed().await.ok();
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                task_flushed.store(true, Ordering::SeqCst);
+            }),
+        );
+
+        let action_saw_flush = Arc::new(AtomicBool::new(false));
+        let action_flag = Arc::clone(&action_saw_flush);
+        let task_flag = Arc::clone(&flushed);
+        manager
+            .run_phase(ShutdownPhase::FlushDisplay, async move {
+                action_flag.store(task_flag.load(Ordering::SeqCst), Ordering::SeqCst);
+            })
+            .await;
+
+        assert!(action_saw_flush.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn test_stuck_task_is_aborted_within_budget() {
+        let mut manager = ShutdownManager::new(Duration::from_millis(50));
+        manager.register(
+            ShutdownPhase::StopIpc,
+            "stuck",
+            tokio::spawn(async {
+                tokio::time::sleep(Duration::from_secs(60)).await;
+            }),
+        );
+
+        let start = Instant::now();
+        manager.run_phase(ShutdownPhase::StopIpc, async {}).await;
+        assert!(start.elapsed() < Duration::from_secs(1));
+        assert_eq!(manager.remaining(), Duration::ZERO);
+    }
+
+    #[tokio::test]
+    async fn test_reserved_phase_survives_stalled_phase() {
+        let mut manager = ShutdownManager::new(Duration::from_millis(50));
+        manager.reserve(ShutdownPhase::RestoreDisplay, Duration::from_millis(200));
+
+        manager
+            .run_phase(ShutdownPhase::FlushDisplay, async {
+                tokio::time::sleep(Duration::from_secs(60)).await;
+            })
+            .await;
+        assert_eq!(manager.remaining(), Duration::ZERO);
+
+        let restored = Arc::new(AtomicBool::new(false));
+        let flag = Arc::clone(&restored);
+        manager
+            .run_phase(ShutdownPhase::RestoreDisplay, async move {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                flag.store(true, Ordering::SeqCst);
+            })
+            .await;
+        assert!(restored.load(Ordering::SeqCst));
+    }
+}
@@ -0,0 +1,176 @@
+//! Shared memory status page.
+//!
+//! A small read-only segment, `/smartrefresh-status`, for overlays and
+//! scripts that want the daemon's state without an IPC round trip. The core
+//! loop rewrites it every tick. The sequence number works like a seqlock:
+//! it is odd while a write is in progress, so readers copy the page until
+//! they see the same even value before and after.
+//!
+//! Layout, little endian, 40 bytes:
+//!
+//! | Offset | Type | Field                                     |
+//! |--------|------|-------------------------------------------|
+//! | 0      | u32  | magic `SRST`                              |
+//! | 4      | u32  | layout version (1)                        |
+//! | 8      | u32  | sequence                                  |
+//! | 12     | u32  | current refresh rate in Hz                |
+//! | 16     | f64  | smoothed FPS                              |
+//! | 24     | u32  | state: 0 paused, 1 manual, 2 stable, 3 dropping, 4 increasing |
+//! | 28     | u32  | reserved                                  |
+//! | 32     | f64  | estimated battery savings, minutes per hour (0 without data) |
+
+use crate::ipc_server::ControlState;
+use crate::units::Hertz;
+use std::ffi::CString;
+use std::sync::atomic::{fence, AtomicU32, Ordering};
+
+/// Segment name
+pub const STATUS_PAGE_NAME: &str = "/smartrefresh-status";
+
+pub const STATUS_PAGE_MAGIC: u32 = u32::from_le_bytes(*b"SRST");
+pub const STATUS_PAGE_VERSION: u32 = 1;
+
+#[repr(C)]
+struct Page {
+    magic: u32,
+    version: u32,
+    sequence: AtomicU32,
+    hz: u32,
+    fps: f64,
+    state: u32,
+    reserved: u32,
+    savings_minutes: f64,
+}
+
+/// Values published on the page.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PageData {
+    pub hz: Hertz,
+    pub fps: f64,
+    pub state: ControlState,
+    pub savings_minutes: f64,
+}
+
+/// State code stored on the page.
+fn state_code(state: ControlState) -> u32 {
+    match state {
+        ControlState::Paused => 0,
+        ControlState::Manual => 1,
+        ControlState::Stable => 2,
+        ControlState::Dropping => 3,
+        ControlState::Increasing => 4,
+    }
+}
+
+/// The mapped segment, removed again on drop.
+pub struct StatusPage {
+    name: CString,
+    page: *mut Page,
+}
+
+// Only the core loop writes; the mapping lives as long as the value.
+unsafe impl Send for StatusPage {}
+unsafe impl Sync for StatusPage {}
+
+impl StatusPage {
+    /// Create (or take over) the segment `name`, readable by everyone.
+    pub fn create(name: &str) -> std::io::Result<Self> {
+        use libc::{close, ftruncate, mmap, shm_open, shm_unlink, MAP_FAILED, MAP_SHARED, O_CREAT, O_RDWR, PROT_READ, PROT_WRITE};
+
+        let name = CString::new(name).map_err(std::io::Error::other)?;
+        let size = std::mem::size_of::<Page>();
+        // SAFETY: plain libc calls on a valid name; every failure is checked
+        unsafe {
+            let fd = shm_open(name.as_ptr(), O_CREAT | O_RDWR, 0o644);
+            if fd < 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            if ftruncate(fd, size as libc::off_t) < 0 {
+                let e = std::io::Error::last_os_error();
+                close(fd);
+                shm_unlink(name.as_ptr());
+                return Err(e);
+            }
+            let ptr = mmap(std::ptr::null_mut(), size, PROT_READ | PROT_WRITE, MAP_SHARED, fd, 0);
+            close(fd);
+            if ptr == MAP_FAILED {
+                let e = std::io::Error::last_os_error();
+                shm_unlink(name.as_ptr());
+                return Err(e);
+            }
+            let page = ptr as *mut Page;
+            std::ptr::write_volatile(
+                page,
+                Page {
+                    magic: STATUS_PAGE_MAGIC,
+                    version: STATUS_PAGE_VERSION,
+                    sequence: AtomicU32::new(0),
+                    hz: 0,
+                    fps: 0.0,
+                    state: state_code(ControlState::Paused),
+                    reserved: 0,
+                    savings_minutes: 0.0,
+                },
+            );
+            Ok(Self { name, page })
+        }
+    }
+
+    /// Write `data` to the page.
+    pub fn publish(&self, data: &PageData) {
+        // SAFETY: `page` is mapped read-write for the lifetime of `self`
+        unsafe {
+            let page = self.page;
+            let sequence = (*page).sequence.load(Ordering::Relaxed);
+            (*page).sequence.store(sequence.wrapping_add(1), Ordering::Relaxed);
+            fence(Ordering::Release);
+            std::ptr::write_volatile(std::ptr::addr_of_mut!((*page).hz), data.hz.get());
+            std::ptr::write_volatile(std::ptr::addr_of_mut!((*page).fps), data.fps);
+            std::ptr::write_volatile(std::ptr::addr_of_mut!((*page).state), state_code(data.state));
+            std::ptr::write_volatile(std::ptr::addr_of_mut!((*page).savings_minutes), data.savings_minutes);
+            (*page).sequence.store(sequence.wrapping_add(2), Ordering::Release);
+        }
+    }
+}
+
+impl Drop for StatusPage {
+    fn drop(&mut self) {
+        // SAFETY: unmapping the mapping made in `create`
+        unsafe {
+            libc::munmap(self.page as *mut libc::c_void, std::mem::size_of::<Page>());
+            libc::shm_unlink(self.name.as_ptr());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_page_layout() {
+        let name = format!("/smartrefresh-status-test-{}", std::process::id());
+        let page = StatusPage::create(&name).unwrap();
+        page.publish(&PageData {
+            hz: Hertz(60),
+            fps: 58.5,
+            state: ControlState::Dropping,
+            savings_minutes: 12.0,
+        });
+
+        let bytes = std::fs::read(format!("/dev/shm{}", name)).unwrap();
+        assert_eq!(bytes.len(), 40);
+        let u32_at = |offset: usize| u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap());
+        let f64_at = |offset: usize| f64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap());
+        assert_eq!(u32_at(0), STATUS_PAGE_MAGIC);
+        assert_eq!(u32_at(4), STATUS_PAGE_VERSION);
+        assert_eq!(u32_at(8), 2);
+        assert_eq!(u32_at(12), 60);
+        assert_eq!(f64_at(16), 58.5);
+        assert_eq!(u32_at(24), 3);
+        assert_eq!(f64_at(32), 12.0);
+
+        drop(page);
+        assert!(!std::path::Path::new(&format!("/dev/shm{}", name)).exists());
+    }
+}
@@ -0,0 +1,142 @@
+//! Last known status, persisted for a fast frontend boot.
+//!
+//! Right after the daemon starts, the FPS and battery tasks have not produced
+//! any data yet and a live status is mostly zeros. The daemon loads the
+//! snapshot written during the previous run and serves it (marked `cached`)
+//! until live data arrives.
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use tracing::warn;
+
+use crate::ipc_server::StatusResponse;
+use crate::persist::atomic_persist;
+
+/// Minimum time between snapshot writes while status keeps changing
+pub const MIN_SAVE_INTERVAL: Duration = Duration::from_secs(30);
+
+const SNAPSHOT_DIR: &str = ".local/share/smart-refresh";
+
+/// Default snapshot path (~/.local/share/smart-refresh/status.json).
+pub fn default_path() -> PathBuf {
+    match std::env::var_os("HOME") {
+        Some(home) => PathBuf::from(home).join(SNAPSHOT_DIR).join("status.json"),
+        None => PathBuf::from("/tmp/smart-refresh/status.json"),
+    }
+}
+
+/// Load the snapshot from the previous run, if any.
+pub fn load(path: &Path) -> Option<StatusResponse> {
+    let contents = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str(&contents)
+        .map_err(|e| warn!("Ignoring unreadable status snapshot: {}", e))
+        .ok()
+}
+
+/// Writes snapshots when status changed, at most every `MIN_SAVE_INTERVAL`.
+#[derive(Debug)]
+pub struct SnapshotWriter {
+    path: PathBuf,
+    last_written: Option<String>,
+    last_save: Option<Instant>,
+}
+
+impl SnapshotWriter {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            last_written: None,
+            last_save: None,
+        }
+    }
+
+    /// Save if status changed and the debounce interval has passed.
+    /// Returns whether a write happened.
+    pub fn maybe_save(&mut self, status: &StatusResponse, now: Instant) -> std::io::Result<bool> {
+        if self
+            .last_save
+            .is_some_and(|last| now.duration_since(last) < MIN_SAVE_INTERVAL)
+        {
+            return Ok(false);
+        }
+        self.save(status, now)
+    }
+
+    /// Save if status changed, ignoring the debounce interval (for shutdown).
+    pub fn flush(&mut self, status: &StatusResponse) -> std::io::Result<bool> {
+        self.save(status, Instant::now())
+    }
+
+    fn save(&mut self, status: &StatusResponse, now: Instant) -> std::io::Result<bool> {
+        // A cached status is the snapshot itself, not new data
+        if status.cached {
+            return Ok(false);
+        }
+        let json = serde_json::to_string(status).map_err(std::io::Error::other)?;
+        if self.last_written.as_deref() == Some(json.as_str()) {
+            return Ok(false);
+        }
+        atomic_persist(&self.path, json.as_bytes())?;
+        self.last_written = Some(json);
+        self.last_save = Some(now);
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::battery::BatteryMonitor;
+    use crate::config::ConfigManager;
+    use crate::ipc_server::DaemonState;
+    use crate::metrics::MetricsCollector;
+    use crate::profiles::ProfileManager;
+    use crate::units::Hertz;
+    use std::sync::Arc;
+    use tempfile::tempdir;
+    use tokio::sync::RwLock;
+
+    async fn sample_status(dir: &Path) -> StatusResponse {
+        let config_manager =
+            Arc::new(ConfigManager::load_or_default(&dir.join("config.json")).unwrap());
+        let state = DaemonState::new(
+            config_manager,
+            Arc::new(RwLock::new(ProfileManager::default())),
+            Arc::new(MetricsCollector::new()),
+            Arc::new(BatteryMonitor::new()),
+        );
+        state.get_status().await
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_round_trip_and_debounce() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("status.json");
+        let mut writer = SnapshotWriter::new(path.clone());
+        let mut status = sample_status(dir.path()).await;
+        let now = Instant::now();
+
+        assert!(writer.maybe_save(&status, now).unwrap());
+        // Unchanged status is not rewritten
+        assert!(!writer.flush(&status).unwrap());
+
+        status.current_hz = Hertz(60);
+        assert!(!writer.maybe_save(&status, now + Duration::from_secs(1)).unwrap());
+        assert!(writer.maybe_save(&status, now + MIN_SAVE_INTERVAL).unwrap());
+        assert_eq!(load(&path).unwrap().current_hz, Hertz(60));
+
+        status.cached = true;
+        status.current_hz = Hertz(45);
+        assert!(!writer.flush(&status).unwrap());
+    }
+
+    #[test]
+    fn test_load_ignores_garbage() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("status.json");
+        assert!(load(&path).is_none());
+        std::fs::write(&path, "{not json").unwrap();
+        assert!(load(&path).is_none());
+    }
+}
@@ -0,0 +1,142 @@
+//! Runtime switches for optional daemon subsystems.
+//!
+//! Each background subsystem checks its switch before doing work, so users
+//! can turn pieces off over IPC without restarting the daemon. Switches are
+//! not persisted; every subsystem starts enabled.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use serde::{Deserialize, Serialize};
+
+/// An optional subsystem that can be switched off at runtime.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Subsystem {
+    /// Battery power sampling
+    Battery,
+    /// External display detection (pauses control when docked)
+    MonitorDetection,
+    /// FPS-variance based sensitivity override (lives on the controller)
+    AdaptiveSensitivity,
+    /// Thermal-aware rate limiting
+    Thermal,
+    /// Switch count and timing metrics
+    Metrics,
+}
+
+impl Subsystem {
+    pub const ALL: [Subsystem; 5] = [
+        Subsystem::Battery,
+        Subsystem::MonitorDetection,
+        Subsystem::AdaptiveSensitivity,
+        Subsystem::Thermal,
+        Subsystem::Metrics,
+    ];
+
+    /// Parse an IPC subsystem name (e.g. "monitor_detection").
+    pub fn parse(name: &str) -> Option<Self> {
+        serde_json::from_value(serde_json::Value::String(name.to_lowercase())).ok()
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Subsystem::Battery => "battery",
+            Subsystem::MonitorDetection => "monitor_detection",
+            Subsystem::AdaptiveSensitivity => "adaptive_sensitivity",
+            Subsystem::Thermal => "thermal",
+            Subsystem::Metrics => "metrics",
+        }
+    }
+}
+
+/// Enabled flags for the subsystems owned by the daemon.
+///
+/// Adaptive sensitivity is tracked by the controller itself and is not
+/// stored here.
+#[derive(Debug)]
+pub struct Subsystems {
+    battery: AtomicBool,
+    monitor_detection: AtomicBool,
+    thermal: AtomicBool,
+    metrics: AtomicBool,
+}
+
+impl Default for Subsystems {
+    fn default() -> Self {
+        Self {
+            battery: AtomicBool::new(true),
+            monitor_detection: AtomicBool::new(true),
+            thermal: AtomicBool::new(true),
+            metrics: AtomicBool::new(true),
+        }
+    }
+}
+
+impl Subsystems {
+    fn flag(&self, subsystem: Subsystem) -> Option<&AtomicBool> {
+        match subsystem {
+            Subsystem::Battery => Some(&self.battery),
+            Subsystem::MonitorDetection => Some(&self.monitor_detection),
+            Subsystem::Thermal => Some(&self.thermal),
+            Subsystem::Metrics => Some(&self.metrics),
+            Subsystem::AdaptiveSensitivity => None,
+        }
+    }
+
+    /// Whether a subsystem is enabled. Always true for controller-owned ones.
+    pub fn is_enabled(&self, subsystem: Subsystem) -> bool {
+        self.flag(subsystem).is_none_or(|f| f.load(Ordering::Relaxed))
+    }
+
+    /// Enable or disable a subsystem. Returns the previous value.
+    pub fn set(&self, subsystem: Subsystem, enabled: bool) -> bool {
+        self.flag(subsystem)
+            .map(|f| f.swap(enabled, Ordering::Relaxed))
+            .unwrap_or(true)
+    }
+}
+
+/// Subsystem switches reported in status.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct SubsystemStatus {
+    pub battery: bool,
+    pub monitor_detection: bool,
+    pub adaptive_sensitivity: bool,
+    pub thermal: bool,
+    pub metrics: bool,
+}
+
+impl SubsystemStatus {
+    pub fn new(subsystems: &Subsystems, adaptive_sensitivity: bool) -> Self {
+        Self {
+            battery: subsystems.is_enabled(Subsystem::Battery),
+            monitor_detection: subsystems.is_enabled(Subsystem::MonitorDetection),
+            adaptive_sensitivity,
+            thermal: subsystems.is_enabled(Subsystem::Thermal),
+            metrics: subsystems.is_enabled(Subsystem::Metrics),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_round_trips() {
+        for subsystem in Subsystem::ALL {
+            assert_eq!(Subsystem::parse(subsystem.as_str()), Some(subsystem));
+        }
+        assert_eq!(Subsystem::parse("Battery"), Some(Subsystem::Battery));
+        assert_eq!(Subsystem::parse("gpu"), None);
+    }
+
+    #[test]
+    fn test_toggle() {
+        let subsystems = Subsystems::default();
+        assert!(subsystems.is_enabled(Subsystem::Metrics));
+        assert!(subsystems.set(Subsystem::Metrics, false));
+        assert!(!subsystems.is_enabled(Subsystem::Metrics));
+        assert!(subsystems.is_enabled(Subsystem::Battery));
+    }
+}
@@ -0,0 +1,193 @@
+//! Sensitivity suggestions for games without a profile.
+//!
+//! During the first session of an unknown game the daemon watches frame
+//! pacing and seeds a profile with a sensitivity guess:
+//!
+//! - Frame-capped games (FPS pinned to one value) settle quickly, so they
+//!   get Aggressive.
+//! - Games with high frametime variance get Conservative to avoid chasing
+//!   spikes.
+//! - Everything else gets Balanced, unless pacing is very steady.
+//!
+//! The resulting profile is marked `suggested` until the user accepts or
+//! edits it.
+
+use crate::core_logic::Sensitivity;
+
+/// Samples needed before suggesting (60 seconds at 100ms polling)
+pub const SUGGESTION_SAMPLE_COUNT: usize = 600;
+
+/// Samples within this distance of the median FPS count towards a frame cap
+const CAP_TOLERANCE_FPS: f64 = 1.5;
+
+/// Fraction of samples that must sit at the median to detect a frame cap
+const CAP_FRACTION: f64 = 0.9;
+
+/// Frametime coefficient of variation above which pacing is unstable
+const FRAMETIME_CV_UNSTABLE: f64 = 0.15;
+
+/// Frametime coefficient of variation below which pacing is very steady
+const FRAMETIME_CV_STABLE: f64 = 0.05;
+
+/// Result of a first-session analysis.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Suggestion {
+    pub sensitivity: Sensitivity,
+    /// Whether the game appeared to run under a frame cap
+    pub frame_capped: bool,
+    /// Frametime standard deviation divided by its mean
+    pub frametime_cv: f64,
+}
+
+/// Collects frame pacing for one game session.
+#[derive(Debug, Clone)]
+pub struct SessionObserver {
+    app_id: String,
+    name: String,
+    fps_samples: Vec<f64>,
+    frametimes: Vec<f64>,
+}
+
+impl SessionObserver {
+    pub fn new(app_id: String, name: String) -> Self {
+        Self {
+            app_id,
+            name,
+            fps_samples: Vec::with_capacity(SUGGESTION_SAMPLE_COUNT),
+            frametimes: Vec::with_capacity(SUGGESTION_SAMPLE_COUNT),
+        }
+    }
+
+    pub fn app_id(&self) -> &str {
+        &self.app_id
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Record one FPS/frametime sample. Idle samples (zero) are ignored.
+    pub fn record(&mut self, fps: f64, frametime_us: u64) {
+        if fps <= 0.0 || frametime_us == 0 || self.is_complete() {
+            return;
+        }
+        self.fps_samples.push(fps);
+        self.frametimes.push(frametime_us as f64);
+    }
+
+    /// Whether enough samples were collected to make a suggestion.
+    pub fn is_complete(&self) -> bool {
+        self.fps_samples.len() >= SUGGESTION_SAMPLE_COUNT
+    }
+
+    /// Analyze collected samples. Returns None until complete.
+    pub fn suggest(&self) -> Option<Suggestion> {
+        if !self.is_complete() {
+            return None;
+        }
+
+        let frame_capped = is_frame_capped(&self.fps_samples);
+        let frametime_cv = coefficient_of_variation(&self.frametimes);
+
+        let sensitivity = if frame_capped || frametime_cv < FRAMETIME_CV_STABLE {
+            Sensitivity::Aggressive
+        } else if frametime_cv > FRAMETIME_CV_UNSTABLE {
+            Sensitivity::Conservative
+        } else {
+            Sensitivity::Balanced
+        };
+
+        Some(Suggestion {
+            sensitivity,
+            frame_capped,
+            frametime_cv,
+        })
+    }
+}
+
+/// Detect a frame cap: nearly all samples sit at the median FPS.
+fn is_frame_capped(fps_samples: &[f64]) -> bool {
+    if fps_samples.is_empty() {
+        return false;
+    }
+    let mut sorted = fps_samples.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let median = sorted[sorted.len() / 2];
+
+    let at_median = fps_samples
+        .iter()
+        .filter(|fps| (*fps - median).abs() <= CAP_TOLERANCE_FPS)
+        .count();
+    at_median as f64 / fps_samples.len() as f64 >= CAP_FRACTION
+}
+
+fn coefficient_of_variation(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    let mean = values.iter().sum::<f64>() / values.len() as f64;
+    if mean <= 0.0 {
+        return 0.0;
+    }
+    let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / values.len() as f64;
+    variance.sqrt() / mean
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn observe(samples: impl Iterator<Item = (f64, u64)>) -> SessionObserver {
+        let mut observer = SessionObserver::new("123".to_string(), "Test".to_string());
+        for (fps, frametime) in samples.take(SUGGESTION_SAMPLE_COUNT) {
+            observer.record(fps, frametime);
+        }
+        observer
+    }
+
+    #[test]
+    fn test_no_suggestion_until_complete() {
+        let mut observer = SessionObserver::new("123".to_string(), "Test".to_string());
+        observer.record(60.0, 16_667);
+        observer.record(0.0, 0);
+        assert!(!observer.is_complete());
+        assert_eq!(observer.suggest(), None);
+    }
+
+    #[test]
+    fn test_frame_capped_game_is_aggressive() {
+        // 40 FPS cap with occasional dips and noisy frametimes
+        let observer = observe((0..).map(|i| {
+            let fps = if i % 20 == 0 { 34.0 } else { 40.0 };
+            let frametime = if i % 2 == 0 { 20_000 } else { 30_000 };
+            (fps, frametime)
+        }));
+        let suggestion = observer.suggest().unwrap();
+        assert!(suggestion.frame_capped);
+        assert_eq!(suggestion.sensitivity, Sensitivity::Aggressive);
+    }
+
+    #[test]
+    fn test_spiky_game_is_conservative() {
+        let observer = observe((0..).map(|i| {
+            let fps = 45.0 + (i % 30) as f64;
+            let frametime = if i % 4 == 0 { 35_000 } else { 16_000 };
+            (fps, frametime)
+        }));
+        let suggestion = observer.suggest().unwrap();
+        assert!(!suggestion.frame_capped);
+        assert!(suggestion.frametime_cv > FRAMETIME_CV_UNSTABLE);
+        assert_eq!(suggestion.sensitivity, Sensitivity::Conservative);
+    }
+
+    #[test]
+    fn test_moderate_variance_is_balanced() {
+        let observer = observe((0..).map(|i| {
+            let fps = 50.0 + (i % 20) as f64;
+            let frametime = if i % 2 == 0 { 15_000 } else { 18_000 };
+            (fps, frametime)
+        }));
+        let suggestion = observer.suggest().unwrap();
+        assert_eq!(suggestion.sensitivity, Sensitivity::Balanced);
+    }
+}
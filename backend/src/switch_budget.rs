@@ -0,0 +1,153 @@
+//! Hourly/daily cap on automatic refresh rate switches.
+//!
+//! Some users worry about panel or LCD driver wear from frequent mode sets.
+//! With a budget configured, every switch is counted; as the budget runs low
+//! the controller's FPS tolerance is widened so only larger FPS changes cause
+//! a switch, and once it is spent automatic switching stops until old
+//! switches age out of the window.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::SwitchBudgetConfig;
+
+const HOUR: Duration = Duration::from_secs(3600);
+const DAY: Duration = Duration::from_secs(24 * 3600);
+
+/// Budget usage at which tolerance starts widening
+const DEGRADE_START: f64 = 0.75;
+
+/// Extra FPS tolerance when the budget is fully spent
+const MAX_TOLERANCE_BOOST: f64 = 5.0;
+
+/// Switch usage reported in status.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct SwitchBudgetStatus {
+    pub used_last_hour: u32,
+    pub used_last_day: u32,
+    pub per_hour: Option<u32>,
+    pub per_day: Option<u32>,
+    /// Extra FPS tolerance currently applied
+    pub tolerance_boost: f64,
+}
+
+/// Sliding-window switch counter.
+#[derive(Debug, Default)]
+pub struct SwitchBudget {
+    /// Switch times within the last day, oldest first
+    switches: VecDeque<Instant>,
+}
+
+impl SwitchBudget {
+    /// Count a switch at `now`.
+    pub fn record(&mut self, now: Instant) {
+        self.prune(now);
+        self.switches.push_back(now);
+    }
+
+    fn prune(&mut self, now: Instant) {
+        while self
+            .switches
+            .front()
+            .is_some_and(|t| now.duration_since(*t) >= DAY)
+        {
+            self.switches.pop_front();
+        }
+    }
+
+    /// Switches in the last hour and the last day.
+    pub fn used(&mut self, now: Instant) -> (u32, u32) {
+        self.prune(now);
+        let hour = self
+            .switches
+            .iter()
+            .rev()
+            .take_while(|t| now.duration_since(**t) < HOUR)
+            .count();
+        (hour as u32, self.switches.len() as u32)
+    }
+
+    /// Fraction of the tighter of the two limits already used (may exceed 1.0).
+    pub fn usage(&mut self, limits: &SwitchBudgetConfig, now: Instant) -> f64 {
+        let (hour, day) = self.used(now);
+        let fraction = |used: u32, limit: Option<u32>| {
+            limit.map_or(0.0, |limit| used as f64 / limit.max(1) as f64)
+        };
+        fraction(hour, limits.per_hour).max(fraction(day, limits.per_day))
+    }
+
+    /// Whether another automatic switch fits in the budget.
+    pub fn allows(&mut self, limits: &SwitchBudgetConfig, now: Instant) -> bool {
+        self.usage(limits, now) < 1.0
+    }
+
+    pub fn status(&mut self, limits: &SwitchBudgetConfig, now: Instant) -> SwitchBudgetStatus {
+        let (used_last_hour, used_last_day) = self.used(now);
+        SwitchBudgetStatus {
+            used_last_hour,
+            used_last_day,
+            per_hour: limits.per_hour,
+            per_day: limits.per_day,
+            tolerance_boost: tolerance_boost(self.usage(limits, now)),
+        }
+    }
+}
+
+/// Extra FPS tolerance for a budget usage fraction.
+///
+/// Zero until `DEGRADE_START`, then rises linearly to `MAX_TOLERANCE_BOOST`.
+pub fn tolerance_boost(usage: f64) -> f64 {
+    if usage <= DEGRADE_START {
+        return 0.0;
+    }
+    let progress = ((usage - DEGRADE_START) / (1.0 - DEGRADE_START)).min(1.0);
+    progress * MAX_TOLERANCE_BOOST
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hourly(limit: u32) -> SwitchBudgetConfig {
+        SwitchBudgetConfig { per_hour: Some(limit), per_day: None }
+    }
+
+    #[test]
+    fn test_budget_exhausts_and_recovers() {
+        let limits = hourly(4);
+        let mut budget = SwitchBudget::default();
+        let start = Instant::now();
+
+        for i in 0..4 {
+            assert!(budget.allows(&limits, start + Duration::from_secs(i)));
+            budget.record(start + Duration::from_secs(i));
+        }
+        assert!(!budget.allows(&limits, start + Duration::from_secs(10)));
+        // The first switch ages out of the hourly window
+        assert!(budget.allows(&limits, start + HOUR + Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn test_daily_limit_counts_whole_day() {
+        let limits = SwitchBudgetConfig { per_hour: None, per_day: Some(2) };
+        let mut budget = SwitchBudget::default();
+        let start = Instant::now();
+        budget.record(start);
+        budget.record(start + 2 * HOUR);
+
+        assert!(!budget.allows(&limits, start + 3 * HOUR));
+        assert_eq!(budget.used(start + 3 * HOUR), (0, 2));
+        assert!(budget.allows(&limits, start + DAY + Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn test_tolerance_boost_ramps_near_limit() {
+        assert_eq!(tolerance_boost(0.5), 0.0);
+        assert_eq!(tolerance_boost(DEGRADE_START), 0.0);
+        assert!(tolerance_boost(0.9) > 0.0 && tolerance_boost(0.9) < MAX_TOLERANCE_BOOST);
+        assert_eq!(tolerance_boost(1.0), MAX_TOLERANCE_BOOST);
+        assert_eq!(tolerance_boost(3.0), MAX_TOLERANCE_BOOST);
+    }
+}
@@ -0,0 +1,114 @@
+//! In-progress OS update detection.
+//!
+//! A mode switch while `steamos-update` or a Discover transaction runs can
+//! stall the compositor at a bad moment, so the rate is held until the
+//! update finishes. Updaters are found by process name and package manager
+//! lock file, PackageKit transactions by its `Locked` D-Bus property.
+
+use std::path::{Path, PathBuf};
+
+/// Updater processes by `/proc/*/comm` (truncated to 15 bytes)
+const UPDATER_PROCESSES: [&str; 3] = ["steamos-update", "steamos-atomupd", "pacman"];
+
+/// Lock files that only exist while a package transaction runs
+const UPDATE_LOCK_FILES: [&str; 1] = ["/var/lib/pacman/db.lck"];
+
+const PACKAGEKIT_NAME: &str = "org.freedesktop.PackageKit";
+
+/// Detects OS updates from the process table and lock files.
+#[derive(Debug, Clone)]
+pub struct UpdateDetector {
+    proc_dir: PathBuf,
+    lock_files: Vec<PathBuf>,
+}
+
+impl Default for UpdateDetector {
+    fn default() -> Self {
+        Self::new(Path::new("/proc"), UPDATE_LOCK_FILES.iter().map(PathBuf::from).collect())
+    }
+}
+
+impl UpdateDetector {
+    pub fn new(proc_dir: &Path, lock_files: Vec<PathBuf>) -> Self {
+        Self { proc_dir: proc_dir.to_path_buf(), lock_files }
+    }
+
+    /// What is updating, if anything: an updater's process name or a
+    /// held lock file.
+    pub fn detect(&self) -> Option<String> {
+        if let Some(updater) = self.running_updater() {
+            return Some(updater.to_string());
+        }
+        self.lock_files
+            .iter()
+            .find(|path| path.exists())
+            .map(|path| path.display().to_string())
+    }
+
+    fn running_updater(&self) -> Option<&'static str> {
+        std::fs::read_dir(&self.proc_dir).ok()?.flatten().find_map(|entry| {
+            entry.file_name().to_str()?.parse::<u32>().ok()?;
+            let comm = std::fs::read_to_string(entry.path().join("comm")).ok()?;
+            UPDATER_PROCESSES.iter().copied().find(|&name| name == comm.trim())
+        })
+    }
+}
+
+/// Whether PackageKit holds the package manager lock (a Discover or
+/// `pkcon` transaction). Only asks a running PackageKit, so polling never
+/// D-Bus-activates it.
+#[cfg(unix)]
+pub async fn packagekit_locked(connection: &zbus::Connection) -> Result<bool, zbus::Error> {
+    let running: bool = connection
+        .call_method(
+            Some("org.freedesktop.DBus"),
+            "/org/freedesktop/DBus",
+            Some("org.freedesktop.DBus"),
+            "NameHasOwner",
+            &(PACKAGEKIT_NAME,),
+        )
+        .await?
+        .body()
+        .deserialize()?;
+    if !running {
+        return Ok(false);
+    }
+
+    let locked: zbus::zvariant::OwnedValue = connection
+        .call_method(
+            Some(PACKAGEKIT_NAME),
+            "/org/freedesktop/PackageKit",
+            Some("org.freedesktop.DBus.Properties"),
+            "Get",
+            &(PACKAGEKIT_NAME, "Locked"),
+        )
+        .await?
+        .body()
+        .deserialize()?;
+    Ok(bool::try_from(locked).unwrap_or(false))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_detect_update() {
+        let proc_dir = tempdir().unwrap();
+        let lock_dir = tempdir().unwrap();
+        let lock = lock_dir.path().join("db.lck");
+        let detector = UpdateDetector::new(proc_dir.path(), vec![lock.clone()]);
+
+        std::fs::create_dir(proc_dir.path().join("1")).unwrap();
+        std::fs::write(proc_dir.path().join("1").join("comm"), "systemd\n").unwrap();
+        assert_eq!(detector.detect(), None);
+
+        std::fs::write(&lock, "").unwrap();
+        assert_eq!(detector.detect(), Some(lock.display().to_string()));
+
+        std::fs::create_dir(proc_dir.path().join("4242")).unwrap();
+        std::fs::write(proc_dir.path().join("4242").join("comm"), "steamos-atomupd\n").unwrap();
+        assert_eq!(detector.detect(), Some("steamos-atomupd".to_string()));
+    }
+}
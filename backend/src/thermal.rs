@@ -0,0 +1,148 @@
+//! SoC temperature from hwmon, for thermal-aware switching.
+//!
+//! Raising the refresh rate while the APU is already throttling makes the
+//! GPU render more frames it cannot sustain, adding heat and power for
+//! nothing. Above the configured limit the controller stops increasing Hz
+//! until the temperature falls `RELEASE_MARGIN_C` below it again.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+/// hwmon class directory
+pub const HWMON_DIR: &str = "/sys/class/hwmon";
+
+/// hwmon drivers reporting the Steam Deck APU (GPU and CPU dies)
+const SENSOR_NAMES: [&str; 2] = ["amdgpu", "k10temp"];
+
+/// Default temperature at which increases stop
+pub const DEFAULT_THERMAL_LIMIT_C: u32 = 95;
+
+/// Accepted range for the thermal limit
+pub const MIN_THERMAL_LIMIT_C: u32 = 60;
+pub const MAX_THERMAL_LIMIT_C: u32 = 110;
+
+/// How far below the limit the temperature must fall to release the hold
+const RELEASE_MARGIN_C: f64 = 5.0;
+
+/// Temperature inputs of the APU hwmon devices.
+#[derive(Debug, Clone, Default)]
+pub struct ThermalSensor {
+    inputs: Vec<PathBuf>,
+}
+
+impl ThermalSensor {
+    /// Find `temp*_input` files of the APU drivers under `hwmon_dir`.
+    pub fn discover(hwmon_dir: &Path) -> Self {
+        let mut inputs = Vec::new();
+        let Ok(entries) = std::fs::read_dir(hwmon_dir) else {
+            return Self { inputs };
+        };
+        for device in entries.flatten().map(|e| e.path()) {
+            let name = std::fs::read_to_string(device.join("name")).unwrap_or_default();
+            if !SENSOR_NAMES.contains(&name.trim()) {
+                continue;
+            }
+            let Ok(files) = std::fs::read_dir(&device) else {
+                continue;
+            };
+            for file in files.flatten().map(|e| e.path()) {
+                let is_temp_input = file
+                    .file_name()
+                    .and_then(|n| n.to_str())
+                    .is_some_and(|n| n.starts_with("temp") && n.ends_with("_input"));
+                if is_temp_input {
+                    inputs.push(file);
+                }
+            }
+        }
+        inputs.sort();
+        debug!("Thermal inputs: {:?}", inputs);
+        Self { inputs }
+    }
+
+    pub fn is_available(&self) -> bool {
+        !self.inputs.is_empty()
+    }
+
+    /// Hottest reading across all inputs, in degrees Celsius.
+    pub fn read_celsius(&self) -> Option<f64> {
+        self.inputs
+            .iter()
+            .filter_map(|path| std::fs::read_to_string(path).ok())
+            .filter_map(|raw| raw.trim().parse::<i64>().ok())
+            .map(|millidegrees| millidegrees as f64 / 1000.0)
+            .reduce(f64::max)
+    }
+}
+
+/// Thermal state reported in status.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+pub struct ThermalStatus {
+    /// Whether an APU temperature sensor was found
+    pub available: bool,
+    /// Last reading in degrees Celsius
+    pub temp_c: Option<f64>,
+    /// Increases are held until the APU cools down
+    pub throttling: bool,
+}
+
+impl ThermalStatus {
+    /// Update with a new reading against `limit_c`. Returns true when the
+    /// throttling state changed.
+    pub fn update(&mut self, temp_c: Option<f64>, limit_c: u32) -> bool {
+        self.temp_c = temp_c;
+        let limit = limit_c as f64;
+        let throttling = match temp_c {
+            Some(temp) if self.throttling => temp > limit - RELEASE_MARGIN_C,
+            Some(temp) => temp >= limit,
+            None => false,
+        };
+        let changed = throttling != self.throttling;
+        self.throttling = throttling;
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_discover_and_read() {
+        let dir = tempdir().unwrap();
+        let device = |name: &str, driver: &str, temps: &[(&str, &str)]| {
+            let path = dir.path().join(name);
+            std::fs::create_dir(&path).unwrap();
+            std::fs::write(path.join("name"), format!("{}\n", driver)).unwrap();
+            for (file, value) in temps {
+                std::fs::write(path.join(file), value).unwrap();
+            }
+        };
+        device("hwmon0", "amdgpu", &[("temp1_input", "71000\n"), ("temp1_crit", "105000")]);
+        device("hwmon1", "k10temp", &[("temp1_input", "78500")]);
+        device("hwmon2", "nvme", &[("temp1_input", "99000")]);
+
+        let sensor = ThermalSensor::discover(dir.path());
+        assert!(sensor.is_available());
+        assert_eq!(sensor.read_celsius(), Some(78.5));
+
+        assert!(!ThermalSensor::discover(&dir.path().join("missing")).is_available());
+    }
+
+    #[test]
+    fn test_throttling_hysteresis() {
+        let mut status = ThermalStatus { available: true, ..Default::default() };
+        assert!(!status.update(Some(94.0), 95));
+        assert!(status.update(Some(95.0), 95));
+        assert!(status.throttling);
+        // Stays throttled until 5 degrees below the limit
+        assert!(!status.update(Some(91.0), 95));
+        assert!(status.update(Some(90.0), 95));
+        assert!(!status.throttling);
+
+        status.update(Some(100.0), 95);
+        assert!(status.update(None, 95));
+    }
+}
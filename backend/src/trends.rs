@@ -0,0 +1,381 @@
+//! Long-term trend tracking and drift detection.
+//!
+//! Active play time, average FPS and switch counts are aggregated per day
+//! and game into ~/.local/share/smart-refresh/trends.json. Comparing the
+//! last week against older history surfaces slow drift that is invisible
+//! within one session, e.g. a game's achievable FPS dropping over weeks
+//! (dust, thermal paste) or switch counts creeping up after an OS update.
+
+use crate::persist::atomic_persist;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::warn;
+
+/// Data directory relative to $HOME
+const TRENDS_DIR: &str = ".local/share/smart-refresh";
+
+/// Days of history kept on disk
+const TREND_HISTORY_DAYS: u64 = 90;
+
+/// Days counted as "recent"
+const RECENT_WINDOW_DAYS: u64 = 7;
+
+/// Baseline only uses data at least this many days old
+const BASELINE_MIN_AGE_DAYS: u64 = 14;
+
+/// Active seconds required in each window before comparing
+const MIN_ACTIVE_SECS: u64 = 1800;
+
+/// Relative FPS drop that triggers an advisory
+const FPS_DECLINE_THRESHOLD: f64 = 0.10;
+
+/// Relative switch rate increase that triggers an advisory
+const SWITCH_RATE_INCREASE_THRESHOLD: f64 = 0.5;
+
+/// Recent switches per hour below this are never reported
+const MIN_SWITCH_RATE_PER_HOUR: f64 = 6.0;
+
+/// Aggregated activity for one game on one day.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DailySummary {
+    /// Days since the Unix epoch
+    pub day: u64,
+    /// Steam AppID, or None outside of games
+    pub app_id: Option<String>,
+    /// Seconds with FPS data while the daemon was running
+    pub active_secs: u64,
+    /// Sum of FPS weighted by seconds (avg FPS = fps_secs / active_secs)
+    pub fps_secs: f64,
+    /// Refresh rate switches
+    pub switches: u64,
+    /// OS version last seen on this day
+    pub os_version: Option<String>,
+}
+
+/// Kind of drift detected.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AdvisoryKind {
+    /// A game's average FPS dropped compared to its history
+    FpsDecline,
+    /// Refresh rate switches per hour increased
+    SwitchRateIncrease,
+}
+
+/// Advisory event the UI can surface.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Advisory {
+    pub kind: AdvisoryKind,
+    pub app_id: Option<String>,
+    pub message: String,
+    /// Baseline value (average FPS or switches per hour)
+    pub baseline: f64,
+    /// Recent value in the same unit
+    pub recent: f64,
+}
+
+/// Totals over a set of daily summaries.
+#[derive(Debug, Default)]
+struct WindowStats {
+    active_secs: u64,
+    fps_secs: f64,
+    switches: u64,
+    /// OS version of the most recent day that reported one
+    os_version: Option<String>,
+}
+
+impl WindowStats {
+    fn collect<'a>(days: impl Iterator<Item = &'a DailySummary>) -> Self {
+        let mut stats = Self::default();
+        let mut latest_day = None;
+        for summary in days {
+            stats.active_secs += summary.active_secs;
+            stats.fps_secs += summary.fps_secs;
+            stats.switches += summary.switches;
+            if summary.os_version.is_some() && latest_day.is_none_or(|d| summary.day >= d) {
+                latest_day = Some(summary.day);
+                stats.os_version = summary.os_version.clone();
+            }
+        }
+        stats
+    }
+
+    fn has_enough_data(&self) -> bool {
+        self.active_secs >= MIN_ACTIVE_SECS
+    }
+
+    fn avg_fps(&self) -> f64 {
+        self.fps_secs / self.active_secs as f64
+    }
+
+    fn switches_per_hour(&self) -> f64 {
+        self.switches as f64 * 3600.0 / self.active_secs as f64
+    }
+}
+
+/// Persistent daily history with drift analysis.
+pub struct TrendStore {
+    path: PathBuf,
+    days: Vec<DailySummary>,
+}
+
+impl TrendStore {
+    /// Default history path (~/.local/share/smart-refresh/trends.json).
+    pub fn default_path() -> PathBuf {
+        match std::env::var_os("HOME") {
+            Some(home) => PathBuf::from(home).join(TRENDS_DIR).join("trends.json"),
+            None => PathBuf::from("/tmp/smart-refresh/trends.json"),
+        }
+    }
+
+    /// Load history, starting empty if the file is missing or unreadable.
+    pub fn load_or_default(path: &Path) -> Self {
+        let days = match std::fs::read_to_string(path) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+                warn!("Failed to parse trend history: {}, starting fresh", e);
+                Vec::new()
+            }),
+            Err(_) => Vec::new(),
+        };
+        Self {
+            path: path.to_path_buf(),
+            days,
+        }
+    }
+
+    /// Persist history with an atomic write.
+    pub fn save(&self) -> Result<(), std::io::Error> {
+        let json = serde_json::to_string(&self.days).map_err(std::io::Error::other)?;
+        atomic_persist(&self.path, json.as_bytes())
+    }
+
+    /// Add activity to the summary for `day` and `app_id`.
+    pub fn record(
+        &mut self,
+        day: u64,
+        app_id: Option<&str>,
+        active_secs: u64,
+        fps: f64,
+        switches: u64,
+        os_version: Option<&str>,
+    ) {
+        let index = match self
+            .days
+            .iter()
+            .position(|s| s.day == day && s.app_id.as_deref() == app_id)
+        {
+            Some(index) => index,
+            None => {
+                self.days.push(DailySummary {
+                    day,
+                    app_id: app_id.map(str::to_string),
+                    active_secs: 0,
+                    fps_secs: 0.0,
+                    switches: 0,
+                    os_version: None,
+                });
+                self.days.len() - 1
+            }
+        };
+
+        let summary = &mut self.days[index];
+        summary.active_secs += active_secs;
+        summary.fps_secs += fps * active_secs as f64;
+        summary.switches += switches;
+        if os_version.is_some() {
+            summary.os_version = os_version.map(str::to_string);
+        }
+
+        self.days.retain(|s| s.day + TREND_HISTORY_DAYS > day);
+    }
+
+    // Only tests read the raw history; the daemon uses `analyze`
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn days(&self) -> &[DailySummary] {
+        &self.days
+    }
+
+    /// Compare the recent window against older history.
+    pub fn analyze(&self, today: u64) -> Vec<Advisory> {
+        let is_recent = |s: &&DailySummary| s.day + RECENT_WINDOW_DAYS > today;
+        let is_baseline = |s: &&DailySummary| s.day + BASELINE_MIN_AGE_DAYS <= today;
+        let mut advisories = Vec::new();
+
+        // FPS drift per game
+        let games: BTreeSet<&str> = self.days.iter().filter_map(|s| s.app_id.as_deref()).collect();
+        for app_id in games {
+            let for_game = |s: &&DailySummary| s.app_id.as_deref() == Some(app_id);
+            let recent = WindowStats::collect(self.days.iter().filter(for_game).filter(is_recent));
+            let baseline = WindowStats::collect(self.days.iter().filter(for_game).filter(is_baseline));
+            if !recent.has_enough_data() || !baseline.has_enough_data() {
+                continue;
+            }
+
+            let (before, now) = (baseline.avg_fps(), recent.avg_fps());
+            if now < before * (1.0 - FPS_DECLINE_THRESHOLD) {
+                advisories.push(Advisory {
+                    kind: AdvisoryKind::FpsDecline,
+                    app_id: Some(app_id.to_string()),
+                    message: format!(
+                        "Average FPS in {} dropped from {:.1} to {:.1}; check cooling (dust, thermal paste)",
+                        app_id, before, now
+                    ),
+                    baseline: before,
+                    recent: now,
+                });
+            }
+        }
+
+        // Switch rate drift across all activity
+        let recent = WindowStats::collect(self.days.iter().filter(is_recent));
+        let baseline = WindowStats::collect(self.days.iter().filter(is_baseline));
+        if recent.has_enough_data() && baseline.has_enough_data() {
+            let (before, now) = (baseline.switches_per_hour(), recent.switches_per_hour());
+            if now >= MIN_SWITCH_RATE_PER_HOUR && now > before * (1.0 + SWITCH_RATE_INCREASE_THRESHOLD) {
+                let mut message = format!(
+                    "Refresh rate switches increased from {:.1}/h to {:.1}/h",
+                    before, now
+                );
+                if let (Some(old), Some(new)) = (&baseline.os_version, &recent.os_version) {
+                    if old != new {
+                        message.push_str(&format!(" after OS update ({} -> {})", old, new));
+                    }
+                }
+                advisories.push(Advisory {
+                    kind: AdvisoryKind::SwitchRateIncrease,
+                    app_id: None,
+                    message,
+                    baseline: before,
+                    recent: now,
+                });
+            }
+        }
+
+        advisories
+    }
+}
+
+/// Current day number (days since the Unix epoch).
+pub fn current_day() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() / 86_400)
+        .unwrap_or(0)
+}
+
+/// Read VERSION_ID from /etc/os-release.
+pub fn read_os_version() -> Option<String> {
+    let contents = std::fs::read_to_string("/etc/os-release").ok()?;
+    parse_os_version(&contents)
+}
+
+fn parse_os_version(os_release: &str) -> Option<String> {
+    os_release
+        .lines()
+        .find_map(|line| line.strip_prefix("VERSION_ID="))
+        .map(|v| v.trim().trim_matches('"').to_string())
+        .filter(|v| !v.is_empty())
+}
+
+/// Advisories that were not in the previous set.
+pub fn new_advisories<'a>(previous: &'a [Advisory], current: &'a [Advisory]) -> impl Iterator<Item = &'a Advisory> {
+    current.iter().filter(move |advisory| {
+        !previous
+            .iter()
+            .any(|p| p.kind == advisory.kind && p.app_id == advisory.app_id)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    const TODAY: u64 = 20_000;
+
+    fn store() -> TrendStore {
+        TrendStore {
+            path: PathBuf::from("/nonexistent/trends.json"),
+            days: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_record_merges_same_day_and_game() {
+        let mut store = store();
+        store.record(TODAY, Some("620"), 60, 50.0, 1, None);
+        store.record(TODAY, Some("620"), 60, 40.0, 2, Some("3.6"));
+        store.record(TODAY, None, 60, 60.0, 0, None);
+
+        assert_eq!(store.days().len(), 2);
+        let game = &store.days()[0];
+        assert_eq!(game.active_secs, 120);
+        assert_eq!(game.fps_secs / game.active_secs as f64, 45.0);
+        assert_eq!(game.switches, 3);
+        assert_eq!(game.os_version.as_deref(), Some("3.6"));
+    }
+
+    #[test]
+    fn test_old_history_is_pruned() {
+        let mut store = store();
+        store.record(TODAY - TREND_HISTORY_DAYS, Some("620"), 60, 50.0, 0, None);
+        store.record(TODAY, Some("620"), 60, 50.0, 0, None);
+        assert_eq!(store.days().len(), 1);
+    }
+
+    #[test]
+    fn test_fps_decline_detected() {
+        let mut store = store();
+        store.record(TODAY - 30, Some("620"), 3600, 60.0, 10, None);
+        store.record(TODAY - 1, Some("620"), 3600, 50.0, 10, None);
+
+        let advisories = store.analyze(TODAY);
+        assert_eq!(advisories.len(), 1);
+        assert_eq!(advisories[0].kind, AdvisoryKind::FpsDecline);
+        assert_eq!(advisories[0].app_id.as_deref(), Some("620"));
+        assert_eq!(advisories[0].baseline, 60.0);
+        assert_eq!(advisories[0].recent, 50.0);
+    }
+
+    #[test]
+    fn test_no_advisory_without_enough_data() {
+        let mut store = store();
+        store.record(TODAY - 30, Some("620"), 3600, 60.0, 0, None);
+        store.record(TODAY - 1, Some("620"), MIN_ACTIVE_SECS - 1, 30.0, 100, None);
+        assert!(store.analyze(TODAY).is_empty());
+    }
+
+    #[test]
+    fn test_switch_rate_increase_mentions_os_update() {
+        let mut store = store();
+        store.record(TODAY - 30, Some("620"), 3600, 60.0, 6, Some("3.5"));
+        store.record(TODAY - 2, Some("620"), 3600, 60.0, 20, Some("3.6"));
+
+        let advisories = store.analyze(TODAY);
+        assert_eq!(advisories.len(), 1);
+        assert_eq!(advisories[0].kind, AdvisoryKind::SwitchRateIncrease);
+        assert!(advisories[0].message.contains("3.5 -> 3.6"), "{}", advisories[0].message);
+    }
+
+    #[test]
+    fn test_save_and_load_roundtrip() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("trends.json");
+
+        let mut store = TrendStore::load_or_default(&path);
+        store.record(TODAY, Some("620"), 60, 50.0, 1, Some("3.6"));
+        store.save().unwrap();
+
+        let loaded = TrendStore::load_or_default(&path);
+        assert_eq!(loaded.days(), store.days());
+    }
+
+    #[test]
+    fn test_parse_os_version() {
+        let os_release = "NAME=\"SteamOS\"\nVERSION_ID=\"3.6.19\"\nBUILD_ID=20241010\n";
+        assert_eq!(parse_os_version(os_release).as_deref(), Some("3.6.19"));
+        assert_eq!(parse_os_version("NAME=Arch\n"), None);
+    }
+}
@@ -0,0 +1,136 @@
+//! Unit newtypes for refresh rates and durations.
+//!
+//! Config, controller, display and IPC values used to be bare integers,
+//! which made it easy to pass milliseconds where seconds were expected or a
+//! frame rate where a refresh rate was. These wrappers serialize as the
+//! plain number, so the config file and IPC wire format are unchanged.
+
+use std::fmt;
+use std::ops::{Add, Sub};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+macro_rules! unit_newtype {
+    ($(#[$meta:meta])* $name:ident($inner:ty)) => {
+        $(#[$meta])*
+        #[derive(
+            Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+        )]
+        #[serde(transparent)]
+        pub struct $name(pub $inner);
+
+        impl $name {
+            pub const fn get(self) -> $inner {
+                self.0
+            }
+
+            pub const fn abs_diff(self, other: Self) -> $inner {
+                self.0.abs_diff(other.0)
+            }
+
+            pub const fn saturating_sub(self, other: Self) -> Self {
+                Self(self.0.saturating_sub(other.0))
+            }
+        }
+
+        impl From<$inner> for $name {
+            fn from(value: $inner) -> Self {
+                Self(value)
+            }
+        }
+
+        impl From<$name> for $inner {
+            fn from(value: $name) -> Self {
+                value.0
+            }
+        }
+
+        impl Add for $name {
+            type Output = Self;
+            fn add(self, rhs: Self) -> Self {
+                Self(self.0 + rhs.0)
+            }
+        }
+
+        impl Sub for $name {
+            type Output = Self;
+            fn sub(self, rhs: Self) -> Self {
+                Self(self.0 - rhs.0)
+            }
+        }
+
+        /// Formats as the bare number so existing "{}Hz"/"{}ms" messages read the same.
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt::Display::fmt(&self.0, f)
+            }
+        }
+    };
+}
+
+unit_newtype! {
+    /// A display refresh rate.
+    Hertz(u32)
+}
+
+unit_newtype! {
+    /// A duration in milliseconds.
+    Millis(u64)
+}
+
+unit_newtype! {
+    /// A duration in whole seconds.
+    Secs(u64)
+}
+
+impl Hertz {
+    pub fn as_f64(self) -> f64 {
+        self.0 as f64
+    }
+}
+
+impl From<Millis> for Duration {
+    fn from(value: Millis) -> Self {
+        Duration::from_millis(value.0)
+    }
+}
+
+impl From<Secs> for Duration {
+    fn from(value: Secs) -> Self {
+        Duration::from_secs(value.0)
+    }
+}
+
+impl From<Secs> for Millis {
+    fn from(value: Secs) -> Self {
+        Millis(value.0.saturating_mul(1000))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serializes_as_plain_number() {
+        assert_eq!(serde_json::to_string(&Hertz(90)).unwrap(), "90");
+        assert_eq!(serde_json::from_str::<Millis>("750").unwrap(), Millis(750));
+        assert_eq!(
+            serde_json::from_str::<Option<Secs>>("null").unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_conversions_and_arithmetic() {
+        assert_eq!(Duration::from(Millis(1500)), Duration::from_millis(1500));
+        assert_eq!(Duration::from(Secs(2)), Duration::from_secs(2));
+        assert_eq!(Millis::from(Secs(3)), Millis(3000));
+        assert_eq!(Hertz(90) - Hertz(5), Hertz(85));
+        assert_eq!(Hertz(40).saturating_sub(Hertz(45)), Hertz(0));
+        assert_eq!(Hertz(60).abs_diff(Hertz(45)), 15);
+        assert_eq!(format!("{}Hz", Hertz(60)), "60Hz");
+        assert!(Hertz(40) < Hertz(90));
+    }
+}
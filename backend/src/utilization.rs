@@ -0,0 +1,180 @@
+//! GPU busy and CPU load sampling, used as a controller input.
+//!
+//! When the GPU is already pegged, a higher refresh rate cannot be
+//! sustained and only costs power, so increases are held while the recent
+//! average GPU busy percentage stays above `GPU_SATURATED_PERCENT`.
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+
+/// DRM class directory with one `card*` entry per GPU
+pub const DRM_DIR: &str = "/sys/class/drm";
+
+/// /proc/stat, for CPU load
+pub const PROC_STAT: &str = "/proc/stat";
+
+/// Average GPU busy percentage above which increases are held
+pub const GPU_SATURATED_PERCENT: f64 = 95.0;
+
+/// GPU samples averaged (gpu_busy_percent is an instantaneous reading)
+const GPU_AVERAGE_SAMPLES: usize = 3;
+
+/// Aggregate CPU jiffies from the first line of /proc/stat.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct CpuTimes {
+    busy: u64,
+    total: u64,
+}
+
+impl CpuTimes {
+    fn parse(stat: &str) -> Option<Self> {
+        let line = stat.lines().next()?;
+        let mut fields = line.split_whitespace();
+        if fields.next()? != "cpu" {
+            return None;
+        }
+        let values: Vec<u64> = fields.filter_map(|f| f.parse().ok()).collect();
+        // user nice system idle iowait irq softirq steal ...
+        let idle = values.get(3)? + values.get(4).copied().unwrap_or(0);
+        let total: u64 = values.iter().take(8).sum();
+        Some(Self { busy: total.saturating_sub(idle), total })
+    }
+}
+
+/// GPU and CPU utilization reported in status.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+pub struct UtilizationStatus {
+    /// Whether sampling is enabled in the config
+    pub enabled: bool,
+    /// Last amdgpu `gpu_busy_percent` reading
+    pub gpu_busy_percent: Option<u32>,
+    /// CPU load since the previous sample, 0-100
+    pub cpu_load_percent: Option<f64>,
+    /// Increases are held because the GPU is pegged
+    pub gpu_saturated: bool,
+}
+
+/// Reads GPU busy and CPU load on each `sample` call.
+#[derive(Debug)]
+pub struct UtilizationSampler {
+    gpu_busy: Option<PathBuf>,
+    proc_stat: PathBuf,
+    last_cpu: Option<CpuTimes>,
+    gpu_history: VecDeque<u32>,
+}
+
+impl UtilizationSampler {
+    /// Use the first `card*/device/gpu_busy_percent` under `drm_dir`.
+    pub fn new(drm_dir: &Path, proc_stat: &Path) -> Self {
+        let mut cards: Vec<PathBuf> = std::fs::read_dir(drm_dir)
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .map(|e| e.path())
+                    .filter(|p| {
+                        p.file_name()
+                            .and_then(|n| n.to_str())
+                            .is_some_and(|n| n.starts_with("card") && !n.contains('-'))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        cards.sort();
+        let gpu_busy = cards
+            .into_iter()
+            .map(|card| card.join("device/gpu_busy_percent"))
+            .find(|path| path.is_file());
+
+        Self {
+            gpu_busy,
+            proc_stat: proc_stat.to_path_buf(),
+            last_cpu: None,
+            gpu_history: VecDeque::with_capacity(GPU_AVERAGE_SAMPLES),
+        }
+    }
+
+    /// Take one sample of both inputs.
+    pub fn sample(&mut self) -> UtilizationStatus {
+        let gpu_busy_percent = self
+            .gpu_busy
+            .as_ref()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|raw| raw.trim().parse::<u32>().ok());
+        match gpu_busy_percent {
+            Some(busy) => {
+                if self.gpu_history.len() >= GPU_AVERAGE_SAMPLES {
+                    self.gpu_history.pop_front();
+                }
+                self.gpu_history.push_back(busy.min(100));
+            }
+            None => self.gpu_history.clear(),
+        }
+        let gpu_saturated = self.gpu_history.len() >= GPU_AVERAGE_SAMPLES
+            && self.gpu_history.iter().sum::<u32>() as f64 / self.gpu_history.len() as f64
+                > GPU_SATURATED_PERCENT;
+
+        let cpu = std::fs::read_to_string(&self.proc_stat)
+            .ok()
+            .and_then(|stat| CpuTimes::parse(&stat));
+        let cpu_load_percent = match (self.last_cpu, cpu) {
+            (Some(prev), Some(now)) if now.total > prev.total => {
+                let busy = now.busy.saturating_sub(prev.busy) as f64;
+                Some(busy * 100.0 / (now.total - prev.total) as f64)
+            }
+            _ => None,
+        };
+        self.last_cpu = cpu;
+
+        UtilizationStatus {
+            enabled: true,
+            gpu_busy_percent,
+            cpu_load_percent,
+            gpu_saturated,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_cpu_times_parse() {
+        let stat = "cpu  100 0 50 800 50 0 0 0 0 0\ncpu0 1 2 3 4\n";
+        assert_eq!(CpuTimes::parse(stat), Some(CpuTimes { busy: 150, total: 1000 }));
+        assert_eq!(CpuTimes::parse("intr 1 2 3"), None);
+    }
+
+    #[test]
+    fn test_sample_saturation_and_load() {
+        let dir = tempdir().unwrap();
+        let device = dir.path().join("card1/device");
+        std::fs::create_dir_all(&device).unwrap();
+        std::fs::create_dir_all(dir.path().join("card1-eDP-1")).unwrap();
+        let busy = device.join("gpu_busy_percent");
+        let stat = dir.path().join("stat");
+
+        std::fs::write(&busy, "99\n").unwrap();
+        std::fs::write(&stat, "cpu 100 0 0 900 0 0 0 0\n").unwrap();
+        let mut sampler = UtilizationSampler::new(dir.path(), &stat);
+        let first = sampler.sample();
+        assert_eq!(first.gpu_busy_percent, Some(99));
+        assert_eq!(first.cpu_load_percent, None);
+        assert!(!first.gpu_saturated);
+
+        std::fs::write(&stat, "cpu 400 0 0 1600 0 0 0 0\n").unwrap();
+        sampler.sample();
+        let third = sampler.sample();
+        assert!(third.gpu_saturated);
+        assert_eq!(third.cpu_load_percent, None);
+
+        // One light frame pulls the average below the limit
+        std::fs::write(&busy, "60").unwrap();
+        std::fs::write(&stat, "cpu 700 0 0 2300 0 0 0 0\n").unwrap();
+        let fourth = sampler.sample();
+        assert!(!fourth.gpu_saturated);
+        assert_eq!(fourth.cpu_load_percent, Some(30.0));
+    }
+}
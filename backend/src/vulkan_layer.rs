@@ -0,0 +1,59 @@
+//! SmartRefresh Vulkan layer FPS source.
+//!
+//! The optional implicit layer in `vulkan-layer/` counts presents and
+//! writes each game's frame rate to `/smartrefresh-fps-<pid>`, in the same
+//! versioned layout as MangoHud's segment, so it's read with the MangoHud
+//! reader. A game that exits without destroying its device leaves the
+//! segment behind, so the source closes once the process is gone.
+
+use crate::error::FpsSourceError;
+use crate::fps_monitor::{process_alive, FpsSample, FpsSmoothing, MangoHudReader};
+use crate::fps_source::{FpsSource, FpsSourceKind};
+use std::path::Path;
+
+/// Segment name prefix the layer writes under
+pub const LAYER_SHM_NAME: &str = "/smartrefresh-fps";
+
+/// Reads the layer's segment for the focused game.
+pub struct VulkanLayerSource {
+    reader: MangoHudReader,
+}
+
+impl VulkanLayerSource {
+    /// Open the segment of the process running `app_id`, or the newest one.
+    pub fn open(app_id: Option<u32>) -> Result<Self, FpsSourceError> {
+        let reader = MangoHudReader::open_for_app(&[LAYER_SHM_NAME.to_string()], app_id)?;
+        Ok(Self { reader })
+    }
+}
+
+impl FpsSource for VulkanLayerSource {
+    fn kind(&self) -> FpsSourceKind {
+        FpsSourceKind::VulkanLayer
+    }
+
+    fn poll(&mut self) -> Result<FpsSample, FpsSourceError> {
+        if let Some(pid) = self.reader.pid() {
+            if !process_alive(Path::new("/proc"), pid) {
+                return Err(FpsSourceError::Closed);
+            }
+        }
+        Ok(self.reader.poll()?)
+    }
+
+    fn smoothed_fps(&self, smoothing: FpsSmoothing) -> f64 {
+        self.reader.get_smoothed_fps(smoothing)
+    }
+
+    fn p99_frametime(&self) -> u64 {
+        self.reader.get_p99_frametime()
+    }
+
+    fn set_window(&mut self, capacity: usize) {
+        self.reader.set_window(capacity)
+    }
+
+    fn pid(&self) -> Option<u32> {
+        self.reader.pid()
+    }
+}
@@ -0,0 +1,415 @@
+//! Optional localhost WebSocket bridge for the frontend.
+//!
+//! Speaks the same JSON command protocol as the Unix socket: each text
+//! message is one command and is answered with one text message. Only the
+//! subset of RFC 6455 needed for that is implemented (no extensions, no
+//! fragmented messages).
+//!
+//! Peer credentials are not available over TCP, so the listener binds to
+//! 127.0.0.1 only, requires the Steam client's origin and the per-start
+//! bridge token (`GET /?token=...`, see [`crate::bridge_token`]), and refuses
+//! commands that store shell commands (profile hooks).
+
+use crate::bridge_token::BridgeToken;
+use crate::error::IpcError;
+use crate::ipc_server::{DaemonState, IpcServer};
+use sha1::{Digest, Sha1};
+use std::sync::Arc;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// GUID appended to the client key for Sec-WebSocket-Accept (RFC 6455 §1.3)
+const WEBSOCKET_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+/// Maximum size of the HTTP upgrade request
+const MAX_HANDSHAKE_BYTES: usize = 8192;
+
+/// Maximum accepted message payload
+const MAX_PAYLOAD_BYTES: u64 = 64 * 1024;
+
+/// Browser origins allowed to connect (the Decky frontend runs here)
+const ALLOWED_ORIGINS: &[&str] = &["https://steamloopback.host"];
+
+const OPCODE_TEXT: u8 = 0x1;
+const OPCODE_CLOSE: u8 = 0x8;
+const OPCODE_PING: u8 = 0x9;
+const OPCODE_PONG: u8 = 0xA;
+
+/// Close status: unsupported data (binary or fragmented messages)
+const CLOSE_UNSUPPORTED: u16 = 1003;
+
+/// Close status: protocol error (unmasked or oversized frame)
+const CLOSE_PROTOCOL_ERROR: u16 = 1002;
+
+/// A decoded client frame.
+#[derive(Debug, PartialEq)]
+struct Frame {
+    fin: bool,
+    opcode: u8,
+    payload: Vec<u8>,
+}
+
+/// Compute Sec-WebSocket-Accept for a client key.
+fn accept_key(client_key: &str) -> String {
+    let mut hasher = Sha1::new();
+    hasher.update(client_key.trim().as_bytes());
+    hasher.update(WEBSOCKET_GUID.as_bytes());
+    base64_encode(&hasher.finalize())
+}
+
+fn base64_encode(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3F) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// Look up a header value (case-insensitive name).
+fn header<'a>(request: &'a str, name: &str) -> Option<&'a str> {
+    request.lines().skip(1).find_map(|line| {
+        let (key, value) = line.split_once(':')?;
+        key.trim().eq_ignore_ascii_case(name).then(|| value.trim())
+    })
+}
+
+/// Token from the `token` query parameter of the request line.
+fn request_token(request: &str) -> Option<&str> {
+    let target = request.lines().next()?.split_whitespace().nth(1)?;
+    let (_, query) = target.split_once('?')?;
+    query.split('&').find_map(|pair| pair.strip_prefix("token="))
+}
+
+/// Validate an upgrade request and build the HTTP response to send.
+///
+/// Returns Err with a complete HTTP error response if the upgrade is refused.
+fn handshake_response(request: &str, token: &BridgeToken) -> Result<String, String> {
+    let reject = |status: &str| format!("HTTP/1.1 {}\r\nConnection: close\r\nContent-Length: 0\r\n\r\n", status);
+
+    if !request.starts_with("GET ") {
+        return Err(reject("405 Method Not Allowed"));
+    }
+    let is_upgrade = header(request, "Upgrade").is_some_and(|v| v.eq_ignore_ascii_case("websocket"));
+    let Some(key) = header(request, "Sec-WebSocket-Key").filter(|_| is_upgrade) else {
+        return Err(reject("400 Bad Request"));
+    };
+    match header(request, "Origin") {
+        Some(origin) if ALLOWED_ORIGINS.contains(&origin) => {}
+        Some(origin) => {
+            tracing::warn!("Rejected WebSocket connection from origin {}", origin);
+            return Err(reject("403 Forbidden"));
+        }
+        None => {
+            tracing::warn!("Rejected WebSocket connection without an Origin header");
+            return Err(reject("403 Forbidden"));
+        }
+    }
+    if !request_token(request).is_some_and(|t| token.matches(t)) {
+        tracing::warn!("Rejected WebSocket connection without a valid bridge token");
+        return Err(reject("401 Unauthorized"));
+    }
+
+    Ok(format!(
+        "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+        accept_key(key)
+    ))
+}
+
+/// Read the HTTP upgrade request up to the blank line.
+async fn read_handshake<S: AsyncRead + Unpin>(stream: &mut S) -> Result<String, IpcError> {
+    let mut buf = Vec::with_capacity(1024);
+    let mut byte = [0u8; 1];
+    while !buf.ends_with(b"\r\n\r\n") {
+        if buf.len() >= MAX_HANDSHAKE_BYTES || stream.read(&mut byte).await? == 0 {
+            return Err(IpcError::ConnectionDropped);
+        }
+        buf.push(byte[0]);
+    }
+    String::from_utf8(buf).map_err(|_| IpcError::InvalidCommand("Invalid handshake".to_string()))
+}
+
+/// Read one client frame. Client frames must be masked.
+async fn read_frame<S: AsyncRead + Unpin>(stream: &mut S) -> Result<Frame, IpcError> {
+    let mut head = [0u8; 2];
+    stream.read_exact(&mut head).await?;
+    let fin = head[0] & 0x80 != 0;
+    let opcode = head[0] & 0x0F;
+    let masked = head[1] & 0x80 != 0;
+
+    let len = match head[1] & 0x7F {
+        126 => {
+            let mut ext = [0u8; 2];
+            stream.read_exact(&mut ext).await?;
+            u16::from_be_bytes(ext) as u64
+        }
+        127 => {
+            let mut ext = [0u8; 8];
+            stream.read_exact(&mut ext).await?;
+            u64::from_be_bytes(ext)
+        }
+        len => len as u64,
+    };
+    if !masked {
+        return Err(IpcError::InvalidCommand("Unmasked client frame".to_string()));
+    }
+    if len > MAX_PAYLOAD_BYTES {
+        return Err(IpcError::InvalidCommand(format!("Frame of {} bytes is too large", len)));
+    }
+
+    let mut mask = [0u8; 4];
+    stream.read_exact(&mut mask).await?;
+    let mut payload = vec![0u8; len as usize];
+    stream.read_exact(&mut payload).await?;
+    for (i, byte) in payload.iter_mut().enumerate() {
+        *byte ^= mask[i % 4];
+    }
+
+    Ok(Frame { fin, opcode, payload })
+}
+
+/// Encode an unmasked server frame.
+fn encode_frame(opcode: u8, payload: &[u8]) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(payload.len() + 10);
+    frame.push(0x80 | opcode);
+    match payload.len() {
+        len if len < 126 => frame.push(len as u8),
+        len if len <= u16::MAX as usize => {
+            frame.push(126);
+            frame.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+        len => {
+            frame.push(127);
+            frame.extend_from_slice(&(len as u64).to_be_bytes());
+        }
+    }
+    frame.extend_from_slice(payload);
+    frame
+}
+
+async fn send_close<S: AsyncWrite + Unpin>(stream: &mut S, code: u16) -> Result<(), IpcError> {
+    stream.write_all(&encode_frame(OPCODE_CLOSE, &code.to_be_bytes())).await?;
+    Ok(())
+}
+
+/// Serve one WebSocket connection.
+async fn handle_connection<S>(
+    mut stream: S,
+    state: Arc<DaemonState>,
+    token: Arc<BridgeToken>,
+) -> Result<(), IpcError>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let request = read_handshake(&mut stream).await?;
+    match handshake_response(&request, &token) {
+        Ok(response) => stream.write_all(response.as_bytes()).await?,
+        Err(response) => {
+            stream.write_all(response.as_bytes()).await?;
+            return Ok(());
+        }
+    }
+
+    loop {
+        let frame = match read_frame(&mut stream).await {
+            Ok(frame) => frame,
+            Err(IpcError::InvalidCommand(e)) => {
+                tracing::warn!("Closing WebSocket connection: {}", e);
+                return send_close(&mut stream, CLOSE_PROTOCOL_ERROR).await;
+            }
+            Err(e) => return Err(e),
+        };
+
+        match frame.opcode {
+            OPCODE_TEXT if frame.fin => {
+                let line = String::from_utf8_lossy(&frame.payload);
+                let response = IpcServer::handle_remote_request(line.trim(), &state).await;
+                let text = serde_json::to_string(&response)?;
+                stream.write_all(&encode_frame(OPCODE_TEXT, text.as_bytes())).await?;
+            }
+            OPCODE_PING => {
+                stream.write_all(&encode_frame(OPCODE_PONG, &frame.payload)).await?;
+            }
+            OPCODE_PONG => {}
+            OPCODE_CLOSE => {
+                stream.write_all(&encode_frame(OPCODE_CLOSE, &frame.payload)).await?;
+                return Ok(());
+            }
+            // Fragmented text, continuation and binary frames
+            _ => {
+                return send_close(&mut stream, CLOSE_UNSUPPORTED).await;
+            }
+        }
+    }
+}
+
+/// Accept WebSocket connections on 127.0.0.1:`port` until the task is dropped.
+pub async fn run_websocket_server(
+    state: Arc<DaemonState>,
+    token: Arc<BridgeToken>,
+    port: u16,
+) -> Result<(), IpcError> {
+    let listener = TcpListener::bind(("127.0.0.1", port)).await.map_err(|e| {
+        IpcError::SocketBindFailed {
+            path: format!("127.0.0.1:{}", port),
+            source: e,
+        }
+    })?;
+    tracing::info!("WebSocket bridge listening on {}", listener.local_addr()?);
+    serve(listener, state, token).await
+}
+
+async fn serve(
+    listener: TcpListener,
+    state: Arc<DaemonState>,
+    token: Arc<BridgeToken>,
+) -> Result<(), IpcError> {
+    loop {
+        let (stream, _addr): (TcpStream, _) = listener.accept().await?;
+        let state = Arc::clone(&state);
+        let token = Arc::clone(&token);
+        tokio::spawn(async move {
+            if let Err(e) = handle_connection(stream, state, token).await {
+                tracing::debug!("WebSocket connection ended: {}", e);
+            }
+        });
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::battery::BatteryMonitor;
+    use crate::config::ConfigManager;
+    use crate::metrics::MetricsCollector;
+    use crate::profiles::ProfileManager;
+    use tempfile::tempdir;
+    use tokio::sync::RwLock;
+
+    fn test_state(dir: &std::path::Path) -> Arc<DaemonState> {
+        let config = ConfigManager::load_or_default(&dir.join("config.json")).unwrap();
+        Arc::new(DaemonState::new(
+            Arc::new(config),
+            Arc::new(RwLock::new(ProfileManager::default())),
+            Arc::new(MetricsCollector::new()),
+            Arc::new(BatteryMonitor::new()),
+        ))
+    }
+
+    fn masked_frame(opcode: u8, payload: &[u8]) -> Vec<u8> {
+        let mask = [0x12, 0x34, 0x56, 0x78];
+        let mut frame = vec![0x80 | opcode, 0x80 | payload.len() as u8];
+        frame.extend_from_slice(&mask);
+        frame.extend(payload.iter().enumerate().map(|(i, b)| b ^ mask[i % 4]));
+        frame
+    }
+
+    const SECRET: &str = "0123abcd";
+
+    async fn spawn_server(dir: &std::path::Path) -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let token = Arc::new(BridgeToken::from_secret(SECRET));
+        tokio::spawn(serve(listener, test_state(dir), token));
+        port
+    }
+
+    async fn connect(port: u16, target: &str, origin: &str) -> (TcpStream, String) {
+        let mut stream = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+        let request = format!(
+            "GET {} HTTP/1.1\r\nHost: localhost\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+             Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\nSec-WebSocket-Version: 13\r\nOrigin: {}\r\n\r\n",
+            target, origin
+        );
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let response = read_handshake(&mut stream).await.unwrap();
+        (stream, response)
+    }
+
+    #[test]
+    fn test_accept_key_matches_rfc_example() {
+        assert_eq!(accept_key("dGhlIHNhbXBsZSBub25jZQ=="), "s3pPLMBiTxaQ9kYGzzhZRbK+xOo=");
+    }
+
+    #[test]
+    fn test_base64_padding() {
+        assert_eq!(base64_encode(b""), "");
+        assert_eq!(base64_encode(b"f"), "Zg==");
+        assert_eq!(base64_encode(b"fo"), "Zm8=");
+        assert_eq!(base64_encode(b"foo"), "Zm9v");
+    }
+
+    #[test]
+    fn test_encode_frame_lengths() {
+        assert_eq!(encode_frame(OPCODE_TEXT, b"hi"), vec![0x81, 2, b'h', b'i']);
+        let medium = encode_frame(OPCODE_TEXT, &[0u8; 300]);
+        assert_eq!(&medium[..4], &[0x81, 126, 0x01, 0x2C]);
+    }
+
+    #[tokio::test]
+    async fn test_command_roundtrip() {
+        let dir = tempdir().unwrap();
+        let port = spawn_server(dir.path()).await;
+
+        let (mut stream, response) = connect(port, &format!("/?token={}", SECRET), ALLOWED_ORIGINS[0]).await;
+        assert!(response.starts_with("HTTP/1.1 101"));
+        assert!(response.contains("s3pPLMBiTxaQ9kYGzzhZRbK+xOo="));
+
+        stream.write_all(&masked_frame(OPCODE_TEXT, br#"{"command":"GetStatus"}"#)).await.unwrap();
+        let mut head = [0u8; 2];
+        stream.read_exact(&mut head).await.unwrap();
+        assert_eq!(head[0], 0x80 | OPCODE_TEXT);
+        let len = match head[1] {
+            126 => {
+                let mut ext = [0u8; 2];
+                stream.read_exact(&mut ext).await.unwrap();
+                u16::from_be_bytes(ext) as usize
+            }
+            len => len as usize,
+        };
+        let mut payload = vec![0u8; len];
+        stream.read_exact(&mut payload).await.unwrap();
+        let response: serde_json::Value = serde_json::from_slice(&payload).unwrap();
+        assert!(response.get("current_hz").is_some());
+
+        stream.write_all(&masked_frame(OPCODE_PING, b"x")).await.unwrap();
+        let mut pong = [0u8; 3];
+        stream.read_exact(&mut pong).await.unwrap();
+        assert_eq!(pong, [0x80 | OPCODE_PONG, 1, b'x']);
+    }
+
+    #[tokio::test]
+    async fn test_foreign_origin_rejected() {
+        let dir = tempdir().unwrap();
+        let port = spawn_server(dir.path()).await;
+
+        let (_stream, response) = connect(port, &format!("/?token={}", SECRET), "https://evil.example").await;
+        assert!(response.starts_with("HTTP/1.1 403"));
+
+        // Any local process could otherwise connect without claiming an origin
+        let no_origin = "GET /?token=0123abcd HTTP/1.1\r\nUpgrade: websocket\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\r\n";
+        let token = BridgeToken::from_secret(SECRET);
+        assert!(handshake_response(no_origin, &token).unwrap_err().starts_with("HTTP/1.1 403"));
+    }
+
+    #[tokio::test]
+    async fn test_missing_or_wrong_token_rejected() {
+        let dir = tempdir().unwrap();
+        let port = spawn_server(dir.path()).await;
+
+        let (_stream, response) = connect(port, "/", ALLOWED_ORIGINS[0]).await;
+        assert!(response.starts_with("HTTP/1.1 401"), "{}", response);
+        let (_stream, response) = connect(port, "/?token=0123abce", ALLOWED_ORIGINS[0]).await;
+        assert!(response.starts_with("HTTP/1.1 401"), "{}", response);
+        let (_stream, response) = connect(port, "/?v=1&token=0123abcd", ALLOWED_ORIGINS[0]).await;
+        assert!(response.starts_with("HTTP/1.1 101"), "{}", response);
+    }
+}
@@ -0,0 +1,200 @@
+//! "What-if" replay of a recorded FPS journal.
+//!
+//! Runs a fresh hysteresis controller over a session's FPS samples with
+//! alternative settings and reports how often the rate would have switched
+//! and how long FPS would have sat below the refresh rate. Users can compare
+//! these projections against their current settings instead of guessing.
+
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+use crate::config::Config;
+use crate::core_logic::{ControlPolicy, DeviceMode, HysteresisController, Sensitivity, StepMode};
+use crate::units::{Hertz, Millis, Secs};
+
+/// Longest journal accepted (six hours at 100ms polling)
+pub const MAX_JOURNAL_SAMPLES: usize = 216_000;
+
+/// Controller settings for one replay.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scenario {
+    pub sensitivity: Sensitivity,
+    pub custom_thresholds: Option<(Duration, Duration)>,
+    pub fps_tolerance: f64,
+    pub min_hz: Hertz,
+    pub max_hz: Hertz,
+    pub allowed_hz: Vec<Hertz>,
+    pub drop_step: StepMode,
+    pub increase_step: StepMode,
+    pub video_cadence: bool,
+    pub low_fps_compensation: bool,
+    pub saturation_pacing: f64,
+    pub loading_grace_ms: Millis,
+    pub min_dwell_secs: Secs,
+    pub predictive_drop: Option<f64>,
+    pub control_policy: ControlPolicy,
+    pub device_mode: DeviceMode,
+}
+
+impl Scenario {
+    /// Scenario matching a configuration and the live controller settings.
+    pub fn from_config(config: &Config, fps_tolerance: f64, device_mode: DeviceMode) -> Self {
+        Self {
+            sensitivity: config.sensitivity,
+            custom_thresholds: config.custom_thresholds(),
+            fps_tolerance,
+            min_hz: config.min_hz,
+            max_hz: config.max_hz,
+            allowed_hz: config.allowed_hz.clone(),
+            drop_step: config.effective_drop_step(),
+            increase_step: config.increase_step,
+            video_cadence: config.video_cadence,
+            low_fps_compensation: config.low_fps_compensation,
+            saturation_pacing: config.saturation_pacing,
+            loading_grace_ms: config.loading_grace_ms,
+            min_dwell_secs: config.min_dwell_secs,
+            predictive_drop: config.predictive_drop(),
+            control_policy: config.control_policy,
+            device_mode,
+        }
+    }
+
+    /// Controller configured for this scenario.
+    pub fn controller(&self) -> HysteresisController {
+        let mut controller = HysteresisController::new(self.sensitivity);
+        controller.set_user_range(self.min_hz, self.max_hz);
+        if let Some((drop, increase)) = self.custom_thresholds {
+            controller.set_custom_thresholds(drop, increase);
+        }
+        controller.set_allowed_hz(self.allowed_hz.clone());
+        controller.set_drop_step(self.drop_step);
+        controller.set_increase_step(self.increase_step);
+        controller.set_video_cadence(self.video_cadence);
+        controller.set_low_fps_compensation(self.low_fps_compensation);
+        controller.set_saturation_pacing(self.saturation_pacing);
+        controller.set_loading_grace(self.loading_grace_ms);
+        controller.set_min_dwell(self.min_dwell_secs);
+        controller.set_predictive_drop(self.predictive_drop);
+        controller.set_control_policy(self.control_policy);
+        controller.set_fps_tolerance(self.fps_tolerance);
+        controller.apply_mode_constraints(self.device_mode);
+        controller
+    }
+}
+
+/// Projected outcome of replaying a journal under one scenario.
+#[derive(Debug, Clone, Serialize)]
+pub struct Projection {
+    pub sensitivity: Sensitivity,
+    pub control_policy: ControlPolicy,
+    pub fps_tolerance: f64,
+    /// Number of refresh rate changes
+    pub switches: u32,
+    /// Seconds where FPS was more than 1 below the refresh rate
+    pub time_below_fps_secs: f64,
+    /// Length of the replayed journal in seconds
+    pub duration_secs: f64,
+    /// Refresh rate at the end of the journal
+    pub final_hz: Hertz,
+}
+
+/// Replay `samples`, taken `interval` apart, starting at the scenario's max Hz.
+pub fn simulate(samples: &[f64], interval: Duration, scenario: &Scenario) -> Projection {
+    let mut controller = scenario.controller();
+    let mut hz = controller.clamp_hz(scenario.max_hz);
+    let mut switches = 0;
+    let mut below = Duration::ZERO;
+    let start = Instant::now();
+
+    for (i, &fps) in samples.iter().enumerate() {
+        if fps < hz.as_f64() - 1.0 {
+            below += interval;
+        }
+        let now = start + interval * i as u32;
+        if let Some(target) = controller.process_with_time(fps, hz, now) {
+            if target != hz {
+                switches += 1;
+                hz = target;
+            }
+        }
+    }
+
+    Projection {
+        sensitivity: scenario.sensitivity,
+        control_policy: scenario.control_policy,
+        fps_tolerance: controller.fps_tolerance(),
+        switches,
+        time_below_fps_secs: below.as_secs_f64(),
+        duration_secs: (interval * samples.len() as u32).as_secs_f64(),
+        final_hz: hz,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POLL: Duration = Duration::from_millis(100);
+
+    fn scenario(sensitivity: Sensitivity, fps_tolerance: f64) -> Scenario {
+        let config = Config { sensitivity, ..Default::default() };
+        Scenario::from_config(&config, fps_tolerance, DeviceMode::Oled)
+    }
+
+    /// 10s at 90 FPS, 10s at 50 FPS, 10s back at 90 FPS.
+    fn dip_journal() -> Vec<f64> {
+        [90.0, 50.0, 90.0]
+            .iter()
+            .flat_map(|fps| std::iter::repeat_n(*fps, 100))
+            .collect()
+    }
+
+    #[test]
+    fn test_steady_journal_never_switches() {
+        let samples = vec![89.0; 300];
+        let projection = simulate(&samples, POLL, &scenario(Sensitivity::Balanced, 3.0));
+        assert_eq!(projection.switches, 0);
+        assert_eq!(projection.time_below_fps_secs, 0.0);
+        assert_eq!(projection.final_hz, Hertz(90));
+        assert!((projection.duration_secs - 30.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_faster_sensitivity_spends_less_time_below() {
+        let journal = dip_journal();
+        let conservative = simulate(&journal, POLL, &scenario(Sensitivity::Conservative, 3.0));
+        let aggressive = simulate(&journal, POLL, &scenario(Sensitivity::Aggressive, 3.0));
+
+        assert!(aggressive.switches >= 2);
+        assert!(aggressive.time_below_fps_secs < conservative.time_below_fps_secs);
+    }
+
+    #[test]
+    fn test_tolerance_suppresses_small_dips() {
+        // Hovering 4 FPS under the panel rate
+        let samples = vec![86.0; 100];
+        let tight = simulate(&samples, POLL, &scenario(Sensitivity::Aggressive, 2.0));
+        let loose = simulate(&samples, POLL, &scenario(Sensitivity::Aggressive, 5.0));
+        assert!(tight.switches > 0);
+        assert_eq!(loose.switches, 0);
+    }
+
+    #[test]
+    fn test_pid_policy_follows_recovery_faster() {
+        let journal = dip_journal();
+        let hysteresis = simulate(&journal, POLL, &scenario(Sensitivity::Conservative, 3.0));
+        let pid = simulate(
+            &journal,
+            POLL,
+            &Scenario {
+                control_policy: ControlPolicy::Pid,
+                ..scenario(Sensitivity::Conservative, 3.0)
+            },
+        );
+        assert!(pid.switches >= 2);
+        // Back at the top rate when FPS recovers, where hysteresis is still climbing
+        assert_eq!(pid.final_hz, Hertz(90));
+        assert!(hysteresis.final_hz < Hertz(90));
+    }
+}
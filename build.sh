@@ -66,6 +66,25 @@ mkdir -p bin
 cp "backend/target/$TARGET/release/smart-refresh-daemon" "bin/"
 echo "Binary size: $(du -h bin/smart-refresh-daemon | cut -f1)"
 
+# Step 3a: Build the Vulkan layer FPS provider
+print_step "Building Vulkan layer"
+cd "$SCRIPT_DIR/vulkan-layer"
+cargo build --release --target "$TARGET"
+cd "$SCRIPT_DIR"
+mkdir -p lib
+cp "vulkan-layer/target/$TARGET/release/libsmartrefresh_vulkan_layer.so" "lib/"
+cp vulkan-layer/VkLayer_smartrefresh.json "lib/"
+
+# Step 3b: Generate man pages and shell completions from the built binary
+print_step "Generating man pages and shell completions"
+rm -rf share
+mkdir -p share/man/man1 share/man/ru/man1 share/completions
+bin/smart-refresh-daemon --generate man > share/man/man1/smart-refresh-daemon.1
+bin/smart-refresh-daemon --generate man-ru > share/man/ru/man1/smart-refresh-daemon.1
+bin/smart-refresh-daemon --generate bash > share/completions/smart-refresh-daemon.bash
+bin/smart-refresh-daemon --generate zsh > share/completions/_smart-refresh-daemon
+bin/smart-refresh-daemon --generate fish > share/completions/smart-refresh-daemon.fish
+
 # Step 4: Build frontend
 print_step "Building React frontend"
 cd "$SCRIPT_DIR/frontend"
@@ -93,6 +112,8 @@ mkdir -p "$PLUGIN_DIR"
 
 # Copy required files (Decky Loader structure)
 cp -r bin "$PLUGIN_DIR/"
+cp -r lib "$PLUGIN_DIR/"
+cp -r share "$PLUGIN_DIR/"
 mkdir -p "$PLUGIN_DIR/dist"
 cp frontend/dist/index.js "$PLUGIN_DIR/dist/"
 cp main.py "$PLUGIN_DIR/"
@@ -114,12 +135,16 @@ echo ""
 echo -e "${GREEN}=== Build Complete ===${NC}"
 echo "Output files:"
 echo "  - bin/smart-refresh-daemon"
+echo "  - lib/ (Vulkan layer)"
+echo "  - share/ (man pages, shell completions)"
 echo "  - frontend/dist/index.js"
 echo "  - $ZIP_NAME"
 echo ""
 echo "ZIP structure:"
 echo "  $PLUGIN_NAME/"
 echo "  ├── bin/smart-refresh-daemon"
+echo "  ├── lib/libsmartrefresh_vulkan_layer.so, lib/VkLayer_smartrefresh.json"
+echo "  ├── share/man, share/completions"
 echo "  ├── dist/index.js"
 echo "  ├── main.py"
 echo "  └── plugin.json"
@@ -1,13 +1,34 @@
-import { call } from "@decky/api";
+import { addEventListener, call, removeEventListener } from "@decky/api";
 
 export interface DaemonConfig {
   min_hz: number;
   max_hz: number;
-  sensitivity: "conservative" | "balanced" | "aggressive";
+  sensitivity: "conservative" | "balanced" | "aggressive" | "custom";
   enabled: boolean;
   adaptive_sensitivity: boolean;
+  custom_drop_ms: number | null;
+  custom_increase_ms: number | null;
+  ramp_down: boolean;
+  drop_step: StepMode;
+  increase_step: StepMode;
+  video_cadence: boolean;
+  low_fps_compensation: boolean;
+  control_policy: ControlPolicy;
+  idle_policy: IdlePolicy;
 }
 
+export type ControlPolicy = "hysteresis" | "pid";
+
+export type IdlePolicy = "hold" | "min" | "max";
+export type StartupRate = "max" | "restore" | "untouched";
+
+// FPS smoothing: ring buffer mean, median or trimmed mean, or exponential moving average
+export type FpsSmoothing = "mean" | "ema" | "median" | "trimmed_mean";
+
+export type FpsStability = "stable" | "variable" | "unstable";
+
+export type StepMode = "single" | "jump";
+
 export interface TransitionRecord {
   timestamp: string;
   from_hz: number;
@@ -28,10 +49,141 @@ export interface DaemonStatus {
   fps_std_dev: number;
   current_app_id: string | null;
   transitions: TransitionRecord[];
+  // Sensitivity in use; differs from config.sensitivity under adaptive or LCD mode
+  effective_sensitivity: DaemonConfig["sensitivity"];
   // v2.0.1 advanced fields
   fps_tolerance: number;
   resume_cooldown_remaining: number;
   sync_frame_limiter: boolean;
+  // Manual override (SetHz)
+  override_hz: number | null;
+  override_remaining: number | null;
+  // Idle deep sleep
+  deep_sleep: boolean;
+  // Runtime subsystem switches
+  subsystems: SubsystemStatus;
+  // Switch budget usage (when configured)
+  switch_budget: SwitchBudgetStatus | null;
+  // Frametime spikes in the stutter window
+  frametime_spikes: number;
+  // Seconds left of oscillation damping
+  oscillation_damping_remaining: number;
+  // Seconds left of a loading-screen hold
+  loading_hold_remaining: number;
+  // Frame limit while low FPS compensation is engaged
+  lfc_fps_limit: number | null;
+  // Effective drop/increase timing at the current rate
+  pacing: DecisionPacing;
+  // Enabled experimental behaviors
+  experimental: ExperimentalFlags;
+  // APU temperature and thermal hold
+  thermal: ThermalStatus;
+  // GPU busy, CPU load and the saturated-GPU hold
+  utilization: UtilizationStatus;
+  // Backend applying refresh rates
+  display_backend: DisplayBackend;
+  // Source the FPS is read from, null while disconnected
+  fps_source: FpsSource | null;
+  // Process the FPS is read from, with per-process MangoHud segments
+  fps_pid: number | null;
+  // VRR mode: requested, panel capability, active and its frame limit
+  vrr: VrrStatus;
+  // Switching paused after repeated display backend failures
+  display_backend_unhealthy: boolean;
+  // Handheld model selecting the built-in presets
+  device_model: "steam_deck_lcd" | "steam_deck_oled" | "rog_ally" | "legion_go" | "unknown";
+  // Docked profile in use (external display on external power)
+  docked: boolean;
+  // External output controlled instead of the panel (e.g. "DP-1")
+  active_output: string | null;
+  // Display connectors with their current mode and role
+  displays: DisplayStatus[];
+  // Session kind: SteamOS game mode (gamescope) or desktop mode
+  session: "gaming" | "desktop" | "unknown";
+  // Switching paused in desktop mode (desktop_mode_switching off)
+  session_paused: boolean;
+  // Steam overlay open, controller frozen until it closes
+  overlay_open: boolean;
+  // FPS readings frozen (game paused or hung), holding the current rate
+  fps_stale: boolean;
+  // Current FPS polling and controller interval (slower while FPS is steady)
+  poll_interval_ms: number;
+  // Smoother behind current_fps: "mean", "ema", "median" or "trimmed_mean"
+  fps_smoothing: FpsSmoothing;
+  // 99th percentile frametime in microseconds over the FPS window
+  frametime_p99_us: number;
+  // "stable", "variable" or "unstable" by the adaptive sensitivity thresholds;
+  // null until the sliding window fills
+  fps_stability: FpsStability | null;
+  // What is recording or streaming (e.g. "obs"), locking the rate until it ends
+  recording: string | null;
+  // OS update in progress (updater or lock), holding the rate until it ends
+  system_update: string | null;
+  // Current game blacklisted (or not whitelisted), holding its max rate
+  game_switching_disabled: boolean;
+  /** Served from the previous run's snapshot while live data is pending */
+  cached: boolean;
+}
+
+export interface DecisionPacing {
+  drop_threshold_ms: number;
+  increase_threshold_ms: number;
+  min_change_interval_ms: number;
+  saturation_pacing: number;
+  /** Next step up reaches max Hz, so saturation_pacing applies */
+  near_max: boolean;
+}
+
+export interface SwitchBudgetStatus {
+  used_last_hour: number;
+  used_last_day: number;
+  per_hour: number | null;
+  per_day: number | null;
+  /** Extra FPS tolerance applied as the budget runs low */
+  tolerance_boost: number;
+}
+
+export type Subsystem =
+  | "battery"
+  | "monitor_detection"
+  | "adaptive_sensitivity"
+  | "thermal"
+  | "metrics";
+
+export type SubsystemStatus = Record<Subsystem, boolean>;
+
+export interface ThermalStatus {
+  available: boolean;
+  temp_c: number | null;
+  throttling: boolean;
+}
+
+export interface UtilizationStatus {
+  enabled: boolean;
+  gpu_busy_percent: number | null;
+  cpu_load_percent: number | null;
+  gpu_saturated: boolean;
+}
+
+export type DisplayBackend = "gamescope_cmd" | "gamescope_atom" | "sysfs" | "wlr_randr" | "noop";
+
+export type FpsSource = "mangohud" | "vulkan_layer" | "mangohud_log" | "gamescope_stats" | "drm_flips";
+
+export interface VrrStatus {
+  requested: boolean;
+  capable: boolean;
+  active: boolean;
+  fps_limit: number | null;
+}
+
+export type Experiment = "predictive_drop" | "vrr_window" | "power_coupling";
+
+export type ExperimentalFlags = Record<Experiment, boolean>;
+
+export interface ExperimentInfo {
+  name: Experiment;
+  enabled: boolean;
+  description: string;
 }
 
 export interface MetricsResponse {
@@ -50,6 +202,17 @@ export interface BatteryResponse {
   available: boolean;
 }
 
+export interface HzPower {
+  hz: number;
+  avg_watts: number;
+  samples: number;
+}
+
+export interface PowerByHzResponse {
+  rates: HzPower[];
+  available: boolean;
+}
+
 export interface GameProfile {
   app_id: string;
   name: string;
@@ -57,8 +220,16 @@ export interface GameProfile {
   max_hz: number;
   sensitivity: string;
   adaptive_sensitivity: boolean;
+  suggested: boolean;
+  on_launch?: string;
+  on_exit?: string;
+  min_dwell_secs?: number;
+  switching: GameSwitching;
 }
 
+// Per-game switching: "auto" follows whitelist_only, "enabled" whitelists, "disabled" blacklists
+export type GameSwitching = "auto" | "enabled" | "disabled";
+
 export interface GlobalDefault {
   min_hz: number;
   max_hz: number;
@@ -72,6 +243,36 @@ export interface ProfilesResponse {
   global_default: GlobalDefault;
 }
 
+export interface TransitionsResponse {
+  transitions: TransitionRecord[];
+  capacity: number;
+}
+
+export interface HistoryPoint {
+  // Unix time in seconds
+  timestamp: number;
+  fps: number;
+  hz: number;
+}
+
+export interface HistoryResponse {
+  points: HistoryPoint[];
+  interval_secs: number;
+  capacity: number;
+}
+
+export interface Advisory {
+  kind: "fps_decline" | "switch_rate_increase";
+  app_id: string | null;
+  message: string;
+  baseline: number;
+  recent: number;
+}
+
+export interface AdvisoriesResponse {
+  advisories: Advisory[];
+}
+
 export type DeviceMode = "oled" | "lcd" | "custom";
 
 // Status
@@ -111,15 +312,47 @@ export async function setSettings(
   minHz: number,
   maxHz: number,
   sensitivity: string,
-  adaptiveSensitivity: boolean = false
+  adaptiveSensitivity: boolean = false,
+  customDropMs: number | null = null,
+  customIncreaseMs: number | null = null,
+  rampDown: boolean | null = null,
+  controlPolicy: ControlPolicy | null = null,
+  videoCadence: boolean | null = null,
+  lowFpsCompensation: boolean | null = null,
+  dropStep: StepMode | null = null,
+  increaseStep: StepMode | null = null
 ): Promise<boolean> {
   try {
-    await call<[number, number, string, boolean], void>(
+    await call<
+      [
+        number,
+        number,
+        string,
+        boolean,
+        number | null,
+        number | null,
+        boolean | null,
+        ControlPolicy | null,
+        boolean | null,
+        boolean | null,
+        StepMode | null,
+        StepMode | null
+      ],
+      void
+    >(
       "set_settings",
       minHz,
       maxHz,
       sensitivity,
-      adaptiveSensitivity
+      adaptiveSensitivity,
+      customDropMs,
+      customIncreaseMs,
+      rampDown,
+      controlPolicy,
+      videoCadence,
+      lowFpsCompensation,
+      dropStep,
+      increaseStep
     );
     return true;
   } catch (error) {
@@ -138,6 +371,46 @@ export async function setDeviceMode(mode: DeviceMode): Promise<boolean> {
   }
 }
 
+export async function setSubsystemEnabled(name: Subsystem, enabled: boolean): Promise<boolean> {
+  try {
+    await call<[string, boolean], void>("set_subsystem_enabled", name, enabled);
+    return true;
+  } catch (error) {
+    console.error("SmartRefresh: Failed to toggle subsystem", error);
+    return false;
+  }
+}
+
+export async function setExperimentEnabled(name: Experiment, enabled: boolean): Promise<boolean> {
+  try {
+    await call<[string, boolean], void>("set_experiment_enabled", name, enabled);
+    return true;
+  } catch (error) {
+    console.error("SmartRefresh: Failed to toggle experiment", error);
+    return false;
+  }
+}
+
+export async function setDisplayBackend(backend: DisplayBackend | "auto"): Promise<boolean> {
+  try {
+    await call<[string], void>("set_display_backend", backend);
+    return true;
+  } catch (error) {
+    console.error("SmartRefresh: Failed to set display backend", error);
+    return false;
+  }
+}
+
+export async function setFpsSource(source: FpsSource | "auto"): Promise<boolean> {
+  try {
+    await call<[string], void>("set_fps_source", source);
+    return true;
+  } catch (error) {
+    console.error("SmartRefresh: Failed to set FPS source", error);
+    return false;
+  }
+}
+
 // Metrics
 export async function getMetrics(): Promise<MetricsResponse | null> {
   try {
@@ -160,6 +433,288 @@ export async function getBatteryStatus(): Promise<BatteryResponse | null> {
   }
 }
 
+export async function getPowerByHz(): Promise<PowerByHzResponse | null> {
+  try {
+    const result = await call<[], PowerByHzResponse>("get_power_by_hz");
+    return result;
+  } catch (error) {
+    console.error("SmartRefresh: Failed to get power by Hz", error);
+    return null;
+  }
+}
+
+// Transitions
+export async function getTransitions(limit: number | null = null): Promise<TransitionsResponse | null> {
+  try {
+    const result = await call<[number | null], TransitionsResponse>("get_transitions", limit);
+    return result;
+  } catch (error) {
+    console.error("SmartRefresh: Failed to get transitions", error);
+    return null;
+  }
+}
+
+// FPS/Hz history for sparklines
+export async function getHistory(limit: number | null = null): Promise<HistoryResponse | null> {
+  try {
+    const result = await call<[number | null], HistoryResponse>("get_history", limit);
+    return result;
+  } catch (error) {
+    console.error("SmartRefresh: Failed to get history", error);
+    return null;
+  }
+}
+
+// Events
+export interface DaemonEvent {
+  id: number;
+  timestamp: string;
+  kind: string;
+  severity: "info" | "warning" | "critical";
+  message: string;
+  /** Show as a notification rather than a silent entry */
+  notify: boolean;
+}
+
+export interface EventsResponse {
+  events: DaemonEvent[];
+}
+
+export async function getEvents(since: number | null = null): Promise<EventsResponse | null> {
+  try {
+    const result = await call<[number | null], EventsResponse>("get_events", since);
+    return result;
+  } catch (error) {
+    console.error("SmartRefresh: Failed to get events", error);
+    return null;
+  }
+}
+
+// What-if analysis
+export interface SimulationScenario {
+  sensitivity?: "conservative" | "balanced" | "aggressive" | "custom";
+  fps_tolerance?: number;
+  custom_drop_ms?: number;
+  custom_increase_ms?: number;
+  ramp_down?: boolean;
+  drop_step?: StepMode;
+  increase_step?: StepMode;
+  video_cadence?: boolean;
+  low_fps_compensation?: boolean;
+  predictive_drop?: boolean;
+  predictive_drop_fps_per_sec?: number;
+  control_policy?: ControlPolicy;
+}
+
+export interface SimulationProjection {
+  sensitivity: string;
+  control_policy: ControlPolicy;
+  fps_tolerance: number;
+  switches: number;
+  time_below_fps_secs: number;
+  duration_secs: number;
+  final_hz: number;
+}
+
+export interface SimulationResponse {
+  baseline: SimulationProjection;
+  scenarios: SimulationProjection[];
+}
+
+export async function simulateSettings(
+  samples: number[],
+  scenarios: SimulationScenario[],
+  sampleIntervalMs: number | null = null
+): Promise<SimulationResponse | null> {
+  try {
+    const result = await call<[number[], number | null, SimulationScenario[]], SimulationResponse>(
+      "simulate_settings",
+      samples,
+      sampleIntervalMs,
+      scenarios
+    );
+    return result;
+  } catch (error) {
+    console.error("SmartRefresh: Failed to simulate settings", error);
+    return null;
+  }
+}
+
+// Version
+export interface VersionInfo {
+  version: string;
+  git_hash: string;
+  build_date: string;
+  features: string[];
+  /** Added by the Python backend */
+  plugin_version?: string | null;
+}
+
+export interface PanelInfo {
+  connector: string;
+  make: string;
+  product_code: number;
+  name: string | null;
+  native_width: number | null;
+  native_height: number | null;
+  min_hz: number | null;
+  max_hz: number | null;
+}
+
+export interface Capabilities {
+  build: VersionInfo;
+  subsystems: Subsystem[];
+  experiments: ExperimentInfo[];
+  /** Internal panel from its EDID, when readable */
+  panel: PanelInfo | null;
+}
+
+export async function getCapabilities(): Promise<Capabilities | null> {
+  try {
+    const result = await call<[], Capabilities>("get_capabilities");
+    return result;
+  } catch (error) {
+    console.error("SmartRefresh: Failed to get capabilities", error);
+    return null;
+  }
+}
+
+export interface SupportedRates {
+  /** Controlled external output the rates are for, null for the panel */
+  output: string | null;
+  /** "drm" when read from the display, "fallback" for the built-in 40-90 Hz */
+  source: "drm" | "fallback";
+  rates: number[];
+  min: number;
+  max: number;
+  /** Range the daemon allows rates in */
+  range_min: number;
+  range_max: number;
+  /** Where the allowed range came from */
+  range_source: "backend" | "edid" | "drm" | "preset" | "output" | "fallback";
+}
+
+export async function getSupportedRates(): Promise<SupportedRates | null> {
+  try {
+    const result = await call<[], SupportedRates>("get_supported_rates");
+    return result;
+  } catch (error) {
+    console.error("SmartRefresh: Failed to get supported rates", error);
+    return null;
+  }
+}
+
+export interface ConnectorStatus {
+  /** Output name, e.g. "DP-1" */
+  name: string;
+  /** DRM connector, e.g. "card0-DP-1" */
+  connector: string;
+  connected: boolean;
+  /** Built-in panel (eDP/DSI); never pauses the daemon */
+  internal: boolean;
+  /** Listed in ignored_connectors, so it never pauses the daemon */
+  ignored: boolean;
+}
+
+export interface DisplayStatus extends ConnectorStatus {
+  /** Mode it is scanned out at, null when off or unreadable */
+  mode: { width: number; height: number; refresh: number; preferred: boolean } | null;
+  /** Its refresh rate is the one being switched */
+  controlled: boolean;
+  /** Counts as an external display that pauses switching */
+  pausing: boolean;
+}
+
+export interface Connectors {
+  connectors: ConnectorStatus[];
+  ignored: string[];
+}
+
+export async function getConnectors(): Promise<Connectors | null> {
+  try {
+    const result = await call<[], Connectors>("get_connectors");
+    return result;
+  } catch (error) {
+    console.error("SmartRefresh: Failed to get connectors", error);
+    return null;
+  }
+}
+
+export async function getVersion(): Promise<VersionInfo | null> {
+  try {
+    const result = await call<[], VersionInfo>("get_version");
+    return result;
+  } catch (error) {
+    console.error("SmartRefresh: Failed to get version", error);
+    return null;
+  }
+}
+
+/** True when the plugin and daemon differ in major or minor version. */
+export function isVersionMismatch(info: VersionInfo): boolean {
+  if (!info.plugin_version) {
+    return false;
+  }
+  const majorMinor = (v: string) => v.split(".").slice(0, 2).join(".");
+  return majorMinor(info.plugin_version) !== majorMinor(info.version);
+}
+
+// Batch
+export interface BatchResponse {
+  responses: Record<string, unknown>[];
+}
+
+export async function batch(commands: Record<string, unknown>[]): Promise<BatchResponse | null> {
+  try {
+    const result = await call<[Record<string, unknown>[]], BatchResponse>("batch", commands);
+    return result;
+  } catch (error) {
+    console.error("SmartRefresh: Failed to run batch", error);
+    return null;
+  }
+}
+
+// FPS stream
+export interface FpsStreamFrame {
+  fps: number;
+  hz: number;
+}
+
+export async function startFpsStream(intervalMs = 250): Promise<boolean> {
+  try {
+    const result = await call<[number], { success?: boolean }>("start_fps_stream", intervalMs);
+    return result?.success ?? false;
+  } catch (error) {
+    console.error("SmartRefresh: Failed to start FPS stream", error);
+    return false;
+  }
+}
+
+export async function stopFpsStream(): Promise<void> {
+  try {
+    await call("stop_fps_stream");
+  } catch (error) {
+    console.error("SmartRefresh: Failed to stop FPS stream", error);
+  }
+}
+
+/** Subscribe to streamed FPS frames; returns an unsubscribe function. */
+export function onFpsFrame(callback: (frame: FpsStreamFrame) => void): () => void {
+  const listener = addEventListener<[FpsStreamFrame]>("fps_frame", callback);
+  return () => removeEventListener("fps_frame", listener);
+}
+
+// Advisories
+export async function getAdvisories(): Promise<AdvisoriesResponse | null> {
+  try {
+    const result = await call<[], AdvisoriesResponse>("get_advisories");
+    return result;
+  } catch (error) {
+    console.error("SmartRefresh: Failed to get advisories", error);
+    return null;
+  }
+}
+
 // Profiles
 export async function getProfiles(): Promise<ProfilesResponse | null> {
   try {
@@ -177,17 +732,26 @@ export async function saveProfile(
   minHz: number,
   maxHz: number,
   sensitivity: string,
-  adaptiveSensitivity: boolean = false
+  adaptiveSensitivity: boolean = false,
+  onLaunch: string | null = null,
+  onExit: string | null = null,
+  minDwellSecs: number | null = null
 ): Promise<boolean> {
   try {
-    await call<[string, string, number, number, string, boolean], void>(
+    await call<
+      [string, string, number, number, string, boolean, string | null, string | null, number | null],
+      void
+    >(
       "save_profile",
       appId,
       name,
       minHz,
       maxHz,
       sensitivity,
-      adaptiveSensitivity
+      adaptiveSensitivity,
+      onLaunch,
+      onExit,
+      minDwellSecs
     );
     return true;
   } catch (error) {
@@ -206,6 +770,35 @@ export async function deleteProfile(appId: string): Promise<boolean> {
   }
 }
 
+export async function acceptProfileSuggestion(appId: string): Promise<boolean> {
+  try {
+    await call<[string], void>("accept_profile_suggestion", appId);
+    return true;
+  } catch (error) {
+    console.error("SmartRefresh: Failed to accept profile suggestion", error);
+    return false;
+  }
+}
+
+export async function setGameSwitching(
+  appId: string,
+  switching: GameSwitching,
+  name: string = ""
+): Promise<boolean> {
+  try {
+    const result = await call<[string, GameSwitching, string], { success?: boolean }>(
+      "set_game_switching",
+      appId,
+      switching,
+      name
+    );
+    return result?.success ?? false;
+  } catch (error) {
+    console.error("SmartRefresh: Failed to set game switching", error);
+    return false;
+  }
+}
+
 export async function setGameId(appId: string, name: string = ""): Promise<boolean> {
   try {
     await call<[string, string], void>("set_game_id", appId, name);
@@ -221,15 +814,76 @@ export interface AdvancedConfig {
   fps_tolerance?: number;
   resume_cooldown_secs?: number;
   sync_frame_limiter?: boolean;
+  saturation_pacing?: number;
+  loading_grace_ms?: number;
+  idle_policy?: IdlePolicy;
+  idle_after_secs?: number;
+  min_dwell_secs?: number;
+  predictive_drop_fps_per_sec?: number;
+  gpu_utilization?: boolean;
+  manual_change_grace_secs?: number;
+  startup_rate?: StartupRate;
+  control_external_display?: boolean;
+  desktop_mode_switching?: boolean;
+  whitelist_only?: boolean;
+  fps_smoothing?: FpsSmoothing;
+  fps_ema_alpha?: number;
+  fps_window_secs?: number;
+  adaptive_polling?: boolean;
+  mangohud_overlay?: boolean;
+  auto_detect_game?: boolean;
 }
 
 export async function setAdvancedConfig(config: AdvancedConfig): Promise<boolean> {
   try {
-    await call<[number | null, number | null, boolean | null], void>(
+    await call<
+      [
+        number | null,
+        number | null,
+        boolean | null,
+        number | null,
+        number | null,
+        IdlePolicy | null,
+        number | null,
+        number | null,
+        number | null,
+        boolean | null,
+        number | null,
+        StartupRate | null,
+        boolean | null,
+        boolean | null,
+        boolean | null,
+        FpsSmoothing | null,
+        number | null,
+        number | null,
+        boolean | null,
+        boolean | null,
+        boolean | null
+      ],
+      void
+    >(
       "set_advanced_config",
       config.fps_tolerance ?? null,
       config.resume_cooldown_secs ?? null,
-      config.sync_frame_limiter ?? null
+      config.sync_frame_limiter ?? null,
+      config.saturation_pacing ?? null,
+      config.loading_grace_ms ?? null,
+      config.idle_policy ?? null,
+      config.idle_after_secs ?? null,
+      config.min_dwell_secs ?? null,
+      config.predictive_drop_fps_per_sec ?? null,
+      config.gpu_utilization ?? null,
+      config.manual_change_grace_secs ?? null,
+      config.startup_rate ?? null,
+      config.control_external_display ?? null,
+      config.desktop_mode_switching ?? null,
+      config.whitelist_only ?? null,
+      config.fps_smoothing ?? null,
+      config.fps_ema_alpha ?? null,
+      config.fps_window_secs ?? null,
+      config.adaptive_polling ?? null,
+      config.mangohud_overlay ?? null,
+      config.auto_detect_game ?? null
     );
     return true;
   } catch (error) {
@@ -237,3 +891,24 @@ export async function setAdvancedConfig(config: AdvancedConfig): Promise<boolean
     return false;
   }
 }
+
+// Manual override
+export async function setHz(hz: number, durationSecs: number | null = null): Promise<boolean> {
+  try {
+    await call<[number, number | null], void>("set_hz", hz, durationSecs);
+    return true;
+  } catch (error) {
+    console.error("SmartRefresh: Failed to set refresh rate override", error);
+    return false;
+  }
+}
+
+export async function clearHz(): Promise<boolean> {
+  try {
+    await call<[], void>("clear_hz");
+    return true;
+  } catch (error) {
+    console.error("SmartRefresh: Failed to clear refresh rate override", error);
+    return false;
+  }
+}
@@ -1,10 +1,12 @@
 import { SliderField } from "@decky/ui";
 import { useState, useEffect } from "react";
-import { getStatus, setSettings, DaemonStatus } from "../api";
+import { getStatus, getSupportedRates, setSettings, DaemonStatus } from "../api";
 
 export function RefreshRangeSlider() {
   const [minHz, setMinHz] = useState(40);
   const [maxHz, setMaxHz] = useState(90);
+  const [rangeMin, setRangeMin] = useState(40);
+  const [rangeMax, setRangeMax] = useState(90);
   const [sensitivity, setSensitivity] = useState("balanced");
   const [loading, setLoading] = useState(true);
 
@@ -16,6 +18,11 @@ export function RefreshRangeSlider() {
         setMaxHz(status.config.max_hz);
         setSensitivity(status.config.sensitivity);
       }
+      const rates = await getSupportedRates();
+      if (rates) {
+        setRangeMin(rates.range_min);
+        setRangeMax(rates.range_max);
+      }
       setLoading(false);
     };
     fetchStatus();
@@ -39,9 +46,9 @@ export function RefreshRangeSlider() {
         label="Minimum Hz"
         description={`${minHz} Hz`}
         value={minHz}
-        min={40}
-        max={90}
-        step={5}
+        min={rangeMin}
+        max={rangeMax}
+        step={1}
         disabled={loading}
         onChange={handleMinChange}
       />
@@ -49,9 +56,9 @@ export function RefreshRangeSlider() {
         label="Maximum Hz"
         description={`${maxHz} Hz`}
         value={maxHz}
-        min={40}
-        max={90}
-        step={5}
+        min={rangeMin}
+        max={rangeMax}
+        step={1}
         disabled={loading}
         onChange={handleMaxChange}
       />
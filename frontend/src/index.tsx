@@ -9,9 +9,11 @@ import {
   DropdownOption,
   Field,
   ButtonItem,
+  Router,
   staticClasses,
 } from "@decky/ui";
 import { FaSync } from "react-icons/fa";
+import { toaster } from "@decky/api";
 import {
   getStatus,
   startDaemon,
@@ -23,7 +25,13 @@ import {
   getProfiles,
   saveProfile,
   setAdvancedConfig,
+  setGameId,
+  getEvents,
+  getVersion,
+  getSupportedRates,
+  isVersionMismatch,
   DaemonStatus,
+  VersionInfo,
   MetricsResponse,
   BatteryResponse,
   ProfilesResponse,
@@ -233,6 +241,22 @@ const Content: FC = () => {
   // FPS history for sparkline
   const [fpsHistory, setFpsHistory] = useState<FpsHistoryPoint[]>([]);
   const intervalRef = useRef<number | null>(null);
+  const [versionInfo, setVersionInfo] = useState<VersionInfo | null>(null);
+  // Slider bounds: the range the daemon allows rates in
+  const [rateRange, setRateRange] = useState({ min: 40, max: 90 });
+
+  useEffect(() => {
+    getVersion().then(setVersionInfo);
+  }, []);
+
+  // The allowed range depends on the device mode and connected output
+  useEffect(() => {
+    getSupportedRates().then((rates) => {
+      if (rates) {
+        setRateRange({ min: rates.range_min, max: rates.range_max });
+      }
+    });
+  }, [deviceModel, status?.active_output]);
 
   // Detect preset from values
   const detectPreset = (min: number, max: number): PresetType => {
@@ -409,8 +433,6 @@ const Content: FC = () => {
   const effectiveSensitivity = isLCD
     ? "conservative"
     : SENSITIVITY_OPTIONS[sensitivityIndex];
-  const sliderMin = isLCD ? 40 : 40;
-  const sliderMax = isLCD ? 60 : 90;
 
   // Warning conditions
   const showDangerWarning =
@@ -464,6 +486,24 @@ const Content: FC = () => {
           </PanelSectionRow>
         )}
 
+        {versionInfo && isVersionMismatch(versionInfo) && (
+          <PanelSectionRow>
+            <div
+              style={{
+                color: "#fbbf24",
+                fontSize: "0.85em",
+                padding: "8px 12px",
+                backgroundColor: "rgba(251, 191, 36, 0.1)",
+                borderRadius: "4px",
+                border: "1px solid rgba(251, 191, 36, 0.3)",
+              }}
+            >
+              ⚠️ Plugin v{versionInfo.plugin_version} and daemon v{versionInfo.version} don't
+              match. Reinstall or update SmartRefresh.
+            </div>
+          </PanelSectionRow>
+        )}
+
         {status?.external_display_detected && (
           <PanelSectionRow>
             <div
@@ -518,8 +558,8 @@ const Content: FC = () => {
             label="Minimum Hz"
             description={`${minHz} Hz`}
             value={minHz}
-            min={sliderMin}
-            max={sliderMax}
+            min={rateRange.min}
+            max={rateRange.max}
             step={5}
             disabled={loading || !enabled}
             onChange={handleMinHzChange}
@@ -531,8 +571,8 @@ const Content: FC = () => {
             label="Maximum Hz"
             description={`${maxHz} Hz`}
             value={maxHz}
-            min={sliderMin}
-            max={sliderMax}
+            min={rateRange.min}
+            max={rateRange.max}
             step={5}
             disabled={loading || !enabled}
             onChange={handleMaxHzChange}
@@ -673,6 +713,25 @@ const Content: FC = () => {
                 </Field>
               </PanelSectionRow>
             )}
+
+            {status?.fps_stability && (
+              <PanelSectionRow>
+                <Field label="Frame Stability">
+                  <span
+                    style={{
+                      color:
+                        status.fps_stability === "stable"
+                          ? "#4ade80"
+                          : status.fps_stability === "unstable"
+                            ? "#f87171"
+                            : "#fbbf24",
+                    }}
+                  >
+                    {status.fps_stability} · P99 {(status.frametime_p99_us / 1000).toFixed(1)}ms
+                  </span>
+                </Field>
+              </PanelSectionRow>
+            )}
           </PanelSection>
 
           {/* Transition Log */}
@@ -780,10 +839,43 @@ const Content: FC = () => {
 };
 
 export default definePlugin(() => {
+  // Tell the daemon when a game starts or exits so it can tell the Steam UI apart from games
+  const appLifetime = SteamClient.GameSessions.RegisterForAppLifetimeNotifications(
+    (update: { unAppID: number; bRunning: boolean }) => {
+      if (update.bRunning) {
+        setGameId(String(update.unAppID), Router.MainRunningApp?.display_name ?? "");
+      } else {
+        setGameId("");
+      }
+    }
+  );
+
+  // Show daemon events routed to "notify" as toasts
+  let lastEventId: number | null = null;
+  const eventPoll = window.setInterval(async () => {
+    const result = await getEvents(lastEventId);
+    if (!result) {
+      return;
+    }
+    // The first poll only catches up; don't replay old events
+    const catchingUp = lastEventId === null;
+    for (const event of result.events) {
+      if (event.notify && !catchingUp) {
+        toaster.toast({ title: "SmartRefresh", body: event.message });
+      }
+      lastEventId = event.id;
+    }
+    lastEventId ??= 0;
+  }, 5000);
+
   return {
     name: "SmartRefresh",
     title: <div className={staticClasses.Title}>SmartRefresh</div>,
     content: <Content />,
     icon: <FaSync />,
+    onDismount() {
+      appLifetime.unregister();
+      window.clearInterval(eventPoll);
+    },
   };
 });
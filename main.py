@@ -25,7 +25,8 @@ import decky  # type: ignore
 
 # Constants
 DAEMON_BINARY_NAME = "smart-refresh-daemon"
-SOCKET_PATH = "/tmp/smart-refresh.sock"
+SOCKET_FILE_NAME = "smart-refresh.sock"
+FALLBACK_SOCKET_PATH = "/tmp/smart-refresh.sock"
 SOCKET_TIMEOUT = 2.0
 SHUTDOWN_TIMEOUT = 2.0
 GAME_POLL_INTERVAL = 5.0
@@ -33,6 +34,17 @@ GAME_POLL_INTERVAL = 5.0
 defaultDir = os.environ.get("DECKY_PLUGIN_DIR")
 
 
+def _default_socket_path() -> str:
+    """Mirror the daemon default: $XDG_RUNTIME_DIR/smart-refresh.sock, else /tmp."""
+    runtime_dir = os.environ.get("XDG_RUNTIME_DIR")
+    if runtime_dir and os.path.isabs(runtime_dir):
+        return os.path.join(runtime_dir, SOCKET_FILE_NAME)
+    return FALLBACK_SOCKET_PATH
+
+
+SOCKET_PATH = _default_socket_path()
+
+
 class Plugin:
     """SmartRefresh Decky Loader Plugin v2.0."""
     
@@ -41,6 +53,7 @@ class Plugin:
         self._daemon_pid: Optional[int] = None
         self._current_app_id: Optional[str] = None
         self._game_monitor_task: Optional[asyncio.Task] = None
+        self._fps_stream_task: Optional[asyncio.Task] = None
     
     def _get_binary_path(self) -> Path:
         """Get the path to the daemon binary."""
@@ -73,8 +86,9 @@ class Plugin:
             if not self._ensure_executable(binary_path):
                 return False
             
+            # Pass the socket path explicitly so both sides always agree
             self._daemon_process = subprocess.Popen(
-                [str(binary_path)],
+                [str(binary_path), "--socket", SOCKET_PATH],
                 stdout=subprocess.DEVNULL,
                 stderr=subprocess.DEVNULL,
                 start_new_session=True,
@@ -220,6 +234,41 @@ class Plugin:
                 decky.logger.error(f"Game monitor error: {e}")
                 await asyncio.sleep(GAME_POLL_INTERVAL)
 
+    async def _fps_stream_loop(self, interval_ms: int):
+        """Forward StreamFps frames from the daemon to the frontend as events."""
+        try:
+            reader, writer = await asyncio.open_unix_connection(SOCKET_PATH)
+            try:
+                command = {"command": "StreamFps", "interval_ms": interval_ms}
+                writer.write((json.dumps(command) + "\n").encode('utf-8'))
+                await writer.drain()
+
+                ack = json.loads(await reader.readline() or b"{}")
+                if "error" in ack:
+                    decky.logger.error(f"FPS stream rejected: {ack['error']}")
+                    return
+
+                while True:
+                    line = await reader.readline()
+                    if not line:
+                        break
+                    await decky.emit("fps_frame", json.loads(line))
+            finally:
+                writer.close()
+        except asyncio.CancelledError:
+            pass
+        except Exception as e:
+            decky.logger.error(f"FPS stream error: {e}")
+
+    async def _stop_fps_stream_task(self):
+        if self._fps_stream_task:
+            self._fps_stream_task.cancel()
+            try:
+                await self._fps_stream_task
+            except asyncio.CancelledError:
+                pass
+            self._fps_stream_task = None
+
     # ==================== Decky Plugin Lifecycle ====================
     
     async def _main(self):
@@ -237,6 +286,8 @@ class Plugin:
         """Plugin unload - called when plugin unloads."""
         decky.logger.info("SmartRefresh plugin unloading...")
         
+        await self._stop_fps_stream_task()
+
         # Cancel game monitor
         if self._game_monitor_task:
             self._game_monitor_task.cancel()
@@ -264,16 +315,108 @@ class Plugin:
         """Get battery status and savings estimate."""
         return self._send_ipc_command({"command": "GetBatteryStatus"})
     
+    async def get_power_by_hz(self) -> Dict[str, Any]:
+        """Get average power draw per refresh rate."""
+        return self._send_ipc_command({"command": "GetPowerByHz"})
+    
+    async def get_transitions(self, limit: Optional[int] = None) -> Dict[str, Any]:
+        """Get recent refresh rate transitions."""
+        command: Dict[str, Any] = {"command": "GetTransitions"}
+        if limit is not None:
+            command["limit"] = limit
+        return self._send_ipc_command(command)
+    
+    async def get_history(self, limit: Optional[int] = None) -> Dict[str, Any]:
+        """Get smoothed FPS and Hz once a second over the last five minutes."""
+        command: Dict[str, Any] = {"command": "GetHistory"}
+        if limit is not None:
+            command["limit"] = limit
+        return self._send_ipc_command(command)
+    
+    async def batch(self, commands: List[Dict[str, Any]]) -> Dict[str, Any]:
+        """Run several daemon commands in one round trip."""
+        return self._send_ipc_command({"command": "Batch", "commands": commands})
+    
+    async def get_version(self) -> Dict[str, Any]:
+        """Get daemon build info alongside the plugin version."""
+        result = self._send_ipc_command({"command": "GetVersion"})
+        if "error" not in result:
+            result["plugin_version"] = getattr(decky, "DECKY_PLUGIN_VERSION", None)
+        return result
+    
+    async def get_events(self, since: Optional[int] = None) -> Dict[str, Any]:
+        """Get events routed to the frontend, newer than `since`."""
+        command: Dict[str, Any] = {"command": "GetEvents"}
+        if since is not None:
+            command["since"] = since
+        return self._send_ipc_command(command)
+    
+    async def simulate_settings(
+        self,
+        samples: List[float],
+        sample_interval_ms: Optional[int] = None,
+        scenarios: Optional[List[Dict[str, Any]]] = None,
+    ) -> Dict[str, Any]:
+        """Replay an FPS journal under the current settings and alternative scenarios."""
+        command: Dict[str, Any] = {
+            "command": "SimulateSettings",
+            "samples": samples,
+            "scenarios": scenarios or [],
+        }
+        if sample_interval_ms is not None:
+            command["sample_interval_ms"] = sample_interval_ms
+        return self._send_ipc_command(command)
+    
+    async def get_advisories(self) -> Dict[str, Any]:
+        """Get long-term drift advisories."""
+        return self._send_ipc_command({"command": "GetAdvisories"})
+    
+    async def start_fps_stream(self, interval_ms: int = 250) -> Dict[str, Any]:
+        """Start pushing fps_frame events to the frontend."""
+        await self._stop_fps_stream_task()
+        self._fps_stream_task = asyncio.create_task(self._fps_stream_loop(interval_ms))
+        return {"success": True, "message": "FPS stream started"}
+    
+    async def stop_fps_stream(self) -> Dict[str, Any]:
+        """Stop pushing fps_frame events."""
+        await self._stop_fps_stream_task()
+        return {"success": True, "message": "FPS stream stopped"}
+    
     async def set_settings(self, min_hz: int, max_hz: int, sensitivity: str, 
-                          adaptive_sensitivity: bool = False) -> Dict[str, Any]:
+                          adaptive_sensitivity: bool = False,
+                          custom_drop_ms: Optional[int] = None,
+                          custom_increase_ms: Optional[int] = None,
+                          ramp_down: Optional[bool] = None,
+                          control_policy: Optional[str] = None,
+                          video_cadence: Optional[bool] = None,
+                          low_fps_compensation: Optional[bool] = None,
+                          drop_step: Optional[str] = None,
+                          increase_step: Optional[str] = None) -> Dict[str, Any]:
         """Update daemon configuration."""
-        return self._send_ipc_command({
+        command: Dict[str, Any] = {
             "command": "SetConfig",
             "min_hz": min_hz,
             "max_hz": max_hz,
             "sensitivity": sensitivity,
             "adaptive_sensitivity": adaptive_sensitivity
-        })
+        }
+        if custom_drop_ms is not None:
+            command["custom_drop_ms"] = custom_drop_ms
+        if custom_increase_ms is not None:
+            command["custom_increase_ms"] = custom_increase_ms
+        if ramp_down is not None:
+            command["ramp_down"] = ramp_down
+        if control_policy is not None:
+            command["control_policy"] = control_policy
+        if video_cadence is not None:
+            command["video_cadence"] = video_cadence
+        if low_fps_compensation is not None:
+            command["low_fps_compensation"] = low_fps_compensation
+        if drop_step is not None:
+            command["drop_step"] = drop_step
+        if increase_step is not None:
+            command["increase_step"] = increase_step
+        return self._send_ipc_command(command)
     
     async def start(self) -> Dict[str, Any]:
         """Start the refresh rate control loop."""
@@ -319,6 +462,48 @@ class Plugin:
             "mode": mode
         })
 
+    async def set_subsystem_enabled(self, name: str, enabled: bool) -> Dict[str, Any]:
+        """Switch an optional subsystem on or off until the daemon restarts."""
+        return self._send_ipc_command({
+            "command": "SetSubsystemEnabled",
+            "name": name,
+            "enabled": enabled
+        })
+
+    async def get_capabilities(self) -> Dict[str, Any]:
+        """Get build features, switchable subsystems and experiments."""
+        return self._send_ipc_command({"command": "GetCapabilities"})
+
+    async def get_supported_rates(self) -> Dict[str, Any]:
+        """Get the refresh rates the internal panel (or controlled external output) supports."""
+        return self._send_ipc_command({"command": "GetSupportedRates"})
+
+    async def get_connectors(self) -> Dict[str, Any]:
+        """Get the display connectors with their status and whether they are ignored."""
+        return self._send_ipc_command({"command": "GetConnectors"})
+
+    async def set_experiment_enabled(self, name: str, enabled: bool) -> Dict[str, Any]:
+        """Switch an experimental behavior on or off (persisted)."""
+        return self._send_ipc_command({
+            "command": "SetExperimentEnabled",
+            "name": name,
+            "enabled": enabled
+        })
+
+    async def set_display_backend(self, backend: str) -> Dict[str, Any]:
+        """Switch the refresh rate backend, or "auto" to detect (persisted)."""
+        return self._send_ipc_command({
+            "command": "SetDisplayBackend",
+            "backend": backend
+        })
+
+    async def set_fps_source(self, source: str) -> Dict[str, Any]:
+        """Switch the FPS source, or "auto" to detect (persisted)."""
+        return self._send_ipc_command({
+            "command": "SetFpsSource",
+            "source": source
+        })
+
     # ==================== Profile Management ====================
     
     async def get_profiles(self) -> Dict[str, Any]:
@@ -326,9 +511,16 @@ class Plugin:
         return self._send_ipc_command({"command": "GetProfiles"})
     
     async def save_profile(self, app_id: str, name: str, min_hz: int, max_hz: int,
-                          sensitivity: str, adaptive_sensitivity: bool = False) -> Dict[str, Any]:
-        """Save a profile for a game."""
-        return self._send_ipc_command({
+                          sensitivity: str, adaptive_sensitivity: bool = False,
+                          on_launch: Optional[str] = None,
+                          on_exit: Optional[str] = None,
+                          min_dwell_secs: Optional[int] = None) -> Dict[str, Any]:
+        """Save a profile for a game.
+
+        Hooks and the dwell time left as None are omitted so the daemon
+        keeps the stored ones; pass "" to remove a hook.
+        """
+        command = {
             "command": "SaveProfile",
             "app_id": app_id,
             "name": name,
@@ -336,7 +528,14 @@ class Plugin:
             "max_hz": max_hz,
             "sensitivity": sensitivity,
             "adaptive_sensitivity": adaptive_sensitivity
-        })
+        }
+        if min_dwell_secs is not None:
+            command["min_dwell_secs"] = min_dwell_secs
+        if on_launch is not None:
+            command["on_launch"] = on_launch
+        if on_exit is not None:
+            command["on_exit"] = on_exit
+        return self._send_ipc_command(command)
     
     async def delete_profile(self, app_id: str) -> Dict[str, Any]:
         """Delete a profile."""
@@ -345,6 +544,24 @@ class Plugin:
             "app_id": app_id
         })
     
+    async def accept_profile_suggestion(self, app_id: str) -> Dict[str, Any]:
+        """Confirm a profile whose sensitivity was suggested by the daemon."""
+        return self._send_ipc_command({
+            "command": "AcceptProfileSuggestion",
+            "app_id": app_id
+        })
+    
+    async def set_game_switching(self, app_id: str, switching: str, name: str = "") -> Dict[str, Any]:
+        """Blacklist ("disabled") or whitelist ("enabled") a game for switching, or reset it ("auto")."""
+        command: Dict[str, Any] = {
+            "command": "SetGameSwitching",
+            "app_id": app_id,
+            "switching": switching
+        }
+        if name:
+            command["name"] = name
+        return self._send_ipc_command(command)
+    
     async def set_game_id(self, app_id: str, name: str = "") -> Dict[str, Any]:
         """Set the current game ID (triggers profile loading)."""
         self._current_app_id = app_id if app_id else None
@@ -354,11 +571,42 @@ class Plugin:
             "name": name
         })
 
+    # ==================== Manual Override ====================
+
+    async def set_hz(self, hz: int, duration_secs: Optional[int] = None) -> Dict[str, Any]:
+        """Force a refresh rate, optionally for a limited time."""
+        command: Dict[str, Any] = {"command": "SetHz", "hz": hz}
+        if duration_secs is not None:
+            command["duration_secs"] = duration_secs
+        return self._send_ipc_command(command)
+
+    async def clear_hz(self) -> Dict[str, Any]:
+        """Clear the manual override and resume automatic control."""
+        return self._send_ipc_command({"command": "ClearHz"})
+
     # ==================== Advanced Config (v2.0.1) ====================
     
     async def set_advanced_config(self, fps_tolerance: Optional[float] = None,
                                   resume_cooldown_secs: Optional[int] = None,
-                                  sync_frame_limiter: Optional[bool] = None) -> Dict[str, Any]:
+                                  sync_frame_limiter: Optional[bool] = None,
+                                  saturation_pacing: Optional[float] = None,
+                                  loading_grace_ms: Optional[int] = None,
+                                  idle_policy: Optional[str] = None,
+                                  idle_after_secs: Optional[int] = None,
+                                  min_dwell_secs: Optional[int] = None,
+                                  predictive_drop_fps_per_sec: Optional[float] = None,
+                                  gpu_utilization: Optional[bool] = None,
+                                  manual_change_grace_secs: Optional[int] = None,
+                                  startup_rate: Optional[str] = None,
+                                  control_external_display: Optional[bool] = None,
+                                  desktop_mode_switching: Optional[bool] = None,
+                                  whitelist_only: Optional[bool] = None,
+                                  fps_smoothing: Optional[str] = None,
+                                  fps_ema_alpha: Optional[float] = None,
+                                  fps_window_secs: Optional[int] = None,
+                                  adaptive_polling: Optional[bool] = None,
+                                  mangohud_overlay: Optional[bool] = None,
+                                  auto_detect_game: Optional[bool] = None) -> Dict[str, Any]:
         """Set advanced configuration options (v2.0.1)."""
         command: Dict[str, Any] = {"command": "SetAdvancedConfig"}
         
@@ -368,5 +616,41 @@ class Plugin:
             command["resume_cooldown_secs"] = resume_cooldown_secs
         if sync_frame_limiter is not None:
             command["sync_frame_limiter"] = sync_frame_limiter
+        if saturation_pacing is not None:
+            command["saturation_pacing"] = saturation_pacing
+        if loading_grace_ms is not None:
+            command["loading_grace_ms"] = loading_grace_ms
+        if idle_policy is not None:
+            command["idle_policy"] = idle_policy
+        if idle_after_secs is not None:
+            command["idle_after_secs"] = idle_after_secs
+        if min_dwell_secs is not None:
+            command["min_dwell_secs"] = min_dwell_secs
+        if predictive_drop_fps_per_sec is not None:
+            command["predictive_drop_fps_per_sec"] = predictive_drop_fps_per_sec
+        if gpu_utilization is not None:
+            command["gpu_utilization"] = gpu_utilization
+        if manual_change_grace_secs is not None:
+            command["manual_change_grace_secs"] = manual_change_grace_secs
+        if startup_rate is not None:
+            command["startup_rate"] = startup_rate
+        if control_external_display is not None:
+            command["control_external_display"] = control_external_display
+        if desktop_mode_switching is not None:
+            command["desktop_mode_switching"] = desktop_mode_switching
+        if whitelist_only is not None:
+            command["whitelist_only"] = whitelist_only
+        if fps_smoothing is not None:
+            command["fps_smoothing"] = fps_smoothing
+        if fps_ema_alpha is not None:
+            command["fps_ema_alpha"] = fps_ema_alpha
+        if fps_window_secs is not None:
+            command["fps_window_secs"] = fps_window_secs
+        if adaptive_polling is not None:
+            command["adaptive_polling"] = adaptive_polling
+        if mangohud_overlay is not None:
+            command["mangohud_overlay"] = mangohud_overlay
+        if auto_detect_game is not None:
+            command["auto_detect_game"] = auto_detect_game
         
         return self._send_ipc_command(command)
@@ -0,0 +1,57 @@
+//! Present rate measurement.
+
+use std::time::{Duration, Instant};
+
+/// Span presents are counted over before the published FPS is updated
+const RATE_WINDOW: Duration = Duration::from_millis(500);
+
+/// Counts presents and turns them into FPS and an average frametime.
+#[derive(Debug, Default)]
+pub struct FrameRate {
+    window_start: Option<Instant>,
+    /// Presents since `window_start`
+    frames: u32,
+}
+
+impl FrameRate {
+    /// Record a present at `now`. Returns the FPS and frametime (µs) over
+    /// the window each time one completes.
+    pub fn present(&mut self, now: Instant) -> Option<(u64, u64)> {
+        let Some(start) = self.window_start else {
+            self.window_start = Some(now);
+            return None;
+        };
+        self.frames += 1;
+        let elapsed = now.saturating_duration_since(start);
+        if elapsed < RATE_WINDOW {
+            return None;
+        }
+        let fps = self.frames as f64 / elapsed.as_secs_f64();
+        let frametime = elapsed.as_micros() as u64 / u64::from(self.frames);
+        self.window_start = Some(now);
+        self.frames = 0;
+        Some((fps.round() as u64, frametime))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_rate() {
+        let start = Instant::now();
+        let mut rate = FrameRate::default();
+        let mut published = Vec::new();
+        for frame in 0..=60u32 {
+            let now = start + Duration::from_micros(16_667 * u64::from(frame));
+            published.extend(rate.present(now));
+        }
+        // One second of presents completes two windows
+        assert_eq!(published.len(), 2);
+        for (fps, frametime) in published {
+            assert_eq!(fps, 60);
+            assert!((16_600..=16_700).contains(&frametime), "{}", frametime);
+        }
+    }
+}
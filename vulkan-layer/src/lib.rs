@@ -0,0 +1,318 @@
+//! SmartRefresh Vulkan layer
+//!
+//! A minimal implicit layer that counts `vkQueuePresentKHR` calls and
+//! publishes the frame rate to a shared memory segment the daemon reads,
+//! for games run without MangoHud. It intercepts only what it needs to
+//! chain through the loader and see presents; everything else goes
+//! straight to the next layer.
+
+mod frames;
+mod shm;
+
+use std::ffi::{c_char, c_void, CStr};
+use std::sync::Mutex;
+use std::time::Instant;
+
+use frames::FrameRate;
+use shm::ShmWriter;
+
+type Handle = *mut c_void;
+type VkResult = i32;
+type VoidFunction = unsafe extern "system" fn();
+type GetInstanceProcAddr = unsafe extern "system" fn(Handle, *const c_char) -> Option<VoidFunction>;
+type GetDeviceProcAddr = unsafe extern "system" fn(Handle, *const c_char) -> Option<VoidFunction>;
+type CreateInstance = unsafe extern "system" fn(*const BaseStructure, *const c_void, *mut Handle) -> VkResult;
+type DestroyInstance = unsafe extern "system" fn(Handle, *const c_void);
+type CreateDevice =
+    unsafe extern "system" fn(Handle, *const BaseStructure, *const c_void, *mut Handle) -> VkResult;
+type DestroyDevice = unsafe extern "system" fn(Handle, *const c_void);
+type QueuePresent = unsafe extern "system" fn(Handle, *const c_void) -> VkResult;
+
+const VK_SUCCESS: VkResult = 0;
+const VK_ERROR_INITIALIZATION_FAILED: VkResult = -3;
+const VK_STRUCTURE_TYPE_LOADER_INSTANCE_CREATE_INFO: i32 = 47;
+const VK_STRUCTURE_TYPE_LOADER_DEVICE_CREATE_INFO: i32 = 48;
+const VK_LAYER_LINK_INFO: i32 = 0;
+const LAYER_NEGOTIATE_INTERFACE_STRUCT: i32 = 1;
+/// Loader/layer interface version this layer implements
+const LAYER_INTERFACE_VERSION: u32 = 2;
+
+/// `sType`/`pNext` header shared by every Vulkan structure
+#[repr(C)]
+struct BaseStructure {
+    s_type: i32,
+    p_next: *const BaseStructure,
+}
+
+#[repr(C)]
+struct LayerInstanceLink {
+    p_next: *mut LayerInstanceLink,
+    next_get_instance_proc_addr: GetInstanceProcAddr,
+    next_get_physical_device_proc_addr: Option<VoidFunction>,
+}
+
+#[repr(C)]
+struct LayerDeviceLink {
+    p_next: *mut LayerDeviceLink,
+    next_get_instance_proc_addr: GetInstanceProcAddr,
+    next_get_device_proc_addr: GetDeviceProcAddr,
+}
+
+/// `VkLayerInstanceCreateInfo` and `VkLayerDeviceCreateInfo`, which differ
+/// only in the link type
+#[repr(C)]
+struct LayerCreateInfo<L> {
+    s_type: i32,
+    p_next: *const BaseStructure,
+    function: i32,
+    layer_info: *mut L,
+}
+
+#[repr(C)]
+pub struct NegotiateLayerInterface {
+    s_type: i32,
+    p_next: *mut c_void,
+    loader_layer_interface_version: u32,
+    get_instance_proc_addr: Option<GetInstanceProcAddr>,
+    get_device_proc_addr: Option<GetDeviceProcAddr>,
+    get_physical_device_proc_addr: Option<VoidFunction>,
+}
+
+struct InstanceEntry {
+    get_proc_addr: GetInstanceProcAddr,
+    destroy: DestroyInstance,
+}
+
+struct DeviceEntry {
+    get_proc_addr: GetDeviceProcAddr,
+    destroy: DestroyDevice,
+    present: QueuePresent,
+}
+
+/// Per-instance and per-device state, keyed by the loader dispatch pointer
+/// (queues share their device's)
+static INSTANCES: Mutex<Vec<(usize, InstanceEntry)>> = Mutex::new(Vec::new());
+static DEVICES: Mutex<Vec<(usize, DeviceEntry)>> = Mutex::new(Vec::new());
+
+/// Frame counting and the segment it's published to, created on the first
+/// present and dropped with the last device
+struct Publisher {
+    rate: FrameRate,
+    writer: Option<ShmWriter>,
+}
+
+static PUBLISHER: Mutex<Option<Publisher>> = Mutex::new(None);
+
+/// Dispatch key of a dispatchable handle.
+///
+/// # Safety
+/// `handle` must be a valid dispatchable Vulkan handle.
+unsafe fn dispatch_key(handle: Handle) -> usize {
+    *(handle as *const usize)
+}
+
+/// Find the loader's link info of type `s_type` in a create info chain.
+unsafe fn find_link_info<L>(mut next: *const BaseStructure, s_type: i32) -> Option<*mut LayerCreateInfo<L>> {
+    while !next.is_null() {
+        let info = next as *mut LayerCreateInfo<L>;
+        if (*info).s_type == s_type && (*info).function == VK_LAYER_LINK_INFO {
+            return Some(info);
+        }
+        next = (*next).p_next;
+    }
+    None
+}
+
+fn lookup<T, R>(table: &Mutex<Vec<(usize, T)>>, key: usize, f: impl FnOnce(&T) -> R) -> Option<R> {
+    let table = table.lock().unwrap_or_else(|e| e.into_inner());
+    table.iter().find(|(k, _)| *k == key).map(|(_, entry)| f(entry))
+}
+
+fn remove<T>(table: &Mutex<Vec<(usize, T)>>, key: usize) -> Option<T> {
+    let mut table = table.lock().unwrap_or_else(|e| e.into_inner());
+    let index = table.iter().position(|(k, _)| *k == key)?;
+    Some(table.swap_remove(index).1)
+}
+
+macro_rules! as_void_function {
+    ($function:expr, $ty:ty) => {
+        Some(std::mem::transmute::<$ty, VoidFunction>($function))
+    };
+}
+
+/// Entry point the loader calls to set the layer up.
+///
+/// # Safety
+/// Called by the Vulkan loader with a valid `NegotiateLayerInterface`.
+#[no_mangle]
+pub unsafe extern "system" fn vkNegotiateLoaderLayerInterfaceVersion(
+    interface: *mut NegotiateLayerInterface,
+) -> VkResult {
+    let Some(interface) = interface.as_mut() else {
+        return VK_ERROR_INITIALIZATION_FAILED;
+    };
+    if interface.s_type != LAYER_NEGOTIATE_INTERFACE_STRUCT || interface.loader_layer_interface_version < 2 {
+        return VK_ERROR_INITIALIZATION_FAILED;
+    }
+    interface.loader_layer_interface_version = LAYER_INTERFACE_VERSION;
+    interface.get_instance_proc_addr = Some(get_instance_proc_addr);
+    interface.get_device_proc_addr = Some(get_device_proc_addr);
+    interface.get_physical_device_proc_addr = None;
+    VK_SUCCESS
+}
+
+unsafe extern "system" fn get_instance_proc_addr(instance: Handle, name: *const c_char) -> Option<VoidFunction> {
+    match CStr::from_ptr(name).to_bytes() {
+        b"vkGetInstanceProcAddr" => as_void_function!(get_instance_proc_addr, GetInstanceProcAddr),
+        b"vkCreateInstance" => as_void_function!(create_instance, CreateInstance),
+        b"vkDestroyInstance" => as_void_function!(destroy_instance, DestroyInstance),
+        b"vkCreateDevice" => as_void_function!(create_device, CreateDevice),
+        name => {
+            if let Some(function) = device_function(name) {
+                return Some(function);
+            }
+            if instance.is_null() {
+                return None;
+            }
+            let next = lookup(&INSTANCES, dispatch_key(instance), |entry| entry.get_proc_addr)?;
+            next(instance, name.as_ptr() as *const c_char)
+        }
+    }
+}
+
+unsafe extern "system" fn get_device_proc_addr(device: Handle, name: *const c_char) -> Option<VoidFunction> {
+    let name = CStr::from_ptr(name);
+    if let Some(function) = device_function(name.to_bytes()) {
+        return Some(function);
+    }
+    let next = lookup(&DEVICES, dispatch_key(device), |entry| entry.get_proc_addr)?;
+    next(device, name.as_ptr())
+}
+
+/// The device-level functions the layer intercepts.
+unsafe fn device_function(name: &[u8]) -> Option<VoidFunction> {
+    match name {
+        b"vkGetDeviceProcAddr" => as_void_function!(get_device_proc_addr, GetDeviceProcAddr),
+        b"vkDestroyDevice" => as_void_function!(destroy_device, DestroyDevice),
+        b"vkQueuePresentKHR" => as_void_function!(queue_present, QueuePresent),
+        _ => None,
+    }
+}
+
+unsafe extern "system" fn create_instance(
+    create_info: *const BaseStructure,
+    allocator: *const c_void,
+    instance: *mut Handle,
+) -> VkResult {
+    let Some(link_info) = find_link_info::<LayerInstanceLink>(
+        (*create_info).p_next,
+        VK_STRUCTURE_TYPE_LOADER_INSTANCE_CREATE_INFO,
+    ) else {
+        return VK_ERROR_INITIALIZATION_FAILED;
+    };
+    let link = (*link_info).layer_info;
+    let next_get_proc_addr = (*link).next_get_instance_proc_addr;
+    let Some(next_create) = next_get_proc_addr(std::ptr::null_mut(), c"vkCreateInstance".as_ptr()) else {
+        return VK_ERROR_INITIALIZATION_FAILED;
+    };
+    let next_create: CreateInstance = std::mem::transmute(next_create);
+
+    // Hand the next layer its own link
+    (*link_info).layer_info = (*link).p_next;
+    let result = next_create(create_info, allocator, instance);
+    if result != VK_SUCCESS {
+        return result;
+    }
+
+    let Some(destroy) = next_get_proc_addr(*instance, c"vkDestroyInstance".as_ptr()) else {
+        return VK_ERROR_INITIALIZATION_FAILED;
+    };
+    let entry = InstanceEntry {
+        get_proc_addr: next_get_proc_addr,
+        destroy: std::mem::transmute::<VoidFunction, DestroyInstance>(destroy),
+    };
+    INSTANCES.lock().unwrap_or_else(|e| e.into_inner()).push((dispatch_key(*instance), entry));
+    VK_SUCCESS
+}
+
+unsafe extern "system" fn destroy_instance(instance: Handle, allocator: *const c_void) {
+    if instance.is_null() {
+        return;
+    }
+    if let Some(entry) = remove(&INSTANCES, dispatch_key(instance)) {
+        (entry.destroy)(instance, allocator);
+    }
+}
+
+unsafe extern "system" fn create_device(
+    physical_device: Handle,
+    create_info: *const BaseStructure,
+    allocator: *const c_void,
+    device: *mut Handle,
+) -> VkResult {
+    let Some(link_info) =
+        find_link_info::<LayerDeviceLink>((*create_info).p_next, VK_STRUCTURE_TYPE_LOADER_DEVICE_CREATE_INFO)
+    else {
+        return VK_ERROR_INITIALIZATION_FAILED;
+    };
+    let link = (*link_info).layer_info;
+    let next_get_instance_proc_addr = (*link).next_get_instance_proc_addr;
+    let next_get_proc_addr = (*link).next_get_device_proc_addr;
+    let Some(next_create) = next_get_instance_proc_addr(std::ptr::null_mut(), c"vkCreateDevice".as_ptr()) else {
+        return VK_ERROR_INITIALIZATION_FAILED;
+    };
+    let next_create: CreateDevice = std::mem::transmute(next_create);
+
+    (*link_info).layer_info = (*link).p_next;
+    let result = next_create(physical_device, create_info, allocator, device);
+    if result != VK_SUCCESS {
+        return result;
+    }
+
+    let (Some(destroy), Some(present)) = (
+        next_get_proc_addr(*device, c"vkDestroyDevice".as_ptr()),
+        next_get_proc_addr(*device, c"vkQueuePresentKHR".as_ptr()),
+    ) else {
+        return VK_ERROR_INITIALIZATION_FAILED;
+    };
+    let entry = DeviceEntry {
+        get_proc_addr: next_get_proc_addr,
+        destroy: std::mem::transmute::<VoidFunction, DestroyDevice>(destroy),
+        present: std::mem::transmute::<VoidFunction, QueuePresent>(present),
+    };
+    DEVICES.lock().unwrap_or_else(|e| e.into_inner()).push((dispatch_key(*device), entry));
+    VK_SUCCESS
+}
+
+unsafe extern "system" fn destroy_device(device: Handle, allocator: *const c_void) {
+    if device.is_null() {
+        return;
+    }
+    if let Some(entry) = remove(&DEVICES, dispatch_key(device)) {
+        (entry.destroy)(device, allocator);
+    }
+    if DEVICES.lock().unwrap_or_else(|e| e.into_inner()).is_empty() {
+        // Last device gone: remove the segment so the daemon stops reading it
+        PUBLISHER.lock().unwrap_or_else(|e| e.into_inner()).take();
+    }
+}
+
+unsafe extern "system" fn queue_present(queue: Handle, present_info: *const c_void) -> VkResult {
+    let Some(present) = lookup(&DEVICES, dispatch_key(queue), |entry| entry.present) else {
+        return VK_ERROR_INITIALIZATION_FAILED;
+    };
+    let result = present(queue, present_info);
+    record_present(Instant::now());
+    result
+}
+
+fn record_present(now: Instant) {
+    let mut publisher = PUBLISHER.lock().unwrap_or_else(|e| e.into_inner());
+    let publisher = publisher.get_or_insert_with(|| Publisher {
+        rate: FrameRate::default(),
+        writer: ShmWriter::create(),
+    });
+    if let (Some((fps, frametime)), Some(writer)) = (publisher.rate.present(now), &publisher.writer) {
+        writer.write(fps, frametime);
+    }
+}
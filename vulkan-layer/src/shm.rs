@@ -0,0 +1,89 @@
+//! The layer's shared memory segment.
+//!
+//! One segment per process, `/smartrefresh-fps-<pid>`, in the versioned
+//! layout the daemon checks MangoHud's segment against (`fps_monitor.rs`):
+//! a `MHUD` magic and version 1, then FPS and frametime as u64s.
+
+use std::ffi::CString;
+
+/// Segment name; the process ID is appended
+const SHM_PREFIX: &str = "/smartrefresh-fps";
+
+const LAYOUT_MAGIC: u32 = u32::from_le_bytes(*b"MHUD");
+const LAYOUT_VERSION: u32 = 1;
+
+#[repr(C)]
+struct Segment {
+    magic: u32,
+    version: u32,
+    fps_val: u64,
+    frametime: u64,
+}
+
+/// A mapped segment, removed again on drop.
+pub struct ShmWriter {
+    name: CString,
+    segment: *mut Segment,
+}
+
+// The mapping stays valid until drop; writes go through the layer's mutex.
+unsafe impl Send for ShmWriter {}
+
+impl ShmWriter {
+    /// Create and map this process's segment. `None` if shared memory is
+    /// unavailable, in which case the layer stays passive.
+    pub fn create() -> Option<Self> {
+        let name = CString::new(format!("{}-{}", SHM_PREFIX, std::process::id())).ok()?;
+        let size = std::mem::size_of::<Segment>();
+        // SAFETY: plain libc calls on a valid name; every failure is checked
+        unsafe {
+            let fd = libc::shm_open(name.as_ptr(), libc::O_CREAT | libc::O_RDWR, 0o644);
+            if fd < 0 {
+                return None;
+            }
+            if libc::ftruncate(fd, size as libc::off_t) < 0 {
+                libc::close(fd);
+                libc::shm_unlink(name.as_ptr());
+                return None;
+            }
+            let ptr = libc::mmap(
+                std::ptr::null_mut(),
+                size,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED,
+                fd,
+                0,
+            );
+            libc::close(fd);
+            if ptr == libc::MAP_FAILED {
+                libc::shm_unlink(name.as_ptr());
+                return None;
+            }
+            let segment = ptr as *mut Segment;
+            std::ptr::write_volatile(
+                segment,
+                Segment { magic: LAYOUT_MAGIC, version: LAYOUT_VERSION, fps_val: 0, frametime: 0 },
+            );
+            Some(Self { name, segment })
+        }
+    }
+
+    /// Publish the latest FPS and frametime (µs).
+    pub fn write(&self, fps: u64, frametime: u64) {
+        // SAFETY: `segment` is mapped read-write for the writer's lifetime
+        unsafe {
+            std::ptr::write_volatile(std::ptr::addr_of_mut!((*self.segment).fps_val), fps);
+            std::ptr::write_volatile(std::ptr::addr_of_mut!((*self.segment).frametime), frametime);
+        }
+    }
+}
+
+impl Drop for ShmWriter {
+    fn drop(&mut self) {
+        // SAFETY: unmapping the mapping made in `create`
+        unsafe {
+            libc::munmap(self.segment as *mut libc::c_void, std::mem::size_of::<Segment>());
+            libc::shm_unlink(self.name.as_ptr());
+        }
+    }
+}